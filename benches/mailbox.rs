@@ -0,0 +1,216 @@
+// Database and parsing benchmarks on a synthetic mailbox.
+//
+// The mailbox holds 100k messages by default; set `TUIMAIL_BENCH_MESSAGES` to use a
+// different size. The populated database is kept in the temp directory and reused by
+// later runs of the same size. Run with `cargo bench --bench mailbox`.
+
+use std::path::PathBuf;
+
+use chrono::{Duration, Local};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use tuimail::database::EmailDatabase;
+use tuimail::email::{Email, EmailAddress, EmailAttachment};
+use tuimail::mime::parse_message;
+
+const ACCOUNT: &str = "bench@example.com";
+const FOLDERS: [&str; 4] = ["INBOX", "Archive", "Sent", "Lists"];
+const SAVE_BATCH: usize = 1000;
+
+fn mailbox_size() -> usize {
+    std::env::var("TUIMAIL_BENCH_MESSAGES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(100_000)
+}
+
+fn contact(n: usize) -> EmailAddress {
+    EmailAddress {
+        name: Some(format!("Contact {}", n)),
+        address: format!("contact{}@example.org", n),
+    }
+}
+
+/// A deterministic message resembling real mail: a few hundred contacts, a mix of
+/// folders, read states and sizes, and an attachment on every 20th message
+fn synthetic_email(uid: usize) -> Email {
+    let mut email = Email::new();
+    email.id = uid.to_string();
+    email.folder = FOLDERS[uid % FOLDERS.len()].to_string();
+    email.from = vec![contact(uid % 500)];
+    email.to = vec![EmailAddress { name: None, address: ACCOUNT.to_string() }];
+    if uid.is_multiple_of(7) {
+        email.cc = vec![contact((uid + 13) % 500)];
+    }
+    email.subject = format!("Update {} on project {}", uid, uid % 97);
+    email.date = Local::now() - Duration::minutes(uid as i64 * 5);
+    email.seen = !uid.is_multiple_of(3);
+    email.flags = if email.seen { vec!["\\Seen".to_string()] } else { Vec::new() };
+    email.body_text = Some(format!("Hello,\n\n{}\n\nRegards,\nContact {}", "Status looks fine. ".repeat(20 + uid % 40), uid % 500));
+    if uid.is_multiple_of(20) {
+        email.attachments.push(EmailAttachment {
+            filename: format!("report-{}.pdf", uid),
+            content_type: "application/pdf".to_string(),
+            data: vec![b'x'; 2048],
+            path: None,
+            size: 2048,
+        });
+    }
+    email
+}
+
+fn save_in_folders(db: &EmailDatabase, emails: &[Email]) {
+    for folder in FOLDERS {
+        let in_folder: Vec<Email> = emails.iter().filter(|e| e.folder == folder).cloned().collect();
+        db.save_emails(ACCOUNT, folder, &in_folder).unwrap();
+    }
+}
+
+/// Open the shared benchmark database, populating it on first use
+fn populated_database() -> EmailDatabase {
+    let size = mailbox_size();
+    let dir = std::env::temp_dir().join(format!("tuimail-bench-{}", size));
+    let path = dir.join("emails.db");
+    let complete = dir.join("complete");
+
+    if !complete.exists() {
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = EmailDatabase::new(&path).unwrap();
+        for start in (0..size).step_by(SAVE_BATCH) {
+            let batch: Vec<Email> = (start..(start + SAVE_BATCH).min(size)).map(|uid| synthetic_email(uid + 1)).collect();
+            save_in_folders(&db, &batch);
+        }
+        std::fs::write(&complete, b"").unwrap();
+    }
+
+    EmailDatabase::new(&path).unwrap()
+}
+
+fn scratch_database(name: &str) -> (PathBuf, EmailDatabase) {
+    let dir = std::env::temp_dir().join(format!("tuimail-bench-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+    (dir, db)
+}
+
+fn bench_save(c: &mut Criterion) {
+    let mut group = c.benchmark_group("save_emails");
+    group.sample_size(20);
+
+    for batch_size in [100, SAVE_BATCH] {
+        let batch: Vec<Email> = (1..=batch_size).map(|uid| {
+            let mut email = synthetic_email(uid);
+            email.folder = "INBOX".to_string();
+            email
+        }).collect();
+        group.throughput(Throughput::Elements(batch_size as u64));
+
+        // Fresh folder: every message is an insert
+        group.bench_with_input(BenchmarkId::new("new_folder", batch_size), &batch, |b, batch| {
+            let (dir, db) = scratch_database("save");
+            let mut round = 0;
+            b.iter_batched(
+                || {
+                    round += 1;
+                    format!("Folder{}", round)
+                },
+                |folder| db.save_emails(ACCOUNT, &folder, batch).unwrap(),
+                BatchSize::SmallInput,
+            );
+            let _ = std::fs::remove_dir_all(dir);
+        });
+
+        // Resync into the large mailbox: every message replaces an existing row
+        group.bench_with_input(BenchmarkId::new("replace_in_mailbox", batch_size), &batch, |b, batch| {
+            let db = populated_database();
+            b.iter(|| db.save_emails(ACCOUNT, "INBOX", batch).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn bench_loads(c: &mut Criterion) {
+    let db = populated_database();
+    let mut group = c.benchmark_group("load");
+    group.sample_size(20);
+
+    for offset in [0, 10_000] {
+        group.bench_with_input(BenchmarkId::new("page_of_100", offset), &offset, |b, &offset| {
+            b.iter(|| db.get_emails_paginated(ACCOUNT, "INBOX", offset, 100).unwrap());
+        });
+    }
+    group.bench_function("recent_500", |b| {
+        b.iter(|| db.get_recent_emails(ACCOUNT, "INBOX", 500).unwrap());
+    });
+    group.bench_function("last_uid", |b| {
+        b.iter(|| db.get_last_uid(ACCOUNT, "INBOX").unwrap());
+    });
+    group.finish();
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let db = populated_database();
+    let mut group = c.benchmark_group("query");
+    group.sample_size(10);
+
+    group.bench_function("emails_with_contact", |b| {
+        b.iter(|| db.get_emails_with_contact(ACCOUNT, "contact42@example.org").unwrap());
+    });
+    group.bench_function("emails_since_7_days", |b| {
+        let since = (Local::now() - Duration::days(7)).timestamp();
+        b.iter(|| db.get_emails_since(ACCOUNT, since).unwrap());
+    });
+    group.bench_function("contacts", |b| {
+        b.iter(|| db.get_contacts(ACCOUNT).unwrap());
+    });
+    group.bench_function("attachment_index", |b| {
+        b.iter(|| db.list_attachments(ACCOUNT).unwrap());
+    });
+    group.finish();
+}
+
+fn raw_plain(uid: usize) -> Vec<u8> {
+    format!(
+        "From: Contact {n} <contact{n}@example.org>\r\nTo: {ACCOUNT}\r\nSubject: Update {uid}\r\n\
+         Date: Mon, 4 Mar 2024 10:00:00 +0000\r\nMessage-ID: <{uid}@example.org>\r\n\
+         Content-Type: text/plain; charset=utf-8\r\n\r\n{body}\r\n",
+        n = uid % 500,
+        body = "Status looks fine. ".repeat(40),
+    )
+    .into_bytes()
+}
+
+fn raw_multipart(uid: usize) -> Vec<u8> {
+    let attachment = "QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVo=\r\n".repeat(200);
+    format!(
+        "From: Contact {n} <contact{n}@example.org>\r\nTo: {ACCOUNT}\r\nSubject: Report {uid}\r\n\
+         Date: Mon, 4 Mar 2024 10:00:00 +0000\r\nMIME-Version: 1.0\r\n\
+         Content-Type: multipart/mixed; boundary=\"b\"\r\n\r\n\
+         --b\r\nContent-Type: multipart/alternative; boundary=\"a\"\r\n\r\n\
+         --a\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{body}\r\n\
+         --a\r\nContent-Type: text/html; charset=utf-8\r\n\r\n<p>{body}</p>\r\n--a--\r\n\
+         --b\r\nContent-Type: application/pdf\r\nContent-Disposition: attachment; filename=\"report.pdf\"\r\n\
+         Content-Transfer-Encoding: base64\r\n\r\n{attachment}--b--\r\n",
+        n = uid % 500,
+        body = "Status looks fine. ".repeat(40),
+    )
+    .into_bytes()
+}
+
+fn bench_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, raw) in [("plain", raw_plain(1)), ("multipart_attachment", raw_multipart(1))] {
+        group.throughput(Throughput::Bytes(raw.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &raw, |b, raw| {
+            b.iter(|| {
+                let parsed = parse_message(raw).unwrap();
+                Email::from_parsed_email(&parsed, "1", "INBOX", Vec::new()).unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_save, bench_loads, bench_queries, bench_parsing);
+criterion_main!(benches);
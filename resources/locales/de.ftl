@@ -0,0 +1,491 @@
+# tuimail UI strings (German)
+
+## Tabs
+
+tab-inbox = Posteingang
+tab-inbox-account = Posteingang ({ $account })
+tab-compose = Verfassen
+tab-settings = Einstellungen
+tab-help = Hilfe
+
+## Status bar
+
+status-unknown-account = Unbekannt
+status-folder = Ordner: { $folder }
+status-emails = E-Mails: { $count }
+status-account = Konto: { $name } ({ $index }/{ $total })
+status-read-only = NUR LESEN
+status-offline = OFFLINE
+status-disk-low = WENIG SPEICHER ({ $free } frei): nur Kopfzeilen
+status-sync-paused = Synchronisierung pausiert
+status-syncing = Synchronisiere...
+status-last-sync = Zuletzt synchronisiert: { $time }
+status-update-available = Version { $version } verfügbar (V)
+status-mode = Modus: { $mode }
+status-error = FEHLER: { $message }
+status-info = INFO: { $message }
+
+hint-normal = 'r' aktualisieren, 'n' nächstes Konto, 'f' Ordner, 'c' verfassen, '?' Hilfe
+hint-tags = +tag fügt ein Tag hinzu, -tag entfernt es, Enter übernimmt, Esc bricht ab
+hint-folder-list = ↑↓ wählt einen Ordner, Enter öffnet ihn, N schaltet Hinweise ab, A schaltet automatisches Abgleichen um, c/r/d legt einen Ordner an, benennt ihn um oder löscht ihn, Esc bricht ab
+hint-folder-edit = Ordnernamen eingeben und mit Enter bestätigen oder mit y/n antworten; Esc bricht ab
+hint-compose = Tab wechselt das Feld, Strg+S sendet, Strg+D speichert als Entwurf, Esc bricht ab
+hint-view-email = r=Antworten, a=Allen antworten, f=Weiterleiten, d=Löschen, m=Stummschalten, ↑↓=Blättern, F=Ganze Nachricht, Esc=Zurück
+hint-delete-confirm = E-Mail löschen? 'y' bestätigt, 'n' oder Esc bricht ab
+hint-reply-warning = Trotzdem antworten? 'y' verfasst die Antwort, 'n' oder Esc geht zurück
+hint-smtp-fallback = Die funktionierenden SMTP-Einstellungen behalten? 'y' speichert, 'n' oder Esc behält die bisherigen
+hint-people = ↑↓ wählt eine Person, Enter zeigt die Unterhaltung, v markiert als VIP, Esc geht zurück
+hint-cleanup = Leertaste=Markieren, g=Absender/Domain, d=Alle löschen, a=Alle archivieren, r=Archivregel anlegen, Esc=Zurück
+hint-cleanup-confirm-delete = Alle E-Mails der markierten Absender löschen? y=Ja, jede andere Taste=Nein
+hint-cleanup-confirm-archive = Alle E-Mails der markierten Absender nach { $folder } verschieben? y=Ja, jede andere Taste=Nein
+hint-drafts = ↑↓ wählt einen Entwurf, Enter setzt ihn fort oder löst einen Konflikt, x verwirft, r synchronisiert, Esc geht zurück
+hint-recently-deleted = ↑↓ wählt eine Nachricht, Enter stellt sie im Ordner wieder her, x vergisst sie, Esc geht zurück
+hint-reminders = ↑↓ wählt eine Erinnerung, Enter öffnet ihre Nachricht, x verwirft sie, Esc geht zurück
+hint-pending-operations = ↑↓ wählt eine Aktion, r versucht sie sofort erneut, x bricht sie ab, t ändert den Zielordner einer Verschiebung, Esc geht zurück
+hint-operation-target = Zielordner eingeben, Tab wechselt durch die Ordner des Kontos, Enter speichert, Esc bricht ab
+hint-changelog = ↑↓ und Bild↑/Bild↓ scrollen, Esc geht zurück
+hint-draft-conflict = Hier und auf einem anderen Gerät bearbeitet: l behält diese Fassung, s die des Servers, b beide; Esc geht zurück
+hint-attachments = / filtert, ↑↓ wählt, Enter zeigt eine Vorschau, s speichert, Esc geht zurück
+hint-rules-report = ↑↓ wählt, +/- vergrößert oder verkleinert den Zeitraum, Esc geht zurück
+hint-auth-help = r versucht es erneut, p gibt ein neues Passwort ein, Esc schließt
+hint-diagnostics = ↑↓ wählt, e exportiert das Protokoll als CSV, r lädt neu, c zeigt die Serverfähigkeiten, Esc geht zurück
+hint-account-settings = 'd' persönliches Wörterbuch, Esc geht zurück
+hint-dictionary = ↑↓ wählt ein Wort, x entfernt es aus dem Wörterbuch, Esc geht zurück
+hint-capabilities = r prüft erneut, Tab wechselt zum nächsten Konto, Esc geht zurück
+hint-imap-console = Enter sendet den Befehl, ↑↓ und Bild↑/Bild↓ blättern, Esc beendet die Sitzung
+hint-missing-secrets = ↑↓ wählt, Enter gibt das Passwort ein, Esc macht ohne weiter
+hint-missing-secrets-typing = Enter speichert das Passwort im Schlüsselbund, Esc bricht ab
+
+## Help screen
+
+help-title = Hilfe
+help-heading = Hilfe zum E-Mail-Programm
+help-section-global = Überall:
+help-section-normal = Hauptansicht:
+help-section-view = E-Mail-Ansicht:
+help-section-compose = Verfassen:
+help-quit = Beenden (in der Hauptansicht)
+help-toggle-help = Hilfe ein-/ausblenden
+help-compose = Neue E-Mail verfassen
+help-refresh = E-Mails aktualisieren
+help-next-account = Nächstes Konto
+help-folders = Ordnerliste zeigen
+help-settings = Einstellungen zeigen
+help-pause-sync = Hintergrund-Synchronisierung anhalten/fortsetzen
+help-people = Personen (Unterhaltungen nach Kontakt)
+help-attachments = Anhänge (alle Ordner)
+help-rules = Probelauf der Filterregeln und der Auto-Archivierung
+help-diagnostics = Diagnose und Protokoll
+help-importance = Hohe Priorität zuerst / nur diese / alle
+help-threads = Konversationen / eine Nachricht pro Zeile
+help-move = Nachricht in einen Ordner verschieben
+help-mark = Nachricht markieren, um mehrere zu verschieben
+help-read-later = Später lesen / erledigt (Liste unter den Ordnern jedes Kontos)
+help-drafts = Entwürfe (fortsetzen, verwerfen, Konflikte lösen)
+help-recently-deleted = Kürzlich gelöschte Nachrichten (auf dem Server wiederherstellen)
+help-pending-operations = Ausstehende Aktionen (wiederholen, abbrechen, Zielordner ändern)
+help-tags = Nachricht taggen (+tag fügt hinzu, -tag entfernt)
+help-remind = Später an die Nachricht erinnern
+help-reminders = Erinnerungen (Nachricht öffnen, verwerfen)
+help-changelog = Änderungsprotokoll und Update-Hinweis
+help-cleanup = Ordner nach Absender oder Domain aufräumen
+help-search = Suche (from: to: subject: has:attachment is:unread before: after: folder: account:)
+help-quick-actions = Schnellaktionen (archivieren, verschieben, Spam, ...; siehe Konfiguration)
+help-navigate = Durch die E-Mails blättern
+help-view = Gewählte E-Mail anzeigen
+help-delete-selected = Gewählte E-Mail löschen
+help-back = Zurück zur E-Mail-Liste
+help-reply = Antworten
+help-reply-all = Allen antworten
+help-forward = Weiterleiten
+help-delete = Löschen
+help-bounce = Unverändert an eine andere Adresse umleiten
+help-resend = Unzustellbare Nachricht an eine korrigierte Adresse erneut senden
+help-answer-invitation = Einladung annehmen, vorläufig annehmen oder ablehnen
+help-add-to-calendar = Einladung in den Kalender eintragen
+help-add-contacts = Angehängte Visitenkarten zu den Kontakten hinzufügen
+help-burst-digest = Digest Nachricht für Nachricht lesen
+help-digest-step = Vorherige/nächste Nachricht des Digests
+help-save-attachment = Gewählten Anhang speichern
+help-cancel-download = Herunterladen des Anhangs abbrechen
+help-headers = Kopfzeilen ein-/ausklappen
+help-expand-quotes = Zitate zeigen/einklappen (für den Absender gemerkt)
+help-html-view = Zwischen Text- und HTML-Fassung wechseln (für den Absender gemerkt)
+help-remote-content = Externe Bilder laden/blockieren (für den Absender gemerkt)
+help-open-html = HTML-Fassung im Browser öffnen
+help-full-message = Gekürzt angezeigte große Nachricht ganz zeigen
+help-next-attachment = Nächsten Anhang wählen
+help-scroll = Im Inhalt blättern
+help-cancel = Abbrechen
+help-send = E-Mail senden
+help-save-draft = Als Entwurf speichern
+help-attach = Anhang hinzufügen (Dateiauswahl)
+help-remove-attachment = Gewählten Anhang entfernen
+help-switch-fields = Zwischen den Feldern wechseln
+help-lookup-name = In An, Cc oder Bcc: den eingegebenen Namen in den Kontakten suchen
+help-markdown = Zwischen Text und Markdown wechseln
+help-priority = Mit hoher, niedriger oder normaler Priorität senden
+help-pgp-sign = Mit dem eigenen PGP-Schlüssel signieren
+help-pgp-encrypt = Für die PGP-Schlüssel der Empfänger verschlüsseln
+help-quote-split = Im Zitat antworten: Zitat am Cursor teilen
+help-quote-paragraph = Absatz am Cursor zitieren
+help-strip-quote = Zitierten Text unter dem Cursor entfernen
+
+## Status messages: selection and accounts
+
+msg-no-email-selected = Keine E-Mail ausgewählt
+msg-invalid-email-selection = Ungültige E-Mail-Auswahl
+msg-invalid-selection = Ungültige Auswahl
+msg-account-not-found = Aktuelles Konto nicht gefunden
+msg-client-not-initialized = E-Mail-Client für das aktuelle Konto nicht initialisiert
+msg-client-not-initialized-here = E-Mail-Client für dieses Konto nicht initialisiert
+msg-load-folders-failed = Ordner von Konto { $account } konnten nicht geladen werden: { $error }
+msg-account-initialized = Konto initialisiert: { $account }
+msg-connect-failed = Verbindung für { $account } fehlgeschlagen: { $error }
+msg-default-account-failed = Standardkonto konnte nicht initialisiert werden: { $error }
+msg-account-init-failed = Konto konnte nicht initialisiert werden: { $error }
+msg-account-not-connected = Das Konto ist nicht verbunden
+msg-only-one-account = Nur ein Konto eingerichtet
+msg-load-inbox-failed = INBOX des Kontos konnte nicht geladen werden: { $error }
+msg-switched-account = Zu Konto gewechselt: { $account }
+msg-store-password-failed = Passwort konnte nicht gespeichert werden: { $error }
+msg-password-saved = { $protocol }-Passwort von { $account } gespeichert
+msg-signed-in = Bei { $account } angemeldet
+msg-retry-failed = Erneuter Versuch fehlgeschlagen: { $error }
+msg-smtp-route-saved = { $account } sendet jetzt über { $route }
+msg-smtp-route-unsaved = { $route } wird für diese Sitzung verwendet; die Konfiguration konnte nicht gespeichert werden: { $error }
+msg-error = Fehler: { $error }
+
+## Status messages: read-only mode
+
+msg-read-only-sending = Im Nur-Lesen-Modus kann nicht gesendet werden
+msg-read-only-folders = Im Nur-Lesen-Modus können Ordner nicht geändert werden
+msg-read-only-read-later = „Später lesen“ ist im Nur-Lesen-Modus deaktiviert
+msg-read-only-cleanup = Aufräumen ist im Nur-Lesen-Modus deaktiviert
+msg-read-only-quick-actions = Schnellaktionen sind im Nur-Lesen-Modus deaktiviert
+msg-read-only-deleting = Im Nur-Lesen-Modus kann nicht gelöscht werden
+msg-read-only-moving = Im Nur-Lesen-Modus kann nicht verschoben werden
+
+## Status messages: spelling and grammar
+
+msg-spellcheck-on = Rechtschreibprüfung eingeschaltet
+msg-spellcheck-off = Rechtschreibprüfung ausgeschaltet
+msg-grammar-on = Grammatikprüfung eingeschaltet
+msg-grammar-off = Grammatikprüfung ausgeschaltet
+msg-no-spelling-suggestions = Keine Rechtschreibvorschläge an der Cursorposition
+msg-no-grammar-suggestions = Keine Grammatikvorschläge an der Cursorposition
+msg-replaced = '{ $original }' durch '{ $suggestion }' ersetzt
+msg-word-added = '{ $word }' zum persönlichen Wörterbuch hinzugefügt
+msg-no-misspelled-word = Kein falsch geschriebenes Wort an der Cursorposition
+msg-dictionary-save-failed = Persönliches Wörterbuch konnte nicht gespeichert werden: { $error }
+msg-word-removed = '{ $word }' aus dem persönlichen Wörterbuch entfernt
+
+## Status messages: tags, VIPs, muting and notifications
+
+msg-no-message-id-tags = Diese Nachricht hat keine Message-ID, unter der sie markiert werden kann
+msg-tags-save-failed = Schlagwörter konnten nicht gespeichert werden: { $error }
+msg-tags-notmuch-failed = Schlagwörter hier gespeichert, aber nicht in notmuch: { $error }
+msg-no-tags = Keine Schlagwörter
+msg-tags = Schlagwörter: { $tags }
+msg-vips-update-failed = VIPs konnten nicht aktualisiert werden: { $error }
+msg-vip-removed = { $address } ist kein VIP mehr
+msg-vip-added = { $address } ist jetzt ein VIP
+msg-no-message-id-mute = Diese Nachricht hat keine Message-ID, daher kann ihre Unterhaltung nicht stummgeschaltet werden
+msg-mute-update-failed = Stummgeschaltete Unterhaltungen konnten nicht aktualisiert werden: { $error }
+msg-muted-archived = Unterhaltung stummgeschaltet: neue Nachrichten werden als gelesen markiert und nach { $folder } verschoben
+msg-muted = Unterhaltung stummgeschaltet: neue Nachrichten werden als gelesen markiert
+msg-unmuted = Stummschaltung der Unterhaltung aufgehoben
+msg-not-forge-link = Diese Nachricht ist keine GitHub- oder GitLab-Benachrichtigung mit Link
+msg-opening = { $url } wird geöffnet
+msg-open-failed = { $url } konnte nicht geöffnet werden: { $error }
+msg-not-forge = Diese Nachricht ist keine GitHub- oder GitLab-Benachrichtigung
+msg-forge-no-unsubscribe = Diese { $forge }-Benachrichtigung hat keine Abmeldeadresse
+msg-forge-muted = { $thread } auf { $forge } stummgeschaltet
+msg-unsubscribe-failed = Abmelden fehlgeschlagen: { $error }
+
+## Status messages: bounces, HTML and invitations
+
+msg-not-bounce = Keine unzustellbare Nachricht, oder die gesendete Nachricht ist nicht gespeichert
+msg-bounced-gone = Die unzustellbare Nachricht ist nicht mehr gespeichert
+msg-bounced-load-failed = Die unzustellbare Nachricht konnte nicht geladen werden: { $error }
+msg-correct-and-resend = Adresse korrigieren und erneut senden
+msg-attach-failed = { $path } konnte nicht angehängt werden: { $error }
+msg-sender-preference-failed = Das konnte für { $address } nicht gespeichert werden: { $error }
+msg-no-html = Diese Nachricht hat keinen HTML-Teil
+msg-remote-content-loaded = Externe Inhalte in Nachrichten von { $sender } werden geladen
+msg-remote-content-blocked = Externe Inhalte in Nachrichten von { $sender } werden blockiert
+msg-opening-browser = Die Nachricht wird im Browser geöffnet
+msg-browser-failed = Die Nachricht konnte nicht im Browser geöffnet werden: { $error }
+msg-not-digest = Diese Nachricht ist keine Sammelnachricht
+msg-no-invitation = Diese Nachricht enthält keine Kalendereinladung
+msg-invitation-cancelled = Diese Nachricht sagt den Termin ab; es gibt nichts hinzuzufügen
+msg-no-calendar = Für dieses Konto ist kein Kalender eingerichtet; ergänzen Sie "caldav" in der Konfiguration
+msg-event-added = '{ $event }' zum Kalender hinzugefügt
+msg-event-add-failed = Der Termin konnte nicht zum Kalender hinzugefügt werden: { $error }
+msg-no-contact-card = Diese Nachricht enthält keine Visitenkarte mit E-Mail-Adresse
+msg-contact-people = { $count } Personen
+msg-contacts-added = { $who } zu den Kontakten hinzugefügt ({ $count ->
+        [one] 1 Adresse
+       *[other] { $count } Adressen
+    })
+msg-contacts-add-failed = Konnte nicht zu den Kontakten hinzugefügt werden: { $error }
+msg-cannot-answer = Auf diese Einladung kann nicht geantwortet werden: { $error }
+msg-answer-failed = Die Antwort auf die Einladung ist fehlgeschlagen: { $error }
+msg-answered = { $organizer } wurde mitgeteilt, dass Sie '{ $event }' { $answer ->
+        [ACCEPTED] zugesagt
+        [TENTATIVE] unter Vorbehalt zugesagt
+       *[DECLINED] abgesagt
+    } haben
+msg-answered-added = { $organizer } wurde mitgeteilt, dass Sie '{ $event }' { $answer ->
+        [ACCEPTED] zugesagt
+        [TENTATIVE] unter Vorbehalt zugesagt
+       *[DECLINED] abgesagt
+    } haben, und der Termin wurde zum Kalender hinzugefügt
+msg-answered-not-added = { $organizer } wurde mitgeteilt, dass Sie '{ $event }' { $answer ->
+        [ACCEPTED] zugesagt
+        [TENTATIVE] unter Vorbehalt zugesagt
+       *[DECLINED] abgesagt
+    } haben, aber der Termin konnte nicht zum Kalender hinzugefügt werden: { $error }
+msg-no-pgp-key = Für dieses Konto ist kein PGP-Schlüssel eingerichtet; ergänzen Sie "pgp" in der Konfiguration
+msg-not-invitation = Diese Nachricht ist keine Einladung, auf die geantwortet werden kann
+
+## Status messages: syncing and folders
+
+msg-updates-by-package-manager = Diese Version wird von Ihrer Paketverwaltung aktualisiert
+msg-sync-paused = Hintergrund-Synchronisierung angehalten ('p' setzt sie fort)
+msg-sync-resumed = Hintergrund-Synchronisierung fortgesetzt
+msg-sync-state-reset = Synchronisierungsstand zurückgesetzt. Die nächste Aktualisierung lädt alle E-Mails.
+msg-load-emails-failed = E-Mails konnten nicht geladen werden: { $error }
+msg-disk-low = Wenig Speicherplatz: { $free } frei. Bis { $needed } MB frei sind, werden nur Kopfzeilen geladen
+msg-disk-ok = Wieder genug Speicherplatz; Nachrichteninhalte werden geladen
+msg-deleted-elsewhere = { $count ->
+        [one] 1 Nachricht in { $folder } wurde
+       *[other] { $count } Nachrichten in { $folder } wurden
+    } von einem anderen Programm gelöscht; U listet sie zum Wiederherstellen auf
+msg-operation-failed = { $error }; O listet die ausstehenden Aktionen auf
+msg-reset-sync-failed = Synchronisierungsstand konnte nicht zurückgesetzt werden: { $error }
+msg-refresh-failed = E-Mails konnten nicht aktualisiert werden: { $error }
+msg-full-resync-done = Vollständige Neusynchronisierung abgeschlossen – alle E-Mails geladen
+msg-refreshed = E-Mails aktualisiert
+msg-mark-read-failed = E-Mail konnte nicht als gelesen markiert werden: { $error }
+msg-inbox-fixed = Die INBOX kann nicht umbenannt oder gelöscht werden
+msg-select-folder = Zuerst einen Ordner auswählen
+msg-folder-creating = { $folder } wird angelegt …
+msg-folder-renaming = { $folder } wird in { $name } umbenannt …
+msg-folder-deleting = { $folder } wird gelöscht …
+msg-folder-created = { $folder } angelegt
+msg-folder-renamed = { $folder } in { $name } umbenannt
+msg-folder-deleted = { $folder } gelöscht
+msg-folder-options = { $folder } { $notify ->
+        [never] meldet nie neue Nachrichten
+       *[other] meldet neue Nachrichten
+    } und wird { $sync ->
+        [opened] nur beim Öffnen
+       *[other] im Hintergrund
+    } synchronisiert
+msg-folder-options-unsaved = { $folder } { $notify ->
+        [never] meldet nie neue Nachrichten
+       *[other] meldet neue Nachrichten
+    } und wird { $sync ->
+        [opened] nur beim Öffnen
+       *[other] im Hintergrund
+    } synchronisiert (nur in dieser Sitzung); die Konfiguration konnte nicht gespeichert werden: { $error }
+msg-density = Die E-Mail-Liste ist jetzt { $density ->
+        [compact] kompakt
+       *[comfortable] luftig
+    }
+msg-density-unsaved = Die E-Mail-Liste ist in dieser Sitzung { $density ->
+        [compact] kompakt
+       *[comfortable] luftig
+    }; die Konfiguration konnte nicht gespeichert werden: { $error }
+msg-load-contacts-failed = Kontakte konnten nicht geladen werden: { $error }
+msg-load-conversation-failed = Unterhaltung konnte nicht geladen werden: { $error }
+msg-importance-all = Alle Nachrichten nach Datum
+msg-importance-high-first = Nachrichten mit hoher Priorität zuerst
+msg-importance-high-only = Nur Nachrichten mit hoher Priorität
+msg-showing-threads = Unterhaltungen werden angezeigt
+msg-showing-messages = Eine Nachricht pro Zeile
+
+## Status messages: searching
+
+msg-invalid-search = Ungültige Suche: { $error }
+msg-no-account-matches = Kein Konto passt zu '{ $account }'
+msg-search-matches = { $count ->
+        [one] 1 Nachricht passt
+       *[other] { $count } Nachrichten passen
+    }
+msg-search-failed = Suche fehlgeschlagen: { $error }
+msg-tag-search-local = Schlagwörter gibt es nur lokal, daher können tag:-Suchen nicht on:server verwenden
+msg-search-local-only = Nicht verbunden: nur der lokale Zwischenspeicher wurde durchsucht
+msg-search-server = { $count ->
+        [one] 1 Nachricht passt
+       *[other] { $count } Nachrichten passen
+    } hier; der Server wird durchsucht …
+msg-server-search-failed = Suche auf dem Server fehlgeschlagen: { $error }
+msg-search-server-matches = { $count ->
+        [one] 1 Nachricht passt
+       *[other] { $count } Nachrichten passen
+    }, davon { $added } auf dem Server gefunden
+
+## Status messages: read later, cleanup and quick actions
+
+msg-load-read-later-failed = Die „Später lesen“-Liste konnte nicht geladen werden: { $error }
+msg-load-vip-failed = Der VIP-Ordner konnte nicht geladen werden: { $error }
+msg-load-notifications-failed = Benachrichtigungen konnten nicht geladen werden: { $error }
+msg-read-later-failed = „Später lesen“ fehlgeschlagen: { $error }
+msg-read-later-saved = Für später gemerkt: { $subject }
+msg-read-later-done = Erledigt: { $subject } (noch { $count })
+msg-read-later-removed = Aus „Später lesen“ entfernt: { $subject }
+msg-cleanup-select-folder = Einen Ordner zum Aufräumen auswählen
+msg-load-folder-failed = { $folder } konnte nicht geladen werden: { $error }
+msg-cleanup-running = Es wird noch aufgeräumt
+msg-already-in = Bereits in { $folder }
+msg-cleanup-started = { $count ->
+        [one] 1 Absender wird
+       *[other] { $count } Absender werden
+    } aufgeräumt …
+msg-cleanup-move = nach { $folder } verschoben
+msg-cleanup-delete = gelöscht
+msg-cleanup-stopped = Aufräumen nach { $count ->
+        [one] 1 Nachricht
+       *[other] { $count } Nachrichten
+    } abgebrochen: { $error }
+msg-cleanup-done = Aufgeräumt: { $count ->
+        [one] 1 Nachricht
+       *[other] { $count } Nachrichten
+    } { $action }
+msg-cleanup-rules-exist = Für diese Absender gibt es schon eine Aufräumregel
+msg-cleanup-rules-added = { $count ->
+        [one] 1 Regel
+       *[other] { $count } Regeln
+    } angelegt, die ihre Post nach { $folder } verschieben; R zeigt, was sie erfassen
+msg-cleanup-rules-unsaved = { $count ->
+        [one] 1 Regel
+       *[other] { $count } Regeln
+    } für diese Sitzung angelegt; die Konfiguration konnte nicht gespeichert werden: { $error }
+msg-dry-run-failed = E-Mails für den Probelauf konnten nicht geladen werden: { $error }
+msg-no-quick-action = Keine Schnellaktion auf { $key } (siehe "quick_actions" in config.json)
+msg-no-spam-folder = Kein Spam-Ordner gefunden; setzen Sie "folder" bei der Spam-Schnellaktion
+msg-quick-action-failed = { $action } fehlgeschlagen: { $error }
+msg-quick-action-done = { $action }: { $subject }
+quick-archive = Nach { $folder } archivieren
+quick-move = Nach { $folder } verschieben
+quick-spam = Spam
+quick-mark-read = Als gelesen markieren
+quick-mark-unread = Als ungelesen markieren
+quick-flag = Markieren
+quick-delete = Löschen
+
+## Status messages: diagnostics, drafts and restoring
+
+msg-load-audit-failed = Das Protokoll konnte nicht geladen werden: { $error }
+msg-audit-exported = { $count ->
+        [one] 1 Eintrag
+       *[other] { $count } Einträge
+    } nach { $path } exportiert
+msg-audit-export-failed = Das Protokoll konnte nicht exportiert werden: { $error }
+msg-load-drafts-failed = Entwürfe konnten nicht geladen werden: { $error }
+msg-draft-sync-failed = Synchronisierung der Entwürfe fehlgeschlagen: { $error }
+msg-draft-conflicts = { $count ->
+        [one] 1 Entwurf wurde
+       *[other] { $count } Entwürfe wurden
+    } hier und auf einem anderen Gerät bearbeitet: wählen Sie eine Fassung unter Entwürfe (W)
+msg-drafts-downloaded = { $count ->
+        [one] 1 Entwurf
+       *[other] { $count } Entwürfe
+    } vom Server aktualisiert
+msg-draft-save-failed = Entwurf konnte nicht gespeichert werden: { $error }
+msg-draft-saved = Entwurf gespeichert: { $subject }
+msg-no-subject = (kein Betreff)
+msg-draft-discard-failed = Entwurf konnte nicht verworfen werden: { $error }
+msg-draft-discarded = Entwurf verworfen
+msg-draft-conflict-resolved = Konflikt beim Entwurf gelöst
+msg-draft-conflict-failed = Konflikt beim Entwurf konnte nicht gelöst werden: { $error }
+msg-load-deleted-failed = Gelöschte Nachrichten konnten nicht geladen werden: { $error }
+msg-restored = "{ $subject }" nach { $folder } wiederhergestellt
+msg-restore-failed = Nachricht konnte nicht wiederhergestellt werden: { $error }
+msg-drop-copy-failed = Die aufbewahrte Kopie konnte nicht entfernt werden: { $error }
+msg-load-operations-failed = Ausstehende Aktionen konnten nicht geladen werden: { $error }
+
+## Status messages: reminders and queued operations
+
+msg-load-reminders-failed = Erinnerungen konnten nicht geladen werden: { $error }
+msg-reminder-set = Erinnerung an "{ $subject }" { $when }; Z listet die Erinnerungen auf
+msg-reminder-save-failed = Die Erinnerung konnte nicht gespeichert werden: { $error }
+msg-reminder-due = ⏰ Erinnerung: { $subject } (Z öffnet sie)
+msg-reminders-due = ⏰ { $count } Erinnerungen sind fällig (Z zeigt sie)
+msg-reminder-account-gone = Das Konto { $account } ist nicht mehr eingerichtet
+msg-reminder-message-gone = "{ $subject }" ist nicht mehr in { $folder }
+msg-reminder-dismiss-failed = Die Erinnerung konnte nicht verworfen werden: { $error }
+msg-operation-target = Die Nachricht wird jetzt nach { $folder } verschoben
+msg-operation-target-failed = Der Zielordner konnte nicht geändert werden: { $error }
+msg-operation-offline = { $account } ist offline; die Aktion wartet, bis das Konto wieder online ist
+msg-operation-retrying = Neuer Versuch: { $operation }
+msg-operation-retry-failed = Die Aktion konnte nicht erneut versucht werden: { $error }
+msg-operation-cancel-failed = Die Aktion konnte nicht abgebrochen werden: { $error }
+msg-operation-not-move = Nur eine Verschiebung hat einen Zielordner
+
+## Status messages: composing, moving and sending
+
+msg-replying = Antwort auf die E-Mail – Cursor steht oben
+msg-replying-all = Antwort an alle – Cursor steht oben
+msg-forwarding = E-Mail weiterleiten – Empfänger hinzufügen
+msg-directory-searching = Das Verzeichnis wird noch nach '{ $name }' durchsucht
+msg-no-contact-named = Kein Kontakt namens '{ $name }'
+msg-move-server-only = Nur Nachrichten auf dem Server können verschoben werden
+msg-move-open-folder = Öffnen Sie { $folder }, um diese Nachricht zu verschieben
+msg-moving = Verschieben nach { $folder }: { $subject }
+msg-moving-marked = { $count } markierte Nachrichten werden nach { $folder } verschoben
+msg-no-typos = Kein Empfänger scheint vertippt
+msg-corrected = Korrigiert zu { $addresses }
+msg-no-bounced-recipients = Keine Zustellung an die Empfänger ist endgültig gescheitert
+msg-bounced-removed = { $addresses } aus der Nachricht, dem Adressbuch und den VIPs entfernt
+msg-bounced-removed-partly = { $addresses } aus der Nachricht entfernt; die Kontakte konnten nicht aktualisiert werden: { $error }
+msg-no-recipient = Kein Empfänger angegeben
+msg-bounced-to = Umgeleitet an { $recipients }
+msg-bounce-failed = Umleiten fehlgeschlagen: { $error }
+msg-email-deleted = E-Mail gelöscht
+msg-delete-failed = E-Mail konnte nicht gelöscht werden: { $error }
+msg-not-an-address = '{ $name }' ist keine Adresse; Enter in seinem Feld sucht danach in den Kontakten
+msg-recipient-typo = { $typo } sieht nach einem Tippfehler für { $suggestion } aus: Alt+C korrigiert, erneut Strg+S sendet wie eingegeben
+msg-recipient-bounced = Zustellung an { $addresses } ist schon endgültig gescheitert: Alt+U entfernt, erneut Strg+S sendet trotzdem
+msg-sent-with-attachments = E-Mail mit { $count ->
+        [one] 1 Anhang
+       *[other] { $count } Anhängen
+    } gesendet
+msg-sent = E-Mail gesendet
+msg-send-failed = E-Mail konnte nicht gesendet werden: { $error }
+
+## Status messages: attachments and files
+
+msg-load-attachments-failed = Anhänge konnten nicht geladen werden: { $error }
+msg-attachment-not-downloaded-open = Der Anhang ist noch nicht heruntergeladen; öffnen Sie seine Nachricht, um ihn zu laden
+msg-attachment-evicted = Der Anhang ist nicht mehr im Zwischenspeicher
+msg-load-attachment-failed = Anhang konnte nicht geladen werden: { $error }
+msg-read-attachment-failed = Anhang konnte nicht gelesen werden: { $error }
+msg-save-attachment-hint = ANHANG SPEICHERN: 'q' speichert direkt in Downloads, oder mit ↑↓ einen Ordner wählen und mit Enter speichern
+msg-file-browser-cancelled = Dateiauswahl abgebrochen
+msg-edit-filename = Dateinamen bearbeiten und mit Enter speichern, oder mit Esc abbrechen
+msg-enter-filename = Dateinamen eingeben und mit Enter speichern, oder mit Esc abbrechen
+msg-downloads-dir-failed = Download-Verzeichnis konnte nicht angelegt werden: { $error }
+msg-read-dir-failed = Verzeichnis konnte nicht gelesen werden: { $error }
+msg-attachment-input-cancelled = Anhang-Eingabe abgebrochen
+msg-file-browser-test = TEST: Dateiauswahl geöffnet – Pfeiltasten ausprobieren, 'q' speichert
+msg-attachment-not-downloaded = Der Anhang ist noch nicht heruntergeladen
+msg-invalid-attachment = Ungültiger Anhang
+msg-no-attachment-selected = Kein Anhang ausgewählt
+msg-attachment-saved = Anhang gespeichert unter: { $path }
+msg-attachment-save-failed = Anhang konnte nicht gespeichert werden: { $error }
+msg-file-browser-hint = Mit ↑↓ bewegen, Enter wählt aus, Rücktaste geht eine Ebene höher, Esc bricht ab
+msg-attachment-added = Anhang hinzugefügt: { $path }
+msg-read-file-failed = Datei { $path } konnte nicht gelesen werden: { $error }
+msg-clipboard-attached = { $filename } ({ $size } KB) aus der Zwischenablage angehängt
+msg-attachment-removed = Anhang entfernt: { $filename }
+msg-attachments-disk-low = Anhänge werden nicht geladen: nur { $free } frei
+msg-attachments-no-account = Konto nicht initialisiert, Anhänge können nicht geladen werden
+msg-attachments-downloading = { $count ->
+        [one] 1 Anhang wird
+       *[other] { $count } Anhänge werden
+    } im Hintergrund geladen
+msg-download-cancelled = Download abgebrochen
+msg-no-download = Für diesen Anhang läuft kein Download
@@ -0,0 +1,494 @@
+# tuimail UI strings (English, the reference catalog)
+#
+# Every other catalog translates the same keys. Placeables such as { $folder } are
+# filled in by the code and must be kept as they are.
+
+## Tabs
+
+tab-inbox = Inbox
+tab-inbox-account = Inbox ({ $account })
+tab-compose = Compose
+tab-settings = Settings
+tab-help = Help
+
+## Status bar
+
+status-unknown-account = Unknown
+status-folder = Folder: { $folder }
+status-emails = Emails: { $count }
+status-account = Account: { $name } ({ $index }/{ $total })
+status-read-only = READ-ONLY
+status-offline = OFFLINE
+status-disk-low = LOW DISK ({ $free } free): headers only
+status-sync-paused = Sync paused
+status-syncing = Syncing...
+status-last-sync = Last sync: { $time }
+status-update-available = Version { $version } available (V)
+status-mode = Mode: { $mode }
+status-error = ERROR: { $message }
+status-info = INFO: { $message }
+
+hint-normal = Press 'r' to refresh, 'n' for next account, 'f' for folders, 'c' to compose, '?' for help
+hint-tags = Type +tag to add and -tag to remove tags, Enter to apply, Esc to cancel
+hint-folder-list = Use ↑↓ to navigate folders, Enter to select, N to never notify, A to toggle auto-sync, c/r/d to create/rename/delete a folder, Esc to cancel
+hint-folder-edit = Type the folder name and press Enter, or answer y/n; Esc cancels
+hint-compose = Tab to switch fields, Ctrl+S to send, Ctrl+D to save as draft, Esc to cancel
+hint-view-email = r=Reply, a=Reply All, f=Forward, d=Delete, m=Mute, ↑↓=Scroll, F=Full message, Esc=Back
+hint-delete-confirm = Delete email? Press 'y' to confirm, 'n' or Esc to cancel
+hint-reply-warning = Reply anyway? Press 'y' to compose the reply, 'n' or Esc to go back
+hint-smtp-fallback = Keep the SMTP settings that worked? Press 'y' to save, 'n' or Esc to keep the current ones
+hint-people = Use ↑↓ to choose a person, Enter to show the conversation, v to mark as VIP, Esc to go back
+hint-cleanup = Space=Mark, g=Sender/domain, d=Delete all, a=Archive all, r=Add archive rule, Esc=Back
+hint-cleanup-confirm-delete = Delete all mail of the marked senders? y=Yes, any other key=No
+hint-cleanup-confirm-archive = Move all mail of the marked senders to { $folder }? y=Yes, any other key=No
+hint-drafts = ↑↓ to choose a draft, Enter to resume or settle a conflict, x to discard, r to sync, Esc to go back
+hint-recently-deleted = ↑↓ to choose a message, Enter to restore it to its folder, x to forget it, Esc to go back
+hint-reminders = ↑↓ selects a reminder, Enter opens its message, x dismisses it, Esc goes back
+hint-pending-operations = ↑↓ to choose an operation, r to retry it now, x to cancel it, t to change the target folder of a move, Esc to go back
+hint-operation-target = Type the target folder, Tab to cycle through the account's folders, Enter to save, Esc to cancel
+hint-changelog = ↑↓ and PgUp/PgDn to scroll, Esc to go back
+hint-draft-conflict = Edited here and on another device: l keeps this version, s the server's, b both; Esc to go back
+hint-attachments = / to filter, ↑↓ to navigate, Enter to preview, s to save, Esc to go back
+hint-rules-report = ↑↓ to navigate, +/- to widen or narrow the window, Esc to go back
+hint-auth-help = r to retry, p to enter a new password, Esc to close
+hint-diagnostics = ↑↓ to navigate, e to export the audit log as CSV, r to reload, c for server capabilities, Esc to go back
+hint-account-settings = 'd' for the personal dictionary, Esc to go back
+hint-dictionary = ↑↓ to choose a word, x to remove it from the dictionary, Esc to go back
+hint-capabilities = r to check again, Tab for the next account, Esc to go back
+hint-imap-console = Enter sends the command, ↑↓ and PgUp/PgDn scroll, Esc closes the session
+hint-missing-secrets = ↑↓ to choose, Enter to type the password, Esc to continue without it
+hint-missing-secrets-typing = Enter saves the password to the keyring, Esc cancels
+
+## Help screen
+
+help-title = Help
+help-heading = Email Client Help
+help-section-global = Global:
+help-section-normal = Normal Mode:
+help-section-view = View Email Mode:
+help-section-compose = Compose Mode:
+help-quit = Quit (in normal mode)
+help-toggle-help = Show/hide help
+help-compose = Compose new email
+help-refresh = Refresh emails
+help-next-account = Next account (rotate)
+help-folders = Show folder list
+help-settings = Show settings
+help-pause-sync = Pause/resume background sync
+help-people = People view (conversations by contact)
+help-attachments = Attachment browser (all folders)
+help-rules = Dry run of filter rules and auto-archive
+help-diagnostics = Diagnostics and audit log
+help-importance = High priority first / only / all
+help-threads = Conversations / one message per row
+help-move = Move the message to a folder
+help-mark = Mark the message, to move several at once
+help-read-later = Read later / mark done (queue under each account's folders)
+help-drafts = Drafts (resume, discard, settle sync conflicts)
+help-recently-deleted = Recently deleted messages (restore to the server)
+help-pending-operations = Queued operations (retry, cancel, change the target folder)
+help-tags = Tag the selected message (+tag adds, -tag removes)
+help-remind = Remind me of the selected message later
+help-reminders = Reminders (open the message, dismiss)
+help-changelog = Changelog and update notice
+help-cleanup = Clean up the folder by sender or domain
+help-search = Search (from: to: subject: has:attachment is:unread before: after: folder: account:)
+help-quick-actions = Quick actions (archive, move, spam, ...; see config)
+help-navigate = Navigate emails
+help-view = View selected email
+help-delete-selected = Delete selected email
+help-back = Return to email list
+help-reply = Reply to email
+help-reply-all = Reply to all
+help-forward = Forward email
+help-delete = Delete email
+help-bounce = Bounce (redirect) to another address, unchanged
+help-resend = Resend a bounced message to a corrected address
+help-answer-invitation = Accept, accept tentatively or decline an invitation
+help-add-to-calendar = Add the invitation to your calendar
+help-add-contacts = Add attached contact cards to your contacts
+help-burst-digest = Read a digest one message at a time
+help-digest-step = Previous/next message of the digest
+help-save-attachment = Save selected attachment
+help-cancel-download = Cancel attachment download
+help-headers = Expand/collapse headers
+help-expand-quotes = Show/fold quoted text (remembered for the sender)
+help-html-view = Switch between the text and HTML bodies (remembered for the sender)
+help-remote-content = Load/block remote images (remembered for the sender)
+help-open-html = Open the HTML body in the browser
+help-full-message = Show all of a large message shown truncated
+help-next-attachment = Select next attachment
+help-scroll = Scroll email content
+help-cancel = Cancel
+help-send = Send email
+help-save-draft = Save as draft
+help-attach = Add attachment (file browser)
+help-remove-attachment = Remove selected attachment
+help-switch-fields = Switch between fields
+help-lookup-name = In To, Cc or Bcc: look up the name typed among your contacts
+help-markdown = Switch between plain text and Markdown
+help-priority = Send with high, low or normal priority
+help-pgp-sign = Sign with your PGP key
+help-pgp-encrypt = Encrypt to the recipients' PGP keys
+help-quote-split = Answer inline: split the quote at the cursor
+help-quote-paragraph = Quote the paragraph at the cursor
+help-strip-quote = Remove quoted text below the cursor
+
+## Status messages: selection and accounts
+
+msg-no-email-selected = No email selected
+msg-invalid-email-selection = Invalid email selection
+msg-invalid-selection = Invalid selection
+msg-account-not-found = Current account not found
+msg-client-not-initialized = Email client not initialized for current account
+msg-client-not-initialized-here = Email client not initialized for this account
+msg-load-folders-failed = Failed to load folders for account { $account }: { $error }
+msg-account-initialized = Initialized account: { $account }
+msg-connect-failed = Failed to connect { $account }: { $error }
+msg-default-account-failed = Failed to initialize default account: { $error }
+msg-account-init-failed = Failed to initialize account: { $error }
+msg-account-not-connected = The account is not connected
+msg-only-one-account = Only one account configured
+msg-load-inbox-failed = Failed to load INBOX for account: { $error }
+msg-switched-account = Switched to account: { $account }
+msg-store-password-failed = Failed to store password: { $error }
+msg-password-saved = Saved the { $protocol } password of { $account }
+msg-signed-in = Signed in to { $account }
+msg-retry-failed = Retry failed: { $error }
+msg-smtp-route-saved = { $account } now sends via { $route }
+msg-smtp-route-unsaved = Using { $route } for this session; failed to save the config: { $error }
+msg-error = Error: { $error }
+
+## Status messages: read-only mode
+
+msg-read-only-sending = Sending is disabled in read-only mode
+msg-read-only-folders = Folders can't be changed in read-only mode
+msg-read-only-read-later = Read later is disabled in read-only mode
+msg-read-only-cleanup = Cleanup is disabled in read-only mode
+msg-read-only-quick-actions = Quick actions are disabled in read-only mode
+msg-read-only-deleting = Deleting is disabled in read-only mode
+msg-read-only-moving = Moving is disabled in read-only mode
+
+## Status messages: spelling and grammar
+
+msg-spellcheck-on = Spell checking enabled
+msg-spellcheck-off = Spell checking disabled
+msg-grammar-on = Grammar checking enabled
+msg-grammar-off = Grammar checking disabled
+msg-no-spelling-suggestions = No spelling suggestions available at cursor position
+msg-no-grammar-suggestions = No grammar suggestions available at cursor position
+msg-replaced = Replaced '{ $original }' with '{ $suggestion }'
+msg-word-added = Added '{ $word }' to personal dictionary
+msg-no-misspelled-word = No misspelled word at cursor position
+msg-dictionary-save-failed = Failed to save personal dictionary: { $error }
+msg-word-removed = Removed '{ $word }' from personal dictionary
+
+## Status messages: tags, VIPs, muting and notifications
+
+msg-no-message-id-tags = This message has no Message-ID to tag it by
+msg-tags-save-failed = Failed to save tags: { $error }
+msg-tags-notmuch-failed = Tags saved here but not in notmuch: { $error }
+msg-no-tags = No tags
+msg-tags = Tags: { $tags }
+msg-vips-update-failed = Failed to update VIPs: { $error }
+msg-vip-removed = { $address } is no longer a VIP
+msg-vip-added = { $address } is now a VIP
+msg-no-message-id-mute = This message has no Message-ID, so its conversation can't be muted
+msg-mute-update-failed = Failed to update muted conversations: { $error }
+msg-muted-archived = Conversation muted: new messages are marked read and moved to { $folder }
+msg-muted = Conversation muted: new messages are marked read
+msg-unmuted = Conversation unmuted
+msg-not-forge-link = This message is not a GitHub or GitLab notification with a link
+msg-opening = Opening { $url }
+msg-open-failed = Failed to open { $url }: { $error }
+msg-not-forge = This message is not a GitHub or GitLab notification
+msg-forge-no-unsubscribe = This { $forge } notification has no unsubscribe address
+msg-forge-muted = Muted { $thread } on { $forge }
+msg-unsubscribe-failed = Failed to unsubscribe: { $error }
+
+## Status messages: bounces, HTML and invitations
+
+msg-not-bounce = Not a bounced message, or the sent message is not stored
+msg-bounced-gone = The bounced message is no longer stored
+msg-bounced-load-failed = Failed to load the bounced message: { $error }
+msg-correct-and-resend = Correct the address and send again
+msg-attach-failed = Failed to attach { $path }: { $error }
+msg-sender-preference-failed = Failed to remember this for { $address }: { $error }
+msg-no-html = This message has no HTML body
+msg-remote-content-loaded = Remote content of messages from { $sender } is loaded
+msg-remote-content-blocked = Remote content of messages from { $sender } is blocked
+msg-opening-browser = Opening the message in the browser
+msg-browser-failed = Failed to open the message in the browser: { $error }
+msg-not-digest = This message is not a digest
+msg-no-invitation = This message has no calendar invitation
+msg-invitation-cancelled = This message cancels the event; there is nothing to add
+msg-no-calendar = No calendar is set up for this account; add "caldav" to it in the config
+msg-event-added = Added '{ $event }' to your calendar
+msg-event-add-failed = Failed to add the event to your calendar: { $error }
+msg-no-contact-card = This message has no contact card with an email address
+msg-contact-people = { $count } people
+msg-contacts-added = Added { $who } to your contacts ({ $count ->
+        [one] 1 address
+       *[other] { $count } addresses
+    })
+msg-contacts-add-failed = Failed to add to your contacts: { $error }
+msg-cannot-answer = Can't answer this invitation: { $error }
+msg-answer-failed = Failed to answer the invitation: { $error }
+msg-answered = Told { $organizer } you { $answer ->
+        [ACCEPTED] accepted
+        [TENTATIVE] tentatively accepted
+       *[DECLINED] declined
+    } '{ $event }'
+msg-answered-added = Told { $organizer } you { $answer ->
+        [ACCEPTED] accepted
+        [TENTATIVE] tentatively accepted
+       *[DECLINED] declined
+    } '{ $event }' and added it to your calendar
+msg-answered-not-added = Told { $organizer } you { $answer ->
+        [ACCEPTED] accepted
+        [TENTATIVE] tentatively accepted
+       *[DECLINED] declined
+    } '{ $event }', but could not add it to your calendar: { $error }
+msg-no-pgp-key = No PGP key is set up for this account; add "pgp" to it in the config
+msg-not-invitation = This message is not an invitation to answer
+
+## Status messages: syncing and folders
+
+msg-updates-by-package-manager = This build is updated by your package manager
+msg-sync-paused = Background sync paused (press 'p' to resume)
+msg-sync-resumed = Background sync resumed
+msg-sync-state-reset = Sync state reset. Next refresh will fetch all emails.
+msg-load-emails-failed = Failed to load emails: { $error }
+msg-disk-low = Low disk space: { $free } free. Downloading headers only until { $needed } MB are free
+msg-disk-ok = Enough disk space again; downloading message bodies
+msg-deleted-elsewhere = { $count ->
+        [one] 1 message in { $folder } was
+       *[other] { $count } messages in { $folder } were
+    } deleted by another client; U lists them to restore
+msg-operation-failed = { $error }; O lists queued operations
+msg-reset-sync-failed = Failed to reset sync state: { $error }
+msg-refresh-failed = Failed to refresh emails: { $error }
+msg-full-resync-done = Full re-sync completed - all emails fetched
+msg-refreshed = Emails refreshed
+msg-mark-read-failed = Failed to mark email as read: { $error }
+msg-inbox-fixed = The INBOX can't be renamed or deleted
+msg-select-folder = Select a folder first
+msg-folder-creating = Creating { $folder }...
+msg-folder-renaming = Renaming { $folder } to { $name }...
+msg-folder-deleting = Deleting { $folder }...
+msg-folder-created = Created { $folder }
+msg-folder-renamed = Renamed { $folder } to { $name }
+msg-folder-deleted = Deleted { $folder }
+msg-folder-options = { $folder } is { $notify ->
+        [never] never notified
+       *[other] notified
+    } and { $sync ->
+        [opened] synced only when opened
+       *[other] synced in the background
+    }
+msg-folder-options-unsaved = { $folder } is { $notify ->
+        [never] never notified
+       *[other] notified
+    } and { $sync ->
+        [opened] synced only when opened
+       *[other] synced in the background
+    } for this session; failed to save the config: { $error }
+msg-density = The email list is now { $density ->
+        [compact] compact
+       *[comfortable] comfortable
+    }
+msg-density-unsaved = The email list is { $density ->
+        [compact] compact
+       *[comfortable] comfortable
+    } for this session; failed to save the config: { $error }
+msg-load-contacts-failed = Failed to load contacts: { $error }
+msg-load-conversation-failed = Failed to load conversation: { $error }
+msg-importance-all = Showing all messages by date
+msg-importance-high-first = Showing high-priority messages first
+msg-importance-high-only = Showing only high-priority messages
+msg-showing-threads = Showing conversations
+msg-showing-messages = Showing one message per row
+
+## Status messages: searching
+
+msg-invalid-search = Invalid search: { $error }
+msg-no-account-matches = No account matches '{ $account }'
+msg-search-matches = { $count ->
+        [one] 1 message matches
+       *[other] { $count } messages match
+    }
+msg-search-failed = Search failed: { $error }
+msg-tag-search-local = Tags are only kept locally, so tag: searches can't use on:server
+msg-search-local-only = Not connected: only the local cache was searched
+msg-search-server = { $count ->
+        [one] 1 message matches
+       *[other] { $count } messages match
+    } here; searching the server…
+msg-server-search-failed = Server search failed: { $error }
+msg-search-server-matches = { $count ->
+        [one] 1 message matches
+       *[other] { $count } messages match
+    }, { $added } of them found on the server
+
+## Status messages: read later, cleanup and quick actions
+
+msg-load-read-later-failed = Failed to load the read-later queue: { $error }
+msg-load-vip-failed = Failed to load the VIP folder: { $error }
+msg-load-notifications-failed = Failed to load notifications: { $error }
+msg-read-later-failed = Read later failed: { $error }
+msg-read-later-saved = Saved to read later: { $subject }
+msg-read-later-done = Done: { $subject } ({ $count } left)
+msg-read-later-removed = Removed from read later: { $subject }
+msg-cleanup-select-folder = Select a folder to clean up
+msg-load-folder-failed = Failed to load { $folder }: { $error }
+msg-cleanup-running = A cleanup is still running
+msg-already-in = Already in { $folder }
+msg-cleanup-started = Cleaning up { $count ->
+        [one] 1 sender
+       *[other] { $count } senders
+    }...
+msg-cleanup-move = move to { $folder }
+msg-cleanup-delete = delete
+msg-cleanup-stopped = Cleanup stopped after { $count ->
+        [one] 1 message
+       *[other] { $count } messages
+    }: { $error }
+msg-cleanup-done = Cleanup: { $action } { $count ->
+        [one] 1 message
+       *[other] { $count } messages
+    }
+msg-cleanup-rules-exist = These senders already have a cleanup rule
+msg-cleanup-rules-added = Added { $count ->
+        [one] 1 rule
+       *[other] { $count } rules
+    } moving their mail to { $folder }; R shows what they match
+msg-cleanup-rules-unsaved = Added { $count ->
+        [one] 1 rule
+       *[other] { $count } rules
+    } for this session; failed to save the config: { $error }
+msg-dry-run-failed = Failed to load emails for dry run: { $error }
+msg-no-quick-action = No quick action on { $key } (see "quick_actions" in config.json)
+msg-no-spam-folder = No spam folder found; set "folder" on the spam quick action
+msg-quick-action-failed = { $action } failed: { $error }
+msg-quick-action-done = { $action }: { $subject }
+quick-archive = Archive to { $folder }
+quick-move = Move to { $folder }
+quick-spam = Spam
+quick-mark-read = Mark read
+quick-mark-unread = Mark unread
+quick-flag = Flag
+quick-delete = Delete
+
+## Status messages: diagnostics, drafts and restoring
+
+msg-load-audit-failed = Failed to load the audit log: { $error }
+msg-audit-exported = Exported { $count ->
+        [one] 1 entry
+       *[other] { $count } entries
+    } to { $path }
+msg-audit-export-failed = Failed to export the audit log: { $error }
+msg-load-drafts-failed = Failed to load drafts: { $error }
+msg-draft-sync-failed = Draft sync failed: { $error }
+msg-draft-conflicts = { $count ->
+        [one] 1 draft was
+       *[other] { $count } drafts were
+    } edited here and on another device: choose a version in Drafts (W)
+msg-drafts-downloaded = { $count ->
+        [one] 1 draft
+       *[other] { $count } drafts
+    } updated from the server
+msg-draft-save-failed = Failed to save draft: { $error }
+msg-draft-saved = Draft saved: { $subject }
+msg-no-subject = (no subject)
+msg-draft-discard-failed = Failed to discard draft: { $error }
+msg-draft-discarded = Draft discarded
+msg-draft-conflict-resolved = Draft conflict resolved
+msg-draft-conflict-failed = Failed to resolve draft conflict: { $error }
+msg-load-deleted-failed = Failed to load deleted messages: { $error }
+msg-restored = Restored "{ $subject }" to { $folder }
+msg-restore-failed = Failed to restore message: { $error }
+msg-drop-copy-failed = Failed to drop the kept copy: { $error }
+msg-load-operations-failed = Failed to load queued operations: { $error }
+
+## Status messages: reminders and queued operations
+
+msg-load-reminders-failed = Failed to load reminders: { $error }
+msg-reminder-set = I'll remind you of "{ $subject }" { $when }; Z lists reminders
+msg-reminder-save-failed = Failed to save the reminder: { $error }
+msg-reminder-due = ⏰ Reminder: { $subject } (Z to open it)
+msg-reminders-due = ⏰ { $count } reminders are due (Z to see them)
+msg-reminder-account-gone = The account { $account } is no longer configured
+msg-reminder-message-gone = "{ $subject }" is no longer in { $folder }
+msg-reminder-dismiss-failed = Failed to dismiss the reminder: { $error }
+msg-operation-target = The move now goes to { $folder }
+msg-operation-target-failed = Failed to change the target folder: { $error }
+msg-operation-offline = { $account } is offline; the operation waits until it is back online
+msg-operation-retrying = Retrying { $operation }
+msg-operation-retry-failed = Failed to retry the operation: { $error }
+msg-operation-cancel-failed = Failed to cancel the operation: { $error }
+msg-operation-not-move = Only a move has a target folder
+
+## Status messages: composing, moving and sending
+
+msg-replying = Replying to email - cursor positioned at top
+msg-replying-all = Replying to all - cursor positioned at top
+msg-forwarding = Forwarding email - add recipients
+msg-directory-searching = Still searching the directory for '{ $name }'
+msg-no-contact-named = No contact named '{ $name }'
+msg-move-server-only = Only messages on the server can be moved
+msg-move-open-folder = Open { $folder } to move this message
+msg-moving = Moving to { $folder }: { $subject }
+msg-moving-marked = Moving { $count } marked messages to { $folder }
+msg-no-typos = No recipient looks mistyped
+msg-corrected = Corrected to { $addresses }
+msg-no-bounced-recipients = No recipient has hard-bounced
+msg-bounced-removed = Removed { $addresses } from the message, the address book and VIPs
+msg-bounced-removed-partly = Removed { $addresses } from the message; failed to update contacts: { $error }
+msg-no-recipient = No recipient given
+msg-bounced-to = Bounced to { $recipients }
+msg-bounce-failed = Bounce failed: { $error }
+msg-email-deleted = Email deleted
+msg-delete-failed = Failed to delete email: { $error }
+msg-not-an-address = '{ $name }' is not an address; Enter in its field looks it up among your contacts
+msg-recipient-typo = { $typo } looks like a typo of { $suggestion }: Alt+C to correct, Ctrl+S again to send as typed
+msg-recipient-bounced = { $addresses } hard-bounced before: Alt+U to remove, Ctrl+S again to send anyway
+msg-sent-with-attachments = Email sent successfully with { $count ->
+        [one] 1 attachment
+       *[other] { $count } attachments
+    }
+msg-sent = Email sent successfully
+msg-send-failed = Failed to send email: { $error }
+
+## Status messages: attachments and files
+
+msg-load-attachments-failed = Failed to load attachments: { $error }
+msg-attachment-not-downloaded-open = Attachment has not been downloaded yet; open its message to fetch it
+msg-attachment-evicted = Attachment is no longer in the cache
+msg-load-attachment-failed = Failed to load attachment: { $error }
+msg-read-attachment-failed = Failed to read attachment: { $error }
+msg-save-attachment-hint = SAVE ATTACHMENT: Press 'q' for quick save to Downloads, or use ↑↓ to navigate folders then Enter to save
+msg-file-browser-cancelled = File browser cancelled
+msg-edit-filename = Edit filename and press Enter to save, or Esc to cancel
+msg-enter-filename = Enter filename and press Enter to save, or Esc to cancel
+msg-downloads-dir-failed = Failed to create downloads directory: { $error }
+msg-read-dir-failed = Failed to read directory: { $error }
+msg-attachment-input-cancelled = Attachment input cancelled
+msg-file-browser-test = TEST: File browser opened - try arrow keys and 'q' to save
+msg-attachment-not-downloaded = Attachment has not been downloaded yet
+msg-invalid-attachment = Invalid attachment index
+msg-no-attachment-selected = No attachment selected
+msg-attachment-saved = Attachment saved to: { $path }
+msg-attachment-save-failed = Failed to save attachment: { $error }
+msg-file-browser-hint = Navigate with ↑↓, Enter to select, Backspace for parent dir, Esc to cancel
+msg-attachment-added = Added attachment: { $path }
+msg-read-file-failed = Failed to read file { $path }: { $error }
+msg-clipboard-attached = Attached { $filename } ({ $size } KB) from the clipboard
+msg-attachment-removed = Removed attachment: { $filename }
+msg-attachments-disk-low = Not downloading attachments: only { $free } free on disk
+msg-attachments-no-account = Account not initialized, cannot download attachments
+msg-attachments-downloading = Downloading { $count ->
+        [one] 1 attachment
+       *[other] { $count } attachments
+    } in background
+msg-download-cancelled = Download cancelled
+msg-no-download = No download in progress for this attachment
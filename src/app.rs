@@ -3,49 +3,29 @@ use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
 use std::thread;
 use std::collections::HashMap;
 
-use chrono::{DateTime, Local, Utc};
+use chrono::{DateTime, Local};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use thiserror::Error;
 
-use crate::config::{Config, EmailAccount};
+use crate::config::{Config, EmailAccount, QuickActionKind};
 use crate::credentials::SecureCredentials;
-use crate::email::{debug_log, Email, EmailClient};
+use crate::email::{debug_log, get_global_sync_timestamp, has_new_emails_since_global, Email, EmailClient};
+use crate::events::{normal_mode_action, Action, AppEvent, EventBus};
+use crate::i18n::{tr, tr_args};
 
-// Global sync tracker for efficient new email detection
-static GLOBAL_SYNC_TIMESTAMPS: std::sync::OnceLock<Arc<std::sync::RwLock<HashMap<String, DateTime<Utc>>>>> = std::sync::OnceLock::new();
+/// Audit log entries shown on the diagnostics screen; the export has all of them
+const AUDIT_LOG_ROWS: usize = 500;
 
-fn get_global_sync_timestamps() -> &'static Arc<std::sync::RwLock<HashMap<String, DateTime<Utc>>>> {
-    GLOBAL_SYNC_TIMESTAMPS.get_or_init(|| Arc::new(std::sync::RwLock::new(HashMap::new())))
-}
-
-pub fn update_global_sync_timestamp(account_email: &str, folder: &str, timestamp: DateTime<Utc>) {
-    let key = format!("{}:{}", account_email, folder);
-    if let Ok(mut timestamps) = get_global_sync_timestamps().write() {
-        timestamps.insert(key, timestamp);
-    }
-}
+/// How long typing has to pause before spelling and grammar are checked again
+const CHECK_DELAY: Duration = Duration::from_millis(300);
 
-pub fn has_new_emails_since_global(account_email: &str, folder: &str, ui_timestamp: DateTime<Utc>) -> bool {
-    let key = format!("{}:{}", account_email, folder);
-    if let Ok(timestamps) = get_global_sync_timestamps().read() {
-        if let Some(latest_sync) = timestamps.get(&key) {
-            *latest_sync > ui_timestamp
-        } else {
-            true // If we don't have a sync timestamp, assume there might be new emails
-        }
-    } else {
-        true
-    }
-}
+/// Characters typed in an address field before the directory is searched for them
+const MIN_DIRECTORY_QUERY: usize = 2;
 
-pub fn get_global_sync_timestamp(account_email: &str, folder: &str) -> Option<DateTime<Utc>> {
-    let key = format!("{}:{}", account_email, folder);
-    if let Ok(timestamps) = get_global_sync_timestamps().read() {
-        timestamps.get(&key).copied()
-    } else {
-        None
-    }
-}
+/// Rows of a folder read from the database at a time. The next page is read when the
+/// selection comes within `EMAIL_PAGE_MARGIN` rows of the last one loaded.
+const EMAIL_PAGE_SIZE: usize = 500;
+const EMAIL_PAGE_MARGIN: usize = 100;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -70,6 +50,106 @@ pub enum ComposeField {
     Body,
 }
 
+/// The messages a digest collects, split out to be read and answered one at a time
+#[derive(Debug, Clone)]
+pub struct DigestView {
+    /// `body_view::body_key` of the digest
+    key: String,
+    pub messages: Vec<Email>,
+    /// The message shown in place of the digest, if any
+    pub selected: Option<usize>,
+}
+
+/// The message a compose answers or forwards, flagged on the server once it is sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeSource {
+    pub account_idx: usize,
+    pub folder: String,
+    pub uid: u32,
+    /// `\Answered` or `$Forwarded`
+    pub flag: &'static str,
+}
+
+/// Contacts matching a name typed in an address field, shown for picking one
+#[derive(Debug, Clone)]
+pub struct RecipientChoices {
+    pub field: ComposeField,
+    /// Where the name is in the field's text
+    pub range: std::ops::Range<usize>,
+    pub name: String,
+    pub contacts: Vec<crate::database::ContactSummary>,
+    pub selected: usize,
+}
+
+/// Folders offered for moving the selected message to, narrowed by what is typed
+#[derive(Debug, Clone, Default)]
+pub struct FolderPicker {
+    pub filter: String,
+    pub selected: usize,
+}
+
+impl FolderPicker {
+    /// The folders whose name contains the filter, ignoring case, other than `current`
+    pub fn matches<'a>(&self, folders: &'a [String], current: &str) -> Vec<&'a String> {
+        let filter = self.filter.to_lowercase();
+        folders.iter().filter(|folder| *folder != current && folder.to_lowercase().contains(&filter)).collect()
+    }
+}
+
+/// A folder being created, renamed or deleted from the folder list, until it is confirmed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FolderEdit {
+    /// The name of a new folder of the account, as typed so far
+    Create { account_index: usize, name: String },
+    /// The new name of `folder`, as typed so far
+    Rename { account_index: usize, folder: String, name: String },
+    /// Waiting for `y` to delete `folder` with its messages
+    Delete { account_index: usize, folder: String },
+}
+
+impl FolderEdit {
+    pub fn account_index(&self) -> usize {
+        match self {
+            FolderEdit::Create { account_index, .. }
+            | FolderEdit::Rename { account_index, .. }
+            | FolderEdit::Delete { account_index, .. } => *account_index,
+        }
+    }
+}
+
+/// The name `folder` has once `old` is renamed to `new`: its own, or the one of a folder
+/// below it, which moves along. `None` for other folders.
+fn renamed_folder(folder: &str, old: &str, new: &str) -> Option<String> {
+    match folder.strip_prefix(old) {
+        Some("") => Some(new.to_string()),
+        Some(rest) if rest.starts_with(['/', '.']) => Some(format!("{}{}", new, rest)),
+        _ => None,
+    }
+}
+
+/// The selected row and first visible row once the list `old` is replaced by `new`,
+/// following the messages by folder and UID. A selected message that is gone leaves the
+/// selection on the same row, or the last. Marks of messages no longer listed are dropped.
+fn carry_list_state(
+    old: &[Email],
+    new: &[Email],
+    selected: Option<usize>,
+    offset: usize,
+    marked: &mut std::collections::HashSet<(String, String)>,
+) -> (Option<usize>, usize) {
+    let key = |email: &Email| (email.folder.clone(), email.id.clone());
+    let position = |idx: usize| old.get(idx).map(key).and_then(|wanted| new.iter().position(|email| key(email) == wanted));
+    let listed: std::collections::HashSet<_> = new.iter().map(key).collect();
+    marked.retain(|marked| listed.contains(marked));
+    let offset = position(offset).unwrap_or(offset);
+    let selected = match selected.and_then(position) {
+        Some(idx) => Some(idx),
+        None if new.is_empty() => None,
+        None => Some(selected.unwrap_or(0).min(new.len() - 1)),
+    };
+    (selected, offset)
+}
+
 #[derive(Debug, Clone)]
 pub struct FileItem {
     pub name: String,
@@ -87,6 +167,24 @@ pub enum AppMode {
     AccountSettings,
     Help,
     DeleteConfirm,
+    People,
+    Attachments,
+    RulesReport,
+    AuthHelp,
+    MissingSecrets,
+    Diagnostics,
+    Capabilities,
+    ImapConsole,
+    SmtpFallbackConfirm,
+    ReplyWarning,
+    Drafts,
+    DraftConflict,
+    Cleanup,
+    Dictionary,
+    RecentlyDeleted,
+    PendingOperations,
+    Reminders,
+    Changelog,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -96,6 +194,49 @@ pub enum FocusPanel {
     ComposeForm,
 }
 
+/// Rows the compose header fields take at first
+pub const COMPOSE_HEADER_HEIGHT: u16 = 12;
+
+/// Fewest rows the compose header fields can be shrunk to: the borders and the To line
+const COMPOSE_HEADER_MIN: u16 = 3;
+
+/// How the email list treats message priority, cycled with `I`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportanceView {
+    All,
+    HighFirst,
+    HighOnly,
+}
+
+impl ImportanceView {
+    fn next(self) -> Self {
+        match self {
+            ImportanceView::All => ImportanceView::HighFirst,
+            ImportanceView::HighFirst => ImportanceView::HighOnly,
+            ImportanceView::HighOnly => ImportanceView::All,
+        }
+    }
+
+    /// Order or narrow a folder's emails (newest first) for display
+    pub fn apply(self, emails: &[Email]) -> Vec<Email> {
+        let high = |email: &Email| email.priority() == Some(crate::config::Priority::High);
+        let rank = |email: &Email| match email.priority() {
+            Some(crate::config::Priority::High) => 0,
+            Some(crate::config::Priority::Low) => 2,
+            _ => 1,
+        };
+        match self {
+            ImportanceView::All => emails.to_vec(),
+            ImportanceView::HighFirst => {
+                let mut sorted = emails.to_vec();
+                sorted.sort_by_key(rank);
+                sorted
+            }
+            ImportanceView::HighOnly => emails.iter().filter(|email| high(email)).cloned().collect(),
+        }
+    }
+}
+
 /// Represents a folder item in the hierarchical view
 #[derive(Debug, Clone)]
 pub enum FolderItem {
@@ -110,6 +251,18 @@ pub enum FolderItem {
         account_index: usize,
         full_path: String, // For IMAP folder path
     },
+    /// Messages of the account tagged to read later, from every folder
+    ReadLater {
+        account_index: usize,
+    },
+    /// Messages of the account from VIP senders, from every folder
+    Vip {
+        account_index: usize,
+    },
+    /// GitHub and GitLab notifications of the account, grouped by repository
+    Notifications {
+        account_index: usize,
+    },
 }
 
 /// Account-specific folder and email data
@@ -133,8 +286,59 @@ impl AccountData {
     }
 }
 
+/// A folder the background sync thread should sync ahead of its schedule
+pub struct SyncRequest {
+    pub account_email: String,
+    pub folder: String,
+    /// Messages on screen that only have headers; their bodies are downloaded first
+    pub window: Vec<u32>,
+}
+
+/// Bodies downloaded per round while filling in a folder in the background
+const BODY_BATCH: usize = 50;
+
+/// Seconds before a failed queued operation is tried again: 30 seconds, doubling with
+/// every failure up to an hour
+fn operation_retry_delay(attempts: u32) -> i64 {
+    (30i64 << attempts.min(7)).min(3600)
+}
+
+/// Hand a link to the desktop's browser without waiting for it
+fn open_in_browser(url: &str) -> std::io::Result<()> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        ("cmd", &["/C", "start", ""])
+    } else {
+        ("xdg-open", &[])
+    };
+    std::process::Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// A refused login shown in the sign-in help dialog
+pub struct AuthProblem {
+    pub account_idx: usize,
+    pub failure: crate::auth::AuthFailure,
+    pub server_message: String,
+    /// Whether the SMTP login failed (while sending) rather than IMAP
+    pub smtp: bool,
+    /// The replacement password being typed, while changing it
+    pub new_password: Option<String>,
+    /// Where to go back to when the dialog is closed
+    pub return_mode: AppMode,
+}
+
 pub struct App {
     pub config: Config,
+    /// Where `config` was loaded from, for saving settings changed in the client
+    pub config_path: Option<String>,
     pub credentials: SecureCredentials,
     pub database: std::sync::Arc<crate::database::EmailDatabase>,  // Add database
     pub should_quit: bool,
@@ -151,6 +355,122 @@ pub struct App {
     // Current view state (for the selected account/folder)
     pub emails: Vec<Email>,
     pub selected_email_idx: Option<usize>,
+    pub email_list_offset: std::cell::Cell<usize>, // First visible row, updated while rendering
+    pub folder_email_count: usize, // Messages cached for the folder on screen, loaded or not
+    pub marked_emails: std::collections::HashSet<(String, String)>, // Folder and UID of each marked message
+
+    // People view: correspondents of the current account, and the one whose thread is shown
+    pub contacts: Vec<crate::database::ContactSummary>,
+    pub selected_contact_idx: usize,
+    // Cleanup assistant: the folder grouped by sender or domain, the marked group keys
+    // and an action waiting for confirmation
+    pub cleanup_folder: Option<(usize, String)>,
+    pub cleanup_by: crate::cleanup::GroupBy,
+    pub cleanup_groups: Vec<crate::cleanup::SenderGroup>,
+    pub selected_cleanup_idx: usize,
+    pub cleanup_marked: std::collections::HashSet<String>,
+    pub cleanup_confirm: Option<crate::cleanup::CleanupAction>,
+    pub cleanup_running: bool,
+    pub contact_filter: Option<String>,
+    // Read-later queue: shown instead of a folder, and how many were marked done this session
+    pub read_later_view: bool,
+    pub read_later_done: usize,
+    /// Whether the list shows the VIP smart folder
+    pub vip_view: bool,
+    /// Whether the list shows the GitHub/GitLab notifications smart folder
+    pub notifications_view: bool,
+    /// Message to open the compose form with once the interface is up (`tuimail compose`)
+    pub pending_compose: Option<crate::mailto::Mailto>,
+    pub importance_view: ImportanceView,
+    /// Conversation grouping of the list, toggled with `H`
+    pub thread_view: crate::threads::ThreadView,
+    // Search bar: the query being typed, and the one whose results the list shows
+    pub search_text: String,
+    pub search_editing: bool,
+    pub search_query: Option<String>,
+
+    // Attachment browser: the account-wide index, rows matching the filter, and the preview
+    pub attachment_index: Vec<crate::database::AttachmentIndexEntry>,
+    pub attachment_index_visible: Vec<usize>,
+    pub attachment_index_selected: usize,
+    pub attachment_filter_text: String,
+    pub attachment_filter_editing: bool,
+
+    // Recipients typed for bouncing the open email, while the prompt is shown
+    pub bounce_text: String,
+    pub bounce_editing: bool,
+    // Asking whether to accept, tentatively accept or decline the open invitation
+    pub rsvp_prompt: bool,
+    // The messages of the open email when it is a digest
+    digest: Option<DigestView>,
+    // Free disk space while it is below `cache.min_free_mb` and sync lists headers only
+    pub disk_space_low: Option<u64>,
+    pub attachment_preview: Option<String>,
+
+    // Dry-run report of filter rules and auto-archive
+    pub dry_run_days: u32,
+    pub dry_run_report: Vec<crate::rules::DryRunEntry>,
+    pub dry_run_selected: usize,
+
+    pub auth_problem: Option<AuthProblem>,
+    /// Passwords the startup check could not read from the keyring, the selected one,
+    /// and the replacement being typed for it
+    pub missing_secrets: Vec<crate::auth::MissingSecret>,
+    pub selected_secret_idx: usize,
+    pub secret_input: Option<String>,
+    /// SMTP settings a message went out with after the configured ones failed, offered
+    /// for saving to the account
+    pub smtp_fallback_offer: Option<(usize, crate::transport::SmtpRoute)>,
+    /// A reply to the open message being confirmed
+    pub reply_warning: Option<crate::reply_check::PendingReply>,
+
+    // Drafts listed with `W`, and the Message-ID of the one open in the compose form
+    pub drafts: Vec<crate::database::Draft>,
+    pub selected_draft_idx: usize,
+    pub compose_draft_id: Option<String>,
+    /// What the message in the compose form replies to or forwards
+    pub compose_source: Option<ComposeSource>,
+    pub drafts_syncing: bool,
+
+    // Deleted messages kept locally, listed with `U` to restore them
+    pub deleted_messages: Vec<crate::database::DeletedMessage>,
+    pub selected_deleted_idx: usize,
+
+    // Operations waiting for the background sync, listed with `O`, and the target folder
+    // being typed for the selected move
+    pub queued_operations: Vec<crate::database::PendingOperation>,
+    pub selected_operation_idx: usize,
+    pub operation_target_input: Option<String>,
+
+    // Reminders of every account, listed with `Z`, and when to be reminded of the selected
+    // message as it is typed after `z`
+    pub reminders: Vec<crate::reminders::Reminder>,
+    pub selected_reminder_idx: usize,
+    pub reminder_input: Option<String>,
+    /// Folder picker for moving the selected message, opened with `m`
+    pub folder_picker: Option<FolderPicker>,
+    pub folder_edit: Option<FolderEdit>,
+
+    // Releases found by the daily update check, newest first, and the newer version if any
+    pub releases: Vec<crate::updates::Release>,
+    pub update_available: Option<String>,
+    pub changelog_scroll: u16,
+
+    // Diagnostics screen: recent audit log entries of all accounts
+    pub audit_entries: Vec<crate::database::AuditEntry>,
+    pub audit_selected: usize,
+    pub pending_operations: usize,
+    pub database_size: Option<u64>,
+    /// Account shown on the server capabilities screen, with what its servers announce
+    /// once the check is done
+    pub capabilities: Option<(usize, Option<crate::capabilities::ServerCapabilities>)>,
+
+    // Raw IMAP console, only with EMAIL_DEBUG set: the transcript, the command being typed,
+    // how many lines the view is scrolled back, and the channel to the session's thread
+    pub console_lines: Vec<String>,
+    pub console_input: String,
+    pub console_scroll: usize,
+    pub console_commands: Option<std::sync::mpsc::Sender<String>>,
 
     pub compose_email: Email,
     pub error_message: Option<String>,
@@ -159,6 +479,8 @@ pub struct App {
 
     // Scrolling state
     pub email_view_scroll: usize,
+    pub headers_expanded: bool, // Full header panel in the email viewer
+    pub view_preferences: crate::database::SenderPreferences, // How the open message is shown, kept per sender
 
     // Sync status
     pub last_sync: Option<DateTime<Local>>,
@@ -170,13 +492,47 @@ pub struct App {
     pub compose_to_text: String,   // Raw text for To field editing
     pub compose_cc_text: String,   // Raw text for CC field editing
     pub compose_bcc_text: String,  // Raw text for BCC field editing
+    /// Markdown bodies are also sent as HTML
+    pub compose_format: crate::config::ComposeFormat,
+    /// Whether the message is signed and encrypted with the account's PGP keys
+    pub compose_pgp: crate::pgp::Protection,
+    /// Addresses the account has corresponded with, most frequent first, for catching
+    /// typos in recipients; loaded per account when composing
+    pub known_recipients: Option<(usize, Vec<String>)>,
+    /// The same contacts with their names, for looking up a name typed as a recipient
+    address_book: Vec<crate::database::ContactSummary>,
+    /// Contacts a name typed in an address field could mean, to pick one from
+    pub recipient_choices: Option<RecipientChoices>,
+    /// The last directory search: account, the text searched and what was found
+    pub directory_results: Option<(usize, String, Result<Vec<crate::database::ContactSummary>, String>)>,
+    /// Text being searched for in the directory; one search runs at a time
+    directory_pending: Option<String>,
+    /// Set once the user was warned about likely recipient typos, so sending again goes ahead
+    pub recipient_typos_confirmed: bool,
+    /// Set once the user was warned about recipients that hard-bounced before
+    pub suppressed_recipients_confirmed: bool,
 
     // Spell checking
-    pub spell_checker: Option<crate::spellcheck::SpellChecker>,
+    pub spell_checker: Option<Arc<crate::spellcheck::SpellChecker>>,
     pub spell_errors: Vec<crate::spellcheck::SpellError>,
+    /// The text `spell_errors` were found in; edits since are checked by paragraph
+    spell_checked_text: String,
+    /// Counts spell checks started, so results of superseded ones are dropped
+    spell_generation: u64,
+    /// When the edits made while typing are due to be checked
+    checks_due: Option<Instant>,
     pub spell_check_enabled: bool,
     pub show_spell_suggestions: bool,
     pub selected_spell_suggestion: usize,
+    /// Word count, readability and tone of the body, shown in the compose footer
+    pub show_compose_stats: bool,
+    /// Whether the compose body fills the form, with the headers as one summary line
+    pub compose_body_fullscreen: bool,
+    /// Rows the compose header fields take, changed with `Alt+↑`/`Alt+↓`; rendering caps
+    /// it to what the screen leaves
+    pub compose_header_height: std::cell::Cell<u16>,
+    /// Selected word on the personal dictionary screen
+    pub selected_dictionary_idx: usize,
 
     // Grammar checking (async)
     pub async_grammar_checker: Option<crate::async_grammar::AsyncGrammarChecker>,
@@ -198,6 +554,7 @@ pub struct App {
     pub file_browser_save_filename: String,     // Filename to save as
     pub file_browser_save_data: Vec<u8>,        // Data to save
     pub file_browser_editing_filename: bool,    // Whether we're editing the filename
+    pub attachment_downloads: crate::downloads::AttachmentDownloadQueue, // Background attachment downloads
 
     // Background email fetching (legacy)
     pub email_receiver: Option<std::sync::mpsc::Receiver<Vec<crate::email::Email>>>,
@@ -206,9 +563,54 @@ pub struct App {
     // Background sync thread
     pub sync_thread_running: Arc<AtomicBool>,
     pub sync_thread_handle: Option<thread::JoinHandle<()>>,
+    pub sync_paused: Arc<AtomicBool>,
+    /// Folders left out of the scheduled sync, as (account email, folder); shared with
+    /// the sync thread so changes apply without restarting it
+    manual_sync_folders: Arc<std::sync::Mutex<std::collections::HashSet<(String, String)>>>,
+
+    // Sender avatars: decoded thumbnails (None = no usable image) and pending lookups
+    pub avatars: HashMap<String, Option<crate::avatar::AvatarImage>>,
+    avatar_pending: std::collections::HashSet<String>,
+    // Large bodies in the viewer: the prepared text by `body_view::body_key`, the key
+    // being prepared, and whether the whole message was asked for
+    pub view_body: Option<(String, crate::body_view::PreparedBody)>,
+    view_body_pending: Option<String>,
+    // PGP in the viewer: the decrypted or checked message by `body_view::body_key`, or
+    // why that failed, and the key being worked on
+    pub pgp_view: Option<(String, Result<crate::pgp::Opened, String>)>,
+    pgp_pending: Option<String>,
+    // Invitations in the viewer: the busy calendar events clashing with the one with this
+    // `body_view::body_key`, or why the calendar could not be read, and the key being checked
+    pub calendar_view: Option<(String, Result<Vec<crate::calendar::Event>, String>)>,
+    calendar_pending: Option<String>,
+    pub show_full_body: bool,
+    pub sync_request_sender: Option<std::sync::mpsc::Sender<SyncRequest>>,
+    /// UIDs in the shown folder that are listed from headers and have no body yet
+    pub pending_bodies: std::collections::HashSet<u32>,
+    /// Folders (account email, folder) with a priority sync under way
+    pub updating_folders: std::collections::HashSet<(String, String)>,
+    /// Accounts being connected in the background
+    pub connecting_accounts: std::collections::HashSet<usize>,
+    /// Failed recipients of the current account's sent messages, by Message-ID
+    pub bounces: HashMap<String, Vec<crate::database::BounceRecord>>,
+    /// Addresses of the current account that hard-bounced, by lowercase address
+    pub suppressed: HashMap<String, crate::database::SuppressedAddress>,
+    /// Muted conversations of the current account
+    pub muted_threads: Vec<crate::mute::MutedThread>,
+    /// Lowercase addresses of the current account's VIP senders
+    pub vips: std::collections::HashSet<String>,
+    /// Tags of the current account's messages by Message-ID, and the changes being typed
+    /// for the selected message (`+tag -tag`)
+    pub tags: HashMap<String, Vec<String>>,
+    pub tag_input: Option<String>,
+    /// People the current account has written to, for impersonation warnings
+    pub known_contacts: crate::impersonation::KnownContacts,
 
     // UI timestamp tracking for efficient new email detection
     pub ui_timestamps: std::collections::HashMap<String, chrono::DateTime<chrono::Utc>>,
+
+    // Input, timer and background events waiting to be dispatched
+    pub events: EventBus,
 }
 
 impl App {
@@ -261,6 +663,9 @@ impl App {
         }
 
         let current_account_idx = config.default_account;
+        let sync_start_paused = config.sync.start_paused;
+        let manual_sync_folders = config.manual_sync_folders();
+        let headers_expanded_default = config.ui.show_headers;
 
         // Debug logging
         if std::env::var("EMAIL_DEBUG").is_ok() {
@@ -283,6 +688,7 @@ impl App {
 
         Self {
             config,
+            config_path: None,
             credentials,
             database,
             should_quit: false,
@@ -299,6 +705,82 @@ impl App {
             // Current view state
             emails: Vec::new(),
             selected_email_idx: None,
+            email_list_offset: std::cell::Cell::new(0),
+            folder_email_count: 0,
+            marked_emails: std::collections::HashSet::new(),
+
+            contacts: Vec::new(),
+            selected_contact_idx: 0,
+            cleanup_folder: None,
+            cleanup_by: crate::cleanup::GroupBy::Sender,
+            cleanup_groups: Vec::new(),
+            selected_cleanup_idx: 0,
+            cleanup_marked: std::collections::HashSet::new(),
+            cleanup_confirm: None,
+            cleanup_running: false,
+            contact_filter: None,
+            read_later_view: false,
+            vip_view: false,
+            notifications_view: false,
+            pending_compose: None,
+            search_text: String::new(),
+            search_editing: false,
+            search_query: None,
+            read_later_done: 0,
+            importance_view: ImportanceView::All,
+            thread_view: crate::threads::ThreadView::default(),
+
+            attachment_index: Vec::new(),
+            attachment_index_visible: Vec::new(),
+            attachment_index_selected: 0,
+            attachment_filter_text: String::new(),
+            attachment_filter_editing: false,
+
+            bounce_text: String::new(),
+            bounce_editing: false,
+            rsvp_prompt: false,
+            digest: None,
+            disk_space_low: None,
+            attachment_preview: None,
+
+            dry_run_days: crate::rules::DEFAULT_DRY_RUN_DAYS,
+            dry_run_report: Vec::new(),
+            dry_run_selected: 0,
+
+            auth_problem: None,
+            missing_secrets: Vec::new(),
+            selected_secret_idx: 0,
+            secret_input: None,
+            smtp_fallback_offer: None,
+            reply_warning: None,
+            drafts: Vec::new(),
+            selected_draft_idx: 0,
+            compose_draft_id: None,
+            compose_source: None,
+            drafts_syncing: false,
+            deleted_messages: Vec::new(),
+            selected_operation_idx: 0,
+            queued_operations: Vec::new(),
+            reminders: Vec::new(),
+            selected_reminder_idx: 0,
+            reminder_input: None,
+            folder_picker: None,
+            folder_edit: None,
+            operation_target_input: None,
+            selected_deleted_idx: 0,
+            releases: Vec::new(),
+            update_available: None,
+            changelog_scroll: 0,
+
+            audit_entries: Vec::new(),
+            audit_selected: 0,
+            capabilities: None,
+            console_lines: Vec::new(),
+            console_input: String::new(),
+            console_scroll: 0,
+            console_commands: None,
+            pending_operations: 0,
+            database_size: None,
 
             compose_email: Email::new(),
             error_message: None,
@@ -306,20 +788,38 @@ impl App {
             message_timeout: None,
 
             email_view_scroll: 0,
+            headers_expanded: headers_expanded_default,
+            view_preferences: crate::database::SenderPreferences::default(),
             last_sync: None,
             is_syncing: false,
             compose_field: ComposeField::To,
             compose_cursor_pos: 0,
             compose_to_text: String::new(),
+            known_recipients: None,
+            address_book: Vec::new(),
+            recipient_choices: None,
+            directory_results: None,
+            directory_pending: None,
+            recipient_typos_confirmed: false,
+            suppressed_recipients_confirmed: false,
             compose_cc_text: String::new(),
+            compose_format: crate::config::ComposeFormat::Plain,
+            compose_pgp: crate::pgp::Protection::default(),
             compose_bcc_text: String::new(),
             
             // Initialize spell checking
-            spell_checker: Self::init_spell_checker(),
+            spell_checker: Self::init_spell_checker().map(Arc::new),
             spell_errors: Vec::new(),
+            spell_checked_text: String::new(),
+            spell_generation: 0,
+            checks_due: None,
             spell_check_enabled: true,
             show_spell_suggestions: false,
             selected_spell_suggestion: 0,
+            show_compose_stats: false,
+            compose_body_fullscreen: false,
+            compose_header_height: std::cell::Cell::new(COMPOSE_HEADER_HEIGHT),
+            selected_dictionary_idx: 0,
             
             // Initialize async grammar checking
             async_grammar_checker: Self::init_async_grammar_checker(),
@@ -342,6 +842,7 @@ impl App {
             file_browser_save_filename: String::new(),
             file_browser_save_data: Vec::new(),
             file_browser_editing_filename: false,
+            attachment_downloads: crate::downloads::AttachmentDownloadQueue::new(),
 
             // Background email fetching (legacy)
             email_receiver: None,
@@ -350,9 +851,32 @@ impl App {
             // Background sync thread
             sync_thread_running: Arc::new(AtomicBool::new(false)),
             sync_thread_handle: None,
+            sync_paused: Arc::new(AtomicBool::new(sync_start_paused)),
+            manual_sync_folders: Arc::new(std::sync::Mutex::new(manual_sync_folders)),
+            avatars: HashMap::new(),
+            avatar_pending: std::collections::HashSet::new(),
+            view_body: None,
+            view_body_pending: None,
+            pgp_view: None,
+            pgp_pending: None,
+            calendar_view: None,
+            calendar_pending: None,
+            show_full_body: false,
+            sync_request_sender: None,
 
             // UI timestamp tracking
+            pending_bodies: std::collections::HashSet::new(),
+            updating_folders: std::collections::HashSet::new(),
+            connecting_accounts: std::collections::HashSet::new(),
+            bounces: HashMap::new(),
+            suppressed: HashMap::new(),
+            muted_threads: Vec::new(),
+            vips: std::collections::HashSet::new(),
+            tags: HashMap::new(),
+            tag_input: None,
+            known_contacts: crate::impersonation::KnownContacts::default(),
             ui_timestamps: std::collections::HashMap::new(),
+            events: EventBus::new(),
         }
     }
 
@@ -360,7 +884,10 @@ impl App {
 
     /// Initialize spell checker
     fn init_spell_checker() -> Option<crate::spellcheck::SpellChecker> {
-        let config = crate::spellcheck::SpellCheckConfig::default();
+        let config = crate::spellcheck::SpellCheckConfig {
+            personal_dictionary_path: Some(crate::profile::dictionary_path().to_string_lossy().into_owned()),
+            ..Default::default()
+        };
         match crate::spellcheck::SpellChecker::new(&config) {
             Ok(checker) => {
                 log::info!("Spell checker initialized successfully");
@@ -387,42 +914,71 @@ impl App {
         }
     }
 
-    /// Check spelling of current compose field
+    /// Text of the compose field being edited, if it is one that gets checked
+    fn checked_field_text(&self) -> Option<String> {
+        match self.compose_field {
+            ComposeField::Subject => Some(self.compose_email.subject.clone()),
+            ComposeField::Body => Some(self.compose_email.body_text.clone().unwrap_or_default()),
+            ComposeField::To | ComposeField::Cc | ComposeField::Bcc => None, // Don't spell check email addresses
+        }
+    }
+
+    /// Check spelling of the whole current compose field, e.g. after switching fields
     pub fn check_spelling(&mut self) {
+        self.spell_errors.clear();
+        self.spell_checked_text.clear();
+        // Whatever is still being checked was for other text
+        self.spell_generation += 1;
+        self.start_spell_check();
+    }
+
+    /// Check the paragraphs edited since the last spell check, off the UI thread
+    fn start_spell_check(&mut self) {
         if !self.spell_check_enabled {
             self.spell_errors.clear();
             return;
         }
+        let (Some(checker), Some(text)) = (self.spell_checker.clone(), self.checked_field_text()) else {
+            return;
+        };
+        let Some(region) = crate::spellcheck::dirty_paragraphs(&self.spell_checked_text, &text) else {
+            return;
+        };
 
-        if let Some(ref checker) = self.spell_checker {
+        self.spell_generation += 1;
+        let generation = self.spell_generation;
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let (start, _, new_end) = region;
             let config = crate::spellcheck::SpellCheckConfig::default();
-            
-            let text = match self.compose_field {
-                ComposeField::Subject => {
-                    log::debug!("Checking spelling for Subject: '{}'", self.compose_email.subject);
-                    &self.compose_email.subject
-                },
-                ComposeField::Body => {
-                    if let Some(ref body) = self.compose_email.body_text {
-                        log::debug!("Checking spelling for Body: '{}'", body);
-                        body
-                    } else {
-                        log::debug!("Body is empty, no spell checking needed");
-                        ""
-                    }
-                }
-                ComposeField::To | ComposeField::Cc | ComposeField::Bcc => {
-                    log::debug!("Skipping spell check for email address fields");
-                    return; // Don't spell check email addresses
-                }
-            };
+            let errors = checker
+                .check_text(&text[start..new_end], &config)
+                .into_iter()
+                .map(|mut error| {
+                    error.position += start;
+                    error
+                })
+                .collect();
+            let _ = sender.send(AppEvent::SpellChecked { generation, text, region, errors });
+        });
+    }
 
-            let errors = checker.check_text(text, &config);
-            log::debug!("Spell check complete. Found {} errors", errors.len());
-            self.spell_errors = errors;
+    /// Take in the result of a spell check unless the field was switched or checked
+    /// again since it started
+    fn apply_spell_check(&mut self, generation: u64, text: String, region: (usize, usize, usize), errors: Vec<crate::spellcheck::SpellError>) {
+        if generation != self.spell_generation || !self.spell_check_enabled {
+            return;
         }
+        log::debug!("Spell check of {}..{} found {} errors", region.0, region.2, errors.len());
+        self.spell_errors = crate::spellcheck::merge_errors(std::mem::take(&mut self.spell_errors), region, errors);
+        self.spell_checked_text = text;
     }
-    
+
+    /// Check spelling and grammar once typing pauses, rather than on every keystroke
+    fn schedule_checks(&mut self) {
+        self.checks_due = Some(Instant::now() + CHECK_DELAY);
+    }
+
     /// Request async grammar check of current compose field
     pub fn request_grammar_check(&mut self) {
         if !self.grammar_check_enabled {
@@ -480,10 +1036,10 @@ impl App {
         self.spell_check_enabled = !self.spell_check_enabled;
         if self.spell_check_enabled {
             self.check_spelling();
-            self.show_info("Spell checking enabled");
+            self.show_info(&tr("msg-spellcheck-on"));
         } else {
             self.spell_errors.clear();
-            self.show_info("Spell checking disabled");
+            self.show_info(&tr("msg-spellcheck-off"));
         }
     }
     
@@ -492,13 +1048,13 @@ impl App {
         self.grammar_check_enabled = !self.grammar_check_enabled;
         if self.grammar_check_enabled {
             self.request_grammar_check();
-            self.show_info("Grammar checking enabled");
+            self.show_info(&tr("msg-grammar-on"));
         } else {
             self.grammar_errors.clear();
             if let Some(ref checker) = self.async_grammar_checker {
                 checker.cancel_pending();
             }
-            self.show_info("Grammar checking disabled");
+            self.show_info(&tr("msg-grammar-off"));
         }
     }
 
@@ -520,7 +1076,7 @@ impl App {
             }
         }
         
-        self.show_info("No spelling suggestions available at cursor position");
+        self.show_info(&tr("msg-no-spelling-suggestions"));
     }
     
     /// Show grammar suggestions for text at cursor
@@ -540,7 +1096,7 @@ impl App {
             }
         }
         
-        self.show_info("No grammar suggestions available at cursor position");
+        self.show_info(&tr("msg-no-grammar-suggestions"));
     }
 
     /// Apply selected spell suggestion
@@ -582,9 +1138,9 @@ impl App {
             }
             
             self.show_spell_suggestions = false;
-            self.check_spelling(); // Recheck after replacement
+            self.start_spell_check(); // Recheck the edited paragraph
             self.request_grammar_check(); // Also recheck grammar asynchronously
-            self.show_info(&format!("Replaced '{}' with '{}'", original_word, suggestion));
+            self.show_info(&tr_args("msg-replaced", &[("original", &original_word), ("suggestion", &suggestion)]));
         }
     }
     
@@ -637,9 +1193,9 @@ impl App {
             }
             
             self.show_grammar_suggestions = false;
-            self.check_spelling(); // Recheck spelling
+            self.start_spell_check(); // Recheck the edited paragraph
             self.request_grammar_check(); // Recheck grammar asynchronously
-            self.show_info(&format!("Replaced '{}' with '{}'", original_text, suggestion));
+            self.show_info(&tr_args("msg-replaced", &[("original", &original_text), ("suggestion", &suggestion)]));
         }
     }
 
@@ -662,13 +1218,61 @@ impl App {
         
         if let Some(word) = word_to_add {
             if let Some(ref mut checker) = self.spell_checker {
-                checker.add_to_personal_dictionary(&word);
+                Arc::make_mut(checker).add_to_personal_dictionary(&word);
                 self.check_spelling(); // Recheck after adding to dictionary
-                self.show_info(&format!("Added '{}' to personal dictionary", word));
+                if self.save_personal_dictionary() {
+                    self.show_info(&tr_args("msg-word-added", &[("word", &word)]));
+                }
             }
         } else {
-            self.show_info("No misspelled word at cursor position");
+            self.show_info(&tr("msg-no-misspelled-word"));
+        }
+    }
+
+    /// Write the personal dictionary to its file; false (with the error shown) if that failed
+    fn save_personal_dictionary(&mut self) -> bool {
+        let Some(checker) = &self.spell_checker else {
+            return false;
+        };
+        let path = crate::profile::dictionary_path();
+        match checker.save_personal_dictionary(&path.to_string_lossy()) {
+            Ok(()) => true,
+            Err(e) => {
+                self.show_error(&tr_args("msg-dictionary-save-failed", &[("error", &e)]));
+                false
+            }
+        }
+    }
+
+    /// Words in the personal dictionary, as listed on the dictionary screen
+    pub fn personal_words(&self) -> Vec<String> {
+        self.spell_checker.as_ref().map(|checker| checker.personal_words()).unwrap_or_default()
+    }
+
+    /// Keys on the personal dictionary screen
+    fn handle_dictionary_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        let words = self.personal_words();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::AccountSettings,
+            KeyCode::Up => self.selected_dictionary_idx = self.selected_dictionary_idx.saturating_sub(1),
+            KeyCode::Down if self.selected_dictionary_idx + 1 < words.len() => self.selected_dictionary_idx += 1,
+            KeyCode::Delete | KeyCode::Char('x') => {
+                let Some(word) = words.get(self.selected_dictionary_idx) else {
+                    return Ok(());
+                };
+                if let Some(checker) = self.spell_checker.as_mut() {
+                    Arc::make_mut(checker).remove_from_personal_dictionary(word);
+                }
+                self.selected_dictionary_idx = self.selected_dictionary_idx.min(words.len().saturating_sub(2));
+                // The word counts as misspelled again in the message being written
+                self.check_spelling();
+                if self.save_personal_dictionary() {
+                    self.show_info(&tr_args("msg-word-removed", &[("word", &word)]));
+                }
+            }
+            _ => {}
         }
+        Ok(())
     }
 
     /// Get spell check statistics for current text
@@ -707,6 +1311,14 @@ impl App {
         })
     }
     
+    /// Statistics of the body being written, when the compose footer shows them
+    pub fn get_compose_stats(&self) -> Option<crate::compose_stats::ComposeStats> {
+        if !self.show_compose_stats {
+            return None;
+        }
+        Some(crate::compose_stats::analyze(self.compose_email.body_text.as_deref().unwrap_or_default()))
+    }
+
     /// Get grammar check statistics for the current compose field
     pub fn get_grammar_stats(&self) -> Option<crate::grammarcheck::GrammarCheckStats> {
         if !self.grammar_check_enabled {
@@ -802,6 +1414,9 @@ impl App {
                             full_path: folder.clone(),
                         });
                     }
+                    new_items.push(FolderItem::ReadLater { account_index: account_idx });
+                    new_items.push(FolderItem::Vip { account_index: account_idx });
+                    new_items.push(FolderItem::Notifications { account_index: account_idx });
                 }
             }
         }
@@ -953,10 +1568,11 @@ impl App {
                             }
                         }
 
-                        self.show_error(&format!(
-                            "Failed to load folders for account {}: {}",
-                            account_idx, e
-                        ));
+                        if crate::auth::is_auth_error(&e) {
+                            self.show_auth_problem(account_idx, &e);
+                        } else {
+                            self.show_error(&tr_args("msg-load-folders-failed", &[("account", &account_idx), ("error", &e)]));
+                        }
                         Err(AppError::EmailError(e))
                     }
                 }
@@ -1046,7 +1662,7 @@ impl App {
             account_data.folders = folders;
 
             let account_email = &self.config.accounts[account_idx].email;
-            self.show_info(&format!("Initialized account: {}", account_email));
+            self.show_info(&tr_args("msg-account-initialized", &[("account", &account_email)]));
             Ok(())
         } else {
             Err(AppError::EmailError(crate::email::EmailError::ImapError(
@@ -1055,58 +1671,250 @@ impl App {
         }
     }
 
-    /// Load emails for a specific account and folder - DATABASE ONLY VERSION
+    /// Open the account-specific database (same path logic as EmailClient)
+    fn open_account_database(&self, account_idx: usize) -> AppResult<crate::database::EmailDatabase> {
+        let account = self.accounts.get(&account_idx).map(|data| &data.account).ok_or_else(|| {
+            AppError::EmailError(crate::email::EmailError::ImapError("Account not found".to_string()))
+        })?;
+
+        let db_path = crate::profile::account_cache_dir(&account.email).join("emails.db");
+
+        crate::database::EmailDatabase::new(&db_path)
+            .map_err(|e| AppError::EmailError(crate::email::EmailError::ImapError(
+                format!("Failed to open account database: {}", e)
+            )))
+    }
+
+    /// Whether the folder on screen is still being connected or synced, for the
+    /// indicator in the list header
+    pub fn is_updating_shown_folder(&self) -> bool {
+        let Some((account_idx, folder)) = self.get_selected_folder_info() else {
+            return false;
+        };
+        if self.connecting_accounts.contains(&account_idx) {
+            return true;
+        }
+        let Some(account) = self.config.accounts.get(account_idx) else {
+            return false;
+        };
+        !self.cross_folder_list() && self.updating_folders.contains(&(account.email.clone(), folder))
+    }
+
+    /// Connect an account without blocking the UI; `AccountConnected` reports back
+    fn connect_account_in_background(&mut self, account_idx: usize) {
+        let connected = self.accounts.get(&account_idx).is_some_and(|data| data.email_client.is_some());
+        if connected || self.connecting_accounts.contains(&account_idx) {
+            return;
+        }
+        let Some(account) = self.config.accounts.get(account_idx).cloned() else {
+            return;
+        };
+        if crate::email::is_account_offline(&account.email) {
+            self.show_cached_folders(account_idx);
+            return;
+        }
+        self.connecting_accounts.insert(account_idx);
+
+        // Special-use folders are looked for until some are known
+        let detect = account.special_folders.is_empty();
+        let client = EmailClient::new(account, self.credentials.clone());
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let folders = client
+                .list_folders()
+                .map_err(|e| (e.to_string(), crate::auth::is_auth_error(&e)));
+            let connected = folders.is_ok();
+            let _ = sender.send(AppEvent::AccountConnected { account_idx, folders });
+            if detect && connected {
+                match client.special_folders() {
+                    Ok(folders) if !folders.is_empty() => {
+                        let _ = sender.send(AppEvent::SpecialFoldersFound { account_idx, folders });
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug_log(&format!("Failed to look for special-use folders: {}", e)),
+                }
+            }
+        });
+    }
+
+    /// Keep the special-use folders the server marks in the account's settings, leaving
+    /// those set by hand as they are
+    fn special_folders_found(&mut self, account_idx: usize, found: crate::config::SpecialFolders) {
+        let Some(account) = self.config.accounts.get_mut(account_idx) else {
+            return;
+        };
+        let before = account.special_folders.clone();
+        account.special_folders.fill_from(found);
+        if account.special_folders == before {
+            return;
+        }
+        debug_log(&format!("Special-use folders of {}: {:?}", account.email, account.special_folders));
+        let account = account.clone();
+        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+            account_data.account = account.clone();
+            if let Some(client) = account_data.email_client.as_mut() {
+                client.set_account(account);
+            }
+        }
+        if let Some(path) = &self.config_path {
+            if let Err(e) = self.config.save(path) {
+                debug_log(&format!("Failed to save the special-use folders: {}", e));
+            }
+        }
+    }
+
+    /// The folder archived mail of the current account goes to
+    pub fn archive_folder(&self) -> String {
+        match self.config.accounts.get(self.current_account_idx) {
+            Some(account) => account.archive_folder(&self.config.retention.archive_folder),
+            None => self.config.retention.archive_folder.clone(),
+        }
+    }
+
+    /// Without a connection, list the folders that have mail in the local database
+    fn show_cached_folders(&mut self, account_idx: usize) {
+        let folders = match self.open_account_database(account_idx).map(|db| db.get_all_folders()) {
+            Ok(Ok(folders)) => folders,
+            _ => return,
+        };
+        let Some(account_data) = self.accounts.get_mut(&account_idx) else {
+            return;
+        };
+        let email = account_data.account.email.clone();
+        let cached: Vec<String> = folders.into_iter().filter(|(account, _)| *account == email).map(|(_, folder)| folder).collect();
+        if cached.is_empty() || cached == account_data.folders {
+            return;
+        }
+        account_data.folders = cached;
+        self.rebuild_folder_items();
+    }
+
+    fn account_connected(&mut self, account_idx: usize, folders: Result<Vec<String>, (String, bool)>) {
+        self.connecting_accounts.remove(&account_idx);
+        match folders {
+            Ok(folders) => {
+                let Some(account) = self.config.accounts.get(account_idx).cloned() else {
+                    return;
+                };
+                let client = EmailClient::new(account.clone(), self.credentials.clone());
+                let account_data = self.accounts.entry(account_idx).or_insert_with(|| AccountData::new(account));
+                account_data.email_client = Some(client);
+                account_data.folders = folders;
+
+                // The full folder list may add rows above the selected folder
+                let selected = self.get_selected_folder_info();
+                self.rebuild_folder_items();
+                if let Some(idx) = self.folder_items.iter().position(|item| {
+                    matches!(item, FolderItem::Folder { account_index, full_path, .. }
+                        if selected.as_ref() == Some(&(*account_index, full_path.clone())))
+                }) {
+                    self.selected_folder_item_idx = idx;
+                }
+
+                // Read state of the shown folder can be checked now
+                if let Some((shown_idx, folder)) = self.get_selected_folder_info() {
+                    if shown_idx == account_idx && !self.cross_folder_list() {
+                        self.start_flag_sync(account_idx, &folder);
+                    }
+                }
+            }
+            Err((message, true)) => self.show_auth_problem(account_idx, &crate::email::EmailError::AuthError(message)),
+            Err((message, false)) => {
+                let email = self.config.accounts.get(account_idx).map(|a| a.email.clone()).unwrap_or_default();
+                self.show_error(&tr_args("msg-connect-failed", &[("account", &email), ("error", &message)]));
+            }
+        }
+    }
+
+    /// Load emails for a specific account and folder - DATABASE ONLY VERSION. Cached
+    /// messages are shown straight away; connecting and syncing happen in the background.
     pub fn load_emails_for_account_folder(
         &mut self,
         account_idx: usize,
         folder: &str,
     ) -> AppResult<()> {
-        // Ensure the account is initialized
-        self.ensure_account_initialized(account_idx)?;
-
-        let (account_email, account_db_path) = if let Some(account_data) = self.accounts.get(&account_idx) {
-            // Create account-specific database path (same logic as EmailClient)
-            let cache_dir = format!("{}/.cache/tuimail/{}", 
-                dirs::home_dir().unwrap_or_default().display(), 
-                account_data.account.email.replace('@', "_at_").replace('.', "_"));
-            let db_path = std::path::PathBuf::from(&cache_dir).join("emails.db");
-            (account_data.account.email.clone(), db_path)
-        } else {
-            return Err(AppError::EmailError(crate::email::EmailError::ImapError(
-                "Account not found".to_string(),
-            )));
+        self.connect_account_in_background(account_idx);
+
+        let account_email = match self.accounts.get(&account_idx) {
+            Some(account_data) => account_data.account.email.clone(),
+            None => {
+                return Err(AppError::EmailError(crate::email::EmailError::ImapError(
+                    "Account not found".to_string(),
+                )))
+            }
         };
-        
+        self.load_bounces(account_idx);
+        self.load_muted_threads(account_idx);
+        self.load_vips(account_idx);
+        self.load_tags(account_idx);
+        self.load_known_contacts(account_idx);
+        self.purge_deleted(account_idx);
+        if account_idx == self.current_account_idx {
+            self.request_server_threads(account_idx, folder);
+        }
+
         // Use account-specific database instead of shared database
-        let account_database = crate::database::EmailDatabase::new(&account_db_path)
-            .map_err(|e| AppError::EmailError(crate::email::EmailError::ImapError(
-                format!("Failed to open account database: {}", e)
-            )))?;
+        let account_database = self.open_account_database(account_idx)?;
         
-        // Load emails from account-specific database
-        match account_database.get_all_emails(&account_email, folder) {
-            Ok(db_emails) => {
+        // Load the newest page from the account-specific database; the rest is read as the
+        // user scrolls down
+        let loaded = account_database.get_email_count(&account_email, folder).and_then(|total| {
+            Ok((total, account_database.get_emails_paginated(&account_email, folder, 0, EMAIL_PAGE_SIZE)?))
+        });
+        match loaded {
+            Ok((total, db_emails)) => {
                 debug_log(&format!(
-                    "Loaded {} emails from database for {}/{}",
+                    "Loaded {} of {} emails from database for {}/{}",
                     db_emails.len(),
+                    total,
                     account_email,
                     folder
                 ));
-                
+                // Only mail newer than what is loaded is picked up by the database poll
+                if let Some(newest) = db_emails.first() {
+                    self.ui_timestamps.insert(format!("{}:{}", account_email, folder), newest.date.with_timezone(&chrono::Utc));
+                }
+
                 if let Some(account_data) = self.accounts.get_mut(&account_idx) {
                     account_data.emails = db_emails;
 
                     // Update legacy fields for backward compatibility
                     if account_idx == self.current_account_idx {
-                        self.emails = account_data.emails.clone();
+                        self.folder_email_count = total;
+                        // A refresh keeps the selection; in another folder nothing matches
+                        let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+                        self.replace_email_list(shown);
+                        self.contact_filter = None;
+                        self.read_later_view = false;
+                        self.vip_view = false;
+                        self.notifications_view = false;
+                        self.search_query = None;
                     }
                 }
+                // Progress of downloads in the previous folder is no longer shown
+                self.attachment_downloads.clear_finished();
+
+                // Bodies of the messages on screen are downloaded before the rest
+                self.pending_bodies = account_database
+                    .get_pending_body_uids(&account_email, folder, None)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                let window: Vec<u32> = self.emails
+                    .iter()
+                    .take(self.config.sync.body_window)
+                    .filter_map(|email| email.id.parse().ok())
+                    .filter(|uid| self.pending_bodies.contains(uid))
+                    .collect();
 
-                // Check if sync is stale and request background sync if needed
-                if let Err(e) = self.request_sync_if_stale(&account_email, folder) {
+                // Opening a folder gets priority over the scheduled sync
+                if let Err(e) = self.request_immediate_sync(&account_email, folder, window) {
                     debug_log(&format!("Failed to request sync: {}", e));
                 }
 
+                // Meanwhile bring read state of cached messages in line with the server
+                self.start_flag_sync(account_idx, folder);
+
                 Ok(())
             }
             Err(e) => {
@@ -1122,11 +1930,12 @@ impl App {
                     account_data.emails = Vec::new();
                     if account_idx == self.current_account_idx {
                         self.emails = Vec::new();
+                        self.folder_email_count = 0;
                     }
                 }
                 
                 // Request immediate sync for empty database
-                if let Err(e) = self.request_immediate_sync(&account_email, folder) {
+                if let Err(e) = self.request_immediate_sync(&account_email, folder, Vec::new()) {
                     debug_log(&format!("Failed to request immediate sync: {}", e));
                 }
                 
@@ -1135,1325 +1944,5460 @@ impl App {
         }
     }
 
-    /// Request sync if data is stale (older than 5 minutes)
-    fn request_sync_if_stale(&self, account_email: &str, folder: &str) -> AppResult<()> {
-        const MAX_AGE_SECONDS: i64 = 300; // 5 minutes
-        
-        match self.database.is_sync_stale(account_email, folder, MAX_AGE_SECONDS) {
-            Ok(true) => {
-                debug_log(&format!("Data is stale for {}/{}, requesting sync", account_email, folder));
-                // In a full implementation, this would send a signal to the sync daemon
-                // For now, we'll just log it
-                Ok(())
+    /// Ask the background sync thread to sync a folder ahead of its schedule, downloading
+    /// the bodies of the `window` messages first
+    fn request_immediate_sync(&mut self, account_email: &str, folder: &str, window: Vec<u32>) -> AppResult<()> {
+        debug_log(&format!("Requesting immediate sync for {}/{} ({} bodies first)", account_email, folder, window.len()));
+        if let Some(sender) = &self.sync_request_sender {
+            let request = SyncRequest {
+                account_email: account_email.to_string(),
+                folder: folder.to_string(),
+                window,
+            };
+            if sender.send(request).is_err() {
+                debug_log("Background sync thread is not running, dropping sync request");
+            } else if !self.is_sync_paused() {
+                self.updating_folders.insert((account_email.to_string(), folder.to_string()));
             }
-            Ok(false) => {
-                debug_log(&format!("Data is fresh for {}/{}", account_email, folder));
-                Ok(())
+        }
+        Ok(())
+    }
+
+    /// Run a flags-only refresh of the cached messages of a folder in the background
+    fn start_flag_sync(&self, account_idx: usize, folder: &str) {
+        if self.is_sync_paused() {
+            return;
+        }
+        let Some(account_data) = self.accounts.get(&account_idx) else {
+            return;
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            return;
+        };
+        let cached: Vec<(u32, bool)> = account_data
+            .emails
+            .iter()
+            .filter_map(|email| email.id.parse::<u32>().ok().map(|uid| (uid, email.seen)))
+            .collect();
+        if cached.is_empty() {
+            return;
+        }
+
+        let account_email = account_data.account.email.clone();
+        let folder = folder.to_string();
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            // Only what changed since last time, when the server keeps mod-sequences
+            match client.sync_changes(&folder) {
+                Ok(Some(synced)) => {
+                    if !synced.seen.is_empty() {
+                        let _ = sender.send(AppEvent::FlagsSynced { account_email: account_email.clone(), folder: folder.clone(), changed: synced.seen });
+                    }
+                    if synced.expunged > 0 {
+                        let _ = sender.send(AppEvent::ExpungedElsewhere { account_email, folder, count: synced.expunged });
+                    }
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => debug_log(&format!("Change sync failed for {}/{}: {}", account_email, folder, e)),
             }
-            Err(e) => {
-                debug_log(&format!("Failed to check sync staleness: {}", e));
-                Ok(())
+            match client.sync_seen_flags(&folder, &cached) {
+                Ok(changed) if !changed.is_empty() => {
+                    let _ = sender.send(AppEvent::FlagsSynced { account_email: account_email.clone(), folder: folder.clone(), changed });
+                }
+                Ok(_) => {}
+                Err(e) => debug_log(&format!("Flag refresh failed for {}/{}: {}", account_email, folder, e)),
+            }
+            // Messages gone from the server since they were cached were expunged by
+            // another client; keep them so they can be restored
+            match client.keep_expunged(&folder) {
+                Ok(0) => {}
+                Ok(count) => {
+                    let _ = sender.send(AppEvent::ExpungedElsewhere { account_email, folder, count });
+                }
+                Err(e) => debug_log(&format!("Expunge check failed for {}/{}: {}", account_email, folder, e)),
+            }
+        });
+    }
+
+    /// Download the opened message ahead of the rest if it is listed from headers only
+    fn request_selected_body(&mut self) {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)) else {
+            return;
+        };
+        let Ok(uid) = email.id.parse::<u32>() else {
+            return;
+        };
+        if !self.pending_bodies.contains(&uid) {
+            return;
+        }
+        let folder = email.folder.clone();
+        if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
+            let account_email = account_data.account.email.clone();
+            if let Err(e) = self.request_immediate_sync(&account_email, &folder, vec![uid]) {
+                debug_log(&format!("Failed to request body of {}: {}", uid, e));
             }
         }
     }
 
-    /// Request immediate sync for empty folders
-    fn request_immediate_sync(&self, account_email: &str, folder: &str) -> AppResult<()> {
-        debug_log(&format!("Requesting immediate sync for {}/{}", account_email, folder));
-        // In a full implementation, this would send a high-priority signal to the sync daemon
-        // For now, we'll just log it
+    /// Show a folder listed from headers by the sync thread, if it is the one on screen
+    fn reload_cached_folder(&mut self, account_email: &str, folder: &str) -> AppResult<()> {
+        let Some((account_idx, folder_path)) = self.get_selected_folder_info() else {
+            return Ok(());
+        };
+        let shown = self.accounts.get(&account_idx).is_some_and(|data| data.account.email == account_email);
+        if !shown || folder_path != folder || self.cross_folder_list() {
+            return Ok(());
+        }
+
+        let database = self.open_account_database(account_idx)?;
+        // As many rows as were loaded before, so the user's place is still there
+        let loaded = self.accounts.get(&account_idx).map_or(0, |data| data.emails.len());
+        let emails = database.get_emails_paginated(account_email, folder, 0, loaded.max(EMAIL_PAGE_SIZE)).unwrap_or_default();
+        self.folder_email_count = database.get_email_count(account_email, folder).unwrap_or(emails.len());
+        self.pending_bodies = database
+            .get_pending_body_uids(account_email, folder, None)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+            account_data.emails = emails;
+            if account_idx == self.current_account_idx {
+                let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+                self.replace_email_list(shown);
+            }
+        }
         Ok(())
     }
 
-    /// Queue an email operation for background processing
-    pub fn queue_email_operation(&mut self, operation_type: &str, email_uid: u32, target_folder: Option<&str>) -> AppResult<()> {
-        if let Some((account_idx, folder_path)) = self.get_selected_folder_info() {
-            if let Some(account_data) = self.accounts.get(&account_idx) {
-                let account_email = &account_data.account.email;
-                
-                // Queue the operation in database
-                self.database.queue_email_operation(
-                    account_email,
-                    operation_type,
-                    email_uid,
-                    &folder_path,
-                    target_folder
-                )?;
-                
-                debug_log(&format!(
-                    "Queued {} operation for email {} in {}/{}",
-                    operation_type, email_uid, account_email, folder_path
-                ));
-                
-                // Update local state immediately for responsive UI
-                match operation_type {
-                    "mark_read" => {
-                        if let Some(email) = self.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
-                            email.seen = true;
-                        }
-                        // Also update in account data
-                        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
-                            if let Some(email) = account_data.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
-                                email.seen = true;
-                            }
-                        }
-                    }
-                    "mark_unread" => {
-                        if let Some(email) = self.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
-                            email.seen = false;
-                        }
-                        // Also update in account data
-                        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
-                            if let Some(email) = account_data.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
-                                email.seen = false;
-                            }
-                        }
-                    }
-                    "delete" => {
-                        // Remove from local state immediately
-                        self.emails.retain(|e| e.id != email_uid.to_string());
-                        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
-                            account_data.emails.retain(|e| e.id != email_uid.to_string());
-                        }
-                        // Adjust selection if needed
-                        if let Some(idx) = self.selected_email_idx {
-                            if idx >= self.emails.len() && !self.emails.is_empty() {
-                                self.selected_email_idx = Some(self.emails.len() - 1);
-                            }
-                        }
-                    }
-                    _ => {}
+    /// Replace messages listed from headers with their downloaded bodies
+    /// Load the bounces recorded for an account's sent messages
+    fn load_bounces(&mut self, account_idx: usize) {
+        let Some(account_email) = self.accounts.get(&account_idx).map(|data| data.account.email.clone()) else {
+            return;
+        };
+        let records = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_bounces(&account_email).map_err(|e| e.to_string()));
+        match records {
+            Ok(records) => {
+                self.bounces.clear();
+                for record in records {
+                    self.bounces.entry(record.message_id.clone()).or_default().push(record);
                 }
-                
-                Ok(())
-            } else {
-                Err(AppError::EmailError(crate::email::EmailError::ImapError(
-                    "Account not found".to_string(),
-                )))
             }
-        } else {
-            Err(AppError::EmailError(crate::email::EmailError::ImapError(
-                "No folder selected".to_string(),
-            )))
+            Err(e) => debug_log(&format!("Failed to load bounces for {}: {}", account_email, e)),
+        }
+        let suppressed = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_suppressed_addresses(&account_email).map_err(|e| e.to_string()));
+        match suppressed {
+            Ok(suppressed) => self.suppressed = suppressed.into_iter().map(|s| (s.address.clone(), s)).collect(),
+            Err(e) => debug_log(&format!("Failed to load the suppression list of {}: {}", account_email, e)),
         }
     }
 
-    /// Mark current email as read (queue operation)
-    pub fn mark_current_email_as_read(&mut self) -> AppResult<()> {
-        if let Some(idx) = self.selected_email_idx {
-            if idx < self.emails.len() {
-                let email = &self.emails[idx];
-                if !email.seen {
-                    let email_uid: u32 = email.id.parse().unwrap_or(0);
-                    self.queue_email_operation("mark_read", email_uid, None)?;
-                }
-            }
+    fn load_muted_threads(&mut self, account_idx: usize) {
+        let Some(account_email) = self.accounts.get(&account_idx).map(|data| data.account.email.clone()) else {
+            return;
+        };
+        let threads = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_muted_threads(&account_email).map_err(|e| e.to_string()));
+        match threads {
+            Ok(threads) => self.muted_threads = threads,
+            Err(e) => debug_log(&format!("Failed to load muted conversations for {}: {}", account_email, e)),
         }
-        Ok(())
     }
 
-    /// Mark current email as unread (queue operation)
-    pub fn mark_current_email_as_unread(&mut self) -> AppResult<()> {
-        if let Some(idx) = self.selected_email_idx {
-            if idx < self.emails.len() {
-                let email = &self.emails[idx];
-                if email.seen {
-                    let email_uid: u32 = email.id.parse().unwrap_or(0);
-                    self.queue_email_operation("mark_unread", email_uid, None)?;
-                }
-            }
+    fn load_vips(&mut self, account_idx: usize) {
+        let Some(account_email) = self.accounts.get(&account_idx).map(|data| data.account.email.clone()) else {
+            return;
+        };
+        let vips = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_vips(&account_email).map_err(|e| e.to_string()));
+        match vips {
+            Ok(vips) => self.vips = vips.into_iter().collect(),
+            Err(e) => debug_log(&format!("Failed to load VIPs for {}: {}", account_email, e)),
         }
-        Ok(())
     }
 
-    /// Delete current email (queue operation)
-    pub fn delete_current_email(&mut self) -> AppResult<()> {
-        if let Some(idx) = self.selected_email_idx {
-            if idx < self.emails.len() {
-                let email = &self.emails[idx];
-                let email_uid: u32 = email.id.parse().unwrap_or(0);
-                self.queue_email_operation("delete", email_uid, None)?;
+    fn load_tags(&mut self, account_idx: usize) {
+        let Some(account_email) = self.accounts.get(&account_idx).map(|data| data.account.email.clone()) else {
+            return;
+        };
+        let tags = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_tags(&account_email).map_err(|e| e.to_string()));
+        match tags {
+            Ok(tags) => self.tags = tags,
+            Err(e) => debug_log(&format!("Failed to load tags for {}: {}", account_email, e)),
+        }
+    }
+
+    /// The tags of `email`, sorted
+    pub fn tags_of(&self, email: &Email) -> &[String] {
+        email
+            .header("Message-ID")
+            .map(|id| id.trim().trim_matches(['<', '>']))
+            .and_then(|id| self.tags.get(id))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Change the tags of the selected message as typed (`+tag -tag`), here and in
+    /// notmuch when it is shared with it
+    fn retag_selected(&mut self, changes: &str) -> AppResult<()> {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)).cloned() else {
+            self.show_error(&tr("msg-no-email-selected"));
+            return Ok(());
+        };
+        let Some(message_id) = crate::database::message_key(&email) else {
+            self.show_error(&tr("msg-no-message-id-tags"));
+            return Ok(());
+        };
+        let old = self.tags_of(&email).to_vec();
+        let new = match crate::tags::apply_changes(&old, changes) {
+            Ok(new) => new,
+            Err(e) => {
+                self.show_error(&e);
+                return Ok(());
+            }
+        };
+
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+        if let Err(e) = database.set_tags(&account_email, &message_id, &new) {
+            self.show_error(&tr_args("msg-tags-save-failed", &[("error", &e)]));
+            return Ok(());
+        }
+        self.load_tags(self.current_account_idx);
+
+        if let Some(notmuch) = self.config.notmuch.as_ref().filter(|_| crate::notmuch::ENABLED) {
+            if let Err(e) = crate::notmuch::Notmuch::new(notmuch).retag(&message_id, &old, &new) {
+                self.show_error(&tr_args("msg-tags-notmuch-failed", &[("error", &e)]));
+                return Ok(());
             }
         }
+        let shown = crate::tags::shown(&new);
+        self.show_info(&if shown.is_empty() { tr("msg-no-tags") } else { tr_args("msg-tags", &[("tags", &shown.join(" "))]) });
         Ok(())
     }
 
-    /// Reset sync state to force full re-sync of current folder
-    pub fn reset_sync_state(&mut self) -> AppResult<()> {
-        if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
-            // Clear database entries for this folder
-            self.database.clear_folder_emails(&account_data.account.email, &self.selected_folder)?;
-            
-            self.info_message = Some("Sync state reset. Next refresh will fetch all emails.".to_string());
-            self.message_timeout = Some(Instant::now() + Duration::from_secs(3));
+    /// Keys while the tag bar is open: Enter applies the changes
+    fn handle_tag_input(&mut self, key: KeyEvent) -> AppResult<()> {
+        let Some(input) = self.tag_input.as_mut() else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.tag_input = None,
+            KeyCode::Enter => {
+                let changes = input.clone();
+                self.tag_input = None;
+                self.retag_selected(&changes)?;
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => input.push(c),
+            _ => {}
         }
         Ok(())
     }
 
-    /// Start background sync thread
-    pub fn start_background_sync(&mut self) -> AppResult<()> {
-        // Don't start if already running
-        if self.sync_thread_running.load(Ordering::Relaxed) {
-            debug_log("Background sync thread already running");
-            return Ok(());
+    /// Whether `email` is marked, to be moved along with the other marked messages
+    pub fn is_marked(&self, email: &Email) -> bool {
+        self.marked_emails.contains(&(email.folder.clone(), email.id.clone()))
+    }
+
+    /// Mark the selected message, or unmark it, and go on to the next
+    fn toggle_mark(&mut self) {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)) else {
+            return;
+        };
+        let key = (email.folder.clone(), email.id.clone());
+        if !self.marked_emails.remove(&key) {
+            self.marked_emails.insert(key);
         }
+        self.select_next_email();
+    }
 
-        debug_log("Starting background sync thread...");
+    /// Whether `email` was sent by one of the current account's VIPs
+    pub fn is_vip(&self, email: &Email) -> bool {
+        crate::vip::is_vip(email, &self.vips)
+    }
 
-        // Get data needed for sync thread
-        let database_path = self.database.get_database_path();
-        let config = self.config.clone();
+    /// Mark the selected contact as VIP, or unmark them when they already are
+    fn toggle_vip(&mut self) -> AppResult<()> {
+        let Some(address) = self.contacts.get(self.selected_contact_idx).map(|c| c.address.to_lowercase()) else {
+            return Ok(());
+        };
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+        let was_vip = self.vips.contains(&address);
+        let result = if was_vip {
+            database.unset_vip(&account_email, &address)
+        } else {
+            database.set_vip(&account_email, &address)
+        };
+        if let Err(e) = result {
+            self.show_error(&tr_args("msg-vips-update-failed", &[("error", &e)]));
+            return Ok(());
+        }
+        self.load_vips(self.current_account_idx);
+        if was_vip {
+            self.show_info(&tr_args("msg-vip-removed", &[("address", &address)]));
+        } else {
+            self.show_info(&tr_args("msg-vip-added", &[("address", &address)]));
+        }
+        Ok(())
+    }
 
-        // Set running flag
-        self.sync_thread_running.store(true, Ordering::Relaxed);
-        let running_flag = Arc::clone(&self.sync_thread_running);
+    /// The muted conversation `email` is part of, if any
+    pub fn muted_thread(&self, email: &Email) -> Option<&crate::mute::MutedThread> {
+        crate::mute::muted_thread(email, &self.muted_threads)
+    }
 
-        // Start background thread
-        let handle = thread::spawn(move || {
-            debug_log("Background sync thread started");
-            
-            // Create sync components inside the thread
-            let database = match crate::database::EmailDatabase::new(std::path::Path::new(&database_path)) {
-                Ok(db) => Arc::new(db),
-                Err(e) => {
-                    debug_log(&format!("Failed to create database in sync thread: {}", e));
-                    return;
-                }
-            };
+    /// Mute the conversation of the message being viewed, or unmute it when it already
+    /// is. With `archive`, later messages are also moved to the archive folder.
+    fn toggle_mute(&mut self, archive: bool) -> AppResult<()> {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)).cloned() else {
+            return Ok(());
+        };
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
 
-            let mut email_clients = HashMap::new();
-            
-            // Initialize email clients for each account
-            for account in &config.accounts {
-                // Create credentials manager
-                let credentials = match crate::credentials::SecureCredentials::new() {
-                    Ok(creds) => creds,
-                    Err(e) => {
-                        debug_log(&format!("Failed to create credentials for {}: {}", account.email, e));
-                        continue;
-                    }
-                };
-                let client = EmailClient::new(account.clone(), credentials);
-                email_clients.insert(account.email.clone(), client);
+        let existing = self.muted_thread(&email).cloned();
+        let result = match existing {
+            // Pressing the other key switches between marking read and archiving
+            Some(thread) if thread.archive != archive => {
+                database.mute_thread(&account_email, &crate::mute::MutedThread { archive, ..thread })
             }
-            
-            // Run sync loop (no need for async since methods are sync)
-            while running_flag.load(Ordering::Relaxed) {
-                // Sync all accounts
-                for account in &config.accounts {
-                    if !running_flag.load(Ordering::Relaxed) {
-                        break;
-                    }
-                    
-                    if let Some(client) = email_clients.get(&account.email) {
-                        // Simple sync - just fetch new emails for INBOX
-                        match client.list_folders() {
-                            Ok(folders) => {
-                                for folder in folders {
-                                    if folder == "INBOX" {
-                                        match client.fetch_emails(&folder, 0) {
-                                            Ok(emails) => {
-                                                // Store emails in database
-                                                if let Err(e) = database.save_emails(&account.email, &folder, &emails) {
-                                                    debug_log(&format!("Failed to save emails: {}", e));
-                                                } else {
-                                                    debug_log(&format!("Synced {} emails for {}", emails.len(), account.email));
-                                                }
-                                            }
-                                            Err(e) => {
-                                                debug_log(&format!("Failed to fetch emails for {}: {}", account.email, e));
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                debug_log(&format!("Failed to get folders for {}: {}", account.email, e));
-                            }
-                        }
-                    }
-                }
-                
-                // Sleep for sync interval (30 seconds)
-                for _ in 0..30 {
-                    if !running_flag.load(Ordering::Relaxed) {
-                        break;
-                    }
-                    std::thread::sleep(Duration::from_secs(1));
-                }
+            Some(thread) => database.unmute_thread(&account_email, &thread.root),
+            None => {
+                let Some(root) = crate::mute::thread_root(&email) else {
+                    self.show_error(&tr("msg-no-message-id-mute"));
+                    return Ok(());
+                };
+                let thread = crate::mute::MutedThread {
+                    root,
+                    subject: email.subject.clone(),
+                    archive,
+                    muted_at: Local::now().timestamp(),
+                };
+                database.mute_thread(&account_email, &thread)
             }
+        };
+        if let Err(e) = result {
+            self.show_error(&tr_args("msg-mute-update-failed", &[("error", &e)]));
+            return Ok(());
+        }
+        self.load_muted_threads(self.current_account_idx);
 
-            debug_log("Background sync thread stopped");
-        });
-
-        self.sync_thread_handle = Some(handle);
-        debug_log("Background sync thread started successfully");
+        match self.muted_thread(&email) {
+            Some(thread) if thread.archive => self.show_info(&tr_args("msg-muted-archived", &[("folder", &self.archive_folder())])),
+            Some(_) => self.show_info(&tr("msg-muted")),
+            None => self.show_info(&tr("msg-unmuted")),
+        }
         Ok(())
     }
 
-    /// Stop background sync thread
-    pub fn stop_background_sync(&mut self) {
-        if self.sync_thread_running.load(Ordering::Relaxed) {
-            debug_log("Stopping background sync thread...");
-            self.sync_thread_running.store(false, Ordering::Relaxed);
-            
-            if let Some(handle) = self.sync_thread_handle.take() {
-                // Give the thread a moment to finish gracefully
-                std::thread::sleep(Duration::from_millis(100));
-                
-                // If it's still running, we'll just detach it
-                if !handle.is_finished() {
-                    debug_log("Background sync thread still running, detaching...");
-                }
-            }
-            
-            debug_log("Background sync thread stopped");
+    /// Open the pull request, issue or pipeline a GitHub or GitLab notification is about
+    fn open_notification_page(&mut self) {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)) else {
+            return;
+        };
+        let Some(url) = crate::forge::detect(email).and_then(|notification| notification.url) else {
+            self.show_error(&tr("msg-not-forge-link"));
+            return;
+        };
+        match open_in_browser(&url) {
+            Ok(()) => self.show_info(&tr_args("msg-opening", &[("url", &url)])),
+            Err(e) => self.show_error(&tr_args("msg-open-failed", &[("url", &url), ("error", &e)])),
         }
     }
 
-    /// Cleanup when app is shutting down
-    pub fn cleanup(&mut self) {
-        debug_log("App cleanup started");
-        self.stop_background_sync();
-        debug_log("App cleanup completed");
-    }
+    /// Stop notifications about the thread of a GitHub or GitLab notification by writing
+    /// to its unsubscribe address, as replying from the web would
+    fn unsubscribe_notification_thread(&mut self) -> AppResult<()> {
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-sending"));
+            return Ok(());
+        }
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)).cloned() else {
+            return Ok(());
+        };
+        let Some(notification) = crate::forge::detect(&email) else {
+            self.show_error(&tr("msg-not-forge"));
+            return Ok(());
+        };
+        let Some(address) = notification.unsubscribe.clone() else {
+            self.show_error(&tr_args("msg-forge-no-unsubscribe", &[("forge", &notification.forge.name())]));
+            return Ok(());
+        };
 
-    /// Refresh emails from database (called periodically) - optimized with sync tracker
-    pub fn refresh_emails_from_database(&mut self) -> AppResult<()> {
-        if let Some((account_idx, folder_path)) = self.get_selected_folder_info() {
-            let account_email = if let Some(account_data) = self.accounts.get(&account_idx) {
-                account_data.account.email.clone()
-            } else {
-                return Ok(());
-            };
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(account_data) = self.accounts.get(&self.current_account_idx) else {
+            self.show_error(&tr("msg-account-not-found"));
+            return Ok(());
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        let account = account_data.account.clone();
+
+        let mut message = Email::new();
+        message.from.push(crate::email::EmailAddress { name: Some(account.name.clone()), address: account.email.clone() });
+        message.to.push(crate::email::EmailAddress { name: None, address: address.clone() });
+        message.subject = "unsubscribe".to_string();
+        message.body_text = Some("unsubscribe".to_string());
+        match client.send_email(&message, crate::pgp::Protection::default()) {
+            Ok(_) => {
+                self.audit_email(&account.email, &email, &format!("unsubscribe via {}", address));
+                self.show_info(&tr_args("msg-forge-muted", &[("thread", &notification.summary()), ("forge", &notification.forge.name())]));
+            }
+            Err(e) if crate::auth::is_auth_error(&e) => self.show_auth_problem(self.current_account_idx, &e),
+            Err(e) => self.show_error(&tr_args("msg-unsubscribe-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
 
-            // Get UI's last known timestamp for this account/folder
-            let ui_key = format!("{}:{}", account_email, folder_path);
-            let ui_timestamp = self.ui_timestamps.get(&ui_key)
-                .copied()
-                .unwrap_or_else(|| chrono::DateTime::from_timestamp(0, 0).unwrap_or_else(chrono::Utc::now));
+    fn load_known_contacts(&mut self, account_idx: usize) {
+        let Some(account_email) = self.accounts.get(&account_idx).map(|data| data.account.email.clone()) else {
+            return;
+        };
+        let rows = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_named_addresses(&account_email).map_err(|e| e.to_string()));
+        match rows {
+            Ok(rows) => self.known_contacts = crate::impersonation::KnownContacts::new(&rows),
+            Err(e) => debug_log(&format!("Failed to load known contacts for {}: {}", account_email, e)),
+        }
+    }
 
-            // Check if there are potentially new emails using the sync tracker
-            if !has_new_emails_since_global(&account_email, &folder_path, ui_timestamp) {
-                // No new emails detected, skip expensive database queries
-                return Ok(());
-            }
+    /// Whether `email` was sent from outside the current account's internal domains
+    pub fn is_external(&self, email: &Email) -> bool {
+        let Some(sender) = email.from.first() else {
+            return false;
+        };
+        self.config
+            .accounts
+            .get(self.current_account_idx)
+            .is_some_and(|account| account.is_external(&sender.address))
+    }
 
-            debug_log(&format!(
-                "Sync tracker indicates new emails for {}/{}, checking database",
-                account_email, folder_path
-            ));
+    /// Whether the sender of `email` looks like someone posing as a known contact
+    pub fn impersonation_warning(&self, email: &Email) -> Option<crate::impersonation::ImpersonationWarning> {
+        self.known_contacts.check(email.from.first()?)
+    }
 
-            // Get emails that arrived after our UI timestamp
-            match self.database.get_emails_since_timestamp(&account_email, &folder_path, ui_timestamp) {
-                Ok(new_emails) if !new_emails.is_empty() => {
-                    debug_log(&format!(
-                        "Found {} new emails for {}/{} since {}",
-                        new_emails.len(), account_email, folder_path, ui_timestamp
-                    ));
-                    
-                    // Merge new emails with existing ones
-                    if let Some(account_data) = self.accounts.get_mut(&account_idx) {
-                        // Add new emails to the beginning (most recent first)
-                        let mut updated_emails = new_emails;
-                        updated_emails.extend(account_data.emails.clone());
-                        
-                        // Remove duplicates based on ID
-                        updated_emails.sort_by(|a, b| b.date.cmp(&a.date)); // Sort by date descending
-                        updated_emails.dedup_by(|a, b| a.id == b.id);
-                        
-                        account_data.emails = updated_emails;
-                        
-                        // Update UI emails if this is the current account
-                        if account_idx == self.current_account_idx {
-                            self.emails = account_data.emails.clone();
-                        }
-                        
-                        // Update UI timestamp to the latest email timestamp
-                        if let Some(latest_email) = account_data.emails.first() {
-                            let email_time = latest_email.date.with_timezone(&chrono::Utc);
-                            self.ui_timestamps.insert(ui_key, email_time);
-                        }
-                    }
-                }
-                Ok(_) => {
-                    // No new emails, but update UI timestamp to current sync timestamp
-                    if let Some(sync_timestamp) = get_global_sync_timestamp(&account_email, &folder_path) {
-                        self.ui_timestamps.insert(ui_key, sync_timestamp);
-                    }
+    /// Recipients a sent message bounced from, if any
+    pub fn bounces_for(&self, email: &Email) -> Option<&[crate::database::BounceRecord]> {
+        let message_id = crate::dsn::normalize_message_id(email.header("Message-ID")?);
+        self.bounces.get(&message_id).map(Vec::as_slice)
+    }
+
+    /// Start a new message with the content of a bounced one, addressed to the recipients
+    /// it bounced from so their addresses can be corrected. Works on the sent message and
+    /// on the bounce notification.
+    fn resend_bounced(&mut self) -> AppResult<()> {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)).cloned() else {
+            self.show_error(&tr("msg-no-email-selected"));
+            return Ok(());
+        };
+
+        let (original, failed) = if let Some(records) = self.bounces_for(&email) {
+            let failed: Vec<String> = records.iter().map(|r| r.recipient.clone()).collect();
+            (email, failed)
+        } else {
+            let uid: u32 = email.id.parse().unwrap_or_default();
+            let Some(records) = self
+                .bounces
+                .values()
+                .find(|records| records.iter().any(|r| r.bounce_folder == email.folder && r.bounce_uid == uid))
+                .cloned()
+            else {
+                self.show_error(&tr("msg-not-bounce"));
+                return Ok(());
+            };
+            let account_email = self.config.accounts[self.current_account_idx].email.clone();
+            let database = self.open_account_database(self.current_account_idx)?;
+            match database.find_email_by_message_id(&account_email, &records[0].message_id) {
+                Ok(Some(original)) => (original, records.iter().map(|r| r.recipient.clone()).collect()),
+                Ok(None) => {
+                    self.show_error(&tr("msg-bounced-gone"));
+                    return Ok(());
                 }
                 Err(e) => {
-                    debug_log(&format!("Error checking for new emails: {}", e));
+                    self.show_error(&tr_args("msg-bounced-load-failed", &[("error", &e)]));
+                    return Ok(());
                 }
             }
-        }
-        
+        };
+
+        let mut resend = Email::new();
+        resend.subject = original.subject.clone();
+        resend.body_text = original.body_text.clone();
+        resend.attachments = original.attachments.clone();
+        let current_account = &self.config.accounts[self.current_account_idx];
+        resend.from = vec![crate::email::EmailAddress {
+            name: Some(current_account.name.clone()),
+            address: current_account.email.clone(),
+        }];
+
+        self.compose_email = resend;
+        self.compose_to_text = failed.join(", ");
+        self.compose_cc_text = String::new();
+        self.compose_bcc_text = String::new();
+        self.mode = AppMode::Compose;
+        self.focus = FocusPanel::ComposeForm;
+        self.compose_field = ComposeField::To;
+        self.compose_cursor_pos = self.compose_to_text.len();
+        self.show_info(&tr("msg-correct-and-resend"));
         Ok(())
     }
 
-    pub fn init(&mut self) -> AppResult<()> {
-        // Debug logging
-        if std::env::var("EMAIL_DEBUG").is_ok() {
-            let log_file = "/tmp/tuimail_debug.log";
-            if let Ok(mut file) = std::fs::OpenOptions::new()
-                .create(true)
-                .write(true)
-                .append(true)
-                .open(log_file)
-            {
-                use std::io::Write;
-                let _ = writeln!(
-                    file,
-                    "[{}] App::init() called",
-                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-                );
+    /// Open the compose form filled in from a `mailto:` link
+    fn compose_from_mailto(&mut self, mailto: crate::mailto::Mailto) {
+        let addresses = |list: &[String]| -> Vec<crate::email::EmailAddress> {
+            list.iter().map(|address| crate::email::EmailAddress { name: None, address: address.clone() }).collect()
+        };
+        self.compose_email = Email::new();
+        self.compose_email.to = addresses(&mailto.to);
+        self.compose_email.cc = addresses(&mailto.cc);
+        self.compose_email.bcc = addresses(&mailto.bcc);
+        self.compose_email.subject = mailto.subject.unwrap_or_default();
+        self.compose_email.body_text = mailto.body;
+        if let Some(in_reply_to) = mailto.in_reply_to {
+            self.compose_email.headers.insert("In-Reply-To".to_string(), in_reply_to.clone());
+            self.compose_email.headers.insert("References".to_string(), in_reply_to);
+        }
+        self.apply_compose_defaults();
+        // The link's own Cc and Bcc come first, then the account's defaults
+        self.compose_to_text = mailto.to.join(", ");
+        self.mode = AppMode::Compose;
+        self.focus = FocusPanel::ComposeForm;
+        // Start at the first field the link leaves empty
+        (self.compose_field, self.compose_cursor_pos) = if mailto.to.is_empty() {
+            (ComposeField::To, 0)
+        } else if self.compose_email.subject.is_empty() {
+            (ComposeField::Subject, 0)
+        } else {
+            (ComposeField::Body, 0)
+        };
+
+        for path in &mailto.attachments {
+            if let Err(e) = self.add_attachment_from_path(&path.to_string_lossy()) {
+                self.show_error(&tr_args("msg-attach-failed", &[("path", &path.display()), ("error", &e)]));
             }
         }
+        self.check_spelling();
+        self.request_grammar_check();
+    }
 
-        // Validate that we have accounts configured
-        if self.config.accounts.is_empty() {
-            return Err(AppError::EmailError(crate::email::EmailError::ImapError(
-                "No email accounts configured".to_string(),
-            )));
+    fn apply_synced_bodies(&mut self, account_email: &str, folder: &str, uids: &[u32]) {
+        let Some((&account_idx, account_data)) = self
+            .accounts
+            .iter()
+            .find(|(_, data)| data.account.email == account_email)
+        else {
+            return;
+        };
+        if !account_data.emails.iter().any(|email| email.folder == folder) {
+            return;
         }
 
-        // Initialize the current account only (don't initialize all accounts at startup)
-        match self.init_account(self.current_account_idx) {
-            Ok(()) => {
-                // Debug logging
-                if std::env::var("EMAIL_DEBUG").is_ok() {
-                    let log_file = "/tmp/tuimail_debug.log";
-                    if let Ok(mut file) = std::fs::OpenOptions::new()
-                        .create(true)
-                        .write(true)
-                        .append(true)
-                        .open(log_file)
-                    {
-                        use std::io::Write;
-                        let _ = writeln!(
-                            file,
-                            "[{}] Successfully initialized account {}",
-                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                            self.current_account_idx
-                        );
-                    }
+        let full = match self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_emails_by_uids(account_email, folder, uids).map_err(|e| e.to_string()))
+        {
+            Ok(full) => full,
+            Err(e) => {
+                debug_log(&format!("Failed to load downloaded bodies for {}/{}: {}", account_email, folder, e));
+                return;
+            }
+        };
+
+        let replace = |emails: &mut Vec<Email>| {
+            for email in emails.iter_mut().filter(|email| email.folder == folder) {
+                if let Some(downloaded) = full.iter().find(|d| d.id == email.id) {
+                    *email = downloaded.clone();
                 }
             }
-            Err(e) => {
-                // Show error but don't fail completely - allow user to switch accounts
-                self.show_error(&format!("Failed to initialize default account: {}", e));
+        };
+        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+            replace(&mut account_data.emails);
+        }
+        if account_idx == self.current_account_idx {
+            replace(&mut self.emails);
+            for uid in uids {
+                self.pending_bodies.remove(uid);
+            }
+        }
+        debug_log(&format!("Bodies of {} messages arrived for {}/{}", full.len(), account_email, folder));
+    }
 
-                // Debug logging
-                if std::env::var("EMAIL_DEBUG").is_ok() {
-                    let log_file = "/tmp/tuimail_debug.log";
-                    if let Ok(mut file) = std::fs::OpenOptions::new()
-                        .create(true)
-                        .write(true)
-                        .append(true)
-                        .open(log_file)
-                    {
-                        use std::io::Write;
-                        let _ = writeln!(
-                            file,
-                            "[{}] Failed to initialize account {}: {}",
-                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                            self.current_account_idx,
-                            e
-                        );
-                    }
+    /// Apply read-state changes reported by a background flag refresh
+    fn apply_flag_sync(&mut self, account_email: &str, folder: &str, changed: &HashMap<u32, bool>) {
+        let Some((account_idx, account_data)) = self
+            .accounts
+            .iter_mut()
+            .find(|(_, data)| data.account.email == account_email)
+        else {
+            return;
+        };
+
+        for email in account_data.emails.iter_mut().filter(|email| email.folder == folder) {
+            if let Some(seen) = email.id.parse::<u32>().ok().and_then(|uid| changed.get(&uid)) {
+                email.seen = *seen;
+            }
+        }
+        if *account_idx == self.current_account_idx {
+            for email in self.emails.iter_mut().filter(|email| email.folder == folder) {
+                if let Some(seen) = email.id.parse::<u32>().ok().and_then(|uid| changed.get(&uid)) {
+                    email.seen = *seen;
                 }
+            }
+        }
+        debug_log(&format!("Read state updated for {} messages in {}/{}", changed.len(), account_email, folder));
+    }
 
-                // Continue with default folder structure
-                self.rebuild_folder_items();
-                return Ok(()); // Don't fail completely
+    /// Load the avatar of the open email's sender from the cache, looking it up in the
+    /// background when it is missing or stale. Senders without one get their domain's
+    /// brand logo when the message passed DMARC.
+    pub fn request_sender_avatar(&mut self) {
+        if !self.config.ui.avatars {
+            return;
+        }
+        let Some(email) = self.get_current_email() else {
+            return;
+        };
+        let Some(address) = email.from.first().map(|addr| crate::avatar::normalize_address(&addr.address)) else {
+            return;
+        };
+        let brand = crate::avatar::bimi_domain(email);
+
+        if self.load_avatar(&address) && matches!(self.avatars.get(&address), Some(None)) {
+            if let Some(domain) = brand {
+                self.load_avatar(&crate::avatar::bimi_key(&domain));
             }
         }
+    }
 
-        // Load emails for the first folder of the current account
-        if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
-            if !account_data.folders.is_empty() {
-                let folder = account_data.folders[0].clone();
-                if let Err(e) =
-                    self.load_emails_for_account_folder(self.current_account_idx, &folder)
-                {
-                    self.show_error(&format!("Failed to load emails: {}", e));
-                }
+    /// Load the image cached under `key` into `avatars`, fetching it in the background when
+    /// it is missing or stale. True once the lookup's outcome is known.
+    fn load_avatar(&mut self, key: &str) -> bool {
+        if self.avatars.contains_key(key) {
+            return true;
+        }
+        if self.avatar_pending.contains(key) {
+            return false;
+        }
+
+        match self.database.load_avatar(key) {
+            Ok(Some((image, fetched_at)))
+                if chrono::Utc::now().timestamp() - fetched_at < crate::avatar::AVATAR_CACHE_TTL_SECS =>
+            {
+                let thumbnail = image.as_deref().and_then(crate::avatar::decode_thumbnail);
+                self.avatars.insert(key.to_string(), thumbnail);
+                return true;
             }
+            Ok(_) => {}
+            Err(e) => debug_log(&format!("Failed to read avatar cache: {}", e)),
         }
 
-        // Start background sync thread
-        if let Err(e) = self.start_background_sync() {
-            debug_log(&format!("Failed to start background sync: {}", e));
-            // Don't fail the app if background sync fails to start
+        self.avatar_pending.insert(key.to_string());
+        let sender = self.events.sender();
+        let key = key.to_string();
+        thread::spawn(move || {
+            let image = crate::avatar::fetch(&key).map_err(|e| e.to_string());
+            let _ = sender.send(AppEvent::AvatarFetched { key, image });
+        });
+        false
+    }
+
+    /// Lay out a large body of the open email in the background; the viewer shows a
+    /// placeholder until `BodyPrepared` arrives. Small bodies are rendered directly.
+    pub fn prepare_view_body(&mut self) {
+        let Some(email) = self.viewed_email() else {
+            return;
+        };
+        let Some(body) = email.body_text.as_deref().filter(|body| crate::body_view::is_large(body)) else {
+            return;
+        };
+        let key = crate::body_view::body_key(email, self.show_full_body);
+        let prepared = self.view_body.as_ref().is_some_and(|(k, _)| *k == key);
+        if prepared || self.view_body_pending.as_ref() == Some(&key) {
+            return;
         }
 
-        Ok(())
+        debug_log(&format!("Preparing a {} byte body off the UI thread", body.len()));
+        let body = body.to_string();
+        let full = self.show_full_body;
+        self.view_body_pending = Some(key.clone());
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let body = crate::body_view::prepare(&body, full);
+            let _ = sender.send(AppEvent::BodyPrepared { key, body });
+        });
     }
 
-    pub fn handle_key_event(&mut self, key: KeyEvent) -> AppResult<()> {
-        debug_log(&format!(
-            "Input received: {:?}, file_browser_mode: {}",
-            key, self.file_browser_mode
-        ));
+    /// Show the open message the way messages from its sender were last shown
+    fn load_sender_preferences(&mut self) {
+        self.view_preferences = crate::database::SenderPreferences::default();
+        let Some(address) = self.viewed_email().and_then(|email| email.from.first()).map(|from| from.address.clone()) else {
+            return;
+        };
+        let Some(account_email) = self.accounts.get(&self.current_account_idx).map(|data| data.account.email.clone()) else {
+            return;
+        };
+        let preferences = self
+            .open_account_database(self.current_account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_sender_preferences(&account_email, &address).map_err(|e| e.to_string()));
+        match preferences {
+            Ok(preferences) => self.view_preferences = preferences.unwrap_or_default(),
+            Err(e) => debug_log(&format!("Failed to load the view preferences for {}: {}", address, e)),
+        }
+    }
 
-        // Handle file browser mode FIRST, regardless of current app mode
-        if self.file_browser_mode {
-            debug_log("Routing to file browser input handler");
-            return self.handle_file_browser_input(key);
+    /// Change how the open message is shown, and show later messages from its sender
+    /// the same way
+    fn change_view_preferences(&mut self, change: impl FnOnce(&mut crate::database::SenderPreferences)) {
+        change(&mut self.view_preferences);
+        let Some(address) = self.viewed_email().and_then(|email| email.from.first()).map(|from| from.address.clone()) else {
+            return;
+        };
+        let Some(account_email) = self.accounts.get(&self.current_account_idx).map(|data| data.account.email.clone()) else {
+            return;
+        };
+        let saved = self
+            .open_account_database(self.current_account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.set_sender_preferences(&account_email, &address, &self.view_preferences).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            self.show_error(&tr_args("msg-sender-preference-failed", &[("address", &address), ("error", &e)]));
         }
+    }
 
-        match self.mode {
-            AppMode::Normal => self.handle_normal_mode(key),
-            AppMode::Compose => self.handle_compose_mode(key),
-            AppMode::ViewEmail => self.handle_view_mode(key),
-            AppMode::FolderList => self.handle_folder_list_mode(key),
-            AppMode::AccountSettings => self.handle_settings_mode(key),
-            AppMode::Help => self.handle_help_mode(key),
-            AppMode::DeleteConfirm => self.handle_delete_confirm_mode(key),
+    /// Switch the open message between its text and HTML bodies
+    fn toggle_html_view(&mut self) {
+        if self.viewed_email().is_none_or(|email| email.body_html.is_none()) {
+            self.show_error(&tr("msg-no-html"));
+            return;
         }
+        self.email_view_scroll = 0;
+        self.change_view_preferences(|preferences| preferences.html = !preferences.html);
     }
 
-    fn handle_normal_mode(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            KeyCode::Char('q') => {
-                debug_log("Quit requested, cleaning up...");
-                self.cleanup();
-                self.should_quit = true;
-                Ok(())
-            }
-            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Test file browser
-                self.test_file_browser()?;
-                Ok(())
-            }
-            KeyCode::Char('c') => {
-                self.mode = AppMode::Compose;
-                self.focus = FocusPanel::ComposeForm;
-                self.compose_email = Email::new();
-                self.compose_field = ComposeField::To;
-                self.compose_cursor_pos = 0;
-                self.compose_to_text = String::new();
-                self.compose_cc_text = String::new();
-                self.compose_bcc_text = String::new();
-                // Initialize spell and grammar checking for new compose
-                self.check_spelling();
-                self.request_grammar_check();
-                Ok(())
-            }
-            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Test file browser
-                self.test_file_browser()?;
-                Ok(())
-            }
-            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Full re-sync: clear cache and fetch all emails
-                if let Err(e) = self.reset_sync_state() {
-                    self.show_error(&format!("Failed to reset sync state: {}", e));
-                } else if let Err(e) = self.load_emails_for_selected_folder() {
-                    self.show_error(&format!("Failed to refresh emails: {}", e));
-                } else {
-                    self.show_info("Full re-sync completed - all emails fetched");
-                }
-                Ok(())
-            }
-            KeyCode::Char('r') => {
-                // Refresh emails for the currently selected folder
-                if let Err(e) = self.load_emails_for_selected_folder() {
-                    self.show_error(&format!("Failed to refresh emails: {}", e));
-                } else {
-                    self.show_info("Emails refreshed");
-                }
-                Ok(())
-            }
-            KeyCode::Char('f') => {
-                self.mode = AppMode::FolderList;
-                self.focus = FocusPanel::FolderList;
-                Ok(())
-            }
-            KeyCode::Char('s') => {
-                self.mode = AppMode::AccountSettings;
-                Ok(())
-            }
-            KeyCode::Char('?') => {
-                self.mode = AppMode::Help;
-                Ok(())
-            }
-            KeyCode::Up => {
-                self.select_prev_email();
-                Ok(())
-            }
-            KeyCode::Down => {
-                self.select_next_email();
-                Ok(())
-            }
-            KeyCode::Enter => {
-                if let Some(idx) = self.selected_email_idx {
-                    debug_log(&format!("Enter pressed: idx={}, self.emails.len()={}", idx, self.emails.len()));
-                    if idx < self.emails.len() {
-                        self.mode = AppMode::ViewEmail;
+    /// Load or block the remote images of the open message and of later ones from its sender
+    fn toggle_remote_content(&mut self) {
+        self.change_view_preferences(|preferences| preferences.remote_content = !preferences.remote_content);
+        let sender = self.viewed_email().and_then(|email| email.from.first()).map_or(String::new(), |from| from.address.clone());
+        if self.view_preferences.remote_content {
+            self.show_info(&tr_args("msg-remote-content-loaded", &[("sender", &sender)]));
+        } else {
+            self.show_info(&tr_args("msg-remote-content-blocked", &[("sender", &sender)]));
+        }
+    }
 
-                        // Mark as read
-                        if let Err(e) = self.ensure_account_initialized(self.current_account_idx) {
-                            self.show_error(&format!("Failed to initialize account: {}", e));
-                        } else if let Some(account_data) =
-                            self.accounts.get(&self.current_account_idx)
-                        {
-                            let email = &self.emails[idx];
-                            debug_log(&format!("Opening email: subject={}", email.subject));
-                            if !email.seen {
-                                // Queue mark as read operation instead of direct IMAP call
-                                if let Err(e) = self.mark_current_email_as_read() {
-                                    self.show_error(&format!(
-                                        "Failed to mark email as read: {}",
-                                        e
-                                    ));
-                                }
-                            }
-                        }
-                    } else {
-                        debug_log(&format!("Invalid email selection: idx={} >= self.emails.len()={}", idx, self.emails.len()));
-                        self.show_error("Invalid email selection");
-                    }
-                } else {
-                    debug_log("No email selected");
-                    self.show_error("No email selected");
-                }
-                Ok(())
-            }
-            KeyCode::Char('n') => {
-                // Rotate to next account
-                self.rotate_to_next_account()?;
-                Ok(())
-            }
-            KeyCode::Delete => {
-                self.show_delete_confirmation();
-                Ok(())
-            }
-            _ => Ok(()),
+    /// Open the HTML body of the open message in the browser, with remote content
+    /// blocked unless it is loaded for the sender
+    fn open_html_in_browser(&mut self) {
+        let Some(html) = self.viewed_email().and_then(|email| email.body_html.as_deref()) else {
+            self.show_error(&tr("msg-no-html"));
+            return;
+        };
+        let html = crate::body_view::browser_html(html, self.view_preferences.remote_content);
+        let path = std::env::temp_dir().join(format!("tuimail-{}.html", std::process::id()));
+        match std::fs::write(&path, html).and_then(|()| open_in_browser(&path.to_string_lossy())) {
+            Ok(()) => self.show_info(&tr("msg-opening-browser")),
+            Err(e) => self.show_error(&tr_args("msg-browser-failed", &[("error", &e)])),
         }
     }
 
-    /// Handle spell suggestion navigation
-    fn handle_spell_suggestions(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            KeyCode::Esc => {
-                self.show_spell_suggestions = false;
-                Ok(())
-            }
-            KeyCode::Up => {
-                if self.selected_spell_suggestion > 0 {
-                    self.selected_spell_suggestion -= 1;
-                }
-                Ok(())
-            }
-            KeyCode::Down => {
-                // Find current error to get suggestion count
-                for error in &self.spell_errors {
-                    let word_end = error.position + error.word.len();
-                    if self.compose_cursor_pos >= error.position && self.compose_cursor_pos <= word_end {
-                        if self.selected_spell_suggestion < error.suggestions.len().saturating_sub(1) {
-                            self.selected_spell_suggestion += 1;
-                        }
-                        break;
-                    }
-                }
-                Ok(())
-            }
-            KeyCode::Enter => {
-                self.apply_spell_suggestion();
-                Ok(())
+    /// Decrypt the open email or check its signature in the background, if it is
+    /// protected with PGP; the viewer shows the outcome once `PgpOpened` arrives
+    pub fn request_pgp_open(&mut self) {
+        let Some(email) = self.get_current_email() else {
+            return;
+        };
+        let Some(kind) = crate::pgp::kind(email) else {
+            return;
+        };
+        let key = crate::body_view::body_key(email, false);
+        if self.pgp_view.as_ref().is_some_and(|(k, _)| *k == key) || self.pgp_pending.as_ref() == Some(&key) {
+            return;
+        }
+        // Received messages are opened with the default gpg setup when the account has none
+        let config = self.config.accounts.get(self.current_account_idx).and_then(|a| a.pgp.clone()).unwrap_or_default();
+        let client = self.accounts.get(&self.current_account_idx).and_then(|data| data.email_client.clone());
+        let email = email.clone();
+        self.pgp_pending = Some(key.clone());
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let fetch_raw = || {
+                let client = client.ok_or_else(|| "The account is not connected".to_string())?;
+                client.fetch_raw_message_chunked(&email.folder, &email.id, 256 * 1024, |_, _| true).map_err(|e| e.to_string())
+            };
+            let opened = crate::pgp::open(&config, &email, kind, fetch_raw);
+            if let Err(e) = &opened {
+                debug_log(&format!("pgp: could not open {}/{}: {}", email.folder, email.id, e));
             }
-            _ => Ok(()),
+            let _ = sender.send(AppEvent::PgpOpened { key, opened });
+        });
+    }
+
+    /// What PGP made of the open email, if it is protected and done with
+    pub fn pgp_opened(&self, email: &Email) -> Option<&Result<crate::pgp::Opened, String>> {
+        let (key, opened) = self.pgp_view.as_ref()?;
+        (*key == crate::body_view::body_key(email, false)).then_some(opened)
+    }
+
+    /// Look up what the open invitation clashes with in the account's calendar, in the
+    /// background; the viewer shows it once `CalendarChecked` arrives
+    pub fn request_calendar_check(&mut self) {
+        let Some(email) = self.get_current_email() else {
+            return;
+        };
+        if self.config.accounts.get(self.current_account_idx).is_none_or(|a| a.caldav.is_none()) {
+            return;
+        }
+        let Some(event) = crate::calendar::invitation(email)
+            .filter(|invitation| invitation.calendar.method.as_deref().is_none_or(|m| m == "REQUEST"))
+            .and_then(|invitation| invitation.event().cloned())
+        else {
+            return;
+        };
+        let key = crate::body_view::body_key(email, false);
+        if self.calendar_view.as_ref().is_some_and(|(k, _)| *k == key) || self.calendar_pending.as_ref() == Some(&key) {
+            return;
         }
+        let client = self.accounts.get(&self.current_account_idx).and_then(|data| data.email_client.clone());
+        self.calendar_pending = Some(key.clone());
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let conflicts = client
+                .ok_or_else(|| "The account is not connected".to_string())
+                .and_then(|client| client.caldav().map_err(|e| e.to_string()))
+                .and_then(|mut caldav| caldav.events_between(event.start, event.end).map_err(|e| e.to_string()))
+                .map(|events| crate::caldav::conflicts(&event, &events).into_iter().cloned().collect());
+            if let Err(e) = &conflicts {
+                debug_log(&format!("caldav: could not check {}: {}", event.uid, e));
+            }
+            let _ = sender.send(AppEvent::CalendarChecked { key, conflicts });
+        });
     }
-    
-    fn handle_grammar_suggestions(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            KeyCode::Esc => {
-                self.show_grammar_suggestions = false;
-                Ok(())
-            }
-            KeyCode::Up => {
-                if self.selected_grammar_suggestion > 0 {
-                    self.selected_grammar_suggestion -= 1;
-                }
-                Ok(())
-            }
-            KeyCode::Down => {
-                // Find current error to get suggestion count
-                for error in &self.grammar_errors {
-                    if self.compose_cursor_pos >= error.start && self.compose_cursor_pos <= error.end {
-                        if self.selected_grammar_suggestion < error.replacements.len().saturating_sub(1) {
-                            self.selected_grammar_suggestion += 1;
-                        }
-                        break;
-                    }
-                }
-                Ok(())
-            }
-            KeyCode::Enter => {
-                self.apply_grammar_suggestion();
-                Ok(())
-            }
-            _ => Ok(()),
+
+    /// Split the open email into the messages it collects, if it is a digest
+    fn load_digest(&mut self) {
+        let Some(email) = self.get_current_email() else {
+            return;
+        };
+        let key = crate::body_view::body_key(email, false);
+        if self.digest.as_ref().is_some_and(|digest| digest.key == key) {
+            return;
         }
+        let messages = crate::digest::burst(email);
+        self.digest = (!messages.is_empty()).then_some(DigestView { key, messages, selected: None });
     }
 
-    fn handle_compose_mode(&mut self, key: KeyEvent) -> AppResult<()> {
-        // Handle spell suggestion mode
-        if self.show_spell_suggestions {
-            return self.handle_spell_suggestions(key);
+    /// The messages of the open email, when it is a digest
+    pub fn digest(&self) -> Option<&DigestView> {
+        let email = self.get_current_email()?;
+        self.digest.as_ref().filter(|digest| digest.key == crate::body_view::body_key(email, false))
+    }
+
+    /// The message of a digest shown in place of it
+    pub fn digest_message(&self) -> Option<&Email> {
+        let digest = self.digest()?;
+        digest.messages.get(digest.selected?)
+    }
+
+    /// What the viewer shows: the open email, or the message of a digest picked from it
+    pub fn viewed_email(&self) -> Option<&Email> {
+        self.digest_message().or_else(|| self.get_current_email())
+    }
+
+    /// Switch between the open digest and the messages it collects
+    fn toggle_digest(&mut self) {
+        if self.digest().is_none() {
+            self.show_error(&tr("msg-not-digest"));
+            return;
         }
-        
-        // Handle grammar suggestion mode
-        if self.show_grammar_suggestions {
-            return self.handle_grammar_suggestions(key);
+        if let Some(digest) = self.digest.as_mut() {
+            digest.selected = if digest.selected.is_some() { None } else { Some(0) };
         }
+        self.email_view_scroll = 0;
+        self.prepare_view_body();
+        self.load_sender_preferences();
+    }
 
-        // Handle attachment input mode separately
-        if self.attachment_input_mode {
-            return self.handle_attachment_input(key);
+    /// Show the next or previous message of the open digest
+    fn step_digest(&mut self, forward: bool) {
+        if self.digest().is_none() {
+            return;
         }
+        let Some(digest) = self.digest.as_mut() else {
+            return;
+        };
+        let Some(selected) = digest.selected else {
+            return;
+        };
+        digest.selected = Some(if forward { (selected + 1).min(digest.messages.len() - 1) } else { selected.saturating_sub(1) });
+        self.email_view_scroll = 0;
+        self.prepare_view_body();
+        self.load_sender_preferences();
+    }
 
-        match key.code {
-            // Spell checking shortcuts
-            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::ALT) => {
-                self.toggle_spell_check();
-                Ok(())
-            }
-            KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::ALT) => {
-                self.show_spell_suggestions_at_cursor();
-                Ok(())
+    /// What the open invitation clashes with, once the calendar has been asked
+    pub fn calendar_conflicts(&self, email: &Email) -> Option<&Result<Vec<crate::calendar::Event>, String>> {
+        let (key, conflicts) = self.calendar_view.as_ref()?;
+        (*key == crate::body_view::body_key(email, false)).then_some(conflicts)
+    }
+
+    /// Put the event of the open invitation into the account's calendar
+    fn add_invitation_to_calendar(&mut self) -> AppResult<()> {
+        let Some(email) = self.get_current_email().cloned() else {
+            return Ok(());
+        };
+        let Some(invitation) = crate::calendar::invitation(&email) else {
+            self.show_error(&tr("msg-no-invitation"));
+            return Ok(());
+        };
+        let Some(event) = invitation.event().cloned() else {
+            return Ok(());
+        };
+        if invitation.calendar.method.as_deref() == Some("CANCEL") {
+            self.show_error(&tr("msg-invitation-cancelled"));
+            return Ok(());
+        }
+        if self.config.accounts.get(self.current_account_idx).is_none_or(|a| a.caldav.is_none()) {
+            self.show_error(&tr("msg-no-calendar"));
+            return Ok(());
+        }
+
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(client) = self.accounts.get(&self.current_account_idx).and_then(|data| data.email_client.clone()) else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        match client.caldav().and_then(|mut caldav| caldav.put_event(&event.uid, &invitation.calendar_object())) {
+            Ok(()) => self.show_info(&tr_args("msg-event-added", &[("event", &event.summary)])),
+            Err(e) => self.show_error(&tr_args("msg-event-add-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Save the people on the contact cards attached to the open email to the address book
+    fn add_attached_contacts(&mut self) -> AppResult<()> {
+        let Some(email) = self.viewed_email() else {
+            return Ok(());
+        };
+        let cards = crate::vcard::attached_cards(email);
+        if cards.is_empty() {
+            self.show_error(&tr("msg-no-contact-card"));
+            return Ok(());
+        }
+
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(client) = self.accounts.get(&self.current_account_idx).and_then(|data| data.email_client.clone()) else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        match client.import_contacts(&cards) {
+            Ok(saved) => {
+                // Offered as recipients from now on
+                self.known_recipients = None;
+                let who = match cards.as_slice() {
+                    [card] => card.name.clone().unwrap_or_else(|| card.emails[0].clone()),
+                    _ => tr_args("msg-contact-people", &[("count", &cards.len())]),
+                };
+                self.show_info(&tr_args("msg-contacts-added", &[("who", &who), ("count", &saved)]));
             }
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::ALT) => {
-                self.add_word_to_dictionary();
-                Ok(())
+            Err(e) => self.show_error(&tr_args("msg-contacts-add-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Send the organizer of the open invitation our answer. Accepted events, also
+    /// tentatively, go into the account's calendar when it has one.
+    fn answer_invitation(&mut self, response: crate::calendar::Response) -> AppResult<()> {
+        use crate::calendar::Response;
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-sending"));
+            return Ok(());
+        }
+        let Some(email) = self.get_current_email().cloned() else {
+            return Ok(());
+        };
+        let Some(invitation) = crate::calendar::invitation(&email) else {
+            return Ok(());
+        };
+        let Some(event) = invitation.event().cloned() else {
+            return Ok(());
+        };
+
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(account_data) = self.accounts.get(&self.current_account_idx) else {
+            self.show_error(&tr("msg-account-not-found"));
+            return Ok(());
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        let account = account_data.account.clone();
+
+        // Answer as the address the invitation went to
+        let attendee = event
+            .attendees
+            .iter()
+            .find(|attendee| attendee.address.eq_ignore_ascii_case(&account.email))
+            .map_or(account.email.clone(), |attendee| attendee.address.clone());
+        let reply = match invitation.reply(&attendee, Some(&account.name), response, chrono::Utc::now()) {
+            Ok(reply) => reply,
+            Err(e) => {
+                self.show_error(&tr_args("msg-cannot-answer", &[("error", &e)]));
+                return Ok(());
             }
-            // Grammar checking shortcuts
-            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::ALT) => {
-                self.toggle_grammar_check();
-                Ok(())
+        };
+        let organizer = event.organizer.clone().unwrap_or_default();
+        let answer = match response {
+            Response::Accepted => "accepted",
+            Response::Tentative => "tentatively accepted",
+            Response::Declined => "declined",
+        };
+
+        let mut message = Email::new();
+        message.from.push(crate::email::EmailAddress { name: Some(account.name.clone()), address: attendee.clone() });
+        message.to.push(crate::email::EmailAddress { name: None, address: organizer.clone() });
+        message.subject = format!("{}: {}", response.label(), event.summary);
+        message.body_text = Some(format!("{} has {} this invitation.", account.name, answer));
+        let message_id = email.message_id();
+        if !message_id.is_empty() {
+            message.set_in_reply_to(message_id.clone());
+            message.set_references(vec![message_id]);
+        }
+        message.attachments.push(crate::email::EmailAttachment {
+            filename: "reply.ics".to_string(),
+            content_type: "text/calendar; method=REPLY; charset=utf-8".to_string(),
+            size: reply.len(),
+            data: reply.into_bytes(),
+            path: None,
+        });
+        match client.send_email(&message, crate::pgp::Protection::default()) {
+            Ok(_) => self.audit_email(&account.email, &email, &format!("{} invitation from {}", answer, organizer)),
+            Err(e) if crate::auth::is_auth_error(&e) => {
+                self.show_auth_problem(self.current_account_idx, &e);
+                return Ok(());
             }
-            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::ALT) => {
-                self.show_grammar_suggestions_at_cursor();
-                Ok(())
+            Err(e) => {
+                self.show_error(&tr_args("msg-answer-failed", &[("error", &e)]));
+                return Ok(());
             }
-            KeyCode::Esc => {
-                self.mode = AppMode::Normal;
-                self.focus = FocusPanel::EmailList;
-                self.compose_field = ComposeField::To;
-                self.compose_cursor_pos = 0;
-                Ok(())
+        }
+
+        if response == Response::Declined || account.caldav.is_none() {
+            self.show_info(&tr_args("msg-answered", &[("organizer", &organizer), ("answer", &response.status()), ("event", &event.summary)]));
+            return Ok(());
+        }
+        match client.caldav().and_then(|mut caldav| caldav.put_event(&event.uid, &invitation.calendar_object())) {
+            Ok(()) => self.show_info(&tr_args(
+                "msg-answered-added",
+                &[("organizer", &organizer), ("answer", &response.status()), ("event", &event.summary)],
+            )),
+            Err(e) => self.show_error(&tr_args(
+                "msg-answered-not-added",
+                &[("organizer", &organizer), ("answer", &response.status()), ("event", &event.summary), ("error", &e)],
+            )),
+        }
+        Ok(())
+    }
+
+    /// Turn signing or encryption of the message being composed on or off
+    fn toggle_compose_pgp(&mut self, encrypt: bool) {
+        let configured = self.config.accounts.get(self.current_account_idx).is_some_and(|a| a.pgp.is_some());
+        if !configured {
+            self.show_error(&tr("msg-no-pgp-key"));
+            return;
+        }
+        if encrypt {
+            self.compose_pgp.encrypt = !self.compose_pgp.encrypt;
+        } else {
+            self.compose_pgp.sign = !self.compose_pgp.sign;
+        }
+    }
+
+    /// Keep a prepared body unless the viewer has moved on to another one meanwhile
+    fn body_prepared(&mut self, key: String, body: crate::body_view::PreparedBody) {
+        if self.view_body_pending.as_ref() == Some(&key) {
+            self.view_body_pending = None;
+            self.view_body = Some((key, body));
+        }
+    }
+
+    /// Look for a new release in the background unless this build or the config turns
+    /// the check off. Releases found in the last day are reused from the database.
+    fn start_update_check(&mut self) {
+        if !crate::updates::ENABLED || !self.config.ui.update_check {
+            return;
+        }
+        let last_checked = match self.database.load_release_check() {
+            Ok(Some((releases, checked_at))) => {
+                self.set_releases(releases);
+                Some(checked_at)
             }
-            KeyCode::Tab => {
-                // Move to next field
-                self.compose_field = match self.compose_field {
-                    ComposeField::To => ComposeField::Cc,
-                    ComposeField::Cc => ComposeField::Bcc,
-                    ComposeField::Bcc => ComposeField::Subject,
-                    ComposeField::Subject => ComposeField::Body,
-                    ComposeField::Body => ComposeField::To,
-                };
-                // Reset cursor position when switching fields
-                self.compose_cursor_pos = match self.compose_field {
-                    ComposeField::To => self.compose_to_text.len(), // End of To field
-                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
-                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
-                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
-                    ComposeField::Body => 0,                        // Beginning of Body for replies
-                };
-                // Trigger spell check when switching to a new field
-                self.check_spelling();
-                Ok(())
+            Ok(None) => None,
+            Err(e) => {
+                debug_log(&format!("Failed to read the last update check: {}", e));
+                None
             }
-            KeyCode::BackTab => {
-                // Move to previous field
-                self.compose_field = match self.compose_field {
-                    ComposeField::To => ComposeField::Body,
-                    ComposeField::Cc => ComposeField::To,
-                    ComposeField::Bcc => ComposeField::Cc,
-                    ComposeField::Subject => ComposeField::Bcc,
-                    ComposeField::Body => ComposeField::Subject,
-                };
-                // Reset cursor position when switching fields
-                self.compose_cursor_pos = match self.compose_field {
-                    ComposeField::To => self.compose_to_text.len(), // End of To field
-                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
-                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
-                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
-                    ComposeField::Body => 0,                        // Beginning of Body for replies
-                };
-                // Trigger spell check when switching to a new field
-                self.check_spelling();
-                Ok(())
+        };
+        if !crate::updates::check_due(last_checked, chrono::Utc::now().timestamp()) {
+            return;
+        }
+
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let releases = crate::updates::fetch_releases().map_err(|e| e.to_string());
+            let _ = sender.send(AppEvent::ReleasesFetched { releases });
+        });
+    }
+
+    /// Keep the releases of a finished update check; a failed check is retried on the next start
+    fn releases_fetched(&mut self, releases: Result<Vec<crate::updates::Release>, String>) {
+        match releases {
+            Ok(releases) => {
+                if let Err(e) = self.database.save_release_check(&releases, chrono::Utc::now().timestamp()) {
+                    debug_log(&format!("Failed to save the update check: {}", e));
+                }
+                self.set_releases(releases);
             }
-            KeyCode::Up => {
-                // Move to previous field
-                self.compose_field = match self.compose_field {
-                    ComposeField::To => ComposeField::Body,
-                    ComposeField::Cc => ComposeField::To,
-                    ComposeField::Bcc => ComposeField::Cc,
-                    ComposeField::Subject => ComposeField::Bcc,
-                    ComposeField::Body => ComposeField::Subject,
-                };
-                // Reset cursor position when switching fields
-                self.compose_cursor_pos = match self.compose_field {
-                    ComposeField::To => self.compose_to_text.len(), // End of To field
-                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
-                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
-                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
-                    ComposeField::Body => 0,                        // Beginning of Body for replies
-                };
-                Ok(())
+            Err(e) => debug_log(&format!("Update check failed: {}", e)),
+        }
+    }
+
+    fn set_releases(&mut self, releases: Vec<crate::updates::Release>) {
+        self.update_available = crate::updates::available_update(&releases, crate::updates::CURRENT_VERSION)
+            .map(|release| release.version.clone());
+        self.releases = releases;
+    }
+
+    fn open_changelog(&mut self) {
+        if !crate::updates::ENABLED {
+            self.show_info(&tr("msg-updates-by-package-manager"));
+            return;
+        }
+        self.changelog_scroll = 0;
+        self.mode = AppMode::Changelog;
+    }
+
+    fn handle_changelog_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            KeyCode::Up => self.changelog_scroll = self.changelog_scroll.saturating_sub(1),
+            KeyCode::Down => self.changelog_scroll = self.changelog_scroll.saturating_add(1),
+            KeyCode::PageUp => self.changelog_scroll = self.changelog_scroll.saturating_sub(10),
+            KeyCode::PageDown => self.changelog_scroll = self.changelog_scroll.saturating_add(10),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Store a finished avatar lookup in the cache and in memory. A failed one is not
+    /// remembered, so the sender is looked up again the next time one of their messages
+    /// is opened.
+    fn store_avatar(&mut self, key: String, image: Result<Option<Vec<u8>>, String>) {
+        self.avatar_pending.remove(&key);
+        let image = match image {
+            Ok(image) => image,
+            Err(e) => {
+                debug_log(&format!("Avatar lookup for {} failed: {}", key, e));
+                return;
             }
-            KeyCode::Down => {
-                // Move to next field
-                self.compose_field = match self.compose_field {
-                    ComposeField::To => ComposeField::Cc,
-                    ComposeField::Cc => ComposeField::Bcc,
-                    ComposeField::Bcc => ComposeField::Subject,
-                    ComposeField::Subject => ComposeField::Body,
-                    ComposeField::Body => ComposeField::To,
-                };
-                // Reset cursor position when switching fields
-                self.compose_cursor_pos = match self.compose_field {
-                    ComposeField::To => self.compose_to_text.len(), // End of To field
-                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
-                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
-                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
-                    ComposeField::Body => 0,                        // Beginning of Body for replies
+        };
+        if let Err(e) = self.database.save_avatar(&key, image.as_deref()) {
+            debug_log(&format!("Failed to cache avatar for {}: {}", key, e));
+        }
+        let thumbnail = image.as_deref().and_then(crate::avatar::decode_thumbnail);
+        self.avatars.insert(key, thumbnail);
+        // A sender without an avatar may still have a brand logo
+        self.request_sender_avatar();
+    }
+
+    /// Pause or resume background syncing
+    pub fn toggle_sync_pause(&mut self) {
+        let paused = !self.sync_paused.load(Ordering::Relaxed);
+        self.sync_paused.store(paused, Ordering::Relaxed);
+        if paused {
+            self.show_info(&tr("msg-sync-paused"));
+        } else {
+            self.show_info(&tr("msg-sync-resumed"));
+        }
+    }
+
+    pub fn is_sync_paused(&self) -> bool {
+        self.sync_paused.load(Ordering::Relaxed)
+    }
+
+    /// Queue an email operation for background processing
+    pub fn queue_email_operation(&mut self, operation_type: &str, email_uid: u32, target_folder: Option<&str>) -> AppResult<()> {
+        // The queue is frozen in read-only mode so nothing can reach the server later
+        if crate::email::is_read_only() {
+            return Err(AppError::EmailError(crate::email::EmailError::ReadOnly(operation_type.replace('_', " "))));
+        }
+
+        if let Some((account_idx, folder_path)) = self.get_selected_folder_info() {
+            if let Some(account_data) = self.accounts.get(&account_idx) {
+                let account_email = &account_data.account.email;
+                
+                // Queue the operation in database
+                let subject = self.emails.iter().find(|e| e.id == email_uid.to_string()).map(|e| e.subject.as_str());
+                self.database.queue_email_operation(
+                    account_email,
+                    operation_type,
+                    email_uid,
+                    &folder_path,
+                    target_folder,
+                    subject,
+                )?;
+                
+                debug_log(&format!(
+                    "Queued {} operation for email {} in {}/{}",
+                    operation_type, email_uid, account_email, folder_path
+                ));
+
+                // The cache keeps the new read state until the server has it too
+                if let "mark_read" | "mark_unread" = operation_type {
+                    let seen = operation_type == "mark_read";
+                    if let Err(e) = self.database.update_email_seen_status(account_email, &folder_path, email_uid, seen) {
+                        debug_log(&format!("Failed to store the read state of {}: {}", email_uid, e));
+                    }
+                }
+
+                let action = match operation_type {
+                    "mark_read" => "flag +\\Seen".to_string(),
+                    "mark_unread" => "flag -\\Seen".to_string(),
+                    "move" => format!("move to {}", target_folder.unwrap_or_default()),
+                    other => other.to_string(),
                 };
-                Ok(())
-            }
-            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.send_email()?;
-                Ok(())
-            }
-            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Add attachment
-                self.add_attachment()?;
-                Ok(())
-            }
-            KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                // Remove selected attachment
-                self.remove_selected_attachment()?;
-                Ok(())
-            }
-            KeyCode::Char(c) => {
-                // Add character to current field at cursor position
-                match self.compose_field {
-                    ComposeField::To => {
-                        // Insert character at cursor position in To field
-                        if self.compose_cursor_pos <= self.compose_to_text.len() {
-                            self.compose_to_text.insert(self.compose_cursor_pos, c);
-                            self.compose_cursor_pos += 1;
-                        } else {
-                            self.compose_to_text.push(c);
-                            self.compose_cursor_pos = self.compose_to_text.len();
+                match self.emails.iter().find(|e| e.id == email_uid.to_string()) {
+                    Some(email) => self.audit_email(account_email, email, &action),
+                    None => crate::audit::record(
+                        &self.database, account_email, &folder_path, Some(email_uid), &action,
+                        crate::audit::Origin::Ui, "",
+                    ),
+                }
+                
+                // Update local state immediately for responsive UI
+                match operation_type {
+                    "mark_read" => {
+                        if let Some(email) = self.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
+                            email.seen = true;
                         }
-
-                        // Parse the to field and update compose_email.to
-                        self.compose_email.to.clear();
-                        for addr in self.compose_to_text.split(',') {
-                            let addr = addr.trim();
-                            if !addr.is_empty() {
-                                self.compose_email.to.push(crate::email::EmailAddress {
-                                    name: None,
-                                    address: addr.to_string(),
-                                });
+                        // Also update in account data
+                        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+                            if let Some(email) = account_data.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
+                                email.seen = true;
                             }
                         }
                     }
-                    ComposeField::Cc => {
-                        // Insert character at cursor position in CC field
-                        if self.compose_cursor_pos <= self.compose_cc_text.len() {
-                            self.compose_cc_text.insert(self.compose_cursor_pos, c);
-                            self.compose_cursor_pos += 1;
-                        } else {
-                            self.compose_cc_text.push(c);
-                            self.compose_cursor_pos = self.compose_cc_text.len();
+                    "mark_unread" => {
+                        if let Some(email) = self.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
+                            email.seen = false;
                         }
-
-                        // Parse the cc field and update compose_email.cc
-                        self.compose_email.cc.clear();
-                        for addr in self.compose_cc_text.split(',') {
-                            let addr = addr.trim();
-                            if !addr.is_empty() {
-                                self.compose_email.cc.push(crate::email::EmailAddress {
-                                    name: None,
-                                    address: addr.to_string(),
-                                });
+                        // Also update in account data
+                        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+                            if let Some(email) = account_data.emails.iter_mut().find(|e| e.id == email_uid.to_string()) {
+                                email.seen = false;
                             }
                         }
                     }
-                    ComposeField::Bcc => {
-                        // Insert character at cursor position in BCC field
-                        if self.compose_cursor_pos <= self.compose_bcc_text.len() {
-                            self.compose_bcc_text.insert(self.compose_cursor_pos, c);
-                            self.compose_cursor_pos += 1;
-                        } else {
-                            self.compose_bcc_text.push(c);
-                            self.compose_cursor_pos = self.compose_bcc_text.len();
+                    "delete" | "move" => {
+                        // Remove from local state immediately
+                        self.emails.retain(|e| e.id != email_uid.to_string());
+                        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+                            account_data.emails.retain(|e| e.id != email_uid.to_string());
                         }
-
-                        // Parse the bcc field and update compose_email.bcc
-                        self.compose_email.bcc.clear();
-                        for addr in self.compose_bcc_text.split(',') {
-                            let addr = addr.trim();
-                            if !addr.is_empty() {
-                                self.compose_email.bcc.push(crate::email::EmailAddress {
-                                    name: None,
-                                    address: addr.to_string(),
-                                });
+                        // Adjust selection if needed
+                        if let Some(idx) = self.selected_email_idx {
+                            if idx >= self.emails.len() && !self.emails.is_empty() {
+                                self.selected_email_idx = Some(self.emails.len() - 1);
                             }
                         }
                     }
-                    ComposeField::Subject => {
-                        self.compose_email.subject.push(c);
-                        // Trigger spell and grammar check for subject
-                        self.check_spelling();
-                        self.request_grammar_check();
-                    }
-                    ComposeField::Body => {
-                        if let Some(ref mut body) = self.compose_email.body_text {
-                            // Ensure cursor position is valid and insert character
-                            let cursor_pos = self.compose_cursor_pos.min(body.len());
-                            body.insert(cursor_pos, c);
-                            self.compose_cursor_pos = cursor_pos + 1;
-                        } else {
-                            self.compose_email.body_text = Some(c.to_string());
-                            self.compose_cursor_pos = 1;
-                        }
-                        // Trigger spell and grammar check for body on any character (more responsive)
-                        self.check_spelling();
-                        self.request_grammar_check();
-                    }
+                    _ => {}
                 }
+                
                 Ok(())
+            } else {
+                Err(AppError::EmailError(crate::email::EmailError::ImapError(
+                    "Account not found".to_string(),
+                )))
             }
-            KeyCode::Backspace => {
-                // Remove character from current field at cursor position
-                match self.compose_field {
-                    ComposeField::To => {
-                        if self.compose_cursor_pos > 0
-                            && self.compose_cursor_pos <= self.compose_to_text.len()
-                        {
-                            self.compose_to_text.remove(self.compose_cursor_pos - 1);
-                            self.compose_cursor_pos -= 1;
+        } else {
+            Err(AppError::EmailError(crate::email::EmailError::ImapError(
+                "No folder selected".to_string(),
+            )))
+        }
+    }
 
-                            // Parse the to field and update compose_email.to
-                            self.compose_email.to.clear();
-                            for addr in self.compose_to_text.split(',') {
-                                let addr = addr.trim();
-                                if !addr.is_empty() {
-                                    self.compose_email.to.push(crate::email::EmailAddress {
-                                        name: None,
-                                        address: addr.to_string(),
-                                    });
-                                }
-                            }
-                        }
+    /// Mark current email as read (queue operation)
+    pub fn mark_current_email_as_read(&mut self) -> AppResult<()> {
+        if let Some(idx) = self.selected_email_idx {
+            if idx < self.emails.len() {
+                let email = &self.emails[idx];
+                // Opening mail in read-only mode simply leaves it unread
+                if !email.seen && !crate::email::is_read_only() {
+                    let email_uid: u32 = email.id.parse().unwrap_or(0);
+                    self.queue_email_operation("mark_read", email_uid, None)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark current email as unread (queue operation)
+    pub fn mark_current_email_as_unread(&mut self) -> AppResult<()> {
+        if let Some(idx) = self.selected_email_idx {
+            if idx < self.emails.len() {
+                let email = &self.emails[idx];
+                if email.seen {
+                    let email_uid: u32 = email.id.parse().unwrap_or(0);
+                    self.queue_email_operation("mark_unread", email_uid, None)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete current email (queue operation)
+    pub fn delete_current_email(&mut self) -> AppResult<()> {
+        if let Some(idx) = self.selected_email_idx {
+            if idx < self.emails.len() {
+                let email = &self.emails[idx];
+                let email_uid: u32 = email.id.parse().unwrap_or(0);
+                self.queue_email_operation("delete", email_uid, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reset sync state to force full re-sync of current folder
+    pub fn reset_sync_state(&mut self) -> AppResult<()> {
+        if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
+            // Clear database entries for this folder
+            self.database.clear_folder_emails(&account_data.account.email, &self.selected_folder)?;
+            
+            self.info_message = Some(tr("msg-sync-state-reset"));
+            self.message_timeout = Some(Instant::now() + Duration::from_secs(3));
+        }
+        Ok(())
+    }
+
+    /// Start background sync thread
+    pub fn start_background_sync(&mut self) -> AppResult<()> {
+        // Don't start if already running
+        if self.sync_thread_running.load(Ordering::Relaxed) {
+            debug_log("Background sync thread already running");
+            return Ok(());
+        }
+
+        debug_log("Starting background sync thread...");
+
+        // Get data needed for sync thread
+        let database_path = self.database.get_database_path();
+        let config = self.config.clone();
+
+        // Set running flag
+        self.sync_thread_running.store(true, Ordering::Relaxed);
+        let running_flag = Arc::clone(&self.sync_thread_running);
+        let paused_flag = Arc::clone(&self.sync_paused);
+        let manual_sync_folders = Arc::clone(&self.manual_sync_folders);
+
+        let (request_sender, request_receiver) = std::sync::mpsc::channel::<SyncRequest>();
+        self.sync_request_sender = Some(request_sender);
+        let events = self.events.sender();
+
+        // Start background thread
+        let handle = thread::spawn(move || {
+            debug_log("Background sync thread started");
+            
+            // Create sync components inside the thread
+            let database = match crate::database::EmailDatabase::new(std::path::Path::new(&database_path)) {
+                Ok(db) => Arc::new(db),
+                Err(e) => {
+                    debug_log(&format!("Failed to create database in sync thread: {}", e));
+                    return;
+                }
+            };
+
+            let mut email_clients = HashMap::new();
+            
+            // Initialize email clients for each account
+            for account in &config.accounts {
+                if crate::email::is_account_offline(&account.email) {
+                    debug_log(&format!("Not syncing {}: offline", account.email));
+                    continue;
+                }
+                // Create credentials manager
+                let credentials = match crate::credentials::SecureCredentials::new() {
+                    Ok(creds) => creds,
+                    Err(e) => {
+                        debug_log(&format!("Failed to create credentials for {}: {}", account.email, e));
+                        continue;
                     }
-                    ComposeField::Cc => {
-                        if self.compose_cursor_pos > 0
-                            && self.compose_cursor_pos <= self.compose_cc_text.len()
-                        {
-                            self.compose_cc_text.remove(self.compose_cursor_pos - 1);
-                            self.compose_cursor_pos -= 1;
+                };
+                let client = EmailClient::new(account.clone(), credentials);
+                email_clients.insert(account.email.clone(), client);
+            }
+            
+            // Messages cached before the header fields were last changed get their values
+            for db_path in std::iter::once(std::path::PathBuf::from(&database_path))
+                .chain(config.accounts.iter().map(|account| crate::profile::account_cache_dir(&account.email).join("emails.db")))
+            {
+                match crate::database::EmailDatabase::new(&db_path).and_then(|db| db.reindex_header_fields()) {
+                    Ok(0) => {}
+                    Ok(count) => debug_log(&format!("Filled in header fields of {} messages in {:?}", count, db_path)),
+                    Err(e) => debug_log(&format!("Failed to fill in header fields in {:?}: {}", db_path, e)),
+                }
+                // Likewise the previews of messages cached before snippets were made this way
+                match crate::database::EmailDatabase::new(&db_path).and_then(|db| db.regenerate_snippets()) {
+                    Ok(0) => {}
+                    Ok(count) => debug_log(&format!("Made the previews of {} messages again in {:?}", count, db_path)),
+                    Err(e) => debug_log(&format!("Failed to make previews in {:?}: {}", db_path, e)),
+                }
+            }
 
-                            // Parse the cc field and update compose_email.cc
-                            self.compose_email.cc.clear();
-                            for addr in self.compose_cc_text.split(',') {
-                                let addr = addr.trim();
-                                if !addr.is_empty() {
-                                    self.compose_email.cc.push(crate::email::EmailAddress {
-                                        name: None,
-                                        address: addr.to_string(),
-                                    });
-                                }
+            // Keep the attachments held locally under the configured size
+            if let Some(max_bytes) = config.cache.max_bytes() {
+                match crate::cache::collect(&crate::cache::open_databases(&config), max_bytes) {
+                    Ok(report) if report.evicted > 0 || report.stray_files > 0 => debug_log(&format!(
+                        "Attachment cache: dropped {} attachments and {} leftover files, {} -> {} bytes",
+                        report.evicted, report.stray_files, report.before, report.after
+                    )),
+                    Ok(_) => {}
+                    Err(e) => debug_log(&format!("Failed to shrink the attachment cache: {}", e)),
+                }
+            }
+
+            // Next time each account is due for a scheduled sync
+            let mut next_due: HashMap<String, Instant> = config
+                .accounts
+                .iter()
+                .map(|account| (account.email.clone(), Instant::now()))
+                .collect();
+
+            // New messages in muted conversations are marked read or archived right away
+            let archive_folder = config.retention.archive_folder.clone();
+            let silence_muted = |client: &EmailClient, database: &crate::database::EmailDatabase, account: &EmailAccount, folder: &str, emails: &[Email]| {
+                if crate::email::is_read_only() {
+                    return;
+                }
+                match client.silence_muted(emails, &account.archive_folder(&archive_folder)) {
+                    Ok(done) => {
+                        for (uid, silence) in &done {
+                            let result = match silence {
+                                crate::mute::Silence::MarkRead => database.update_email_seen_status(&account.email, folder, *uid, true),
+                                crate::mute::Silence::Archive => database.delete_email(&account.email, folder, *uid),
+                            };
+                            if let Err(e) = result {
+                                debug_log(&format!("Failed to update muted message {}: {}", uid, e));
                             }
                         }
+                        if !done.is_empty() {
+                            debug_log(&format!("Silenced {} messages of muted conversations in {}/{}", done.len(), account.email, folder));
+                        }
                     }
-                    ComposeField::Bcc => {
-                        if self.compose_cursor_pos > 0
-                            && self.compose_cursor_pos <= self.compose_bcc_text.len()
-                        {
-                            self.compose_bcc_text.remove(self.compose_cursor_pos - 1);
-                            self.compose_cursor_pos -= 1;
+                    Err(e) => debug_log(&format!("Failed to silence muted conversations in {}/{}: {}", account.email, folder, e)),
+                }
+            };
 
-                            // Parse the bcc field and update compose_email.bcc
-                            self.compose_email.bcc.clear();
-                            for addr in self.compose_bcc_text.split(',') {
-                                let addr = addr.trim();
-                                if !addr.is_empty() {
-                                    self.compose_email.bcc.push(crate::email::EmailAddress {
-                                        name: None,
-                                        address: addr.to_string(),
-                                    });
+            let sync_folder = |account: &EmailAccount, folder: &str| {
+                if let Some(client) = email_clients.get(&account.email) {
+                    match client.fetch_emails(folder, 0) {
+                        Ok(emails) => {
+                            // Store emails in database
+                            if let Err(e) = database.save_emails(&account.email, folder, &emails) {
+                                debug_log(&format!("Failed to save emails: {}", e));
+                            } else {
+                                debug_log(&format!("Synced {} emails for {}/{}", emails.len(), account.email, folder));
+                                crate::dsn::record_bounces(&database, &account.email, &emails);
+                                silence_muted(client, &database, account, folder, &emails);
+                                if let Some(notmuch) = config.notmuch.as_ref().filter(|_| crate::notmuch::ENABLED) {
+                                    if let Err(e) = client.sync_notmuch_tags(notmuch, &emails) {
+                                        debug_log(&format!("Failed to sync notmuch tags of {}/{}: {}", account.email, folder, e));
+                                    }
                                 }
                             }
                         }
-                    }
-                    ComposeField::Subject => {
-                        self.compose_email.subject.pop();
-                        // Trigger spell and grammar check for subject
-                        self.check_spelling();
-                        self.request_grammar_check();
-                    }
-                    ComposeField::Body => {
-                        if let Some(ref mut body) = self.compose_email.body_text {
-                            if self.compose_cursor_pos > 0 && self.compose_cursor_pos <= body.len()
-                            {
-                                body.remove(self.compose_cursor_pos - 1);
-                                self.compose_cursor_pos -= 1;
-                                
-                                // Trigger spell and grammar check after deletion
-                                self.check_spelling();
-                                self.request_grammar_check();
-                            }
+                        Err(e) => {
+                            debug_log(&format!("Failed to fetch {} for {}: {}", folder, account.email, e));
                         }
                     }
                 }
-                Ok(())
-            }
-            KeyCode::Enter => {
-                // In body field, add newline at cursor position
-                if self.compose_field == ComposeField::Body {
-                    if let Some(ref mut body) = self.compose_email.body_text {
-                        // Ensure cursor position is valid
-                        let cursor_pos = self.compose_cursor_pos.min(body.len());
-                        body.insert(cursor_pos, '\n');
-                        self.compose_cursor_pos = cursor_pos + 1;
-                        
-                        // Trigger spell and grammar check after newline
-                        self.check_spelling();
-                        self.request_grammar_check();
-                    } else {
-                        // If body is None, create it with a newline
-                        self.compose_email.body_text = Some("\n".to_string());
-                        self.compose_cursor_pos = 1;
-                        // Trigger spell and grammar check for new body
-                        self.check_spelling();
-                        self.request_grammar_check();
+            };
+
+            // Download bodies of messages listed from headers only and tell the UI
+            let fetch_bodies = |account_email: &str, folder: &str, uids: &[u32]| {
+                let Some(client) = email_clients.get(account_email) else {
+                    return;
+                };
+                // Checked before every batch, so a large sync stops before the disk fills up
+                if let Some(free) = crate::cache::low_disk_space(&config) {
+                    debug_log(&format!("Not fetching bodies in {} for {}: {} bytes free", folder, account_email, free));
+                    return;
+                }
+                match client.fetch_bodies(folder, uids) {
+                    Ok(stored) if !stored.is_empty() => {
+                        let _ = events.send(AppEvent::BodiesSynced {
+                            account_email: account_email.to_string(),
+                            folder: folder.to_string(),
+                            uids: stored,
+                        });
                     }
+                    Ok(_) => {}
+                    Err(e) => debug_log(&format!("Failed to fetch bodies in {} for {}: {}", folder, account_email, e)),
                 }
-                Ok(())
-            }
-            KeyCode::Left => {
-                // Move cursor left in current field
-                match self.compose_field {
-                    ComposeField::To => {
-                        if self.compose_cursor_pos > 0 {
-                            self.compose_cursor_pos -= 1;
+            };
+
+            // A folder seen for the first time is listed from headers, which is much faster
+            // for large folders; then the newest bodies are downloaded
+            let sync_headers = |account: &EmailAccount, folder: &str| {
+                let Some(client) = email_clients.get(&account.email) else {
+                    return;
+                };
+                match client.sync_folder_headers(folder) {
+                    Ok(count) => {
+                        debug_log(&format!("Listed {} messages of {}/{} from headers", count, account.email, folder));
+                        let _ = events.send(AppEvent::HeadersSynced {
+                            account_email: account.email.clone(),
+                            folder: folder.to_string(),
+                        });
+                        if let Ok(newest) = client.pending_bodies(folder, Some(config.sync.body_window)) {
+                            fetch_bodies(&account.email, folder, &newest);
                         }
                     }
-                    ComposeField::Cc => {
-                        if self.compose_cursor_pos > 0 {
-                            self.compose_cursor_pos -= 1;
+                    Err(e) => debug_log(&format!("Failed to list {} for {}: {}", folder, account.email, e)),
+                }
+            };
+
+            // Folders whose remaining bodies are downloaded in the background
+            let mut filling: Vec<(String, String)> = Vec::new();
+            // Free space while it is below the `min_free_mb` setting; only headers are
+            // listed then, and their bodies fetched once there is room again
+            let mut low_space: Option<u64> = None;
+
+            // Run sync loop (no need for async since methods are sync)
+            while running_flag.load(Ordering::Relaxed) {
+                if paused_flag.load(Ordering::Relaxed) {
+                    // Leave priority requests queued until syncing resumes
+                    std::thread::sleep(Duration::from_secs(1));
+                    continue;
+                }
+
+                let now_low = crate::cache::low_disk_space(&config);
+                if now_low.is_some() != low_space.is_some() {
+                    debug_log(&format!("Free disk space {:?}: headers only = {}", now_low, now_low.is_some()));
+                    let _ = events.send(AppEvent::DiskSpaceChanged { low: now_low });
+                }
+                low_space = now_low;
+
+                // Folder-open requests jump ahead of the schedule
+                let mut requested = false;
+                while let Ok(request) = request_receiver.try_recv() {
+                    requested = true;
+                    let folder = request.folder;
+                    if let Some(account) = config.accounts.iter().find(|a| a.email == request.account_email) {
+                        debug_log(&format!("Priority sync for {}/{}", account.email, folder));
+                        fetch_bodies(&account.email, &folder, &request.window);
+                        // An interrupted listing is picked up where it stopped
+                        let listed = email_clients
+                            .get(&account.email)
+                            .is_some_and(|c| c.has_cached_emails(&folder) && !c.initial_sync_unfinished(&folder));
+                        if listed && low_space.is_none() {
+                            sync_folder(account, &folder);
+                        } else {
+                            sync_headers(account, &folder);
                         }
-                    }
-                    ComposeField::Bcc => {
-                        if self.compose_cursor_pos > 0 {
-                            self.compose_cursor_pos -= 1;
+                        let _ = events.send(AppEvent::FolderSynced {
+                            account_email: account.email.clone(),
+                            folder: folder.clone(),
+                        });
+                        if folder == "INBOX" {
+                            next_due.insert(account.email.clone(), Instant::now() + config.sync_interval_for(account));
                         }
-                    }
-                    ComposeField::Body => {
-                        if self.compose_cursor_pos > 0 {
-                            self.compose_cursor_pos -= 1;
+                        let key = (account.email.clone(), folder);
+                        if !filling.contains(&key) {
+                            filling.push(key);
                         }
                     }
-                    _ => {}
                 }
-                Ok(())
-            }
-            KeyCode::Right => {
-                // Move cursor right in current field
-                match self.compose_field {
-                    ComposeField::To => {
-                        if self.compose_cursor_pos < self.compose_to_text.len() {
-                            self.compose_cursor_pos += 1;
+
+                // Folders marked to sync only when opened get no scheduled sync and no
+                // background body downloads
+                let manual = manual_sync_folders.lock().map(|folders| folders.clone()).unwrap_or_default();
+                filling.retain(|key| !manual.contains(key));
+
+                // Changes made while browsing go out before anything else; after a failure
+                // the rest of the account's queue waits so they are carried out in order
+                if !crate::email::is_read_only() {
+                    let now = chrono::Local::now().timestamp();
+                    let mut folders = Vec::new();
+                    let mut failed = Vec::new();
+                    for (account_email, client) in &email_clients {
+                        for op in database.get_due_operations(account_email, now).unwrap_or_default() {
+                            let key = (account_email.clone(), op.folder.clone());
+                            if !folders.contains(&key) {
+                                folders.push(key);
+                            }
+                            match client.apply_operation(&op) {
+                                Ok(()) => {
+                                    if let Err(e) = database.mark_operation_processed(op.id) {
+                                        debug_log(&format!("Failed to mark operation {} done: {}", op.id, e));
+                                    }
+                                }
+                                Err(e) => {
+                                    debug_log(&format!("Queued {} of UID {} in {}/{} failed: {}", op.operation_type, op.email_uid, account_email, op.folder, e));
+                                    if op.attempts == 0 {
+                                        failed.push(format!("Queued {} failed: {}", op.describe(), e));
+                                    }
+                                    let retry_at = now + operation_retry_delay(op.attempts);
+                                    if let Err(e) = database.mark_operation_failed(op.id, &e.to_string(), retry_at) {
+                                        debug_log(&format!("Failed to record the error of operation {}: {}", op.id, e));
+                                    }
+                                    break;
+                                }
+                            }
                         }
                     }
-                    ComposeField::Cc => {
-                        if self.compose_cursor_pos < self.compose_cc_text.len() {
-                            self.compose_cursor_pos += 1;
-                        }
+                    if !folders.is_empty() {
+                        let _ = events.send(AppEvent::OperationsProcessed { folders, failed });
                     }
-                    ComposeField::Bcc => {
-                        if self.compose_cursor_pos < self.compose_bcc_text.len() {
-                            self.compose_cursor_pos += 1;
-                        }
+                }
+
+                // Scheduled INBOX sync for every account whose interval has elapsed
+                for account in &config.accounts {
+                    if !running_flag.load(Ordering::Relaxed) || paused_flag.load(Ordering::Relaxed) {
+                        break;
                     }
-                    ComposeField::Body => {
-                        if let Some(body) = &self.compose_email.body_text {
-                            if self.compose_cursor_pos < body.len() {
-                                self.compose_cursor_pos += 1;
-                            }
+                    let due = next_due.get(&account.email).copied().unwrap_or_else(Instant::now);
+                    if Instant::now() < due || manual.contains(&(account.email.clone(), "INBOX".to_string())) {
+                        continue;
+                    }
+                    if email_clients.get(&account.email).is_some_and(|c| c.initial_sync_unfinished("INBOX")) {
+                        sync_headers(account, "INBOX");
+                    } else if low_space.is_some() {
+                        sync_headers(account, "INBOX");
+                        let key = (account.email.clone(), "INBOX".to_string());
+                        if !filling.contains(&key) {
+                            filling.push(key);
                         }
+                    } else {
+                        sync_folder(account, "INBOX");
                     }
-                    _ => {}
+                    next_due.insert(account.email.clone(), Instant::now() + config.sync_interval_for(account));
                 }
-                Ok(())
-            }
-            KeyCode::Home => {
-                // Move cursor to beginning of current line in body field
-                if self.compose_field == ComposeField::Body {
-                    if let Some(body) = &self.compose_email.body_text {
-                        // Find the beginning of the current line
-                        let text_before_cursor = &body[..self.compose_cursor_pos];
-                        if let Some(last_newline) = text_before_cursor.rfind('\n') {
-                            self.compose_cursor_pos = last_newline + 1;
+
+                // One batch of remaining bodies per round, while nothing more urgent is waiting
+                if !requested {
+                    if let Some((account_email, folder)) = filling.first().cloned() {
+                        let batch = email_clients
+                            .get(&account_email)
+                            .and_then(|client| client.pending_bodies(&folder, Some(BODY_BATCH)).ok())
+                            .unwrap_or_default();
+                        if batch.is_empty() {
+                            filling.remove(0);
                         } else {
-                            self.compose_cursor_pos = 0;
+                            fetch_bodies(&account_email, &folder, &batch);
                         }
                     }
                 }
-                Ok(())
+
+                std::thread::sleep(Duration::from_secs(1));
             }
-            KeyCode::End => {
-                // Move cursor to end of current line in body field
-                if self.compose_field == ComposeField::Body {
-                    if let Some(body) = &self.compose_email.body_text {
-                        // Find the end of the current line
-                        let text_after_cursor = &body[self.compose_cursor_pos..];
-                        if let Some(next_newline) = text_after_cursor.find('\n') {
-                            self.compose_cursor_pos += next_newline;
-                        } else {
-                            self.compose_cursor_pos = body.len();
+
+            debug_log("Background sync thread stopped");
+        });
+
+        self.sync_thread_handle = Some(handle);
+        debug_log("Background sync thread started successfully");
+        Ok(())
+    }
+
+    /// Stop background sync thread
+    pub fn stop_background_sync(&mut self) {
+        if self.sync_thread_running.load(Ordering::Relaxed) {
+            debug_log("Stopping background sync thread...");
+            self.sync_thread_running.store(false, Ordering::Relaxed);
+            
+            if let Some(handle) = self.sync_thread_handle.take() {
+                // Give the thread a moment to finish gracefully
+                std::thread::sleep(Duration::from_millis(100));
+                
+                // If it's still running, we'll just detach it
+                if !handle.is_finished() {
+                    debug_log("Background sync thread still running, detaching...");
+                }
+            }
+            
+            debug_log("Background sync thread stopped");
+        }
+    }
+
+    /// Cleanup when app is shutting down
+    pub fn cleanup(&mut self) {
+        debug_log("App cleanup started");
+        self.stop_background_sync();
+        debug_log("App cleanup completed");
+    }
+
+    /// Refresh emails from database (called periodically) - optimized with sync tracker
+    pub fn refresh_emails_from_database(&mut self) -> AppResult<()> {
+        if let Some((account_idx, folder_path)) = self.get_selected_folder_info() {
+            let account_email = if let Some(account_data) = self.accounts.get(&account_idx) {
+                account_data.account.email.clone()
+            } else {
+                return Ok(());
+            };
+
+            // Get UI's last known timestamp for this account/folder
+            let ui_key = format!("{}:{}", account_email, folder_path);
+            let ui_timestamp = self.ui_timestamps.get(&ui_key)
+                .copied()
+                .unwrap_or_else(|| chrono::DateTime::from_timestamp(0, 0).unwrap_or_else(chrono::Utc::now));
+
+            // Check if there are potentially new emails using the sync tracker
+            if !has_new_emails_since_global(&account_email, &folder_path, ui_timestamp) {
+                // No new emails detected, skip expensive database queries
+                return Ok(());
+            }
+
+            debug_log(&format!(
+                "Sync tracker indicates new emails for {}/{}, checking database",
+                account_email, folder_path
+            ));
+
+            // Get emails that arrived after our UI timestamp
+            match self.database.get_emails_since_timestamp(&account_email, &folder_path, ui_timestamp) {
+                Ok(new_emails) if !new_emails.is_empty() => {
+                    debug_log(&format!(
+                        "Found {} new emails for {}/{} since {}",
+                        new_emails.len(), account_email, folder_path, ui_timestamp
+                    ));
+                    
+                    // Merge new emails with existing ones
+                    if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+                        // Add new emails to the beginning (most recent first)
+                        let mut updated_emails = new_emails;
+                        updated_emails.extend(account_data.emails.clone());
+                        
+                        // Remove duplicates based on ID
+                        updated_emails.sort_by(|a, b| b.date.cmp(&a.date)); // Sort by date descending
+                        updated_emails.dedup_by(|a, b| a.id == b.id);
+                        
+                        if account_idx == self.current_account_idx {
+                            self.folder_email_count += updated_emails.len().saturating_sub(account_data.emails.len());
+                        }
+                        account_data.emails = updated_emails;
+
+                        // Update UI timestamp to the latest email timestamp
+                        if let Some(latest_email) = account_data.emails.first() {
+                            let email_time = latest_email.date.with_timezone(&chrono::Utc);
+                            self.ui_timestamps.insert(ui_key, email_time);
+                        }
+
+                        // Update UI emails if this is the current account (unless a contact thread is shown)
+                        if account_idx == self.current_account_idx && self.contact_filter.is_none() && !self.read_later_view && !self.vip_view && !self.notifications_view && self.search_query.is_none() {
+                            let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+                            self.replace_email_list(shown);
                         }
                     }
                 }
-                Ok(())
+                Ok(_) => {
+                    // No new emails, but update UI timestamp to current sync timestamp
+                    if let Some(sync_timestamp) = get_global_sync_timestamp(&account_email, &folder_path) {
+                        self.ui_timestamps.insert(ui_key, sync_timestamp);
+                    }
+                }
+                Err(e) => {
+                    debug_log(&format!("Error checking for new emails: {}", e));
+                }
+            }
+        }
+        
+        Ok(())
+    }
+
+    pub fn init(&mut self) -> AppResult<()> {
+        // Debug logging
+        if std::env::var("EMAIL_DEBUG").is_ok() {
+            let log_file = "/tmp/tuimail_debug.log";
+            if let Ok(mut file) = std::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(true)
+                .open(log_file)
+            {
+                use std::io::Write;
+                let _ = writeln!(
+                    file,
+                    "[{}] App::init() called",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+                );
+            }
+        }
+
+        // Validate that we have accounts configured
+        if self.config.accounts.is_empty() {
+            return Err(AppError::EmailError(crate::email::EmailError::ImapError(
+                "No email accounts configured".to_string(),
+            )));
+        }
+
+        self.check_secrets();
+        self.start_update_check();
+
+        // Initialize the current account only (don't initialize all accounts at startup)
+        match self.init_account(self.current_account_idx) {
+            Ok(()) => {
+                // Debug logging
+                if std::env::var("EMAIL_DEBUG").is_ok() {
+                    let log_file = "/tmp/tuimail_debug.log";
+                    if let Ok(mut file) = std::fs::OpenOptions::new()
+                        .create(true)
+                        .write(true)
+                        .append(true)
+                        .open(log_file)
+                    {
+                        use std::io::Write;
+                        let _ = writeln!(
+                            file,
+                            "[{}] Successfully initialized account {}",
+                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                            self.current_account_idx
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                // Show error but don't fail completely - allow user to switch accounts
+                if self.auth_problem.is_none() {
+                    self.show_error(&tr_args("msg-default-account-failed", &[("error", &e)]));
+                }
+
+                // Debug logging
+                if std::env::var("EMAIL_DEBUG").is_ok() {
+                    let log_file = "/tmp/tuimail_debug.log";
+                    if let Ok(mut file) = std::fs::OpenOptions::new()
+                        .create(true)
+                        .write(true)
+                        .append(true)
+                        .open(log_file)
+                    {
+                        use std::io::Write;
+                        let _ = writeln!(
+                            file,
+                            "[{}] Failed to initialize account {}: {}",
+                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                            self.current_account_idx,
+                            e
+                        );
+                    }
+                }
+
+                // Continue with default folder structure
+                self.rebuild_folder_items();
+                return Ok(()); // Don't fail completely
+            }
+        }
+
+        // Load emails for the first folder of the current account
+        if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
+            if !account_data.folders.is_empty() {
+                let folder = account_data.folders[0].clone();
+                if let Err(e) =
+                    self.load_emails_for_account_folder(self.current_account_idx, &folder)
+                {
+                    self.show_error(&tr_args("msg-load-emails-failed", &[("error", &e)]));
+                }
+            }
+        }
+
+        // Start background sync thread
+        if let Err(e) = self.start_background_sync() {
+            debug_log(&format!("Failed to start background sync: {}", e));
+            // Don't fail the app if background sync fails to start
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> AppResult<()> {
+        debug_log(&format!(
+            "Input received: {:?}, file_browser_mode: {}",
+            key, self.file_browser_mode
+        ));
+
+        // Handle file browser mode FIRST, regardless of current app mode
+        if self.file_browser_mode {
+            debug_log("Routing to file browser input handler");
+            return self.handle_file_browser_input(key);
+        }
+
+        match self.mode {
+            AppMode::Normal => self.handle_normal_mode(key),
+            AppMode::Compose => self.handle_compose_mode(key),
+            AppMode::ViewEmail => self.handle_view_mode(key),
+            AppMode::FolderList => self.handle_folder_list_mode(key),
+            AppMode::AccountSettings => self.handle_settings_mode(key),
+            AppMode::Dictionary => self.handle_dictionary_mode(key),
+            AppMode::Help => self.handle_help_mode(key),
+            AppMode::DeleteConfirm => self.handle_delete_confirm_mode(key),
+            AppMode::People => self.handle_people_mode(key),
+            AppMode::Cleanup => self.handle_cleanup_mode(key),
+            AppMode::Attachments => self.handle_attachments_mode(key),
+            AppMode::RulesReport => self.handle_rules_report_mode(key),
+            AppMode::AuthHelp => self.handle_auth_help_mode(key),
+            AppMode::MissingSecrets => self.handle_missing_secrets_mode(key),
+            AppMode::SmtpFallbackConfirm => self.handle_smtp_fallback_confirm_mode(key),
+            AppMode::ReplyWarning => self.handle_reply_warning_mode(key),
+            AppMode::Drafts => self.handle_drafts_mode(key),
+            AppMode::DraftConflict => self.handle_draft_conflict_mode(key),
+            AppMode::RecentlyDeleted => self.handle_recently_deleted_mode(key),
+            AppMode::PendingOperations => self.handle_pending_operations_mode(key),
+            AppMode::Reminders => self.handle_reminders_mode(key),
+            AppMode::Changelog => self.handle_changelog_mode(key),
+            AppMode::Diagnostics => self.handle_diagnostics_mode(key),
+            AppMode::Capabilities => self.handle_capabilities_mode(key),
+            AppMode::ImapConsole => self.handle_imap_console_mode(key),
+        }
+    }
+
+    fn handle_normal_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        if self.search_editing {
+            return self.handle_search_input(key);
+        }
+        if self.tag_input.is_some() {
+            return self.handle_tag_input(key);
+        }
+        if self.reminder_input.is_some() {
+            return self.handle_reminder_input(key);
+        }
+        if self.folder_picker.is_some() {
+            return self.handle_folder_picker(key);
+        }
+        match normal_mode_action(&key, self.cross_folder_list()) {
+            Some(action) => self.apply_action(action),
+            None => Ok(()),
+        }
+    }
+
+    /// Post an event to be handled on the next `process_events`
+    pub fn post(&self, event: AppEvent) {
+        self.events.send(event);
+    }
+
+    /// Handle every event waiting on the bus, stopping at the first error
+    pub fn process_events(&mut self) -> AppResult<()> {
+        while let Some(event) = self.events.try_recv() {
+            self.dispatch(event)?;
+            if self.should_quit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The single entry point for state changes from input, timers and background work
+    pub fn dispatch(&mut self, event: AppEvent) -> AppResult<()> {
+        match event {
+            AppEvent::Key(key) => self.handle_key_event(key),
+            AppEvent::Tick => self.tick(),
+            AppEvent::PollDatabase => {
+                // Check for new emails from background fetcher (legacy)
+                self.check_for_new_emails();
+                // A failed poll is retried on the next one, so don't bother the user
+                if let Err(e) = self.refresh_emails_from_database() {
+                    debug_log(&format!("Database poll error: {}", e));
+                }
+                self.fire_due_reminders();
+                Ok(())
+            }
+            AppEvent::FlagsSynced { account_email, folder, changed } => {
+                self.apply_flag_sync(&account_email, &folder, &changed);
+                Ok(())
+            }
+            AppEvent::DiskSpaceChanged { low } => {
+                self.disk_space_low = low;
+                match low {
+                    Some(free) => self.show_error(&tr_args(
+                        "msg-disk-low",
+                        &[
+                            ("free", &crate::attachment_index::format_size(free as usize)),
+                            ("needed", &self.config.cache.min_free_mb.unwrap_or_default()),
+                        ],
+                    )),
+                    None => self.show_info(&tr("msg-disk-ok")),
+                }
+                Ok(())
+            }
+            AppEvent::HeadersSynced { account_email, folder } => self.reload_cached_folder(&account_email, &folder),
+            AppEvent::BodiesSynced { account_email, folder, uids } => {
+                self.apply_synced_bodies(&account_email, &folder, &uids);
+                if self.mode == AppMode::ViewEmail {
+                    self.prepare_view_body();
+                    self.request_pgp_open();
+                    self.request_calendar_check();
+                    self.load_digest();
+                }
+                // Downloaded bodies may include bounce notifications
+                self.load_bounces(self.current_account_idx);
+                Ok(())
+            }
+            AppEvent::ExpungedElsewhere { account_email, folder, count } => {
+                self.show_info(&tr_args("msg-deleted-elsewhere", &[("count", &count), ("folder", &folder)]));
+                self.reload_cached_folder(&account_email, &folder)
+            }
+            AppEvent::FolderSynced { account_email, folder } => {
+                self.updating_folders.remove(&(account_email.clone(), folder.clone()));
+                // The sync may have brought in tags from notmuch
+                if crate::notmuch::ENABLED && self.config.accounts.get(self.current_account_idx).is_some_and(|a| a.email == account_email) {
+                    self.load_tags(self.current_account_idx);
+                }
+                self.reload_cached_folder(&account_email, &folder)
+            }
+            AppEvent::AccountConnected { account_idx, folders } => {
+                self.account_connected(account_idx, folders);
+                Ok(())
+            }
+            AppEvent::SpecialFoldersFound { account_idx, folders } => {
+                self.special_folders_found(account_idx, folders);
+                Ok(())
+            }
+            AppEvent::DraftsSynced { account_idx, result } => {
+                self.drafts_synced(account_idx, result);
+                Ok(())
+            }
+            AppEvent::CleanupFinished { account_idx, folder, action, done, error } => {
+                self.cleanup_finished(account_idx, &folder, &action, done, error)
+            }
+            AppEvent::BodyPrepared { key, body } => {
+                self.body_prepared(key, body);
+                Ok(())
+            }
+            AppEvent::PgpOpened { key, opened } => {
+                if self.pgp_pending.as_ref() == Some(&key) {
+                    self.pgp_pending = None;
+                    self.pgp_view = Some((key, opened));
+                }
+                Ok(())
+            }
+            AppEvent::CalendarChecked { key, conflicts } => {
+                if self.calendar_pending.as_ref() == Some(&key) {
+                    self.calendar_pending = None;
+                    self.calendar_view = Some((key, conflicts));
+                }
+                Ok(())
+            }
+            AppEvent::ThreadsFetched { account_idx, folder, threads } => {
+                self.threads_fetched(account_idx, &folder, threads);
+                Ok(())
+            }
+            AppEvent::FolderEdited { edit, result } => self.folder_edited(edit, result),
+            AppEvent::ServerSearched { account_idx, query, emails } => {
+                self.server_searched(account_idx, &query, emails);
+                Ok(())
+            }
+            AppEvent::DirectorySearched { account_idx, query, contacts } => {
+                self.directory_pending = None;
+                self.directory_results = Some((account_idx, query, contacts));
+                // Search again if more was typed meanwhile
+                if self.mode == AppMode::Compose {
+                    self.request_directory_search();
+                }
+                Ok(())
+            }
+            AppEvent::CapabilitiesChecked { account_idx, capabilities } => {
+                if self.capabilities.as_ref().is_some_and(|(shown, _)| *shown == account_idx) {
+                    self.capabilities = Some((account_idx, Some(capabilities)));
+                }
+                Ok(())
+            }
+            AppEvent::ConsoleOutput { lines } => {
+                self.console_lines.extend(lines);
+                Ok(())
+            }
+            AppEvent::SpellChecked { generation, text, region, errors } => {
+                self.apply_spell_check(generation, text, region, errors);
+                Ok(())
+            }
+            AppEvent::ReleasesFetched { releases } => {
+                self.releases_fetched(releases);
+                Ok(())
+            }
+            AppEvent::OperationsProcessed { folders, failed } => {
+                if let Some(error) = failed.first() {
+                    self.show_error(&tr_args("msg-operation-failed", &[("error", &error)]));
+                }
+                if self.mode == AppMode::PendingOperations {
+                    self.load_pending_operations();
+                }
+                for (account_email, folder) in folders {
+                    self.reload_cached_folder(&account_email, &folder)?;
+                }
+                Ok(())
+            }
+            AppEvent::AvatarFetched { key, image } => {
+                self.store_avatar(key, image);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn apply_action(&mut self, action: Action) -> AppResult<()> {
+        debug_log(&format!("Action: {:?}", action));
+        match action {
+            Action::Quit => {
+                debug_log("Quit requested, cleaning up...");
+                self.cleanup();
+                self.should_quit = true;
+                Ok(())
+            }
+            Action::TestFileBrowser => {
+                self.test_file_browser()?;
+                Ok(())
+            }
+            Action::Compose => {
+                self.mode = AppMode::Compose;
+                self.focus = FocusPanel::ComposeForm;
+                self.compose_email = Email::new();
+                self.compose_field = ComposeField::To;
+                self.compose_cursor_pos = 0;
+                self.compose_to_text = String::new();
+                self.compose_cc_text = String::new();
+                self.compose_bcc_text = String::new();
+                self.compose_source = None;
+                self.apply_compose_defaults();
+                // Initialize spell and grammar checking for new compose
+                self.check_spelling();
+                self.request_grammar_check();
+                Ok(())
+            }
+            Action::FullResync => {
+                // Full re-sync: clear cache and fetch all emails
+                if let Err(e) = self.reset_sync_state() {
+                    self.show_error(&tr_args("msg-reset-sync-failed", &[("error", &e)]));
+                } else if let Err(e) = self.load_emails_for_selected_folder() {
+                    self.show_error(&tr_args("msg-refresh-failed", &[("error", &e)]));
+                } else {
+                    self.show_info(&tr("msg-full-resync-done"));
+                }
+                Ok(())
+            }
+            Action::Refresh => {
+                // Refresh emails for the currently selected folder
+                if let Err(e) = self.load_emails_for_selected_folder() {
+                    self.show_error(&tr_args("msg-refresh-failed", &[("error", &e)]));
+                } else {
+                    self.show_info(&tr("msg-refreshed"));
+                }
+                Ok(())
+            }
+            Action::OpenFolders => {
+                self.mode = AppMode::FolderList;
+                self.focus = FocusPanel::FolderList;
+                Ok(())
+            }
+            Action::OpenSettings => {
+                self.mode = AppMode::AccountSettings;
+                Ok(())
+            }
+            Action::OpenHelp => {
+                self.mode = AppMode::Help;
+                Ok(())
+            }
+            Action::ToggleSyncPause => {
+                self.toggle_sync_pause();
+                Ok(())
+            }
+            Action::OpenPeople => self.open_people_view(),
+            Action::OpenDrafts => self.open_drafts(),
+            Action::OpenRecentlyDeleted => self.open_recently_deleted(),
+            Action::OpenPendingOperations => self.open_pending_operations(),
+            Action::RemindMe => {
+                if self.selected_email_idx.is_some() {
+                    self.reminder_input = Some(String::new());
+                }
+                Ok(())
+            }
+            Action::OpenReminders => {
+                self.selected_reminder_idx = 0;
+                self.load_reminders();
+                self.mode = AppMode::Reminders;
+                Ok(())
+            }
+            Action::EditTags => {
+                if self.selected_email_idx.is_some() {
+                    self.tag_input = Some(String::new());
+                }
+                Ok(())
+            }
+            Action::OpenChangelog => {
+                self.open_changelog();
+                Ok(())
+            }
+            Action::OpenCleanup => self.open_cleanup(),
+            Action::OpenAttachments => self.open_attachment_browser(),
+            Action::RunRulesDryRun => self.run_rules_dry_run(),
+            Action::OpenDiagnostics => self.open_diagnostics(),
+            Action::ToggleReadLater => self.toggle_read_later(),
+            Action::Search => {
+                self.search_editing = true;
+                self.search_text = self.search_query.clone().unwrap_or_default();
+                Ok(())
+            }
+            Action::CycleImportanceView => {
+                self.cycle_importance_view();
+                Ok(())
+            }
+            Action::ToggleThreads => {
+                self.toggle_threads();
+                Ok(())
+            }
+            Action::MoveToFolder => {
+                self.open_folder_picker();
+                Ok(())
+            }
+            Action::ToggleMark => {
+                self.toggle_mark();
+                Ok(())
+            }
+            Action::RunQuick(key) => self.run_quick_action(key),
+            Action::CloseContactThread => self.close_contact_thread(),
+            Action::SelectPrevious => {
+                self.select_prev_email();
+                Ok(())
+            }
+            Action::SelectNext => {
+                self.select_next_email();
+                Ok(())
+            }
+            Action::OpenSelected => {
+                if let Some(idx) = self.selected_email_idx {
+                    debug_log(&format!("Enter pressed: idx={}, self.emails.len()={}", idx, self.emails.len()));
+                    if idx < self.emails.len() {
+                        self.mode = AppMode::ViewEmail;
+                        self.show_full_body = false;
+                        self.prepare_view_body();
+                        self.load_sender_preferences();
+                        self.request_pgp_open();
+                        self.request_calendar_check();
+                        self.load_digest();
+
+                        // Mark as read
+                        if let Err(e) = self.ensure_account_initialized(self.current_account_idx) {
+                            self.show_error(&tr_args("msg-account-init-failed", &[("error", &e)]));
+                        } else if let Some(account_data) =
+                            self.accounts.get(&self.current_account_idx)
+                        {
+                            let email = &self.emails[idx];
+                            debug_log(&format!("Opening email: subject={}", email.subject));
+                            if let (Some(client), false) = (&account_data.email_client, email.attachments.is_empty()) {
+                                if let Err(e) = client.touch_cached_attachments(&email.folder, &email.id) {
+                                    debug_log(&format!("Failed to note the attachments as opened: {}", e));
+                                }
+                            }
+                            if !email.seen {
+                                // Queue mark as read operation instead of direct IMAP call
+                                if let Err(e) = self.mark_current_email_as_read() {
+                                    self.show_error(&tr_args("msg-mark-read-failed", &[("error", &e)]));
+                                }
+                            }
+                            self.queue_missing_attachment_downloads();
+                            self.request_sender_avatar();
+                            self.request_selected_body();
+                        }
+                    } else {
+                        debug_log(&format!("Invalid email selection: idx={} >= self.emails.len()={}", idx, self.emails.len()));
+                        self.show_error(&tr("msg-invalid-email-selection"));
+                    }
+                } else {
+                    debug_log("No email selected");
+                    self.show_error(&tr("msg-no-email-selected"));
+                }
+                Ok(())
+            }
+            Action::NextAccount => self.rotate_to_next_account(),
+            Action::ConfirmDelete => {
+                self.show_delete_confirmation();
+                Ok(())
+            }
+        }
+    }
+
+    /// Handle spell suggestion navigation
+    fn handle_spell_suggestions(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc => {
+                self.show_spell_suggestions = false;
+                Ok(())
+            }
+            KeyCode::Up => {
+                if self.selected_spell_suggestion > 0 {
+                    self.selected_spell_suggestion -= 1;
+                }
+                Ok(())
+            }
+            KeyCode::Down => {
+                // Find current error to get suggestion count
+                for error in &self.spell_errors {
+                    let word_end = error.position + error.word.len();
+                    if self.compose_cursor_pos >= error.position && self.compose_cursor_pos <= word_end {
+                        if self.selected_spell_suggestion < error.suggestions.len().saturating_sub(1) {
+                            self.selected_spell_suggestion += 1;
+                        }
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            KeyCode::Enter => {
+                self.apply_spell_suggestion();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+    
+    fn handle_grammar_suggestions(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc => {
+                self.show_grammar_suggestions = false;
+                Ok(())
+            }
+            KeyCode::Up => {
+                if self.selected_grammar_suggestion > 0 {
+                    self.selected_grammar_suggestion -= 1;
+                }
+                Ok(())
+            }
+            KeyCode::Down => {
+                // Find current error to get suggestion count
+                for error in &self.grammar_errors {
+                    if self.compose_cursor_pos >= error.start && self.compose_cursor_pos <= error.end {
+                        if self.selected_grammar_suggestion < error.replacements.len().saturating_sub(1) {
+                            self.selected_grammar_suggestion += 1;
+                        }
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            KeyCode::Enter => {
+                self.apply_grammar_suggestion();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_compose_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        // Handle spell suggestion mode
+        if self.show_spell_suggestions {
+            return self.handle_spell_suggestions(key);
+        }
+        
+        // Handle grammar suggestion mode
+        if self.show_grammar_suggestions {
+            return self.handle_grammar_suggestions(key);
+        }
+
+        // Handle attachment input mode separately
+        if self.attachment_input_mode {
+            return self.handle_attachment_input(key);
+        }
+
+        if self.recipient_choices.is_some() {
+            return self.handle_recipient_choices(key);
+        }
+
+        match key.code {
+            // Spell checking shortcuts
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.toggle_spell_check();
+                Ok(())
+            }
+            KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.show_spell_suggestions_at_cursor();
+                Ok(())
+            }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.add_word_to_dictionary();
+                Ok(())
+            }
+            // Grammar checking shortcuts
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.toggle_grammar_check();
+                Ok(())
+            }
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.show_grammar_suggestions_at_cursor();
+                Ok(())
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.show_compose_stats = !self.show_compose_stats;
+                Ok(())
+            }
+            KeyCode::Char('m') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.compose_format = match self.compose_format {
+                    crate::config::ComposeFormat::Plain => crate::config::ComposeFormat::Markdown,
+                    crate::config::ComposeFormat::Markdown => crate::config::ComposeFormat::Plain,
+                };
+                Ok(())
+            }
+            KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.cycle_compose_priority();
+                Ok(())
+            }
+            KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.toggle_compose_pgp(false);
+                Ok(())
+            }
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.toggle_compose_pgp(true);
+                Ok(())
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.correct_recipient_typos();
+                Ok(())
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.drop_suppressed_recipients();
+                Ok(())
+            }
+            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.toggle_compose_fullscreen();
+                Ok(())
+            }
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::ALT) => {
+                let height = self.compose_header_height.get();
+                self.compose_header_height.set(height.saturating_sub(1).max(COMPOSE_HEADER_MIN));
+                Ok(())
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::ALT) => {
+                let height = self.compose_header_height.get();
+                self.compose_header_height.set(height.saturating_add(1));
+                Ok(())
+            }
+            // Interleaved reply tools
+            KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.edit_body_quoting(crate::quoting::split_quote);
+                Ok(())
+            }
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.edit_body_quoting(crate::quoting::quote_paragraph);
+                Ok(())
+            }
+            KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.edit_body_quoting(crate::quoting::strip_quotes_below);
+                Ok(())
+            }
+            KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                self.focus = FocusPanel::EmailList;
+                self.compose_field = ComposeField::To;
+                self.compose_cursor_pos = 0;
+                self.compose_draft_id = None;
+                self.compose_source = None;
+                Ok(())
+            }
+            KeyCode::Tab => {
+                // Move to next field
+                self.compose_field = match self.compose_field {
+                    ComposeField::To => ComposeField::Cc,
+                    ComposeField::Cc => ComposeField::Bcc,
+                    ComposeField::Bcc => ComposeField::Subject,
+                    ComposeField::Subject => ComposeField::Body,
+                    ComposeField::Body => ComposeField::To,
+                };
+                // Reset cursor position when switching fields
+                self.compose_cursor_pos = match self.compose_field {
+                    ComposeField::To => self.compose_to_text.len(), // End of To field
+                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
+                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
+                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
+                    ComposeField::Body => 0,                        // Beginning of Body for replies
+                };
+                // Trigger spell check when switching to a new field
+                self.check_spelling();
+                Ok(())
+            }
+            KeyCode::BackTab => {
+                // Move to previous field
+                self.compose_field = match self.compose_field {
+                    ComposeField::To => ComposeField::Body,
+                    ComposeField::Cc => ComposeField::To,
+                    ComposeField::Bcc => ComposeField::Cc,
+                    ComposeField::Subject => ComposeField::Bcc,
+                    ComposeField::Body => ComposeField::Subject,
+                };
+                // Reset cursor position when switching fields
+                self.compose_cursor_pos = match self.compose_field {
+                    ComposeField::To => self.compose_to_text.len(), // End of To field
+                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
+                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
+                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
+                    ComposeField::Body => 0,                        // Beginning of Body for replies
+                };
+                // Trigger spell check when switching to a new field
+                self.check_spelling();
+                Ok(())
+            }
+            KeyCode::Up => {
+                // Move to previous field
+                self.compose_field = match self.compose_field {
+                    ComposeField::To => ComposeField::Body,
+                    ComposeField::Cc => ComposeField::To,
+                    ComposeField::Bcc => ComposeField::Cc,
+                    ComposeField::Subject => ComposeField::Bcc,
+                    ComposeField::Body => ComposeField::Subject,
+                };
+                // Reset cursor position when switching fields
+                self.compose_cursor_pos = match self.compose_field {
+                    ComposeField::To => self.compose_to_text.len(), // End of To field
+                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
+                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
+                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
+                    ComposeField::Body => 0,                        // Beginning of Body for replies
+                };
+                Ok(())
+            }
+            KeyCode::Down => {
+                // Move to next field
+                self.compose_field = match self.compose_field {
+                    ComposeField::To => ComposeField::Cc,
+                    ComposeField::Cc => ComposeField::Bcc,
+                    ComposeField::Bcc => ComposeField::Subject,
+                    ComposeField::Subject => ComposeField::Body,
+                    ComposeField::Body => ComposeField::To,
+                };
+                // Reset cursor position when switching fields
+                self.compose_cursor_pos = match self.compose_field {
+                    ComposeField::To => self.compose_to_text.len(), // End of To field
+                    ComposeField::Cc => self.compose_cc_text.len(), // End of CC field
+                    ComposeField::Bcc => self.compose_bcc_text.len(), // End of BCC field
+                    ComposeField::Subject => self.compose_email.subject.len(), // End of Subject
+                    ComposeField::Body => 0,                        // Beginning of Body for replies
+                };
+                Ok(())
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.send_email()?;
+                Ok(())
+            }
+            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                // Add attachment
+                self.add_attachment()?;
+                Ok(())
+            }
+            KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                // Remove selected attachment
+                self.remove_selected_attachment()?;
+                Ok(())
+            }
+            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.attach_clipboard_image();
+                Ok(())
+            }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => self.save_draft(),
+            KeyCode::Char(c) => {
+                // Add character to current field at cursor position
+                match self.compose_field {
+                    ComposeField::To => {
+                        // Insert character at cursor position in To field
+                        if self.compose_cursor_pos <= self.compose_to_text.len() {
+                            self.compose_to_text.insert(self.compose_cursor_pos, c);
+                            self.compose_cursor_pos += 1;
+                        } else {
+                            self.compose_to_text.push(c);
+                            self.compose_cursor_pos = self.compose_to_text.len();
+                        }
+
+                        // Parse the to field and update compose_email.to
+                        self.compose_email.to.clear();
+                        for addr in self.compose_to_text.split(',') {
+                            let addr = addr.trim();
+                            if !addr.is_empty() {
+                                self.compose_email.to.push(crate::email::EmailAddress {
+                                    name: None,
+                                    address: addr.to_string(),
+                                });
+                            }
+                        }
+                    }
+                    ComposeField::Cc => {
+                        // Insert character at cursor position in CC field
+                        if self.compose_cursor_pos <= self.compose_cc_text.len() {
+                            self.compose_cc_text.insert(self.compose_cursor_pos, c);
+                            self.compose_cursor_pos += 1;
+                        } else {
+                            self.compose_cc_text.push(c);
+                            self.compose_cursor_pos = self.compose_cc_text.len();
+                        }
+
+                        // Parse the cc field and update compose_email.cc
+                        self.compose_email.cc.clear();
+                        for addr in self.compose_cc_text.split(',') {
+                            let addr = addr.trim();
+                            if !addr.is_empty() {
+                                self.compose_email.cc.push(crate::email::EmailAddress {
+                                    name: None,
+                                    address: addr.to_string(),
+                                });
+                            }
+                        }
+                    }
+                    ComposeField::Bcc => {
+                        // Insert character at cursor position in BCC field
+                        if self.compose_cursor_pos <= self.compose_bcc_text.len() {
+                            self.compose_bcc_text.insert(self.compose_cursor_pos, c);
+                            self.compose_cursor_pos += 1;
+                        } else {
+                            self.compose_bcc_text.push(c);
+                            self.compose_cursor_pos = self.compose_bcc_text.len();
+                        }
+
+                        // Parse the bcc field and update compose_email.bcc
+                        self.compose_email.bcc.clear();
+                        for addr in self.compose_bcc_text.split(',') {
+                            let addr = addr.trim();
+                            if !addr.is_empty() {
+                                self.compose_email.bcc.push(crate::email::EmailAddress {
+                                    name: None,
+                                    address: addr.to_string(),
+                                });
+                            }
+                        }
+                    }
+                    ComposeField::Subject => {
+                        self.compose_email.subject.push(c);
+                        self.schedule_checks();
+                    }
+                    ComposeField::Body => {
+                        if let Some(ref mut body) = self.compose_email.body_text {
+                            // Ensure cursor position is valid and insert character
+                            let cursor_pos = self.compose_cursor_pos.min(body.len());
+                            body.insert(cursor_pos, c);
+                            self.compose_cursor_pos = cursor_pos + 1;
+                        } else {
+                            self.compose_email.body_text = Some(c.to_string());
+                            self.compose_cursor_pos = 1;
+                        }
+                        self.schedule_checks();
+                    }
+                }
+                if matches!(self.compose_field, ComposeField::To | ComposeField::Cc | ComposeField::Bcc) {
+                    self.request_directory_search();
+                }
+                Ok(())
+            }
+            KeyCode::Backspace => {
+                // Remove character from current field at cursor position
+                match self.compose_field {
+                    ComposeField::To => {
+                        if self.compose_cursor_pos > 0
+                            && self.compose_cursor_pos <= self.compose_to_text.len()
+                        {
+                            self.compose_to_text.remove(self.compose_cursor_pos - 1);
+                            self.compose_cursor_pos -= 1;
+
+                            // Parse the to field and update compose_email.to
+                            self.compose_email.to.clear();
+                            for addr in self.compose_to_text.split(',') {
+                                let addr = addr.trim();
+                                if !addr.is_empty() {
+                                    self.compose_email.to.push(crate::email::EmailAddress {
+                                        name: None,
+                                        address: addr.to_string(),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    ComposeField::Cc => {
+                        if self.compose_cursor_pos > 0
+                            && self.compose_cursor_pos <= self.compose_cc_text.len()
+                        {
+                            self.compose_cc_text.remove(self.compose_cursor_pos - 1);
+                            self.compose_cursor_pos -= 1;
+
+                            // Parse the cc field and update compose_email.cc
+                            self.compose_email.cc.clear();
+                            for addr in self.compose_cc_text.split(',') {
+                                let addr = addr.trim();
+                                if !addr.is_empty() {
+                                    self.compose_email.cc.push(crate::email::EmailAddress {
+                                        name: None,
+                                        address: addr.to_string(),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    ComposeField::Bcc => {
+                        if self.compose_cursor_pos > 0
+                            && self.compose_cursor_pos <= self.compose_bcc_text.len()
+                        {
+                            self.compose_bcc_text.remove(self.compose_cursor_pos - 1);
+                            self.compose_cursor_pos -= 1;
+
+                            // Parse the bcc field and update compose_email.bcc
+                            self.compose_email.bcc.clear();
+                            for addr in self.compose_bcc_text.split(',') {
+                                let addr = addr.trim();
+                                if !addr.is_empty() {
+                                    self.compose_email.bcc.push(crate::email::EmailAddress {
+                                        name: None,
+                                        address: addr.to_string(),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    ComposeField::Subject => {
+                        self.compose_email.subject.pop();
+                        self.schedule_checks();
+                    }
+                    ComposeField::Body => {
+                        if let Some(ref mut body) = self.compose_email.body_text {
+                            if self.compose_cursor_pos > 0 && self.compose_cursor_pos <= body.len()
+                            {
+                                body.remove(self.compose_cursor_pos - 1);
+                                self.compose_cursor_pos -= 1;
+                                
+                                self.schedule_checks();
+                            }
+                        }
+                    }
+                }
+                if matches!(self.compose_field, ComposeField::To | ComposeField::Cc | ComposeField::Bcc) {
+                    self.request_directory_search();
+                }
+                Ok(())
+            }
+            KeyCode::Enter => {
+                // A name typed in an address field is looked up in the address book
+                if matches!(self.compose_field, ComposeField::To | ComposeField::Cc | ComposeField::Bcc) {
+                    self.resolve_recipient_name();
+                }
+                // In body field, add newline at cursor position
+                if self.compose_field == ComposeField::Body {
+                    if let Some(ref mut body) = self.compose_email.body_text {
+                        // Ensure cursor position is valid
+                        let cursor_pos = self.compose_cursor_pos.min(body.len());
+                        body.insert(cursor_pos, '\n');
+                        self.compose_cursor_pos = cursor_pos + 1;
+                        
+                        self.schedule_checks();
+                    } else {
+                        // If body is None, create it with a newline
+                        self.compose_email.body_text = Some("\n".to_string());
+                        self.compose_cursor_pos = 1;
+                        self.schedule_checks();
+                    }
+                }
+                Ok(())
+            }
+            KeyCode::Left => {
+                // Move cursor left in current field
+                match self.compose_field {
+                    ComposeField::To => {
+                        if self.compose_cursor_pos > 0 {
+                            self.compose_cursor_pos -= 1;
+                        }
+                    }
+                    ComposeField::Cc => {
+                        if self.compose_cursor_pos > 0 {
+                            self.compose_cursor_pos -= 1;
+                        }
+                    }
+                    ComposeField::Bcc => {
+                        if self.compose_cursor_pos > 0 {
+                            self.compose_cursor_pos -= 1;
+                        }
+                    }
+                    ComposeField::Body => {
+                        if self.compose_cursor_pos > 0 {
+                            self.compose_cursor_pos -= 1;
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(())
+            }
+            KeyCode::Right => {
+                // Move cursor right in current field
+                match self.compose_field {
+                    ComposeField::To => {
+                        if self.compose_cursor_pos < self.compose_to_text.len() {
+                            self.compose_cursor_pos += 1;
+                        }
+                    }
+                    ComposeField::Cc => {
+                        if self.compose_cursor_pos < self.compose_cc_text.len() {
+                            self.compose_cursor_pos += 1;
+                        }
+                    }
+                    ComposeField::Bcc => {
+                        if self.compose_cursor_pos < self.compose_bcc_text.len() {
+                            self.compose_cursor_pos += 1;
+                        }
+                    }
+                    ComposeField::Body => {
+                        if let Some(body) = &self.compose_email.body_text {
+                            if self.compose_cursor_pos < body.len() {
+                                self.compose_cursor_pos += 1;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(())
+            }
+            KeyCode::Home => {
+                // Move cursor to beginning of current line in body field
+                if self.compose_field == ComposeField::Body {
+                    if let Some(body) = &self.compose_email.body_text {
+                        // Find the beginning of the current line
+                        let text_before_cursor = &body[..self.compose_cursor_pos];
+                        if let Some(last_newline) = text_before_cursor.rfind('\n') {
+                            self.compose_cursor_pos = last_newline + 1;
+                        } else {
+                            self.compose_cursor_pos = 0;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            KeyCode::End => {
+                // Move cursor to end of current line in body field
+                if self.compose_field == ComposeField::Body {
+                    if let Some(body) = &self.compose_email.body_text {
+                        // Find the end of the current line
+                        let text_after_cursor = &body[self.compose_cursor_pos..];
+                        if let Some(next_newline) = text_after_cursor.find('\n') {
+                            self.compose_cursor_pos += next_newline;
+                        } else {
+                            self.compose_cursor_pos = body.len();
+                        }
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_view_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        if self.reminder_input.is_some() {
+            return self.handle_reminder_input(key);
+        }
+        if self.bounce_editing {
+            match key.code {
+                KeyCode::Enter => {
+                    self.bounce_editing = false;
+                    self.bounce_current_email()?;
+                }
+                KeyCode::Esc => self.bounce_editing = false,
+                KeyCode::Backspace => {
+                    self.bounce_text.pop();
+                }
+                KeyCode::Char(c) => self.bounce_text.push(c),
+                _ => {}
+            }
+            return Ok(());
+        }
+        if self.rsvp_prompt {
+            self.rsvp_prompt = false;
+            let response = match key.code {
+                KeyCode::Char('a') => crate::calendar::Response::Accepted,
+                KeyCode::Char('t') => crate::calendar::Response::Tentative,
+                KeyCode::Char('d') => crate::calendar::Response::Declined,
+                _ => return Ok(()),
+            };
+            return self.answer_invitation(response);
+        }
+
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                self.email_view_scroll = 0; // Reset scroll when exiting
+                self.view_body = None;
+                self.view_body_pending = None;
+                self.digest = None;
+                Ok(())
+            }
+            KeyCode::Up => {
+                if self.email_view_scroll > 0 {
+                    self.email_view_scroll -= 1;
+                }
+                Ok(())
+            }
+            KeyCode::Down => {
+                self.email_view_scroll += 1;
+                Ok(())
+            }
+            KeyCode::PageUp => {
+                self.email_view_scroll = self.email_view_scroll.saturating_sub(10);
+                Ok(())
+            }
+            KeyCode::PageDown => {
+                self.email_view_scroll += 10;
+                Ok(())
+            }
+            KeyCode::Home => {
+                self.email_view_scroll = 0;
+                Ok(())
+            }
+            KeyCode::Char('r') => self.confirm_reply(false),
+            KeyCode::Char('a') => self.confirm_reply(true),
+            KeyCode::Char('f') => {
+                self.forward_email()?;
+                Ok(())
+            }
+            KeyCode::Char('d') => {
+                self.show_delete_confirmation();
+                Ok(())
+            }
+            KeyCode::Char('e') => self.resend_bounced(),
+            KeyCode::Char('m') => self.toggle_mute(false),
+            KeyCode::Char('M') => self.toggle_mute(true),
+            KeyCode::Char('o') => {
+                self.open_notification_page();
+                Ok(())
+            }
+            KeyCode::Char('u') => self.unsubscribe_notification_thread(),
+            KeyCode::Char('c') => self.add_invitation_to_calendar(),
+            KeyCode::Char('A') => self.add_attached_contacts(),
+            KeyCode::Char('B') => {
+                self.toggle_digest();
+                Ok(())
+            }
+            KeyCode::Char(']') => {
+                self.step_digest(true);
+                Ok(())
+            }
+            KeyCode::Char('[') => {
+                self.step_digest(false);
+                Ok(())
+            }
+            KeyCode::Char('i') => {
+                let invited = self
+                    .get_current_email()
+                    .and_then(crate::calendar::invitation)
+                    .is_some_and(|invitation| invitation.calendar.method.as_deref().is_none_or(|m| m == "REQUEST"));
+                if invited {
+                    self.rsvp_prompt = true;
+                } else {
+                    self.show_error(&tr("msg-not-invitation"));
+                }
+                Ok(())
+            }
+            KeyCode::Char('z') => {
+                self.reminder_input = Some(String::new());
+                Ok(())
+            }
+            KeyCode::Char('b') => {
+                // Ask where to redirect the message
+                self.bounce_text.clear();
+                self.bounce_editing = true;
+                Ok(())
+            }
+            KeyCode::Char('s') => {
+                // Save selected attachment
+                self.save_selected_attachment()?;
+                Ok(())
+            }
+            KeyCode::Char('h') => {
+                // Expand or collapse the header panel
+                self.headers_expanded = !self.headers_expanded;
+                Ok(())
+            }
+            KeyCode::Char('Q') => {
+                self.change_view_preferences(|preferences| preferences.expand_quotes = !preferences.expand_quotes);
+                Ok(())
+            }
+            KeyCode::Char('H') => {
+                self.toggle_html_view();
+                Ok(())
+            }
+            KeyCode::Char('R') => {
+                self.toggle_remote_content();
+                Ok(())
+            }
+            KeyCode::Char('w') => {
+                self.open_html_in_browser();
+                Ok(())
+            }
+            KeyCode::Char('F') => {
+                // Show all of a truncated large body
+                if self.view_body.as_ref().is_some_and(|(_, body)| body.truncated) {
+                    self.show_full_body = true;
+                    self.prepare_view_body();
+                }
+                Ok(())
+            }
+            KeyCode::Char('x') => {
+                // Cancel the download of the selected attachment
+                self.cancel_selected_attachment_download();
+                Ok(())
+            }
+            KeyCode::Tab => {
+                // Navigate through attachments
+                self.select_next_attachment();
+                Ok(())
+            }
+            KeyCode::BackTab => {
+                // Navigate through attachments (reverse)
+                self.select_previous_attachment();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_folder_list_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        if self.folder_edit.is_some() {
+            self.handle_folder_edit(key);
+            return Ok(());
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                self.focus = FocusPanel::FolderList;
+                Ok(())
+            }
+            KeyCode::Up => {
+                if !self.folder_items.is_empty() && self.selected_folder_item_idx > 0 {
+                    self.selected_folder_item_idx -= 1;
+                }
+                Ok(())
+            }
+            KeyCode::Down => {
+                if !self.folder_items.is_empty()
+                    && self.selected_folder_item_idx < self.folder_items.len().saturating_sub(1)
+                {
+                    self.selected_folder_item_idx += 1;
+                }
+                Ok(())
+            }
+            KeyCode::Enter => {
+                if let Some(item) = self
+                    .folder_items
+                    .get(self.selected_folder_item_idx)
+                    .cloned()
+                {
+                    match item {
+                        crate::app::FolderItem::Account { index, .. } => {
+                            // Toggle account expansion
+                            self.toggle_account_expansion(index);
+                        }
+                        crate::app::FolderItem::Folder {
+                            account_index,
+                            full_path,
+                            ..
+                        } => {
+                            // Select folder and switch to normal mode
+                            self.current_account_idx = account_index;
+                            self.mode = AppMode::Normal;
+                            self.focus = FocusPanel::EmailList;
+
+                            // Load emails for the selected folder
+                            if let Err(e) =
+                                self.load_emails_for_account_folder(account_index, &full_path)
+                            {
+                                self.show_error(&tr_args("msg-load-emails-failed", &[("error", &e)]));
+                            }
+                        }
+                        crate::app::FolderItem::ReadLater { account_index } => {
+                            self.current_account_idx = account_index;
+                            self.mode = AppMode::Normal;
+                            self.focus = FocusPanel::EmailList;
+                            self.open_read_later()?;
+                        }
+                        crate::app::FolderItem::Vip { account_index } => {
+                            self.current_account_idx = account_index;
+                            self.mode = AppMode::Normal;
+                            self.focus = FocusPanel::EmailList;
+                            self.open_vip_folder()?;
+                        }
+                        crate::app::FolderItem::Notifications { account_index } => {
+                            self.current_account_idx = account_index;
+                            self.mode = AppMode::Normal;
+                            self.focus = FocusPanel::EmailList;
+                            self.open_notifications_folder()?;
+                        }
+                    }
+                } else {
+                    self.show_error(&tr("msg-invalid-selection"));
+                }
+                Ok(())
+            }
+            KeyCode::Char('N') => {
+                self.toggle_folder_option(|options| options.never_notify = !options.never_notify);
+                Ok(())
+            }
+            KeyCode::Char('A') => {
+                self.toggle_folder_option(|options| options.no_auto_sync = !options.no_auto_sync);
+                Ok(())
+            }
+            KeyCode::Char('c') => {
+                self.start_folder_edit('c');
+                Ok(())
+            }
+            KeyCode::Char('r') => {
+                self.start_folder_edit('r');
+                Ok(())
+            }
+            KeyCode::Char('d') => {
+                self.start_folder_edit('d');
+                Ok(())
+            }
+            KeyCode::Char(' ') => {
+                // Space bar also toggles account expansion
+                if let Some(item) = self
+                    .folder_items
+                    .get(self.selected_folder_item_idx)
+                    .cloned()
+                {
+                    if let crate::app::FolderItem::Account { index, .. } = item {
+                        self.toggle_account_expansion(index);
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Ask for the name of a new folder of the selected account (`c`), for the new name of
+    /// the selected folder (`r`) or whether to delete it (`d`)
+    fn start_folder_edit(&mut self, key: char) {
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-folders"));
+            return;
+        }
+        let selected = self.folder_items.get(self.selected_folder_item_idx).cloned();
+        let account_index = match &selected {
+            Some(FolderItem::Account { index, .. }) => *index,
+            Some(
+                FolderItem::Folder { account_index, .. }
+                | FolderItem::ReadLater { account_index }
+                | FolderItem::Vip { account_index }
+                | FolderItem::Notifications { account_index },
+            ) => *account_index,
+            None => return,
+        };
+        let folder = match selected {
+            Some(FolderItem::Folder { full_path, .. }) => Some(full_path),
+            _ => None,
+        };
+        self.folder_edit = match (key, folder) {
+            ('c', _) => Some(FolderEdit::Create { account_index, name: String::new() }),
+            (_, Some(folder)) if folder.eq_ignore_ascii_case("INBOX") => {
+                self.show_error(&tr("msg-inbox-fixed"));
+                None
+            }
+            ('r', Some(folder)) => Some(FolderEdit::Rename { account_index, name: folder.clone(), folder }),
+            ('d', Some(folder)) => Some(FolderEdit::Delete { account_index, folder }),
+            _ => {
+                self.show_error(&tr("msg-select-folder"));
+                None
+            }
+        };
+    }
+
+    fn handle_folder_edit(&mut self, key: KeyEvent) {
+        let Some(edit) = self.folder_edit.as_mut() else {
+            return;
+        };
+        match (edit, key.code) {
+            (_, KeyCode::Esc) => self.folder_edit = None,
+            (FolderEdit::Delete { .. }, KeyCode::Char('y') | KeyCode::Char('Y')) => {
+                if let Some(edit) = self.folder_edit.take() {
+                    self.run_folder_edit(edit);
+                }
+            }
+            (FolderEdit::Delete { .. }, _) => self.folder_edit = None,
+            (FolderEdit::Create { name, .. } | FolderEdit::Rename { name, .. }, KeyCode::Backspace) => {
+                name.pop();
+            }
+            (FolderEdit::Create { name, .. } | FolderEdit::Rename { name, .. }, KeyCode::Char(c)) => name.push(c),
+            (FolderEdit::Create { name, .. } | FolderEdit::Rename { name, .. }, KeyCode::Enter) => {
+                let trimmed = name.trim().to_string();
+                *name = trimmed;
+                let edit = self.folder_edit.take();
+                match edit {
+                    Some(FolderEdit::Create { name, .. }) | Some(FolderEdit::Rename { name, .. }) if name.is_empty() => {}
+                    Some(FolderEdit::Rename { folder, name, .. }) if folder == name => {}
+                    Some(edit) => self.run_folder_edit(edit),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Create, rename or delete a folder on the server in the background; the folder
+    /// list follows once `FolderEdited` arrives
+    fn run_folder_edit(&mut self, edit: FolderEdit) {
+        let account_index = edit.account_index();
+        if let Err(e) = self.ensure_account_initialized(account_index) {
+            self.show_error(&tr_args("msg-account-init-failed", &[("error", &e)]));
+            return;
+        }
+        let Some(client) = self.accounts.get(&account_index).and_then(|data| data.email_client.clone()) else {
+            self.show_error(&tr("msg-account-not-connected"));
+            return;
+        };
+        match &edit {
+            FolderEdit::Create { name, .. } => self.show_info(&tr_args("msg-folder-creating", &[("folder", &name)])),
+            FolderEdit::Rename { folder, name, .. } => self.show_info(&tr_args("msg-folder-renaming", &[("folder", &folder), ("name", &name)])),
+            FolderEdit::Delete { folder, .. } => self.show_info(&tr_args("msg-folder-deleting", &[("folder", &folder)])),
+        }
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let result = match &edit {
+                FolderEdit::Create { name, .. } => client.create_folder(name),
+                FolderEdit::Rename { folder, name, .. } => client.rename_folder(folder, name),
+                FolderEdit::Delete { folder, .. } => client.delete_folder(folder),
+            };
+            let _ = sender.send(AppEvent::FolderEdited { edit, result: result.map_err(|e| e.to_string()) });
+        });
+    }
+
+    /// Bring the folder list, the folder options in the config and the list on screen in
+    /// line with a folder created, renamed or deleted on the server
+    fn folder_edited(&mut self, edit: FolderEdit, result: Result<(), String>) -> AppResult<()> {
+        if let Err(e) = result {
+            self.show_error(&e);
+            return Ok(());
+        }
+        let account_index = edit.account_index();
+        let Some(account_data) = self.accounts.get_mut(&account_index) else {
+            return Ok(());
+        };
+        let account_email = account_data.account.email.clone();
+        let (folder, action, detail) = match &edit {
+            FolderEdit::Create { name, .. } => {
+                if !account_data.folders.contains(name) {
+                    account_data.folders.push(name.clone());
+                }
+                (name.clone(), "create folder", String::new())
+            }
+            FolderEdit::Rename { folder, name, .. } => {
+                for known in account_data.folders.iter_mut() {
+                    if let Some(renamed) = renamed_folder(known, folder, name) {
+                        *known = renamed;
+                    }
+                }
+                (folder.clone(), "rename folder", format!("to {}", name))
+            }
+            FolderEdit::Delete { folder, .. } => {
+                account_data.folders.retain(|known| known != folder);
+                (folder.clone(), "delete folder", String::new())
+            }
+        };
+        crate::audit::record(&self.database, &account_email, &folder, None, action, crate::audit::Origin::Ui, &detail);
+
+        // The folder's options go with it, and those of the folders below it with them
+        // when it is renamed; the folders below a deleted one stay on the server
+        let moved = |known: &str| match &edit {
+            FolderEdit::Rename { folder, name, .. } => renamed_folder(known, folder, name).map(Some),
+            FolderEdit::Delete { folder, .. } => (known == folder).then_some(None),
+            FolderEdit::Create { .. } => None,
+        };
+        if let Some(account) = self.config.accounts.get_mut(account_index) {
+            let affected: Vec<(String, Option<String>)> =
+                account.folder_options.keys().filter_map(|known| Some((known.clone(), moved(known)?))).collect();
+            for (known, new) in &affected {
+                let options = account.folder_options.remove(known).unwrap_or_default();
+                if let Some(new) = new {
+                    account.set_folder_options(new, options);
+                }
+            }
+            if !affected.is_empty() {
+                let account = account.clone();
+                if let Some(account_data) = self.accounts.get_mut(&account_index) {
+                    account_data.account = account;
+                }
+                if let Ok(mut folders) = self.manual_sync_folders.lock() {
+                    *folders = self.config.manual_sync_folders();
+                }
+                if let Some(path) = &self.config_path {
+                    if let Err(e) = self.config.save(path) {
+                        debug_log(&format!("Failed to save the folder options after changing {}: {}", folder, e));
+                    }
+                }
+            }
+        }
+        self.rebuild_folder_items();
+
+        match &edit {
+            FolderEdit::Create { name, .. } => self.show_info(&tr_args("msg-folder-created", &[("folder", &name)])),
+            FolderEdit::Rename { folder, name, .. } => self.show_info(&tr_args("msg-folder-renamed", &[("folder", &folder), ("name", &name)])),
+            FolderEdit::Delete { folder, .. } => self.show_info(&tr_args("msg-folder-deleted", &[("folder", &folder)])),
+        }
+
+        // The list on screen was of the folder that changed
+        if account_index == self.current_account_idx {
+            if let Some(new) = moved(&self.selected_folder.clone()) {
+                let shown = new.unwrap_or_else(|| "INBOX".to_string());
+                self.load_emails_for_account_folder(account_index, &shown)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Change the options of the selected folder in the folder list and save the config
+    fn toggle_folder_option(&mut self, change: impl FnOnce(&mut crate::config::FolderOptions)) {
+        let Some(FolderItem::Folder { account_index, full_path, .. }) = self.folder_items.get(self.selected_folder_item_idx).cloned() else {
+            return;
+        };
+        let Some(account) = self.config.accounts.get_mut(account_index) else {
+            return;
+        };
+        let mut options = account.folder_options.get(&full_path).cloned().unwrap_or_default();
+        change(&mut options);
+        account.set_folder_options(&full_path, options.clone());
+        let account = account.clone();
+        if let Some(account_data) = self.accounts.get_mut(&account_index) {
+            account_data.account = account.clone();
+        }
+        if let Ok(mut folders) = self.manual_sync_folders.lock() {
+            *folders = self.config.manual_sync_folders();
+        }
+
+        let notify = if options.never_notify { "never" } else { "always" };
+        let sync = if options.no_auto_sync { "opened" } else { "background" };
+        let saved = match &self.config_path {
+            Some(path) => self.config.save(path).map_err(|e| e.to_string()),
+            None => Err("no config file".to_string()),
+        };
+        match saved {
+            Ok(()) => self.show_info(&tr_args("msg-folder-options", &[("folder", &full_path), ("notify", &notify), ("sync", &sync)])),
+            Err(e) => self.show_error(&tr_args(
+                "msg-folder-options-unsaved",
+                &[("folder", &full_path), ("notify", &notify), ("sync", &sync), ("error", &e)],
+            )),
+        }
+    }
+
+    fn handle_settings_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                Ok(())
+            }
+            KeyCode::Char('d') => {
+                self.selected_dictionary_idx = 0;
+                self.mode = AppMode::Dictionary;
+                Ok(())
+            }
+            KeyCode::Char('l') => {
+                self.toggle_list_density();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Switch the email list between one-line rows and two-line rows with a preview, and
+    /// keep the choice in the config file
+    fn toggle_list_density(&mut self) {
+        self.config.ui.density = self.config.ui.density.toggled();
+        let density = match self.config.ui.density {
+            crate::config::ListDensity::Compact => "compact",
+            crate::config::ListDensity::Comfortable => "comfortable",
+        };
+        let saved = match &self.config_path {
+            Some(path) => self.config.save(path).map_err(|e| e.to_string()),
+            None => Err("no config file".to_string()),
+        };
+        match saved {
+            Ok(()) => self.show_info(&tr_args("msg-density", &[("density", &density)])),
+            Err(e) => self.show_error(&tr_args("msg-density-unsaved", &[("density", &density), ("error", &e)])),
+        }
+    }
+
+    /// Show everyone the current account has corresponded with, most recent first
+    fn open_people_view(&mut self) -> AppResult<()> {
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+
+        match database.get_contacts(&account_email) {
+            Ok(contacts) => {
+                debug_log(&format!("Loaded {} contacts for {}", contacts.len(), account_email));
+                self.contacts = contacts;
+                self.selected_contact_idx = 0;
+                self.mode = AppMode::People;
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-contacts-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Replace the email list with all correspondence with the selected contact
+    fn open_contact_thread(&mut self) -> AppResult<()> {
+        let Some(contact) = self.contacts.get(self.selected_contact_idx) else {
+            return Ok(());
+        };
+        let address = contact.address.clone();
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+
+        match database.get_emails_with_contact(&account_email, &address) {
+            Ok(emails) => {
+                debug_log(&format!("Loaded {} emails exchanged with {}", emails.len(), address));
+                self.emails = emails;
+                // Start at the latest message; the thread reads oldest to newest
+                self.selected_email_idx = self.emails.len().checked_sub(1);
+                self.contact_filter = Some(address);
+                self.read_later_view = false;
+                self.vip_view = false;
+                self.notifications_view = false;
+                self.search_query = None;
+                self.mode = AppMode::Normal;
+                self.focus = FocusPanel::EmailList;
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-conversation-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Switch the folder list between date order, high priority first and high priority only
+    fn cycle_importance_view(&mut self) {
+        self.importance_view = self.importance_view.next();
+        if !self.cross_folder_list() {
+            if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
+                let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+                self.replace_email_list(shown);
+            }
+        }
+        self.show_info(&tr(match self.importance_view {
+            ImportanceView::All => "msg-importance-all",
+            ImportanceView::HighFirst => "msg-importance-high-first",
+            ImportanceView::HighOnly => "msg-importance-high-only",
+        }));
+    }
+
+    /// Switch the folder list between one message per row and conversations
+    fn toggle_threads(&mut self) {
+        self.thread_view.enabled = !self.thread_view.enabled;
+        if !self.cross_folder_list() {
+            if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
+                let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+                self.replace_email_list(shown);
+            }
+        }
+        if self.thread_view.enabled {
+            let folder = self.selected_folder.clone();
+            self.request_server_threads(self.current_account_idx, &folder);
+            self.show_info(&tr("msg-showing-threads"));
+        } else {
+            self.show_info(&tr("msg-showing-messages"));
+        }
+    }
+
+    /// Ask the server to thread `folder` in the background when threading is on. Until
+    /// the threads arrive, and for servers without THREAD, the list is grouped from the
+    /// message headers.
+    fn request_server_threads(&mut self, account_idx: usize, folder: &str) {
+        let Some(account_data) = self.accounts.get(&account_idx) else {
+            return;
+        };
+        self.thread_view.keep_server_threads_of(&account_data.account.email);
+        if !self.thread_view.enabled {
+            return;
+        }
+        let Some(client) = account_data.email_client.clone() else {
+            return;
+        };
+        let folder = folder.to_string();
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let threads = client.threads(&folder).unwrap_or_else(|e| {
+                debug_log(&format!("Failed to thread {}: {}", folder, e));
+                None
+            });
+            let _ = sender.send(AppEvent::ThreadsFetched { account_idx, folder, threads });
+        });
+    }
+
+    /// Regroup the list by the server's threads, if they are for the folder on screen
+    fn threads_fetched(&mut self, account_idx: usize, folder: &str, threads: Option<Vec<Vec<u32>>>) {
+        if account_idx != self.current_account_idx || folder != self.selected_folder || !self.thread_view.enabled {
+            return;
+        }
+        let Some(account_data) = self.accounts.get(&account_idx) else {
+            return;
+        };
+        self.thread_view.set_server_threads(&account_data.account.email, folder, threads);
+        if !self.cross_folder_list() {
+            let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+            self.replace_email_list(shown);
+        }
+    }
+
+    /// Whether the list shows messages from several folders (a contact thread, the
+    /// read-later queue, the VIP or notifications folder or search results) rather than the selected folder
+    pub fn cross_folder_list(&self) -> bool {
+        self.contact_filter.is_some()
+            || self.read_later_view
+            || self.vip_view
+            || self.notifications_view
+            || self.search_query.is_some()
+    }
+
+    /// Keys while the search bar is open: Enter searches, Tab completes a filter name
+    fn handle_search_input(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc => self.search_editing = false,
+            KeyCode::Enter => {
+                self.search_editing = false;
+                let text = self.search_text.trim().to_string();
+                if text.is_empty() {
+                    if self.search_query.is_some() {
+                        self.close_contact_thread()?;
+                    }
+                } else {
+                    self.run_search(&text)?;
+                }
+            }
+            KeyCode::Tab => self.search_text = crate::search::complete(&self.search_text),
+            KeyCode::Backspace => {
+                self.search_text.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => self.search_text.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Replace the email list with the stored messages matching the query, newest first.
+    /// `account:` searches (and switches to) the first account whose address or name
+    /// contains the text; otherwise the current account is searched.
+    fn run_search(&mut self, text: &str) -> AppResult<()> {
+        let query = match crate::search::SearchQuery::parse(text) {
+            Ok(query) => query,
+            Err(e) => {
+                self.show_error(&tr_args("msg-invalid-search", &[("error", &e)]));
+                return Ok(());
+            }
+        };
+
+        if let Some(wanted) = &query.account {
+            let found = self.config.accounts.iter().position(|account| {
+                account.email.to_lowercase().contains(wanted.as_str()) || account.name.to_lowercase().contains(wanted.as_str())
+            });
+            match found {
+                Some(idx) if idx != self.current_account_idx => self.switch_to_account(idx)?,
+                Some(_) => {}
+                None => {
+                    self.show_error(&tr_args("msg-no-account-matches", &[("account", &wanted)]));
+                    return Ok(());
+                }
+            }
+        }
+
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+        match database.search_emails(&account_email, &query) {
+            Ok(emails) => {
+                debug_log(&format!("Search '{}' matched {} emails", text, emails.len()));
+                self.show_info(&tr_args("msg-search-matches", &[("count", &emails.len())]));
+                self.emails = emails;
+                self.selected_email_idx = if self.emails.is_empty() { None } else { Some(0) };
+                self.contact_filter = None;
+                self.read_later_view = false;
+                self.vip_view = false;
+                self.notifications_view = false;
+                self.search_query = Some(text.to_string());
+                self.focus = FocusPanel::EmailList;
+                if query.server {
+                    self.request_server_search(text, &query);
+                }
+            }
+            Err(e) => self.show_error(&tr_args("msg-search-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Search the server in the background for messages of folders that are not fully
+    /// synced, to add to the results of the search `text` when they arrive
+    fn request_server_search(&mut self, text: &str, query: &crate::search::SearchQuery) {
+        let Some(criteria) = query.to_imap() else {
+            self.show_error(&tr("msg-tag-search-local"));
+            return;
+        };
+        let account_idx = self.current_account_idx;
+        let Some(account_data) = self.accounts.get(&account_idx) else {
+            return;
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-search-local-only"));
+            return;
+        };
+        let folders = match &query.folder {
+            Some(wanted) => vec![account_data.folders.iter().find(|f| f.eq_ignore_ascii_case(wanted)).unwrap_or(wanted).clone()],
+            None => account_data.folders.clone(),
+        };
+        let count = self.emails.len();
+        self.show_info(&tr_args("msg-search-server", &[("count", &count)]));
+        let query = text.to_string();
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let emails = client.search_server(&folders, &criteria).map_err(|e| e.to_string());
+            if let Err(e) = &emails {
+                debug_log(&format!("Server search for '{}' failed: {}", query, e));
+            }
+            let _ = sender.send(AppEvent::ServerSearched { account_idx, query, emails });
+        });
+    }
+
+    /// Merge what the server found into the results of the search it was started for,
+    /// if they are still on screen
+    fn server_searched(&mut self, account_idx: usize, query: &str, emails: Result<Vec<Email>, String>) {
+        if account_idx != self.current_account_idx || self.search_query.as_deref() != Some(query) {
+            return;
+        }
+        let found = match emails {
+            Ok(found) => found,
+            Err(e) => {
+                self.show_error(&tr_args("msg-server-search-failed", &[("error", &e)]));
+                return;
+            }
+        };
+        let mut merged = self.emails.clone();
+        let mut added = 0;
+        for email in found {
+            if !merged.iter().any(|known| known.folder == email.folder && known.id == email.id) {
+                merged.push(email);
+                added += 1;
+            }
+        }
+        merged.sort_by_key(|email| std::cmp::Reverse(email.date));
+        self.replace_email_list(merged);
+        self.show_info(&tr_args("msg-search-server-matches", &[("count", &self.emails.len()), ("added", &added)]));
+    }
+
+    /// Replace the email list with the messages tagged to read later, oldest first
+    fn open_read_later(&mut self) -> AppResult<()> {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+
+        match database.get_emails_with_flag(&account_email, crate::email::READ_LATER_KEYWORD) {
+            Ok(emails) => {
+                debug_log(&format!("Loaded {} emails to read later", emails.len()));
+                self.emails = emails;
+                self.selected_email_idx = if self.emails.is_empty() { None } else { Some(0) };
+                self.contact_filter = None;
+                self.read_later_view = true;
+                self.vip_view = false;
+                self.notifications_view = false;
+                self.search_query = None;
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-read-later-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Replace the email list with the messages from VIP senders, newest first
+    fn open_vip_folder(&mut self) -> AppResult<()> {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+
+        match database.get_emails_from_vips(&account_email) {
+            Ok(emails) => {
+                debug_log(&format!("Loaded {} emails from VIPs", emails.len()));
+                self.emails = emails;
+                self.selected_email_idx = if self.emails.is_empty() { None } else { Some(0) };
+                self.contact_filter = None;
+                self.read_later_view = false;
+                self.vip_view = true;
+                self.notifications_view = false;
+                self.search_query = None;
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-vip-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Replace the email list with the GitHub and GitLab notifications, grouped by
+    /// repository
+    fn open_notifications_folder(&mut self) -> AppResult<()> {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+
+        match database.get_forge_notifications(&account_email) {
+            Ok(emails) => {
+                let emails = crate::forge::group_by_repository(emails);
+                debug_log(&format!("Loaded {} GitHub/GitLab notifications", emails.len()));
+                self.emails = emails;
+                self.selected_email_idx = if self.emails.is_empty() { None } else { Some(0) };
+                self.contact_filter = None;
+                self.read_later_view = false;
+                self.vip_view = false;
+                self.notifications_view = true;
+                self.search_query = None;
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-notifications-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Queue the selected message to read later, or in the queue mark it done. Only the
+    /// `$ReadLater` keyword changes; the message stays read or unread as it was.
+    fn toggle_read_later(&mut self) -> AppResult<()> {
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-read-later"));
+            return Ok(());
+        }
+        let Some(idx) = self.selected_email_idx.filter(|&idx| idx < self.emails.len()) else {
+            self.show_error(&tr("msg-no-email-selected"));
+            return Ok(());
+        };
+        let email = self.emails[idx].clone();
+        let keyword = crate::email::READ_LATER_KEYWORD;
+        let set = !email.flags.iter().any(|f| f == keyword);
+
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(account_data) = self.accounts.get(&self.current_account_idx) else {
+            self.show_error(&tr("msg-account-not-found"));
+            return Ok(());
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        let account_email = account_data.account.email.clone();
+
+        if let Err(e) = client.set_flag(&email, keyword, set) {
+            if crate::auth::is_auth_error(&e) {
+                self.show_auth_problem(self.current_account_idx, &e);
+            } else {
+                self.show_error(&tr_args("msg-read-later-failed", &[("error", &e)]));
+            }
+            return Ok(());
+        }
+        self.audit_email(&account_email, &email, &format!("flag {}{}", if set { '+' } else { '-' }, keyword));
+
+        let update = |emails: &mut Vec<Email>| {
+            if let Some(e) = emails.iter_mut().find(|e| e.id == email.id && e.folder == email.folder) {
+                e.flags.retain(|f| f != keyword);
+                if set {
+                    e.flags.push(keyword.to_string());
+                }
+            }
+        };
+        update(&mut self.emails);
+        if let Some(account_data) = self.accounts.get_mut(&self.current_account_idx) {
+            update(&mut account_data.emails);
+        }
+
+        if set {
+            self.show_info(&tr_args("msg-read-later-saved", &[("subject", &email.subject)]));
+        } else if self.read_later_view {
+            self.read_later_done += 1;
+            self.emails.remove(idx);
+            if idx >= self.emails.len() {
+                self.selected_email_idx = self.emails.len().checked_sub(1);
+            }
+            self.show_info(&tr_args("msg-read-later-done", &[("subject", &email.subject), ("count", &self.emails.len())]));
+        } else {
+            self.show_info(&tr_args("msg-read-later-removed", &[("subject", &email.subject)]));
+        }
+        Ok(())
+    }
+
+    /// Leave a contact thread, the read-later queue, the VIP folder or search results and go back to the
+    /// folder that was open
+    fn close_contact_thread(&mut self) -> AppResult<()> {
+        self.contact_filter = None;
+        self.read_later_view = false;
+        self.vip_view = false;
+        self.notifications_view = false;
+        self.search_query = None;
+        if let Some(account_data) = self.accounts.get(&self.current_account_idx) {
+            self.emails = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+        }
+        self.selected_email_idx = if self.emails.is_empty() { None } else { Some(0) };
+        Ok(())
+    }
+
+    fn handle_people_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.mode = AppMode::Normal;
+                Ok(())
+            }
+            KeyCode::Up => {
+                self.selected_contact_idx = self.selected_contact_idx.saturating_sub(1);
+                Ok(())
+            }
+            KeyCode::Down => {
+                if self.selected_contact_idx + 1 < self.contacts.len() {
+                    self.selected_contact_idx += 1;
+                }
+                Ok(())
+            }
+            KeyCode::Enter => self.open_contact_thread(),
+            KeyCode::Char('v') => self.toggle_vip(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Group the shown folder by sender for the cleanup assistant
+    fn open_cleanup(&mut self) -> AppResult<()> {
+        let Some((account_idx, folder)) = self.get_selected_folder_info() else {
+            self.show_error(&tr("msg-cleanup-select-folder"));
+            return Ok(());
+        };
+        self.cleanup_folder = Some((account_idx, folder));
+        self.cleanup_marked.clear();
+        self.cleanup_confirm = None;
+        self.selected_cleanup_idx = 0;
+        self.load_cleanup_groups()?;
+        self.mode = AppMode::Cleanup;
+        Ok(())
+    }
+
+    fn load_cleanup_groups(&mut self) -> AppResult<()> {
+        let Some((account_idx, folder)) = self.cleanup_folder.clone() else {
+            return Ok(());
+        };
+        let account_email = self.config.accounts[account_idx].email.clone();
+        let database = self.open_account_database(account_idx)?;
+        match database.get_all_emails(&account_email, &folder) {
+            Ok(emails) => {
+                self.cleanup_groups = crate::cleanup::group(&emails, self.cleanup_by);
+                self.cleanup_marked.retain(|key| self.cleanup_groups.iter().any(|group| group.key == *key));
+                self.selected_cleanup_idx = self.selected_cleanup_idx.min(self.cleanup_groups.len().saturating_sub(1));
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-folder-failed", &[("folder", &folder), ("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// The marked groups, or the selected one when nothing is marked
+    fn cleanup_targets(&self) -> Vec<&crate::cleanup::SenderGroup> {
+        if self.cleanup_marked.is_empty() {
+            self.cleanup_groups.get(self.selected_cleanup_idx).into_iter().collect()
+        } else {
+            self.cleanup_groups.iter().filter(|group| self.cleanup_marked.contains(&group.key)).collect()
+        }
+    }
+
+    fn handle_cleanup_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        use crate::cleanup::CleanupAction;
+
+        if let Some(action) = self.cleanup_confirm.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter) {
+                self.run_cleanup(action);
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            KeyCode::Up => self.selected_cleanup_idx = self.selected_cleanup_idx.saturating_sub(1),
+            KeyCode::Down if self.selected_cleanup_idx + 1 < self.cleanup_groups.len() => {
+                self.selected_cleanup_idx += 1;
+            }
+            KeyCode::Char(' ') => {
+                if let Some(group) = self.cleanup_groups.get(self.selected_cleanup_idx) {
+                    if !self.cleanup_marked.remove(&group.key) {
+                        self.cleanup_marked.insert(group.key.clone());
+                    }
+                }
+                if self.selected_cleanup_idx + 1 < self.cleanup_groups.len() {
+                    self.selected_cleanup_idx += 1;
+                }
+            }
+            KeyCode::Char('g') => {
+                self.cleanup_by = self.cleanup_by.toggle();
+                self.cleanup_marked.clear();
+                self.selected_cleanup_idx = 0;
+                self.load_cleanup_groups()?;
+            }
+            KeyCode::Char('d') | KeyCode::Char('a') if !self.cleanup_targets().is_empty() => {
+                if self.cleanup_running {
+                    self.show_info(&tr("msg-cleanup-running"));
+                } else if crate::email::is_read_only() {
+                    self.show_error(&tr("msg-read-only-cleanup"));
+                } else {
+                    let action = if key.code == KeyCode::Char('d') { CleanupAction::Delete } else { CleanupAction::Archive };
+                    self.cleanup_confirm = Some(action);
+                }
+            }
+            KeyCode::Char('r') => self.add_cleanup_rules(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Delete or archive all mail of the chosen groups in the background
+    fn run_cleanup(&mut self, action: crate::cleanup::CleanupAction) {
+        let Some((account_idx, folder)) = self.cleanup_folder.clone() else {
+            return;
+        };
+        let Some(client) = self.accounts.get(&account_idx).and_then(|data| data.email_client.clone()) else {
+            self.show_error(&tr("msg-client-not-initialized-here"));
+            return;
+        };
+        let target = match action {
+            crate::cleanup::CleanupAction::Delete => None,
+            crate::cleanup::CleanupAction::Archive => Some(self.config.accounts[account_idx].archive_folder(&self.config.retention.archive_folder)),
+        };
+        if target.as_ref() == Some(&folder) {
+            self.show_info(&tr_args("msg-already-in", &[("folder", &folder)]));
+            return;
+        }
+        let groups: Vec<(String, Vec<u32>)> = self
+            .cleanup_targets()
+            .into_iter()
+            .map(|group| (group.key.clone(), group.uids.clone()))
+            .collect();
+        let label = match &target {
+            Some(target) => tr_args("msg-cleanup-move", &[("folder", target)]),
+            None => tr("msg-cleanup-delete"),
+        };
+        self.cleanup_running = true;
+        self.show_info(&tr_args("msg-cleanup-started", &[("count", &groups.len())]));
+
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let mut done = Vec::new();
+            let mut error = None;
+            for (key, uids) in groups {
+                match client.remove_messages(&folder, &uids, target.as_deref()) {
+                    Ok(count) => done.push((key, count)),
+                    Err(e) => {
+                        error = Some(e.to_string());
+                        break;
+                    }
+                }
+            }
+            let _ = sender.send(AppEvent::CleanupFinished { account_idx, folder, action: label, done, error });
+        });
+    }
+
+    fn cleanup_finished(
+        &mut self,
+        account_idx: usize,
+        folder: &str,
+        action: &str,
+        done: Vec<(String, usize)>,
+        error: Option<String>,
+    ) -> AppResult<()> {
+        self.cleanup_running = false;
+        let account_email = self.config.accounts[account_idx].email.clone();
+        for (key, count) in &done {
+            crate::audit::record(
+                &self.database,
+                &account_email,
+                folder,
+                None,
+                action,
+                crate::audit::Origin::Ui,
+                &format!("cleanup: {} message(s) from {}", count, key),
+            );
+            self.cleanup_marked.remove(key);
+        }
+        let total: usize = done.iter().map(|(_, count)| count).sum();
+        match error {
+            Some(e) => self.show_error(&tr_args("msg-cleanup-stopped", &[("count", &total), ("error", &e)])),
+            None => self.show_info(&tr_args("msg-cleanup-done", &[("action", &action), ("count", &total)])),
+        }
+
+        if matches!(self.mode, AppMode::Cleanup) {
+            self.load_cleanup_groups()?;
+        }
+        self.reload_cached_folder(&account_email, folder)
+    }
+
+    /// Add rules archiving future mail of the chosen groups in this folder
+    fn add_cleanup_rules(&mut self) {
+        let Some((account_idx, folder)) = self.cleanup_folder.clone() else {
+            return;
+        };
+        let account_email = self.config.accounts[account_idx].email.clone();
+        let archive_folder = self.config.accounts[account_idx].archive_folder(&self.config.retention.archive_folder);
+        let action = crate::config::RuleAction::Move { folder: archive_folder.clone() };
+        let rules: Vec<crate::config::FilterRule> = self
+            .cleanup_targets()
+            .into_iter()
+            .map(|group| crate::cleanup::rule_for(group, self.cleanup_by, &account_email, &folder, action.clone()))
+            .filter(|rule| !self.config.rules.iter().any(|existing| existing.name == rule.name))
+            .collect();
+        if rules.is_empty() {
+            self.show_info(&tr("msg-cleanup-rules-exist"));
+            return;
+        }
+
+        let added = rules.len();
+        self.config.rules.extend(rules);
+        let saved = match &self.config_path {
+            Some(path) => self.config.save(path).map_err(|e| e.to_string()),
+            None => Err("no config file".to_string()),
+        };
+        match saved {
+            Ok(()) => self.show_info(&tr_args("msg-cleanup-rules-added", &[("count", &added), ("folder", &archive_folder)])),
+            Err(e) => self.show_error(&tr_args("msg-cleanup-rules-unsaved", &[("count", &added), ("error", &e)])),
+        }
+    }
+
+    /// List every attachment of the current account, across all folders
+    fn open_attachment_browser(&mut self) -> AppResult<()> {
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+
+        match database.list_attachments(&account_email) {
+            Ok(entries) => {
+                debug_log(&format!("Loaded {} indexed attachments for {}", entries.len(), account_email));
+                self.attachment_index = entries;
+                self.attachment_preview = None;
+                self.attachment_filter_editing = false;
+                self.apply_attachment_filter();
+                self.mode = AppMode::Attachments;
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-attachments-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    fn apply_attachment_filter(&mut self) {
+        let filter = match crate::attachment_index::AttachmentFilter::parse(&self.attachment_filter_text) {
+            Ok(filter) => filter,
+            Err(e) => {
+                self.show_error(&e);
+                return;
+            }
+        };
+
+        self.attachment_index_visible = self
+            .attachment_index
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| filter.matches(entry))
+            .map(|(i, _)| i)
+            .collect();
+        self.attachment_index_selected = 0;
+        self.attachment_preview = None;
+    }
+
+    pub fn selected_index_attachment(&self) -> Option<&crate::database::AttachmentIndexEntry> {
+        self.attachment_index_visible
+            .get(self.attachment_index_selected)
+            .and_then(|&i| self.attachment_index.get(i))
+    }
+
+    /// Load the selected attachment from the cache, reporting why it can't be used
+    fn load_selected_index_attachment(&mut self) -> Option<crate::email::EmailAttachment> {
+        let entry = self.selected_index_attachment()?.clone();
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+
+        let loaded = self
+            .open_account_database(self.current_account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|database| {
+                database
+                    .load_attachment(&account_email, &entry.folder, entry.email_uid, &entry.filename)
+                    .map_err(|e| e.to_string())
+            });
+
+        match loaded {
+            Ok(Some(attachment)) if attachment.is_available() => Some(attachment),
+            Ok(Some(_)) => {
+                self.show_error(&tr("msg-attachment-not-downloaded-open"));
+                None
+            }
+            Ok(None) => {
+                self.show_error(&tr("msg-attachment-evicted"));
+                None
+            }
+            Err(e) => {
+                self.show_error(&tr_args("msg-load-attachment-failed", &[("error", &e)]));
+                None
+            }
+        }
+    }
+
+    fn preview_index_attachment(&mut self) -> AppResult<()> {
+        let Some(attachment) = self.load_selected_index_attachment() else {
+            return Ok(());
+        };
+
+        if !crate::attachment_index::is_previewable(&attachment.content_type, &attachment.filename) {
+            self.attachment_preview = Some(format!(
+                "No preview available for {} ({}). Press 's' to save it.",
+                attachment.filename, attachment.content_type
+            ));
+            return Ok(());
+        }
+
+        match attachment.load_data() {
+            Ok(data) => {
+                let shown = &data[..data.len().min(crate::attachment_index::PREVIEW_LIMIT)];
+                let mut text = String::from_utf8_lossy(shown).to_string();
+                if data.len() > shown.len() {
+                    text.push_str("\n\n[preview truncated]");
+                }
+                self.attachment_preview = Some(text);
+            }
+            Err(e) => self.show_error(&tr_args("msg-read-attachment-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    fn save_index_attachment(&mut self) -> AppResult<()> {
+        let Some(attachment) = self.load_selected_index_attachment() else {
+            return Ok(());
+        };
+        let data = match attachment.load_data() {
+            Ok(data) => data,
+            Err(e) => {
+                self.show_error(&tr_args("msg-read-attachment-failed", &[("error", &e)]));
+                return Ok(());
+            }
+        };
+
+        self.file_browser_save_mode = true;
+        self.file_browser_save_filename = attachment.filename;
+        self.file_browser_save_data = data;
+        self.file_browser_mode = true;
+        self.load_file_browser_directory()?;
+        self.file_browser_selected = 0;
+        self.show_info(&tr("msg-save-attachment-hint"));
+        Ok(())
+    }
+
+    fn handle_attachments_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        if self.attachment_filter_editing {
+            match key.code {
+                KeyCode::Enter => {
+                    self.attachment_filter_editing = false;
+                    self.apply_attachment_filter();
+                }
+                KeyCode::Esc => self.attachment_filter_editing = false,
+                KeyCode::Backspace => {
+                    self.attachment_filter_text.pop();
+                }
+                KeyCode::Char(c) => self.attachment_filter_text.push(c),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Esc if self.attachment_preview.is_some() => {
+                self.attachment_preview = None;
+                Ok(())
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.mode = AppMode::Normal;
+                Ok(())
+            }
+            KeyCode::Char('/') => {
+                self.attachment_filter_editing = true;
+                Ok(())
+            }
+            KeyCode::Up => {
+                self.attachment_index_selected = self.attachment_index_selected.saturating_sub(1);
+                self.attachment_preview = None;
+                Ok(())
+            }
+            KeyCode::Down => {
+                if self.attachment_index_selected + 1 < self.attachment_index_visible.len() {
+                    self.attachment_index_selected += 1;
+                }
+                self.attachment_preview = None;
+                Ok(())
+            }
+            KeyCode::Enter => self.preview_index_attachment(),
+            KeyCode::Char('s') => self.save_index_attachment(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Evaluate the filter rules and auto-archive policy against stored mail of the
+    /// current account and show what they would have done
+    fn run_rules_dry_run(&mut self) -> AppResult<()> {
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+
+        // Auto-archive acts on mail that was already old when the window started
+        let lookback = self.dry_run_days + self.config.retention.archive_after_days.unwrap_or(0);
+        let now = Local::now();
+        let since = (now - chrono::Duration::days(lookback as i64)).timestamp();
+
+        match database.get_emails_since(&account_email, since) {
+            Ok(emails) => {
+                self.dry_run_report = crate::rules::dry_run(&self.config, &account_email, &emails, now, self.dry_run_days);
+                self.dry_run_selected = 0;
+                self.mode = AppMode::RulesReport;
+                debug_log(&format!(
+                    "Dry run over {} emails of {} produced {} actions",
+                    emails.len(), account_email, self.dry_run_report.len()
+                ));
+            }
+            Err(e) => self.show_error(&tr_args("msg-dry-run-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Run the quick action bound to a number key on the selected email
+    fn run_quick_action(&mut self, key: u8) -> AppResult<()> {
+        let Some(quick) = self.config.quick_actions.iter().find(|q| q.key == key).cloned() else {
+            self.show_info(&tr_args("msg-no-quick-action", &[("key", &key)]));
+            return Ok(());
+        };
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-quick-actions"));
+            return Ok(());
+        }
+        let Some(idx) = self.selected_email_idx.filter(|&idx| idx < self.emails.len()) else {
+            self.show_error(&tr("msg-no-email-selected"));
+            return Ok(());
+        };
+        let email = self.emails[idx].clone();
+        let archive_folder = self.archive_folder();
+        let label = quick.label(&archive_folder);
+
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(account_data) = self.accounts.get(&self.current_account_idx) else {
+            self.show_error(&tr("msg-account-not-found"));
+            return Ok(());
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        let account_email = account_data.account.email.clone();
+
+        // Either the folder the message is moved to, or the flag that is set or cleared
+        let flagged = email.flags.iter().any(|f| f == "\\Flagged");
+        let flag_change = match &quick.action {
+            QuickActionKind::MarkRead => Some(("\\Seen", true)),
+            QuickActionKind::MarkUnread => Some(("\\Seen", false)),
+            QuickActionKind::ToggleFlag => Some(("\\Flagged", !flagged)),
+            _ => None,
+        };
+        let (result, action) = match (&quick.action, flag_change) {
+            (_, Some((flag, set))) => (
+                client.set_flag(&email, flag, set),
+                format!("flag {}{}", if set { '+' } else { '-' }, flag),
+            ),
+            (QuickActionKind::Delete, None) => (client.delete_email(&email), "delete".to_string()),
+            (kind, None) => {
+                let target = match kind {
+                    QuickActionKind::Move { folder } | QuickActionKind::Spam { folder: Some(folder) } => Some(folder.clone()),
+                    QuickActionKind::Spam { folder: None } => account_data.account.junk_folder(&account_data.folders).cloned(),
+                    _ => Some(archive_folder),
+                };
+                let Some(target) = target else {
+                    self.show_error(&tr("msg-no-spam-folder"));
+                    return Ok(());
+                };
+                if target == email.folder {
+                    self.show_info(&tr_args("msg-already-in", &[("folder", &target)]));
+                    return Ok(());
+                }
+                (client.move_email(&email, &target), format!("move to {}", target))
+            }
+        };
+
+        if let Err(e) = result {
+            if crate::auth::is_auth_error(&e) {
+                self.show_auth_problem(self.current_account_idx, &e);
+            } else {
+                self.show_error(&tr_args("msg-quick-action-failed", &[("action", &label), ("error", &e)]));
+            }
+            return Ok(());
+        }
+        self.audit_email(&account_email, &email, &action);
+
+        let update = |emails: &mut Vec<Email>| match flag_change {
+            Some((flag, set)) => {
+                if let Some(e) = emails.iter_mut().find(|e| e.id == email.id) {
+                    e.flags.retain(|f| f != flag);
+                    if set {
+                        e.flags.push(flag.to_string());
+                    }
+                    e.seen = e.flags.iter().any(|f| f == "\\Seen");
+                }
+            }
+            None => emails.retain(|e| e.id != email.id),
+        };
+        update(&mut self.emails);
+        if let Some(account_data) = self.accounts.get_mut(&self.current_account_idx) {
+            update(&mut account_data.emails);
+        }
+        if flag_change.is_none() && idx >= self.emails.len() {
+            self.selected_email_idx = self.emails.len().checked_sub(1);
+        }
+
+        self.show_info(&tr_args("msg-quick-action-done", &[("action", &label), ("subject", &email.subject)]));
+        Ok(())
+    }
+
+    /// Show the diagnostics screen with the audit log
+    fn open_diagnostics(&mut self) -> AppResult<()> {
+        match self.database.get_audit_log(None, Some(AUDIT_LOG_ROWS)) {
+            Ok(entries) => {
+                self.audit_entries = entries;
+                self.audit_selected = 0;
+                self.pending_operations = self.database.get_pending_operations().map(|ops| ops.len()).unwrap_or(0);
+                self.database_size = self.database.get_database_size().ok();
+                self.mode = AppMode::Diagnostics;
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-audit-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Write the whole audit log as CSV to the Downloads folder
+    fn export_audit_log(&mut self) {
+        let entries = match self.database.get_audit_log(None, None) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.show_error(&tr_args("msg-load-audit-failed", &[("error", &e)]));
+                return;
+            }
+        };
+        let downloads_dir = std::env::var("HOME")
+            .map(|home| std::path::PathBuf::from(format!("{}/Downloads", home)))
+            .unwrap_or_else(|_| std::path::PathBuf::from("./downloads"));
+        let path = downloads_dir.join(format!("tuimail-audit-{}.csv", Local::now().format("%Y%m%d-%H%M%S")));
+
+        let result = std::fs::create_dir_all(&downloads_dir)
+            .and_then(|_| std::fs::write(&path, crate::audit::to_csv(&entries)));
+        match result {
+            Ok(()) => self.show_info(&tr_args("msg-audit-exported", &[("count", &entries.len()), ("path", &path.display())])),
+            Err(e) => self.show_error(&tr_args("msg-audit-export-failed", &[("error", &e)])),
+        }
+    }
+
+    fn handle_diagnostics_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            KeyCode::Up => self.audit_selected = self.audit_selected.saturating_sub(1),
+            KeyCode::Down if self.audit_selected + 1 < self.audit_entries.len() => self.audit_selected += 1,
+            KeyCode::Char('e') => self.export_audit_log(),
+            KeyCode::Char('r') => return self.open_diagnostics(),
+            KeyCode::Char('c') => self.check_capabilities(self.current_account_idx),
+            KeyCode::Char('i') if std::env::var("EMAIL_DEBUG").is_ok() => self.open_imap_console(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Show what the servers of an account support, asking them in the background
+    fn check_capabilities(&mut self, account_idx: usize) {
+        let Some(account) = self.config.accounts.get(account_idx).cloned() else {
+            return;
+        };
+        self.capabilities = Some((account_idx, None));
+        self.mode = AppMode::Capabilities;
+
+        let client = EmailClient::new(account.clone(), self.credentials.clone());
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let capabilities = crate::capabilities::ServerCapabilities {
+                imap: client.imap_capabilities().map_err(|e| e.to_string()),
+                smtp: crate::capabilities::smtp_extensions(&account).map_err(|e| e.to_string()),
+            };
+            let _ = sender.send(AppEvent::CapabilitiesChecked { account_idx, capabilities });
+        });
+    }
+
+    /// Open a session of the current account for typing raw IMAP commands
+    fn open_imap_console(&mut self) {
+        let Some(account) = self.config.accounts.get(self.current_account_idx).cloned() else {
+            return;
+        };
+        let (commands, receiver) = std::sync::mpsc::channel();
+        self.console_lines = vec![format!("Connecting to {}:{} as {}...", account.imap_server, account.imap_port, account.email)];
+        self.console_input.clear();
+        self.console_scroll = 0;
+        self.console_commands = Some(commands);
+        self.mode = AppMode::ImapConsole;
+
+        let client = EmailClient::new(account, self.credentials.clone());
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let output = sender.clone();
+            let result = client.run_console(receiver, move |lines| {
+                let _ = output.send(AppEvent::ConsoleOutput { lines });
+            });
+            if let Err(e) = result {
+                let _ = sender.send(AppEvent::ConsoleOutput { lines: vec![format!("Could not connect: {}", e)] });
+            }
+        });
+    }
+
+    fn handle_imap_console_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc => {
+                // Dropping the sender ends the session
+                self.console_commands = None;
+                self.mode = AppMode::Diagnostics;
+            }
+            KeyCode::Enter if !self.console_input.trim().is_empty() => {
+                let command = std::mem::take(&mut self.console_input);
+                self.console_scroll = 0;
+                let sent = self.console_commands.as_ref().is_some_and(|commands| commands.send(command).is_ok());
+                if !sent {
+                    self.console_lines.push("The session is closed; press Esc and open the console again".to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                self.console_input.pop();
+            }
+            KeyCode::Up => self.console_scroll = (self.console_scroll + 1).min(self.console_lines.len().saturating_sub(1)),
+            KeyCode::Down => self.console_scroll = self.console_scroll.saturating_sub(1),
+            KeyCode::PageUp => self.console_scroll = (self.console_scroll + 10).min(self.console_lines.len().saturating_sub(1)),
+            KeyCode::PageDown => self.console_scroll = self.console_scroll.saturating_sub(10),
+            KeyCode::Char(c) => self.console_input.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_capabilities_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        let shown = self.capabilities.as_ref().map_or(self.current_account_idx, |(idx, _)| *idx);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.capabilities = None;
+                self.mode = AppMode::Diagnostics;
+            }
+            KeyCode::Char('r') => self.check_capabilities(shown),
+            KeyCode::Tab if !self.config.accounts.is_empty() => {
+                self.check_capabilities((shown + 1) % self.config.accounts.len());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_rules_report_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.mode = AppMode::Normal;
+                Ok(())
+            }
+            KeyCode::Up => {
+                self.dry_run_selected = self.dry_run_selected.saturating_sub(1);
+                Ok(())
+            }
+            KeyCode::Down => {
+                if self.dry_run_selected + 1 < self.dry_run_report.len() {
+                    self.dry_run_selected += 1;
+                }
+                Ok(())
+            }
+            KeyCode::Char('+') => {
+                self.dry_run_days = (self.dry_run_days + 7).min(3650);
+                self.run_rules_dry_run()
+            }
+            KeyCode::Char('-') => {
+                self.dry_run_days = self.dry_run_days.saturating_sub(7).max(1);
+                self.run_rules_dry_run()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Explain a refused login in the sign-in help dialog instead of showing the raw error
+    pub fn show_auth_problem(&mut self, account_idx: usize, error: &crate::email::EmailError) {
+        let Some(account) = self.config.accounts.get(account_idx) else {
+            return;
+        };
+        // Accounts without a stored password are already listed by the startup check
+        if self.mode == AppMode::MissingSecrets {
+            debug_log(&format!("Login of {} failed while its passwords are being fixed: {}", account.email, error));
+            return;
+        }
+        let server_message = match error {
+            crate::email::EmailError::AuthError(message) | crate::email::EmailError::SmtpError(message) => message.clone(),
+            other => other.to_string(),
+        };
+        let smtp = matches!(error, crate::email::EmailError::SmtpError(_)) || server_message.contains("SMTP");
+        debug_log(&format!("Login refused for {}: {}", account.email, server_message));
+
+        let return_mode = match self.auth_problem.take() {
+            Some(previous) => previous.return_mode,
+            None if self.mode == AppMode::AuthHelp => AppMode::Normal,
+            None => self.mode,
+        };
+        self.auth_problem = Some(AuthProblem {
+            account_idx,
+            failure: crate::auth::classify(&server_message, account),
+            server_message,
+            smtp,
+            new_password: None,
+            return_mode,
+        });
+        self.mode = AppMode::AuthHelp;
+    }
+
+    /// Make sure every account's passwords can be read from the keyring, and list the
+    /// ones that cannot so they are fixed up front rather than failing one by one later
+    fn check_secrets(&mut self) {
+        let credentials = &self.credentials;
+        self.missing_secrets = crate::auth::check_secrets(&self.config.accounts, |email, kind| {
+            credentials.get_password(email, kind)
+        });
+        if !self.missing_secrets.is_empty() {
+            debug_log(&format!("{} passwords need attention", self.missing_secrets.len()));
+            self.selected_secret_idx = 0;
+            self.secret_input = None;
+            self.mode = AppMode::MissingSecrets;
+        }
+    }
+
+    fn handle_missing_secrets_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        // Typing the password for the selected entry
+        if let Some(password) = self.secret_input.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.secret_input = None,
+                KeyCode::Backspace => {
+                    password.pop();
+                }
+                KeyCode::Char(c) => password.push(c),
+                KeyCode::Enter if !password.is_empty() => {
+                    let password = password.clone();
+                    let Some(secret) = self.missing_secrets.get(self.selected_secret_idx).cloned() else {
+                        return Ok(());
+                    };
+                    let account = self.config.accounts[secret.account_idx].clone();
+                    let stored = if secret.smtp {
+                        account.store_smtp_password(&self.credentials, &password)
+                    } else {
+                        account.store_imap_password(&self.credentials, &password)
+                    };
+                    if let Err(e) = stored {
+                        self.show_error(&tr_args("msg-store-password-failed", &[("error", &e)]));
+                        return Ok(());
+                    }
+                    self.secret_input = None;
+                    self.missing_secrets.remove(self.selected_secret_idx);
+                    self.selected_secret_idx = self.selected_secret_idx.min(self.missing_secrets.len().saturating_sub(1));
+                    let protocol = if secret.smtp { "SMTP" } else { "IMAP" };
+                    self.show_info(&tr_args("msg-password-saved", &[("protocol", &protocol), ("account", &account.email)]));
+                    if !secret.smtp {
+                        self.connect_account_in_background(secret.account_idx);
+                    }
+                    if self.missing_secrets.is_empty() {
+                        self.mode = AppMode::Normal;
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Up => self.selected_secret_idx = self.selected_secret_idx.saturating_sub(1),
+            KeyCode::Down if self.selected_secret_idx + 1 < self.missing_secrets.len() => self.selected_secret_idx += 1,
+            KeyCode::Enter | KeyCode::Char('p') if !self.missing_secrets.is_empty() => self.secret_input = Some(String::new()),
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Try the failed login again: reconnect the account, or resend the composed email
+    fn retry_auth(&mut self) -> AppResult<()> {
+        let Some(problem) = self.auth_problem.take() else {
+            self.mode = AppMode::Normal;
+            return Ok(());
+        };
+        self.mode = problem.return_mode;
+
+        if problem.smtp {
+            self.current_account_idx = problem.account_idx;
+            return self.send_email();
+        }
+        match self.init_account(problem.account_idx) {
+            Ok(()) => {
+                let email = self.config.accounts[problem.account_idx].email.clone();
+                self.show_info(&tr_args("msg-signed-in", &[("account", &email)]));
+            }
+            // A refused login has reopened the dialog; anything else is shown as usual
+            Err(e) if self.auth_problem.is_none() => self.show_error(&tr_args("msg-retry-failed", &[("error", &e)])),
+            Err(_) => {}
+        }
+        Ok(())
+    }
+
+    fn handle_auth_help_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        let Some(problem) = self.auth_problem.as_mut() else {
+            self.mode = AppMode::Normal;
+            return Ok(());
+        };
+
+        // Typing a replacement password
+        if let Some(password) = problem.new_password.as_mut() {
+            match key.code {
+                KeyCode::Esc => problem.new_password = None,
+                KeyCode::Backspace => {
+                    password.pop();
+                }
+                KeyCode::Char(c) => password.push(c),
+                KeyCode::Enter if !password.is_empty() => {
+                    let password = password.clone();
+                    let account = self.config.accounts[problem.account_idx].clone();
+                    let stored = if problem.smtp {
+                        account.store_smtp_password(&self.credentials, &password)
+                    } else {
+                        account.store_imap_password(&self.credentials, &password)
+                    };
+                    if let Err(e) = stored {
+                        self.show_error(&tr_args("msg-store-password-failed", &[("error", &e)]));
+                        return Ok(());
+                    }
+                    return self.retry_auth();
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Char('r') => self.retry_auth(),
+            KeyCode::Char('p') => {
+                problem.new_password = Some(String::new());
+                Ok(())
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.mode = problem.return_mode;
+                self.auth_problem = None;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_help_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                self.mode = AppMode::Normal;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_smtp_fallback_confirm_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                self.mode = AppMode::Normal;
+                if let Some((account_idx, route)) = self.smtp_fallback_offer.take() {
+                    self.use_smtp_route(account_idx, &route);
+                }
+                Ok(())
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                self.smtp_fallback_offer = None;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Make an SMTP route that worked the account's setting and save the config
+    fn use_smtp_route(&mut self, account_idx: usize, route: &crate::transport::SmtpRoute) {
+        let Some(account) = self.config.accounts.get_mut(account_idx) else {
+            return;
+        };
+        account.smtp_port = route.port;
+        account.smtp_security = route.security.clone();
+        account.smtp_ip_family = route.family;
+        let account = account.clone();
+        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+            account_data.account = account.clone();
+            if let Some(client) = account_data.email_client.as_mut() {
+                client.set_account(account.clone());
+            }
+        }
+
+        let saved = match &self.config_path {
+            Some(path) => self.config.save(path).map_err(|e| e.to_string()),
+            None => Err("no config file".to_string()),
+        };
+        match saved {
+            Ok(()) => self.show_info(&tr_args("msg-smtp-route-saved", &[("account", &account.email), ("route", &route)])),
+            Err(e) => self.show_error(&tr_args("msg-smtp-route-unsaved", &[("route", &route), ("error", &e)])),
+        }
+    }
+
+    fn handle_delete_confirm_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                // User confirmed deletion
+                self.mode = AppMode::Normal;
+                self.delete_selected_email()?;
+                Ok(())
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                // User cancelled deletion
+                self.mode = AppMode::Normal;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn show_delete_confirmation(&mut self) {
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-deleting"));
+            return;
+        }
+        self.mode = AppMode::DeleteConfirm;
+    }
+
+    /// Show a new version of the current list without losing the user's place. Rows are
+    /// matched by folder and UID: the selected message stays selected and the rows on
+    /// screen stay put when messages arrive above them. If the selected message is gone,
+    /// the selection stays on the same row.
+    fn replace_email_list(&mut self, emails: Vec<Email>) {
+        let (selected, offset) =
+            carry_list_state(&self.emails, &emails, self.selected_email_idx, self.email_list_offset.get(), &mut self.marked_emails);
+        self.emails = emails;
+        self.selected_email_idx = selected;
+        self.email_list_offset.set(offset);
+    }
+
+    pub fn select_next_email(&mut self) {
+        if self.emails.is_empty() {
+            self.selected_email_idx = None;
+            return;
+        }
+
+        match self.selected_email_idx {
+            Some(idx) => {
+                if idx < self.emails.len().saturating_sub(1) {
+                    self.selected_email_idx = Some(idx + 1);
+                }
+                // If we're at the last email, stay there
+            }
+            None => {
+                // If nothing is selected, select the first email
+                self.selected_email_idx = Some(0);
+            }
+        }
+        self.load_more_emails();
+    }
+
+    /// Messages of the folder on screen not read from the database yet
+    pub fn unloaded_email_count(&self) -> usize {
+        if self.cross_folder_list() {
+            return 0;
+        }
+        let loaded = self.accounts.get(&self.current_account_idx).map_or(0, |data| data.emails.len());
+        self.folder_email_count.saturating_sub(loaded)
+    }
+
+    /// Read the next page of the folder on screen once the selection nears the last row
+    /// loaded, so a large folder is only read as far as the user scrolls
+    fn load_more_emails(&mut self) {
+        let Some(selected) = self.selected_email_idx else {
+            return;
+        };
+        if selected + EMAIL_PAGE_MARGIN < self.emails.len() || self.unloaded_email_count() == 0 {
+            return;
+        }
+        let Some((account_idx, folder)) = self.get_selected_folder_info() else {
+            return;
+        };
+        let Some(account_data) = self.accounts.get(&account_idx).filter(|_| account_idx == self.current_account_idx) else {
+            return;
+        };
+        let account_email = account_data.account.email.clone();
+        let loaded = account_data.emails.len();
+        let page = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.get_emails_paginated(&account_email, &folder, loaded, EMAIL_PAGE_SIZE).map_err(|e| e.to_string()));
+        let page = match page {
+            Ok(page) => page,
+            Err(e) => {
+                debug_log(&format!("Failed to load more emails of {}/{}: {}", account_email, folder, e));
+                return;
+            }
+        };
+        if page.is_empty() {
+            // Messages went away since the folder was counted
+            self.folder_email_count = loaded;
+            return;
+        }
+        if let Some(account_data) = self.accounts.get_mut(&account_idx) {
+            // Mail that arrived meanwhile pushes rows down, so the page may repeat some
+            let known: std::collections::HashSet<String> = account_data.emails.iter().map(|email| email.id.clone()).collect();
+            account_data.emails.extend(page.into_iter().filter(|email| !known.contains(&email.id)));
+            let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+            self.replace_email_list(shown);
+        }
+    }
+
+    pub fn select_prev_email(&mut self) {
+        if self.emails.is_empty() {
+            self.selected_email_idx = None;
+            return;
+        }
+
+        match self.selected_email_idx {
+            Some(idx) => {
+                if idx > 0 {
+                    self.selected_email_idx = Some(idx - 1);
+                }
+                // If we're at the first email, stay there
+            }
+            None => {
+                // If nothing is selected, select the first email
+                self.selected_email_idx = Some(0);
+            }
+        }
+    }
+
+    /// Reply to the open email, first asking for confirmation if it is old or the
+    /// sender is likely unmonitored
+    fn confirm_reply(&mut self, all: bool) -> AppResult<()> {
+        let Some(email) = self.digest_message().or_else(|| self.selected_email_idx.and_then(|idx| self.emails.get(idx))) else {
+            return Ok(());
+        };
+        let config = &self.config.reply_warnings;
+        let mut warnings = crate::reply_check::warnings(email, config, Local::now());
+        let mut recipients = Vec::new();
+        if all {
+            let own_address = &self.config.accounts[self.current_account_idx].email;
+            let (to, cc) = crate::reply_check::reply_all_recipients(email, own_address);
+            let all_recipients: Vec<_> = to.into_iter().chain(cc).collect();
+            let own_domains = self.own_domains();
+            let guard = crate::reply_check::reply_all_warnings(&all_recipients, config, &own_domains);
+            if !guard.is_empty() {
+                warnings.extend(guard);
+                recipients = all_recipients
+                    .into_iter()
+                    .map(|r| {
+                        let outside = !crate::reply_check::is_own_domain(&r.address, &own_domains);
+                        (r, outside)
+                    })
+                    .collect();
+            }
+        }
+        if !warnings.is_empty() {
+            self.reply_warning = Some(crate::reply_check::PendingReply { all, warnings, recipients });
+            self.mode = AppMode::ReplyWarning;
+            return Ok(());
+        }
+        if all {
+            self.reply_all_to_email()
+        } else {
+            self.reply_to_email()
+        }
+    }
+
+    /// The domains replies to all are expected to stay within: `reply_warnings.own_domains`,
+    /// or those of the configured accounts
+    fn own_domains(&self) -> Vec<String> {
+        if !self.config.reply_warnings.own_domains.is_empty() {
+            return self.config.reply_warnings.own_domains.clone();
+        }
+        let mut domains: Vec<String> = self
+            .config
+            .accounts
+            .iter()
+            .filter_map(|account| account.email.rsplit_once('@').map(|(_, domain)| domain.to_lowercase()))
+            .collect();
+        domains.sort();
+        domains.dedup();
+        domains
+    }
+
+    /// List the current account's drafts and sync them with the Drafts folder
+    fn open_drafts(&mut self) -> AppResult<()> {
+        self.load_drafts()?;
+        self.selected_draft_idx = 0;
+        self.mode = AppMode::Drafts;
+        self.sync_drafts_in_background(self.current_account_idx);
+        Ok(())
+    }
+
+    fn load_drafts(&mut self) -> AppResult<()> {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+        match database.get_drafts(&account_email) {
+            Ok(drafts) => {
+                self.drafts = drafts.into_iter().filter(|draft| !draft.deleted).collect();
+                self.selected_draft_idx = self.selected_draft_idx.min(self.drafts.len().saturating_sub(1));
+            }
+            Err(e) => self.show_error(&tr_args("msg-load-drafts-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Sync drafts with the server unless the account is offline or a sync is running
+    fn sync_drafts_in_background(&mut self, account_idx: usize) {
+        if self.drafts_syncing || crate::email::is_read_only() {
+            return;
+        }
+        let Some(account_data) = self.accounts.get(&account_idx) else {
+            return;
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            return;
+        };
+        let folder = account_data
+            .account
+            .drafts_folder(&account_data.folders)
+            .cloned()
+            .unwrap_or_else(|| "Drafts".to_string());
+        self.drafts_syncing = true;
+
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let result = client.sync_drafts(&folder).map_err(|e| e.to_string());
+            let _ = sender.send(AppEvent::DraftsSynced { account_idx, result });
+        });
+    }
+
+    fn drafts_synced(&mut self, account_idx: usize, result: Result<crate::drafts::DraftSyncReport, String>) {
+        self.drafts_syncing = false;
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                self.show_error(&tr_args("msg-draft-sync-failed", &[("error", &e)]));
+                return;
             }
-            _ => Ok(()),
+        };
+        if account_idx == self.current_account_idx && matches!(self.mode, AppMode::Drafts) {
+            if let Err(e) = self.load_drafts() {
+                debug_log(&format!("Failed to reload drafts: {}", e));
+            }
+        }
+        if report.conflicts > 0 {
+            self.show_error(&tr_args("msg-draft-conflicts", &[("count", &report.conflicts)]));
+        } else if report.downloaded > 0 {
+            self.show_info(&tr_args("msg-drafts-downloaded", &[("count", &report.downloaded)]));
         }
     }
 
-    fn handle_view_mode(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            KeyCode::Esc => {
-                self.mode = AppMode::Normal;
-                self.email_view_scroll = 0; // Reset scroll when exiting
-                Ok(())
+    /// Save the compose form as a draft, keeping the Message-ID of a resumed one, and
+    /// close the form
+    fn save_draft(&mut self) -> AppResult<()> {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+        let message_id = self
+            .compose_draft_id
+            .clone()
+            .unwrap_or_else(|| crate::drafts::new_message_id(&account_email));
+        let now = Local::now().timestamp();
+
+        let mut draft = crate::drafts::local_draft(&message_id, &self.compose_email, now);
+        let existing = database
+            .get_drafts(&account_email)?
+            .into_iter()
+            .find(|d| d.message_id == message_id);
+        if let Some(existing) = existing {
+            draft.server_uid = existing.server_uid;
+            draft.synced = existing.synced;
+            draft.conflict = existing.conflict;
+            // Saved in the same second as the last sync: still newer than the server copy
+            draft.modified = now.max(existing.synced + 1);
+        }
+        if let Err(e) = database.save_draft(&account_email, &draft) {
+            self.show_error(&tr_args("msg-draft-save-failed", &[("error", &e)]));
+            return Ok(());
+        }
+
+        let subject = if draft.email.subject.is_empty() { tr("msg-no-subject") } else { draft.email.subject.clone() };
+        self.show_info(&tr_args("msg-draft-saved", &[("subject", &subject)]));
+        self.compose_email = Email::new();
+        self.compose_to_text.clear();
+        self.compose_cc_text.clear();
+        self.compose_bcc_text.clear();
+        self.compose_draft_id = None;
+        self.compose_source = None;
+        self.mode = AppMode::Normal;
+        self.focus = FocusPanel::EmailList;
+        self.sync_drafts_in_background(self.current_account_idx);
+        Ok(())
+    }
+
+    /// Drop a draft here; a copy on the server is removed at the next sync
+    fn discard_draft(&mut self, message_id: &str) {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let result = self.open_account_database(self.current_account_idx).and_then(|database| {
+            let drafts = database.get_drafts(&account_email)?;
+            let Some(mut draft) = drafts.into_iter().find(|d| d.message_id == message_id) else {
+                return Ok(());
+            };
+            if draft.server_uid.is_none() && draft.conflict.is_none() {
+                database.delete_draft(&account_email, message_id)?;
+            } else {
+                draft.deleted = true;
+                database.save_draft(&account_email, &draft)?;
             }
-            KeyCode::Up => {
-                if self.email_view_scroll > 0 {
-                    self.email_view_scroll -= 1;
+            Ok(())
+        });
+        if let Err(e) = result {
+            self.show_error(&tr_args("msg-draft-discard-failed", &[("error", &e)]));
+        }
+        self.sync_drafts_in_background(self.current_account_idx);
+    }
+
+    /// Open the selected draft in the compose form
+    fn resume_draft(&mut self) {
+        let Some(draft) = self.drafts.get(self.selected_draft_idx).cloned() else {
+            return;
+        };
+        let join = |addresses: &[crate::email::EmailAddress]| {
+            addresses.iter().map(|a| a.address.as_str()).collect::<Vec<_>>().join(", ")
+        };
+        self.compose_to_text = join(&draft.email.to);
+        self.compose_cc_text = join(&draft.email.cc);
+        self.compose_bcc_text = join(&draft.email.bcc);
+        self.compose_email = draft.email;
+        self.compose_email.folder = "INBOX".to_string();
+        self.compose_draft_id = Some(draft.message_id);
+        self.compose_source = None;
+        self.mode = AppMode::Compose;
+        self.focus = FocusPanel::ComposeForm;
+        self.compose_field = ComposeField::To;
+        self.compose_cursor_pos = self.compose_to_text.len();
+        self.check_spelling();
+        self.request_grammar_check();
+    }
+
+    fn handle_drafts_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            KeyCode::Up => self.selected_draft_idx = self.selected_draft_idx.saturating_sub(1),
+            KeyCode::Down if self.selected_draft_idx + 1 < self.drafts.len() => self.selected_draft_idx += 1,
+            KeyCode::Enter => match self.drafts.get(self.selected_draft_idx) {
+                Some(draft) if draft.conflict.is_some() => self.mode = AppMode::DraftConflict,
+                Some(_) => self.resume_draft(),
+                None => {}
+            },
+            KeyCode::Delete | KeyCode::Char('x') => {
+                if let Some(draft) = self.drafts.get(self.selected_draft_idx) {
+                    let message_id = draft.message_id.clone();
+                    self.discard_draft(&message_id);
+                    self.load_drafts()?;
+                    self.show_info(&tr("msg-draft-discarded"));
                 }
-                Ok(())
-            }
-            KeyCode::Down => {
-                self.email_view_scroll += 1;
-                Ok(())
-            }
-            KeyCode::PageUp => {
-                self.email_view_scroll = self.email_view_scroll.saturating_sub(10);
-                Ok(())
-            }
-            KeyCode::PageDown => {
-                self.email_view_scroll += 10;
-                Ok(())
-            }
-            KeyCode::Home => {
-                self.email_view_scroll = 0;
-                Ok(())
-            }
-            KeyCode::Char('r') => {
-                self.reply_to_email()?;
-                Ok(())
             }
-            KeyCode::Char('a') => {
-                self.reply_all_to_email()?;
-                Ok(())
+            KeyCode::Char('r') => self.sync_drafts_in_background(self.current_account_idx),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keys while choosing between the local and the server version of a draft
+    fn handle_draft_conflict_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        let resolution = match key.code {
+            KeyCode::Char('l') => crate::drafts::Resolution::Local,
+            KeyCode::Char('s') => crate::drafts::Resolution::Server,
+            KeyCode::Char('b') => crate::drafts::Resolution::Both,
+            KeyCode::Esc => {
+                self.mode = AppMode::Drafts;
+                return Ok(());
             }
-            KeyCode::Char('f') => {
-                self.forward_email()?;
-                Ok(())
+            _ => return Ok(()),
+        };
+        let Some(message_id) = self.drafts.get(self.selected_draft_idx).map(|d| d.message_id.clone()) else {
+            self.mode = AppMode::Drafts;
+            return Ok(());
+        };
+
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+        let now = Local::now().timestamp();
+        match crate::drafts::resolve_conflict(&database, &account_email, &message_id, resolution, now) {
+            Ok(()) => self.show_info(&tr("msg-draft-conflict-resolved")),
+            Err(e) => self.show_error(&tr_args("msg-draft-conflict-failed", &[("error", &e)])),
+        }
+        self.load_drafts()?;
+        self.mode = AppMode::Drafts;
+        self.sync_drafts_in_background(self.current_account_idx);
+        Ok(())
+    }
+
+    /// Drop kept copies of deleted messages older than `retention.keep_deleted_days`
+    fn purge_deleted(&mut self, account_idx: usize) {
+        let before = Local::now().timestamp() - i64::from(self.config.retention.keep_deleted_days) * 86_400;
+        let purged = self
+            .open_account_database(account_idx)
+            .map_err(|e| e.to_string())
+            .and_then(|db| db.purge_deleted(before).map_err(|e| e.to_string()));
+        match purged {
+            Ok(0) => {}
+            Ok(count) => debug_log(&format!("Dropped {} expired copies of deleted messages", count)),
+            Err(e) => debug_log(&format!("Failed to drop expired deleted messages: {}", e)),
+        }
+    }
+
+    /// List the messages of the current account deleted in the last few days
+    fn open_recently_deleted(&mut self) -> AppResult<()> {
+        self.load_deleted_messages()?;
+        self.selected_deleted_idx = 0;
+        self.mode = AppMode::RecentlyDeleted;
+        Ok(())
+    }
+
+    fn load_deleted_messages(&mut self) -> AppResult<()> {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let database = self.open_account_database(self.current_account_idx)?;
+        match database.get_deleted_messages(&account_email) {
+            Ok(messages) => {
+                self.deleted_messages = messages;
+                self.selected_deleted_idx = self.selected_deleted_idx.min(self.deleted_messages.len().saturating_sub(1));
             }
-            KeyCode::Char('d') => {
-                self.show_delete_confirmation();
-                Ok(())
+            Err(e) => self.show_error(&tr_args("msg-load-deleted-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Upload the selected deleted message back to the folder it was deleted from
+    fn restore_deleted(&mut self) -> AppResult<()> {
+        let Some(message) = self.deleted_messages.get(self.selected_deleted_idx).cloned() else {
+            return Ok(());
+        };
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(account_data) = self.accounts.get(&self.current_account_idx) else {
+            return Ok(());
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        let account_email = account_data.account.email.clone();
+
+        match client.restore_deleted(&message) {
+            Ok(()) => {
+                self.show_info(&tr_args("msg-restored", &[("subject", &message.subject), ("folder", &message.folder)]));
+                self.load_deleted_messages()?;
+                self.request_immediate_sync(&account_email, &message.folder, Vec::new())?;
             }
-            KeyCode::Char('s') => {
-                // Save selected attachment
-                self.save_selected_attachment()?;
-                Ok(())
+            Err(e) if crate::auth::is_auth_error(&e) => self.show_auth_problem(self.current_account_idx, &e),
+            Err(e) => self.show_error(&tr_args("msg-restore-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    fn handle_recently_deleted_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            KeyCode::Up => self.selected_deleted_idx = self.selected_deleted_idx.saturating_sub(1),
+            KeyCode::Down if self.selected_deleted_idx + 1 < self.deleted_messages.len() => self.selected_deleted_idx += 1,
+            KeyCode::Enter | KeyCode::Char('u') => self.restore_deleted()?,
+            KeyCode::Delete | KeyCode::Char('x') => {
+                if let Some(message) = self.deleted_messages.get(self.selected_deleted_idx) {
+                    let id = message.id;
+                    let database = self.open_account_database(self.current_account_idx)?;
+                    if let Err(e) = database.forget_deleted(id) {
+                        self.show_error(&tr_args("msg-drop-copy-failed", &[("error", &e)]));
+                    }
+                    self.load_deleted_messages()?;
+                }
             }
-            KeyCode::Tab => {
-                // Navigate through attachments
-                self.select_next_attachment();
-                Ok(())
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// List the operations of all accounts still waiting for the background sync
+    fn open_pending_operations(&mut self) -> AppResult<()> {
+        self.selected_operation_idx = 0;
+        self.operation_target_input = None;
+        self.load_pending_operations();
+        self.mode = AppMode::PendingOperations;
+        Ok(())
+    }
+
+    fn load_pending_operations(&mut self) {
+        match self.database.get_pending_operations() {
+            Ok(operations) => {
+                self.queued_operations = operations;
+                self.selected_operation_idx =
+                    self.selected_operation_idx.min(self.queued_operations.len().saturating_sub(1));
             }
-            KeyCode::BackTab => {
-                // Navigate through attachments (reverse)
-                self.select_previous_attachment();
-                Ok(())
+            Err(e) => self.show_error(&tr_args("msg-load-operations-failed", &[("error", &e)])),
+        }
+    }
+
+    /// The next folder of the operation's account after the one typed, for Tab
+    fn next_target_folder(&self, op: &crate::database::PendingOperation, typed: &str) -> Option<String> {
+        let folders = &self.accounts.values().find(|data| data.account.email == op.account_email)?.folders;
+        let candidates: Vec<&String> = folders.iter().filter(|folder| **folder != op.folder).collect();
+        let next = match candidates.iter().position(|folder| *folder == typed) {
+            Some(i) => candidates.get(i + 1).or(candidates.first()),
+            None => candidates.first(),
+        };
+        next.map(|folder| folder.to_string())
+    }
+
+    fn load_reminders(&mut self) {
+        match self.database.get_reminders() {
+            Ok(reminders) => {
+                self.reminders = reminders;
+                self.selected_reminder_idx = self.selected_reminder_idx.min(self.reminders.len().saturating_sub(1));
             }
-            _ => Ok(()),
+            Err(e) => self.show_error(&tr_args("msg-load-reminders-failed", &[("error", &e)])),
         }
     }
 
-    fn handle_folder_list_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+    /// Keys while the time of a reminder is typed: Enter sets it on the selected message
+    fn handle_reminder_input(&mut self, key: KeyEvent) -> AppResult<()> {
+        let Some(input) = self.reminder_input.as_mut() else {
+            return Ok(());
+        };
         match key.code {
-            KeyCode::Esc => {
-                self.mode = AppMode::Normal;
-                self.focus = FocusPanel::FolderList;
-                Ok(())
-            }
-            KeyCode::Up => {
-                if !self.folder_items.is_empty() && self.selected_folder_item_idx > 0 {
-                    self.selected_folder_item_idx -= 1;
-                }
-                Ok(())
+            KeyCode::Esc => self.reminder_input = None,
+            KeyCode::Enter => {
+                let when = input.clone();
+                self.reminder_input = None;
+                self.set_reminder(&when);
             }
-            KeyCode::Down => {
-                if !self.folder_items.is_empty()
-                    && self.selected_folder_item_idx < self.folder_items.len().saturating_sub(1)
-                {
-                    self.selected_folder_item_idx += 1;
-                }
-                Ok(())
+            KeyCode::Backspace => {
+                input.pop();
             }
-            KeyCode::Enter => {
-                if let Some(item) = self
-                    .folder_items
-                    .get(self.selected_folder_item_idx)
-                    .cloned()
-                {
-                    match item {
-                        crate::app::FolderItem::Account { index, .. } => {
-                            // Toggle account expansion
-                            self.toggle_account_expansion(index);
-                        }
-                        crate::app::FolderItem::Folder {
-                            account_index,
-                            full_path,
-                            ..
-                        } => {
-                            // Select folder and switch to normal mode
-                            self.current_account_idx = account_index;
-                            self.mode = AppMode::Normal;
-                            self.focus = FocusPanel::EmailList;
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => input.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
 
-                            // Load emails for the selected folder
-                            if let Err(e) =
-                                self.load_emails_for_account_folder(account_index, &full_path)
-                            {
-                                self.show_error(&format!("Failed to load emails: {}", e));
-                            }
-                        }
-                    }
-                } else {
-                    self.show_error("Invalid selection");
-                }
-                Ok(())
-            }
-            KeyCode::Char(' ') => {
-                // Space bar also toggles account expansion
-                if let Some(item) = self
-                    .folder_items
-                    .get(self.selected_folder_item_idx)
-                    .cloned()
-                {
-                    if let crate::app::FolderItem::Account { index, .. } = item {
-                        self.toggle_account_expansion(index);
-                    }
-                }
-                Ok(())
+    fn set_reminder(&mut self, when: &str) {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)) else {
+            self.show_error(&tr("msg-no-email-selected"));
+            return;
+        };
+        let remind_at = match crate::reminders::parse_when(when, Local::now()) {
+            Ok(remind_at) => remind_at,
+            Err(e) => {
+                self.show_error(&e);
+                return;
             }
-            _ => Ok(()),
+        };
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let reminder = crate::reminders::Reminder::new(&account_email, email, remind_at);
+        match self.database.add_reminder(&reminder) {
+            Ok(_) => self.show_info(&tr_args(
+                "msg-reminder-set",
+                &[("subject", &reminder.subject), ("when", &remind_at.format("%a %d %b %H:%M"))],
+            )),
+            Err(e) => self.show_error(&tr_args("msg-reminder-save-failed", &[("error", &e)])),
         }
     }
 
-    fn handle_settings_mode(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            KeyCode::Esc => {
-                self.mode = AppMode::Normal;
-                Ok(())
+    /// Announce the reminders that are due, here and on the desktop
+    fn fire_due_reminders(&mut self) {
+        let due = match self.database.fire_due_reminders(Local::now().timestamp()) {
+            Ok(due) => due,
+            Err(e) => {
+                debug_log(&format!("Failed to check reminders: {}", e));
+                return;
             }
-            _ => Ok(()),
+        };
+        for reminder in &due {
+            crate::reminders::notify_desktop(&format!("Reminder: {}", reminder.subject), &format!("From {}", reminder.sender));
+        }
+        match due.as_slice() {
+            [] => return,
+            [reminder] => self.show_info(&tr_args("msg-reminder-due", &[("subject", &reminder.subject)])),
+            more => self.show_info(&tr_args("msg-reminders-due", &[("count", &more.len())])),
+        }
+        if self.mode == AppMode::Reminders {
+            self.load_reminders();
         }
     }
 
-    fn handle_help_mode(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
-                self.mode = AppMode::Normal;
+    /// Show the message of a reminder: in the folder it was in, or wherever its
+    /// Message-ID is found now
+    fn open_reminder_message(&mut self, reminder: &crate::reminders::Reminder) -> AppResult<()> {
+        let Some(account_idx) = self.config.accounts.iter().position(|account| account.email == reminder.account_email) else {
+            self.show_error(&tr_args("msg-reminder-account-gone", &[("account", &reminder.account_email)]));
+            return Ok(());
+        };
+        let database = self.open_account_database(account_idx)?;
+        let moved = match &reminder.message_id {
+            Some(message_id) => database.find_email_by_message_id(&reminder.account_email, message_id).ok().flatten(),
+            None => None,
+        };
+        let (folder, uid) = match moved {
+            Some(email) => (email.folder, email.id),
+            None => (reminder.folder.clone(), reminder.uid.to_string()),
+        };
+
+        if account_idx != self.current_account_idx {
+            self.switch_to_account(account_idx)?;
+        }
+        self.mode = AppMode::Normal;
+        self.focus = FocusPanel::EmailList;
+        self.load_emails_for_account_folder(account_idx, &folder)?;
+        match self.emails.iter().position(|email| email.id == uid && email.folder == folder) {
+            Some(idx) => {
+                self.selected_email_idx = Some(idx);
+                self.apply_action(Action::OpenSelected)
+            }
+            None => {
+                self.show_error(&tr_args("msg-reminder-message-gone", &[("subject", &reminder.subject), ("folder", &folder)]));
                 Ok(())
             }
-            _ => Ok(()),
         }
     }
 
-    fn handle_delete_confirm_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+    fn handle_reminders_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        let selected = self.reminders.get(self.selected_reminder_idx).cloned();
         match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                // User confirmed deletion
-                self.mode = AppMode::Normal;
-                self.delete_selected_email()?;
-                Ok(())
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            KeyCode::Up => self.selected_reminder_idx = self.selected_reminder_idx.saturating_sub(1),
+            KeyCode::Down if self.selected_reminder_idx + 1 < self.reminders.len() => self.selected_reminder_idx += 1,
+            KeyCode::Enter => {
+                if let Some(reminder) = selected {
+                    return self.open_reminder_message(&reminder);
+                }
             }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                // User cancelled deletion
-                self.mode = AppMode::Normal;
-                Ok(())
+            KeyCode::Char('x') | KeyCode::Delete => {
+                if let Some(reminder) = selected {
+                    if let Err(e) = self.database.dismiss_reminder(reminder.id) {
+                        self.show_error(&tr_args("msg-reminder-dismiss-failed", &[("error", &e)]));
+                    }
+                    self.load_reminders();
+                }
             }
-            _ => Ok(()),
+            _ => {}
         }
+        Ok(())
     }
 
-    fn show_delete_confirmation(&mut self) {
-        self.mode = AppMode::DeleteConfirm;
-    }
+    fn handle_pending_operations_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        let selected = self.queued_operations.get(self.selected_operation_idx).cloned();
 
-    pub fn select_next_email(&mut self) {
-        if self.emails.is_empty() {
-            self.selected_email_idx = None;
-            return;
+        // Typing the target folder of the selected move
+        if let Some(target) = self.operation_target_input.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.operation_target_input = None,
+                KeyCode::Backspace => {
+                    target.pop();
+                }
+                KeyCode::Char(c) => target.push(c),
+                KeyCode::Tab => {
+                    let typed = target.clone();
+                    if let Some(next) = selected.as_ref().and_then(|op| self.next_target_folder(op, &typed)) {
+                        self.operation_target_input = Some(next);
+                    }
+                }
+                KeyCode::Enter if !target.trim().is_empty() => {
+                    let target = target.trim().to_string();
+                    self.operation_target_input = None;
+                    if let Some(op) = selected {
+                        match self.database.set_operation_target(op.id, &target) {
+                            Ok(()) => self.show_info(&tr_args("msg-operation-target", &[("folder", &target)])),
+                            Err(e) => self.show_error(&tr_args("msg-operation-target-failed", &[("error", &e)])),
+                        }
+                        self.load_pending_operations();
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
         }
 
-        match self.selected_email_idx {
-            Some(idx) => {
-                if idx < self.emails.len().saturating_sub(1) {
-                    self.selected_email_idx = Some(idx + 1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+            KeyCode::Up => self.selected_operation_idx = self.selected_operation_idx.saturating_sub(1),
+            KeyCode::Down if self.selected_operation_idx + 1 < self.queued_operations.len() => self.selected_operation_idx += 1,
+            KeyCode::Char('r') | KeyCode::Enter => {
+                if let Some(op) = selected {
+                    match self.database.retry_operation(op.id) {
+                        Ok(()) if crate::email::is_account_offline(&op.account_email) => {
+                            self.show_info(&tr_args("msg-operation-offline", &[("account", &op.account_email)]))
+                        }
+                        Ok(()) => self.show_info(&tr_args("msg-operation-retrying", &[("operation", &op.describe())])),
+                        Err(e) => self.show_error(&tr_args("msg-operation-retry-failed", &[("error", &e)])),
+                    }
+                    self.load_pending_operations();
                 }
-                // If we're at the last email, stay there
             }
-            None => {
-                // If nothing is selected, select the first email
-                self.selected_email_idx = Some(0);
+            KeyCode::Delete | KeyCode::Char('x') => {
+                if let Some(op) = selected {
+                    if let Err(e) = self.database.cancel_operation(op.id) {
+                        self.show_error(&tr_args("msg-operation-cancel-failed", &[("error", &e)]));
+                    }
+                    self.load_pending_operations();
+                    // The message shows its state on the server again
+                    self.reload_cached_folder(&op.account_email, &op.folder)?;
+                }
             }
+            KeyCode::Char('t') => match selected {
+                Some(op) if op.operation_type == "move" => {
+                    self.operation_target_input = Some(op.target_folder.unwrap_or_default());
+                }
+                Some(_) => self.show_error(&tr("msg-operation-not-move")),
+                None => {}
+            },
+            _ => {}
         }
+        Ok(())
     }
 
-    pub fn select_prev_email(&mut self) {
-        if self.emails.is_empty() {
-            self.selected_email_idx = None;
-            return;
-        }
-
-        match self.selected_email_idx {
-            Some(idx) => {
-                if idx > 0 {
-                    self.selected_email_idx = Some(idx - 1);
+    fn handle_reply_warning_mode(&mut self, key: KeyEvent) -> AppResult<()> {
+        match key.code {
+            // With the recipient list shown only an explicit 'y' goes ahead
+            KeyCode::Enter if self.reply_warning.as_ref().is_some_and(|reply| !reply.recipients.is_empty()) => Ok(()),
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                self.mode = AppMode::ViewEmail;
+                match self.reply_warning.take() {
+                    Some(reply) if reply.all => self.reply_all_to_email(),
+                    Some(_) => self.reply_to_email(),
+                    None => Ok(()),
                 }
-                // If we're at the first email, stay there
             }
-            None => {
-                // If nothing is selected, select the first email
-                self.selected_email_idx = Some(0);
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.mode = AppMode::ViewEmail;
+                self.reply_warning = None;
+                Ok(())
             }
+            _ => Ok(()),
         }
     }
 
     pub fn reply_to_email(&mut self) -> AppResult<()> {
         if let Some(idx) = self.selected_email_idx {
             if idx >= self.emails.len() {
-                self.show_error("Invalid email selection");
+                self.show_error(&tr("msg-invalid-email-selection"));
                 return Ok(());
             }
 
-            let original = &self.emails[idx];
+            let original = self.digest_message().unwrap_or(&self.emails[idx]);
 
             let mut reply = Email::new();
 
@@ -2555,14 +7499,16 @@ impl App {
             self.compose_to_text = to_text;
             self.compose_cc_text = String::new(); // Clear CC field for reply
             self.compose_bcc_text = String::new(); // Clear BCC field for reply
+            self.compose_source = self.source_of_compose(idx, "\\Answered");
+            self.apply_compose_defaults();
             self.mode = AppMode::Compose;
             self.focus = FocusPanel::ComposeForm;
             self.compose_field = ComposeField::Body;
             self.compose_cursor_pos = 0; // Position cursor at the very beginning for user to start typing
 
-            self.show_info("Replying to email - cursor positioned at top");
+            self.show_info(&tr("msg-replying"));
         } else {
-            self.show_error("No email selected");
+            self.show_error(&tr("msg-no-email-selected"));
         }
 
         Ok(())
@@ -2571,11 +7517,11 @@ impl App {
     pub fn reply_all_to_email(&mut self) -> AppResult<()> {
         if let Some(idx) = self.selected_email_idx {
             if idx >= self.emails.len() {
-                self.show_error("Invalid email selection");
+                self.show_error(&tr("msg-invalid-email-selection"));
                 return Ok(());
             }
 
-            let original = &self.emails[idx];
+            let original = self.digest_message().unwrap_or(&self.emails[idx]);
             let current_account = &self.config.accounts[self.current_account_idx];
 
             let mut reply = Email::new();
@@ -2594,49 +7540,9 @@ impl App {
             }];
 
             // For reply-all, include original sender and all recipients except current user
-            let current_email = &current_account.email;
-
-            // Add original sender (reply-to if present, otherwise from)
-            let reply_to_addrs = original.reply_to();
-            let original_sender = if !reply_to_addrs.is_empty() {
-                &reply_to_addrs
-            } else {
-                &original.from
-            };
-
-            for addr in original_sender {
-                if addr.address != *current_email {
-                    reply.to.push(addr.clone());
-                }
-            }
-
-            // Add all original TO recipients except current user
-            for addr in &original.to {
-                if addr.address != *current_email
-                    && !reply
-                        .to
-                        .iter()
-                        .any(|existing| existing.address == addr.address)
-                {
-                    reply.to.push(addr.clone());
-                }
-            }
-
-            // Add all original CC recipients except current user to CC
-            for addr in &original.cc {
-                if addr.address != *current_email
-                    && !reply
-                        .cc
-                        .iter()
-                        .any(|existing| existing.address == addr.address)
-                {
-                    reply.cc.push(addr.clone());
-                }
-            }
-
-            // Deduplicate all addresses to prevent duplicates
-            reply.to.dedup_by(|a, b| a.address == b.address);
-            reply.cc.dedup_by(|a, b| a.address == b.address);
+            let (to, cc) = crate::reply_check::reply_all_recipients(original, &current_account.email);
+            reply.to = to;
+            reply.cc = cc;
 
             // Set In-Reply-To and References headers for proper threading
             let original_msg_id = original.message_id();
@@ -2693,15 +7599,17 @@ impl App {
             self.compose_to_text = to_text;
             self.compose_cc_text = cc_text;
             self.compose_bcc_text = String::new(); // Clear BCC field for reply-all
+            self.compose_source = self.source_of_compose(idx, "\\Answered");
+            self.apply_compose_defaults();
             
             self.mode = AppMode::Compose;
             self.focus = FocusPanel::ComposeForm;
             self.compose_field = ComposeField::Body;
             self.compose_cursor_pos = 0; // Position cursor at the very beginning
 
-            self.show_info("Replying to all - cursor positioned at top");
+            self.show_info(&tr("msg-replying-all"));
         } else {
-            self.show_error("No email selected");
+            self.show_error(&tr("msg-no-email-selected"));
         }
 
         Ok(())
@@ -2710,11 +7618,11 @@ impl App {
     pub fn forward_email(&mut self) -> AppResult<()> {
         if let Some(idx) = self.selected_email_idx {
             if idx >= self.emails.len() {
-                self.show_error("Invalid email selection");
+                self.show_error(&tr("msg-invalid-email-selection"));
                 return Ok(());
             }
 
-            let original = &self.emails[idx];
+            let original = self.digest_message().unwrap_or(&self.emails[idx]);
 
             let mut forward = Email::new();
 
@@ -2725,90 +7633,618 @@ impl App {
                 format!("Fwd: {}", original.subject)
             };
 
-            // Set from field to current account
-            let current_account = &self.config.accounts[self.current_account_idx];
-            forward.from = vec![crate::email::EmailAddress {
-                name: Some(current_account.name.clone()),
-                address: current_account.email.clone(),
-            }];
+            // Set from field to current account
+            let current_account = &self.config.accounts[self.current_account_idx];
+            forward.from = vec![crate::email::EmailAddress {
+                name: Some(current_account.name.clone()),
+                address: current_account.email.clone(),
+            }];
+
+            // Set body with space for typing at the top, then forwarded content header
+            let from_str = original
+                .from
+                .iter()
+                .map(|addr| {
+                    if let Some(name) = &addr.name {
+                        format!("{} <{}>", name, addr.address)
+                    } else {
+                        addr.address.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let to_str = original
+                .to
+                .iter()
+                .map(|addr| {
+                    if let Some(name) = &addr.name {
+                        format!("{} <{}>", name, addr.address)
+                    } else {
+                        addr.address.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let mut forward_header = format!(
+                "\n\n\n\n---------- Forwarded message ----------\nFrom: {}\nDate: {}\nSubject: {}\nTo: {}",
+                from_str,
+                original.date.format("%a, %d %b %Y %H:%M:%S %z"),
+                original.subject,
+                to_str
+            );
+
+            // Add CC if present
+            if !original.cc.is_empty() {
+                let cc_str = original
+                    .cc
+                    .iter()
+                    .map(|addr| {
+                        if let Some(name) = &addr.name {
+                            format!("{} <{}>", name, addr.address)
+                        } else {
+                            addr.address.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                forward_header.push_str(&format!("\nCc: {}", cc_str));
+            }
+
+            forward_header.push_str("\n\n");
+
+            // Add original body
+            if let Some(body) = &original.body_text {
+                forward.body_text = Some(format!("{}{}", forward_header, body));
+            } else {
+                forward.body_text = Some(forward_header);
+            }
+
+            // Copy attachments
+            forward.attachments = original.attachments.clone();
+
+            self.compose_email = forward;
+            self.compose_to_text = String::new(); // Forward starts with empty To field
+            self.compose_source = self.source_of_compose(idx, "$Forwarded");
+            self.apply_compose_defaults();
+            self.mode = AppMode::Compose;
+            self.focus = FocusPanel::ComposeForm;
+            self.compose_field = ComposeField::To; // Start in To field for forward
+            self.compose_cursor_pos = 0; // Position cursor at the beginning
+
+            self.show_info(&tr("msg-forwarding"));
+        } else {
+            self.show_error(&tr("msg-no-email-selected"));
+        }
+
+        Ok(())
+    }
+
+    /// The message at `idx` as the source of a reply or forward. `None` for a message of
+    /// a digest, which is not on the server.
+    fn source_of_compose(&self, idx: usize, flag: &'static str) -> Option<ComposeSource> {
+        if self.digest_message().is_some() {
+            return None;
+        }
+        let email = self.emails.get(idx)?;
+        Some(ComposeSource {
+            account_idx: self.current_account_idx,
+            folder: email.folder.clone(),
+            uid: email.id.parse().ok()?,
+            flag,
+        })
+    }
+
+    /// Flag the message a sent reply or forward came from, so the list shows it as
+    /// handled. The server is told through the operation queue; Gmail and Exchange
+    /// accounts have no such flags, so there it is only kept in the cache.
+    fn mark_compose_source(&mut self, source: ComposeSource) {
+        let Some(account) = self.config.accounts.get(source.account_idx) else {
+            return;
+        };
+        let account_email = account.email.clone();
+        let server_flags = account.gmail_api.is_none() && account.exchange.is_none();
+        let operation = if source.flag == "$Forwarded" { "mark_forwarded" } else { "mark_answered" };
+        let same = |email: &Email| email.folder == source.folder && email.id == source.uid.to_string();
+        let subject = self.emails.iter().find(|email| same(email)).map(|email| email.subject.clone());
+        let result = if server_flags {
+            self.database
+                .queue_email_operation(&account_email, operation, source.uid, &source.folder, None, subject.as_deref())
+                .map_err(AppError::from)
+        } else {
+            self.open_account_database(source.account_idx).and_then(|database| {
+                database.add_email_flag(&account_email, &source.folder, source.uid, source.flag).map_err(AppError::from)
+            })
+        };
+        if let Err(e) = result {
+            debug_log(&format!("Could not mark UID {} in {} {}: {}", source.uid, source.folder, source.flag, e));
+            return;
+        }
+        crate::audit::record(
+            &self.database, &account_email, &source.folder, Some(source.uid), &format!("flag +{}", source.flag),
+            crate::audit::Origin::Ui, subject.as_deref().unwrap_or_default(),
+        );
+
+        let cached = self.accounts.get_mut(&source.account_idx).map(|data| data.emails.iter_mut());
+        for email in self.emails.iter_mut().chain(cached.into_iter().flatten()) {
+            if same(email) && !email.flags.iter().any(|flag| flag == source.flag) {
+                email.flags.push(source.flag.to_string());
+            }
+        }
+    }
+
+    /// Apply the current account's compose defaults to a message just started: Cc/Bcc,
+    /// signature, format and the priority and read-receipt headers
+    fn apply_compose_defaults(&mut self) {
+        let Some(account) = self.config.accounts.get(self.current_account_idx) else {
+            return;
+        };
+        let defaults = &account.compose;
+        let email = &mut self.compose_email;
+
+        for (addresses, defaults) in [(&mut email.cc, &defaults.cc), (&mut email.bcc, &defaults.bcc)] {
+            for address in defaults {
+                if !addresses.iter().any(|a| a.address.eq_ignore_ascii_case(address)) {
+                    addresses.push(crate::email::EmailAddress { name: None, address: address.clone() });
+                }
+            }
+        }
+        let join = |addresses: &[crate::email::EmailAddress]| {
+            addresses.iter().map(|a| a.address.as_str()).collect::<Vec<_>>().join(", ")
+        };
+        self.compose_cc_text = join(&email.cc);
+        self.compose_bcc_text = join(&email.bcc);
+
+        if let Some(signature) = self.config.signature_for(account) {
+            let body = email.body_text.as_deref().unwrap_or("");
+            email.body_text = Some(crate::quoting::with_signature(body, signature));
+        }
+        if let Some(priority) = defaults.priority {
+            let (x_priority, importance) = priority.header_values();
+            email.headers.insert("X-Priority".to_string(), x_priority.to_string());
+            email.headers.insert("Importance".to_string(), importance.to_string());
+        }
+        if defaults.read_receipt {
+            email.headers.insert("Disposition-Notification-To".to_string(), account.email.clone());
+        }
+        self.compose_format = defaults.format;
+        self.compose_pgp = crate::pgp::Protection::defaults(account.pgp.as_ref());
+        self.recipient_typos_confirmed = false;
+        self.suppressed_recipients_confirmed = false;
+        self.load_known_recipients();
+    }
+
+    fn load_known_recipients(&mut self) {
+        if matches!(&self.known_recipients, Some((idx, _)) if *idx == self.current_account_idx) {
+            return;
+        }
+        let Some(account) = self.config.accounts.get(self.current_account_idx) else {
+            return;
+        };
+        let account_email = account.email.clone();
+        let contacts = self
+            .open_account_database(self.current_account_idx)
+            .ok()
+            .and_then(|database| database.get_contacts(&account_email).ok());
+        let Some(mut contacts) = contacts else {
+            return;
+        };
+        contacts.sort_by_key(|contact| std::cmp::Reverse(contact.message_count));
+        let addresses = contacts.iter().map(|contact| contact.address.clone()).collect();
+        self.known_recipients = Some((self.current_account_idx, addresses));
+        self.address_book = contacts;
+    }
+
+    /// Text of the To, Cc or Bcc field
+    fn address_field_text(&self, field: ComposeField) -> Option<&String> {
+        match field {
+            ComposeField::To => Some(&self.compose_to_text),
+            ComposeField::Cc => Some(&self.compose_cc_text),
+            ComposeField::Bcc => Some(&self.compose_bcc_text),
+            ComposeField::Subject | ComposeField::Body => None,
+        }
+    }
+
+    /// Replace the text of an address field and the recipients parsed from it
+    fn set_address_field(&mut self, field: ComposeField, text: String) {
+        let addresses = text
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(|address| crate::email::EmailAddress { name: None, address: address.to_string() })
+            .collect();
+        match field {
+            ComposeField::To => (self.compose_to_text, self.compose_email.to) = (text, addresses),
+            ComposeField::Cc => (self.compose_cc_text, self.compose_email.cc) = (text, addresses),
+            ComposeField::Bcc => (self.compose_bcc_text, self.compose_email.bcc) = (text, addresses),
+            ComposeField::Subject | ComposeField::Body => {}
+        }
+    }
+
+    /// Look up the name under the cursor of an address field in the address book:
+    /// a single match replaces it with the address, several are offered to pick from
+    fn resolve_recipient_name(&mut self) {
+        let field = self.compose_field;
+        let Some(text) = self.address_field_text(field) else {
+            return;
+        };
+        let range = crate::recipients::entry_at(text, self.compose_cursor_pos);
+        let name = text[range.clone()].to_string();
+        if name.is_empty() || name.contains('@') {
+            return;
+        }
+        self.load_known_recipients();
+        let mut contacts: Vec<_> = crate::recipients::find_by_name(&name, &self.address_book).into_iter().cloned().collect();
+        for found in self.directory_matches(&name) {
+            if !contacts.iter().any(|contact| contact.address.eq_ignore_ascii_case(&found.address)) {
+                contacts.push(found.clone());
+            }
+        }
+        match contacts.len() {
+            0 if self.directory_pending.as_ref().is_some_and(|query| query.eq_ignore_ascii_case(&name)) => {
+                self.show_error(&tr_args("msg-directory-searching", &[("name", &name)]))
+            }
+            0 => self.show_error(&tr_args("msg-no-contact-named", &[("name", &name)])),
+            1 => self.insert_recipient(field, range, &contacts[0].address),
+            _ => self.recipient_choices = Some(RecipientChoices { field, range, name, contacts, selected: 0 }),
+        }
+    }
+
+    /// The name or address being typed in the current address field
+    fn typed_recipient(&self) -> Option<String> {
+        let text = self.address_field_text(self.compose_field)?;
+        Some(text[crate::recipients::entry_at(text, self.compose_cursor_pos)].to_string())
+    }
+
+    /// Search the account's LDAP directory for what is being typed in an address field,
+    /// in the background. One search runs at a time; when it ends, the text typed by then
+    /// is searched for.
+    fn request_directory_search(&mut self) {
+        let account_idx = self.current_account_idx;
+        if self.directory_pending.is_some() || self.config.accounts.get(account_idx).is_none_or(|a| a.ldap.is_none()) {
+            return;
+        }
+        let Some(query) = self.typed_recipient() else {
+            return;
+        };
+        if query.chars().count() < MIN_DIRECTORY_QUERY || query.contains('@') {
+            return;
+        }
+        if self.directory_results.as_ref().is_some_and(|(idx, searched, _)| *idx == account_idx && searched.eq_ignore_ascii_case(&query)) {
+            return;
+        }
+        let Some(client) = self.accounts.get(&account_idx).and_then(|data| data.email_client.clone()) else {
+            return;
+        };
+        self.directory_pending = Some(query.clone());
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            let contacts = client.search_directory(&query).map_err(|e| e.to_string());
+            if let Err(e) = &contacts {
+                debug_log(&format!("ldap: could not search for {}: {}", query, e));
+            }
+            let _ = sender.send(AppEvent::DirectorySearched { account_idx, query, contacts });
+        });
+    }
+
+    /// People the directory found for `name`, once it has been searched for
+    pub fn directory_matches(&self, name: &str) -> &[crate::database::ContactSummary] {
+        match &self.directory_results {
+            Some((idx, query, Ok(contacts))) if *idx == self.current_account_idx && query.eq_ignore_ascii_case(name.trim()) => contacts,
+            _ => &[],
+        }
+    }
+
+    /// Directory matches for what is being typed in the current address field
+    pub fn directory_suggestions(&self) -> &[crate::database::ContactSummary] {
+        self.typed_recipient().map_or(&[], |typed| self.directory_matches(&typed))
+    }
+
+    /// Put `address` in place of the name at `range` of an address field
+    fn insert_recipient(&mut self, field: ComposeField, range: std::ops::Range<usize>, address: &str) {
+        let Some(text) = self.address_field_text(field) else {
+            return;
+        };
+        let mut text = text.clone();
+        text.replace_range(range.clone(), address);
+        if self.compose_field == field {
+            self.compose_cursor_pos = range.start + address.len();
+        }
+        self.set_address_field(field, text);
+    }
+
+    fn handle_recipient_choices(&mut self, key: KeyEvent) -> AppResult<()> {
+        let Some(choices) = self.recipient_choices.as_mut() else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.recipient_choices = None,
+            KeyCode::Up => choices.selected = choices.selected.saturating_sub(1),
+            KeyCode::Down => choices.selected = (choices.selected + 1).min(choices.contacts.len().saturating_sub(1)),
+            KeyCode::Enter => {
+                if let Some(choices) = self.recipient_choices.take() {
+                    if let Some(contact) = choices.contacts.get(choices.selected) {
+                        self.insert_recipient(choices.field, choices.range, &contact.address);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Offer the account's folders for moving the selected message to
+    fn open_folder_picker(&mut self) {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)) else {
+            self.show_error(&tr("msg-no-email-selected"));
+            return;
+        };
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-moving"));
+        } else if email.id.parse::<u32>().is_err() {
+            self.show_error(&tr("msg-move-server-only"));
+        } else if self.get_selected_folder_info().map(|(_, folder)| folder).as_deref() != Some(email.folder.as_str()) {
+            self.show_error(&tr_args("msg-move-open-folder", &[("folder", &email.folder)]));
+        } else {
+            self.folder_picker = Some(FolderPicker::default());
+        }
+    }
+
+    /// The folders the picker offers: those of the current account matching its filter,
+    /// other than the one the selected message is in
+    pub fn folder_picker_choices(&self) -> Vec<String> {
+        let (Some(picker), Some(account_data)) = (&self.folder_picker, self.accounts.get(&self.current_account_idx)) else {
+            return Vec::new();
+        };
+        let current = self.selected_email_idx.and_then(|idx| self.emails.get(idx)).map(|email| email.folder.as_str());
+        picker.matches(&account_data.folders, current.unwrap_or_default()).into_iter().cloned().collect()
+    }
+
+    fn handle_folder_picker(&mut self, key: KeyEvent) -> AppResult<()> {
+        let choices = self.folder_picker_choices();
+        let Some(picker) = self.folder_picker.as_mut() else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.folder_picker = None,
+            KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+            KeyCode::Down => picker.selected = (picker.selected + 1).min(choices.len().saturating_sub(1)),
+            KeyCode::Backspace => {
+                picker.filter.pop();
+                picker.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                picker.filter.push(c);
+                picker.selected = 0;
+            }
+            KeyCode::Enter => {
+                let target = choices.get(picker.selected).cloned();
+                self.folder_picker = None;
+                if let Some(target) = target {
+                    return self.move_selected_email(&target);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Queue a move of the marked messages to `target`, or of the selected one when none
+    /// is marked. They leave the list at once; the server follows when the queue is next
+    /// processed, which waits while offline.
+    fn move_selected_email(&mut self, target: &str) -> AppResult<()> {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)).cloned() else {
+            return Ok(());
+        };
+        let marked: Vec<u32> = self
+            .emails
+            .iter()
+            .filter(|marked| marked.folder == email.folder && self.is_marked(marked))
+            .filter_map(|marked| marked.id.parse().ok())
+            .collect();
+        if marked.is_empty() {
+            let Ok(uid) = email.id.parse::<u32>() else {
+                return Ok(());
+            };
+            self.queue_email_operation("move", uid, Some(target))?;
+            self.show_info(&tr_args("msg-moving", &[("folder", &target), ("subject", &email.subject)]));
+            return Ok(());
+        }
+        for uid in &marked {
+            self.queue_email_operation("move", *uid, Some(target))?;
+        }
+        self.marked_emails.retain(|(folder, _)| *folder != email.folder);
+        self.show_info(&tr_args("msg-moving-marked", &[("count", &marked.len()), ("folder", &target)]));
+        Ok(())
+    }
+
+    /// Switch the compose body between its own pane and the whole form. Going full
+    /// screen moves to the body; moving to another field shows the headers again.
+    fn toggle_compose_fullscreen(&mut self) {
+        if self.compose_fullscreen() {
+            self.compose_body_fullscreen = false;
+            return;
+        }
+        self.compose_body_fullscreen = true;
+        if self.compose_field != ComposeField::Body {
+            self.compose_field = ComposeField::Body;
+            self.compose_cursor_pos = 0;
+            self.check_spelling();
+        }
+    }
 
-            // Set body with space for typing at the top, then forwarded content header
-            let from_str = original
-                .from
-                .iter()
-                .map(|addr| {
-                    if let Some(name) = &addr.name {
-                        format!("{} <{}>", name, addr.address)
-                    } else {
-                        addr.address.clone()
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(", ");
+    /// Whether the compose body is shown full screen, which it is only while being edited
+    pub fn compose_fullscreen(&self) -> bool {
+        self.compose_body_fullscreen && self.compose_field == ComposeField::Body
+    }
 
-            let to_str = original
-                .to
-                .iter()
-                .map(|addr| {
-                    if let Some(name) = &addr.name {
-                        format!("{} <{}>", name, addr.address)
-                    } else {
-                        addr.address.clone()
-                    }
+    /// Recipients of the message being composed that look like typos of known
+    /// addresses, with the suggested correction
+    pub fn recipient_typos(&self) -> Vec<(String, String)> {
+        let Some((_, known)) = &self.known_recipients else {
+            return Vec::new();
+        };
+        let email = &self.compose_email;
+        email
+            .to
+            .iter()
+            .chain(&email.cc)
+            .chain(&email.bcc)
+            .filter_map(|recipient| {
+                crate::recipients::suggest_correction(&recipient.address, known)
+                    .map(|suggestion| (recipient.address.clone(), suggestion.to_string()))
+            })
+            .collect()
+    }
+
+    /// Replace likely typos in To, Cc and Bcc with the suggested addresses
+    fn correct_recipient_typos(&mut self) {
+        let typos = self.recipient_typos();
+        if typos.is_empty() {
+            self.show_info(&tr("msg-no-typos"));
+            return;
+        }
+        let correct = |text: &str| -> String {
+            text.split(',')
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(|address| {
+                    typos
+                        .iter()
+                        .find(|(typo, _)| typo == address)
+                        .map_or(address, |(_, suggestion)| suggestion.as_str())
                 })
                 .collect::<Vec<_>>()
-                .join(", ");
+                .join(", ")
+        };
+        self.compose_to_text = correct(&self.compose_to_text);
+        self.compose_cc_text = correct(&self.compose_cc_text);
+        self.compose_bcc_text = correct(&self.compose_bcc_text);
+        let parse = |text: &str| -> Vec<crate::email::EmailAddress> {
+            text.split(',')
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(|address| crate::email::EmailAddress { name: None, address: address.to_string() })
+                .collect()
+        };
+        self.compose_email.to = parse(&self.compose_to_text);
+        self.compose_email.cc = parse(&self.compose_cc_text);
+        self.compose_email.bcc = parse(&self.compose_bcc_text);
+        self.compose_cursor_pos = self.compose_cursor_pos.min(match self.compose_field {
+            ComposeField::To => self.compose_to_text.len(),
+            ComposeField::Cc => self.compose_cc_text.len(),
+            ComposeField::Bcc => self.compose_bcc_text.len(),
+            _ => usize::MAX,
+        });
 
-            let mut forward_header = format!(
-                "\n\n\n\n---------- Forwarded message ----------\nFrom: {}\nDate: {}\nSubject: {}\nTo: {}",
-                from_str,
-                original.date.format("%a, %d %b %Y %H:%M:%S %z"),
-                original.subject,
-                to_str
-            );
+        let fixed: Vec<&str> = typos.iter().map(|(_, suggestion)| suggestion.as_str()).collect();
+        self.show_info(&tr_args("msg-corrected", &[("addresses", &fixed.join(", "))]));
+    }
 
-            // Add CC if present
-            if !original.cc.is_empty() {
-                let cc_str = original
-                    .cc
-                    .iter()
-                    .map(|addr| {
-                        if let Some(name) = &addr.name {
-                            format!("{} <{}>", name, addr.address)
-                        } else {
-                            addr.address.clone()
-                        }
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                forward_header.push_str(&format!("\nCc: {}", cc_str));
+    /// Recipients of the message being composed that are on the suppression list
+    pub fn suppressed_recipients(&self) -> Vec<&crate::database::SuppressedAddress> {
+        let email = &self.compose_email;
+        let mut found: Vec<&crate::database::SuppressedAddress> = Vec::new();
+        for recipient in email.to.iter().chain(&email.cc).chain(&email.bcc) {
+            if let Some(suppressed) = self.suppressed.get(&recipient.address.to_lowercase()) {
+                if !found.iter().any(|known| known.address == suppressed.address) {
+                    found.push(suppressed);
+                }
             }
+        }
+        found
+    }
 
-            forward_header.push_str("\n\n");
+    /// Remove recipients that hard-bounced from the message being composed, and from the
+    /// address book and VIPs so they are not offered again
+    fn drop_suppressed_recipients(&mut self) {
+        let dead: Vec<String> = self.suppressed_recipients().iter().map(|s| s.address.clone()).collect();
+        if dead.is_empty() {
+            self.show_info(&tr("msg-no-bounced-recipients"));
+            return;
+        }
+        let keep = |text: &str| -> String {
+            text.split(',')
+                .map(str::trim)
+                .filter(|address| !address.is_empty() && !dead.contains(&address.to_lowercase()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        self.compose_to_text = keep(&self.compose_to_text);
+        self.compose_cc_text = keep(&self.compose_cc_text);
+        self.compose_bcc_text = keep(&self.compose_bcc_text);
+        for list in [&mut self.compose_email.to, &mut self.compose_email.cc, &mut self.compose_email.bcc] {
+            list.retain(|recipient| !dead.contains(&recipient.address.to_lowercase()));
+        }
+        self.compose_cursor_pos = self.compose_cursor_pos.min(match self.compose_field {
+            ComposeField::To => self.compose_to_text.len(),
+            ComposeField::Cc => self.compose_cc_text.len(),
+            ComposeField::Bcc => self.compose_bcc_text.len(),
+            _ => usize::MAX,
+        });
 
-            // Add original body
-            if let Some(body) = &original.body_text {
-                forward.body_text = Some(format!("{}{}", forward_header, body));
-            } else {
-                forward.body_text = Some(forward_header);
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let removed = self.open_account_database(self.current_account_idx).and_then(|database| {
+            for address in &dead {
+                database.delete_contact(&account_email, address)?;
+                database.unset_vip(&account_email, address)?;
             }
+            Ok(())
+        });
+        for address in &dead {
+            self.vips.remove(address);
+        }
+        if let Some((_, known)) = self.known_recipients.as_mut() {
+            known.retain(|address| !dead.contains(&address.to_lowercase()));
+        }
+        match removed {
+            Ok(()) => self.show_info(&tr_args("msg-bounced-removed", &[("addresses", &dead.join(", "))])),
+            Err(e) => self.show_error(&tr_args("msg-bounced-removed-partly", &[("addresses", &dead.join(", ")), ("error", &e)])),
+        }
+    }
 
-            // Copy attachments
-            forward.attachments = original.attachments.clone();
+    /// Take addresses off the suppression list once mail was sent to them anyway
+    fn lift_suppression(&mut self, addresses: &[String]) {
+        let account_email = self.config.accounts[self.current_account_idx].email.clone();
+        let result = self.open_account_database(self.current_account_idx).and_then(|database| {
+            for address in addresses {
+                database.unsuppress_address(&account_email, address)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            debug_log(&format!("Failed to update the suppression list of {}: {}", account_email, e));
+        }
+        for address in addresses {
+            self.suppressed.remove(address);
+        }
+    }
 
-            self.compose_email = forward;
-            self.compose_to_text = String::new(); // Forward starts with empty To field
-            self.mode = AppMode::Compose;
-            self.focus = FocusPanel::ComposeForm;
-            self.compose_field = ComposeField::To; // Start in To field for forward
-            self.compose_cursor_pos = 0; // Position cursor at the beginning
+    /// Priority the message being composed is sent with, if any
+    pub fn compose_priority(&self) -> Option<crate::config::Priority> {
+        self.compose_email.priority()
+    }
 
-            self.show_info("Forwarding email - add recipients");
-        } else {
-            self.show_error("No email selected");
+    /// Step the outgoing priority through none, high and low
+    fn cycle_compose_priority(&mut self) {
+        use crate::config::Priority;
+        let next = match self.compose_priority() {
+            None | Some(Priority::Normal) => Some(Priority::High),
+            Some(Priority::High) => Some(Priority::Low),
+            Some(Priority::Low) => None,
+        };
+        let headers = &mut self.compose_email.headers;
+        headers.retain(|name, _| {
+            !["X-Priority", "Importance", "Priority", "X-MSMail-Priority"]
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(name))
+        });
+        if let Some(priority) = next {
+            let (x_priority, importance) = priority.header_values();
+            headers.insert("X-Priority".to_string(), x_priority.to_string());
+            headers.insert("Importance".to_string(), importance.to_string());
         }
-
-        Ok(())
     }
 
     /// Ensure the specified account is expanded in folder view
@@ -2898,7 +8334,7 @@ impl App {
                     // Exit file browser
                     self.file_browser_mode = false;
                     self.file_browser_editing_filename = false;
-                    self.show_info("File browser cancelled");
+                    self.show_info(&tr("msg-file-browser-cancelled"));
                     Ok(())
                 }
                 KeyCode::Up => {
@@ -2951,16 +8387,12 @@ impl App {
                                 // Selected a file - use its name as default but allow editing
                                 self.file_browser_save_filename = selected_item.name.clone();
                                 self.file_browser_editing_filename = true;
-                                self.show_info(
-                                    "Edit filename and press Enter to save, or Esc to cancel",
-                                );
+                                self.show_info(&tr("msg-edit-filename"));
                             }
                         } else {
                             // No selection - start editing filename
                             self.file_browser_editing_filename = true;
-                            self.show_info(
-                                "Enter filename and press Enter to save, or Esc to cancel",
-                            );
+                            self.show_info(&tr("msg-enter-filename"));
                         }
                     } else {
                         // Loading mode - select file or navigate into directory
@@ -2991,7 +8423,7 @@ impl App {
 
                     // Create Downloads directory if it doesn't exist
                     if let Err(e) = std::fs::create_dir_all(&downloads_dir) {
-                        self.show_error(&format!("Failed to create downloads directory: {}", e));
+                        self.show_error(&tr_args("msg-downloads-dir-failed", &[("error", &e)]));
                         return Ok(());
                     }
 
@@ -3005,7 +8437,7 @@ impl App {
                 KeyCode::Char('f') if self.file_browser_save_mode => {
                     // Start editing filename
                     self.file_browser_editing_filename = true;
-                    self.show_info("Enter filename and press Enter to save, or Esc to cancel");
+                    self.show_info(&tr("msg-enter-filename"));
                     Ok(())
                 }
                 KeyCode::Char('s') if self.file_browser_save_mode => {
@@ -3108,7 +8540,7 @@ impl App {
                 self.file_browser_items = items;
             }
             Err(e) => {
-                self.show_error(&format!("Failed to read directory: {}", e));
+                self.show_error(&tr_args("msg-read-dir-failed", &[("error", &e)]));
                 self.file_browser_mode = false;
             }
         }
@@ -3123,7 +8555,7 @@ impl App {
                 // Cancel attachment input
                 self.attachment_input_mode = false;
                 self.attachment_input_text.clear();
-                self.show_info("Attachment input cancelled");
+                self.show_info(&tr("msg-attachment-input-cancelled"));
                 Ok(())
             }
             KeyCode::Enter => {
@@ -3206,7 +8638,7 @@ impl App {
         self.file_browser_mode = true;
         self.load_file_browser_directory()?;
         self.file_browser_selected = 0;
-        self.show_info("TEST: File browser opened - try arrow keys and 'q' to save");
+        self.show_info(&tr("msg-file-browser-test"));
 
         Ok(())
     }
@@ -3229,13 +8661,23 @@ impl App {
             let (filename, data) = if let Some(email) = self.get_current_email() {
                 if attachment_idx < email.attachments.len() {
                     let attachment = &email.attachments[attachment_idx];
-                    (attachment.filename.clone(), attachment.data.clone())
+                    if !attachment.is_available() {
+                        self.show_error(&tr("msg-attachment-not-downloaded"));
+                        return Ok(());
+                    }
+                    match attachment.load_data() {
+                        Ok(data) => (attachment.filename.clone(), data),
+                        Err(e) => {
+                            self.show_error(&tr_args("msg-read-attachment-failed", &[("error", &e)]));
+                            return Ok(());
+                        }
+                    }
                 } else {
-                    self.show_error("Invalid attachment index");
+                    self.show_error(&tr("msg-invalid-attachment"));
                     return Ok(());
                 }
             } else {
-                self.show_error("No email selected");
+                self.show_error(&tr("msg-no-email-selected"));
                 return Ok(());
             };
 
@@ -3248,9 +8690,9 @@ impl App {
             self.file_browser_mode = true;
             self.load_file_browser_directory()?;
             self.file_browser_selected = 0;
-            self.show_info("SAVE ATTACHMENT: Press 'q' for quick save to Downloads, or use ↑↓ to navigate folders then Enter to save");
+            self.show_info(&tr("msg-save-attachment-hint"));
         } else {
-            self.show_error("No attachment selected");
+            self.show_error(&tr("msg-no-attachment-selected"));
         }
         Ok(())
     }
@@ -3259,13 +8701,13 @@ impl App {
     fn save_attachment_to_path(&mut self, path: &std::path::Path) -> AppResult<()> {
         match std::fs::write(path, &self.file_browser_save_data) {
             Ok(_) => {
-                self.show_info(&format!("Attachment saved to: {}", path.display()));
+                self.show_info(&tr_args("msg-attachment-saved", &[("path", &path.display())]));
                 // Clear save data
                 self.file_browser_save_data.clear();
                 self.file_browser_save_filename.clear();
             }
             Err(e) => {
-                self.show_error(&format!("Failed to save attachment: {}", e));
+                self.show_error(&tr_args("msg-attachment-save-failed", &[("error", &e)]));
             }
         }
         Ok(())
@@ -3275,9 +8717,7 @@ impl App {
         self.file_browser_mode = true;
         self.load_file_browser_directory()?;
         self.file_browser_selected = 0;
-        self.show_info(
-            "Navigate with ↑↓, Enter to select, Backspace for parent dir, Esc to cancel",
-        );
+        self.show_info(&tr("msg-file-browser-hint"));
         Ok(())
     }
 
@@ -3324,19 +8764,56 @@ impl App {
                 let attachment = crate::email::EmailAttachment {
                     filename,
                     content_type,
+                    size: data.len(),
                     data,
+                    path: None,
                 };
 
                 self.compose_email.attachments.push(attachment);
-                self.show_info(&format!("Added attachment: {}", expanded_path));
+                self.show_info(&tr_args("msg-attachment-added", &[("path", &expanded_path)]));
             }
             Err(e) => {
-                self.show_error(&format!("Failed to read file {}: {}", expanded_path, e));
+                self.show_error(&tr_args("msg-read-file-failed", &[("path", &expanded_path), ("error", &e)]));
             }
         }
         Ok(())
     }
 
+    /// Apply one of the `quoting` helpers to the body at the cursor
+    fn edit_body_quoting(&mut self, edit: fn(&str, usize) -> (String, usize)) {
+        if self.compose_field != ComposeField::Body {
+            return;
+        }
+        let Some(body) = &self.compose_email.body_text else {
+            return;
+        };
+        let (body, cursor) = edit(body, self.compose_cursor_pos);
+        self.compose_email.body_text = Some(body);
+        self.compose_cursor_pos = cursor;
+        self.start_spell_check();
+        self.request_grammar_check();
+    }
+
+    /// Attach the image on the system clipboard, e.g. a screenshot, as a PNG
+    pub fn attach_clipboard_image(&mut self) {
+        match crate::clipboard::read_png() {
+            Ok(data) => {
+                let existing: Vec<String> = self.compose_email.attachments.iter().map(|a| a.filename.clone()).collect();
+                let filename = crate::clipboard::pasted_image_name(&existing, chrono::Local::now());
+                let size = data.len();
+                self.compose_email.attachments.push(crate::email::EmailAttachment {
+                    filename: filename.clone(),
+                    content_type: "image/png".to_string(),
+                    data,
+                    path: None,
+                    size,
+                });
+                self.show_info(&tr_args("msg-clipboard-attached", &[("filename", &filename), ("size", &size.div_ceil(1024))]));
+            }
+            Err(e) => self.show_error(&e),
+        }
+    }
+
     /// Remove the selected attachment from compose email
     pub fn remove_selected_attachment(&mut self) -> AppResult<()> {
         if let Some(idx) = self.selected_attachment_idx {
@@ -3351,10 +8828,10 @@ impl App {
                     self.selected_attachment_idx = Some(self.compose_email.attachments.len().saturating_sub(1));
                 }
 
-                self.show_info(&format!("Removed attachment: {}", filename));
+                self.show_info(&tr_args("msg-attachment-removed", &[("filename", &filename)]));
             }
         } else {
-            self.show_info("No attachment selected");
+            self.show_info(&tr("msg-no-attachment-selected"));
         }
         Ok(())
     }
@@ -3362,18 +8839,28 @@ impl App {
     /// Rotate to the next account and load its INBOX
     pub fn rotate_to_next_account(&mut self) -> AppResult<()> {
         if self.config.accounts.len() <= 1 {
-            self.show_info("Only one account configured");
+            self.show_info(&tr("msg-only-one-account"));
             return Ok(());
         }
 
         // Calculate next account index
         let next_account_idx = (self.current_account_idx + 1) % self.config.accounts.len();
+        self.switch_to_account(next_account_idx)
+    }
+
+    /// Make the account current and load its INBOX
+    fn switch_to_account(&mut self, next_account_idx: usize) -> AppResult<()> {
+        self.contact_filter = None;
+        self.read_later_view = false;
+        self.vip_view = false;
+        self.notifications_view = false;
+        self.search_query = None;
 
         // Switch to the next account
         self.current_account_idx = next_account_idx;
 
-        // Initialize the account if needed (only if not already initialized)
-        self.ensure_account_initialized(next_account_idx)?;
+        // Connect in the background if needed; cached mail is shown meanwhile
+        self.connect_account_in_background(next_account_idx);
 
         // Check if we already have emails cached for this account
         let need_to_load_emails = if let Some(account_data) = self.accounts.get(&next_account_idx) {
@@ -3387,17 +8874,25 @@ impl App {
         if need_to_load_emails {
             // Load INBOX for the new account only if not cached
             if let Err(e) = self.load_emails_for_account_folder(next_account_idx, "INBOX") {
-                self.show_error(&format!("Failed to load INBOX for account: {}", e));
+                self.show_error(&tr_args("msg-load-inbox-failed", &[("error", &e)]));
             }
         } else {
+            let folder = self
+                .accounts
+                .get(&next_account_idx)
+                .and_then(|account_data| account_data.emails.first())
+                .map(|email| email.folder.clone());
+            if let Some(folder) = folder {
+                self.request_server_threads(next_account_idx, &folder);
+            }
             // Use cached emails from the account
             if let Some(account_data) = self.accounts.get(&next_account_idx) {
-                self.emails = account_data.emails.clone();
+                self.emails = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
             }
         }
 
         let account_name = &self.config.accounts[next_account_idx].name;
-        self.show_info(&format!("Switched to account: {}", account_name));
+        self.show_info(&tr_args("msg-switched-account", &[("account", &account_name)]));
 
         // Reset selection
         self.selected_email_idx = if self.emails.is_empty() {
@@ -3494,12 +8989,14 @@ impl App {
             let account_email = &account_data.account.email;
             let folder = &self.selected_folder;
             
-            // Load emails from database
-            match self.database.load_emails(account_email, folder) {
+            // Load as many of the newest rows as are shown; new mail is among them
+            let limit = account_data.emails.len().max(EMAIL_PAGE_SIZE);
+            match self.database.get_emails_paginated(account_email, folder, 0, limit) {
                 Ok(db_emails) => {
                     // Check if we have new emails compared to what's currently in UI
+                    // Compare with the whole folder; the list may be narrowed by importance
                     let current_email_ids: std::collections::HashSet<String> = 
-                        self.emails.iter().map(|e| e.id.clone()).collect();
+                        account_data.emails.iter().map(|e| e.id.clone()).collect();
                     
                     let new_emails: Vec<crate::email::Email> = db_emails
                         .iter()
@@ -3513,10 +9010,20 @@ impl App {
                             new_emails.len()
                         ));
 
+                        // Mail in muted conversations and in folders marked never to notify
+                        // arrives without a notice, and during quiet hours only mail from VIPs
+                        // is announced
                         let new_count = new_emails.len();
+                        let notice = if account_data.account.notifies(folder) {
+                            let announced: Vec<&Email> = new_emails.iter().filter(|email| self.muted_thread(email).is_none()).collect();
+                            let quiet = crate::vip::in_quiet_hours(&self.config.notifications, Local::now().time());
+                            crate::vip::new_mail_notice(&announced, &self.vips, quiet)
+                        } else {
+                            None
+                        };
 
                         // Merge new emails with existing ones
-                        let mut all_emails = self.emails.clone();
+                        let mut all_emails = account_data.emails.clone();
                         all_emails.extend(new_emails);
 
                         // Remove duplicates based on email ID (UID)
@@ -3536,46 +9043,39 @@ impl App {
                         debug_log(&format!(
                             "Merged emails: {} new + {} existing = {} total (after dedup and sort)",
                             new_count,
-                            self.emails.len(),
+                            account_data.emails.len(),
                             all_emails.len()
                         ));
 
-                        self.emails = all_emails;
+                        let added = all_emails.len().saturating_sub(account_data.emails.len());
+                        let shown = self.thread_view.arrange(self.importance_view.apply(&all_emails));
+                        self.replace_email_list(shown);
+                        self.folder_email_count += added;
 
                         // Update the account's cached emails
                         if let Some(account_data) = self.accounts.get_mut(&self.current_account_idx)
                         {
-                            account_data.emails = self.emails.clone();
+                            account_data.emails = all_emails;
                         }
 
-                        // Keep current selection if valid, otherwise select first email
-                        if let Some(selected_idx) = self.selected_email_idx {
-                            if selected_idx >= self.emails.len() {
-                                self.selected_email_idx = if self.emails.is_empty() {
-                                    None
-                                } else {
-                                    Some(0)
-                                };
-                            }
-                        } else if !self.emails.is_empty() {
-                            self.selected_email_idx = Some(0);
+                        if let Some(notice) = notice {
+                            self.show_info(&notice);
                         }
-
-                        self.show_info(&format!("Found {} new emails", new_count));
                     } else {
                         // Update emails from database even if no new ones (in case of changes)
-                        if db_emails.len() != self.emails.len() {
+                        if db_emails.len() != account_data.emails.len() {
                             debug_log(&format!(
                                 "Email count changed: {} in DB vs {} in UI, updating",
                                 db_emails.len(),
-                                self.emails.len()
+                                account_data.emails.len()
                             ));
-                            self.emails = db_emails;
-                            
+                            let shown = self.thread_view.arrange(self.importance_view.apply(&db_emails));
+                            self.replace_email_list(shown);
+
                             // Update the account's cached emails
                             if let Some(account_data) = self.accounts.get_mut(&self.current_account_idx)
                             {
-                                account_data.emails = self.emails.clone();
+                                account_data.emails = db_emails;
                             }
                         }
                     }
@@ -3587,10 +9087,65 @@ impl App {
         }
     }
 
+    /// Record an operation on one message in the audit log
+    fn audit_email(&self, account_email: &str, email: &Email, action: &str) {
+        let sender = email.from.first().map(|a| a.address.as_str()).unwrap_or_default();
+        crate::audit::record(
+            &self.database,
+            account_email,
+            &email.folder,
+            email.id.parse().ok(),
+            action,
+            crate::audit::Origin::Ui,
+            &format!("{}: {}", sender, email.subject),
+        );
+    }
+
+    /// Bounce the open email to the addresses typed at the prompt
+    fn bounce_current_email(&mut self) -> AppResult<()> {
+        let Some(email) = self.selected_email_idx.and_then(|idx| self.emails.get(idx)).cloned() else {
+            self.show_error(&tr("msg-no-email-selected"));
+            return Ok(());
+        };
+        let to: Vec<crate::email::EmailAddress> = self
+            .bounce_text
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(|address| crate::email::EmailAddress { name: None, address: address.to_string() })
+            .collect();
+        if to.is_empty() {
+            self.show_error(&tr("msg-no-recipient"));
+            return Ok(());
+        }
+
+        self.ensure_account_initialized(self.current_account_idx)?;
+        let Some(account_data) = self.accounts.get(&self.current_account_idx) else {
+            self.show_error(&tr("msg-account-not-found"));
+            return Ok(());
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-client-not-initialized"));
+            return Ok(());
+        };
+        let account_email = account_data.account.email.clone();
+
+        let recipients = to.iter().map(|a| a.address.as_str()).collect::<Vec<_>>().join(", ");
+        match client.bounce_email(&email, &to) {
+            Ok(()) => {
+                self.audit_email(&account_email, &email, &format!("bounce to {}", recipients));
+                self.show_info(&tr_args("msg-bounced-to", &[("recipients", &recipients)]));
+            }
+            Err(e) if crate::auth::is_auth_error(&e) => self.show_auth_problem(self.current_account_idx, &e),
+            Err(e) => self.show_error(&tr_args("msg-bounce-failed", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
     pub fn delete_selected_email(&mut self) -> AppResult<()> {
         if let Some(idx) = self.selected_email_idx {
             if idx >= self.emails.len() {
-                self.show_error("Invalid email selection");
+                self.show_error(&tr("msg-invalid-email-selection"));
                 return Ok(());
             }
 
@@ -3605,6 +9160,8 @@ impl App {
                 if let Some(client) = &account_data.email_client {
                     match client.delete_email(&email) {
                         Ok(_) => {
+                            let account_email = account_data.account.email.clone();
+                            self.audit_email(&account_email, &email, "delete");
                             self.emails.remove(idx);
 
                             // Adjust selection after deletion
@@ -3617,21 +9174,21 @@ impl App {
                             // If we deleted an email in the middle, the selection stays the same
                             // which will now point to the next email
 
-                            self.show_info("Email deleted");
+                            self.show_info(&tr("msg-email-deleted"));
                         }
                         Err(e) => {
-                            self.show_error(&format!("Failed to delete email: {}", e));
+                            self.show_error(&tr_args("msg-delete-failed", &[("error", &e)]));
                             return Err(AppError::EmailError(e));
                         }
                     }
                 } else {
-                    self.show_error("Email client not initialized for current account");
+                    self.show_error(&tr("msg-client-not-initialized"));
                 }
             } else {
-                self.show_error("Current account not found");
+                self.show_error(&tr("msg-account-not-found"));
             }
         } else {
-            self.show_error("No email selected");
+            self.show_error(&tr("msg-no-email-selected"));
         }
 
         Ok(())
@@ -3639,6 +9196,32 @@ impl App {
 
     /// Send the composed email using the current account
     pub fn send_email(&mut self) -> AppResult<()> {
+        if crate::email::is_read_only() {
+            self.show_error(&tr("msg-read-only-sending"));
+            return Ok(());
+        }
+
+        let email = &self.compose_email;
+        if let Some(name) = email.to.iter().chain(&email.cc).chain(&email.bcc).find(|r| !r.address.contains('@')) {
+            self.show_error(&tr_args("msg-not-an-address", &[("name", &name.address)]));
+            return Ok(());
+        }
+
+        let typos = self.recipient_typos();
+        if !typos.is_empty() && !self.recipient_typos_confirmed {
+            self.recipient_typos_confirmed = true;
+            let (typo, suggestion) = &typos[0];
+            self.show_error(&tr_args("msg-recipient-typo", &[("typo", &typo), ("suggestion", &suggestion)]));
+            return Ok(());
+        }
+
+        let sent_anyway: Vec<String> = self.suppressed_recipients().iter().map(|s| s.address.clone()).collect();
+        if !sent_anyway.is_empty() && !self.suppressed_recipients_confirmed {
+            self.suppressed_recipients_confirmed = true;
+            self.show_error(&tr_args("msg-recipient-bounced", &[("addresses", &sent_anyway.join(", "))]));
+            return Ok(());
+        }
+
         // Ensure the current account is initialized
         self.ensure_account_initialized(self.current_account_idx)?;
 
@@ -3654,43 +9237,131 @@ impl App {
                     });
                 }
 
-                match client.send_email(&self.compose_email) {
-                    Ok(_) => {
+                let text = self.compose_email.body_text.as_deref().unwrap_or("");
+                self.compose_email.body_html = (self.compose_format == crate::config::ComposeFormat::Markdown)
+                    .then(|| crate::markdown::to_html(text));
+
+                // Known up front so the server's copy in Sent can replace the local one
+                if self.compose_email.header("Message-ID").is_none() {
+                    let message_id = crate::transport::new_message_id(&account_data.account.email);
+                    self.compose_email.headers.insert("Message-ID".to_string(), message_id);
+                }
+
+                match client.send_email(&self.compose_email, self.compose_pgp) {
+                    Ok(fallback) => {
+                        let recipients: Vec<&str> = self.compose_email.to.iter()
+                            .chain(&self.compose_email.cc)
+                            .chain(&self.compose_email.bcc)
+                            .map(|a| a.address.as_str())
+                            .collect();
+                        let detail = format!("to {}: {}", recipients.join(", "), self.compose_email.subject);
+                        let account_email = account_data.account.email.clone();
+                        crate::audit::record(&self.database, &account_email, "", None, "send", crate::audit::Origin::Ui, &detail);
+
                         let attachment_count = self.compose_email.attachments.len();
                         if attachment_count > 0 {
-                            self.show_info(&format!(
-                                "Email sent successfully with {} attachment(s)",
-                                attachment_count
-                            ));
+                            self.show_info(&tr_args("msg-sent-with-attachments", &[("count", &attachment_count)]));
                         } else {
-                            self.show_info("Email sent successfully");
+                            self.show_info(&tr("msg-sent"));
+                        }
+
+                        // A sent draft is removed here and from the Drafts folder
+                        if let Some(message_id) = self.compose_draft_id.take() {
+                            self.discard_draft(&message_id);
+                        }
+                        let sent = std::mem::replace(&mut self.compose_email, crate::email::Email::new());
+                        self.store_sent_copy(sent);
+                        if let Some(source) = self.compose_source.take() {
+                            self.mark_compose_source(source);
+                        }
+                        // A new hard bounce puts them back on the list
+                        if !sent_anyway.is_empty() {
+                            self.lift_suppression(&sent_anyway);
                         }
 
                         // Clear the compose form
-                        self.compose_email = crate::email::Email::new();
                         self.compose_to_text.clear();
                         self.compose_cc_text.clear();
                         self.compose_bcc_text.clear();
 
                         self.mode = AppMode::Normal;
                         self.focus = FocusPanel::EmailList;
+
+                        if let Some(route) = fallback {
+                            match self.config.accounts[self.current_account_idx].smtp_fallback {
+                                crate::config::SmtpFallback::Apply => self.use_smtp_route(self.current_account_idx, &route),
+                                _ => {
+                                    self.smtp_fallback_offer = Some((self.current_account_idx, route));
+                                    self.mode = AppMode::SmtpFallbackConfirm;
+                                }
+                            }
+                        }
                         Ok(())
                     }
                     Err(e) => {
-                        self.show_error(&format!("Failed to send email: {}", e));
+                        if crate::auth::is_auth_error(&e) {
+                            // The dialog explains it; the compose form is kept for a retry
+                            self.show_auth_problem(self.current_account_idx, &e);
+                            return Ok(());
+                        }
+                        self.show_error(&tr_args("msg-send-failed", &[("error", &e)]));
                         Err(AppError::EmailError(e))
                     }
                 }
             } else {
-                self.show_error("Email client not initialized for current account");
+                self.show_error(&tr("msg-client-not-initialized"));
                 Ok(())
             }
         } else {
-            self.show_error("Current account not found");
+            self.show_error(&tr("msg-account-not-found"));
             Ok(())
         }
     }
 
+    /// Put a copy of a message just sent into the cached Sent folder, so the
+    /// conversation shows it right away rather than after the Sent folder syncs. The
+    /// server's copy replaces it when it arrives.
+    fn store_sent_copy(&mut self, mut sent: Email) {
+        let cross_folder = self.cross_folder_list();
+        let Some(account_data) = self.accounts.get_mut(&self.current_account_idx) else {
+            return;
+        };
+        let Some(folder) = account_data.account.sent_folder(&account_data.folders).cloned() else {
+            return;
+        };
+        sent.date = Local::now();
+        sent.seen = true;
+        sent.flags = vec!["\\Seen".to_string()];
+        sent.bcc.clear();
+        match self.database.save_sent_copy(&account_data.account.email, &folder, &sent) {
+            Ok(uid) => {
+                sent.id = uid.to_string();
+                sent.folder = folder.clone();
+            }
+            Err(e) => {
+                debug_log(&format!("Failed to store sent copy in {}: {}", folder, e));
+                return;
+            }
+        }
+
+        let in_sent = account_data.folders.get(account_data.selected_folder_idx) == Some(&folder);
+        if in_sent {
+            account_data.emails.insert(0, sent.clone());
+        }
+        if let Some(address) = &self.contact_filter {
+            // The conversation reads oldest to newest, so the reply goes last
+            let with_contact = sent.to.iter().chain(&sent.cc).any(|to| to.address.eq_ignore_ascii_case(address));
+            if with_contact && self.search_query.is_none() && !self.read_later_view && !self.vip_view && !self.notifications_view {
+                let mut emails = self.emails.clone();
+                emails.push(sent);
+                self.replace_email_list(emails);
+            }
+        } else if in_sent && !cross_folder {
+            let shown = self.thread_view.arrange(self.importance_view.apply(&account_data.emails));
+            self.replace_email_list(shown);
+        }
+    }
+
     pub fn show_error(&mut self, message: &str) {
         self.error_message = Some(message.to_string());
         self.message_timeout = Some(Instant::now() + Duration::from_secs(5));
@@ -3701,7 +9372,105 @@ impl App {
         self.message_timeout = Some(Instant::now() + Duration::from_secs(3));
     }
 
+    /// Queue background downloads for attachments of the open email that aren't cached yet
+    pub fn queue_missing_attachment_downloads(&mut self) {
+        let Some(email) = self.get_current_email().cloned() else {
+            return;
+        };
+        let missing: Vec<usize> = email
+            .attachments
+            .iter()
+            .enumerate()
+            .filter(|(_, attachment)| !attachment.is_available())
+            .map(|(idx, _)| idx)
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        if let Some(free) = self.disk_space_low {
+            self.show_error(&tr_args("msg-attachments-disk-low", &[("free", &crate::attachment_index::format_size(free as usize))]));
+            return;
+        }
+
+        let Some(account_data) = self.accounts.get(&self.current_account_idx) else {
+            return;
+        };
+        let Some(client) = account_data.email_client.clone() else {
+            self.show_error(&tr("msg-attachments-no-account"));
+            return;
+        };
+        let account_email = account_data.account.email.clone();
+
+        for idx in &missing {
+            self.attachment_downloads.enqueue(client.clone(), &account_email, &email, *idx);
+        }
+        self.show_info(&tr_args("msg-attachments-downloading", &[("count", &missing.len())]));
+    }
+
+    /// Cancel the download of the selected attachment in the open email
+    pub fn cancel_selected_attachment_download(&mut self) {
+        let Some(attachment_idx) = self.selected_attachment_idx else {
+            self.show_error(&tr("msg-no-attachment-selected"));
+            return;
+        };
+        let Some(email) = self.get_current_email() else {
+            return;
+        };
+        let account_email = self.config.accounts
+            .get(self.current_account_idx)
+            .map(|account| account.email.clone())
+            .unwrap_or_default();
+
+        let job_id = self.attachment_downloads
+            .job_for(&account_email, &email.folder, &email.id, attachment_idx)
+            .filter(|job| job.status.is_active())
+            .map(|job| job.id);
+
+        match job_id {
+            Some(id) if self.attachment_downloads.cancel(id) => self.show_info(&tr("msg-download-cancelled")),
+            _ => self.show_error(&tr("msg-no-download")),
+        }
+    }
+
+    /// Apply finished background attachment downloads to the loaded emails
+    fn process_attachment_downloads(&mut self) {
+        for (job, attachment) in self.attachment_downloads.poll() {
+            let is_current_account = self.config.accounts
+                .get(self.current_account_idx)
+                .map(|account| account.email == job.account_email)
+                .unwrap_or(false);
+            if !is_current_account {
+                continue;
+            }
+
+            if let Some(email) = self.emails
+                .iter_mut()
+                .find(|email| email.id == job.email_id && email.folder == job.folder)
+            {
+                if let Some(slot) = email.attachments.get_mut(job.attachment_idx) {
+                    *slot = attachment;
+                }
+            }
+            debug_log(&format!("Attachment '{}' downloaded for email {}", job.filename, job.email_id));
+        }
+    }
+
     pub fn tick(&mut self) -> AppResult<()> {
+        self.process_attachment_downloads();
+
+        // Waits for startup dialogs, such as for missing passwords, to be dealt with
+        if self.mode == AppMode::Normal {
+            if let Some(mailto) = self.pending_compose.take() {
+                self.compose_from_mailto(mailto);
+            }
+        }
+
+        if self.checks_due.is_some_and(|due| Instant::now() >= due) {
+            self.checks_due = None;
+            self.start_spell_check();
+            self.request_grammar_check();
+        }
+
         // Clear messages after timeout
         if let Some(timeout) = self.message_timeout {
             if std::time::Instant::now() > timeout {
@@ -3714,3 +9483,42 @@ impl App {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emails(uids: &[u32]) -> Vec<Email> {
+        uids.iter()
+            .map(|uid| Email { id: uid.to_string(), ..Email::new() })
+            .collect()
+    }
+
+    fn marks(uids: &[u32]) -> std::collections::HashSet<(String, String)> {
+        uids.iter().map(|uid| ("INBOX".to_string(), uid.to_string())).collect()
+    }
+
+    #[test]
+    fn selection_follows_the_message() {
+        // A new message on top pushes the selected one and the first visible row down
+        let old = emails(&[5, 4, 3, 2, 1]);
+        let new = emails(&[6, 5, 4, 3, 2, 1]);
+        assert_eq!(carry_list_state(&old, &new, Some(2), 1, &mut marks(&[])), (Some(3), 2));
+    }
+
+    #[test]
+    fn selection_stays_on_the_row_of_a_removed_message() {
+        let old = emails(&[5, 4, 3, 2, 1]);
+        assert_eq!(carry_list_state(&old, &emails(&[5, 4, 2, 1]), Some(2), 0, &mut marks(&[])), (Some(2), 0));
+        assert_eq!(carry_list_state(&old, &emails(&[5, 4]), Some(4), 0, &mut marks(&[])), (Some(1), 0));
+        assert_eq!(carry_list_state(&old, &[], Some(4), 0, &mut marks(&[])), (None, 0));
+    }
+
+    #[test]
+    fn marks_are_kept_for_messages_still_listed() {
+        let old = emails(&[5, 4, 3, 2, 1]);
+        let mut marked = marks(&[4, 2]);
+        carry_list_state(&old, &emails(&[6, 5, 4, 3, 1]), Some(0), 0, &mut marked);
+        assert_eq!(marked, marks(&[4]));
+    }
+}
@@ -0,0 +1,84 @@
+use ratatui::buffer::Buffer;
+
+/// Glyphs the UI draws with and their ASCII stand-ins. Box drawing, arrows and emoji
+/// indicators all come out garbled on some remote terminals and fonts.
+const REPLACEMENTS: &[(&str, &str)] = &[
+    ("▼", "v"),
+    ("▶", ">"),
+    ("↑", "^"),
+    ("↓", "v"),
+    ("←", "<"),
+    ("→", ">"),
+    ("↩", "<"),
+    ("⇄", "="),
+    ("·", "-"),
+    ("…", "."),
+    ("—", "-"),
+    ("–", "-"),
+    ("✗", "x"),
+    ("✓", "v"),
+    ("⚠", "!"),
+    ("📁", "+"),
+    ("📄", "-"),
+    ("📎", "@"),
+    ("🔖", "*"),
+];
+
+/// ASCII replacement for one terminal cell, if its symbol needs one. Letters and other
+/// text are left alone so mail in other languages stays readable.
+pub fn ascii_symbol(symbol: &str) -> Option<&'static str> {
+    if symbol.is_ascii() {
+        return None;
+    }
+    if let Some((_, ascii)) = REPLACEMENTS.iter().find(|(glyph, _)| *glyph == symbol) {
+        return Some(ascii);
+    }
+    let c = symbol.chars().next()?;
+    match c {
+        // Box drawing: lines stay lines, every junction and corner becomes a plus
+        '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' => Some("-"),
+        '│' | '┃' | '║' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' => Some("|"),
+        '\u{2500}'..='\u{257F}' => Some("+"),
+        // Block elements (avatar thumbnails, gauges) and shapes
+        '\u{2580}'..='\u{259F}' => Some(" "),
+        '\u{25A0}'..='\u{25FF}' => Some("*"),
+        // Other arrows, symbols, dingbats and emoji; also variation selectors left
+        // behind by emoji such as "⚠️"
+        '\u{2190}'..='\u{21FF}' | '\u{2600}'..='\u{27BF}' | '\u{1F300}'..='\u{1FAFF}' => Some("*"),
+        '\u{FE00}'..='\u{FE0F}' => Some(""),
+        _ => None,
+    }
+}
+
+/// Replace the glyphs of a drawn frame with ASCII, keeping colours and layout
+pub fn asciify(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        if let Some(ascii) = ascii_symbol(&cell.symbol) {
+            cell.set_symbol(if ascii.is_empty() { " " } else { ascii });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::widgets::{Block, Borders, Widget};
+
+    #[test]
+    fn test_asciify_keeps_text_and_replaces_chrome() {
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buffer = Buffer::empty(area);
+        Block::default().borders(Borders::ALL).title("Grüße ↑").render(area, &mut buffer);
+        buffer.set_string(1, 1, "📎 ok", Default::default());
+        asciify(&mut buffer);
+
+        let rows: Vec<String> = (0..3)
+            .map(|y| (0..12).map(|x| buffer.get(x, y).symbol.clone()).collect())
+            .collect();
+        assert_eq!(rows[0], "+Grüße ^---+");
+        assert!(rows[1].starts_with("|@  ok"));
+        assert_eq!(rows[2], "+----------+");
+        assert!(rows.iter().all(|row| row.chars().all(|c| c.is_ascii() || "rüß".contains(c))));
+    }
+}
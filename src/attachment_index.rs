@@ -0,0 +1,192 @@
+use chrono::{Local, NaiveDate, TimeZone};
+
+use crate::database::AttachmentIndexEntry;
+
+/// Largest text attachment rendered in the preview pane
+pub const PREVIEW_LIMIT: usize = 64 * 1024;
+
+/// Filter for the attachment browser, parsed from a query such as
+/// `type:pdf from:alice after:2024-03-01 larger:100k invoice`.
+///
+/// Supported terms:
+/// - `type:<text>`   content type or file extension contains the text
+/// - `from:<text>`   sender name or address contains the text
+/// - `after:<date>` / `before:<date>`   message date bounds (YYYY-MM-DD)
+/// - `larger:<size>` / `smaller:<size>` size bounds, with optional k/m/g suffix
+///
+/// Any other word must appear in the filename or the message subject.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttachmentFilter {
+    pub content_type: Option<String>,
+    pub sender: Option<String>,
+    pub after: Option<i64>,
+    pub before: Option<i64>,
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    pub words: Vec<String>,
+}
+
+impl AttachmentFilter {
+    /// Parse a filter query. Terms with an unparseable value are reported as errors.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut filter = AttachmentFilter::default();
+
+        for term in query.split_whitespace() {
+            let Some((key, value)) = term.split_once(':').filter(|(_, value)| !value.is_empty()) else {
+                filter.words.push(term.to_lowercase());
+                continue;
+            };
+
+            match key.to_lowercase().as_str() {
+                "type" => filter.content_type = Some(value.to_lowercase()),
+                "from" => filter.sender = Some(value.to_lowercase()),
+                "after" => filter.after = Some(parse_date(value)?),
+                "before" => filter.before = Some(parse_date(value)?),
+                "larger" => filter.min_size = Some(parse_size(value)?),
+                "smaller" => filter.max_size = Some(parse_size(value)?),
+                _ => filter.words.push(term.to_lowercase()),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    pub fn matches(&self, entry: &AttachmentIndexEntry) -> bool {
+        if let Some(content_type) = &self.content_type {
+            let extension = entry
+                .filename
+                .rsplit_once('.')
+                .map(|(_, ext)| ext.to_lowercase())
+                .unwrap_or_default();
+            if !entry.content_type.to_lowercase().contains(content_type.as_str()) && extension != *content_type {
+                return false;
+            }
+        }
+        if let Some(sender) = &self.sender {
+            if !entry.sender.to_lowercase().contains(sender.as_str()) {
+                return false;
+            }
+        }
+        if self.after.is_some_and(|after| entry.date < after) {
+            return false;
+        }
+        if self.before.is_some_and(|before| entry.date >= before) {
+            return false;
+        }
+        if self.min_size.is_some_and(|min| entry.size < min) {
+            return false;
+        }
+        if self.max_size.is_some_and(|max| entry.size > max) {
+            return false;
+        }
+
+        let filename = entry.filename.to_lowercase();
+        let subject = entry.subject.to_lowercase();
+        self.words
+            .iter()
+            .all(|word| filename.contains(word.as_str()) || subject.contains(word.as_str()))
+    }
+}
+
+/// Whether an attachment can be shown as text in the preview pane
+pub fn is_previewable(content_type: &str, filename: &str) -> bool {
+    let content_type = content_type.to_lowercase();
+    let filename = filename.to_lowercase();
+    content_type.starts_with("text/")
+        || content_type.ends_with("/json")
+        || content_type.ends_with("/xml")
+        || [".txt", ".csv", ".log", ".md", ".json", ".xml", ".ics", ".vcf"]
+            .iter()
+            .any(|ext| filename.ends_with(ext))
+}
+
+/// Human readable size, e.g. "1.5 MB"
+pub fn format_size(size: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", size)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Local midnight of a YYYY-MM-DD date as a Unix timestamp
+pub(crate) fn parse_date(value: &str) -> Result<i64, String> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value))?;
+    let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|datetime| datetime.timestamp())
+        .ok_or_else(|| format!("Invalid local date '{}'", value))
+}
+
+fn parse_size(value: &str) -> Result<usize, String> {
+    let lower = value.to_lowercase();
+    let trimmed = lower.trim_end_matches('b');
+    let (number, multiplier) = match trimmed.chars().last() {
+        Some('k') => (&trimmed[..trimmed.len() - 1], 1024),
+        Some('m') => (&trimmed[..trimmed.len() - 1], 1024 * 1024),
+        Some('g') => (&trimmed[..trimmed.len() - 1], 1024 * 1024 * 1024),
+        _ => (trimmed, 1),
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|n| *n >= 0.0)
+        .map(|n| (n * multiplier as f64) as usize)
+        .ok_or_else(|| format!("Invalid size '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(filename: &str, content_type: &str, size: usize) -> AttachmentIndexEntry {
+        AttachmentIndexEntry {
+            folder: "INBOX".to_string(),
+            email_uid: 1,
+            filename: filename.to_string(),
+            content_type: content_type.to_string(),
+            size,
+            sender: "Alice <alice@example.com>".to_string(),
+            subject: "March invoice".to_string(),
+            date: parse_date("2024-03-15").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("100"), Ok(100));
+        assert_eq!(parse_size("2k"), Ok(2048));
+        assert_eq!(parse_size("1.5MB"), Ok(1536 * 1024));
+        assert!(parse_size("lots").is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let pdf = entry("Invoice-2024.pdf", "application/pdf", 200 * 1024);
+        let image = entry("photo.jpg", "image/jpeg", 3 * 1024 * 1024);
+
+        let filter = AttachmentFilter::parse("type:pdf from:alice after:2024-03-01 before:2024-04-01").unwrap();
+        assert!(filter.matches(&pdf));
+        assert!(!filter.matches(&image));
+
+        let filter = AttachmentFilter::parse("larger:1m").unwrap();
+        assert!(!filter.matches(&pdf));
+        assert!(filter.matches(&image));
+
+        let filter = AttachmentFilter::parse("invoice").unwrap();
+        assert!(filter.matches(&pdf));
+        assert!(filter.matches(&image)); // matched through the subject
+
+        assert!(AttachmentFilter::parse("after:March").is_err());
+    }
+}
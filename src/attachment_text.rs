@@ -0,0 +1,222 @@
+use std::io::{Cursor, Read};
+
+use flate2::read::ZlibDecoder;
+
+/// Text kept per attachment for search; the start of a long document is enough to find it
+const MAX_TEXT_CHARS: usize = 100_000;
+
+/// Attachments larger than this are not opened for their text
+pub const MAX_EXTRACT_BYTES: usize = 20 * 1024 * 1024;
+
+const DOCX_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// The searchable text of an attachment, for PDF, DOCX and plain text or CSV files.
+/// `None` for other types and for files the text can't be read from.
+pub fn extract_text(content_type: &str, filename: &str, data: &[u8]) -> Option<String> {
+    if data.is_empty() || data.len() > MAX_EXTRACT_BYTES {
+        return None;
+    }
+    let content_type = content_type.to_lowercase();
+    let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+
+    let text = if content_type == "application/pdf" || extension == "pdf" {
+        pdf_text(data)
+    } else if content_type == DOCX_TYPE || extension == "docx" {
+        docx_text(data)
+    } else if content_type == "text/plain" || content_type == "text/csv" || extension == "txt" || extension == "csv" {
+        Some(String::from_utf8_lossy(data).into_owned())
+    } else {
+        None
+    }?;
+
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(text.chars().take(MAX_TEXT_CHARS).collect())
+}
+
+/// The text runs of `word/document.xml`, a line per paragraph
+fn docx_text(data: &[u8]) -> Option<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).ok()?;
+    let mut xml = String::new();
+    archive.by_name("word/document.xml").ok()?.read_to_string(&mut xml).ok()?;
+
+    let mut text = String::new();
+    let mut rest = xml.as_str();
+    while let Some(start) = rest.find('<') {
+        text.push_str(&unescape_xml(&rest[..start]));
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        match tag.split([' ', '/']).find(|name| !name.is_empty()).unwrap_or_default() {
+            "w:p" if tag.starts_with('/') => text.push('\n'),
+            "w:tab" | "w:br" => text.push(' '),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    Some(text)
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Text shown by the `Tj`/`TJ` operators of a PDF's content streams. Only literal
+/// strings are read, so text in fonts with custom encodings (common for CJK) is missed.
+fn pdf_text(data: &[u8]) -> Option<String> {
+    if !data.starts_with(b"%PDF") {
+        return None;
+    }
+    let mut text = String::new();
+    let mut rest = data;
+    while let Some(start) = find(rest, b"stream") {
+        let dictionary = &rest[..start];
+        let mut body = &rest[start + b"stream".len()..];
+        // The stream data starts after the end of the keyword's line
+        if body.starts_with(b"\r\n") {
+            body = &body[2..];
+        } else if body.starts_with(b"\n") {
+            body = &body[1..];
+        }
+        let Some(end) = find(body, b"endstream") else {
+            break;
+        };
+        let raw = &body[..end];
+        // Only the dictionary of this stream, not earlier objects
+        let dictionary = match find_last(dictionary, b"obj") {
+            Some(i) => &dictionary[i..],
+            None => dictionary,
+        };
+        if find(dictionary, b"/FlateDecode").is_some() {
+            let mut decoded = Vec::new();
+            if ZlibDecoder::new(raw).read_to_end(&mut decoded).is_ok() {
+                content_stream_text(&decoded, &mut text);
+            }
+        } else if find(dictionary, b"/Filter").is_none() {
+            content_stream_text(raw, &mut text);
+        }
+        rest = &body[end + b"endstream".len()..];
+    }
+    Some(text)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn find_last(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|window| window == needle)
+}
+
+/// Append the strings shown between `BT` and `ET` of a content stream. Separate
+/// show operators and wide gaps inside a `TJ` array become spaces.
+fn content_stream_text(stream: &[u8], text: &mut String) {
+    let mut in_text = false;
+    let mut i = 0;
+    while i < stream.len() {
+        match stream[i] {
+            b'(' if in_text => {
+                let (string, end) = literal_string(stream, i + 1);
+                text.push_str(&string);
+                i = end;
+                continue;
+            }
+            b']' if in_text => text.push(' '),
+            b'-' if in_text => {
+                // A kerning adjustment inside a TJ array; large ones separate words
+                let digits: String = stream[i + 1..].iter().take_while(|b| b.is_ascii_digit()).map(|&b| b as char).collect();
+                if digits.parse::<u32>().is_ok_and(|gap| gap >= 200) {
+                    text.push(' ');
+                }
+            }
+            b'B' if stream[i..].starts_with(b"BT") => in_text = true,
+            b'E' if stream[i..].starts_with(b"ET") && in_text => {
+                in_text = false;
+                text.push('\n');
+            }
+            b'T' if in_text && (stream[i..].starts_with(b"Tj") || stream[i..].starts_with(b"T*")) => text.push(' '),
+            b'\'' | b'"' if in_text => text.push(' '),
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// A PDF literal string starting after its `(`, and the index after its `)`
+fn literal_string(stream: &[u8], mut i: usize) -> (String, usize) {
+    let mut string = String::new();
+    let mut depth = 0;
+    while i < stream.len() {
+        match stream[i] {
+            b'\\' if i + 1 < stream.len() => {
+                i += 1;
+                match stream[i] {
+                    b'n' | b'r' | b't' => string.push(' '),
+                    digit @ b'0'..=b'7' => {
+                        let mut value = u32::from(digit - b'0');
+                        for _ in 0..2 {
+                            match stream.get(i + 1) {
+                                Some(next @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(next - b'0');
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        string.extend(char::from_u32(value));
+                    }
+                    other => string.push(other as char),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                string.push('(');
+            }
+            b')' if depth == 0 => return (string, i + 1),
+            b')' => {
+                depth -= 1;
+                string.push(')');
+            }
+            byte => string.push(byte as char),
+        }
+        i += 1;
+    }
+    (string, i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_extract_text() {
+        assert_eq!(extract_text("text/csv", "totals.csv", b"item,amount\nwidget,4821\n").as_deref(), Some("item,amount\nwidget,4821"));
+        assert_eq!(extract_text("image/png", "logo.png", b"\x89PNG"), None);
+
+        // A PDF page whose content stream is compressed, as most are
+        let content = b"BT /F1 12 Tf 72 712 Td (Invoice) Tj [(No)-250(4821)] TJ ET BT (Total: \\(EUR\\) 99) Tj ET";
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut pdf = b"%PDF-1.4\n4 0 obj\n<< /Length 99 /Filter /FlateDecode >>\nstream\n".to_vec();
+        pdf.extend(compressed);
+        pdf.extend(b"\nendstream\nendobj\n%%EOF\n");
+        let text = extract_text("application/pdf", "invoice.pdf", &pdf).unwrap();
+        assert!(text.contains("Invoice No 4821"), "{:?}", text);
+        assert!(text.contains("Total: (EUR) 99"), "{:?}", text);
+
+        let mut docx = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        docx.start_file("word/document.xml", zip::write::FileOptions::default()).unwrap();
+        docx.write_all(br#"<w:document><w:body><w:p><w:r><w:t>Order</w:t></w:r><w:r><w:t xml:space="preserve"> 4821 &amp; co</w:t></w:r></w:p><w:p><w:r><w:t>Thanks</w:t></w:r></w:p></w:body></w:document>"#).unwrap();
+        let docx = docx.finish().unwrap().into_inner();
+        assert_eq!(extract_text(DOCX_TYPE, "order.docx", &docx).as_deref(), Some("Order 4821 & co\nThanks"));
+    }
+}
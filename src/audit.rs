@@ -0,0 +1,115 @@
+use chrono::{Local, TimeZone};
+
+use crate::database::{AuditEntry, EmailDatabase};
+use crate::email::debug_log;
+
+/// Where an audited operation was started from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Ui,
+    // Not produced yet: rules only run as a dry run
+    #[allow(dead_code)]
+    Rule,
+    Cli,
+}
+
+impl Origin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Origin::Ui => "ui",
+            Origin::Rule => "rule",
+            Origin::Cli => "cli",
+        }
+    }
+}
+
+/// Record an operation in the audit log. Failing to record never fails the operation
+/// itself, so errors are only logged.
+pub fn record(
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    uid: Option<u32>,
+    action: &str,
+    origin: Origin,
+    detail: &str,
+) {
+    let entry = AuditEntry {
+        timestamp: Local::now().timestamp(),
+        account_email: account_email.to_string(),
+        folder: folder.to_string(),
+        uid,
+        action: action.to_string(),
+        origin: origin.as_str().to_string(),
+        detail: detail.to_string(),
+    };
+    if let Err(e) = database.record_audit(&entry) {
+        debug_log(&format!("Failed to record {} in the audit log: {}", action, e));
+    }
+}
+
+pub fn format_time(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// The entries as CSV with a header row, for `tuimail audit --csv` and the export
+pub fn to_csv(entries: &[AuditEntry]) -> String {
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    let mut csv = String::from("time,account,folder,uid,action,origin,detail\n");
+    for entry in entries {
+        let uid = entry.uid.map(|uid| uid.to_string()).unwrap_or_default();
+        let row = [
+            format_time(entry.timestamp).as_str(),
+            &entry.account_email,
+            &entry.folder,
+            &uid,
+            &entry.action,
+            &entry.origin,
+            &entry.detail,
+        ]
+        .map(field)
+        .join(",");
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_log() {
+        let dir = std::env::temp_dir().join(format!("tuimail-audit-{}", std::process::id()));
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+
+        record(&db, "me@example.com", "INBOX", Some(42), "delete", Origin::Ui, "Invoice, \"final\"");
+        record(&db, "other@example.com", "", None, "send", Origin::Ui, "to bob@example.com");
+
+        let entries = db.get_audit_log(None, Some(10)).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, "send"); // newest first
+
+        let mine = db.get_audit_log(Some("me@example.com"), None).unwrap();
+        assert_eq!(mine.len(), 1);
+        assert_eq!((mine[0].uid, mine[0].origin.as_str()), (Some(42), "ui"));
+
+        let csv = to_csv(&mine);
+        assert!(csv.starts_with("time,account,folder,uid,action,origin,detail\n"));
+        assert!(csv.ends_with(",me@example.com,INBOX,42,delete,ui,\"Invoice, \"\"final\"\"\"\n"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::config::EmailAccount;
+use crate::email::EmailError;
+use crate::providers;
+
+/// Why a login was refused, as far as the server's response lets us tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthFailure {
+    /// No password is stored for the account
+    MissingPassword,
+    WrongPassword,
+    /// The provider only accepts an app password from mail clients
+    AppPasswordRequired,
+    /// The provider has turned off password logins in favour of OAuth
+    OAuthRequired,
+    /// The provider wants the sign-in confirmed in a web browser first (2FA, suspicious login)
+    SignInBlocked,
+    TooManyAttempts,
+}
+
+/// Whether an error is a refused login rather than a network or server problem
+pub fn is_auth_error(error: &EmailError) -> bool {
+    match error {
+        EmailError::AuthError(_) => true,
+        // lettre reports SMTP 535/534 replies as permanent errors carrying the code
+        EmailError::SmtpError(message) => {
+            let message = message.to_ascii_lowercase();
+            message.contains("535") || message.contains("534") || message.contains("authentication")
+        }
+        _ => false,
+    }
+}
+
+/// Classify a login failure from the server's message and the account's provider.
+///
+/// Servers rarely say more than "authentication failed", so a generic refusal from a
+/// provider known to require an app password or OAuth is reported as that instead.
+pub fn classify(message: &str, account: &EmailAccount) -> AuthFailure {
+    let message = message.to_ascii_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+
+    if has(&["password not found", "no password"]) {
+        return AuthFailure::MissingPassword;
+    }
+    if has(&["application-specific password", "app password", "app-specific password", "185833"]) {
+        return AuthFailure::AppPasswordRequired;
+    }
+    if has(&["basicauthblocked", "basic authentication is disabled", "oauth", "xoauth"]) {
+        return AuthFailure::OAuthRequired;
+    }
+    if has(&["webalert", "web browser", "web login required", "2-step", "verify your account", "5.7.9"]) {
+        return AuthFailure::SignInBlocked;
+    }
+    if has(&["too many", "rate limit", "try again later", "[throttled]"]) {
+        return AuthFailure::TooManyAttempts;
+    }
+
+    match provider_id(account) {
+        Some("gmail") | Some("yahoo") | Some("icloud") => AuthFailure::AppPasswordRequired,
+        Some("outlook") => AuthFailure::OAuthRequired,
+        _ => AuthFailure::WrongPassword,
+    }
+}
+
+/// The preset for the account, by address or by the IMAP server it uses
+fn provider_id(account: &EmailAccount) -> Option<&'static str> {
+    providers::for_address(&account.email)
+        .or_else(|| {
+            providers::PRESETS
+                .iter()
+                .find(|p| p.imap_server.eq_ignore_ascii_case(&account.imap_server))
+        })
+        .map(|p| p.id)
+}
+
+impl AuthFailure {
+    pub fn headline(&self) -> &'static str {
+        match self {
+            AuthFailure::MissingPassword => "No password is stored for this account",
+            AuthFailure::WrongPassword => "The server rejected the username or password",
+            AuthFailure::AppPasswordRequired => "This provider needs an app password",
+            AuthFailure::OAuthRequired => "This provider has turned off password sign-in",
+            AuthFailure::SignInBlocked => "The provider blocked the sign-in",
+            AuthFailure::TooManyAttempts => "Too many failed sign-in attempts",
+        }
+    }
+
+    /// What the user can do about it, including the provider's own instructions if known
+    pub fn guidance(&self, account: &EmailAccount) -> String {
+        let advice = match self {
+            AuthFailure::MissingPassword => "Enter the password to store it in the keyring.",
+            AuthFailure::WrongPassword => {
+                "Check the username in the account settings and enter the password again. \
+                 If the provider uses two-factor authentication, it probably needs an app password."
+            }
+            AuthFailure::AppPasswordRequired => {
+                "Your normal password will not work from a mail client. Create an app password \
+                 in the provider's security settings and enter it instead."
+            }
+            AuthFailure::OAuthRequired => {
+                "tuimail does not support OAuth sign-in yet. Ask the administrator to allow IMAP \
+                 and SMTP password logins, or create an app password if the account offers one."
+            }
+            AuthFailure::SignInBlocked => {
+                "Sign in to the provider's website, confirm the recent sign-in attempt or \
+                 finish any pending security check, then retry."
+            }
+            AuthFailure::TooManyAttempts => "Wait a few minutes before retrying so the provider lifts the block.",
+        };
+
+        let preset = providers::for_address(&account.email);
+        match (self, preset) {
+            (AuthFailure::MissingPassword | AuthFailure::TooManyAttempts, _) | (_, None) => advice.to_string(),
+            (_, Some(preset)) => format!("{}\n\n{}: {}", advice, preset.name, preset.notes),
+        }
+    }
+}
+
+/// A password an account needs that cannot be read from the keyring
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingSecret {
+    pub account_idx: usize,
+    /// Whether it is the SMTP password rather than the IMAP one
+    pub smtp: bool,
+    pub reason: String,
+}
+
+/// Check that the IMAP and SMTP passwords of every account can be read and are not
+/// empty. `lookup` reads a secret by account address and kind (`imap` or `smtp`).
+pub fn check_secrets(
+    accounts: &[EmailAccount],
+    lookup: impl Fn(&str, &str) -> anyhow::Result<Option<String>>,
+) -> Vec<MissingSecret> {
+    let mut missing = Vec::new();
+    for (account_idx, account) in accounts.iter().enumerate() {
+        for (kind, smtp) in [("imap", false), ("smtp", true)] {
+            // Local Maildir accounts have no IMAP server to log in to, Gmail API accounts
+            // sign in with OAuth instead of passwords, and Exchange accounts send through
+            // EWS with the IMAP password
+            if (!smtp && account.maildir.is_some()) || account.gmail_api.is_some() || (smtp && account.exchange.is_some()) {
+                continue;
+            }
+            let reason = match lookup(&account.email, kind) {
+                Ok(Some(password)) if !password.is_empty() => continue,
+                Ok(Some(_)) => "stored password is empty".to_string(),
+                Ok(None) => "no password stored".to_string(),
+                Err(e) => format!("keyring error: {}", e),
+            };
+            missing.push(MissingSecret { account_idx, smtp, reason });
+        }
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_auth_failures() {
+        let account = |email: &str| EmailAccount { email: email.to_string(), ..EmailAccount::default() };
+        let other = account("me@example.com");
+
+        assert_eq!(
+            classify("[ALERT] Application-specific password required: https://support.google.com/mail/accounts/answer/185833", &other),
+            AuthFailure::AppPasswordRequired
+        );
+        assert_eq!(classify("AUTHENTICATE failed. BasicAuthBlocked", &other), AuthFailure::OAuthRequired);
+        assert_eq!(classify("[WEBALERT https://accounts.google.com/] Web login required", &other), AuthFailure::SignInBlocked);
+        assert_eq!(classify("Failed to get IMAP password: IMAP password not found for me@example.com", &other), AuthFailure::MissingPassword);
+        assert_eq!(classify("[AUTHENTICATIONFAILED] Invalid credentials", &other), AuthFailure::WrongPassword);
+
+        // A bare refusal is attributed to what the provider is known to require
+        assert_eq!(classify("LOGIN failed.", &account("me@outlook.com")), AuthFailure::OAuthRequired);
+        assert_eq!(classify("LOGIN failed.", &account("me@icloud.com")), AuthFailure::AppPasswordRequired);
+        assert!(AuthFailure::AppPasswordRequired.guidance(&account("me@gmail.com")).contains("apppasswords"));
+
+        assert!(is_auth_error(&EmailError::SmtpError("permanent error (535): 5.7.8 Username and Password not accepted".into())));
+        assert!(!is_auth_error(&EmailError::ConnectionError("connection refused".into())));
+    }
+
+    #[test]
+    fn test_check_secrets() {
+        let accounts = vec![
+            EmailAccount { email: "ok@example.com".to_string(), ..EmailAccount::default() },
+            EmailAccount { email: "broken@example.com".to_string(), ..EmailAccount::default() },
+        ];
+        let missing = check_secrets(&accounts, |email, kind| match (email, kind) {
+            ("ok@example.com", _) => Ok(Some("secret".to_string())),
+            (_, "imap") => Ok(Some(String::new())),
+            _ => Err(anyhow::anyhow!("locked")),
+        });
+        assert_eq!(
+            missing,
+            vec![
+                MissingSecret { account_idx: 1, smtp: false, reason: "stored password is empty".to_string() },
+                MissingSecret { account_idx: 1, smtp: true, reason: "keyring error: locked".to_string() },
+            ]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::email::Email;
+
+/// One check of an `Authentication-Results` header, such as `dkim=pass header.d=example.com`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// `dkim`, `spf`, `dmarc`, `arc` and so on, lowercase
+    pub method: String,
+    /// `pass`, `fail`, `softfail`, `neutral`, `none`, `temperror` or `permerror`, lowercase
+    pub result: String,
+    pub reason: Option<String>,
+    /// Properties such as `header.d` or `smtp.mailfrom`, names lowercase
+    pub properties: Vec<(String, String)>,
+}
+
+impl Check {
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn passed(&self) -> bool {
+        self.result == "pass"
+    }
+
+    /// The domain the check vouches for: the signing domain for DKIM, the envelope sender's
+    /// for SPF and the From domain for DMARC
+    pub fn domain(&self) -> Option<String> {
+        let value = match self.method.as_str() {
+            "dkim" => self.property("header.d").or_else(|| self.property("header.i")),
+            "spf" => self.property("smtp.mailfrom").or_else(|| self.property("smtp.helo")),
+            "dmarc" => self.property("header.from"),
+            _ => None,
+        }?;
+        let domain = value.rsplit('@').next().unwrap_or(value).trim().to_lowercase();
+        (!domain.is_empty()).then_some(domain)
+    }
+
+    /// `dkim=pass (example.com)`
+    pub fn summary(&self) -> String {
+        match self.domain() {
+            Some(domain) => format!("{}={} ({})", self.method, self.result, domain),
+            None => format!("{}={}", self.method, self.result),
+        }
+    }
+}
+
+/// An `Authentication-Results` header (RFC 8601): the server that checked the message
+/// and what it found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthResults {
+    pub authserv_id: String,
+    pub checks: Vec<Check>,
+}
+
+/// How far the checks show the message comes from the domain in its From address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// DMARC passed, or DKIM or SPF passed for the From domain
+    Verified,
+    /// DMARC failed, or every DKIM and SPF check failed
+    Failed,
+    /// Checks were made but none vouch for the From domain
+    Unverified,
+}
+
+/// Drop comments, which may nest, leaving quoted strings alone
+fn strip_comments(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let (mut depth, mut quoted, mut escaped) = (0usize, false, false);
+    for c in value.chars() {
+        if escaped {
+            escaped = false;
+            if depth == 0 {
+                text.push(c);
+            }
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' if depth == 0 => {
+                quoted = !quoted;
+                text.push(c);
+            }
+            '(' if !quoted => depth += 1,
+            ')' if !quoted && depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    text.push(' ');
+                }
+            }
+            _ if depth == 0 => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Split at `separator` outside quoted strings
+fn split_unquoted(text: &str, separator: impl Fn(char) -> bool) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quoted = false;
+    for c in text.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && separator(c) {
+            parts.push(String::new());
+        } else if let Some(last) = parts.last_mut() {
+            last.push(c);
+        }
+    }
+    parts.into_iter().map(|part| part.trim().to_string()).filter(|part| !part.is_empty()).collect()
+}
+
+/// Parse the value of an `Authentication-Results` header. `None` when it has no
+/// server id.
+pub fn parse(value: &str) -> Option<AuthResults> {
+    let mut sections = split_unquoted(&strip_comments(value), |c| c == ';').into_iter();
+    // The id may be followed by a version number
+    let authserv_id = sections.next()?.split_whitespace().next()?.to_lowercase();
+
+    let checks = sections
+        .filter_map(|section| {
+            let mut tokens = split_unquoted(&section, char::is_whitespace).into_iter();
+            let (method, result) = tokens.next()?.split_once('=').map(|(m, r)| (m.to_string(), r.to_string()))?;
+            // `dkim/1=pass`: a method version
+            let method = method.split('/').next().unwrap_or(&method).trim().to_lowercase();
+            let mut check = Check { method, result: result.trim().to_lowercase(), reason: None, properties: Vec::new() };
+            for token in tokens {
+                let Some((name, value)) = token.split_once('=') else {
+                    continue;
+                };
+                let name = name.trim().to_lowercase();
+                if name == "reason" {
+                    check.reason = Some(value.to_string());
+                } else {
+                    check.properties.push((name, value.to_string()));
+                }
+            }
+            Some(check)
+        })
+        .filter(|check| check.method != "none")
+        .collect();
+    Some(AuthResults { authserv_id, checks })
+}
+
+/// The results the receiving server added. Servers put theirs on top, so that one is
+/// kept when a message has several; headers further down come from earlier hops or the
+/// sender and can't be trusted.
+pub fn results(email: &Email) -> Option<AuthResults> {
+    parse(email.header("Authentication-Results")?).filter(|results| !results.checks.is_empty())
+}
+
+/// Whether `domain` is `other` or one of its subdomains, or the other way round, which is
+/// close to DMARC's relaxed alignment
+fn aligned(domain: &str, other: &str) -> bool {
+    domain == other || domain.ends_with(&format!(".{}", other)) || other.ends_with(&format!(".{}", domain))
+}
+
+impl AuthResults {
+    /// What the checks say about the sender's domain
+    pub fn verdict(&self, from_domain: &str) -> Verdict {
+        let from_domain = from_domain.to_lowercase();
+        if let Some(dmarc) = self.checks.iter().find(|check| check.method == "dmarc") {
+            match dmarc.result.as_str() {
+                "pass" => return Verdict::Verified,
+                "fail" => return Verdict::Failed,
+                _ => {}
+            }
+        }
+        let checked: Vec<&Check> = self.checks.iter().filter(|check| matches!(check.method.as_str(), "dkim" | "spf")).collect();
+        if checked.iter().any(|check| check.passed() && check.domain().is_some_and(|domain| aligned(&domain, &from_domain))) {
+            Verdict::Verified
+        } else if !checked.is_empty() && checked.iter().all(|check| matches!(check.result.as_str(), "fail" | "permerror")) {
+            Verdict::Failed
+        } else {
+            Verdict::Unverified
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_are_parsed_and_judged() {
+        let results = parse(
+            "mx.google.com;\r\n dkim=pass header.i=@example.com header.s=s1 header.b=\"a;b\";\r\n \
+             spf=pass (google.com: domain of x@bounce.example.com designates 1.2.3.4 as permitted sender) smtp.mailfrom=x@bounce.example.com;\r\n \
+             dmarc=pass (p=REJECT sp=REJECT dis=NONE) header.from=example.com",
+        )
+        .unwrap();
+        assert_eq!(results.authserv_id, "mx.google.com");
+        assert_eq!(results.checks.len(), 3);
+        assert_eq!(results.checks[0].property("header.b"), Some("a;b"));
+        assert_eq!(results.checks[1].summary(), "spf=pass (bounce.example.com)");
+        assert_eq!(results.verdict("example.com"), Verdict::Verified);
+
+        let spoofed = parse("mx.example.net 1; dkim=pass header.d=evil.test; spf=softfail smtp.mailfrom=evil.test").unwrap();
+        assert_eq!(spoofed.verdict("bank.example"), Verdict::Unverified);
+        assert_eq!(spoofed.verdict("mail.evil.test"), Verdict::Verified);
+
+        let failed = parse("mx.example.net; dmarc=fail reason=\"policy\" header.from=bank.example; dkim=none").unwrap();
+        assert_eq!(failed.verdict("bank.example"), Verdict::Failed);
+        assert_eq!(failed.checks[0].reason.as_deref(), Some("policy"));
+
+        let unsigned = parse("mx.example.net; dkim=fail (bad signature) header.d=a.test; spf=fail smtp.mailfrom=a.test").unwrap();
+        assert_eq!(unsigned.verdict("a.test"), Verdict::Failed);
+        assert!(parse("mx.example.net; none").unwrap().checks.is_empty());
+    }
+
+    #[test]
+    fn the_receiving_servers_results_are_used() {
+        let raw = b"Authentication-Results: mx.example.net; dmarc=fail header.from=bank.example\r\n\
+Authentication-Results: forged.example; dmarc=pass header.from=bank.example\r\n\
+From: Bank <alerts@bank.example>\r\nSubject: Hi\r\n\r\nHello\r\n";
+        let parsed = mail_parser::Message::parse(raw).unwrap();
+        let email = Email::from_parsed_email(&parsed, "1", "INBOX", Vec::new()).unwrap();
+        let results = results(&email).unwrap();
+        assert_eq!(results.authserv_id, "mx.example.net");
+        assert_eq!(results.verdict("bank.example"), Verdict::Failed);
+    }
+}
@@ -0,0 +1,372 @@
+use std::io::{BufRead, BufReader};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use native_tls::TlsConnector;
+
+use crate::http::https_get;
+use crate::config::{EmailAccount, ImapSecurity, SmtpSecurity, SpecialFolders};
+use crate::email::debug_log;
+
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Mozilla's database of provider settings, consulted when the domain publishes none
+const ISPDB_HOST: &str = "autoconfig.thunderbird.net";
+
+/// Where a set of server settings came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Preset(&'static str),
+    Autoconfig(String),
+    Srv,
+    Probe,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Source::Preset(name) => write!(f, "{} preset", name),
+            Source::Autoconfig(url) => write!(f, "autoconfig ({})", url),
+            Source::Srv => write!(f, "DNS SRV records"),
+            Source::Probe => write!(f, "probing common server names"),
+        }
+    }
+}
+
+/// Server settings found for an email address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discovered {
+    pub source: Source,
+    pub imap_server: String,
+    pub imap_port: u16,
+    pub imap_security: ImapSecurity,
+    pub smtp_server: String,
+    pub smtp_port: u16,
+    pub smtp_security: SmtpSecurity,
+    pub username: String,
+}
+
+impl Discovered {
+    pub fn account(&self, name: &str, email: &str) -> EmailAccount {
+        EmailAccount {
+            name: name.to_string(),
+            email: email.to_string(),
+            imap_server: self.imap_server.clone(),
+            imap_port: self.imap_port,
+            imap_security: self.imap_security.clone(),
+            imap_username: self.username.clone(),
+            smtp_server: self.smtp_server.clone(),
+            smtp_port: self.smtp_port,
+            smtp_security: self.smtp_security.clone(),
+            smtp_username: self.username.clone(),
+            signature: None,
+            sync_interval: None,
+            compose: crate::config::ComposeDefaults::default(),
+            smtp_ip_family: None,
+            smtp_fallback: crate::config::SmtpFallback::default(),
+            internal_domains: Vec::new(),
+            maildir: None,
+            folder_options: Default::default(),
+            gmail_api: None,
+            exchange: None,
+            pgp: None,
+            caldav: None,
+            ldap: None,
+            special_folders: SpecialFolders::default(),
+        }
+    }
+
+    /// Servers named by SRV records that are outside the domain of `email`. DNS answers
+    /// are not authenticated, so RFC 6186 (section 6) has the user confirm these before
+    /// they are used.
+    pub fn hosts_to_confirm(&self, email: &str) -> Vec<&str> {
+        if self.source != Source::Srv {
+            return Vec::new();
+        }
+        let domain = email.rsplit_once('@').map(|(_, domain)| domain.trim().to_ascii_lowercase()).unwrap_or_default();
+        let mut hosts = Vec::new();
+        for host in [self.imap_server.as_str(), self.smtp_server.as_str()] {
+            let name = host.trim_end_matches('.').to_ascii_lowercase();
+            if name != domain && !name.ends_with(&format!(".{}", domain)) && !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+        hosts
+    }
+}
+
+/// Find the IMAP and SMTP settings for an address. Tries, in order: the built-in provider
+/// presets, Mozilla autoconfig (the domain's own file, then the Thunderbird ISPDB), RFC 6186
+/// SRV records, and finally connecting to the usual host names and ports.
+pub fn discover(email: &str) -> Result<Discovered> {
+    let (_, domain) = email
+        .rsplit_once('@')
+        .ok_or_else(|| anyhow!("'{}' is not an email address", email))?;
+    let domain = domain.trim().to_ascii_lowercase();
+
+    if let Some(preset) = crate::providers::for_address(email) {
+        return Ok(Discovered {
+            source: Source::Preset(preset.name),
+            imap_server: preset.imap_server.to_string(),
+            imap_port: preset.imap_port,
+            imap_security: preset.imap_security.clone(),
+            smtp_server: preset.smtp_server.to_string(),
+            smtp_port: preset.smtp_port,
+            smtp_security: preset.smtp_security.clone(),
+            username: email.to_string(),
+        });
+    }
+
+    let autoconfig_urls = [
+        (format!("autoconfig.{}", domain), format!("/mail/config-v1.1.xml?emailaddress={}", email.replace('+', "%2B").replace('@', "%40"))),
+        (domain.clone(), "/.well-known/autoconfig/mail/config-v1.1.xml".to_string()),
+        (ISPDB_HOST.to_string(), format!("/v1.1/{}", domain)),
+    ];
+    for (host, path) in &autoconfig_urls {
+        match https_get(host, path) {
+            Ok((200, body)) => {
+                let url = format!("https://{}{}", host, path);
+                match parse_autoconfig(&String::from_utf8_lossy(&body), email) {
+                    Some(mut found) => {
+                        found.source = Source::Autoconfig(url);
+                        return Ok(found);
+                    }
+                    None => debug_log(&format!("Autoconfig at {} had no usable servers", url)),
+                }
+            }
+            Ok((status, _)) => debug_log(&format!("Autoconfig on {} returned {}", host, status)),
+            Err(e) => debug_log(&format!("Autoconfig on {} failed: {}", host, e)),
+        }
+    }
+
+    if let Some(found) = discover_srv(&domain, email) {
+        return Ok(found);
+    }
+
+    discover_by_probing(&domain, email)
+        .ok_or_else(|| anyhow!("Could not find the mail servers for {}", domain))
+}
+
+/// Read the first IMAP and SMTP servers from a Mozilla autoconfig document
+/// (`clientConfig` version 1.1). Servers without TLS are skipped.
+pub fn parse_autoconfig(xml: &str, email: &str) -> Option<Discovered> {
+    let (local_part, domain) = email.rsplit_once('@')?;
+    let expand = |value: &str| {
+        value
+            .replace("%EMAILADDRESS%", email)
+            .replace("%EMAILLOCALPART%", local_part)
+            .replace("%EMAILDOMAIN%", domain)
+    };
+
+    let document = roxmltree::Document::parse(xml).ok()?;
+    let mut imap = None;
+    let mut smtp = None;
+    for server in document.descendants().filter(|node| node.is_element()) {
+        let kind = match (server.tag_name().name(), server.attribute("type")) {
+            ("incomingServer", Some("imap")) => "imap",
+            ("outgoingServer", Some("smtp")) => "smtp",
+            _ => continue,
+        };
+        let text = |name: &str| server.children().find(|node| node.has_tag_name(name)).and_then(|node| node.text()).map(str::trim);
+        let (Some(hostname), Some(Ok(port))) = (text("hostname"), text("port").map(str::parse::<u16>)) else {
+            continue;
+        };
+        let username = text("username").map(expand).unwrap_or_else(|| email.to_string());
+        let socket_type = text("socketType").unwrap_or_default();
+        let found = (expand(hostname), port, socket_type.to_string(), username);
+        match kind {
+            "imap" if imap.is_none() && socket_type != "plain" => imap = Some(found),
+            "smtp" if smtp.is_none() && socket_type != "plain" => smtp = Some(found),
+            _ => {}
+        }
+    }
+
+    let (imap_server, imap_port, imap_socket, username) = imap?;
+    let (smtp_server, smtp_port, smtp_socket, _) = smtp?;
+    Some(Discovered {
+        source: Source::Autoconfig(String::new()),
+        imap_server,
+        imap_port,
+        imap_security: if imap_socket == "STARTTLS" { ImapSecurity::StartTLS } else { ImapSecurity::SSL },
+        smtp_server,
+        smtp_port,
+        smtp_security: if smtp_socket == "STARTTLS" { SmtpSecurity::StartTLS } else { SmtpSecurity::SSL },
+        username,
+    })
+}
+
+/// Look up RFC 6186 SRV records, preferring implicit TLS over STARTTLS. The blocking
+/// resolver runs a runtime of its own, so it gets a thread outside any caller's.
+fn discover_srv(domain: &str, email: &str) -> Option<Discovered> {
+    std::thread::scope(|scope| scope.spawn(|| srv_servers(domain, email)).join().ok().flatten())
+}
+
+fn srv_servers(domain: &str, email: &str) -> Option<Discovered> {
+    let resolver = match hickory_resolver::Resolver::from_system_conf() {
+        Ok(resolver) => resolver,
+        Err(e) => {
+            debug_log(&format!("No DNS resolver for SRV lookups: {}", e));
+            return None;
+        }
+    };
+    // A target of "." means the service is deliberately not offered
+    let lookup = |service: &str| {
+        let name = format!("{}._tcp.{}.", service, domain);
+        match resolver.srv_lookup(name.as_str()) {
+            Ok(records) => records
+                .iter()
+                .filter(|record| !record.target().is_root())
+                .min_by_key(|record| record.priority())
+                .map(|record| (record.target().to_utf8().trim_end_matches('.').to_string(), record.port())),
+            Err(e) => {
+                debug_log(&format!("SRV lookup of {} failed: {}", name, e));
+                None
+            }
+        }
+    };
+
+    let ((imap_server, imap_port), imap_security) = match lookup("_imaps") {
+        Some(record) => (record, ImapSecurity::SSL),
+        None => (lookup("_imap")?, ImapSecurity::StartTLS),
+    };
+    let ((smtp_server, smtp_port), smtp_security) = match lookup("_submissions") {
+        Some(record) => (record, SmtpSecurity::SSL),
+        None => (lookup("_submission")?, SmtpSecurity::StartTLS),
+    };
+    Some(Discovered {
+        source: Source::Srv,
+        imap_server,
+        imap_port,
+        imap_security,
+        smtp_server,
+        smtp_port,
+        smtp_security,
+        username: email.to_string(),
+    })
+}
+
+/// Connect to the host names and ports most providers use and keep the first that greets
+fn discover_by_probing(domain: &str, email: &str) -> Option<Discovered> {
+    let imap_candidates = [
+        (format!("imap.{}", domain), 993, ImapSecurity::SSL),
+        (format!("mail.{}", domain), 993, ImapSecurity::SSL),
+        (domain.to_string(), 993, ImapSecurity::SSL),
+    ];
+    let smtp_candidates = [
+        (format!("smtp.{}", domain), 465, SmtpSecurity::SSL),
+        (format!("smtp.{}", domain), 587, SmtpSecurity::StartTLS),
+        (format!("mail.{}", domain), 465, SmtpSecurity::SSL),
+        (format!("mail.{}", domain), 587, SmtpSecurity::StartTLS),
+    ];
+
+    let (imap_server, imap_port, imap_security) = imap_candidates
+        .into_iter()
+        .find(|(host, port, _)| probe(host, *port, true, "* OK"))?;
+    let (smtp_server, smtp_port, smtp_security) = smtp_candidates
+        .into_iter()
+        .find(|(host, port, security)| probe(host, *port, *security == SmtpSecurity::SSL, "220"))?;
+
+    Some(Discovered {
+        source: Source::Probe,
+        imap_server,
+        imap_port,
+        imap_security,
+        smtp_server,
+        smtp_port,
+        smtp_security,
+        username: email.to_string(),
+    })
+}
+
+/// Whether `host:port` answers with a greeting starting with `greeting`
+fn probe(host: &str, port: u16, tls: bool, greeting: &str) -> bool {
+    if crate::email::is_offline() {
+        return false;
+    }
+    let result = (|| -> Result<bool> {
+        let addr = (host, port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("could not resolve {}", host))?;
+        let tcp = TcpStream::connect_timeout(&addr, NETWORK_TIMEOUT)?;
+        tcp.set_read_timeout(Some(NETWORK_TIMEOUT))?;
+
+        let mut line = String::new();
+        if tls {
+            let stream = TlsConnector::new()?.connect(host, tcp)?;
+            BufReader::new(stream).read_line(&mut line)?;
+        } else {
+            BufReader::new(tcp).read_line(&mut line)?;
+        }
+        Ok(line.starts_with(greeting))
+    })();
+    match result {
+        Ok(greeted) => greeted,
+        Err(e) => {
+            debug_log(&format!("Probe of {}:{} failed: {}", host, port, e));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_autoconfig() {
+        let xml = r#"<?xml version="1.0"?>
+<clientConfig version="1.1">
+  <emailProvider id="example.com">
+    <incomingServer type="pop3">
+      <hostname>pop.example.com</hostname><port>995</port><socketType>SSL</socketType>
+    </incomingServer>
+    <incomingServer type="imap">
+      <hostname>imap.example.com</hostname>
+      <port>143</port>
+      <socketType>STARTTLS</socketType>
+      <username>%EMAILLOCALPART%</username>
+    </incomingServer>
+    <outgoingServer type="smtp">
+      <hostname>plain.example.com</hostname><port>25</port><socketType>plain</socketType>
+    </outgoingServer>
+    <outgoingServer type="smtp">
+      <hostname>smtp.%EMAILDOMAIN%</hostname>
+      <port>465</port>
+      <socketType>SSL</socketType>
+      <username>%EMAILADDRESS%</username>
+    </outgoingServer>
+  </emailProvider>
+</clientConfig>"#;
+        let found = parse_autoconfig(xml, "jane@example.com").unwrap();
+        assert_eq!((found.imap_server.as_str(), found.imap_port), ("imap.example.com", 143));
+        assert_eq!(found.imap_security, ImapSecurity::StartTLS);
+        assert_eq!(found.username, "jane");
+        assert_eq!((found.smtp_server.as_str(), found.smtp_port), ("smtp.example.com", 465));
+        assert_eq!(found.smtp_security, SmtpSecurity::SSL);
+
+        assert!(parse_autoconfig("<clientConfig/>", "jane@example.com").is_none());
+    }
+
+    #[test]
+    fn test_srv_hosts_outside_the_domain_need_confirming() {
+        let mut found = Discovered {
+            source: Source::Srv,
+            imap_server: "imap.example.com".to_string(),
+            imap_port: 993,
+            imap_security: ImapSecurity::SSL,
+            smtp_server: "mail.hoster.net".to_string(),
+            smtp_port: 465,
+            smtp_security: SmtpSecurity::SSL,
+            username: "jane@example.com".to_string(),
+        };
+        assert_eq!(found.hosts_to_confirm("jane@Example.com"), vec!["mail.hoster.net"]);
+        found.smtp_server = "example.com.".to_string();
+        assert!(found.hosts_to_confirm("jane@example.com").is_empty());
+        found.smtp_server = "badexample.com".to_string();
+        assert_eq!(found.hosts_to_confirm("jane@example.com"), vec!["badexample.com"]);
+        found.source = Source::Probe;
+        assert!(found.hosts_to_confirm("jane@example.com").is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+use anyhow::{anyhow, Result};
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::Resolver;
+use resvg::{tiny_skia, usvg};
+use sha2::{Digest, Sha256};
+
+use crate::email::Email;
+use crate::http::https_get;
+
+/// Avatars are rendered as a square of this many pixels (two pixels per terminal row)
+pub const AVATAR_PIXELS: usize = 8;
+
+/// How long a cached lookup (including "no avatar") is trusted before asking again
+pub const AVATAR_CACHE_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Services queried in order, all of which accept SHA-256 address hashes
+const AVATAR_HOSTS: &[&str] = &["www.gravatar.com", "seccdn.libravatar.org"];
+
+/// Size in pixels asked of the services, and the largest image decoded: anything bigger
+/// is not what was asked for. SVG logos are drawn at this size.
+const IMAGE_SIZE: u32 = 32;
+
+/// Cache keys of brand logos start with this, followed by the domain
+const BIMI_PREFIX: &str = "bimi:";
+
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// A downscaled avatar ready for half-block rendering, row-major RGB
+#[derive(Debug, Clone)]
+pub struct AvatarImage {
+    pub pixels: Vec<(u8, u8, u8)>,
+}
+
+impl AvatarImage {
+    pub fn pixel(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        self.pixels[y * AVATAR_PIXELS + x]
+    }
+}
+
+/// Initials for the fallback badge, e.g. "Jane Doe" -> "JD", "bob@example.com" -> "B"
+pub fn initials(name: Option<&str>, address: &str) -> String {
+    let source = match name {
+        Some(name) if !name.trim().is_empty() => name,
+        _ => address.split('@').next().unwrap_or(address),
+    };
+    let letters: String = source
+        .split(|c: char| c.is_whitespace() || c == '.' || c == '_' || c == '-')
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .take(2)
+        .collect();
+    if letters.is_empty() {
+        "?".to_string()
+    } else {
+        letters.to_uppercase()
+    }
+}
+
+/// Stable background colour for an address's initials badge
+pub fn badge_color(address: &str) -> (u8, u8, u8) {
+    const PALETTE: &[(u8, u8, u8)] = &[
+        (0x1f, 0x77, 0xb4),
+        (0xd6, 0x27, 0x28),
+        (0x2c, 0xa0, 0x2c),
+        (0x94, 0x67, 0xbd),
+        (0x8c, 0x56, 0x4b),
+        (0xe3, 0x77, 0xc2),
+        (0xbc, 0xbd, 0x22),
+        (0x17, 0xbe, 0xcf),
+    ];
+    let digest = Sha256::digest(normalize_address(address).as_bytes());
+    PALETTE[digest[0] as usize % PALETTE.len()]
+}
+
+pub fn normalize_address(address: &str) -> String {
+    address.trim().to_lowercase()
+}
+
+/// SHA-256 hex digest of the normalized address, as used by Gravatar and Libravatar
+pub fn avatar_hash(address: &str) -> String {
+    Sha256::digest(normalize_address(address).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Cache key of the BIMI logo published for `domain`
+pub fn bimi_key(domain: &str) -> String {
+    format!("{}{}", BIMI_PREFIX, domain.to_lowercase())
+}
+
+/// The From domain when the receiving server found that DMARC passed for it. Brand logos
+/// are only shown for such messages, since anyone can put a domain in a From header.
+pub fn bimi_domain(email: &Email) -> Option<String> {
+    let address = email.from.first()?.address.to_lowercase();
+    let domain = address.rsplit_once('@')?.1.trim().to_string();
+    let results = crate::auth_results::results(email)?;
+    let passed = results.checks.iter().any(|check| {
+        check.method == "dmarc" && check.passed() && check.domain().is_none_or(|checked| checked == domain)
+    });
+    (passed && !domain.is_empty()).then_some(domain)
+}
+
+/// Fetch what is cached under `key`: a brand logo for `bimi_key` keys, otherwise the
+/// address's avatar
+pub fn fetch(key: &str) -> Result<Option<Vec<u8>>> {
+    match key.strip_prefix(BIMI_PREFIX) {
+        Some(domain) => fetch_bimi_logo(domain),
+        None => fetch_avatar(key),
+    }
+}
+
+/// Look the address up on each avatar service. Returns `Ok(None)` when no service has an
+/// image, which callers should cache so we don't ask again on every view.
+pub fn fetch_avatar(address: &str) -> Result<Option<Vec<u8>>> {
+    let path = format!("/avatar/{}?s={}&d=404", avatar_hash(address), IMAGE_SIZE);
+    let mut last_error = None;
+
+    for host in AVATAR_HOSTS {
+        match https_get(host, &path) {
+            Ok((200, body)) if !body.is_empty() => return Ok(Some(body)),
+            Ok((status, _)) => {
+                crate::email::debug_log(&format!("Avatar lookup on {} returned {}", host, status));
+                last_error = None;
+            }
+            Err(e) => {
+                crate::email::debug_log(&format!("Avatar lookup on {} failed: {}", host, e));
+                last_error = last_error.or(Some(e));
+            }
+        }
+    }
+
+    // Report an error only if the services were unreachable, so offline runs don't
+    // poison the cache with "no avatar" entries
+    match last_error {
+        Some(e) => Err(e),
+        None => Ok(None),
+    }
+}
+
+/// The value of a `name=value` tag in a DNS TXT record such as BIMI's or DMARC's
+fn record_tag<'a>(record: &'a str, name: &str) -> Option<&'a str> {
+    record
+        .split(';')
+        .filter_map(|tag| tag.split_once('='))
+        .find(|(tag, _)| tag.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// The domain and, for a subdomain, its organizational domain, where BIMI and DMARC
+/// records are looked up when the domain has none of its own. The last two labels stand
+/// in for the organizational domain, which is wrong for names like `example.co.uk` but
+/// only ever finds no record there.
+fn record_domains(domain: &str) -> Vec<String> {
+    let labels: Vec<&str> = domain.trim_end_matches('.').split('.').collect();
+    let mut domains = vec![labels.join(".")];
+    if labels.len() > 2 {
+        domains.push(labels[labels.len() - 2..].join("."));
+    }
+    domains
+}
+
+/// Look up the logo a domain publishes with BIMI (`default._bimi.<domain>`) and fetch it.
+/// A logo only counts while the domain's DMARC policy rejects or quarantines mail that
+/// fails, as BIMI requires. Returns `Ok(None)` when there is no usable logo.
+pub fn fetch_bimi_logo(domain: &str) -> Result<Option<Vec<u8>>> {
+    if crate::email::is_offline() {
+        return Err(anyhow!("offline: not looking up the logo of {}", domain));
+    }
+    let resolver = Resolver::from_system_conf()?;
+    // Records longer than 255 bytes are split into several strings
+    let txt = |name: String| -> Result<Vec<String>> {
+        match resolver.txt_lookup(name.as_str()) {
+            Ok(records) => Ok(records
+                .iter()
+                .map(|record| record.txt_data().iter().map(|part| String::from_utf8_lossy(part)).collect())
+                .collect()),
+            Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    };
+
+    let mut location = None;
+    for name in record_domains(domain) {
+        let records = txt(format!("default._bimi.{}.", name))?;
+        if let Some(record) = records.iter().find(|record| record_tag(record, "v") == Some("BIMI1")) {
+            location = record_tag(record, "l").map(str::to_string);
+            break;
+        }
+    }
+    // An empty location means the domain declines to show a logo
+    let Some(location) = location.filter(|location| !location.is_empty()) else {
+        return Ok(None);
+    };
+
+    let mut enforced = false;
+    for (index, name) in record_domains(domain).iter().enumerate() {
+        let records = txt(format!("_dmarc.{}.", name))?;
+        if let Some(record) = records.iter().find(|record| record_tag(record, "v") == Some("DMARC1")) {
+            // A subdomain is governed by its organizational domain's `sp` when there is one
+            let policy = if index > 0 { record_tag(record, "sp").or_else(|| record_tag(record, "p")) } else { record_tag(record, "p") };
+            enforced = policy.is_some_and(|policy| policy.eq_ignore_ascii_case("quarantine") || policy.eq_ignore_ascii_case("reject"));
+            break;
+        }
+    }
+    if !enforced {
+        crate::email::debug_log(&format!("Ignoring the BIMI logo of {}: DMARC is not enforced", domain));
+        return Ok(None);
+    }
+
+    // BIMI logos are only ever served over HTTPS
+    let Some(rest) = location.strip_prefix("https://") else {
+        return Ok(None);
+    };
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let path = if path.is_empty() { "/" } else { path };
+    match https_get(host, path)? {
+        (200, body) if !body.is_empty() => Ok(Some(body)),
+        (status, _) => {
+            crate::email::debug_log(&format!("BIMI logo of {} returned {}", domain, status));
+            Ok(None)
+        }
+    }
+}
+
+/// Decode a PNG, or draw an SVG logo, and downscale it to an `AVATAR_PIXELS` square.
+/// Anything else (including JPEG) returns `None` and the caller falls back to initials.
+pub fn decode_thumbnail(data: &[u8]) -> Option<AvatarImage> {
+    let (width, height, rgba) = if data.starts_with(PNG_SIGNATURE) { decode_png(data)? } else { render_svg(data)? };
+
+    let mut pixels = Vec::with_capacity(AVATAR_PIXELS * AVATAR_PIXELS);
+    for y in 0..AVATAR_PIXELS {
+        for x in 0..AVATAR_PIXELS {
+            let sx = x * width / AVATAR_PIXELS;
+            let sy = y * height / AVATAR_PIXELS;
+            let [r, g, b, a] = rgba[sy * width + sx];
+            // Blend transparent areas over a dark background
+            let blend = |c: u8| ((c as u16 * a as u16 + 0x20 * (255 - a as u16)) / 255) as u8;
+            pixels.push((blend(r), blend(g), blend(b)));
+        }
+    }
+    Some(AvatarImage { pixels })
+}
+
+fn decode_png(data: &[u8]) -> Option<(usize, usize, Vec<[u8; 4]>)> {
+    // The allocation limit keeps a small file from claiming a huge image
+    let mut decoder = png::Decoder::new_with_limits(data, png::Limits { bytes: 1024 * 1024 });
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let (width, height) = reader.info().size();
+    if width == 0 || height == 0 || width > IMAGE_SIZE || height > IMAGE_SIZE {
+        return None;
+    }
+
+    let mut image = vec![0u8; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut image).ok()?;
+    let channels = frame.color_type.samples();
+    let rgba = image[..frame.buffer_size()]
+        .chunks_exact(channels)
+        .map(|p| match frame.color_type {
+            png::ColorType::Grayscale => [p[0], p[0], p[0], 255],
+            png::ColorType::GrayscaleAlpha => [p[0], p[0], p[0], p[1]],
+            png::ColorType::Rgb => [p[0], p[1], p[2], 255],
+            _ => [p[0], p[1], p[2], p[3]],
+        })
+        .collect();
+
+    Some((width as usize, height as usize, rgba))
+}
+
+fn render_svg(data: &[u8]) -> Option<(usize, usize, Vec<[u8; 4]>)> {
+    let mut options = usvg::Options::default();
+    // A logo has no business reading images off the disk
+    options.image_href_resolver = usvg::ImageHrefResolver {
+        resolve_data: Box::new(|_, _, _| None),
+        resolve_string: Box::new(|_, _| None),
+    };
+    let tree = usvg::Tree::from_data(data, &options).ok()?;
+
+    let size = tree.size();
+    let scale = IMAGE_SIZE as f32 / size.width().max(size.height());
+    let mut pixmap = tiny_skia::Pixmap::new(IMAGE_SIZE, IMAGE_SIZE)?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Some((IMAGE_SIZE as usize, IMAGE_SIZE as usize, rgba))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(initials(Some("Jane Doe"), "jane@example.com"), "JD");
+        assert_eq!(initials(None, "bob.smith@example.com"), "BS");
+        assert_eq!(initials(Some("  "), "alice@example.com"), "A");
+        assert_eq!(initials(None, ""), "?");
+    }
+
+    /// An 8-bit RGB PNG of the given size holding the pixels in `rgb`
+    fn png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(rgb).unwrap();
+        writer.finish().unwrap();
+        out
+    }
+
+    #[test]
+    fn test_decode_rgb_png() {
+        // 2x2 RGB image: red, green / blue, white
+        let rgb = [
+            255, 0, 0, 0, 255, 0,
+            0, 0, 255, 255, 255, 255,
+        ];
+        let image = decode_thumbnail(&png(2, 2, &rgb)).expect("PNG should decode");
+        assert_eq!(image.pixel(0, 0), (255, 0, 0));
+        assert_eq!(image.pixel(AVATAR_PIXELS - 1, 0), (0, 255, 0));
+        assert_eq!(image.pixel(0, AVATAR_PIXELS - 1), (0, 0, 255));
+        assert_eq!(image.pixel(AVATAR_PIXELS - 1, AVATAR_PIXELS - 1), (255, 255, 255));
+        assert!(decode_thumbnail(b"not a png").is_none());
+    }
+
+    #[test]
+    fn oversized_pngs_are_refused() {
+        let size = IMAGE_SIZE as usize + 1;
+        assert!(decode_thumbnail(&png(IMAGE_SIZE + 1, 1, &vec![0; size * 3])).is_none());
+        assert!(decode_thumbnail(&png(1, 100_000, &vec![0; 300_000])).is_none());
+    }
+
+    #[test]
+    fn test_render_svg_logo() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 2 2">
+            <rect width="1" height="2" fill="#ff0000"/>
+            <rect x="1" width="1" height="2" fill="#0000ff"/>
+            <image href="/etc/passwd" width="2" height="2"/>
+        </svg>"##;
+        let image = decode_thumbnail(svg).expect("SVG should render");
+        assert_eq!(image.pixel(0, 0), (255, 0, 0));
+        assert_eq!(image.pixel(AVATAR_PIXELS - 1, AVATAR_PIXELS - 1), (0, 0, 255));
+    }
+
+    #[test]
+    fn test_bimi_records() {
+        let record = "v=BIMI1; l=https://example.com/logo.svg; a=";
+        assert_eq!(record_tag(record, "v"), Some("BIMI1"));
+        assert_eq!(record_tag(record, "L"), Some("https://example.com/logo.svg"));
+        assert_eq!(record_tag(record, "a"), Some(""));
+        assert_eq!(record_tag("v=DMARC1; p=reject", "sp"), None);
+        assert_eq!(record_domains("news.example.com."), vec!["news.example.com", "example.com"]);
+        assert_eq!(record_domains("example.com"), vec!["example.com"]);
+    }
+
+    #[test]
+    fn test_bimi_needs_dmarc() {
+        let email = |results: &str| {
+            let mut email = Email::new();
+            email.from = vec![crate::email::EmailAddress { name: None, address: "News@Brand.example".to_string() }];
+            email.headers.insert("Authentication-Results".to_string(), results.to_string());
+            email
+        };
+        assert_eq!(bimi_domain(&email("mx.test; dmarc=pass header.from=brand.example")), Some("brand.example".to_string()));
+        assert_eq!(bimi_domain(&email("mx.test; dmarc=pass header.from=other.example")), None);
+        assert_eq!(bimi_domain(&email("mx.test; dkim=pass header.d=brand.example")), None);
+        assert_eq!(bimi_key("Brand.example"), "bimi:brand.example");
+    }
+}
@@ -0,0 +1,312 @@
+use crate::email::Email;
+
+/// Bodies longer than this (in bytes) are prepared off the UI thread and shown truncated
+pub const LARGE_BODY: usize = 512 * 1024;
+
+/// How much of a large body is shown until the full message is asked for
+pub const TRUNCATED_LENGTH: usize = 256 * 1024;
+
+/// A large body made ready for the viewer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedBody {
+    pub text: String,
+    /// Whether `text` stops short of the message
+    pub truncated: bool,
+    /// Length of the whole body in bytes
+    pub total_len: usize,
+}
+
+pub fn is_large(body: &str) -> bool {
+    body.len() > LARGE_BODY
+}
+
+/// Identifies what a prepared body was made from, so a stale one is never shown
+pub fn body_key(email: &Email, full: bool) -> String {
+    let len = email.body_text.as_deref().map_or(0, str::len);
+    format!("{}/{}/{}/{}", email.folder, email.id, len, full)
+}
+
+/// Clean up a large body for display: unify line endings, drop control characters and
+/// the long runs of blank lines converted newsletters are full of, and unless `full`
+/// is set cut it after `TRUNCATED_LENGTH` bytes at a line break.
+pub fn prepare(body: &str, full: bool) -> PreparedBody {
+    let mut source = body;
+    let mut truncated = false;
+    if !full && body.len() > TRUNCATED_LENGTH {
+        let mut end = TRUNCATED_LENGTH;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(line_end) = body[..end].rfind('\n') {
+            end = line_end;
+        }
+        source = &body[..end];
+        truncated = true;
+    }
+
+    let mut text = String::with_capacity(source.len());
+    let mut blank_lines = 0;
+    for line in source.lines() {
+        let line: String = line.chars().filter(|c| *c == '\t' || !c.is_control()).collect();
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            if blank_lines > 2 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+
+    PreparedBody { text, truncated, total_len: body.len() }
+}
+
+/// Elements of an HTML body whose content is not shown
+const HIDDEN_ELEMENTS: &[&str] = &["head", "style", "script", "title"];
+
+/// Elements of an HTML body that start a new line
+const BLOCK_ELEMENTS: &[&str] =
+    &["p", "div", "tr", "li", "ul", "ol", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "table", "pre", "blockquote"];
+
+/// The value of attribute `name` in the tag `tag` (without its `<` and `>`), entities
+/// decoded
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let start = from + found;
+        from = start + name.len();
+        let preceded = lower[..start].ends_with(|c: char| c.is_ascii_whitespace());
+        let rest = lower[from..].trim_start();
+        if !preceded || !rest.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - rest.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(|c: char| c.is_ascii_whitespace() || c == '/').next().unwrap_or_default(),
+        };
+        return Some(crate::snippet::decode_entities(value));
+    }
+    None
+}
+
+/// Whether `url` is fetched from the network when the message is rendered
+fn is_remote(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    url.starts_with("http://") || url.starts_with("https://") || url.starts_with("//")
+}
+
+/// An HTML body as text for the viewer: tags dropped, a line break at block elements,
+/// quoted text marked with `>` as in a text body, links followed by their address and
+/// images shown by their description. Remote images are only named with their address
+/// when `remote_content` is set; the second value counts those left out.
+pub fn html_text(html: &str, remote_content: bool) -> (String, usize) {
+    // Lines of text with how deeply they are quoted
+    let mut lines: Vec<(usize, String)> = vec![(0, String::new())];
+    let mut quote_depth = 0;
+    let mut hidden = 0usize;
+    let mut link: Option<String> = None;
+    let mut blocked = 0;
+    let new_line = |lines: &mut Vec<(usize, String)>, depth: usize| match lines.last_mut() {
+        Some(last) if last.1.trim().is_empty() => *last = (depth, String::new()),
+        _ => lines.push((depth, String::new())),
+    };
+    // Whitespace collapsed to single spaces, as browsers do
+    let push_text = |lines: &mut Vec<(usize, String)>, text: &str| {
+        let line = &mut lines.last_mut().expect("never empty").1;
+        let mut spaced = text.starts_with(char::is_whitespace);
+        for word in text.split_whitespace() {
+            if spaced && !line.is_empty() && !line.ends_with(' ') {
+                line.push(' ');
+            }
+            line.push_str(word);
+            spaced = true;
+        }
+        if text.ends_with(char::is_whitespace) && !line.is_empty() && !line.ends_with(' ') {
+            line.push(' ');
+        }
+    };
+
+    let lower = html.to_ascii_lowercase();
+    let mut rest = 0;
+    loop {
+        let start = html[rest..].find('<').map_or(html.len(), |open| rest + open);
+        if hidden == 0 {
+            push_text(&mut lines, &crate::snippet::decode_entities(&html[rest..start]));
+        }
+        if start == html.len() {
+            break;
+        }
+        let end = if lower[start..].starts_with("<!--") {
+            lower[start..].find("-->").map(|close| start + close + 3)
+        } else {
+            lower[start..].find('>').map(|close| start + close + 1)
+        };
+        let Some(end) = end else {
+            break;
+        };
+        rest = end;
+
+        let tag = &html[start + 1..end - 1];
+        let closing = tag.starts_with('/');
+        let name = lower[start + 1..end - 1].trim_start_matches('/').chars().take_while(|c| c.is_ascii_alphanumeric()).collect::<String>();
+        if HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            if closing {
+                hidden = hidden.saturating_sub(1);
+            } else if !tag.ends_with('/') {
+                hidden += 1;
+            }
+            continue;
+        }
+        if hidden > 0 {
+            continue;
+        }
+        match name.as_str() {
+            "br" => lines.push((quote_depth, String::new())),
+            "blockquote" => {
+                quote_depth = if closing { quote_depth.saturating_sub(1) } else { quote_depth + 1 };
+                new_line(&mut lines, quote_depth);
+            }
+            "p" if closing => {
+                new_line(&mut lines, quote_depth);
+                lines.push((quote_depth, String::new()));
+            }
+            "a" if closing => {
+                let text = lines.last().map(|(_, line)| line.trim_end().to_string()).unwrap_or_default();
+                if let Some(href) = link.take().filter(|href| !text.ends_with(href.trim_start_matches("mailto:"))) {
+                    push_text(&mut lines, &format!(" <{}>", href));
+                }
+            }
+            "a" => {
+                link = attribute(tag, "href").filter(|href| is_remote(href) || href.starts_with("mailto:"));
+            }
+            "img" => {
+                let alt = attribute(tag, "alt").filter(|alt| !alt.trim().is_empty());
+                let label = alt.map_or("[image]".to_string(), |alt| format!("[image: {}]", alt.trim()));
+                match attribute(tag, "src").filter(|src| is_remote(src)) {
+                    Some(src) if remote_content => push_text(&mut lines, &format!(" {} <{}> ", label, src)),
+                    Some(_) => {
+                        blocked += 1;
+                        push_text(&mut lines, &format!(" {} ", label));
+                    }
+                    None => push_text(&mut lines, &format!(" {} ", label)),
+                }
+            }
+            _ if BLOCK_ELEMENTS.contains(&name.as_str()) => new_line(&mut lines, quote_depth),
+            _ => {}
+        }
+    }
+
+    // One blank line at most, and none at the start
+    let mut text = String::new();
+    let mut after_blank = true;
+    for (depth, line) in &lines {
+        let line = line.trim();
+        if line.is_empty() {
+            if !after_blank {
+                text.push_str(&">".repeat(*depth));
+                text.push('\n');
+            }
+            after_blank = true;
+            continue;
+        }
+        after_blank = false;
+        text.push_str(&"> ".repeat(*depth));
+        text.push_str(line);
+        text.push('\n');
+    }
+    (text.trim_end().to_string() + "\n", blocked)
+}
+
+/// `text` with each run of quoted lines (those starting with `>`) folded into a line
+/// saying how many there are
+pub fn fold_quotes(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    let mut quoted = 0;
+    let flush = |folded: &mut String, quoted: &mut usize| {
+        if *quoted > 0 {
+            let lines = if *quoted == 1 { "line" } else { "lines" };
+            folded.push_str(&format!("[… {} quoted {}, 'Q' to show]\n", quoted, lines));
+            *quoted = 0;
+        }
+    };
+    for line in text.lines() {
+        if line.trim_start().starts_with('>') {
+            quoted += 1;
+            continue;
+        }
+        flush(&mut folded, &mut quoted);
+        folded.push_str(line);
+        folded.push('\n');
+    }
+    flush(&mut folded, &mut quoted);
+    folded
+}
+
+/// An HTML body to open in the browser. A Content-Security-Policy keeps scripts from
+/// running, and unless `remote_content` is set also keeps anything from being loaded
+/// from the network, such as tracking pixels.
+pub fn browser_html(html: &str, remote_content: bool) -> String {
+    let policy = if remote_content {
+        "script-src 'none'; object-src 'none'"
+    } else {
+        "default-src 'none'; style-src 'unsafe-inline'; img-src data: cid:"
+    };
+    format!("<meta http-equiv=\"Content-Security-Policy\" content=\"{}\">\n{}", policy, html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_truncates_at_a_line_break() {
+        let body = "line of text\r\n".repeat(TRUNCATED_LENGTH / 10);
+        let prepared = prepare(&body, false);
+        assert!(prepared.truncated);
+        assert!(prepared.text.len() <= TRUNCATED_LENGTH);
+        assert!(prepared.text.lines().all(|line| line == "line of text"));
+        assert_eq!(prepared.total_len, body.len());
+
+        let full = prepare(&body, true);
+        assert!(!full.truncated);
+        assert_eq!(full.text.lines().count(), TRUNCATED_LENGTH / 10);
+    }
+
+    #[test]
+    fn test_html_bodies_are_shown_as_text() {
+        let html = "<html><head><title>Hi</title><style>p { color: red; }</style></head><body>\
+                    <p>Hello&nbsp;<b>there</b>,<br>see <a href=\"https://example.com/a\">the plan</a>.</p>\
+                    <img alt=\"Logo\" src=\"https://track.example.com/p.gif\"><img src=\"cid:part1\">\
+                    <blockquote><p>Earlier</p><blockquote>Much earlier</blockquote></blockquote><p>Bye</p></body></html>";
+        let (text, blocked) = html_text(html, false);
+        assert_eq!(
+            text,
+            "Hello there,\nsee the plan <https://example.com/a>.\n\n[image: Logo] [image]\n> Earlier\n>\n> > Much earlier\nBye\n"
+        );
+        assert_eq!(blocked, 1);
+        let (text, blocked) = html_text(html, true);
+        assert!(text.contains("[image: Logo] <https://track.example.com/p.gif>"));
+        assert_eq!(blocked, 0);
+        assert!(browser_html(html, false).starts_with("<meta http-equiv=\"Content-Security-Policy\" content=\"default-src 'none'"));
+    }
+
+    #[test]
+    fn test_quotes_are_folded() {
+        let text = "Sounds good.\n\nOn Monday Ann wrote:\n> Lunch?\n>\n> > Or dinner\nBob\n> P.S.\n";
+        assert_eq!(
+            fold_quotes(text),
+            "Sounds good.\n\nOn Monday Ann wrote:\n[… 3 quoted lines, 'Q' to show]\nBob\n[… 1 quoted line, 'Q' to show]\n"
+        );
+    }
+
+    #[test]
+    fn test_prepare_drops_blank_runs_and_control_characters() {
+        let prepared = prepare("a\u{7}b\n\n\n\n\n\tc", true);
+        assert_eq!(prepared.text, "ab\n\n\n\tc\n");
+    }
+}
@@ -0,0 +1,201 @@
+use std::io::{Read, Write};
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::config_crypto::{self, ConfigEncryption};
+use crate::email::{ensure_writable, Email, EmailError};
+use crate::transport::ImapBackend;
+
+/// Environment variable consulted before prompting for a bundle passphrase
+pub const PASSPHRASE_ENV: &str = "TUIMAIL_BUNDLE_PASSPHRASE";
+
+const BUNDLE_VERSION: u32 = 1;
+const FETCH_BATCH: usize = 100;
+
+/// One message in a bundle, as listed by `tuimail import --list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub folder: String,
+    pub uid: u32,
+    pub date: i64,
+    pub from: String,
+    pub subject: String,
+    pub flags: Vec<String>,
+    pub attachments: Vec<String>,
+    /// Size of the raw message in bytes
+    pub size: usize,
+}
+
+/// Messages exported from an account. The raw messages are kept as the server sent them,
+/// attachments included, so an import restores them unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub created: i64,
+    pub account: String,
+    pub index: Vec<IndexEntry>,
+    /// Base64 of each raw message, in index order
+    messages: Vec<String>,
+}
+
+impl Bundle {
+    /// Seal the bundle into an age file, compressed before encryption
+    pub fn seal(&self, encryption: &ConfigEncryption) -> Result<String> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encryption.encrypt(&encoder.finish()?)
+    }
+
+    /// Open a sealed bundle. `passphrase` is called once to unlock it.
+    pub fn open(content: &str, passphrase: impl FnOnce() -> Result<String>) -> Result<Self> {
+        let (compressed, _) = config_crypto::decrypt_bytes(content, passphrase)?;
+        let mut json = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut json)
+            .context("Bundle content is corrupt")?;
+        let bundle: Bundle = serde_json::from_slice(&json).context("Bundle content is corrupt")?;
+        if bundle.version != BUNDLE_VERSION {
+            return Err(anyhow!("Unsupported bundle version {}", bundle.version));
+        }
+        if bundle.messages.len() != bundle.index.len() {
+            return Err(anyhow!("Bundle index does not match its messages"));
+        }
+        Ok(bundle)
+    }
+
+    /// Raw messages with their index entries
+    pub fn messages(&self) -> impl Iterator<Item = (&IndexEntry, Result<Vec<u8>>)> {
+        self.index
+            .iter()
+            .zip(&self.messages)
+            .map(|(entry, raw)| (entry, BASE64.decode(raw).context("Malformed message in bundle")))
+    }
+}
+
+/// Export messages of a folder into a bundle: the given UIDs, or the whole folder
+pub fn export(
+    imap: &mut dyn ImapBackend,
+    account_email: &str,
+    folder: &str,
+    uids: Option<&[u32]>,
+) -> Result<Bundle, EmailError> {
+    let uids = match uids {
+        Some(uids) => uids.to_vec(),
+        None => imap.uids(folder)?,
+    };
+
+    let mut bundle = Bundle {
+        version: BUNDLE_VERSION,
+        created: chrono::Local::now().timestamp(),
+        account: account_email.to_string(),
+        index: Vec::new(),
+        messages: Vec::new(),
+    };
+    for batch in uids.chunks(FETCH_BATCH) {
+        for message in imap.fetch(folder, batch)? {
+            bundle.index.push(index_entry(folder, message.uid, &message.flags, &message.raw));
+            bundle.messages.push(BASE64.encode(&message.raw));
+        }
+    }
+
+    if bundle.index.len() < uids.len() {
+        let missing: Vec<String> = uids
+            .iter()
+            .filter(|uid| !bundle.index.iter().any(|entry| entry.uid == **uid))
+            .map(|uid| uid.to_string())
+            .collect();
+        return Err(EmailError::NotFound(format!("UIDs {} in {}", missing.join(","), folder)));
+    }
+    Ok(bundle)
+}
+
+fn index_entry(folder: &str, uid: u32, flags: &[String], raw: &[u8]) -> IndexEntry {
+    let email = crate::mime::parse_message(raw)
+        .and_then(|parsed| Email::from_parsed_email(&parsed, &uid.to_string(), folder, flags.to_vec()).ok());
+    IndexEntry {
+        folder: folder.to_string(),
+        uid,
+        date: email.as_ref().map(|e| e.date.timestamp()).unwrap_or_default(),
+        from: email
+            .as_ref()
+            .and_then(|e| e.from.first())
+            .map(|a| a.address.clone())
+            .unwrap_or_default(),
+        subject: email.as_ref().map(|e| e.subject.clone()).unwrap_or_default(),
+        flags: flags.to_vec(),
+        attachments: email
+            .map(|e| e.attachments.into_iter().map(|a| a.filename).collect())
+            .unwrap_or_default(),
+        size: raw.len(),
+    }
+}
+
+/// Append the bundle's messages to the server, into `folder` or each message's original
+/// folder. Returns how many messages were imported; they appear at the next sync.
+pub fn import(imap: &mut dyn ImapBackend, bundle: &Bundle, folder: Option<&str>) -> Result<usize, EmailError> {
+    ensure_writable("import messages")?;
+
+    let mut imported = 0;
+    for (entry, raw) in bundle.messages() {
+        let raw = raw.map_err(|e| EmailError::ImapError(e.to_string()))?;
+        imap.append(folder.unwrap_or(&entry.folder), &raw, &entry.flags)?;
+        imported += 1;
+    }
+    Ok(imported)
+}
+
+/// Write a sealed bundle, readable only by the current user
+pub fn write_file(path: &std::path::Path, sealed: &str) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(sealed.as_bytes())?;
+    Ok(())
+}
+
+/// Read a bundle passphrase from `TUIMAIL_BUNDLE_PASSPHRASE`, or prompt without echo
+pub fn read_passphrase(prompt: &str) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    config_crypto::prompt_hidden(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_seal_and_open() {
+        let raw = b"From: alice@example.com\r\nSubject: Contract\r\n\r\nSigned copy attached.\r\n";
+        let bundle = Bundle {
+            version: BUNDLE_VERSION,
+            created: 0,
+            account: "me@example.com".to_string(),
+            index: vec![index_entry("Legal", 7, &["\\Seen".to_string()], raw)],
+            messages: vec![BASE64.encode(raw)],
+        };
+        assert_eq!((bundle.index[0].subject.as_str(), bundle.index[0].from.as_str()), ("Contract", "alice@example.com"));
+
+        // A low work factor keeps the test fast; the file records the one used
+        let encryption = ConfigEncryption::with_passphrase("hand over").unwrap().with_work_factor(10);
+        let sealed = bundle.seal(&encryption).unwrap();
+        assert!(!sealed.contains("Contract"));
+        assert!(Bundle::open(&sealed, || Ok("wrong".to_string())).is_err());
+
+        let opened = Bundle::open(&sealed, || Ok("hand over".to_string())).unwrap();
+        let (entry, message) = opened.messages().next().unwrap();
+        assert_eq!(entry.uid, 7);
+        assert_eq!(message.unwrap(), raw.to_vec());
+    }
+}
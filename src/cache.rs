@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::database::{CachedAttachment, EmailDatabase};
+use crate::email::debug_log;
+
+/// Files in the attachment cache no row refers to are removed once this old, so one
+/// written just before its row is not mistaken for a leftover
+const STRAY_FILE_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// What a cache collection found and did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GcReport {
+    /// Bytes of attachments held locally before and after
+    pub before: u64,
+    pub after: u64,
+    /// Attachments whose local copy was dropped
+    pub evicted: usize,
+    /// Leftover cache files removed
+    pub stray_files: usize,
+    /// Size of the database files afterwards
+    pub database_size: u64,
+}
+
+/// Local size of one attachment: the bytes in its row, or its cache file
+fn local_size(attachment: &CachedAttachment) -> u64 {
+    attachment.stored + attachment.path.as_ref().and_then(|path| std::fs::metadata(path).ok()).map_or(0, |m| m.len())
+}
+
+/// Remove files under `dir` that are not in `referenced` and are old enough, and the
+/// directories left empty. Returns how many files went.
+fn remove_stray_files(dir: &Path, referenced: &HashSet<PathBuf>, now: SystemTime) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            removed += remove_stray_files(&path, referenced, now);
+            // Fails while the directory still has files, which is fine
+            let _ = std::fs::remove_dir(&path);
+            continue;
+        }
+        let old = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= STRAY_FILE_AGE);
+        if old && !referenced.contains(&path) && std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Bytes free for unprivileged use on the file system holding `path`, or its nearest
+/// existing parent; `None` when that can't be told
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let existing = path.ancestors().find(|dir| dir.exists())?;
+    let c_path = std::ffi::CString::new(existing.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is NUL-terminated and `stat` is only read after statvfs filled it in
+    if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// The free space left under the cache directories of the profile and its accounts,
+/// when it is below the `min_free_mb` setting. Sync then only downloads headers.
+pub fn low_disk_space(config: &Config) -> Option<u64> {
+    let min_free = config.cache.min_free_bytes()?;
+    let mut dirs = vec![crate::profile::cache_dir()];
+    dirs.extend(config.accounts.iter().map(|account| crate::profile::account_cache_dir(&account.email)));
+    dirs.iter().filter_map(|dir| free_space(dir)).min().filter(|free| *free < min_free)
+}
+
+/// The databases of the active profile that exist: the shared one and those of each
+/// account
+pub fn open_databases(config: &Config) -> Vec<EmailDatabase> {
+    let mut paths = vec![crate::profile::database_path()];
+    for account in &config.accounts {
+        let path = crate::profile::account_cache_dir(&account.email).join("emails.db");
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+        .into_iter()
+        .filter(|path| path.exists())
+        .filter_map(|path| match EmailDatabase::new(&path) {
+            Ok(db) => Some(db),
+            Err(e) => {
+                debug_log(&format!("cache: could not open {}: {}", path.display(), e));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Bytes of attachments held locally across `databases`
+pub fn usage(databases: &[EmailDatabase]) -> Result<u64> {
+    let mut total = 0;
+    for db in databases {
+        total += db.cached_attachments()?.iter().map(local_size).sum::<u64>();
+    }
+    Ok(total)
+}
+
+/// Bring the attachments held locally across `databases` down to `max_bytes`, dropping
+/// the least recently opened first. Only attachments of messages still on the server
+/// are dropped, as they are downloaded again when needed; leftover cache files go too.
+pub fn collect(databases: &[EmailDatabase], max_bytes: u64) -> Result<GcReport> {
+    let mut report = GcReport::default();
+    let now = SystemTime::now();
+
+    let mut candidates: Vec<(usize, CachedAttachment, u64)> = Vec::new();
+    for (index, db) in databases.iter().enumerate() {
+        let attachments = db.cached_attachments()?;
+        let referenced: HashSet<PathBuf> = attachments.iter().filter_map(|a| a.path.clone()).collect();
+        report.stray_files += remove_stray_files(&db.attachment_cache_dir(), &referenced, now);
+        for attachment in attachments {
+            let size = local_size(&attachment);
+            report.before += size;
+            if attachment.on_server {
+                candidates.push((index, attachment, size));
+            }
+        }
+    }
+
+    candidates.sort_by_key(|(_, attachment, _)| attachment.used_at);
+    let mut total = report.before;
+    let mut shrunk = vec![false; databases.len()];
+    for (index, attachment, size) in candidates {
+        if total <= max_bytes {
+            break;
+        }
+        databases[index].evict_attachment(attachment.id)?;
+        if let Some(path) = &attachment.path {
+            if let Err(e) = std::fs::remove_file(path) {
+                debug_log(&format!("cache: could not remove {}: {}", path.display(), e));
+            }
+            // The message's directory, once empty
+            let _ = path.parent().map(std::fs::remove_dir);
+        }
+        shrunk[index] |= attachment.stored > 0;
+        total -= size;
+        report.evicted += 1;
+    }
+    report.after = total;
+
+    for (db, shrunk) in databases.iter().zip(shrunk) {
+        if shrunk {
+            db.vacuum()?;
+        }
+        report.database_size += db.get_database_size()?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::{Email, EmailAttachment};
+
+    fn email(uid: u32, data: Vec<u8>) -> Email {
+        let mut email = Email::new();
+        email.id = uid.to_string();
+        email.folder = "INBOX".to_string();
+        email.subject = format!("Message {}", uid);
+        email.attachments.push(EmailAttachment {
+            filename: "report.bin".to_string(),
+            content_type: "application/octet-stream".to_string(),
+            size: data.len(),
+            data,
+            path: None,
+        });
+        email
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn free_space_is_read_from_the_nearest_existing_directory() {
+        let missing = std::env::temp_dir().join("tuimail-no-such-dir").join("deeper");
+        assert!(free_space(&missing).is_some());
+
+        let mut config = Config::default();
+        config.cache.min_free_mb = Some(u64::MAX / (1024 * 1024));
+        assert!(low_disk_space(&config).is_some());
+        config.cache.min_free_mb = None;
+        assert_eq!(low_disk_space(&config), None);
+    }
+
+    #[test]
+    fn least_recently_opened_attachments_go_first() {
+        let dir = std::env::temp_dir().join(format!("tuimail-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let big = crate::database::ATTACHMENT_SPILL_THRESHOLD + 1;
+        let local_uid = crate::database::LOCAL_UID_FLOOR + 1;
+        db.save_emails("me@example.com", "INBOX", &[email(1, vec![1; big]), email(2, vec![2; 1000]), email(local_uid, vec![3; 1000])])
+            .unwrap();
+        db.execute_sql("UPDATE attachments SET used_at = 100").unwrap();
+        db.touch_attachments("me@example.com", "INBOX", 1).unwrap();
+        let stray = db.attachment_cache_dir().join("leftover.bin");
+        std::fs::write(&stray, b"old").unwrap();
+
+        let spilled = db.cached_attachments().unwrap().into_iter().find_map(|a| a.path).unwrap();
+        let databases = [db];
+        assert_eq!(usage(&databases).unwrap(), big as u64 + 2000);
+        let report = collect(&databases, big as u64 + 1500).unwrap();
+        // Message 2 was opened longest ago; the sent copy that never reached the server stays
+        assert_eq!((report.evicted, report.after), (1, big as u64 + 1000));
+        // Too new to be taken for a leftover
+        assert_eq!(report.stray_files, 0);
+        assert!(stray.exists());
+
+        let report = collect(&databases, 0).unwrap();
+        assert_eq!((report.evicted, report.after), (1, 1000));
+        let loaded = databases[0].load_emails("me@example.com", "INBOX").unwrap();
+        let first = loaded.iter().find(|e| e.id == "1").unwrap();
+        assert!(!first.attachments[0].is_available());
+        assert_eq!(first.attachments[0].size(), big);
+        assert!(!spilled.exists() && !spilled.parent().unwrap().exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
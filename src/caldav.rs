@@ -0,0 +1,180 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use roxmltree::Document;
+
+use crate::calendar::Event;
+use crate::email::EmailError;
+
+const DAV_NS: &str = "DAV:";
+const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+
+/// Largest CalDAV response read
+const RESPONSE_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// One request to the account's calendar collection: method, extra headers and body,
+/// against a path relative to the collection
+pub trait CalDavHttp {
+    fn call(&mut self, method: &str, resource: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<(u16, Vec<u8>)>;
+}
+
+/// Talks to a CalDAV calendar collection over HTTPS with basic authentication
+pub struct CalDavHttpClient {
+    host: String,
+    /// Path of the collection, ending in `/`
+    path: String,
+    authorization: String,
+}
+
+impl CalDavHttpClient {
+    pub fn new(url: &str, username: &str, password: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("https://")
+            .ok_or_else(|| anyhow!("CalDAV URL must start with https://: {}", url))?;
+        let (host, path) = rest.split_once('/').map_or((rest, "/".to_string()), |(host, path)| (host, format!("/{}", path)));
+        crate::http::split_authority(host).map_err(|e| anyhow!("Bad CalDAV URL {}: {}", url, e))?;
+        let path = if path.ends_with('/') { path } else { format!("{}/", path) };
+        let authorization = format!("Basic {}", BASE64.encode(format!("{}:{}", username, password)));
+        Ok(Self { host: host.to_string(), path, authorization })
+    }
+}
+
+impl CalDavHttp for CalDavHttpClient {
+    fn call(&mut self, method: &str, resource: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<(u16, Vec<u8>)> {
+        let mut all = vec![("Authorization", self.authorization.as_str())];
+        all.extend_from_slice(headers);
+        crate::http::https_request(method, &self.host, &format!("{}{}", self.path, resource), &all, Some(body), RESPONSE_LIMIT)
+    }
+}
+
+/// `20240306T150000Z`, as time ranges are written
+fn utc_stamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// A resource name for an event UID: letters, digits, `-`, `_`, `.` and `@` kept
+fn resource_name(uid: &str) -> String {
+    let name: String = uid
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@') { c } else { '-' })
+        .collect();
+    format!("{}.ics", name.trim_matches('.'))
+}
+
+/// A calendar collection (RFC 4791)
+pub struct CalDav<H> {
+    http: H,
+}
+
+impl<H: CalDavHttp> CalDav<H> {
+    pub fn new(http: H) -> Self {
+        Self { http }
+    }
+
+    fn check(status: u16, what: &str) -> Result<(), EmailError> {
+        match status {
+            200..=299 => Ok(()),
+            401 | 403 => Err(EmailError::AuthError("The calendar refused the username or password".to_string())),
+            _ => Err(EmailError::ConnectionError(format!("CalDAV {} failed with HTTP {}", what, status))),
+        }
+    }
+
+    /// Events overlapping `start`..`end`, recurring ones expanded by the server into
+    /// their occurrences
+    pub fn events_between(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<Event>, EmailError> {
+        let (start, end) = (utc_stamp(start), utc_stamp(end));
+        let query = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+             <C:calendar-query xmlns:D=\"{}\" xmlns:C=\"{}\">\
+             <D:prop><C:calendar-data><C:expand start=\"{start}\" end=\"{end}\"/></C:calendar-data></D:prop>\
+             <C:filter><C:comp-filter name=\"VCALENDAR\"><C:comp-filter name=\"VEVENT\">\
+             <C:time-range start=\"{start}\" end=\"{end}\"/></C:comp-filter></C:comp-filter></C:filter>\
+             </C:calendar-query>",
+            DAV_NS, CALDAV_NS
+        );
+        let (status, response) = self
+            .http
+            .call("REPORT", "", &[("Depth", "1"), ("Content-Type", "application/xml; charset=utf-8")], query.as_bytes())
+            .map_err(|e| EmailError::ConnectionError(format!("CalDAV: {}", e)))?;
+        Self::check(status, "search")?;
+
+        let text = String::from_utf8_lossy(&response);
+        let document = Document::parse(&text).map_err(|e| EmailError::ImapError(format!("Malformed CalDAV response: {}", e)))?;
+        Ok(document
+            .descendants()
+            .filter(|n| n.has_tag_name((CALDAV_NS, "calendar-data")))
+            .filter_map(|n| crate::calendar::parse(n.text()?).ok())
+            .flat_map(|calendar| calendar.events)
+            .collect())
+    }
+
+    /// Store an event, replacing the earlier copy with the same UID
+    pub fn put_event(&mut self, uid: &str, ics: &str) -> Result<(), EmailError> {
+        let (status, _) = self
+            .http
+            .call("PUT", &resource_name(uid), &[("Content-Type", "text/calendar; charset=utf-8")], ics.as_bytes())
+            .map_err(|e| EmailError::ConnectionError(format!("CalDAV: {}", e)))?;
+        Self::check(status, "upload")
+    }
+}
+
+/// Busy events of `calendar` that overlap `event`, leaving out the event itself when it
+/// is already there
+pub fn conflicts<'a>(event: &Event, calendar: &'a [Event]) -> Vec<&'a Event> {
+    calendar
+        .iter()
+        .filter(|other| other.is_busy() && other.overlaps(event) && (event.uid.is_empty() || other.uid != event.uid))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[derive(Default)]
+    struct Recorder {
+        requests: Vec<(String, String, String)>,
+    }
+
+    impl CalDavHttp for &mut Recorder {
+        fn call(&mut self, method: &str, resource: &str, _headers: &[(&str, &str)], body: &[u8]) -> Result<(u16, Vec<u8>)> {
+            self.requests.push((method.to_string(), resource.to_string(), String::from_utf8_lossy(body).into_owned()));
+            let answer = if method == "REPORT" {
+                "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\" xmlns:cal=\"urn:ietf:params:xml:ns:caldav\">\
+                 <d:response><d:href>/cal/a.ics</d:href><d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR\n\
+                 BEGIN:VEVENT\nUID:a\nSUMMARY:Standup\nDTSTART:20240306T093000Z\nDTEND:20240306T094500Z\nEND:VEVENT\n\
+                 END:VCALENDAR\n</cal:calendar-data></d:prop></d:propstat></d:response>\
+                 <d:response><d:href>/cal/b.ics</d:href><d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR\n\
+                 BEGIN:VEVENT\nUID:b\nSUMMARY:Lunch\nDTSTART:20240306T120000Z\nDTEND:20240306T130000Z\nEND:VEVENT\n\
+                 END:VCALENDAR\n</cal:calendar-data></d:prop></d:propstat></d:response></d:multistatus>"
+            } else {
+                ""
+            };
+            Ok((if method == "PUT" { 201 } else { 207 }, answer.as_bytes().to_vec()))
+        }
+    }
+
+    #[test]
+    fn events_are_searched_and_stored() {
+        let mut recorder = Recorder::default();
+        let mut caldav = CalDav::new(&mut recorder);
+        let start = Utc.with_ymd_and_hms(2024, 3, 6, 9, 0, 0).unwrap();
+        let events = caldav.events_between(start, start + chrono::Duration::hours(1)).unwrap();
+        assert_eq!(events.len(), 2);
+
+        let meeting = Event { uid: "x".to_string(), start, end: start + chrono::Duration::hours(1), ..events[1].clone() };
+        let clashes = conflicts(&meeting, &events);
+        assert_eq!(clashes.len(), 1);
+        assert_eq!(clashes[0].summary, "Standup");
+
+        caldav.put_event("040000/8200?E0", "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n").unwrap();
+        assert!(recorder.requests[0].2.contains("<C:time-range start=\"20240306T090000Z\" end=\"20240306T100000Z\"/>"));
+        assert_eq!(recorder.requests[1].1, "040000-8200-E0.ics");
+
+        assert!(CalDavHttpClient::new("http://example.com/cal", "u", "p").is_err());
+        assert_eq!(CalDavHttpClient::new("https://example.com/dav/cal", "u", "p").unwrap().path, "/dav/cal/");
+        assert_eq!(CalDavHttpClient::new("https://example.com:8443/cal", "u", "p").unwrap().host, "example.com:8443");
+        assert!(CalDavHttpClient::new("https://example.com:dav/cal", "u", "p").is_err());
+    }
+}
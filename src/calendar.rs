@@ -0,0 +1,633 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+
+use crate::email::Email;
+
+/// An event of an iCalendar object (RFC 5545), with times in UTC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Given as dates rather than times; such events start at local midnight
+    pub all_day: bool,
+    pub location: Option<String>,
+    /// Address of the organizer, without `mailto:`
+    pub organizer: Option<String>,
+    /// `CONFIRMED`, `TENTATIVE` or `CANCELLED`
+    pub status: Option<String>,
+    /// `TRANSP:TRANSPARENT` events don't take up time
+    pub transparent: bool,
+    pub attendees: Vec<Attendee>,
+}
+
+/// Someone invited to an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attendee {
+    pub address: String,
+    pub name: Option<String>,
+    /// `NEEDS-ACTION`, `ACCEPTED`, `TENTATIVE`, `DECLINED` or `DELEGATED`
+    pub status: String,
+    /// `OPT-PARTICIPANT` when attending is optional
+    pub optional: bool,
+}
+
+/// An answer to an invitation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    Accepted,
+    Tentative,
+    Declined,
+}
+
+impl Response {
+    /// The `PARTSTAT` value
+    pub fn status(self) -> &'static str {
+        match self {
+            Response::Accepted => "ACCEPTED",
+            Response::Tentative => "TENTATIVE",
+            Response::Declined => "DECLINED",
+        }
+    }
+
+    /// As reply subjects start: `Accepted: Planning`
+    pub fn label(self) -> &'static str {
+        match self {
+            Response::Accepted => "Accepted",
+            Response::Tentative => "Tentative",
+            Response::Declined => "Declined",
+        }
+    }
+}
+
+impl Event {
+    /// Whether the two events take up some of the same time
+    pub fn overlaps(&self, other: &Event) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// Whether the event keeps you busy: not cancelled and not marked free
+    pub fn is_busy(&self) -> bool {
+        !self.transparent && !self.status.as_deref().is_some_and(|status| status.eq_ignore_ascii_case("CANCELLED"))
+    }
+
+    /// When it is, in local time: `Wed 6 Mar 15:00–16:00`, or just the days when all-day
+    pub fn when(&self) -> String {
+        let start = self.start.with_timezone(&Local);
+        let end = self.end.with_timezone(&Local);
+        if self.all_day {
+            let last = end - Duration::days(1);
+            return if last.date_naive() <= start.date_naive() {
+                start.format("%a %-d %b %Y").to_string()
+            } else {
+                format!("{}–{}", start.format("%a %-d %b"), last.format("%a %-d %b %Y"))
+            };
+        }
+        if start.date_naive() == end.date_naive() {
+            format!("{}–{}", start.format("%a %-d %b %Y %H:%M"), end.format("%H:%M"))
+        } else {
+            format!("{} – {}", start.format("%a %-d %b %H:%M"), end.format("%a %-d %b %Y %H:%M"))
+        }
+    }
+}
+
+/// An iCalendar object: the events it holds and, for an invitation, its iTIP method
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Calendar {
+    /// `REQUEST` for an invitation, `CANCEL`, `REPLY` and so on
+    pub method: Option<String>,
+    pub events: Vec<Event>,
+}
+
+/// One content line: name, parameters and value
+#[derive(Debug)]
+pub(crate) struct Property {
+    pub(crate) name: String,
+    pub(crate) params: Vec<(String, String)>,
+    pub(crate) value: String,
+}
+
+impl Property {
+    pub(crate) fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Lines of `ics` with folded continuation lines joined back (RFC 5545, 3.1)
+pub(crate) fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Split a content line at the first colon outside a quoted parameter value
+pub(crate) fn parse_line(line: &str) -> Option<Property> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(i),
+            _ => {}
+        }
+        None
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.trim().to_ascii_uppercase(), value.trim_matches('"').to_string()))
+        .collect();
+    Some(Property { name, params, value: value.to_string() })
+}
+
+/// Undo the escaping of TEXT values
+pub(crate) fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
+
+/// A yearly rule such as `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`, as the month, weekday and
+/// which one of the month (negative counts from the end)
+fn yearly_rule(rrule: &str) -> Option<(u32, Weekday, i32)> {
+    let mut month = None;
+    let mut day = None;
+    for part in rrule.split(';') {
+        match part.split_once('=')? {
+            ("FREQ", freq) if freq != "YEARLY" => return None,
+            ("BYMONTH", value) => month = value.parse().ok(),
+            ("BYDAY", value) => {
+                let split = value.len().checked_sub(2)?;
+                let weekday = match &value[split..] {
+                    "MO" => Weekday::Mon,
+                    "TU" => Weekday::Tue,
+                    "WE" => Weekday::Wed,
+                    "TH" => Weekday::Thu,
+                    "FR" => Weekday::Fri,
+                    "SA" => Weekday::Sat,
+                    "SU" => Weekday::Sun,
+                    _ => return None,
+                };
+                day = Some((weekday, value[..split].parse().unwrap_or(1)));
+            }
+            _ => {}
+        }
+    }
+    let (weekday, nth) = day?;
+    Some((month?, weekday, nth))
+}
+
+/// The `nth` `weekday` of a month; negative `nth` counts from its end
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, nth: i32) -> Option<NaiveDate> {
+    if nth > 0 {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let ahead = (7 + weekday.num_days_from_monday() - first.weekday().num_days_from_monday()) % 7;
+        first.checked_add_signed(Duration::days(i64::from(ahead) + 7 * i64::from(nth - 1)))
+    } else {
+        let next = if month == 12 { NaiveDate::from_ymd_opt(year + 1, 1, 1)? } else { NaiveDate::from_ymd_opt(year, month + 1, 1)? };
+        let last = next.pred_opt()?;
+        let back = (7 + last.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        last.checked_sub_signed(Duration::days(i64::from(back) + 7 * i64::from(-nth - 1)))
+    }
+}
+
+/// A `STANDARD` or `DAYLIGHT` part of a `VTIMEZONE`
+#[derive(Debug, Default)]
+struct Observance {
+    start: Option<NaiveDateTime>,
+    offset_from: i32,
+    offset_to: i32,
+    rule: Option<(u32, Weekday, i32)>,
+}
+
+/// A `VTIMEZONE`, enough of it for the usual yearly daylight saving rules
+#[derive(Debug, Default)]
+struct Zone {
+    id: String,
+    observances: Vec<Observance>,
+}
+
+impl Zone {
+    /// The UTC offset in seconds in force at local time `at`: that of the latest change
+    /// before it
+    fn offset_at(&self, at: NaiveDateTime) -> Option<i32> {
+        let mut latest: Option<(NaiveDateTime, i32)> = None;
+        for observance in &self.observances {
+            let start = observance.start?;
+            let mut changes = vec![start];
+            if let Some((month, weekday, nth)) = observance.rule {
+                for year in [at.year() - 1, at.year()] {
+                    if let Some(day) = nth_weekday(year, month, weekday, nth) {
+                        changes.push(day.and_time(start.time()));
+                    }
+                }
+            }
+            for change in changes.into_iter().filter(|change| *change <= at && *change >= start) {
+                if latest.is_none_or(|(when, _)| change > when) {
+                    latest = Some((change, observance.offset_to));
+                }
+            }
+        }
+        latest
+            .map(|(_, offset)| offset)
+            .or_else(|| self.observances.iter().filter(|o| o.start.is_some()).min_by_key(|o| o.start).map(|o| o.offset_from))
+    }
+}
+
+/// `+0100` or `-0530` as seconds
+fn parse_offset(value: &str) -> Option<i32> {
+    let (sign, digits) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => (1, value),
+    };
+    let hours: i32 = digits.get(0..2)?.parse().ok()?;
+    let minutes: i32 = digits.get(2..4)?.parse().ok()?;
+    let seconds: i32 = digits.get(4..6).and_then(|s| s.parse().ok()).unwrap_or(0);
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// A `DURATION` such as `PT1H30M` or `P1D`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = value.strip_prefix('-').map_or((false, value), |rest| (true, rest));
+    let value = value.strip_prefix('+').unwrap_or(value).strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let count: i64 = number.parse().ok()?;
+                number.clear();
+                total += match unit {
+                    'W' => Duration::weeks(count),
+                    'D' => Duration::days(count),
+                    'H' => Duration::hours(count),
+                    'M' => Duration::minutes(count),
+                    'S' => Duration::seconds(count),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// A DATE or DATE-TIME value: UTC when it ends in `Z`, in the `TZID` zone when the
+/// calendar defines it, and local time otherwise. Returns whether it was a date.
+fn parse_time(property: &Property, zones: &[Zone]) -> Option<(DateTime<Utc>, bool)> {
+    let value = property.value.trim();
+    if property.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let midnight = Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()?;
+        return Some((midnight.with_timezone(&Utc), true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time), false));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let offset = property
+        .param("TZID")
+        .and_then(|id| zones.iter().find(|zone| zone.id == id))
+        .and_then(|zone| zone.offset_at(time));
+    match offset {
+        Some(offset) => Some((Utc.from_utc_datetime(&(time - Duration::seconds(i64::from(offset)))), false)),
+        None => Some((Local.from_local_datetime(&time).earliest()?.with_timezone(&Utc), false)),
+    }
+}
+
+/// Parse an iCalendar object. Events without a start are left out; recurring events
+/// are read as their first occurrence.
+pub fn parse(ics: &str) -> Result<Calendar, String> {
+    let properties: Vec<Property> = unfold(ics).iter().filter_map(|line| parse_line(line)).collect();
+    if !properties.iter().any(|p| p.name == "BEGIN" && p.value.eq_ignore_ascii_case("VCALENDAR")) {
+        return Err("Not an iCalendar object".to_string());
+    }
+
+    // Time zones first, as events may come before them
+    let mut zones: Vec<Zone> = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    for property in &properties {
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.to_ascii_uppercase();
+                match component.as_str() {
+                    "VTIMEZONE" => zones.push(Zone::default()),
+                    "STANDARD" | "DAYLIGHT" => {
+                        if let Some(zone) = zones.last_mut() {
+                            zone.observances.push(Observance::default());
+                        }
+                    }
+                    _ => {}
+                }
+                stack.push(component);
+            }
+            "END" => {
+                stack.pop();
+            }
+            name => {
+                let (Some(zone), Some(component)) = (zones.last_mut(), stack.last()) else {
+                    continue;
+                };
+                if component == "VTIMEZONE" && name == "TZID" {
+                    zone.id = property.value.clone();
+                } else if matches!(component.as_str(), "STANDARD" | "DAYLIGHT") {
+                    let Some(observance) = zone.observances.last_mut() else {
+                        continue;
+                    };
+                    match name {
+                        "DTSTART" => observance.start = NaiveDateTime::parse_from_str(property.value.trim(), "%Y%m%dT%H%M%S").ok(),
+                        "TZOFFSETFROM" => observance.offset_from = parse_offset(&property.value).unwrap_or(0),
+                        "TZOFFSETTO" => observance.offset_to = parse_offset(&property.value).unwrap_or(0),
+                        "RRULE" => observance.rule = yearly_rule(&property.value.to_ascii_uppercase()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    let mut calendar = Calendar::default();
+    let mut event: Option<Vec<&Property>> = None;
+    let mut depth = 0;
+    for property in &properties {
+        match (property.name.as_str(), event.as_mut()) {
+            ("BEGIN", None) if property.value.eq_ignore_ascii_case("VEVENT") => event = Some(Vec::new()),
+            // Alarms and other parts inside the event
+            ("BEGIN", Some(_)) => depth += 1,
+            ("END", Some(_)) if depth > 0 => depth -= 1,
+            ("END", Some(_)) => {
+                if let Some(parsed) = event.take().and_then(|properties| event_from(&properties, &zones)) {
+                    calendar.events.push(parsed);
+                }
+            }
+            ("METHOD", None) => calendar.method = Some(property.value.trim().to_ascii_uppercase()),
+            (_, Some(properties)) if depth == 0 => properties.push(property),
+            _ => {}
+        }
+    }
+    Ok(calendar)
+}
+
+/// A `mailto:` value as the bare address
+fn mailto(value: &str) -> String {
+    let value = value.trim();
+    value.get(..7).filter(|s| s.eq_ignore_ascii_case("mailto:")).map_or(value, |_| &value[7..]).to_string()
+}
+
+fn event_from(properties: &[&Property], zones: &[Zone]) -> Option<Event> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name).copied();
+    let (start, all_day) = parse_time(get("DTSTART")?, zones)?;
+    let end = match (get("DTEND").and_then(|p| parse_time(p, zones)), get("DURATION").and_then(|p| parse_duration(p.value.trim()))) {
+        (Some((end, _)), _) => end,
+        (None, Some(duration)) => start + duration,
+        (None, None) if all_day => start + Duration::days(1),
+        (None, None) => start,
+    };
+    Some(Event {
+        uid: get("UID").map(|p| p.value.trim().to_string()).unwrap_or_default(),
+        summary: get("SUMMARY").map(|p| unescape(&p.value)).unwrap_or_default(),
+        start,
+        end: end.max(start),
+        all_day,
+        location: get("LOCATION").map(|p| unescape(&p.value)).filter(|l| !l.is_empty()),
+        organizer: get("ORGANIZER").map(|p| mailto(&p.value)),
+        status: get("STATUS").map(|p| p.value.trim().to_ascii_uppercase()),
+        transparent: get("TRANSP").is_some_and(|p| p.value.trim().eq_ignore_ascii_case("TRANSPARENT")),
+        attendees: properties
+            .iter()
+            .filter(|p| p.name == "ATTENDEE")
+            .map(|p| Attendee {
+                address: mailto(&p.value),
+                name: p.param("CN").map(str::to_string).filter(|name| !name.is_empty()),
+                status: p.param("PARTSTAT").unwrap_or("NEEDS-ACTION").to_ascii_uppercase(),
+                optional: p.param("ROLE").is_some_and(|role| role.eq_ignore_ascii_case("OPT-PARTICIPANT")),
+            })
+            .collect(),
+    })
+}
+
+/// A calendar invitation or update carried by a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invitation {
+    pub calendar: Calendar,
+    /// The iCalendar object as it came
+    pub ics: String,
+}
+
+impl Invitation {
+    /// The event asked about; invitations hold one, possibly with exceptions after it
+    pub fn event(&self) -> Option<&Event> {
+        self.calendar.events.first()
+    }
+
+    /// The object to store in a calendar: the same, without the iTIP `METHOD`, which
+    /// stored objects must not have (RFC 4791, 4.1)
+    pub fn calendar_object(&self) -> String {
+        let lines: Vec<&str> = self.ics.lines().filter(|line| !line.to_ascii_uppercase().starts_with("METHOD:")).collect();
+        let mut object = lines.join("\r\n");
+        object.push_str("\r\n");
+        object
+    }
+}
+
+/// Fold a content line into lines of at most 75 octets (RFC 5545, 3.1)
+pub(crate) fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+impl Invitation {
+    /// The iTIP reply (RFC 5546, 3.2.3) giving `response` as `attendee`, answering the
+    /// first event. It repeats what identifies the event and the time zones it uses.
+    pub fn reply(&self, attendee: &str, name: Option<&str>, response: Response, now: DateTime<Utc>) -> Result<String, String> {
+        let event = self.event().ok_or_else(|| "The invitation holds no event".to_string())?;
+        if event.organizer.is_none() {
+            return Err("The invitation names no organizer to reply to".to_string());
+        }
+
+        let mut timezones = String::new();
+        let mut fields = String::new();
+        let mut stack: Vec<String> = Vec::new();
+        let mut events_seen = 0;
+        for line in unfold(&self.ics) {
+            let Some(property) = parse_line(&line) else {
+                continue;
+            };
+            match property.name.as_str() {
+                "BEGIN" => {
+                    let component = property.value.trim().to_ascii_uppercase();
+                    if component == "VEVENT" {
+                        events_seen += 1;
+                    }
+                    stack.push(component);
+                }
+                "END" => {
+                    if stack.iter().any(|c| c == "VTIMEZONE") {
+                        timezones.push_str(&fold(&line));
+                    }
+                    stack.pop();
+                    continue;
+                }
+                _ => {}
+            }
+            if stack.iter().any(|c| c == "VTIMEZONE") {
+                timezones.push_str(&fold(&line));
+            } else if events_seen == 1
+                && stack.last().is_some_and(|c| c == "VEVENT")
+                && matches!(
+                    property.name.as_str(),
+                    "UID" | "SEQUENCE" | "RECURRENCE-ID" | "DTSTART" | "DTEND" | "DURATION" | "ORGANIZER" | "SUMMARY"
+                )
+            {
+                fields.push_str(&fold(&line));
+            }
+        }
+
+        let cn = name.map(|name| format!(";CN=\"{}\"", name.replace(['"', '\r', '\n'], ""))).unwrap_or_default();
+        Ok(format!(
+            "BEGIN:VCALENDAR\r\nPRODID:-//tuimail//EN\r\nVERSION:2.0\r\nMETHOD:REPLY\r\n{}BEGIN:VEVENT\r\n{}DTSTAMP:{}\r\n{}END:VEVENT\r\nEND:VCALENDAR\r\n",
+            timezones,
+            fields,
+            now.format("%Y%m%dT%H%M%SZ"),
+            fold(&format!("ATTENDEE;PARTSTAT={}{}:mailto:{}", response.status(), cn, attendee)),
+        ))
+    }
+}
+
+/// The invitation in `email`, from a `text/calendar` part or an `.ics` attachment
+pub fn invitation(email: &Email) -> Option<Invitation> {
+    email
+        .attachments
+        .iter()
+        .filter(|attachment| {
+            matches!(attachment.content_type.as_str(), "text/calendar" | "application/ics")
+                || attachment.filename.to_lowercase().ends_with(".ics")
+        })
+        .find_map(|attachment| {
+            let ics = String::from_utf8_lossy(&attachment.load_data().ok()?).into_owned();
+            let calendar = parse(&ics).ok().filter(|calendar| !calendar.events.is_empty())?;
+            Some(Invitation { calendar, ics })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVITE: &str = "BEGIN:VCALENDAR\r\n\
+METHOD:REQUEST\r\n\
+PRODID:Microsoft Exchange Server 2010\r\n\
+BEGIN:VTIMEZONE\r\n\
+TZID:W. Europe Standard Time\r\n\
+BEGIN:STANDARD\r\n\
+DTSTART:16010101T030000\r\n\
+TZOFFSETFROM:+0200\r\n\
+TZOFFSETTO:+0100\r\n\
+RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=10\r\n\
+END:STANDARD\r\n\
+BEGIN:DAYLIGHT\r\n\
+DTSTART:16010101T020000\r\n\
+TZOFFSETFROM:+0100\r\n\
+TZOFFSETTO:+0200\r\n\
+RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=3\r\n\
+END:DAYLIGHT\r\n\
+END:VTIMEZONE\r\n\
+BEGIN:VEVENT\r\n\
+ORGANIZER;CN=\"Smith, Ann\":mailto:ann@example.com\r\n\
+SUMMARY;LANGUAGE=en-US:Planning\\, Q3\r\n\
+DTSTART;TZID=W. Europe Standard Time:20240402T150000\r\n\
+DTEND;TZID=W. Europe Standard Time:20240402T160000\r\n\
+UID:040000008200E00074C5B7101A82E008\r\n\
+LOCATION:Room 4\r\n\
+BEGIN:VALARM\r\n\
+TRIGGER:-PT15M\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn invitations_are_parsed_with_their_time_zone() {
+        let calendar = parse(INVITE).unwrap();
+        assert_eq!(calendar.method.as_deref(), Some("REQUEST"));
+        let event = &calendar.events[0];
+        assert_eq!(event.summary, "Planning, Q3");
+        assert_eq!(event.organizer.as_deref(), Some("ann@example.com"));
+        assert_eq!(event.location.as_deref(), Some("Room 4"));
+        // Summer time: two hours ahead of UTC
+        assert_eq!(event.start, Utc.with_ymd_and_hms(2024, 4, 2, 13, 0, 0).unwrap());
+        assert_eq!(event.end, Utc.with_ymd_and_hms(2024, 4, 2, 14, 0, 0).unwrap());
+
+        let winter = INVITE.replace("20240402T150000", "20240312T150000").replace("20240402T160000", "20240312T160000");
+        assert_eq!(parse(&winter).unwrap().events[0].start, Utc.with_ymd_and_hms(2024, 3, 12, 14, 0, 0).unwrap());
+
+        let invitation = Invitation { calendar, ics: INVITE.to_string() };
+        assert!(!invitation.calendar_object().contains("METHOD"));
+        assert!(parse("hello").is_err());
+    }
+
+    #[test]
+    fn replies_repeat_the_event_and_give_the_answer() {
+        let ics = INVITE.replace(
+            "LOCATION:Room 4\r\n",
+            "LOCATION:Room 4\r\nATTENDEE;ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;RSVP=TRUE;CN=Bob:mailto:bob@example.com\r\n",
+        );
+        let calendar = parse(&ics).unwrap();
+        assert_eq!(calendar.events[0].attendees[0].address, "bob@example.com");
+        assert_eq!(calendar.events[0].attendees[0].status, "NEEDS-ACTION");
+
+        let invitation = Invitation { calendar, ics };
+        let now = Utc.with_ymd_and_hms(2024, 3, 6, 12, 0, 0).unwrap();
+        let reply = invitation.reply("bob@example.com", Some("Bob"), Response::Tentative, now).unwrap();
+        let parsed = parse(&reply).unwrap();
+        assert_eq!(parsed.method.as_deref(), Some("REPLY"));
+        assert_eq!(parsed.events[0].uid, "040000008200E00074C5B7101A82E008");
+        assert_eq!(parsed.events[0].start, invitation.event().unwrap().start);
+        assert_eq!(parsed.events[0].attendees[0].status, "TENTATIVE");
+        assert!(reply.contains("DTSTAMP:20240306T120000Z\r\n"));
+        assert!(!reply.contains("LOCATION") && !reply.contains("VALARM") && !reply.contains("RSVP"));
+        assert!(reply.lines().all(|line| line.len() <= 75));
+    }
+
+    #[test]
+    fn durations_and_overlaps() {
+        let ics = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:a\nDTSTART:20240306T090000Z\nDURATION:PT1H30M\nEND:VEVENT\n\
+BEGIN:VEVENT\nUID:b\nDTSTART:20240306T103000Z\nDTEND:20240306T110000Z\nTRANSP:TRANSPARENT\nEND:VEVENT\nEND:VCALENDAR\n";
+        let events = parse(ics).unwrap().events;
+        assert_eq!(events[0].end, Utc.with_ymd_and_hms(2024, 3, 6, 10, 30, 0).unwrap());
+        assert!(!events[0].overlaps(&events[1]));
+        assert!(!events[1].is_busy());
+        assert_eq!(parse_duration("-P1W"), Some(-Duration::weeks(1)));
+        assert_eq!(nth_weekday(2024, 10, Weekday::Sun, -1), NaiveDate::from_ymd_opt(2024, 10, 27));
+        assert_eq!(nth_weekday(2024, 3, Weekday::Sun, 2), NaiveDate::from_ymd_opt(2024, 3, 10));
+    }
+}
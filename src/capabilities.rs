@@ -0,0 +1,217 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use native_tls::TlsConnector;
+
+use crate::config::{EmailAccount, SmtpSecurity};
+use crate::email::{ensure_online, EmailError};
+
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What an account's servers announce, or why asking failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    /// IMAP CAPABILITY atoms, e.g. `IDLE` or `AUTH=PLAIN`
+    pub imap: Result<Vec<String>, String>,
+    /// SMTP EHLO keywords with their parameters, e.g. `SIZE 35882577`
+    pub smtp: Result<Vec<String>, String>,
+}
+
+/// A server extension and what tuimail does with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+    pub capability: &'static str,
+    /// What the extension is used for; `None` when tuimail does not use it
+    pub used_for: Option<&'static str>,
+    /// What happens on a server without it
+    pub without: &'static str,
+}
+
+pub const IMAP_FEATURES: [Feature; 8] = [
+    Feature {
+        capability: "IDLE",
+        used_for: Some("Push: new mail shows up as it arrives"),
+        without: "New mail only shows up at the next scheduled sync",
+    },
+    Feature {
+        capability: "MOVE",
+        used_for: Some("Moving, archiving and spam in one step"),
+        without: "Moves copy the message, then delete and expunge the original",
+    },
+    Feature {
+        capability: "CONDSTORE",
+        used_for: Some("Only flag changes since the last sync are fetched; with QRESYNC, deletions too"),
+        without: "Read state is checked by fetching the flags of every listed message",
+    },
+    Feature {
+        capability: "QUOTA",
+        used_for: None,
+        without: "Mailbox usage is not shown",
+    },
+    Feature {
+        capability: "COMPRESS",
+        used_for: None,
+        without: "Traffic is not compressed",
+    },
+    Feature {
+        capability: "SPECIAL-USE",
+        used_for: Some("Finding the Sent, Drafts, Trash, Junk and Archive folders"),
+        without: "Drafts, Sent and Spam folders are found by name",
+    },
+    Feature {
+        capability: "SORT",
+        used_for: Some("Large folders are listed newest first by date"),
+        without: "Large folders are listed in the order messages arrived",
+    },
+    Feature {
+        capability: "THREAD",
+        used_for: Some("Conversations in the threaded list (H) are grouped by the server"),
+        without: "Conversations are grouped from the message headers",
+    },
+];
+
+pub const SMTP_FEATURES: [Feature; 5] = [
+    Feature {
+        capability: "STARTTLS",
+        used_for: Some("Encrypting port 587 connections"),
+        without: "Accounts set to STARTTLS cannot send",
+    },
+    Feature {
+        capability: "AUTH",
+        used_for: Some("Logging in to send"),
+        without: "The server will not take mail from a login",
+    },
+    Feature {
+        capability: "SMTPUTF8",
+        used_for: Some("Addresses with non-ASCII characters"),
+        without: "Such addresses are refused",
+    },
+    Feature {
+        capability: "8BITMIME",
+        used_for: Some("Sending text without transfer encoding"),
+        without: "Bodies are sent encoded",
+    },
+    Feature {
+        capability: "SIZE",
+        used_for: None,
+        without: "The size limit is only found out when a message is refused",
+    },
+];
+
+/// Whether `announced` lists `capability`, with or without parameters
+/// (`AUTH=PLAIN` and `AUTH PLAIN LOGIN` both count as `AUTH`)
+pub fn has_capability(announced: &[String], capability: &str) -> bool {
+    announced.iter().any(|entry| {
+        let keyword = entry.split([' ', '=']).next().unwrap_or_default();
+        keyword.eq_ignore_ascii_case(capability)
+    })
+}
+
+/// The keywords of an EHLO reply, without the greeting on its first line
+pub fn parse_ehlo(lines: &[String]) -> Vec<String> {
+    lines.iter().skip(1).map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect()
+}
+
+/// A reply code and the text of its lines
+fn read_reply(reader: &mut impl BufRead) -> Result<(u16, Vec<String>), EmailError> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(smtp_io)? == 0 {
+            return Err(EmailError::SmtpError("Connection closed by the server".to_string()));
+        }
+        let line = line.trim_end();
+        let code = line.get(..3).and_then(|code| code.parse().ok()).ok_or_else(|| {
+            EmailError::SmtpError(format!("Unexpected reply: {}", line))
+        })?;
+        lines.push(line.get(4..).unwrap_or_default().to_string());
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, lines));
+        }
+    }
+}
+
+fn command<S: Read + Write>(reader: &mut BufReader<S>, command: &str) -> Result<(u16, Vec<String>), EmailError> {
+    reader.get_mut().write_all(format!("{}\r\n", command).as_bytes()).map_err(smtp_io)?;
+    read_reply(reader)
+}
+
+fn ehlo<S: Read + Write>(reader: &mut BufReader<S>) -> Result<Vec<String>, EmailError> {
+    match command(reader, "EHLO localhost")? {
+        (250, lines) => Ok(parse_ehlo(&lines)),
+        (code, lines) => Err(EmailError::SmtpError(format!("EHLO refused: {} {}", code, lines.join(" ")))),
+    }
+}
+
+fn smtp_io(e: impl std::fmt::Display) -> EmailError {
+    EmailError::ConnectionError(e.to_string())
+}
+
+/// Ask the account's SMTP server which extensions it offers. With STARTTLS the list is
+/// taken after switching to TLS, since servers often only offer AUTH there.
+pub fn smtp_extensions(account: &EmailAccount) -> Result<Vec<String>, EmailError> {
+    let host = account.smtp_server.as_str();
+    ensure_online(&account.email, host)?;
+    let addr = (host, account.smtp_port)
+        .to_socket_addrs()
+        .map_err(smtp_io)?
+        .next()
+        .ok_or_else(|| EmailError::ConnectionError(format!("Could not resolve {}", host)))?;
+    let tcp = TcpStream::connect_timeout(&addr, NETWORK_TIMEOUT).map_err(smtp_io)?;
+    tcp.set_read_timeout(Some(NETWORK_TIMEOUT)).map_err(smtp_io)?;
+    let tls = TlsConnector::new().map_err(smtp_io)?;
+
+    let extensions = match account.smtp_security {
+        SmtpSecurity::SSL => {
+            let mut reader = BufReader::new(tls.connect(host, tcp).map_err(smtp_io)?);
+            read_reply(&mut reader)?;
+            let extensions = ehlo(&mut reader)?;
+            let _ = command(&mut reader, "QUIT");
+            extensions
+        }
+        SmtpSecurity::StartTLS => {
+            let mut reader = BufReader::new(tcp);
+            read_reply(&mut reader)?;
+            let plain = ehlo(&mut reader)?;
+            if !has_capability(&plain, "STARTTLS") || command(&mut reader, "STARTTLS")?.0 != 220 {
+                let _ = command(&mut reader, "QUIT");
+                return Ok(plain);
+            }
+            let mut reader = BufReader::new(tls.connect(host, reader.into_inner()).map_err(smtp_io)?);
+            let mut extensions = ehlo(&mut reader)?;
+            // The offer made before TLS still says what the server supports
+            extensions.insert(0, "STARTTLS".to_string());
+            let _ = command(&mut reader, "QUIT");
+            extensions
+        }
+        SmtpSecurity::None => {
+            let mut reader = BufReader::new(tcp);
+            read_reply(&mut reader)?;
+            let extensions = ehlo(&mut reader)?;
+            let _ = command(&mut reader, "QUIT");
+            extensions
+        }
+    };
+    Ok(extensions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ehlo_reply_and_capability_matching() {
+        let reply = "250-mail.example.com at your service\r\n250-SIZE 35882577\r\n250-AUTH LOGIN PLAIN\r\n250 SMTPUTF8\r\n";
+        let (code, lines) = read_reply(&mut reply.as_bytes()).unwrap();
+        assert_eq!(code, 250);
+        let extensions = parse_ehlo(&lines);
+        assert_eq!(extensions, vec!["SIZE 35882577", "AUTH LOGIN PLAIN", "SMTPUTF8"]);
+        assert!(has_capability(&extensions, "auth"));
+        assert!(!has_capability(&extensions, "STARTTLS"));
+
+        let imap = vec!["IMAP4rev1".to_string(), "AUTH=PLAIN".to_string(), "COMPRESS=DEFLATE".to_string()];
+        assert!(has_capability(&imap, "COMPRESS"));
+        assert!(!has_capability(&imap, "IDLE"));
+    }
+}
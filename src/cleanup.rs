@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::config::{FilterRule, RuleAction};
+use crate::email::Email;
+
+/// What the cleanup assistant groups a folder by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Sender,
+    Domain,
+}
+
+impl GroupBy {
+    pub fn toggle(self) -> Self {
+        match self {
+            GroupBy::Sender => GroupBy::Domain,
+            GroupBy::Domain => GroupBy::Sender,
+        }
+    }
+}
+
+/// What to do with the mail of the marked groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupAction {
+    Delete,
+    Archive,
+}
+
+/// All mail of one sender or domain in a folder
+#[derive(Debug, Clone)]
+pub struct SenderGroup {
+    /// Lowercase address, or domain
+    pub key: String,
+    /// A display name the sender used, when grouping by sender
+    pub name: Option<String>,
+    pub count: usize,
+    pub unread: usize,
+    /// Bytes of stored content: bodies and attachments
+    pub size: usize,
+    pub uids: Vec<u32>,
+}
+
+fn group_key(email: &Email, by: GroupBy) -> String {
+    let address = email.from.first().map(|addr| addr.address.to_lowercase()).unwrap_or_default();
+    match by {
+        GroupBy::Sender => address,
+        GroupBy::Domain => address.rsplit_once('@').map_or(address.clone(), |(_, domain)| domain.to_string()),
+    }
+}
+
+/// Approximate size of a stored message; the server's size is not kept
+pub fn stored_size(email: &Email) -> usize {
+    email.body_text.as_deref().map_or(0, str::len)
+        + email.body_html.as_deref().map_or(0, str::len)
+        + email.attachments.iter().map(|a| a.data.len()).sum::<usize>()
+}
+
+/// Group `emails` by sender or domain, largest groups first
+pub fn group(emails: &[Email], by: GroupBy) -> Vec<SenderGroup> {
+    let mut groups: HashMap<String, SenderGroup> = HashMap::new();
+    for email in emails {
+        let key = group_key(email, by);
+        let group = groups.entry(key.clone()).or_insert_with(|| SenderGroup {
+            key,
+            name: None,
+            count: 0,
+            unread: 0,
+            size: 0,
+            uids: Vec::new(),
+        });
+        if by == GroupBy::Sender && group.name.is_none() {
+            group.name = email.from.first().and_then(|addr| addr.name.clone()).filter(|n| !n.is_empty());
+        }
+        group.count += 1;
+        group.unread += usize::from(!email.seen);
+        group.size += stored_size(email);
+        group.uids.extend(email.id.parse::<u32>().ok());
+    }
+
+    let mut groups: Vec<SenderGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.count.cmp(&a.count).then(b.size.cmp(&a.size)).then(a.key.cmp(&b.key)));
+    groups
+}
+
+/// A filter rule applying `action` to future mail of the group in `folder`
+pub fn rule_for(group: &SenderGroup, by: GroupBy, account_email: &str, folder: &str, action: RuleAction) -> FilterRule {
+    let from_contains = match by {
+        GroupBy::Sender => group.key.clone(),
+        GroupBy::Domain => format!("@{}", group.key),
+    };
+    FilterRule {
+        name: format!("Cleanup: {}", group.key),
+        account: Some(account_email.to_string()),
+        folder: Some(folder.to_string()),
+        from_contains: Some(from_contains),
+        to_contains: None,
+        subject_contains: None,
+        action,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailAddress;
+
+    fn email(from: &str, body: &str, seen: bool) -> Email {
+        let mut email = Email::new();
+        email.id = "1".to_string();
+        email.from = vec![EmailAddress { name: None, address: from.to_string() }];
+        email.body_text = Some(body.to_string());
+        email.seen = seen;
+        email
+    }
+
+    #[test]
+    fn test_group_by_sender_and_domain() {
+        let emails = vec![
+            email("news@shop.example", "12345", false),
+            email("News@Shop.example", "123", true),
+            email("deals@shop.example", "1", false),
+            email("friend@mail.example", "hello", true),
+        ];
+
+        let senders = group(&emails, GroupBy::Sender);
+        assert_eq!(senders[0].key, "news@shop.example");
+        assert_eq!((senders[0].count, senders[0].unread, senders[0].size), (2, 1, 8));
+        assert_eq!(senders[0].uids.len(), 2);
+        assert_eq!(senders.len(), 3);
+
+        let domains = group(&emails, GroupBy::Domain);
+        assert_eq!(domains[0].key, "shop.example");
+        assert_eq!(domains[0].count, 3);
+        let rule = rule_for(&domains[0], GroupBy::Domain, "me@example.com", "INBOX", RuleAction::Delete);
+        assert_eq!(rule.from_contains.as_deref(), Some("@shop.example"));
+        assert!(crate::rules::rule_matches(&rule, "me@example.com", &{
+            let mut e = email("deals@shop.example", "", false);
+            e.folder = "INBOX".to_string();
+            e
+        }));
+    }
+
+    #[test]
+    fn test_group_cached_messages() {
+        let dir = std::env::temp_dir().join(format!("tuimail-cleanup-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = crate::database::EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let emails: Vec<Email> = ["news@shop.example", "deals@shop.example", "news@shop.example", "friend@mail.example"]
+            .iter()
+            .enumerate()
+            .map(|(n, from)| {
+                let mut email = email(from, "buy", false);
+                email.id = (n + 1).to_string();
+                email
+            })
+            .collect();
+        db.save_emails("me@example.com", "INBOX", &emails).unwrap();
+
+        let groups = group(&db.get_all_emails("me@example.com", "INBOX").unwrap(), GroupBy::Domain);
+        assert_eq!((groups[0].key.as_str(), groups[0].count), ("shop.example", 3));
+        let mut uids = groups[0].uids.clone();
+        uids.sort();
+        assert_eq!(uids, vec![1, 2, 3]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,226 @@
+use serde::Serialize;
+
+use crate::email::EmailError;
+
+/// Outcome of a command-line invocation, reported as the process exit code so scripts
+/// can tell a wrong password from a network outage. Code 2 is left to argument errors,
+/// which clap reports itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStatus {
+    Success,
+    Failure,
+    NotFound,
+    Auth,
+    Network,
+    Config,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Failure => 1,
+            ExitStatus::NotFound => 3,
+            ExitStatus::Auth => 4,
+            ExitStatus::Network => 5,
+            ExitStatus::Config => 6,
+        }
+    }
+}
+
+impl From<&EmailError> for ExitStatus {
+    fn from(error: &EmailError) -> Self {
+        match error {
+            EmailError::AuthError(_) => ExitStatus::Auth,
+            EmailError::NotFound(_) => ExitStatus::NotFound,
+            EmailError::ConnectionError(_) | EmailError::IoError(_) | EmailError::TlsError(_) => ExitStatus::Network,
+            EmailError::ImapError(_) | EmailError::SmtpError(_) | EmailError::ReadOnly(_) => ExitStatus::Failure,
+        }
+    }
+}
+
+/// One account's line in `tuimail status`
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountStatus {
+    pub name: String,
+    pub email: String,
+    pub folder: String,
+    pub connected: bool,
+    /// Unread count from the server, or from the local cache when offline
+    pub unread: Option<usize>,
+    /// Whether `unread` comes from the local cache rather than the server
+    pub cached: bool,
+    pub status: ExitStatus,
+    pub error: Option<String>,
+}
+
+impl AccountStatus {
+    /// Short form for status bars, e.g. `work 3` or `work 3? (offline)`
+    pub fn line(&self) -> String {
+        let unread = match self.unread {
+            Some(n) if self.cached => format!("{}?", n),
+            Some(n) => n.to_string(),
+            None => "-".to_string(),
+        };
+        match &self.error {
+            Some(_) if self.connected => format!("{} {} (error)", self.name, unread),
+            Some(_) => format!("{} {} (offline)", self.name, unread),
+            None => format!("{} {}", self.name, unread),
+        }
+    }
+}
+
+/// Exit status for a set of account checks: the first failure, or success
+pub fn overall_status(accounts: &[AccountStatus]) -> ExitStatus {
+    accounts
+        .iter()
+        .map(|a| a.status)
+        .find(|s| *s != ExitStatus::Success)
+        .unwrap_or(ExitStatus::Success)
+}
+
+/// Unread messages of one account, as cached in the shared database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnreadCount {
+    pub name: String,
+    pub email: String,
+    pub unread: usize,
+}
+
+/// How `tuimail unread` prints its counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UnreadFormat {
+    /// Every account with its count, e.g. `work 3 home 0`
+    Plain,
+    /// A highlighted total for tmux `status-right`, empty when nothing is unread
+    Tmux,
+    /// A total for a starship custom module, empty (so the module hides) when nothing is unread
+    Starship,
+}
+
+/// File format of `tuimail export` and `tuimail import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ArchiveFormat {
+    /// Passphrase-encrypted bundle of the messages as the server has them
+    Bundle,
+    /// mbox file (mboxrd) for mutt, Thunderbird and most other mail programs
+    Mbox,
+}
+
+/// File format of `tuimail import-filters`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FilterFormat {
+    /// Thunderbird's msgFilterRules.dat, found in each account's folder of the profile
+    Thunderbird,
+    /// Sieve script, as used by Roundcube, Fastmail, Proton and other servers
+    Sieve,
+}
+
+/// Render unread counts for a status line
+pub fn format_unread(counts: &[UnreadCount], format: UnreadFormat) -> String {
+    let total: usize = counts.iter().map(|c| c.unread).sum();
+    match format {
+        UnreadFormat::Plain => counts
+            .iter()
+            .map(|c| format!("{} {}", c.name, c.unread))
+            .collect::<Vec<_>>()
+            .join(" "),
+        UnreadFormat::Tmux if total > 0 => format!("#[fg=yellow,bold]✉ {}#[default]", total),
+        UnreadFormat::Starship if total > 0 => format!("✉ {}", total),
+        UnreadFormat::Tmux | UnreadFormat::Starship => String::new(),
+    }
+}
+
+/// Writes subcommand results either for people or, with `--json`, as one JSON value
+/// on stdout
+pub struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+
+    /// Print `value` as JSON, or let `text` print it in human form
+    pub fn print<T: Serialize>(&self, value: &T, text: impl FnOnce(&T)) {
+        if self.json {
+            match serde_json::to_string(value) {
+                Ok(json) => println!("{}", json),
+                Err(e) => self.fail(ExitStatus::Failure, &format!("Failed to encode output: {}", e)),
+            }
+        } else {
+            text(value);
+        }
+    }
+
+    /// Confirm a completed action
+    pub fn success(&self, message: &str) {
+        if self.json {
+            println!("{}", serde_json::json!({ "ok": true, "message": message }));
+        } else {
+            println!("✓ {}", message);
+        }
+    }
+
+    /// Report an error and exit with the code for its kind
+    pub fn fail(&self, status: ExitStatus, message: &str) -> ! {
+        if self.json {
+            println!("{}", serde_json::json!({ "ok": false, "status": status, "error": message }));
+        } else {
+            eprintln!("Error: {}", message);
+        }
+        std::process::exit(status.code());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(name: &str, status: ExitStatus, unread: Option<usize>, cached: bool) -> AccountStatus {
+        AccountStatus {
+            name: name.to_string(),
+            email: format!("{}@example.com", name),
+            folder: "INBOX".to_string(),
+            connected: status == ExitStatus::Success,
+            unread,
+            cached,
+            status,
+            error: (status != ExitStatus::Success).then(|| "failed".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_exit_status() {
+        assert_eq!(ExitStatus::from(&EmailError::AuthError("bad password".into())), ExitStatus::Auth);
+        assert_eq!(ExitStatus::from(&EmailError::ConnectionError("refused".into())), ExitStatus::Network);
+        assert_eq!(ExitStatus::from(&EmailError::ImapError("NO".into())), ExitStatus::Failure);
+
+        let home = account("home", ExitStatus::Success, Some(3), false);
+        let work = account("work", ExitStatus::Network, Some(5), true);
+        assert_eq!(home.line(), "home 3");
+        assert_eq!(work.line(), "work 5? (offline)");
+        assert_eq!(overall_status(std::slice::from_ref(&home)), ExitStatus::Success);
+        assert_eq!(overall_status(&[home, work]), ExitStatus::Network);
+        assert_eq!(serde_json::to_value(ExitStatus::NotFound).unwrap(), "not_found");
+    }
+
+    #[test]
+    fn test_format_unread() {
+        let count = |name: &str, unread| UnreadCount { name: name.to_string(), email: String::new(), unread };
+        let counts = [count("work", 3), count("home", 1)];
+        assert_eq!(format_unread(&counts, UnreadFormat::Plain), "work 3 home 1");
+        assert_eq!(format_unread(&counts, UnreadFormat::Tmux), "#[fg=yellow,bold]✉ 4#[default]");
+        assert_eq!(format_unread(&counts, UnreadFormat::Starship), "✉ 4");
+
+        let none = [count("work", 0)];
+        assert_eq!(format_unread(&none, UnreadFormat::Plain), "work 0");
+        assert_eq!(format_unread(&none, UnreadFormat::Tmux), "");
+    }
+}
@@ -0,0 +1,133 @@
+use std::process::{Command, Stdio};
+
+use crate::email::debug_log;
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// A command that lists the clipboard's types and one that prints its PNG content
+struct ClipboardTool {
+    name: &'static str,
+    targets: &'static [&'static str],
+    read_png: &'static [&'static str],
+}
+
+const WL_PASTE: ClipboardTool = ClipboardTool {
+    name: "wl-paste",
+    targets: &["wl-paste", "--list-types"],
+    read_png: &["wl-paste", "--no-newline", "--type", "image/png"],
+};
+
+const XCLIP: ClipboardTool = ClipboardTool {
+    name: "xclip",
+    targets: &["xclip", "-selection", "clipboard", "-t", "TARGETS", "-o"],
+    read_png: &["xclip", "-selection", "clipboard", "-t", "image/png", "-o"],
+};
+
+// pngpaste has no way to list types; it simply fails when there is no image
+const PNGPASTE: ClipboardTool = ClipboardTool {
+    name: "pngpaste",
+    targets: &[],
+    read_png: &["pngpaste", "-"],
+};
+
+/// Read an image from the system clipboard as PNG data. The error says whether there was
+/// no image or no tool to read the clipboard with.
+pub fn read_png() -> Result<Vec<u8>, String> {
+    let tools = tools_for_session(
+        std::env::var_os("WAYLAND_DISPLAY").is_some(),
+        std::env::var_os("DISPLAY").is_some(),
+    );
+
+    let mut found_tool = false;
+    for tool in tools {
+        if !tool.targets.is_empty() {
+            let Some(targets) = run(tool.targets) else {
+                continue;
+            };
+            found_tool = true;
+            if !has_png_target(&String::from_utf8_lossy(&targets)) {
+                return Err("No image on the clipboard".to_string());
+            }
+        }
+        match run(tool.read_png) {
+            Some(data) if data.starts_with(PNG_MAGIC) => return Ok(data),
+            Some(_) => return Err(format!("{} did not return a PNG image", tool.name)),
+            None if tool.targets.is_empty() => continue,
+            None => return Err(format!("{} could not read the clipboard image", tool.name)),
+        }
+    }
+
+    if found_tool {
+        Err("No image on the clipboard".to_string())
+    } else {
+        Err("No image on the clipboard, or no clipboard tool (install wl-clipboard, xclip or pngpaste)".to_string())
+    }
+}
+
+/// Tools to try, those for the running display server first
+fn tools_for_session(wayland: bool, x11: bool) -> Vec<&'static ClipboardTool> {
+    if cfg!(target_os = "macos") {
+        return vec![&PNGPASTE];
+    }
+    match (wayland, x11) {
+        (true, _) => vec![&WL_PASTE, &XCLIP],
+        (false, true) => vec![&XCLIP, &WL_PASTE],
+        (false, false) => Vec::new(),
+    }
+}
+
+fn has_png_target(targets: &str) -> bool {
+    targets.lines().any(|line| line.trim() == "image/png")
+}
+
+/// Run a command and return its output if it succeeded
+fn run(command: &[&str]) -> Option<Vec<u8>> {
+    let output = Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => Some(output.stdout),
+        Ok(_) => None,
+        Err(e) => {
+            debug_log(&format!("Could not run {}: {}", command[0], e));
+            None
+        }
+    }
+}
+
+/// Name for a pasted image that no attachment in `existing` has yet
+pub fn pasted_image_name(existing: &[String], now: chrono::DateTime<chrono::Local>) -> String {
+    let stem = format!("pasted-image-{}", now.format("%Y%m%d-%H%M%S"));
+    let mut name = format!("{}.png", stem);
+    let mut n = 2;
+    while existing.contains(&name) {
+        name = format!("{}-{}.png", stem, n);
+        n += 1;
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_clipboard_helpers() {
+        assert!(has_png_target("TARGETS\nimage/png\ntext/plain\n"));
+        assert!(!has_png_target("UTF8_STRING\ntext/plain;charset=utf-8\n"));
+
+        if !cfg!(target_os = "macos") {
+            assert_eq!(tools_for_session(true, true)[0].name, "wl-paste");
+            assert_eq!(tools_for_session(false, true)[0].name, "xclip");
+            assert!(tools_for_session(false, false).is_empty());
+        }
+
+        let now = chrono::Local.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap();
+        assert_eq!(pasted_image_name(&[], now), "pasted-image-20240304-100000.png");
+        let taken = vec!["pasted-image-20240304-100000.png".to_string()];
+        assert_eq!(pasted_image_name(&taken, now), "pasted-image-20240304-100000-2.png");
+    }
+}
@@ -0,0 +1,179 @@
+use crate::spellcheck::SpellChecker;
+
+/// Average silent reading speed in words per minute
+const READING_WPM: usize = 230;
+
+/// Forms of "to be" that start a passive construction
+const BE_FORMS: [&str; 8] = ["am", "is", "are", "was", "were", "be", "been", "being"];
+
+/// Common past participles that don't end in -ed
+const IRREGULAR_PARTICIPLES: [&str; 24] = [
+    "done", "given", "made", "taken", "seen", "known", "shown", "sent", "written", "held", "told", "found",
+    "brought", "built", "paid", "kept", "left", "lost", "chosen", "forgotten", "broken", "spent", "bought", "put",
+];
+
+const POLITE_MARKERS: [&str; 8] = ["please", "thank", "thanks", "appreciate", "appreciated", "kindly", "grateful", "sorry"];
+const DEMANDING_MARKERS: [&str; 6] = ["asap", "immediately", "must", "urgent", "urgently", "now"];
+
+/// Statistics of a message being written, for the compose footer
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComposeStats {
+    pub words: usize,
+    pub sentences: usize,
+    /// Estimated reading time in seconds
+    pub reading_secs: usize,
+    /// Flesch reading ease: about 60-70 is plain English, lower is harder
+    pub readability: f64,
+    /// Sentences using "to be" with a past participle, e.g. "was sent"
+    pub passive: usize,
+    pub tone: Tone,
+}
+
+/// A rough hint about how the message may come across
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Polite,
+    Neutral,
+    /// Words like "ASAP" or "must" and nothing softening them
+    Demanding,
+    /// Several words in capitals or repeated exclamation marks
+    Shouting,
+}
+
+impl Tone {
+    pub fn hint(self) -> &'static str {
+        match self {
+            Tone::Polite => "polite",
+            Tone::Neutral => "neutral",
+            Tone::Demanding => "may read as demanding",
+            Tone::Shouting => "may read as shouting",
+        }
+    }
+}
+
+/// The Flesch band a score falls in
+pub fn readability_label(score: f64) -> &'static str {
+    match score {
+        s if s >= 80.0 => "easy",
+        s if s >= 60.0 => "plain",
+        s if s >= 50.0 => "fairly hard",
+        s if s >= 30.0 => "hard",
+        _ => "very hard",
+    }
+}
+
+/// Vowel groups, not counting a silent final e; at least one per word
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = "aeiouy".contains(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+fn is_participle(word: &str) -> bool {
+    (word.len() > 3 && word.ends_with("ed")) || IRREGULAR_PARTICIPLES.contains(&word)
+}
+
+/// Whether a sentence has a "to be" form followed by a participle, allowing one adverb
+/// in between ("was quickly sent")
+fn is_passive(words: &[String]) -> bool {
+    words.iter().enumerate().any(|(i, word)| {
+        if !BE_FORMS.contains(&word.as_str()) {
+            return false;
+        }
+        match words.get(i + 1) {
+            Some(next) if next.ends_with("ly") => words.get(i + 2).is_some_and(|w| is_participle(w)),
+            Some(next) => is_participle(next),
+            None => false,
+        }
+    })
+}
+
+fn tone(text: &str, words: &[String]) -> Tone {
+    let shouted = SpellChecker::extract_words_static(text)
+        .iter()
+        .filter(|w| w.word.chars().count() > 3 && w.word.chars().all(|c| c.is_uppercase()))
+        .count();
+    if shouted >= 2 || text.contains("!!") {
+        return Tone::Shouting;
+    }
+    let polite = words.iter().any(|w| POLITE_MARKERS.contains(&w.as_str()));
+    let demanding = words.iter().any(|w| DEMANDING_MARKERS.contains(&w.as_str()));
+    match (polite, demanding) {
+        (true, _) => Tone::Polite,
+        (false, true) => Tone::Demanding,
+        (false, false) => Tone::Neutral,
+    }
+}
+
+/// Statistics of `body`, leaving out quoted lines (`>`) so only the new text counts
+pub fn analyze(body: &str) -> ComposeStats {
+    let text: String = body
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('>'))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let sentences: Vec<Vec<String>> = text
+        .split(['.', '!', '?'])
+        .map(|sentence| {
+            SpellChecker::extract_words_static(sentence)
+                .into_iter()
+                .map(|w| w.word.trim_matches(['\'', '-']).to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect();
+    let words: Vec<String> = sentences.iter().flatten().cloned().collect();
+
+    let readability = if words.is_empty() {
+        100.0
+    } else {
+        let syllable_count: usize = words.iter().map(|w| syllables(w)).sum();
+        let per_sentence = words.len() as f64 / sentences.len() as f64;
+        let per_word = syllable_count as f64 / words.len() as f64;
+        (206.835 - 1.015 * per_sentence - 84.6 * per_word).clamp(0.0, 100.0)
+    };
+
+    ComposeStats {
+        words: words.len(),
+        sentences: sentences.len(),
+        reading_secs: (words.len() * 60).div_ceil(READING_WPM),
+        readability,
+        passive: sentences.iter().filter(|sentence| is_passive(sentence)).count(),
+        tone: tone(&text, &words),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        let stats = analyze("Hi Sam,\n\nThe report was sent yesterday. Could you please check it?\n\n> Where is the report?\n");
+        assert_eq!(stats.words, 12);
+        assert_eq!(stats.sentences, 2);
+        assert_eq!(stats.passive, 1);
+        assert_eq!(stats.tone, Tone::Polite);
+        assert!(stats.readability > 60.0, "{}", stats.readability);
+        assert_eq!(stats.reading_secs, 4);
+
+        assert_eq!(analyze("Send it now. We must ship.").tone, Tone::Demanding);
+        assert_eq!(analyze("We NEVER AGREED to this").tone, Tone::Shouting);
+        assert_eq!(analyze("").words, 0);
+        assert_eq!(syllables("readability"), 5);
+        assert_eq!(syllables("make"), 1);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
@@ -5,6 +6,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::i18n::{tr, tr_args};
+
 #[derive(Error, Debug)]
 pub enum ConfigError {
     #[error("Failed to read config file: {0}")]
@@ -15,22 +18,54 @@ pub enum ConfigError {
     
     #[error("Failed to create config directory")]
     CreateDirError,
+
+    #[error("Failed to decrypt config file: {0}")]
+    DecryptError(String),
+
+    #[error("Failed to encrypt config file: {0}")]
+    EncryptError(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ImapSecurity {
     None,
     StartTLS,
     SSL,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SmtpSecurity {
     None,
     StartTLS,
     SSL,
 }
 
+impl std::str::FromStr for ImapSecurity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(ImapSecurity::None),
+            "starttls" => Ok(ImapSecurity::StartTLS),
+            "ssl" => Ok(ImapSecurity::SSL),
+            _ => Err(format!("Unknown security setting '{}' (expected None, StartTLS or SSL)", s)),
+        }
+    }
+}
+
+impl std::str::FromStr for SmtpSecurity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(SmtpSecurity::None),
+            "starttls" => Ok(SmtpSecurity::StartTLS),
+            "ssl" => Ok(SmtpSecurity::SSL),
+            _ => Err(format!("Unknown security setting '{}' (expected None, StartTLS or SSL)", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailAccount {
     pub name: String,
@@ -46,9 +81,286 @@ pub struct EmailAccount {
     pub smtp_username: String,
     // Password removed from config - now stored securely
     pub signature: Option<String>,
+    /// Seconds between background syncs; falls back to `sync.default_interval`
+    #[serde(default)]
+    pub sync_interval: Option<u64>,
+    /// Defaults applied to new messages, replies and forwards from this account
+    #[serde(default)]
+    pub compose: ComposeDefaults,
+    /// Connect to the SMTP server over this address family only
+    #[serde(default)]
+    pub smtp_ip_family: Option<IpFamily>,
+    /// What to do when the SMTP server can't be reached with the settings above
+    #[serde(default)]
+    pub smtp_fallback: SmtpFallback,
+    /// Domains of your organisation; mail from any other domain is marked external.
+    /// Nothing is marked when empty.
+    #[serde(default)]
+    pub internal_domains: Vec<String>,
+    /// Read mail from this local Maildir tree instead of an IMAP server; the IMAP
+    /// settings and password are then not used
+    #[serde(default)]
+    pub maildir: Option<String>,
+    /// Folders that are not notified about or synced like the rest of the account,
+    /// by folder path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub folder_options: BTreeMap<String, FolderOptions>,
+    /// Sync and send through the Gmail REST API instead of IMAP and SMTP; set up with
+    /// `tuimail gmail-login`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gmail_api: Option<GmailApiConfig>,
+    /// Read and send mail through Exchange Web Services instead of IMAP and SMTP, signing
+    /// in with the IMAP username and password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange: Option<ExchangeConfig>,
+    /// Sign and encrypt messages with OpenPGP; see `PgpConfig`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pgp: Option<PgpConfig>,
+    /// Calendar to check invitations against and add accepted ones to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caldav: Option<CalDavConfig>,
+    /// Directory searched for recipients while composing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ldap: Option<LdapConfig>,
+    /// Sent, Drafts, Trash, Junk and Archive folders as the server marks them, filled in
+    /// when the account connects; folders set here by hand are kept
+    #[serde(default, skip_serializing_if = "SpecialFolders::is_empty")]
+    pub special_folders: SpecialFolders,
+}
+
+/// Folders the server marks for a special use with SPECIAL-USE (RFC 6154) or Gmail's
+/// older XLIST
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecialFolders {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drafts: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub junk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<String>,
+}
+
+impl SpecialFolders {
+    pub fn is_empty(&self) -> bool {
+        *self == SpecialFolders::default()
+    }
+
+    /// Record `folder` for a mailbox attribute such as `\Sent`; other attributes are
+    /// ignored. `\Archive` wins over Gmail's All Mail (`\All`, `\AllMail` in XLIST).
+    pub fn assign(&mut self, attribute: &str, folder: &str) {
+        let slot = match attribute.to_ascii_lowercase().as_str() {
+            "\\sent" => &mut self.sent,
+            "\\drafts" => &mut self.drafts,
+            "\\trash" => &mut self.trash,
+            "\\junk" | "\\spam" => &mut self.junk,
+            "\\archive" => {
+                self.archive = Some(folder.to_string());
+                return;
+            }
+            "\\all" | "\\allmail" => &mut self.archive,
+            _ => return,
+        };
+        slot.get_or_insert_with(|| folder.to_string());
+    }
+
+    /// Take the folders of `found` for the uses without one here
+    pub fn fill_from(&mut self, found: SpecialFolders) {
+        for (slot, found) in [
+            (&mut self.sent, found.sent),
+            (&mut self.drafts, found.drafts),
+            (&mut self.trash, found.trash),
+            (&mut self.junk, found.junk),
+            (&mut self.archive, found.archive),
+        ] {
+            if slot.is_none() {
+                *slot = found;
+            }
+        }
+    }
+}
+
+/// Where an Exchange account's EWS endpoint is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeConfig {
+    /// Such as `https://mail.example.com/EWS/Exchange.asmx`
+    pub url: String,
+}
+
+/// Where an account's CalDAV calendar is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalDavConfig {
+    /// The calendar collection, such as `https://dav.example.com/calendars/me/personal/`
+    pub url: String,
+    /// Defaults to the IMAP username; the IMAP password is used with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+/// Where an account's LDAP directory is, such as a company's Active Directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LdapConfig {
+    /// `ldaps://ldap.example.com`, or `ldap://` for a server upgraded with StartTLS
+    pub url: String,
+    /// Where searches start, such as `ou=people,dc=example,dc=com`
+    pub base_dn: String,
+    /// DN (or Active Directory user principal) to bind as with the IMAP password;
+    /// searches anonymously when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_dn: Option<String>,
+    /// Search filter with `{}` for the text typed; `ldap::DEFAULT_FILTER` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+/// The Google Cloud OAuth client a Gmail API account signs in with. The client secret
+/// and refresh token are kept in the keyring.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GmailApiConfig {
+    pub client_id: String,
+}
+
+/// Exceptions for one folder to how its account is notified about and synced
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderOptions {
+    /// Never announce new mail in this folder, not even from VIPs or outside quiet hours
+    #[serde(default)]
+    pub never_notify: bool,
+    /// Leave this folder out of the scheduled sync and background body downloads; it is
+    /// still synced when opened
+    #[serde(default)]
+    pub no_auto_sync: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+/// Whether sending tries other ports, TLS modes and address families after a
+/// connection or TLS failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpFallback {
+    /// Only ever use the configured settings
+    Off,
+    /// Try the alternatives and offer to keep the one that worked
+    #[default]
+    Ask,
+    /// Try the alternatives and keep the one that worked without asking
+    Apply,
+}
+
+/// How the body of an outgoing message is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComposeFormat {
+    #[default]
+    Plain,
+    /// The body is sent as typed and as HTML rendered from it as Markdown
+    Markdown,
+}
+
+/// `X-Priority`/`Importance` of outgoing messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// Values of the `X-Priority` and `Importance` headers
+    pub fn header_values(&self) -> (&'static str, &'static str) {
+        match self {
+            Priority::High => ("1 (Highest)", "high"),
+            Priority::Normal => ("3 (Normal)", "normal"),
+            Priority::Low => ("5 (Lowest)", "low"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComposeDefaults {
+    pub format: ComposeFormat,
+    /// Addresses added to Cc of every message
+    pub cc: Vec<String>,
+    /// Addresses added to Bcc of every message, e.g. an archive address
+    pub bcc: Vec<String>,
+    /// Ask recipients' mail clients for a read receipt (`Disposition-Notification-To`)
+    pub read_receipt: bool,
+    pub priority: Option<Priority>,
+    /// Name of an entry in the config's `signatures`, used instead of the account's
+    /// `signature`
+    pub signature: Option<String>,
 }
 
 impl EmailAccount {
+    /// The folder sent mail is kept in: the one the server marks, else one with a usual name
+    pub fn sent_folder<'a>(&'a self, folders: &'a [String]) -> Option<&'a String> {
+        self.special_folders.sent.as_ref().or_else(|| find_sent_folder(folders))
+    }
+
+    /// The drafts folder, found like `sent_folder`
+    pub fn drafts_folder<'a>(&'a self, folders: &'a [String]) -> Option<&'a String> {
+        self.special_folders.drafts.as_ref().or_else(|| find_drafts_folder(folders))
+    }
+
+    /// The spam folder, found like `sent_folder`
+    pub fn junk_folder<'a>(&'a self, folders: &'a [String]) -> Option<&'a String> {
+        self.special_folders.junk.as_ref().or_else(|| find_spam_folder(folders))
+    }
+
+    /// The folder archived mail goes to: the one the server marks, else `default` (the
+    /// `retention.archive_folder` setting)
+    pub fn archive_folder(&self, default: &str) -> String {
+        self.special_folders.archive.clone().unwrap_or_else(|| default.to_string())
+    }
+
+    /// Whether mail from `address` comes from outside `internal_domains`
+    pub fn is_external(&self, address: &str) -> bool {
+        !self.internal_domains.is_empty() && !crate::reply_check::is_own_domain(address, &self.internal_domains)
+    }
+
+    /// `host:port` of the IMAP and SMTP servers this account connects to
+    pub fn servers(&self) -> Vec<String> {
+        vec![
+            format!("{}:{}", self.imap_server, self.imap_port),
+            format!("{}:{}", self.smtp_server, self.smtp_port),
+        ]
+    }
+
+    /// The Maildir tree of a local account, with `~` expanded
+    pub fn maildir_path(&self) -> Option<std::path::PathBuf> {
+        self.maildir.as_deref().map(|path| std::path::PathBuf::from(shellexpand::tilde(path).into_owned()))
+    }
+
+    /// Whether new mail in `folder` is announced
+    pub fn notifies(&self, folder: &str) -> bool {
+        !self.folder_options.get(folder).is_some_and(|options| options.never_notify)
+    }
+
+    /// Whether `folder` is synced in the background, and not only when opened
+    pub fn auto_syncs(&self, folder: &str) -> bool {
+        !self.folder_options.get(folder).is_some_and(|options| options.no_auto_sync)
+    }
+
+    /// Change the options of `folder`; folders back at the defaults are left out of the config
+    pub fn set_folder_options(&mut self, folder: &str, options: FolderOptions) {
+        if options == FolderOptions::default() {
+            self.folder_options.remove(folder);
+        } else {
+            self.folder_options.insert(folder.to_string(), options);
+        }
+    }
+
     /// Get IMAP password from secure storage
     pub fn get_imap_password(&self, credentials: &crate::credentials::SecureCredentials) -> Result<String> {
         let account_id = &self.email;
@@ -90,6 +402,19 @@ impl Default for EmailAccount {
             smtp_security: SmtpSecurity::StartTLS,
             smtp_username: "user@example.com".to_string(),
             signature: Some("Sent from Email Client".to_string()),
+            sync_interval: None,
+            compose: ComposeDefaults::default(),
+            smtp_ip_family: None,
+            smtp_fallback: SmtpFallback::default(),
+            internal_domains: Vec::new(),
+            maildir: None,
+            folder_options: BTreeMap::new(),
+            gmail_api: None,
+            exchange: None,
+            pgp: None,
+            caldav: None,
+            ldap: None,
+            special_folders: SpecialFolders::default(),
         }
     }
 }
@@ -100,6 +425,67 @@ pub struct UIConfig {
     pub show_headers: bool,
     pub refresh_interval: u64,
     pub preview_pane: bool,
+    /// Additional headers listed in the expanded viewer header panel
+    #[serde(default = "default_extra_headers")]
+    pub extra_headers: Vec<String>,
+    /// Look up sender avatars on Gravatar/Libravatar and brand logos published with BIMI.
+    /// Off by default, since every lookup tells those servers whose mail is being read.
+    #[serde(default = "default_avatars")]
+    pub avatars: bool,
+    /// Language of the interface, such as "de"; taken from LANG and friends when unset
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Draw borders, arrows and indicators with plain ASCII for terminals and fonts
+    /// that garble box-drawing characters and emoji
+    #[serde(default)]
+    pub ascii: bool,
+    /// Look for a new release on GitHub once a day. Off unless turned on, as it contacts
+    /// GitHub without being asked to.
+    #[serde(default = "default_update_check")]
+    pub update_check: bool,
+    /// Rows of the email list: one line per message, or two with a preview
+    #[serde(default)]
+    pub density: ListDensity,
+}
+
+/// How much of each message the email list shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListDensity {
+    /// Date, sender and subject on one line
+    #[default]
+    Compact,
+    /// Sender and subject on the first line, a preview of the text on the second
+    Comfortable,
+}
+
+impl ListDensity {
+    /// Terminal lines taken by one message
+    pub fn row_height(self) -> usize {
+        match self {
+            ListDensity::Compact => 1,
+            ListDensity::Comfortable => 2,
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            ListDensity::Compact => ListDensity::Comfortable,
+            ListDensity::Comfortable => ListDensity::Compact,
+        }
+    }
+}
+
+fn default_avatars() -> bool {
+    false
+}
+
+fn default_update_check() -> bool {
+    false
+}
+
+fn default_extra_headers() -> Vec<String> {
+    vec!["List-Id".to_string(), "X-Mailer".to_string()]
 }
 
 impl Default for UIConfig {
@@ -109,15 +495,346 @@ impl Default for UIConfig {
             show_headers: false,
             refresh_interval: 300,
             preview_pane: true,
+            extra_headers: default_extra_headers(),
+            avatars: default_avatars(),
+            locale: None,
+            ascii: false,
+            update_check: default_update_check(),
+            density: ListDensity::default(),
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Seconds between background syncs for accounts without their own interval
+    pub default_interval: u64,
+    /// Start with background syncing paused
+    pub start_paused: bool,
+    /// Message bodies downloaded first when a folder is opened that is only listed from
+    /// headers; the rest follow in the background
+    pub body_window: usize,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            default_interval: 30,
+            start_paused: false,
+            body_window: 50,
+        }
+    }
+}
+
+/// What a filter rule does with a matching message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    Move { folder: String },
+    MarkRead,
+    Delete,
+}
+
+/// A filter rule. All conditions that are set must match (case-insensitive substring).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterRule {
+    pub name: String,
+    /// Only apply to this account; all accounts when unset
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Only apply to messages in this folder; all folders when unset
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub from_contains: Option<String>,
+    #[serde(default)]
+    pub to_contains: Option<String>,
+    #[serde(default)]
+    pub subject_contains: Option<String>,
+    pub action: RuleAction,
+}
+
+/// Auto-archive policy: move old mail out of the listed folders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Archive messages older than this many days; disabled when unset
+    pub archive_after_days: Option<u32>,
+    pub archive_folder: String,
+    pub folders: Vec<String>,
+    /// Leave unread messages where they are
+    pub only_read: bool,
+    /// Days a copy of each deleted message is kept locally so it can be restored
+    pub keep_deleted_days: u32,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            archive_after_days: None,
+            archive_folder: "Archive".to_string(),
+            folders: vec!["INBOX".to_string()],
+            only_read: true,
+            keep_deleted_days: 30,
+        }
+    }
+}
+
+/// How much disk the local cache may take
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Megabytes of attachments kept locally; beyond it the least recently opened ones
+    /// of messages still on the server are dropped, to be downloaded again when needed.
+    /// No limit when unset.
+    pub max_attachment_mb: Option<u64>,
+    /// Megabytes that must stay free on the disk holding the cache; below it sync only
+    /// downloads headers and attachments are not downloaded. No check when unset.
+    pub min_free_mb: Option<u64>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { max_attachment_mb: Some(2048), min_free_mb: Some(500) }
+    }
+}
+
+impl CacheConfig {
+    pub fn max_bytes(&self) -> Option<u64> {
+        self.max_attachment_mb.map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+
+    pub fn min_free_bytes(&self) -> Option<u64> {
+        self.min_free_mb.map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+}
+
+/// When new mail is announced
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// Start of the quiet hours as `HH:MM`, during which only mail from VIPs is announced
+    pub quiet_hours_start: Option<String>,
+    /// End of the quiet hours as `HH:MM`; may be earlier than the start to span midnight
+    pub quiet_hours_end: Option<String>,
+}
+
+/// A header of incoming mail, such as `X-GitHub-Reason`, stored in its own column when
+/// messages are synced, searchable with `<name>:<text>` and optionally shown in the list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeaderField {
+    /// Short lowercase name used in searches, e.g. `reason` for `reason:mention`
+    pub name: String,
+    /// The header, matched ignoring case
+    pub header: String,
+    /// Show the value as a column of the email list, this many characters wide
+    #[serde(default)]
+    pub column_width: Option<usize>,
+}
+
+/// Sharing tags with notmuch; only used in builds with the `notmuch` feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotmuchConfig {
+    /// The notmuch program, with its configuration picked up as usual
+    pub command: String,
+    /// Folder under the notmuch database root that synced messages notmuch doesn't know
+    /// yet are added to, as `<folder>/<account>/<mail folder>`; when unset only messages
+    /// notmuch indexes already (e.g. of a Maildir account) share tags
+    pub index_folder: Option<String>,
+}
+
+impl Default for NotmuchConfig {
+    fn default() -> Self {
+        Self { command: "notmuch".to_string(), index_folder: None }
+    }
+}
+
+/// OpenPGP keys and defaults of an account; signing, encryption and checking received
+/// messages are done by running gpg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PgpConfig {
+    /// The gpg program, with its keyring and agent picked up as usual
+    pub command: String,
+    /// Key to sign with and encrypt your own copy to, as a fingerprint, key ID or user
+    /// ID; the account address when unset
+    pub key: Option<String>,
+    pub sign_by_default: bool,
+    pub encrypt_by_default: bool,
+}
+
+impl Default for PgpConfig {
+    fn default() -> Self {
+        Self { command: "gpg".to_string(), key: None, sign_by_default: false, encrypt_by_default: false }
+    }
+}
+
+/// When to ask for confirmation before replying to a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplyWarningConfig {
+    /// Warn about messages older than this many months; never when unset
+    pub older_than_months: Option<u32>,
+    /// Parts of addresses that are likely unmonitored, compared ignoring case, dots,
+    /// dashes and underscores
+    pub no_reply_patterns: Vec<String>,
+    /// Ask before a reply to all that would address more recipients than this
+    pub reply_all_limit: Option<usize>,
+    /// Domains counted as your own; the domains of the configured accounts when empty.
+    /// A reply to all that reaches any other domain is confirmed first.
+    pub own_domains: Vec<String>,
+}
+
+impl Default for ReplyWarningConfig {
+    fn default() -> Self {
+        Self {
+            older_than_months: Some(12),
+            reply_all_limit: Some(10),
+            own_domains: Vec::new(),
+            no_reply_patterns: ["noreply", "donotreply", "noresponse", "mailerdaemon"]
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// What a quick action does with the selected message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuickActionKind {
+    /// Move to the archive folder of the retention policy
+    Archive,
+    Move { folder: String },
+    /// Move to the account's spam folder, found by name when not given
+    Spam {
+        #[serde(default)]
+        folder: Option<String>,
+    },
+    MarkRead,
+    MarkUnread,
+    /// Set or clear `\Flagged`
+    ToggleFlag,
+    Delete,
+}
+
+/// A triage action bound to a number key in the email list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickAction {
+    /// 1 to 9
+    pub key: u8,
+    /// Shown in the quick action bar; derived from the action when unset
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(flatten)]
+    pub action: QuickActionKind,
+}
+
+impl QuickAction {
+    pub fn new(key: u8, action: QuickActionKind) -> Self {
+        Self { key, label: None, action }
+    }
+
+    pub fn label(&self, archive_folder: &str) -> String {
+        if let Some(label) = &self.label {
+            return label.clone();
+        }
+        match &self.action {
+            QuickActionKind::Archive => tr_args("quick-archive", &[("folder", &archive_folder)]),
+            QuickActionKind::Move { folder } => tr_args("quick-move", &[("folder", folder)]),
+            QuickActionKind::Spam { .. } => tr("quick-spam"),
+            QuickActionKind::MarkRead => tr("quick-mark-read"),
+            QuickActionKind::MarkUnread => tr("quick-mark-unread"),
+            QuickActionKind::ToggleFlag => tr("quick-flag"),
+            QuickActionKind::Delete => tr("quick-delete"),
+        }
+    }
+}
+
+/// Common names of spam folders, in order of preference
+const SPAM_FOLDER_NAMES: &[&str] = &["Spam", "Junk", "Junk E-mail", "Junk Email", "Bulk Mail"];
+
+/// The spam folder among an account's folders, also under a parent such as `[Gmail]/`
+pub fn find_spam_folder(folders: &[String]) -> Option<&String> {
+    SPAM_FOLDER_NAMES.iter().find_map(|name| {
+        folders.iter().find(|folder| {
+            let leaf = folder.rsplit(['/', '.']).next().unwrap_or(folder);
+            leaf.eq_ignore_ascii_case(name)
+        })
+    })
+}
+
+/// Common names of drafts folders, in order of preference
+const DRAFTS_FOLDER_NAMES: &[&str] = &["Drafts", "Draft", "Brouillons", "Entwürfe"];
+
+/// Common names of sent folders, in order of preference
+const SENT_FOLDER_NAMES: &[&str] = &["Sent", "Sent Mail", "Sent Items", "Sent Messages", "Gesendet", "Gesendete Objekte", "Envoyés"];
+
+/// The drafts folder among an account's folders, also under a parent such as `[Gmail]/`
+pub fn find_drafts_folder(folders: &[String]) -> Option<&String> {
+    find_folder(folders, DRAFTS_FOLDER_NAMES)
+}
+
+/// The folder sent mail is kept in, found the same way as the drafts folder
+pub fn find_sent_folder(folders: &[String]) -> Option<&String> {
+    find_folder(folders, SENT_FOLDER_NAMES)
+}
+
+fn find_folder<'a>(folders: &'a [String], names: &[&str]) -> Option<&'a String> {
+    names.iter().find_map(|name| {
+        folders.iter().find(|folder| {
+            let leaf = folder.rsplit(['/', '.']).next().unwrap_or(folder);
+            leaf.eq_ignore_ascii_case(name)
+        })
+    })
+}
+
+fn default_quick_actions() -> Vec<QuickAction> {
+    vec![
+        QuickAction::new(1, QuickActionKind::Archive),
+        QuickAction::new(2, QuickActionKind::Spam { folder: None }),
+        QuickAction::new(3, QuickActionKind::MarkUnread),
+        QuickAction::new(4, QuickActionKind::ToggleFlag),
+    ]
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub accounts: Vec<EmailAccount>,
     pub default_account: usize,
     pub ui: UIConfig,
+    #[serde(default)]
+    pub sync: SyncConfig,
+    #[serde(default)]
+    pub rules: Vec<FilterRule>,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// Number-key actions of the email list; an empty list hides the quick action bar
+    #[serde(default = "default_quick_actions")]
+    pub quick_actions: Vec<QuickAction>,
+    /// Named signatures that accounts can pick with `compose.signature`
+    #[serde(default)]
+    pub signatures: BTreeMap<String, String>,
+    #[serde(default)]
+    pub reply_warnings: ReplyWarningConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Headers extracted into their own column; see `header_fields`
+    #[serde(default)]
+    pub header_fields: Vec<HeaderField>,
+    /// Share tags with notmuch; see `notmuch`
+    #[serde(default)]
+    pub notmuch: Option<NotmuchConfig>,
+    /// `host:port` of the servers the user allowed tuimail to connect to for good
+    #[serde(default)]
+    pub approved_servers: Vec<String>,
+    /// Set when the file on disk is encrypted; saving re-encrypts with the same key
+    #[serde(skip)]
+    pub encryption: Option<crate::config_crypto::ConfigEncryption>,
 }
 
 impl Default for Config {
@@ -126,11 +843,44 @@ impl Default for Config {
             accounts: vec![],
             default_account: 0,
             ui: UIConfig::default(),
+            sync: SyncConfig::default(),
+            rules: Vec::new(),
+            retention: RetentionConfig::default(),
+            cache: CacheConfig::default(),
+            quick_actions: default_quick_actions(),
+            signatures: BTreeMap::new(),
+            reply_warnings: ReplyWarningConfig::default(),
+            notifications: NotificationConfig::default(),
+            header_fields: Vec::new(),
+            notmuch: None,
+            approved_servers: Vec::new(),
+            encryption: None,
         }
     }
 }
 
 impl Config {
+    /// The servers of `account` not yet allowed for good
+    pub fn unapproved_servers(&self, account: &EmailAccount) -> Vec<String> {
+        account.servers().into_iter().filter(|server| !self.approved_servers.contains(server)).collect()
+    }
+
+    /// Remember that the servers of `account` may always be connected to
+    pub fn approve_servers(&mut self, account: &EmailAccount) {
+        for server in self.unapproved_servers(account) {
+            self.approved_servers.push(server);
+        }
+    }
+
+    /// The signature for messages from an account: its chosen named signature, or its own
+    pub fn signature_for<'a>(&'a self, account: &'a EmailAccount) -> Option<&'a str> {
+        match &account.compose.signature {
+            Some(name) => self.signatures.get(name).map(String::as_str),
+            None => account.signature.as_deref(),
+        }
+        .filter(|signature| !signature.trim().is_empty())
+    }
+
     pub fn load(path: &str) -> Result<Self, ConfigError> {
         let path = Path::new(path);
         
@@ -140,6 +890,17 @@ impl Config {
         }
         
         let content = fs::read_to_string(path)?;
+        if crate::config_crypto::is_encrypted(&content) {
+            let (plaintext, encryption) = crate::config_crypto::decrypt(&content, || {
+                crate::config_crypto::read_passphrase("Config passphrase: ")
+            })
+            .map_err(|e| ConfigError::DecryptError(e.to_string()))?;
+
+            let mut config: Config = serde_json::from_str(&plaintext)?;
+            config.encryption = Some(encryption);
+            return Ok(config);
+        }
+
         let config = serde_json::from_str(&content)?;
         
         Ok(config)
@@ -153,12 +914,37 @@ impl Config {
             fs::create_dir_all(parent).map_err(|_| ConfigError::CreateDirError)?;
         }
         
-        let content = serde_json::to_string_pretty(self)?;
+        let mut content = serde_json::to_string_pretty(self)?;
+        if let Some(encryption) = &self.encryption {
+            content = encryption
+                .encrypt(content.as_bytes())
+                .map_err(|e| ConfigError::EncryptError(e.to_string()))?;
+        }
         fs::write(path, content)?;
         
         Ok(())
     }
     
+    /// Folders of every account that are only synced when opened, as (account email, folder)
+    pub fn manual_sync_folders(&self) -> std::collections::HashSet<(String, String)> {
+        self.accounts
+            .iter()
+            .flat_map(|account| {
+                account
+                    .folder_options
+                    .iter()
+                    .filter(|(_, options)| options.no_auto_sync)
+                    .map(|(folder, _)| (account.email.clone(), folder.clone()))
+            })
+            .collect()
+    }
+
+    /// Background sync interval for an account, never shorter than 5 seconds
+    pub fn sync_interval_for(&self, account: &EmailAccount) -> std::time::Duration {
+        let secs = account.sync_interval.unwrap_or(self.sync.default_interval);
+        std::time::Duration::from_secs(secs.max(5))
+    }
+
     pub fn get_current_account_safe(&self) -> EmailAccount {
         if self.accounts.is_empty() {
             // Return a default account if none exist (this shouldn't happen in normal usage)
@@ -0,0 +1,252 @@
+use std::io::{Read, Write as _};
+
+use age::armor::{ArmoredReader, ArmoredWriter, Format};
+use age::secrecy::{ExposeSecret, SecretString};
+use anyhow::{anyhow, Context, Result};
+#[cfg(feature = "tui")]
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::credentials::CredentialManager;
+
+/// Environment variable consulted before prompting for the config passphrase
+pub const PASSPHRASE_ENV: &str = "TUIMAIL_CONFIG_PASSPHRASE";
+
+/// First line of an ASCII-armored age file
+const ARMOR_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+// Keyring entry holding the age identity for keyring-protected configs
+const KEYRING_ACCOUNT: &str = "config";
+const KEYRING_KEY_TYPE: &str = "config-key";
+
+/// Where the config encryption key comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    Passphrase,
+    Keyring,
+}
+
+/// What an encrypted config is sealed with
+#[derive(Clone)]
+enum Key {
+    /// An age scrypt recipient, and the work factor it is made with when not age's default
+    Passphrase(SecretString, Option<u8>),
+    /// An age X25519 identity kept in the system keyring
+    Keyring(age::x25519::Identity),
+}
+
+/// Key material for an encrypted config, kept so saves are re-encrypted transparently.
+/// The file is an ASCII-armored age file, so `age -d` opens it too: with the passphrase,
+/// or with the identity exported from the keyring.
+#[derive(Clone)]
+pub struct ConfigEncryption {
+    key: Key,
+}
+
+impl std::fmt::Debug for ConfigEncryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigEncryption")
+            .field("source", &self.source())
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl ConfigEncryption {
+    /// Encrypt to a passphrase, with a fresh salt on every save
+    pub fn with_passphrase(passphrase: &str) -> Result<Self> {
+        Ok(Self { key: Key::Passphrase(SecretString::from(passphrase.to_string()), None) })
+    }
+
+    /// Generate an age identity and store it in the system keyring
+    pub fn with_keyring_key() -> Result<Self> {
+        if !CredentialManager::is_available() {
+            return Err(anyhow!("System keyring is not available"));
+        }
+
+        let identity = age::x25519::Identity::generate();
+        CredentialManager::new()?
+            .store_password(KEYRING_ACCOUNT, KEYRING_KEY_TYPE, identity.to_string().expose_secret())
+            .context("Failed to store config key in keyring")?;
+
+        Ok(Self { key: Key::Keyring(identity) })
+    }
+
+    /// Use a cheaper scrypt work factor than age picks for this machine, for tests
+    #[cfg(test)]
+    pub(crate) fn with_work_factor(mut self, log_n: u8) -> Self {
+        if let Key::Passphrase(_, work_factor) = &mut self.key {
+            *work_factor = Some(log_n);
+        }
+        self
+    }
+
+    fn source(&self) -> KeySource {
+        match self.key {
+            Key::Passphrase(..) => KeySource::Passphrase,
+            Key::Keyring(_) => KeySource::Keyring,
+        }
+    }
+
+    /// Remove the keyring-held key once the config no longer needs it
+    pub fn forget_key(&self) -> Result<()> {
+        if self.source() == KeySource::Keyring {
+            CredentialManager::new()?.delete_password(KEYRING_ACCOUNT, KEYRING_KEY_TYPE)?;
+        }
+        Ok(())
+    }
+
+    /// Seal `plaintext` into the armored age file written to the config file
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String> {
+        let encryptor = match &self.key {
+            Key::Passphrase(passphrase, work_factor) => {
+                let mut recipient = age::scrypt::Recipient::new(passphrase.clone());
+                if let Some(log_n) = work_factor {
+                    recipient.set_work_factor(*log_n);
+                }
+                age::Encryptor::with_recipients(std::iter::once(&recipient as _))
+            }
+            Key::Keyring(identity) => age::Encryptor::with_recipients(std::iter::once(&identity.to_public() as _)),
+        }
+        .map_err(|e| anyhow!("Failed to encrypt config: {}", e))?;
+
+        let mut sealed = Vec::new();
+        let mut writer = encryptor.wrap_output(ArmoredWriter::wrap_output(&mut sealed, Format::AsciiArmor)?)?;
+        writer.write_all(plaintext)?;
+        writer.finish()?.finish()?;
+        Ok(String::from_utf8(sealed).expect("armor is ASCII"))
+    }
+}
+
+/// Whether a config file's content is an age file rather than plain JSON config
+pub fn is_encrypted(content: &str) -> bool {
+    content.trim_start().starts_with(ARMOR_BEGIN)
+}
+
+/// Open an encrypted config. `passphrase` is only called for passphrase-protected files.
+pub fn decrypt(content: &str, passphrase: impl FnOnce() -> Result<String>) -> Result<(String, ConfigEncryption)> {
+    let (plaintext, encryption) = decrypt_bytes(content, passphrase)?;
+    let plaintext = String::from_utf8(plaintext).context("Decrypted config is not valid UTF-8")?;
+    Ok((plaintext, encryption))
+}
+
+/// Open any file written by `ConfigEncryption::encrypt`, such as a mail bundle
+pub fn decrypt_bytes(content: &str, passphrase: impl FnOnce() -> Result<String>) -> Result<(Vec<u8>, ConfigEncryption)> {
+    let decryptor = age::Decryptor::new_buffered(ArmoredReader::new(content.as_bytes())).context("Malformed encrypted file")?;
+
+    let encryption = if decryptor.is_scrypt() {
+        ConfigEncryption::with_passphrase(&passphrase()?)?
+    } else {
+        let stored = CredentialManager::new()?
+            .get_password(KEYRING_ACCOUNT, KEYRING_KEY_TYPE)?
+            .ok_or_else(|| anyhow!("Config key not found in the system keyring"))?;
+        let identity = stored.trim().parse().map_err(|e| anyhow!("Malformed config key in keyring: {}", e))?;
+        ConfigEncryption { key: Key::Keyring(identity) }
+    };
+
+    let opened = match &encryption.key {
+        Key::Passphrase(passphrase, _) => {
+            let identity = age::scrypt::Identity::new(passphrase.clone());
+            decryptor.decrypt(std::iter::once(&identity as _))
+        }
+        Key::Keyring(identity) => decryptor.decrypt(std::iter::once(identity as _)),
+    };
+    let mut plaintext = Vec::new();
+    opened
+        .map_err(|_| anyhow!("Wrong passphrase or key, or the file was tampered with"))?
+        .read_to_end(&mut plaintext)
+        .map_err(|_| anyhow!("Wrong passphrase or key, or the file was tampered with"))?;
+
+    Ok((plaintext, encryption))
+}
+
+/// Read the config passphrase from `TUIMAIL_CONFIG_PASSPHRASE`, or prompt without echo
+pub fn read_passphrase(prompt: &str) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    prompt_hidden(prompt)
+}
+
+#[cfg(feature = "tui")]
+pub(crate) fn prompt_hidden(prompt: &str) -> Result<String> {
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+
+    crossterm::terminal::enable_raw_mode()?;
+    let result = read_hidden_line();
+    crossterm::terminal::disable_raw_mode()?;
+    eprintln!();
+
+    result
+}
+
+/// Without a terminal backend there is no way to turn off echo, so embedders must
+/// supply the passphrase through the environment or their own callback
+#[cfg(not(feature = "tui"))]
+pub(crate) fn prompt_hidden(_prompt: &str) -> Result<String> {
+    Err(anyhow!("Config is encrypted; set {} to unlock it", PASSPHRASE_ENV))
+}
+
+#[cfg(feature = "tui")]
+fn read_hidden_line() -> Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Esc => return Err(anyhow!("Passphrase entry cancelled")),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(anyhow!("Passphrase entry cancelled"))
+            }
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("System random number generator failed: {}", e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passphrase_roundtrip() {
+        // A low work factor keeps the test fast; the file records the one used
+        let encryption = ConfigEncryption::with_passphrase("correct horse").unwrap().with_work_factor(10);
+        let sealed = encryption.encrypt(br#"{"accounts":[]}"#).unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!is_encrypted(r#"{"accounts":[]}"#));
+        assert!(!sealed.contains("accounts"));
+
+        let (plaintext, reopened) = decrypt(&sealed, || Ok("correct horse".to_string())).unwrap();
+        assert_eq!(plaintext, r#"{"accounts":[]}"#);
+        assert_eq!(reopened.source(), KeySource::Passphrase);
+
+        assert!(decrypt(&sealed, || Ok("wrong".to_string())).is_err());
+    }
+
+    #[test]
+    fn identity_files_are_plain_age() {
+        // What the keyring holds is an age identity, so its files open with any age tool
+        let identity = age::x25519::Identity::generate();
+        let sealed = ConfigEncryption { key: Key::Keyring(identity.clone()) }.encrypt(b"secret").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(age::decrypt(&identity, sealed.as_bytes()).unwrap(), b"secret");
+    }
+}
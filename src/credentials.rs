@@ -11,7 +11,7 @@ impl CredentialManager {
     /// Create a new credential manager
     pub fn new() -> Result<Self> {
         Ok(Self {
-            app_name: "email-client".to_string(),
+            app_name: crate::profile::keyring_app_name(),
         })
     }
 
@@ -84,10 +84,7 @@ pub struct FallbackCredentialManager {
 
 impl FallbackCredentialManager {
     pub fn new() -> Result<Self> {
-        let config_dir = dirs::config_dir()
-            .context("Failed to get config directory")?
-            .join("tuimail")
-            .join("credentials");
+        let config_dir = crate::profile::credentials_dir();
         
         std::fs::create_dir_all(&config_dir)
             .context("Failed to create credentials directory")?;
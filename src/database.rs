@@ -1,13 +1,303 @@
 use crate::email::{Email, EmailAttachment, EmailAddress};
+use crate::reminders::Reminder;
 use anyhow::{Result, Context};
 use chrono::{DateTime, Local, TimeZone};
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, ErrorCode, OptionalExtension, Transaction, TransactionBehavior, params};
 use serde_json;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+/// Attachments larger than this are stored in cache files rather than in SQLite rows
+pub const ATTACHMENT_SPILL_THRESHOLD: usize = 1024 * 1024;
+
+/// How long a connection waits for another one, such as the sync daemon's, to finish
+/// writing before SQLite reports the database as busy
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Further attempts at a write that still finds the database busy, with doubling pauses
+const BUSY_RETRIES: u32 = 4;
+
+/// Copies of sent messages stored before the Sent folder syncs get UIDs counting down
+/// from `u32::MAX` to here, far above any UID a server hands out
+pub const LOCAL_UID_FLOOR: u32 = u32::MAX - 1_000_000;
+
+/// A correspondent aggregated over every folder of an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactSummary {
+    pub address: String,
+    pub name: Option<String>,
+    pub last_activity: i64, // Unix timestamp of the latest message
+    pub message_count: usize,
+    /// In the address book, not only seen in mail
+    pub saved: bool,
+}
+
+/// A person saved to the address book, by lowercase address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedContact {
+    pub address: String,
+    pub name: Option<String>,
+    pub organization: Option<String>,
+    pub phone: Option<String>,
+}
+
+/// One attachment in the account-wide attachment index, with enough of its message to
+/// filter and display it without loading the attachment data
+#[derive(Debug, Clone)]
+pub struct AttachmentIndexEntry {
+    pub folder: String,
+    pub email_uid: u32,
+    pub filename: String,
+    pub content_type: String,
+    pub size: usize,
+    pub sender: String,
+    pub subject: String,
+    pub date: i64, // Unix timestamp of the message
+}
+
+/// An attachment payload kept locally; see `EmailDatabase::cached_attachments`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedAttachment {
+    pub id: i64,
+    /// Bytes held in the row itself
+    pub stored: u64,
+    /// Cache file holding the payload instead
+    pub path: Option<std::path::PathBuf>,
+    /// Unix time it was last opened, or cached when never
+    pub used_at: i64,
+    /// Whether its message is on the server, so it can be downloaded again
+    pub on_server: bool,
+}
+
+/// A recorded delete, move, flag change or send
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AuditEntry {
+    pub timestamp: i64, // Unix timestamp
+    pub account_email: String,
+    pub folder: String,
+    pub uid: Option<u32>, // None for sends
+    pub action: String,
+    /// Where the operation was started: "ui", "rule" or "cli"
+    pub origin: String,
+    pub detail: String,
+}
+
+/// A recipient a sent message bounced from, found in a delivery status notification
+#[derive(Debug, Clone)]
+pub struct BounceRecord {
+    /// Message-ID of the sent message, without angle brackets
+    pub message_id: String,
+    pub recipient: String,
+    pub status: String,
+    pub reason: String,
+    /// Where the bounce notification itself is stored
+    pub bounce_folder: String,
+    pub bounce_uid: u32,
+    pub detected_at: i64, // Unix timestamp of the bounce
+}
+
+/// An address mail hard-bounced from (see `dsn::is_hard`), so sending to it again is
+/// warned about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuppressedAddress {
+    /// Lowercase
+    pub address: String,
+    pub status: String,
+    pub reason: String,
+    pub bounced_at: i64, // Unix timestamp of the latest hard bounce
+}
+
+/// How messages from one sender are shown, learned from how earlier ones were viewed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SenderPreferences {
+    /// Load images and other remote content of HTML bodies
+    pub remote_content: bool,
+    /// Show quoted text instead of folding it away
+    pub expand_quotes: bool,
+    /// Show the HTML body rather than the text one
+    pub html: bool,
+}
+
+/// A message deleted from the server whose full content is kept locally for a while
+#[derive(Debug, Clone)]
+pub struct DeletedMessage {
+    pub id: i64,
+    /// Folder and UID it had, restored to that folder
+    pub folder: String,
+    pub uid: u32,
+    pub subject: String,
+    pub sender: String,
+    pub flags: Vec<String>,
+    pub deleted_at: i64,
+    /// Expunged by another client rather than deleted here; the content was rebuilt
+    /// from the cache instead of downloaded
+    pub elsewhere: bool,
+}
+
+/// A change queued with `queue_email_operation` that the background sync has not carried
+/// out yet
+#[derive(Debug, Clone)]
+pub struct PendingOperation {
+    pub id: i64,
+    pub account_email: String,
+    /// `mark_read`, `mark_unread`, `delete` or `move`
+    pub operation_type: String,
+    pub email_uid: u32,
+    pub folder: String,
+    pub target_folder: Option<String>,
+    pub created_at: i64,
+    /// Failed attempts so far, the last error, and when the next attempt is due
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub retry_at: i64,
+    /// Subject of the message when it was queued
+    pub subject: Option<String>,
+}
+
+impl PendingOperation {
+    /// What the operation does, for messages and the pending operations screen
+    pub fn describe(&self) -> String {
+        match (self.operation_type.as_str(), &self.target_folder) {
+            ("mark_read", _) => "mark read".to_string(),
+            ("mark_unread", _) => "mark unread".to_string(),
+            ("move", Some(target)) => format!("move to {}", target),
+            (other, _) => other.replace('_', " "),
+        }
+    }
+}
+
+/// The text body of an `emails` row, falling back to the body shared with copies of the
+/// message in other folders (see `save_emails`)
+pub(crate) const BODY_TEXT: &str = "COALESCE(body_text, (SELECT s.body_text FROM shared_bodies s
+    WHERE s.account_email = emails.account_email AND s.message_id = emails.content_key))";
+
+/// The HTML body of an `emails` row, like `BODY_TEXT`
+const BODY_HTML: &str = "COALESCE(body_html, (SELECT s.body_html FROM shared_bodies s
+    WHERE s.account_email = emails.account_email AND s.message_id = emails.content_key))";
+
+/// The Message-ID copies of a message in different folders are matched by, without
+/// angle brackets
+pub(crate) fn message_key(email: &Email) -> Option<String> {
+    email
+        .header("Message-ID")
+        .map(|id| id.trim().trim_matches(['<', '>']).to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// The key a message's body is shared under for its Gmail id. Unlike a Message-ID, which
+/// anyone can reuse, it names one message.
+fn gmail_key(gmail_id: &str) -> String {
+    format!("gmail:{}", gmail_id)
+}
+
+/// The text searched for an attachment, read while the payload is still at hand
+fn attachment_search_text(attachment: &crate::email::EmailAttachment) -> Option<String> {
+    if attachment.size() > crate::attachment_text::MAX_EXTRACT_BYTES {
+        return None;
+    }
+    let data = attachment.load_data().ok()?;
+    crate::attachment_text::extract_text(&attachment.content_type, &attachment.filename, &data)
+}
+
+/// A compose draft kept locally and mirrored to the account's Drafts folder
+#[derive(Debug, Clone)]
+pub struct Draft {
+    /// Message-ID of the draft, which stays the same across edits and devices
+    pub message_id: String,
+    pub email: Email,
+    pub modified: i64, // Unix timestamp of the last local edit
+    /// UID of the copy in the Drafts folder this draft was last synced with
+    pub server_uid: Option<u32>,
+    /// `modified` as of the last sync; a later edit still has to be uploaded
+    pub synced: i64,
+    /// Discarded or sent here; the server copy is removed at the next sync
+    pub deleted: bool,
+    /// A newer server copy edited elsewhere while this one had local changes
+    pub conflict: Option<(u32, Email)>,
+}
+
+impl Draft {
+    /// Whether there are local edits the server has not seen
+    pub fn has_local_changes(&self) -> bool {
+        self.server_uid.is_none() || self.modified > self.synced
+    }
+}
+
+/// Give a Gmail message the next UID of a label unless it has one there already
+fn add_gmail_uid(conn: &Connection, account_email: &str, label_id: &str, message_id: &str) -> rusqlite::Result<()> {
+    let added = conn.execute(
+        "INSERT OR IGNORE INTO gmail_uids (account_email, label_id, uid, message_id)
+         SELECT ?1, ?2, next_uid, ?3 FROM gmail_labels WHERE account_email = ?1 AND label_id = ?2",
+        params![account_email, label_id, message_id],
+    )?;
+    if added > 0 {
+        conn.execute(
+            "UPDATE gmail_labels SET next_uid = next_uid + 1 WHERE account_email = ?1 AND label_id = ?2",
+            params![account_email, label_id],
+        )?;
+    }
+    Ok(())
+}
 
 pub struct EmailDatabase {
     conn: Connection,
     db_path: std::path::PathBuf,
+    /// Shared by every connection of this process to the same file (see `writer_lock`)
+    writer: Arc<Mutex<()>>,
+}
+
+/// The single writer lock of a database file. The TUI, its sync thread and short-lived
+/// connections of one process take turns writing through it rather than failing on each
+/// other's SQLite locks; other processes are waited for with the busy timeout.
+fn writer_lock(path: &Path) -> Arc<Mutex<()>> {
+    static WRITERS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+    let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut writers = WRITERS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    writers.entry(key).or_default().clone()
+}
+
+/// Whether SQLite refused an operation because another connection holds the database
+fn is_busy(error: &rusqlite::Error) -> bool {
+    matches!(error.sqlite_error_code(), Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked))
+}
+
+/// Run `op`, trying again a few times with growing pauses while the database is busy
+fn retry_busy<T>(mut op: impl FnMut() -> rusqlite::Result<T>) -> rusqlite::Result<T> {
+    let mut pause = Duration::from_millis(50);
+    for _ in 0..BUSY_RETRIES {
+        match op() {
+            Err(e) if is_busy(&e) => {
+                crate::email::debug_log(&format!("Database busy, retrying in {:?}", pause));
+                std::thread::sleep(pause);
+                pause *= 2;
+            }
+            result => return result,
+        }
+    }
+    op()
+}
+
+/// A write transaction that holds the process's writer lock until it is committed or dropped
+struct WriteTransaction<'a> {
+    // Declared first so it is rolled back before the lock is released
+    tx: Transaction<'a>,
+    _writing: MutexGuard<'a, ()>,
+}
+
+impl<'a> WriteTransaction<'a> {
+    fn commit(self) -> rusqlite::Result<()> {
+        self.tx.commit()
+    }
+}
+
+impl<'a> std::ops::Deref for WriteTransaction<'a> {
+    type Target = Transaction<'a>;
+
+    fn deref(&self) -> &Transaction<'a> {
+        &self.tx
+    }
 }
 
 impl EmailDatabase {
@@ -21,21 +311,50 @@ impl EmailDatabase {
         let conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open database: {:?}", db_path))?;
 
+        // Readers don't block the writer in WAL mode, and writers wait for each other
+        // instead of failing straight away
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        retry_busy(|| conn.pragma_update(None, "journal_mode", "WAL"))
+            .with_context(|| format!("Failed to enable WAL mode: {:?}", db_path))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+
         let db = EmailDatabase { 
             conn,
             db_path: db_path.to_path_buf(),
+            writer: writer_lock(db_path),
         };
         db.initialize_schema()?;
         Ok(db)
     }
 
+    /// Take this process's writer lock for the database file; a panic while writing
+    /// doesn't leave it unusable
+    fn lock_writer(&self) -> MutexGuard<'_, ()> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run one write statement under the writer lock, retrying while the database is busy
+    fn execute<P: rusqlite::Params + Clone>(&self, sql: &str, params: P) -> rusqlite::Result<usize> {
+        let _writing = self.lock_writer();
+        retry_busy(|| self.conn.execute(sql, params.clone()))
+    }
+
+    /// Start a write transaction under the writer lock. SQLite's write lock is taken at
+    /// the start, so a busy database is found (and retried) before any work is done
+    /// rather than when a read inside the transaction turns into a write.
+    fn write_transaction(&self) -> rusqlite::Result<WriteTransaction<'_>> {
+        let writing = self.lock_writer();
+        let tx = retry_busy(|| Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate))?;
+        Ok(WriteTransaction { tx, _writing: writing })
+    }
+
     pub fn get_database_path(&self) -> String {
         self.db_path.to_string_lossy().to_string()
     }
 
     fn initialize_schema(&self) -> Result<()> {
         // Create emails table
-        self.conn.execute(
+        self.execute(
             "CREATE TABLE IF NOT EXISTS emails (
                 uid INTEGER NOT NULL,
                 account_email TEXT NOT NULL,
@@ -59,8 +378,53 @@ impl EmailDatabase {
             [],
         )?;
 
+        // Values of the configured header fields (see `header_fields`) as a JSON object by
+        // field name, for searching; added later than the rest of the table
+        let has_header_fields_column = self.conn
+            .prepare("SELECT header_fields FROM emails LIMIT 0")
+            .is_ok();
+        if !has_header_fields_column {
+            self.execute("ALTER TABLE emails ADD COLUMN header_fields TEXT", [])?;
+        }
+
+        // Preview line for two-line list rows, filled in when the body is saved
+        let has_snippet_column = self.conn
+            .prepare("SELECT snippet FROM emails LIMIT 0")
+            .is_ok();
+        if !has_snippet_column {
+            self.execute("ALTER TABLE emails ADD COLUMN snippet TEXT", [])?;
+        }
+
+        // The key of the row's body in shared_bodies (see `save_emails`); bodies used to be
+        // shared under the Message-ID alone
+        let has_content_key_column = self.conn
+            .prepare("SELECT content_key FROM emails LIMIT 0")
+            .is_ok();
+        if !has_content_key_column {
+            self.execute("ALTER TABLE emails ADD COLUMN content_key TEXT", [])?;
+            self.execute("UPDATE emails SET content_key = message_id", [])?;
+        }
+
+        // The header fields the header_fields column was last filled for, as JSON
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS header_field_index (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                fields TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // The `snippet::VERSION` the snippet column was last filled with
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS snippet_index (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                version INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
         // Create attachments table
-        self.conn.execute(
+        self.execute(
             "CREATE TABLE IF NOT EXISTS attachments (
                 id INTEGER PRIMARY KEY,
                 account_email TEXT NOT NULL,
@@ -76,8 +440,101 @@ impl EmailDatabase {
             [],
         )?;
 
+        // Older databases predate the path column for attachments spilled to disk
+        let has_path_column = self.conn
+            .prepare("SELECT path FROM attachments LIMIT 0")
+            .is_ok();
+        if !has_path_column {
+            self.execute("ALTER TABLE attachments ADD COLUMN path TEXT", [])?;
+        }
+
+        // Text of PDF, DOCX and plain text attachments for search, added later still
+        let has_text_column = self.conn
+            .prepare("SELECT text FROM attachments LIMIT 0")
+            .is_ok();
+        if !has_text_column {
+            self.execute("ALTER TABLE attachments ADD COLUMN text TEXT", [])?;
+        }
+
+        // When each attachment was last opened, for evicting the least recently used
+        let has_used_column = self.conn
+            .prepare("SELECT used_at FROM attachments LIMIT 0")
+            .is_ok();
+        if !has_used_column {
+            self.execute("ALTER TABLE attachments ADD COLUMN used_at INTEGER", [])?;
+        }
+
+        // Account-wide lookups for the attachment browser
+        self.execute(
+            "CREATE INDEX IF NOT EXISTS idx_attachments_account
+             ON attachments(account_email, content_type)",
+            [],
+        )?;
+
+        // Sender avatar cache, with brand logos under `bimi:<domain>`; a NULL image records
+        // that no avatar exists
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS avatars (
+                address TEXT PRIMARY KEY,
+                image BLOB,
+                fetched_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // The releases found by the last update check, as JSON
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS release_check (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                releases TEXT NOT NULL,
+                checked_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Bodies of messages stored in several folders (Gmail labels, copies), kept once
+        // under their content key, despite the column's name. The emails rows then leave
+        // their body columns empty.
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS shared_bodies (
+                account_email TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                body_text TEXT,
+                body_html TEXT,
+                PRIMARY KEY(account_email, message_id)
+            )",
+            [],
+        )?;
+
+        // When each folder was last synced, read and reset through `save_sync_state` and
+        // `clear_folder_emails`
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                account_email TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                last_sync_timestamp INTEGER NOT NULL,
+                last_uid_seen INTEGER NOT NULL,
+                sync_in_progress BOOLEAN DEFAULT FALSE,
+                last_error TEXT,
+                PRIMARY KEY(account_email, folder)
+            )",
+            [],
+        )?;
+
+        // Where an initial sync of a folder got to; the row is removed once it completes
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS sync_progress (
+                account_email TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                last_uid INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                PRIMARY KEY(account_email, folder)
+            )",
+            [],
+        )?;
+
         // Create folder metadata table
-        self.conn.execute(
+        self.execute(
             "CREATE TABLE IF NOT EXISTS folder_metadata (
                 id INTEGER PRIMARY KEY,
                 account_email TEXT NOT NULL,
@@ -90,71 +547,420 @@ impl EmailDatabase {
             [],
         )?;
 
+        // The UIDVALIDITY and HIGHESTMODSEQ of CONDSTORE servers, added later
+        let has_modseq_columns = self.conn
+            .prepare("SELECT highest_modseq FROM folder_metadata LIMIT 0")
+            .is_ok();
+        if !has_modseq_columns {
+            self.execute("ALTER TABLE folder_metadata ADD COLUMN uid_validity INTEGER NOT NULL DEFAULT 0", [])?;
+            self.execute("ALTER TABLE folder_metadata ADD COLUMN highest_modseq INTEGER NOT NULL DEFAULT 0", [])?;
+        }
+
+        // Messages stored with headers only, whose bodies are still to be fetched
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS pending_bodies (
+                account_email TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                uid INTEGER NOT NULL,
+                PRIMARY KEY(account_email, folder, uid)
+            )",
+            [],
+        )?;
+
+        // Audit trail of operations that change or remove mail on the server
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                account_email TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                uid INTEGER,
+                action TEXT NOT NULL,
+                origin TEXT NOT NULL,
+                detail TEXT NOT NULL DEFAULT ''
+            )",
+            [],
+        )?;
+
+        // Recipients sent messages bounced from, keyed by the sent message's Message-ID
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS bounces (
+                account_email TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                recipient TEXT NOT NULL,
+                status TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                bounce_folder TEXT NOT NULL,
+                bounce_uid INTEGER NOT NULL,
+                detected_at INTEGER NOT NULL,
+                PRIMARY KEY(account_email, message_id, recipient)
+            )",
+            [],
+        )?;
+
+        // Addresses that hard-bounced. Those of bounces recorded before the list existed
+        // are added when it is created.
+        let new_suppression_list = self.conn.prepare("SELECT 1 FROM suppressed_addresses LIMIT 0").is_err();
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS suppressed_addresses (
+                account_email TEXT NOT NULL,
+                address TEXT NOT NULL,
+                status TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                bounced_at INTEGER NOT NULL,
+                PRIMARY KEY(account_email, address)
+            )",
+            [],
+        )?;
+        if new_suppression_list {
+            let mut stmt = self.conn.prepare(
+                "SELECT account_email, recipient, status, reason, detected_at FROM bounces ORDER BY detected_at",
+            )?;
+            let bounces = stmt
+                .query_map([], |row| {
+                    let suppressed = SuppressedAddress {
+                        address: row.get(1)?,
+                        status: row.get(2)?,
+                        reason: row.get(3)?,
+                        bounced_at: row.get(4)?,
+                    };
+                    Ok((row.get::<_, String>(0)?, suppressed))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (account_email, suppressed) in bounces {
+                if crate::dsn::is_hard(&suppressed.status) {
+                    self.suppress_address(&account_email, &suppressed)?;
+                }
+            }
+        }
+
+        // How messages from a sender were last viewed, by lowercase address
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS sender_preferences (
+                account_email TEXT NOT NULL,
+                address TEXT NOT NULL,
+                remote_content BOOLEAN NOT NULL DEFAULT 0,
+                expand_quotes BOOLEAN NOT NULL DEFAULT 0,
+                html BOOLEAN NOT NULL DEFAULT 0,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY(account_email, address)
+            )",
+            [],
+        )?;
+
+        // Muted conversations by the Message-ID of their first message
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS muted_threads (
+                account_email TEXT NOT NULL,
+                root TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                archive BOOLEAN NOT NULL DEFAULT 0,
+                muted_at INTEGER NOT NULL,
+                PRIMARY KEY(account_email, root)
+            )",
+            [],
+        )?;
+
+        // Recently deleted messages with their raw content, for restoring with APPEND
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS deleted_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_email TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                uid INTEGER NOT NULL,
+                subject TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                flags TEXT NOT NULL,
+                deleted_at INTEGER NOT NULL,
+                elsewhere BOOLEAN NOT NULL DEFAULT 0,
+                raw BLOB NOT NULL,
+                UNIQUE(account_email, folder, uid)
+            )",
+            [],
+        )?;
+
+        // Changes made while browsing (mark read, delete, ...) waiting for the background
+        // sync to carry them out on the server, with the subject of the message for the
+        // pending operations screen, the last error and when to try again
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS email_operations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_email TEXT NOT NULL,
+                operation_type TEXT NOT NULL,
+                email_uid INTEGER NOT NULL,
+                folder TEXT NOT NULL,
+                target_folder TEXT,
+                subject TEXT,
+                created_at INTEGER NOT NULL,
+                processed BOOLEAN NOT NULL DEFAULT FALSE,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                error TEXT,
+                retry_at INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+
+        // Reminders about messages, in the global database so one list covers every
+        // account; kept after they go off until they are dismissed
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS reminders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_email TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                uid INTEGER NOT NULL,
+                message_id TEXT,
+                subject TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                remind_at INTEGER NOT NULL,
+                fired BOOLEAN NOT NULL DEFAULT FALSE
+            )",
+            [],
+        )?;
+
+        // Tags of messages, by Message-ID so every copy of a message has the same ones as
+        // in notmuch; with the notmuch feature they are kept in step with its database
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS tags (
+                account_email TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY(account_email, message_id, tag)
+            )",
+            [],
+        )?;
+
+        // Senders marked as VIP, by lowercase address
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS vip_contacts (
+                account_email TEXT NOT NULL,
+                address TEXT NOT NULL,
+                added_at INTEGER NOT NULL,
+                PRIMARY KEY(account_email, address)
+            )",
+            [],
+        )?;
+
+        // People saved to the address book, from vCards or the viewer, by lowercase address
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS address_book (
+                account_email TEXT NOT NULL,
+                address TEXT NOT NULL,
+                name TEXT,
+                organization TEXT,
+                phone TEXT,
+                added_at INTEGER NOT NULL,
+                PRIMARY KEY(account_email, address)
+            )",
+            [],
+        )?;
+
+        // Compose drafts; the content is the draft email as JSON
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS drafts (
+                account_email TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                content TEXT NOT NULL,
+                modified INTEGER NOT NULL,
+                server_uid INTEGER,
+                synced INTEGER NOT NULL DEFAULT 0,
+                deleted BOOLEAN NOT NULL DEFAULT 0,
+                conflict_uid INTEGER,
+                conflict_content TEXT,
+                PRIMARY KEY(account_email, message_id)
+            )",
+            [],
+        )?;
+
+        // Gmail API accounts: the labels of each message, the UIDs given to the messages
+        // of each label (see `gmail::GmailBackend`), the next UID of each label listed so
+        // far, and the history ID the cache is current to
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS gmail_messages (
+                account_email TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                label_ids TEXT NOT NULL,
+                PRIMARY KEY(account_email, message_id)
+            )",
+            [],
+        )?;
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS gmail_uids (
+                account_email TEXT NOT NULL,
+                label_id TEXT NOT NULL,
+                uid INTEGER NOT NULL,
+                message_id TEXT NOT NULL,
+                PRIMARY KEY(account_email, label_id, uid),
+                UNIQUE(account_email, label_id, message_id)
+            )",
+            [],
+        )?;
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS gmail_labels (
+                account_email TEXT NOT NULL,
+                label_id TEXT NOT NULL,
+                next_uid INTEGER NOT NULL,
+                PRIMARY KEY(account_email, label_id)
+            )",
+            [],
+        )?;
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS gmail_history (
+                account_email TEXT PRIMARY KEY,
+                history_id TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Exchange accounts: the EWS item ID behind each UID. UIDs come from one counter
+        // for every folder, so they only grow and are never given out twice.
+        self.execute(
+            "CREATE TABLE IF NOT EXISTS exchange_items (
+                uid INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_email TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                UNIQUE(account_email, folder, item_id)
+            )",
+            [],
+        )?;
+
         // Create indexes for better performance
-        self.conn.execute(
+        self.execute(
             "CREATE INDEX IF NOT EXISTS idx_emails_account_folder 
              ON emails(account_email, folder)",
             [],
         )?;
 
-        self.conn.execute(
+        self.execute(
             "CREATE INDEX IF NOT EXISTS idx_emails_uid 
              ON emails(account_email, folder, uid)",
             [],
         )?;
 
-        self.conn.execute(
+        self.execute(
             "CREATE INDEX IF NOT EXISTS idx_emails_date 
              ON emails(account_email, folder, date_received DESC)",
             [],
         )?;
 
         // Simple index on timestamp for efficient MAX() queries
-        self.conn.execute(
+        self.execute(
             "CREATE INDEX IF NOT EXISTS idx_emails_timestamp 
              ON emails(date_received DESC)",
             [],
         )?;
 
-        self.conn.execute(
+        self.execute(
             "CREATE INDEX IF NOT EXISTS idx_attachments_email 
              ON attachments(account_email, folder, email_uid)",
             [],
         )?;
 
+        self.execute(
+            "CREATE INDEX IF NOT EXISTS idx_emails_message_id
+             ON emails(account_email, message_id)",
+            [],
+        )?;
+
+        self.execute(
+            "CREATE INDEX IF NOT EXISTS idx_emails_content_key
+             ON emails(account_email, content_key)",
+            [],
+        )?;
+
         Ok(())
     }
 
     pub fn save_emails(&self, account_email: &str, folder: &str, emails: &[Email]) -> Result<()> {
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.write_transaction()?;
 
         for email in emails {
             // Parse UID from email.id (which is stored as string)
             let uid: u32 = email.id.parse().unwrap_or(0);
+
+            // The server's copy of a message sent from here replaces the local one
+            let key = message_key(email);
+            if let (Some(key), true) = (&key, uid < LOCAL_UID_FLOOR) {
+                tx.execute(
+                    "DELETE FROM attachments WHERE account_email = ?1 AND folder = ?2 AND email_uid >= ?3
+                     AND email_uid IN (SELECT uid FROM emails WHERE account_email = ?1 AND folder = ?2 AND message_id = ?4)",
+                    params![account_email, folder, LOCAL_UID_FLOOR, key],
+                )?;
+                tx.execute(
+                    "DELETE FROM emails WHERE account_email = ?1 AND folder = ?2 AND uid >= ?3 AND message_id = ?4",
+                    params![account_email, folder, LOCAL_UID_FLOOR, key],
+                )?;
+            }
+
+            // A body seen before under the same content key is stored once and shared. The
+            // key is the Gmail id where the server has one, which a row keeps once it is
+            // known, and the Message-ID otherwise. A different body under a reused
+            // Message-ID stays with its own row.
+            let content_key = match &email.gmail_id {
+                Some(gmail_id) => Some(gmail_key(gmail_id)),
+                None => tx
+                    .query_row(
+                        "SELECT content_key FROM emails WHERE account_email = ?1 AND folder = ?2 AND uid = ?3",
+                        params![account_email, folder, uid],
+                        |row| row.get::<_, Option<String>>(0),
+                    )
+                    .optional()?
+                    .flatten()
+                    .filter(|known| known.starts_with(&gmail_key("")))
+                    .or_else(|| key.clone()),
+            };
+            let has_body = email.body_text.is_some() || email.body_html.is_some();
+            let mut body = (email.body_text.as_deref(), email.body_html.as_deref());
+            if let (Some(key), true) = (&content_key, has_body) {
+                let shared: Option<(Option<String>, Option<String>)> = tx
+                    .query_row(
+                        "SELECT body_text, body_html FROM shared_bodies WHERE account_email = ?1 AND message_id = ?2",
+                        params![account_email, key],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                match shared {
+                    None => {
+                        tx.execute(
+                            "INSERT INTO shared_bodies (account_email, message_id, body_text, body_html)
+                             VALUES (?1, ?2, ?3, ?4)",
+                            params![account_email, key, email.body_text, email.body_html],
+                        )?;
+                        body = (None, None);
+                    }
+                    Some((text, html)) if text == email.body_text && html == email.body_html => body = (None, None),
+                    Some(_) => {}
+                }
+            }
             
             // Insert or replace email
+            let header_fields = crate::header_fields::extract(&email.headers);
+            let snippet = crate::snippet::generate(email).or_else(|| email.snippet.clone());
             tx.execute(
                 "INSERT OR REPLACE INTO emails (
                     uid, account_email, folder, message_id, subject,
                     from_addresses, to_addresses, cc_addresses, bcc_addresses,
-                    date_received, body_text, body_html, flags, headers, seen
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                    date_received, body_text, body_html, flags, headers, seen, header_fields, snippet, content_key
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
                 params![
                     uid,
                     account_email,
                     folder,
-                    None::<String>, // We don't have message_id in current Email struct
+                    key,
                     email.subject,
                     serde_json::to_string(&email.from)?,
                     serde_json::to_string(&email.to)?,
                     serde_json::to_string(&email.cc)?,
                     serde_json::to_string(&email.bcc)?,
                     email.date.timestamp(),
-                    email.body_text.as_deref(),
-                    email.body_html.as_deref(),
+                    body.0,
+                    body.1,
                     serde_json::to_string(&email.flags)?,
                     serde_json::to_string(&email.headers)?,
                     email.seen,
+                    serde_json::to_string(&header_fields)?,
+                    snippet,
+                    content_key,
                 ],
             )?;
 
@@ -164,19 +970,23 @@ impl EmailDatabase {
                 params![account_email, folder, uid],
             )?;
 
-            // Insert attachments
+            // Insert attachments, keeping large payloads in cache files instead of the row
             for attachment in &email.attachments {
+                let (data, path) = self.spill_attachment(account_email, folder, uid, attachment)?;
+                let text = attachment_search_text(attachment);
                 tx.execute(
-                    "INSERT INTO attachments (account_email, folder, email_uid, filename, content_type, data, size)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    "INSERT INTO attachments (account_email, folder, email_uid, filename, content_type, data, size, path, text)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
                     params![
                         account_email,
                         folder,
                         uid,
                         attachment.filename,
                         attachment.content_type,
-                        attachment.data,
-                        attachment.data.len() as i64,
+                        data,
+                        attachment.size() as i64,
+                        path,
+                        text,
                     ],
                 )?;
             }
@@ -187,14 +997,14 @@ impl EmailDatabase {
     }
 
     pub fn load_emails(&self, account_email: &str, folder: &str) -> Result<Vec<Email>> {
-        let mut stmt = self.conn.prepare(
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT uid, message_id, subject, from_addresses, to_addresses, 
-                    cc_addresses, bcc_addresses, date_received, body_text, body_html,
-                    flags, headers, seen
+                    cc_addresses, bcc_addresses, date_received, {BODY_TEXT}, {BODY_HTML},
+                    flags, headers, seen, snippet
              FROM emails 
              WHERE account_email = ?1 AND folder = ?2 
-             ORDER BY date_received DESC",
-        )?;
+             ORDER BY date_received DESC"
+        ))?;
 
         let email_rows = stmt.query_map(params![account_email, folder], |row| {
             Ok((
@@ -211,17 +1021,18 @@ impl EmailDatabase {
                 row.get::<_, String>(10)?,   // flags
                 row.get::<_, String>(11)?,   // headers
                 row.get::<_, bool>(12)?,     // seen
+                row.get::<_, Option<String>>(13)?, // snippet
             ))
         })?;
 
         let mut emails = Vec::new();
         for row_result in email_rows {
             let (uid, _message_id, subject, from_json, to_json, cc_json, bcc_json,
-                 date_timestamp, body_text, body_html, flags_json, headers_json, seen) = row_result?;
+                 date_timestamp, body_text, body_html, flags_json, headers_json, seen, snippet) = row_result?;
 
             // Load attachments for this email
             let mut attachment_stmt = self.conn.prepare(
-                "SELECT filename, content_type, data FROM attachments 
+                "SELECT filename, content_type, data, path, size FROM attachments 
                  WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3"
             )?;
             
@@ -230,6 +1041,8 @@ impl EmailDatabase {
                     filename: row.get(0)?,
                     content_type: row.get(1)?,
                     data: row.get(2)?,
+                    path: row.get::<_, Option<String>>(3)?.map(std::path::PathBuf::from),
+                    size: row.get::<_, i64>(4)?.max(0) as usize,
                 })
             })?;
 
@@ -255,6 +1068,8 @@ impl EmailDatabase {
                 headers: serde_json::from_str(&headers_json)?,
                 seen,
                 folder: folder.to_string(),
+                snippet,
+                gmail_id: None,
             };
 
             emails.push(email);
@@ -263,17 +1078,157 @@ impl EmailDatabase {
         Ok(emails)
     }
 
-    pub fn save_folder_metadata(&self, account_email: &str, folder: &str, last_uid: u32, total_messages: u32) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO folder_metadata (account_email, folder, last_uid, total_messages, last_sync)
-             VALUES (?1, ?2, ?3, ?4, strftime('%s', 'now'))",
-            params![account_email, folder, last_uid, total_messages],
-        )?;
-        Ok(())
+    /// Fill the header_fields column of every cached message again from its headers when
+    /// the configured header fields changed since it was last filled. Returns how many
+    /// messages were updated.
+    pub fn reindex_header_fields(&self) -> Result<usize> {
+        let fields = serde_json::to_string(&crate::header_fields::fields())?;
+        let indexed: Option<String> = self
+            .conn
+            .query_row("SELECT fields FROM header_field_index WHERE id = 1", [], |row| row.get(0))
+            .optional()?;
+        if indexed.as_deref() == Some(fields.as_str()) {
+            return Ok(0);
+        }
+
+        let tx = self.write_transaction()?;
+        let mut updated = 0;
+        {
+            let mut select = tx.prepare("SELECT rowid, headers FROM emails")?;
+            let mut update = tx.prepare("UPDATE emails SET header_fields = ?1 WHERE rowid = ?2")?;
+            let mut rows = select.query([])?;
+            while let Some(row) = rows.next()? {
+                let headers: HashMap<String, String> = serde_json::from_str(&row.get::<_, String>(1)?).unwrap_or_default();
+                update.execute(params![serde_json::to_string(&crate::header_fields::extract(&headers))?, row.get::<_, i64>(0)?])?;
+                updated += 1;
+            }
+        }
+        tx.execute("INSERT OR REPLACE INTO header_field_index (id, fields) VALUES (1, ?1)", params![fields])?;
+        tx.commit()?;
+        Ok(updated)
     }
 
-    pub fn load_folder_metadata(&self, account_email: &str, folder: &str) -> Result<(u32, u32, i64)> {
-        let result = self.conn.query_row(
+    /// Make the snippet of every cached message with a body again when snippets were
+    /// last made by another `snippet::VERSION`, or not at all. Returns how many messages
+    /// were updated.
+    pub fn regenerate_snippets(&self) -> Result<usize> {
+        let version: Option<i64> = self
+            .conn
+            .query_row("SELECT version FROM snippet_index WHERE id = 1", [], |row| row.get(0))
+            .optional()?;
+        if version == Some(crate::snippet::VERSION) {
+            return Ok(0);
+        }
+
+        let tx = self.write_transaction()?;
+        let mut updated = 0;
+        {
+            let mut select = tx.prepare(&format!("SELECT rowid, {BODY_TEXT}, {BODY_HTML} FROM emails"))?;
+            let mut update = tx.prepare("UPDATE emails SET snippet = ?1 WHERE rowid = ?2")?;
+            let mut rows = select.query([])?;
+            while let Some(row) = rows.next()? {
+                let (text, html): (Option<String>, Option<String>) = (row.get(1)?, row.get(2)?);
+                if text.is_none() && html.is_none() {
+                    continue;
+                }
+                update.execute(params![crate::snippet::from_bodies(text.as_deref(), html.as_deref()), row.get::<_, i64>(0)?])?;
+                updated += 1;
+            }
+        }
+        tx.execute("INSERT OR REPLACE INTO snippet_index (id, version) VALUES (1, ?1)", params![crate::snippet::VERSION])?;
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Store a copy of a message just sent in the Sent folder under a local UID, until
+    /// the server's copy arrives with the same Message-ID. Returns the UID given.
+    pub fn save_sent_copy(&self, account_email: &str, folder: &str, email: &Email) -> Result<u32> {
+        let lowest: Option<u32> = self.conn.query_row(
+            "SELECT MIN(uid) FROM emails WHERE account_email = ?1 AND folder = ?2 AND uid >= ?3",
+            params![account_email, folder, LOCAL_UID_FLOOR],
+            |row| row.get(0),
+        )?;
+        let uid = lowest.map_or(u32::MAX, |uid| uid - 1);
+        let mut copy = email.clone();
+        copy.id = uid.to_string();
+        copy.folder = folder.to_string();
+        self.save_emails(account_email, folder, &[copy])?;
+        Ok(uid)
+    }
+
+    pub fn save_folder_metadata(&self, account_email: &str, folder: &str, last_uid: u32, total_messages: u32) -> Result<()> {
+        // An upsert, so the mod-sequence stored alongside survives
+        self.execute(
+            "INSERT INTO folder_metadata (account_email, folder, last_uid, total_messages, last_sync)
+             VALUES (?1, ?2, ?3, ?4, strftime('%s', 'now'))
+             ON CONFLICT(account_email, folder) DO UPDATE SET
+                 last_uid = excluded.last_uid,
+                 total_messages = excluded.total_messages,
+                 last_sync = excluded.last_sync",
+            params![account_email, folder, last_uid, total_messages],
+        )?;
+        Ok(())
+    }
+
+    /// The UIDVALIDITY and HIGHESTMODSEQ the cached flags of a folder are current to,
+    /// once a CONDSTORE server has reported them
+    pub fn get_modseq(&self, account_email: &str, folder: &str) -> Result<Option<(u32, u64)>> {
+        let state: Option<(u32, i64)> = self
+            .conn
+            .query_row(
+                "SELECT uid_validity, highest_modseq FROM folder_metadata WHERE account_email = ?1 AND folder = ?2",
+                params![account_email, folder],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(state.filter(|(_, modseq)| *modseq > 0).map(|(uid_validity, modseq)| (uid_validity, modseq as u64)))
+    }
+
+    pub fn save_modseq(&self, account_email: &str, folder: &str, uid_validity: u32, highest_modseq: u64) -> Result<()> {
+        self.execute(
+            "INSERT INTO folder_metadata (account_email, folder, uid_validity, highest_modseq) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(account_email, folder) DO UPDATE SET
+                 uid_validity = excluded.uid_validity,
+                 highest_modseq = excluded.highest_modseq",
+            params![account_email, folder, uid_validity, highest_modseq as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Record that an initial sync has stored every message up to `last_uid`
+    pub fn save_sync_progress(&self, account_email: &str, folder: &str, last_uid: u32) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO sync_progress (account_email, folder, last_uid, updated_at)
+             VALUES (?1, ?2, ?3, strftime('%s', 'now'))",
+            params![account_email, folder, last_uid],
+        )?;
+        Ok(())
+    }
+
+    /// The UID an interrupted initial sync of the folder got to, if one is unfinished
+    pub fn get_sync_progress(&self, account_email: &str, folder: &str) -> Result<Option<u32>> {
+        let result = self.conn.query_row(
+            "SELECT last_uid FROM sync_progress WHERE account_email = ?1 AND folder = ?2",
+            params![account_email, folder],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(uid) => Ok(Some(uid)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn clear_sync_progress(&self, account_email: &str, folder: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM sync_progress WHERE account_email = ?1 AND folder = ?2",
+            params![account_email, folder],
+        )?;
+        Ok(())
+    }
+
+    pub fn load_folder_metadata(&self, account_email: &str, folder: &str) -> Result<(u32, u32, i64)> {
+        let result = self.conn.query_row(
             "SELECT last_uid, total_messages, last_sync FROM folder_metadata 
              WHERE account_email = ?1 AND folder = ?2",
             params![account_email, folder],
@@ -287,7 +1242,6 @@ impl EmailDatabase {
         }
     }
 
-    #[allow(dead_code)]
     pub fn get_email_count(&self, account_email: &str, folder: &str) -> Result<usize> {
         let count: i64 = self.conn.query_row(
             "SELECT COUNT(*) FROM emails WHERE account_email = ?1 AND folder = ?2",
@@ -297,22 +1251,126 @@ impl EmailDatabase {
         Ok(count as usize)
     }
 
+    /// Unread messages cached for a folder, as of the last sync
+    pub fn get_unread_count(&self, account_email: &str, folder: &str) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM emails WHERE account_email = ?1 AND folder = ?2 AND seen = 0",
+            params![account_email, folder],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Remove one message (and its attachments) from the cache
+    pub fn delete_email(&self, account_email: &str, folder: &str, uid: u32) -> Result<()> {
+        self.execute(
+            "DELETE FROM attachments WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3",
+            params![account_email, folder, uid],
+        )?;
+        self.execute(
+            "DELETE FROM emails WHERE account_email = ?1 AND folder = ?2 AND uid = ?3",
+            params![account_email, folder, uid],
+        )?;
+        self.drop_unshared_bodies(account_email)?;
+        self.clear_bodies_pending(account_email, folder, &[uid])
+    }
+
+    /// Remove shared bodies no stored message refers to anymore
+    fn drop_unshared_bodies(&self, account_email: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM shared_bodies WHERE account_email = ?1 AND NOT EXISTS (
+                SELECT 1 FROM emails e WHERE e.account_email = shared_bodies.account_email
+                  AND e.content_key = shared_bodies.message_id)",
+            params![account_email],
+        )?;
+        Ok(())
+    }
+
+    /// Whether the message is already stored under another Gmail label with its whole
+    /// content, so the new copy needs no download. Only the Gmail id says that two copies
+    /// are the same message; a matching Message-ID may come with a different body, so
+    /// those copies are always downloaded. So are copies with attachments, which are kept
+    /// per folder.
+    pub fn has_shared_content(&self, account_email: &str, email: &Email) -> Result<bool> {
+        let Some(key) = email.gmail_id.as_deref().map(gmail_key) else {
+            return Ok(false);
+        };
+        Ok(self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM shared_bodies WHERE account_email = ?1 AND message_id = ?2)
+                AND NOT EXISTS (SELECT 1 FROM emails e JOIN attachments a
+                    ON a.account_email = e.account_email AND a.folder = e.folder AND a.email_uid = e.uid
+                    WHERE e.account_email = ?1 AND e.content_key = ?2)",
+            params![account_email, key],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Record that these messages were stored with headers only
+    pub fn mark_bodies_pending(&self, account_email: &str, folder: &str, uids: &[u32]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        for uid in uids {
+            tx.execute(
+                "INSERT OR IGNORE INTO pending_bodies (account_email, folder, uid) VALUES (?1, ?2, ?3)",
+                params![account_email, folder, uid],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn clear_bodies_pending(&self, account_email: &str, folder: &str, uids: &[u32]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        for uid in uids {
+            tx.execute(
+                "DELETE FROM pending_bodies WHERE account_email = ?1 AND folder = ?2 AND uid = ?3",
+                params![account_email, folder, uid],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// UIDs of messages in the folder still without a body, newest first
+    pub fn get_pending_body_uids(&self, account_email: &str, folder: &str, limit: Option<usize>) -> Result<Vec<u32>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.uid FROM pending_bodies p
+             LEFT JOIN emails e ON e.account_email = p.account_email AND e.folder = p.folder AND e.uid = p.uid
+             WHERE p.account_email = ?1 AND p.folder = ?2
+             ORDER BY e.date_received DESC
+             LIMIT ?3",
+        )?;
+        // SQLite treats a negative LIMIT as no limit
+        let limit = limit.map_or(-1, |n| n as i64);
+        let uids = stmt
+            .query_map(params![account_email, folder, limit], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<u32>>>()?;
+        Ok(uids)
+    }
+
+    /// The cached messages with these UIDs, in no particular order
+    pub fn get_emails_by_uids(&self, account_email: &str, folder: &str, uids: &[u32]) -> Result<Vec<Email>> {
+        let uids = serde_json::to_string(uids)?;
+        self.query_emails(
+            account_email,
+            "folder = ?2 AND uid IN (SELECT value FROM json_each(?3))",
+            &[&folder, &uids],
+        )
+    }
+
     #[allow(dead_code)]
     pub fn delete_emails_by_folder(&self, account_email: &str, folder: &str) -> Result<()> {
-        self.conn.execute(
+        self.execute(
             "DELETE FROM emails WHERE account_email = ?1 AND folder = ?2",
             params![account_email, folder],
         )?;
-        Ok(())
+        self.drop_unshared_bodies(account_email)
     }
 
-    #[allow(dead_code)]
     pub fn vacuum(&self) -> Result<()> {
-        self.conn.execute("VACUUM", [])?;
+        self.execute("VACUUM", [])?;
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn get_database_size(&self) -> Result<u64> {
         let size: i64 = self.conn.query_row(
             "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
@@ -324,12 +1382,12 @@ impl EmailDatabase {
 
     // Sync daemon specific methods
     pub fn execute_sql(&self, sql: &str) -> Result<()> {
-        self.conn.execute(sql, [])?;
+        self.execute(sql, [])?;
         Ok(())
     }
 
     pub fn save_sync_state(&self, account_email: &str, folder: &str, last_uid: u32, last_sync: i64) -> Result<()> {
-        self.conn.execute(
+        self.execute(
             "INSERT OR REPLACE INTO sync_state (account_email, folder, last_uid_seen, last_sync_timestamp, sync_in_progress)
              VALUES (?1, ?2, ?3, ?4, FALSE)",
             params![account_email, folder, last_uid, last_sync],
@@ -345,231 +1403,1229 @@ impl EmailDatabase {
             |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         );
 
-        match result {
-            Ok((last_uid, last_sync, in_progress)) => Ok((last_uid, last_sync, in_progress)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok((0, 0, false)),
-            Err(e) => Err(e.into()),
-        }
+        match result {
+            Ok((last_uid, last_sync, in_progress)) => Ok((last_uid, last_sync, in_progress)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok((0, 0, false)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn set_sync_in_progress(&self, account_email: &str, folder: &str, in_progress: bool) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO sync_state (account_email, folder, sync_in_progress, last_uid_seen, last_sync_timestamp)
+             VALUES (?1, ?2, ?3, 
+                     COALESCE((SELECT last_uid_seen FROM sync_state WHERE account_email = ?1 AND folder = ?2), 0),
+                     COALESCE((SELECT last_sync_timestamp FROM sync_state WHERE account_email = ?1 AND folder = ?2), 0))",
+            params![account_email, folder, in_progress],
+        )?;
+        Ok(())
+    }
+
+    pub fn queue_email_operation(&self, account_email: &str, operation_type: &str, email_uid: u32, folder: &str,
+                                target_folder: Option<&str>, subject: Option<&str>) -> Result<()> {
+        self.execute(
+            "INSERT INTO email_operations (account_email, operation_type, email_uid, folder, target_folder, subject, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, strftime('%s', 'now'))",
+            params![account_email, operation_type, email_uid, folder, target_folder, subject],
+        )?;
+        Ok(())
+    }
+
+    /// Operations still to be carried out, oldest first
+    pub fn get_pending_operations(&self) -> Result<Vec<PendingOperation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, account_email, operation_type, email_uid, folder, target_folder,
+                    created_at, attempts, error, retry_at, subject
+             FROM email_operations
+             WHERE processed = FALSE
+             ORDER BY created_at ASC, id ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(PendingOperation {
+                id: row.get(0)?,
+                account_email: row.get(1)?,
+                operation_type: row.get(2)?,
+                email_uid: row.get(3)?,
+                folder: row.get(4)?,
+                target_folder: row.get(5)?,
+                created_at: row.get(6)?,
+                attempts: row.get(7)?,
+                error: row.get(8)?,
+                retry_at: row.get(9)?,
+                subject: row.get(10)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn add_reminder(&self, reminder: &Reminder) -> Result<i64> {
+        self.execute(
+            "INSERT INTO reminders (account_email, folder, uid, message_id, subject, sender, remind_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                reminder.account_email,
+                reminder.folder,
+                reminder.uid,
+                reminder.message_id,
+                reminder.subject,
+                reminder.sender,
+                reminder.remind_at
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Every reminder not dismissed yet, those that have gone off first, then by when
+    /// they are due
+    pub fn get_reminders(&self) -> Result<Vec<Reminder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, account_email, folder, uid, message_id, subject, sender, remind_at, fired
+             FROM reminders ORDER BY fired DESC, remind_at ASC, id ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Reminder {
+                id: row.get(0)?,
+                account_email: row.get(1)?,
+                folder: row.get(2)?,
+                uid: row.get(3)?,
+                message_id: row.get(4)?,
+                subject: row.get(5)?,
+                sender: row.get(6)?,
+                remind_at: row.get(7)?,
+                fired: row.get(8)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Mark the reminders due at `now` as gone off and return them
+    pub fn fire_due_reminders(&self, now: i64) -> Result<Vec<Reminder>> {
+        let due: Vec<Reminder> = self
+            .get_reminders()?
+            .into_iter()
+            .filter(|reminder| !reminder.fired && reminder.remind_at <= now)
+            .collect();
+        for reminder in &due {
+            self.execute("UPDATE reminders SET fired = TRUE WHERE id = ?1", params![reminder.id])?;
+        }
+        Ok(due)
+    }
+
+    pub fn dismiss_reminder(&self, reminder_id: i64) -> Result<()> {
+        self.execute("DELETE FROM reminders WHERE id = ?1", params![reminder_id])?;
+        Ok(())
+    }
+
+    /// Operations of an account whose turn has come at `now`, oldest first
+    pub fn get_due_operations(&self, account_email: &str, now: i64) -> Result<Vec<PendingOperation>> {
+        Ok(self
+            .get_pending_operations()?
+            .into_iter()
+            .filter(|op| op.account_email == account_email && op.retry_at <= now)
+            .collect())
+    }
+
+    pub fn mark_operation_processed(&self, operation_id: i64) -> Result<()> {
+        self.execute(
+            "UPDATE email_operations SET processed = TRUE, error = NULL WHERE id = ?1",
+            params![operation_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record why an operation failed and put off the next attempt until `retry_at`
+    pub fn mark_operation_failed(&self, operation_id: i64, error: &str, retry_at: i64) -> Result<()> {
+        self.execute(
+            "UPDATE email_operations SET error = ?1, attempts = attempts + 1, retry_at = ?2 WHERE id = ?3",
+            params![error, retry_at, operation_id],
+        )?;
+        Ok(())
+    }
+
+    /// Make an operation due straight away
+    pub fn retry_operation(&self, operation_id: i64) -> Result<()> {
+        self.execute("UPDATE email_operations SET retry_at = 0 WHERE id = ?1", params![operation_id])?;
+        Ok(())
+    }
+
+    /// Drop an operation that has not been carried out yet
+    pub fn cancel_operation(&self, operation_id: i64) -> Result<()> {
+        self.execute(
+            "DELETE FROM email_operations WHERE id = ?1 AND processed = FALSE",
+            params![operation_id],
+        )?;
+        Ok(())
+    }
+
+    /// Change the folder a queued move goes to; it is tried again straight away
+    pub fn set_operation_target(&self, operation_id: i64, target_folder: &str) -> Result<()> {
+        self.execute(
+            "UPDATE email_operations SET target_folder = ?1, retry_at = 0 WHERE id = ?2",
+            params![target_folder, operation_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_emails_paginated(&self, account_email: &str, folder: &str, 
+                               offset: usize, limit: usize) -> Result<Vec<Email>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT uid, message_id, subject, from_addresses, to_addresses, 
+                    cc_addresses, bcc_addresses, date_received, {BODY_TEXT}, {BODY_HTML},
+                    flags, headers, seen, snippet
+             FROM emails 
+             WHERE account_email = ?1 AND folder = ?2 
+             ORDER BY date_received DESC
+             LIMIT ?3 OFFSET ?4"
+        ))?;
+
+        let email_rows = stmt.query_map(params![account_email, folder, limit, offset], |row| {
+            Ok((
+                row.get::<_, u32>(0)?,       // uid
+                row.get::<_, Option<String>>(1)?, // message_id
+                row.get::<_, String>(2)?,    // subject
+                row.get::<_, String>(3)?,    // from_addresses
+                row.get::<_, String>(4)?,    // to_addresses
+                row.get::<_, String>(5)?,    // cc_addresses
+                row.get::<_, String>(6)?,    // bcc_addresses
+                row.get::<_, i64>(7)?,       // date_received
+                row.get::<_, Option<String>>(8)?, // body_text
+                row.get::<_, Option<String>>(9)?, // body_html
+                row.get::<_, String>(10)?,   // flags
+                row.get::<_, String>(11)?,   // headers
+                row.get::<_, bool>(12)?,     // seen
+                row.get::<_, Option<String>>(13)?, // snippet
+            ))
+        })?;
+
+        let mut emails = Vec::new();
+        for row_result in email_rows {
+            let (uid, _message_id, subject, from_json, to_json, cc_json, bcc_json,
+                 date_timestamp, body_text, body_html, flags_json, headers_json, seen, snippet) = row_result?;
+
+            // Load attachments for this email
+            let mut attachment_stmt = self.conn.prepare(
+                "SELECT filename, content_type, data, path, size FROM attachments 
+                 WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3"
+            )?;
+            
+            let attachment_rows = attachment_stmt.query_map(params![account_email, folder, uid], |row| {
+                Ok(crate::email::EmailAttachment {
+                    filename: row.get(0)?,
+                    content_type: row.get(1)?,
+                    data: row.get(2)?,
+                    path: row.get::<_, Option<String>>(3)?.map(std::path::PathBuf::from),
+                    size: row.get::<_, i64>(4)?.max(0) as usize,
+                })
+            })?;
+
+            let mut attachments = Vec::new();
+            for attachment_result in attachment_rows {
+                attachments.push(attachment_result?);
+            }
+
+            let email = Email {
+                id: uid.to_string(), // Convert UID back to string for Email struct
+                subject,
+                from: serde_json::from_str(&from_json)?,
+                to: serde_json::from_str(&to_json)?,
+                cc: serde_json::from_str(&cc_json)?,
+                bcc: serde_json::from_str(&bcc_json)?,
+                date: chrono::DateTime::from_timestamp(date_timestamp, 0)
+                    .unwrap_or_else(|| chrono::Local::now().into())
+                    .with_timezone(&chrono::Local),
+                body_text,
+                body_html,
+                attachments,
+                flags: serde_json::from_str(&flags_json)?,
+                headers: serde_json::from_str(&headers_json)?,
+                seen,
+                folder: folder.to_string(),
+                snippet,
+                gmail_id: None,
+            };
+
+            emails.push(email);
+        }
+
+        Ok(emails)
+    }
+
+    pub fn get_all_emails(&self, account_email: &str, folder: &str) -> Result<Vec<Email>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT uid, message_id, subject, from_addresses, to_addresses, 
+                    cc_addresses, bcc_addresses, date_received, {BODY_TEXT}, {BODY_HTML},
+                    flags, headers, seen, snippet
+             FROM emails 
+             WHERE account_email = ?1 AND folder = ?2 
+             ORDER BY date_received DESC"
+        ))?;
+
+        let email_rows = stmt.query_map(params![account_email, folder], |row| {
+            Ok((
+                row.get::<_, u32>(0)?,       // uid
+                row.get::<_, Option<String>>(1)?, // message_id
+                row.get::<_, String>(2)?,    // subject
+                row.get::<_, String>(3)?,    // from_addresses
+                row.get::<_, String>(4)?,    // to_addresses
+                row.get::<_, String>(5)?,    // cc_addresses
+                row.get::<_, String>(6)?,    // bcc_addresses
+                row.get::<_, i64>(7)?,       // date_received
+                row.get::<_, Option<String>>(8)?, // body_text
+                row.get::<_, Option<String>>(9)?, // body_html
+                row.get::<_, String>(10)?,   // flags
+                row.get::<_, String>(11)?,   // headers
+                row.get::<_, bool>(12)?,     // seen
+                row.get::<_, Option<String>>(13)?, // snippet
+            ))
+        })?;
+
+        let mut emails = Vec::new();
+        for row_result in email_rows {
+            let (uid, _message_id, subject, from_json, to_json, cc_json, bcc_json,
+                 date_timestamp, body_text, body_html, flags_json, headers_json, seen, snippet) = row_result?;
+
+            // Load attachments for this email
+            let mut attachment_stmt = self.conn.prepare(
+                "SELECT filename, content_type, data, path, size FROM attachments 
+                 WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3"
+            )?;
+            
+            let attachment_rows = attachment_stmt.query_map(params![account_email, folder, uid], |row| {
+                Ok(crate::email::EmailAttachment {
+                    filename: row.get(0)?,
+                    content_type: row.get(1)?,
+                    data: row.get(2)?,
+                    path: row.get::<_, Option<String>>(3)?.map(std::path::PathBuf::from),
+                    size: row.get::<_, i64>(4)?.max(0) as usize,
+                })
+            })?;
+
+            let mut attachments = Vec::new();
+            for attachment_result in attachment_rows {
+                attachments.push(attachment_result?);
+            }
+
+            let email = Email {
+                id: uid.to_string(), // Convert UID back to string for Email struct
+                subject,
+                from: serde_json::from_str(&from_json)?,
+                to: serde_json::from_str(&to_json)?,
+                cc: serde_json::from_str(&cc_json)?,
+                bcc: serde_json::from_str(&bcc_json)?,
+                date: chrono::DateTime::from_timestamp(date_timestamp, 0)
+                    .unwrap_or_else(|| chrono::Local::now().into())
+                    .with_timezone(&chrono::Local),
+                body_text,
+                body_html,
+                attachments,
+                flags: serde_json::from_str(&flags_json)?,
+                headers: serde_json::from_str(&headers_json)?,
+                seen,
+                folder: folder.to_string(),
+                snippet,
+                gmail_id: None,
+            };
+
+            emails.push(email);
+        }
+
+        Ok(emails)
+    }
+
+    /// Load emails of an account from any folder. `filter` is appended after the account
+    /// condition (e.g. `"seen = 0 ORDER BY date_received DESC"`) and may use `?2`, `?3`, ...
+    /// for the values in `extra_params`.
+    fn query_emails(&self, account_email: &str, filter: &str, extra_params: &[&dyn rusqlite::ToSql]) -> Result<Vec<Email>> {
+        let mut emails = Vec::new();
+        self.each_email(account_email, filter, extra_params, |email| {
+            emails.push(email);
+            Ok(())
+        })?;
+        Ok(emails)
+    }
+
+    /// Every cached message of a folder dated at or after `after` and before `before`
+    /// (Unix timestamps, unbounded when `None`), oldest first, handed to `f` one at a time
+    /// so a large folder never has to fit in memory
+    pub fn for_each_email_in_folder(
+        &self,
+        account_email: &str,
+        folder: &str,
+        after: Option<i64>,
+        before: Option<i64>,
+        f: impl FnMut(Email) -> Result<()>,
+    ) -> Result<()> {
+        self.each_email(
+            account_email,
+            "folder = ?2 AND (?3 IS NULL OR date_received >= ?3) AND (?4 IS NULL OR date_received < ?4)
+             ORDER BY date_received ASC, uid ASC",
+            &[&folder, &after, &before],
+            f,
+        )
+    }
+
+    /// Like `query_emails`, but each message is handed to `f` as it is read
+    fn each_email(
+        &self,
+        account_email: &str,
+        filter: &str,
+        extra_params: &[&dyn rusqlite::ToSql],
+        mut f: impl FnMut(Email) -> Result<()>,
+    ) -> Result<()> {
+        let sql = format!(
+            "SELECT uid, folder, subject, from_addresses, to_addresses,
+                    cc_addresses, bcc_addresses, date_received, {BODY_TEXT}, {BODY_HTML},
+                    flags, headers, seen, snippet
+             FROM emails
+             WHERE account_email = ?1 AND {filter}"
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+
+        let mut query_params: Vec<&dyn rusqlite::ToSql> = vec![&account_email];
+        query_params.extend_from_slice(extra_params);
+
+        let email_rows = stmt.query_map(query_params.as_slice(), |row| {
+            Ok((
+                row.get::<_, u32>(0)?,       // uid
+                row.get::<_, String>(1)?,    // folder
+                row.get::<_, String>(2)?,    // subject
+                row.get::<_, String>(3)?,    // from_addresses
+                row.get::<_, String>(4)?,    // to_addresses
+                row.get::<_, Option<String>>(5)?, // cc_addresses
+                row.get::<_, Option<String>>(6)?, // bcc_addresses
+                row.get::<_, i64>(7)?,       // date_received
+                row.get::<_, Option<String>>(8)?, // body_text
+                row.get::<_, Option<String>>(9)?, // body_html
+                row.get::<_, String>(10)?,   // flags
+                row.get::<_, String>(11)?,   // headers
+                row.get::<_, bool>(12)?,     // seen
+                row.get::<_, Option<String>>(13)?, // snippet
+            ))
+        })?;
+
+        for row_result in email_rows {
+            let (uid, folder, subject, from_json, to_json, cc_json, bcc_json,
+                 date_timestamp, body_text, body_html, flags_json, headers_json, seen, snippet) = row_result?;
+
+            let attachments = self.load_attachments(account_email, &folder, uid)?;
+
+            f(Email {
+                id: uid.to_string(),
+                subject,
+                from: serde_json::from_str(&from_json)?,
+                to: serde_json::from_str(&to_json)?,
+                cc: cc_json.map(|json| serde_json::from_str(&json)).transpose()?.unwrap_or_default(),
+                bcc: bcc_json.map(|json| serde_json::from_str(&json)).transpose()?.unwrap_or_default(),
+                date: chrono::DateTime::from_timestamp(date_timestamp, 0)
+                    .unwrap_or_else(|| chrono::Local::now().into())
+                    .with_timezone(&chrono::Local),
+                body_text,
+                body_html,
+                attachments,
+                flags: serde_json::from_str(&flags_json)?,
+                headers: serde_json::from_str(&headers_json)?,
+                seen,
+                folder,
+                snippet,
+                gmail_id: None,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn load_attachments(&self, account_email: &str, folder: &str, uid: u32) -> Result<Vec<EmailAttachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT filename, content_type, data, path, size FROM attachments
+             WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3"
+        )?;
+        let rows = stmt.query_map(params![account_email, folder, uid], |row| {
+            Ok(EmailAttachment {
+                filename: row.get(0)?,
+                content_type: row.get(1)?,
+                data: row.get(2)?,
+                path: row.get::<_, Option<String>>(3)?.map(std::path::PathBuf::from),
+                size: row.get::<_, i64>(4)?.max(0) as usize,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Everyone the account has exchanged mail with, most recent activity first
+    pub fn get_contacts(&self, account_email: &str) -> Result<Vec<ContactSummary>> {
+        let mut stmt = self.conn.prepare(
+            // Every address of From, To and Cc, then grouped once so a message naming the
+            // same person in two fields counts once
+            "WITH participants AS (
+                 SELECT e.folder || '/' || e.uid AS message, e.date_received, a.value
+                 FROM emails e, json_each(e.from_addresses) a
+                 WHERE e.account_email = ?1
+                 UNION ALL
+                 SELECT e.folder || '/' || e.uid, e.date_received, a.value
+                 FROM emails e, json_each(e.to_addresses) a
+                 WHERE e.account_email = ?1
+                 UNION ALL
+                 SELECT e.folder || '/' || e.uid, e.date_received, a.value
+                 FROM emails e, json_each(COALESCE(e.cc_addresses, '[]')) a
+                 WHERE e.account_email = ?1
+             )
+             SELECT lower(json_extract(value, '$.address')) AS address,
+                    MAX(json_extract(value, '$.name')),
+                    MAX(date_received),
+                    COUNT(DISTINCT message)
+             FROM participants
+             GROUP BY address",
+        )?;
+
+        let rows = stmt.query_map(params![account_email], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+
+        let own_address = account_email.to_lowercase();
+        let mut contacts: std::collections::HashMap<String, ContactSummary> = std::collections::HashMap::new();
+        for row in rows {
+            let (address, name, last_date, count) = row?;
+            let Some(address) = address.filter(|a| !a.is_empty() && *a != own_address) else {
+                continue;
+            };
+            let entry = contacts.entry(address.clone()).or_insert_with(|| ContactSummary {
+                address,
+                name: None,
+                last_activity: last_date,
+                message_count: 0,
+                saved: false,
+            });
+            entry.message_count += count as usize;
+            entry.last_activity = entry.last_activity.max(last_date);
+            if entry.name.is_none() {
+                entry.name = name.filter(|n| !n.is_empty());
+            }
+        }
+
+        // The address book's names win over those seen in mail; people never written
+        // with sort last
+        for saved in self.get_address_book(account_email)? {
+            let entry = contacts.entry(saved.address.clone()).or_insert_with(|| ContactSummary {
+                address: saved.address.clone(),
+                name: None,
+                last_activity: 0,
+                message_count: 0,
+                saved: true,
+            });
+            entry.saved = true;
+            entry.name = saved.name.or(entry.name.take());
+        }
+
+        let mut contacts: Vec<ContactSummary> = contacts.into_values().collect();
+        contacts.sort_by_key(|contact| std::cmp::Reverse(contact.last_activity));
+        Ok(contacts)
+    }
+
+    /// Save a person to the address book. Saving an address again updates it, keeping
+    /// what is known of it where `contact` has nothing.
+    pub fn save_contact(&self, account_email: &str, contact: &SavedContact) -> Result<()> {
+        self.execute(
+            "INSERT INTO address_book (account_email, address, name, organization, phone, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(account_email, address) DO UPDATE SET
+                 name = COALESCE(excluded.name, name),
+                 organization = COALESCE(excluded.organization, organization),
+                 phone = COALESCE(excluded.phone, phone)",
+            params![
+                account_email,
+                contact.address.to_lowercase(),
+                contact.name,
+                contact.organization,
+                contact.phone,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a person from the address book
+    pub fn delete_contact(&self, account_email: &str, address: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM address_book WHERE account_email = ?1 AND address = ?2",
+            params![account_email, address.to_lowercase()],
+        )?;
+        Ok(())
+    }
+
+    /// Everyone in the account's address book, by address
+    pub fn get_address_book(&self, account_email: &str) -> Result<Vec<SavedContact>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT address, name, organization, phone FROM address_book WHERE account_email = ?1 ORDER BY address",
+        )?;
+        let rows = stmt.query_map(params![account_email], |row| {
+            Ok(SavedContact { address: row.get(0)?, name: row.get(1)?, organization: row.get(2)?, phone: row.get(3)? })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Display names seen with each address, from incoming From headers and the
+    /// recipients of mail the account sent, as `(lowercase address, name, written_to)`
+    pub fn get_named_addresses(&self, account_email: &str) -> Result<Vec<(String, Option<String>, bool)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT lower(json_extract(a.value, '$.address')), json_extract(a.value, '$.name'), 1
+             FROM emails e, json_each(e.to_addresses) a
+             WHERE e.account_email = ?1 AND EXISTS (
+                 SELECT 1 FROM json_each(e.from_addresses) f
+                 WHERE lower(json_extract(f.value, '$.address')) = lower(?1))
+             UNION
+             SELECT lower(json_extract(a.value, '$.address')), json_extract(a.value, '$.name'), 1
+             FROM emails e, json_each(e.cc_addresses) a
+             WHERE e.account_email = ?1 AND EXISTS (
+                 SELECT 1 FROM json_each(e.from_addresses) f
+                 WHERE lower(json_extract(f.value, '$.address')) = lower(?1))
+             UNION
+             SELECT lower(json_extract(a.value, '$.address')), json_extract(a.value, '$.name'), 0
+             FROM emails e, json_each(e.from_addresses) a
+             WHERE e.account_email = ?1",
+        )?;
+        let rows = stmt.query_map(params![account_email], |row| {
+            Ok((row.get::<_, Option<String>>(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        let mut named = Vec::new();
+        for row in rows {
+            let (address, name, written_to) = row?;
+            if let Some(address) = address.filter(|a| !a.is_empty()) {
+                named.push((address, name, written_to));
+            }
+        }
+        Ok(named)
+    }
+
+    /// All mail sent to or received from `address`, in any folder, oldest first
+    pub fn get_emails_with_contact(&self, account_email: &str, address: &str) -> Result<Vec<Email>> {
+        let address = address.to_lowercase();
+        self.query_emails(
+            account_email,
+            "(EXISTS (SELECT 1 FROM json_each(from_addresses) WHERE lower(json_extract(value, '$.address')) = ?2)
+               OR EXISTS (SELECT 1 FROM json_each(to_addresses) WHERE lower(json_extract(value, '$.address')) = ?2)
+               OR EXISTS (SELECT 1 FROM json_each(COALESCE(cc_addresses, '[]')) WHERE lower(json_extract(value, '$.address')) = ?2))
+             ORDER BY date_received ASC",
+            &[&address],
+        )
+    }
+
+    /// Mail of the account in any folder carrying the IMAP flag or keyword, oldest first
+    pub fn get_emails_with_flag(&self, account_email: &str, flag: &str) -> Result<Vec<Email>> {
+        self.query_emails(
+            account_email,
+            "EXISTS (SELECT 1 FROM json_each(flags) WHERE value = ?2) ORDER BY date_received ASC",
+            &[&flag],
+        )
+    }
+
+    /// A stored message of the account by its Message-ID (with or without angle brackets)
+    pub fn find_email_by_message_id(&self, account_email: &str, message_id: &str) -> Result<Option<Email>> {
+        let message_id = message_id.trim().trim_start_matches('<').trim_end_matches('>').to_string();
+        let emails = self.query_emails(
+            account_email,
+            "trim(json_extract(headers, '$.\"Message-ID\"'), '<> ') = ?2 LIMIT 1",
+            &[&message_id],
+        )?;
+        Ok(emails.into_iter().next())
+    }
+
+    /// The latest message the account sent to `recipient` at or before `before` (Unix time)
+    pub fn find_latest_sent_to(&self, account_email: &str, recipient: &str, before: i64) -> Result<Option<Email>> {
+        let emails = self.query_emails(
+            account_email,
+            "lower(json_extract(from_addresses, '$[0].address')) = lower(?1)
+               AND (EXISTS (SELECT 1 FROM json_each(to_addresses) WHERE lower(json_extract(value, '$.address')) = lower(?2))
+                 OR EXISTS (SELECT 1 FROM json_each(COALESCE(cc_addresses, '[]')) WHERE lower(json_extract(value, '$.address')) = lower(?2)))
+               AND date_received <= ?3
+             ORDER BY date_received DESC LIMIT 1",
+            &[&recipient, &before],
+        )?;
+        Ok(emails.into_iter().next())
+    }
+
+    pub fn record_bounce(&self, account_email: &str, record: &BounceRecord) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO bounces
+                (account_email, message_id, recipient, status, reason, bounce_folder, bounce_uid, detected_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                account_email,
+                record.message_id,
+                record.recipient,
+                record.status,
+                record.reason,
+                record.bounce_folder,
+                record.bounce_uid,
+                record.detected_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Every recorded bounce of the account, oldest first
+    pub fn get_bounces(&self, account_email: &str) -> Result<Vec<BounceRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id, recipient, status, reason, bounce_folder, bounce_uid, detected_at
+             FROM bounces WHERE account_email = ?1 ORDER BY detected_at",
+        )?;
+        let rows = stmt.query_map(params![account_email], |row| {
+            Ok(BounceRecord {
+                message_id: row.get(0)?,
+                recipient: row.get(1)?,
+                status: row.get(2)?,
+                reason: row.get(3)?,
+                bounce_folder: row.get(4)?,
+                bounce_uid: row.get(5)?,
+                detected_at: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Add an address to the suppression list, or update when it last bounced
+    pub fn suppress_address(&self, account_email: &str, suppressed: &SuppressedAddress) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO suppressed_addresses (account_email, address, status, reason, bounced_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                account_email,
+                suppressed.address.to_lowercase(),
+                suppressed.status,
+                suppressed.reason,
+                suppressed.bounced_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Take an address off the suppression list
+    pub fn unsuppress_address(&self, account_email: &str, address: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM suppressed_addresses WHERE account_email = ?1 AND address = ?2",
+            params![account_email, address.to_lowercase()],
+        )?;
+        Ok(())
+    }
+
+    /// The account's suppression list, by address
+    pub fn get_suppressed_addresses(&self, account_email: &str) -> Result<Vec<SuppressedAddress>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT address, status, reason, bounced_at FROM suppressed_addresses WHERE account_email = ?1 ORDER BY address",
+        )?;
+        let rows = stmt.query_map(params![account_email], |row| {
+            Ok(SuppressedAddress { address: row.get(0)?, status: row.get(1)?, reason: row.get(2)?, bounced_at: row.get(3)? })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// How messages from `address` are shown, if that was ever changed for them
+    pub fn get_sender_preferences(&self, account_email: &str, address: &str) -> Result<Option<SenderPreferences>> {
+        let preferences = self
+            .conn
+            .query_row(
+                "SELECT remote_content, expand_quotes, html FROM sender_preferences WHERE account_email = ?1 AND address = ?2",
+                params![account_email, address.to_lowercase()],
+                |row| Ok(SenderPreferences { remote_content: row.get(0)?, expand_quotes: row.get(1)?, html: row.get(2)? }),
+            )
+            .optional()?;
+        Ok(preferences)
+    }
+
+    /// Remember how messages from `address` are shown. Preferences back at the defaults
+    /// are forgotten.
+    pub fn set_sender_preferences(&self, account_email: &str, address: &str, preferences: &SenderPreferences) -> Result<()> {
+        if *preferences == SenderPreferences::default() {
+            self.execute(
+                "DELETE FROM sender_preferences WHERE account_email = ?1 AND address = ?2",
+                params![account_email, address.to_lowercase()],
+            )?;
+            return Ok(());
+        }
+        self.execute(
+            "INSERT OR REPLACE INTO sender_preferences (account_email, address, remote_content, expand_quotes, html, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                account_email,
+                address.to_lowercase(),
+                preferences.remote_content,
+                preferences.expand_quotes,
+                preferences.html,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Mute a conversation, or change whether it is archived if it already is muted
+    pub fn mute_thread(&self, account_email: &str, thread: &crate::mute::MutedThread) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO muted_threads (account_email, root, subject, archive, muted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![account_email, thread.root, thread.subject, thread.archive, thread.muted_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn unmute_thread(&self, account_email: &str, root: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM muted_threads WHERE account_email = ?1 AND root = ?2",
+            params![account_email, root],
+        )?;
+        Ok(())
+    }
+
+    /// The account's muted conversations, most recently muted first
+    pub fn get_muted_threads(&self, account_email: &str) -> Result<Vec<crate::mute::MutedThread>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT root, subject, archive, muted_at FROM muted_threads
+             WHERE account_email = ?1 ORDER BY muted_at DESC",
+        )?;
+        let rows = stmt.query_map(params![account_email], |row| {
+            Ok(crate::mute::MutedThread {
+                root: row.get(0)?,
+                subject: row.get(1)?,
+                archive: row.get(2)?,
+                muted_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Keep a copy of a message deleted from the server
+    pub fn keep_deleted(&self, account_email: &str, message: &DeletedMessage, raw: &[u8]) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO deleted_messages
+                (account_email, folder, uid, subject, sender, flags, deleted_at, elsewhere, raw)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                account_email,
+                message.folder,
+                message.uid,
+                message.subject,
+                message.sender,
+                serde_json::to_string(&message.flags)?,
+                message.deleted_at,
+                message.elsewhere,
+                raw
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The account's recently deleted messages, most recently deleted first
+    pub fn get_deleted_messages(&self, account_email: &str) -> Result<Vec<DeletedMessage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, folder, uid, subject, sender, flags, deleted_at, elsewhere FROM deleted_messages
+             WHERE account_email = ?1 ORDER BY deleted_at DESC, id DESC",
+        )?;
+        let rows = stmt.query_map(params![account_email], |row| {
+            Ok(DeletedMessage {
+                id: row.get(0)?,
+                folder: row.get(1)?,
+                uid: row.get(2)?,
+                subject: row.get(3)?,
+                sender: row.get(4)?,
+                flags: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+                deleted_at: row.get(6)?,
+                elsewhere: row.get(7)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// The raw content of a kept message
+    pub fn get_deleted_raw(&self, id: i64) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .conn
+            .query_row("SELECT raw FROM deleted_messages WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?)
+    }
+
+    pub fn forget_deleted(&self, id: i64) -> Result<()> {
+        self.execute("DELETE FROM deleted_messages WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Drop kept messages deleted before `before`; returns how many were dropped
+    pub fn purge_deleted(&self, before: i64) -> Result<usize> {
+        Ok(self.execute("DELETE FROM deleted_messages WHERE deleted_at < ?1", params![before])?)
+    }
+
+    /// UIDs of the messages cached for a folder
+    pub fn get_uids(&self, account_email: &str, folder: &str) -> Result<Vec<u32>> {
+        let mut stmt = self.conn.prepare("SELECT uid FROM emails WHERE account_email = ?1 AND folder = ?2 ORDER BY uid")?;
+        let rows = stmt.query_map(params![account_email, folder], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn set_vip(&self, account_email: &str, address: &str) -> Result<()> {
+        self.execute(
+            "INSERT OR IGNORE INTO vip_contacts (account_email, address, added_at) VALUES (?1, ?2, ?3)",
+            params![account_email, address.to_lowercase(), chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    pub fn unset_vip(&self, account_email: &str, address: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM vip_contacts WHERE account_email = ?1 AND address = ?2",
+            params![account_email, address.to_lowercase()],
+        )?;
+        Ok(())
+    }
+
+    /// The Gmail history ID the cached labels are current to
+    pub fn gmail_history_id(&self, account_email: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT history_id FROM gmail_history WHERE account_email = ?1", params![account_email], |row| row.get(0))
+            .optional()?)
+    }
+
+    pub fn set_gmail_history_id(&self, account_email: &str, history_id: &str) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO gmail_history (account_email, history_id) VALUES (?1, ?2)",
+            params![account_email, history_id],
+        )?;
+        Ok(())
+    }
+
+    /// Start over when the history is too old to catch up with: every label is listed
+    /// again. UIDs already given stay with their messages.
+    pub fn reset_gmail_history(&self, account_email: &str) -> Result<()> {
+        self.execute("DELETE FROM gmail_history WHERE account_email = ?1", params![account_email])?;
+        self.execute("UPDATE gmail_labels SET next_uid = -next_uid WHERE account_email = ?1 AND next_uid > 0", params![account_email])?;
+        Ok(())
+    }
+
+    /// Whether the messages of a label have been listed and are kept up to date
+    pub fn gmail_label_listed(&self, account_email: &str, label_id: &str) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM gmail_labels WHERE account_email = ?1 AND label_id = ?2 AND next_uid > 0)",
+            params![account_email, label_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// UIDs and Gmail message IDs of a label's messages, by UID
+    pub fn gmail_uids(&self, account_email: &str, label_id: &str) -> Result<Vec<(u32, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT uid, message_id FROM gmail_uids WHERE account_email = ?1 AND label_id = ?2 ORDER BY uid",
+        )?;
+        let rows = stmt.query_map(params![account_email, label_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// The Gmail message ID behind a UID of a label
+    pub fn gmail_message_id(&self, account_email: &str, label_id: &str, uid: u32) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT message_id FROM gmail_uids WHERE account_email = ?1 AND label_id = ?2 AND uid = ?3",
+                params![account_email, label_id, uid],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Cached labels of a Gmail message
+    pub fn gmail_labels(&self, account_email: &str, message_id: &str) -> Result<Vec<String>> {
+        let labels: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT label_ids FROM gmail_messages WHERE account_email = ?1 AND message_id = ?2",
+                params![account_email, message_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(labels.map(|json| serde_json::from_str(&json)).transpose()?.unwrap_or_default())
+    }
+
+    /// Record the current labels of Gmail messages, giving each a UID in the listed labels
+    /// it joined and dropping it from those it left. An empty label list forgets a message
+    /// that was deleted.
+    pub fn set_gmail_labels(&self, account_email: &str, messages: &[(String, Vec<String>)]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        let listed: Vec<String> = {
+            let mut stmt = tx.prepare("SELECT label_id FROM gmail_labels WHERE account_email = ?1 AND next_uid > 0")?;
+            let rows = stmt.query_map(params![account_email], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for (message_id, labels) in messages {
+            if labels.is_empty() {
+                tx.execute("DELETE FROM gmail_messages WHERE account_email = ?1 AND message_id = ?2", params![account_email, message_id])?;
+            } else {
+                tx.execute(
+                    "INSERT OR REPLACE INTO gmail_messages (account_email, message_id, label_ids) VALUES (?1, ?2, ?3)",
+                    params![account_email, message_id, serde_json::to_string(labels)?],
+                )?;
+            }
+            for label in &listed {
+                if labels.contains(label) {
+                    add_gmail_uid(&tx, account_email, label, message_id)?;
+                } else {
+                    tx.execute(
+                        "DELETE FROM gmail_uids WHERE account_email = ?1 AND label_id = ?2 AND message_id = ?3",
+                        params![account_email, label, message_id],
+                    )?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store a full listing of a label, newest message first as Gmail lists them: messages
+    /// not seen before get UIDs, oldest first, and ones no longer in the label lose theirs.
+    /// The label is kept up to date from then on.
+    pub fn set_gmail_label_listing(&self, account_email: &str, label_id: &str, message_ids: &[String]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        // A label listed before keeps counting from where it was
+        tx.execute(
+            "INSERT INTO gmail_labels (account_email, label_id, next_uid) VALUES (?1, ?2, 1)
+             ON CONFLICT(account_email, label_id) DO UPDATE SET next_uid = abs(next_uid)",
+            params![account_email, label_id],
+        )?;
+        let ids = serde_json::to_string(message_ids)?;
+        tx.execute(
+            "DELETE FROM gmail_uids WHERE account_email = ?1 AND label_id = ?2
+             AND message_id NOT IN (SELECT value FROM json_each(?3))",
+            params![account_email, label_id, ids],
+        )?;
+        for message_id in message_ids.iter().rev() {
+            add_gmail_uid(&tx, account_email, label_id, message_id)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store the current listing of an Exchange folder, oldest item first: new items get
+    /// UIDs and ones no longer in the folder lose theirs. Returns the UIDs of the folder.
+    pub fn set_exchange_items(&self, account_email: &str, folder: &str, item_ids: &[String]) -> Result<Vec<u32>> {
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "DELETE FROM exchange_items WHERE account_email = ?1 AND folder = ?2
+             AND item_id NOT IN (SELECT value FROM json_each(?3))",
+            params![account_email, folder, serde_json::to_string(item_ids)?],
+        )?;
+        for item_id in item_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO exchange_items (account_email, folder, item_id) VALUES (?1, ?2, ?3)",
+                params![account_email, folder, item_id],
+            )?;
+        }
+        let uids = {
+            let mut stmt = tx.prepare("SELECT uid FROM exchange_items WHERE account_email = ?1 AND folder = ?2 ORDER BY uid")?;
+            let rows = stmt.query_map(params![account_email, folder], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<Vec<u32>>>()?
+        };
+        tx.commit()?;
+        Ok(uids)
+    }
+
+    /// The EWS item ID behind a UID of an Exchange folder
+    pub fn exchange_item_id(&self, account_email: &str, folder: &str, uid: u32) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT item_id FROM exchange_items WHERE account_email = ?1 AND folder = ?2 AND uid = ?3",
+                params![account_email, folder, uid],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Drop the UID of an item deleted or moved out of an Exchange folder
+    pub fn forget_exchange_item(&self, account_email: &str, folder: &str, uid: u32) -> Result<()> {
+        self.execute(
+            "DELETE FROM exchange_items WHERE account_email = ?1 AND folder = ?2 AND uid = ?3",
+            params![account_email, folder, uid],
+        )?;
+        Ok(())
+    }
+
+    /// The tags of the account's messages, by Message-ID (without angle brackets)
+    pub fn get_tags(&self, account_email: &str) -> Result<HashMap<String, Vec<String>>> {
+        let mut stmt = self.conn.prepare("SELECT message_id, tag FROM tags WHERE account_email = ?1 ORDER BY tag")?;
+        let rows = stmt.query_map(params![account_email], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (message_id, tag) = row?;
+            tags.entry(message_id).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+
+    /// Replace the tags of a message
+    pub fn set_tags(&self, account_email: &str, message_id: &str, tags: &[String]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        tx.execute("DELETE FROM tags WHERE account_email = ?1 AND message_id = ?2", params![account_email, message_id])?;
+        for tag in tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO tags (account_email, message_id, tag) VALUES (?1, ?2, ?3)",
+                params![account_email, message_id, tag],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Lowercase addresses of the account's VIP senders
+    pub fn get_vips(&self, account_email: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT address FROM vip_contacts WHERE account_email = ?1 ORDER BY address")?;
+        let rows = stmt.query_map(params![account_email], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Mail of the account in any folder sent by a VIP, newest first
+    pub fn get_emails_from_vips(&self, account_email: &str) -> Result<Vec<Email>> {
+        self.query_emails(
+            account_email,
+            "EXISTS (SELECT 1 FROM json_each(from_addresses) JOIN vip_contacts v
+                     ON v.account_email = ?1 AND v.address = lower(json_extract(value, '$.address')))
+             ORDER BY date_received DESC",
+            &[],
+        )
     }
 
-    pub fn set_sync_in_progress(&self, account_email: &str, folder: &str, in_progress: bool) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO sync_state (account_email, folder, sync_in_progress, last_uid_seen, last_sync_timestamp)
-             VALUES (?1, ?2, ?3, 
-                     COALESCE((SELECT last_uid_seen FROM sync_state WHERE account_email = ?1 AND folder = ?2), 0),
-                     COALESCE((SELECT last_sync_timestamp FROM sync_state WHERE account_email = ?1 AND folder = ?2), 0))",
-            params![account_email, folder, in_progress],
-        )?;
-        Ok(())
+    /// Messages that look like GitHub or GitLab notifications by their headers, newest
+    /// first; `forge::detect` has the last word
+    pub fn get_forge_notifications(&self, account_email: &str) -> Result<Vec<Email>> {
+        self.query_emails(
+            account_email,
+            "(headers LIKE '%\"x-github-%' OR headers LIKE '%\"x-gitlab-%')
+             ORDER BY date_received DESC",
+            &[],
+        )
     }
 
-    pub fn queue_email_operation(&self, account_email: &str, operation_type: &str, 
-                                email_uid: u32, folder: &str, target_folder: Option<&str>) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO email_operations (account_email, operation_type, email_uid, folder, target_folder, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, strftime('%s', 'now'))",
-            params![account_email, operation_type, email_uid, folder, target_folder],
+    /// Insert or replace a draft
+    pub fn save_draft(&self, account_email: &str, draft: &Draft) -> Result<()> {
+        let (conflict_uid, conflict_content) = match &draft.conflict {
+            Some((uid, email)) => (Some(*uid), Some(serde_json::to_string(email)?)),
+            None => (None, None),
+        };
+        self.execute(
+            "INSERT OR REPLACE INTO drafts
+                (account_email, message_id, content, modified, server_uid, synced, deleted, conflict_uid, conflict_content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                account_email,
+                draft.message_id,
+                serde_json::to_string(&draft.email)?,
+                draft.modified,
+                draft.server_uid,
+                draft.synced,
+                draft.deleted,
+                conflict_uid,
+                conflict_content
+            ],
         )?;
         Ok(())
     }
 
-    pub fn get_pending_operations(&self) -> Result<Vec<(i64, String, String, u32, String, Option<String>)>> {
+    /// Every draft of the account, discarded ones included, latest edit first
+    pub fn get_drafts(&self, account_email: &str) -> Result<Vec<Draft>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, account_email, operation_type, email_uid, folder, target_folder
-             FROM email_operations 
-             WHERE processed = FALSE 
-             ORDER BY created_at ASC"
+            "SELECT message_id, content, modified, server_uid, synced, deleted, conflict_uid, conflict_content
+             FROM drafts WHERE account_email = ?1 ORDER BY modified DESC",
         )?;
-
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![account_email], |row| {
             Ok((
-                row.get::<_, i64>(0)?,
+                row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, u32>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, Option<String>>(5)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<u32>>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, bool>(5)?,
+                row.get::<_, Option<u32>>(6)?,
+                row.get::<_, Option<String>>(7)?,
             ))
         })?;
 
-        let mut operations = Vec::new();
+        let mut drafts = Vec::new();
         for row in rows {
-            operations.push(row?);
+            let (message_id, content, modified, server_uid, synced, deleted, conflict_uid, conflict_content) = row?;
+            let conflict = match (conflict_uid, conflict_content) {
+                (Some(uid), Some(content)) => Some((uid, serde_json::from_str(&content)?)),
+                _ => None,
+            };
+            drafts.push(Draft {
+                message_id,
+                email: serde_json::from_str(&content)?,
+                modified,
+                server_uid,
+                synced,
+                deleted,
+                conflict,
+            });
         }
-
-        Ok(operations)
+        Ok(drafts)
     }
 
-    pub fn mark_operation_processed(&self, operation_id: i64) -> Result<()> {
-        self.conn.execute(
-            "UPDATE email_operations SET processed = TRUE WHERE id = ?1",
-            params![operation_id],
+    pub fn delete_draft(&self, account_email: &str, message_id: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM drafts WHERE account_email = ?1 AND message_id = ?2",
+            params![account_email, message_id],
         )?;
         Ok(())
     }
 
-    pub fn mark_operation_failed(&self, operation_id: i64, error: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE email_operations SET error = ?1 WHERE id = ?2",
-            params![error, operation_id],
-        )?;
-        Ok(())
+    /// All stored mail of the account received at or after `since` (Unix time), newest first
+    pub fn get_emails_since(&self, account_email: &str, since: i64) -> Result<Vec<Email>> {
+        self.query_emails(account_email, "date_received >= ?2 ORDER BY date_received DESC", &[&since])
     }
 
-    pub fn get_emails_paginated(&self, account_email: &str, folder: &str, 
-                               offset: usize, limit: usize) -> Result<Vec<Email>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT uid, message_id, subject, from_addresses, to_addresses, 
-                    cc_addresses, bcc_addresses, date_received, body_text, body_html,
-                    flags, headers, seen
-             FROM emails 
-             WHERE account_email = ?1 AND folder = ?2 
-             ORDER BY date_received DESC
-             LIMIT ?3 OFFSET ?4",
-        )?;
-
-        let email_rows = stmt.query_map(params![account_email, folder, limit, offset], |row| {
-            Ok((
-                row.get::<_, u32>(0)?,       // uid
-                row.get::<_, Option<String>>(1)?, // message_id
-                row.get::<_, String>(2)?,    // subject
-                row.get::<_, String>(3)?,    // from_addresses
-                row.get::<_, String>(4)?,    // to_addresses
-                row.get::<_, String>(5)?,    // cc_addresses
-                row.get::<_, String>(6)?,    // bcc_addresses
-                row.get::<_, i64>(7)?,       // date_received
-                row.get::<_, Option<String>>(8)?, // body_text
-                row.get::<_, Option<String>>(9)?, // body_html
-                row.get::<_, String>(10)?,   // flags
-                row.get::<_, String>(11)?,   // headers
-                row.get::<_, bool>(12)?,     // seen
-            ))
-        })?;
-
-        let mut emails = Vec::new();
-        for row_result in email_rows {
-            let (uid, _message_id, subject, from_json, to_json, cc_json, bcc_json,
-                 date_timestamp, body_text, body_html, flags_json, headers_json, seen) = row_result?;
-
-            // Load attachments for this email
-            let mut attachment_stmt = self.conn.prepare(
-                "SELECT filename, content_type, data FROM attachments 
-                 WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3"
-            )?;
-            
-            let attachment_rows = attachment_stmt.query_map(params![account_email, folder, uid], |row| {
-                Ok(crate::email::EmailAttachment {
-                    filename: row.get(0)?,
-                    content_type: row.get(1)?,
-                    data: row.get(2)?,
-                })
-            })?;
-
-            let mut attachments = Vec::new();
-            for attachment_result in attachment_rows {
-                attachments.push(attachment_result?);
-            }
-
-            let email = Email {
-                id: uid.to_string(), // Convert UID back to string for Email struct
-                subject,
-                from: serde_json::from_str(&from_json)?,
-                to: serde_json::from_str(&to_json)?,
-                cc: serde_json::from_str(&cc_json)?,
-                bcc: serde_json::from_str(&bcc_json)?,
-                date: chrono::DateTime::from_timestamp(date_timestamp, 0)
-                    .unwrap_or_else(|| chrono::Local::now().into())
-                    .with_timezone(&chrono::Local),
-                body_text,
-                body_html,
-                attachments,
-                flags: serde_json::from_str(&flags_json)?,
-                headers: serde_json::from_str(&headers_json)?,
-                seen,
-                folder: folder.to_string(),
-            };
-
-            emails.push(email);
-        }
-
-        Ok(emails)
+    /// Stored mail of the account matching the search, in any folder, newest first
+    pub fn search_emails(&self, account_email: &str, query: &crate::search::SearchQuery) -> Result<Vec<Email>> {
+        let (condition, params) = query.to_sql();
+        let params: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+        self.query_emails(account_email, &format!("{} ORDER BY date_received DESC", condition), &params)
     }
 
-    pub fn get_all_emails(&self, account_email: &str, folder: &str) -> Result<Vec<Email>> {
+    /// Every attachment of the account across all folders, newest message first
+    pub fn list_attachments(&self, account_email: &str) -> Result<Vec<AttachmentIndexEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT uid, message_id, subject, from_addresses, to_addresses, 
-                    cc_addresses, bcc_addresses, date_received, body_text, body_html,
-                    flags, headers, seen
-             FROM emails 
-             WHERE account_email = ?1 AND folder = ?2 
-             ORDER BY date_received DESC",
+            "SELECT a.folder, a.email_uid, a.filename, a.content_type, a.size,
+                    COALESCE(json_extract(e.from_addresses, '$[0].name'), ''),
+                    COALESCE(json_extract(e.from_addresses, '$[0].address'), ''),
+                    e.subject, e.date_received
+             FROM attachments a
+             JOIN emails e
+               ON e.account_email = a.account_email AND e.folder = a.folder AND e.uid = a.email_uid
+             WHERE a.account_email = ?1
+             ORDER BY e.date_received DESC, a.id"
         )?;
 
-        let email_rows = stmt.query_map(params![account_email, folder], |row| {
-            Ok((
-                row.get::<_, u32>(0)?,       // uid
-                row.get::<_, Option<String>>(1)?, // message_id
-                row.get::<_, String>(2)?,    // subject
-                row.get::<_, String>(3)?,    // from_addresses
-                row.get::<_, String>(4)?,    // to_addresses
-                row.get::<_, String>(5)?,    // cc_addresses
-                row.get::<_, String>(6)?,    // bcc_addresses
-                row.get::<_, i64>(7)?,       // date_received
-                row.get::<_, Option<String>>(8)?, // body_text
-                row.get::<_, Option<String>>(9)?, // body_html
-                row.get::<_, String>(10)?,   // flags
-                row.get::<_, String>(11)?,   // headers
-                row.get::<_, bool>(12)?,     // seen
-            ))
+        let rows = stmt.query_map(params![account_email], |row| {
+            let name: String = row.get(5)?;
+            let address: String = row.get(6)?;
+            Ok(AttachmentIndexEntry {
+                folder: row.get(0)?,
+                email_uid: row.get(1)?,
+                filename: row.get(2)?,
+                content_type: row.get(3)?,
+                size: row.get::<_, i64>(4)?.max(0) as usize,
+                sender: if name.is_empty() { address } else { format!("{} <{}>", name, address) },
+                subject: row.get(7)?,
+                date: row.get(8)?,
+            })
         })?;
 
-        let mut emails = Vec::new();
-        for row_result in email_rows {
-            let (uid, _message_id, subject, from_json, to_json, cc_json, bcc_json,
-                 date_timestamp, body_text, body_html, flags_json, headers_json, seen) = row_result?;
-
-            // Load attachments for this email
-            let mut attachment_stmt = self.conn.prepare(
-                "SELECT filename, content_type, data FROM attachments 
-                 WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3"
-            )?;
-            
-            let attachment_rows = attachment_stmt.query_map(params![account_email, folder, uid], |row| {
-                Ok(crate::email::EmailAttachment {
-                    filename: row.get(0)?,
-                    content_type: row.get(1)?,
-                    data: row.get(2)?,
-                })
-            })?;
-
-            let mut attachments = Vec::new();
-            for attachment_result in attachment_rows {
-                attachments.push(attachment_result?);
-            }
-
-            let email = Email {
-                id: uid.to_string(), // Convert UID back to string for Email struct
-                subject,
-                from: serde_json::from_str(&from_json)?,
-                to: serde_json::from_str(&to_json)?,
-                cc: serde_json::from_str(&cc_json)?,
-                bcc: serde_json::from_str(&bcc_json)?,
-                date: chrono::DateTime::from_timestamp(date_timestamp, 0)
-                    .unwrap_or_else(|| chrono::Local::now().into())
-                    .with_timezone(&chrono::Local),
-                body_text,
-                body_html,
-                attachments,
-                flags: serde_json::from_str(&flags_json)?,
-                headers: serde_json::from_str(&headers_json)?,
-                seen,
-                folder: folder.to_string(),
-            };
-
-            emails.push(email);
-        }
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
 
-        Ok(emails)
+    /// Load a single indexed attachment, including its data or cache path
+    pub fn load_attachment(&self, account_email: &str, folder: &str, uid: u32, filename: &str) -> Result<Option<EmailAttachment>> {
+        Ok(self
+            .load_attachments(account_email, folder, uid)?
+            .into_iter()
+            .find(|attachment| attachment.filename == filename))
     }
 
     /// Get recent emails with a limit for better performance
@@ -587,15 +2643,15 @@ impl EmailDatabase {
     }
 
     pub fn get_recent_emails(&self, account_email: &str, folder: &str, limit: usize) -> Result<Vec<Email>> {
-        let mut stmt = self.conn.prepare(
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT uid, message_id, subject, from_addresses, to_addresses, 
-                    cc_addresses, bcc_addresses, date_received, body_text, body_html,
-                    flags, headers, seen
+                    cc_addresses, bcc_addresses, date_received, {BODY_TEXT}, {BODY_HTML},
+                    flags, headers, seen, snippet
              FROM emails 
              WHERE account_email = ?1 AND folder = ?2 
              ORDER BY date_received DESC
-             LIMIT ?3",
-        )?;
+             LIMIT ?3"
+        ))?;
 
         let email_rows = stmt.query_map(params![account_email, folder, limit], |row| {
             Ok((
@@ -612,6 +2668,7 @@ impl EmailDatabase {
                 row.get::<_, String>(10)?,   // flags
                 row.get::<_, String>(11)?,   // headers
                 row.get::<_, bool>(12)?,     // seen
+                row.get::<_, Option<String>>(13)?, // snippet
             ))
         })?;
 
@@ -619,9 +2676,9 @@ impl EmailDatabase {
         let mut email_data = Vec::new();
         for row_result in email_rows {
             let (uid, _message_id, subject, from_str, to_str, cc_str, bcc_str, date_received, 
-                 body_text, body_html, flags_str, headers_str, seen) = row_result?;
+                 body_text, body_html, flags_str, headers_str, seen, snippet) = row_result?;
             email_data.push((uid, subject, from_str, to_str, cc_str, bcc_str, date_received, 
-                           body_text, body_html, flags_str, headers_str, seen));
+                           body_text, body_html, flags_str, headers_str, seen, snippet));
         }
         
         // Load ALL attachments for these emails in one query (much faster!)
@@ -629,7 +2686,7 @@ impl EmailDatabase {
         let uid_placeholders = uids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
         
         let attachment_query = format!(
-            "SELECT email_uid, filename, content_type, data FROM attachments 
+            "SELECT email_uid, filename, content_type, data, path, size FROM attachments 
              WHERE account_email = ? AND folder = ? AND email_uid IN ({})",
             uid_placeholders
         );
@@ -647,6 +2704,8 @@ impl EmailDatabase {
                         filename: row.get(1)?,
                         content_type: row.get(2)?,
                         data: row.get(3)?,
+                        path: row.get::<_, Option<String>>(4)?.map(std::path::PathBuf::from),
+                        size: row.get::<_, i64>(5)?.max(0) as usize,
                     }
                 ))
             }
@@ -665,7 +2724,7 @@ impl EmailDatabase {
         let mut emails = Vec::new();
         
         for (uid, subject, from_str, to_str, cc_str, bcc_str, date_received, 
-             body_text, body_html, flags_str, headers_str, seen) in email_data {
+             body_text, body_html, flags_str, headers_str, seen, snippet) in email_data {
             
             // Parse addresses
             let from_addresses: Vec<crate::email::EmailAddress> = serde_json::from_str(&from_str).unwrap_or_default();
@@ -700,6 +2759,8 @@ impl EmailDatabase {
                 headers,
                 seen,
                 folder: folder.to_string(),
+                snippet,
+                gmail_id: None,
             };
 
             emails.push(email);
@@ -709,7 +2770,7 @@ impl EmailDatabase {
     }
 
     pub fn update_email_seen_status(&self, account_email: &str, folder: &str, uid: u32, seen: bool) -> Result<()> {
-        self.conn.execute(
+        self.execute(
             "UPDATE emails SET seen = ?1, updated_at = strftime('%s', 'now') 
              WHERE account_email = ?2 AND folder = ?3 AND uid = ?4",
             params![seen, account_email, folder, uid],
@@ -717,6 +2778,233 @@ impl EmailDatabase {
         Ok(())
     }
 
+    pub fn save_avatar(&self, address: &str, image: Option<&[u8]>) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO avatars (address, image, fetched_at)
+             VALUES (?1, ?2, strftime('%s', 'now'))",
+            params![address, image],
+        )?;
+        Ok(())
+    }
+
+    /// Cached avatar lookup: `None` if never looked up, otherwise the image (if any)
+    /// and the Unix timestamp of the lookup
+    pub fn load_avatar(&self, address: &str) -> Result<Option<(Option<Vec<u8>>, i64)>> {
+        let result = self.conn.query_row(
+            "SELECT image, fetched_at FROM avatars WHERE address = ?1",
+            params![address],
+            |row| Ok((row.get::<_, Option<Vec<u8>>>(0)?, row.get::<_, i64>(1)?)),
+        );
+        match result {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save_release_check(&self, releases: &[crate::updates::Release], checked_at: i64) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO release_check (id, releases, checked_at) VALUES (1, ?1, ?2)",
+            params![serde_json::to_string(releases)?, checked_at],
+        )?;
+        Ok(())
+    }
+
+    /// The releases found by the last update check and when it ran, if one ever did
+    pub fn load_release_check(&self) -> Result<Option<(Vec<crate::updates::Release>, i64)>> {
+        let row = self
+            .conn
+            .query_row("SELECT releases, checked_at FROM release_check WHERE id = 1", [], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .optional()?;
+        Ok(row.map(|(releases, checked_at)| (serde_json::from_str(&releases).unwrap_or_default(), checked_at)))
+    }
+
+    pub fn record_audit(&self, entry: &AuditEntry) -> Result<()> {
+        self.execute(
+            "INSERT INTO audit_log (timestamp, account_email, folder, uid, action, origin, detail)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                entry.timestamp,
+                entry.account_email,
+                entry.folder,
+                entry.uid,
+                entry.action,
+                entry.origin,
+                entry.detail
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The most recent audit entries, newest first, optionally for one account only.
+    /// Without a limit the whole log is returned.
+    pub fn get_audit_log(&self, account_email: Option<&str>, limit: Option<usize>) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, account_email, folder, uid, action, origin, detail
+             FROM audit_log
+             WHERE ?1 IS NULL OR account_email = ?1
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?2",
+        )?;
+        // SQLite treats a negative LIMIT as no limit
+        let limit = limit.map_or(-1, |n| n as i64);
+        let rows = stmt.query_map(params![account_email, limit], |row| {
+            Ok(AuditEntry {
+                timestamp: row.get(0)?,
+                account_email: row.get(1)?,
+                folder: row.get(2)?,
+                uid: row.get(3)?,
+                action: row.get(4)?,
+                origin: row.get(5)?,
+                detail: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn update_email_flags(&self, account_email: &str, folder: &str, uid: u32, flags: &[String], seen: bool) -> Result<()> {
+        self.execute(
+            "UPDATE emails SET flags = ?1, seen = ?2, updated_at = strftime('%s', 'now')
+             WHERE account_email = ?3 AND folder = ?4 AND uid = ?5",
+            params![serde_json::to_string(flags)?, seen, account_email, folder, uid],
+        )?;
+        Ok(())
+    }
+
+    /// Add `flag` to the cached flags of a message, keeping the others
+    pub fn add_email_flag(&self, account_email: &str, folder: &str, uid: u32, flag: &str) -> Result<()> {
+        let flags: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT flags FROM emails WHERE account_email = ?1 AND folder = ?2 AND uid = ?3",
+                params![account_email, folder, uid],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(flags) = flags else {
+            return Ok(());
+        };
+        let mut flags: Vec<String> = serde_json::from_str(&flags).unwrap_or_default();
+        if !flags.iter().any(|f| f == flag) {
+            flags.push(flag.to_string());
+            self.execute(
+                "UPDATE emails SET flags = ?1, updated_at = strftime('%s', 'now')
+                 WHERE account_email = ?2 AND folder = ?3 AND uid = ?4",
+                params![serde_json::to_string(&flags)?, account_email, folder, uid],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn update_attachment_data(&self, account_email: &str, folder: &str, uid: u32, attachment: &EmailAttachment) -> Result<()> {
+        let (data, path) = self.spill_attachment(account_email, folder, uid, attachment)?;
+        self.execute(
+            "UPDATE attachments SET data = ?1, size = ?2, path = ?3
+             WHERE account_email = ?4 AND folder = ?5 AND email_uid = ?6 AND filename = ?7",
+            params![data, attachment.size() as i64, path, account_email, folder, uid, attachment.filename],
+        )?;
+        Ok(())
+    }
+
+    /// Directory holding attachment payloads too large to keep in SQLite
+    pub fn attachment_cache_dir(&self) -> std::path::PathBuf {
+        self.db_path
+            .parent()
+            .map(|dir| dir.join("attachments"))
+            .unwrap_or_else(|| std::path::PathBuf::from("attachments"))
+    }
+
+    /// Attachment payloads held locally, in rows or cache files
+    pub fn cached_attachments(&self) -> Result<Vec<CachedAttachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, length(a.data), a.path, COALESCE(a.used_at, a.created_at),
+                    a.email_uid < ?1 AND EXISTS (SELECT 1 FROM emails e
+                        WHERE e.account_email = a.account_email AND e.folder = a.folder AND e.uid = a.email_uid)
+             FROM attachments a WHERE length(a.data) > 0 OR a.path IS NOT NULL",
+        )?;
+        let rows = stmt.query_map(params![LOCAL_UID_FLOOR], |row| {
+            Ok(CachedAttachment {
+                id: row.get(0)?,
+                stored: row.get::<_, i64>(1)? as u64,
+                path: row.get::<_, Option<String>>(2)?.map(std::path::PathBuf::from),
+                used_at: row.get(3)?,
+                on_server: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Drop the local copy of an attachment; it is downloaded again when next needed.
+    /// The cache file is left to the caller.
+    pub fn evict_attachment(&self, id: i64) -> Result<()> {
+        self.execute("UPDATE attachments SET data = X'', path = NULL WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Note that the attachments of a message were just looked at
+    pub fn touch_attachments(&self, account_email: &str, folder: &str, uid: u32) -> Result<()> {
+        self.execute(
+            "UPDATE attachments SET used_at = strftime('%s', 'now')
+             WHERE account_email = ?1 AND folder = ?2 AND email_uid = ?3",
+            params![account_email, folder, uid],
+        )?;
+        Ok(())
+    }
+
+    /// Write attachments above the spill threshold to a cache file. Returns the bytes to store
+    /// in the row (empty when spilled) and the cache file path, if any.
+    fn spill_attachment<'a>(&self, account_email: &str, folder: &str, uid: u32, attachment: &'a EmailAttachment) -> Result<(&'a [u8], Option<String>)> {
+        if attachment.data.len() <= ATTACHMENT_SPILL_THRESHOLD {
+            let path = attachment.path.as_ref().map(|p| p.to_string_lossy().to_string());
+            return Ok((&attachment.data, path));
+        }
+        let file_path = self.write_attachment_file(account_email, folder, uid, &attachment.data)?;
+        Ok((&[], Some(file_path.to_string_lossy().to_string())))
+    }
+
+    /// Move the payloads of a message's attachments above the spill threshold to cache
+    /// files right away, so a batch being synced doesn't hold them all in memory until it
+    /// is saved
+    pub fn spill_attachments(&self, account_email: &str, folder: &str, email: &mut Email) -> Result<()> {
+        let Ok(uid) = email.id.parse::<u32>() else {
+            return Ok(());
+        };
+        for attachment in &mut email.attachments {
+            if attachment.data.len() > ATTACHMENT_SPILL_THRESHOLD {
+                attachment.path = Some(self.write_attachment_file(account_email, folder, uid, &attachment.data)?);
+                attachment.size = attachment.data.len();
+                attachment.data = Vec::new();
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a payload to the message's cache directory. Files are named by the hash of
+    /// their content, so attachments sharing a filename don't overwrite each other and
+    /// no name from the message ends up in a path.
+    fn write_attachment_file(&self, account_email: &str, folder: &str, uid: u32, data: &[u8]) -> Result<PathBuf> {
+        use sha2::{Digest, Sha256};
+        let sanitize = |name: &str| -> String {
+            name.chars()
+                .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+                .collect()
+        };
+        let dir = self.attachment_cache_dir()
+            .join(sanitize(account_email))
+            .join(sanitize(folder))
+            .join(uid.to_string());
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create attachment cache directory: {:?}", dir))?;
+
+        let hash: String = Sha256::digest(data).iter().map(|byte| format!("{:02x}", byte)).collect();
+        let file_path = dir.join(hash);
+        std::fs::write(&file_path, data)
+            .with_context(|| format!("Failed to write attachment cache file: {:?}", file_path))?;
+        Ok(file_path)
+    }
+
     pub fn is_sync_stale(&self, account_email: &str, folder: &str, max_age_seconds: i64) -> Result<bool> {
         let current_time = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
@@ -734,37 +3022,82 @@ impl EmailDatabase {
 
     pub fn clear_folder_emails(&self, account_email: &str, folder: &str) -> Result<()> {
         // Clear emails for this folder
-        self.conn.execute(
+        self.execute(
             "DELETE FROM emails WHERE account_email = ?1 AND folder = ?2",
             params![account_email, folder],
         )?;
         
         // Clear attachments for this folder
-        self.conn.execute(
+        self.execute(
             "DELETE FROM attachments WHERE account_email = ?1 AND folder = ?2",
             params![account_email, folder],
         )?;
         
+        self.execute(
+            "DELETE FROM pending_bodies WHERE account_email = ?1 AND folder = ?2",
+            params![account_email, folder],
+        )?;
+
         // Reset folder metadata
-        self.conn.execute(
+        self.execute(
             "DELETE FROM folder_metadata WHERE account_email = ?1 AND folder = ?2",
             params![account_email, folder],
         )?;
         
         // Reset sync state
-        self.conn.execute(
+        self.execute(
             "DELETE FROM sync_state WHERE account_email = ?1 AND folder = ?2",
             params![account_email, folder],
         )?;
+        self.clear_sync_progress(account_email, folder)?;
+        self.drop_unshared_bodies(account_email)?;
         
         Ok(())
     }
     
+    /// Move what is cached of a folder, and of the folders below it, to the name it was
+    /// renamed to on the server. Below it are folders named after it and a `/` or `.`, the
+    /// hierarchy delimiters servers use.
+    pub fn rename_folder(&self, account_email: &str, old: &str, new: &str) -> Result<()> {
+        for (table, column) in [
+            ("emails", "folder"),
+            ("attachments", "folder"),
+            ("pending_bodies", "folder"),
+            ("folder_metadata", "folder"),
+            ("sync_state", "folder"),
+            ("sync_progress", "folder"),
+            ("deleted_messages", "folder"),
+            ("email_operations", "folder"),
+            ("email_operations", "target_folder"),
+        ] {
+            self.execute(
+                &format!(
+                    "UPDATE {table} SET {column} = ?3 || substr({column}, length(?2) + 1)
+                     WHERE account_email = ?1
+                       AND ({column} = ?2 OR substr({column}, 1, length(?2) + 1) IN (?2 || '/', ?2 || '.'))"
+                ),
+                params![account_email, old, new],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Forget a folder deleted on the server: its cached messages and the operations
+    /// still queued on them, which could never be carried out
+    pub fn forget_folder(&self, account_email: &str, folder: &str) -> Result<()> {
+        self.clear_folder_emails(account_email, folder)?;
+        self.execute(
+            "DELETE FROM email_operations WHERE account_email = ?1 AND (folder = ?2 OR target_folder = ?2)",
+            params![account_email, folder],
+        )?;
+        Ok(())
+    }
+
     /// Get the highest UID for a specific account and folder (for new mail checking)
     pub fn get_last_uid(&self, account_email: &str, folder: &str) -> Result<u32> {
         let result = self.conn.query_row(
-            "SELECT MAX(CAST(uid AS INTEGER)) FROM emails WHERE account_email = ?1 AND folder = ?2",
-            params![account_email, folder],
+            "SELECT MAX(CAST(uid AS INTEGER)) FROM emails WHERE account_email = ?1 AND folder = ?2 AND uid < ?3",
+            params![account_email, folder, LOCAL_UID_FLOOR],
             |row| {
                 let uid: Option<i64> = row.get(0)?;
                 Ok(uid.unwrap_or(0) as u32)
@@ -839,13 +3172,13 @@ impl EmailDatabase {
     ) -> Result<Vec<Email>> {
         let since_timestamp = since.timestamp();
         
-        let mut stmt = self.conn.prepare(
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT uid, message_id, subject, from_addresses, to_addresses, cc_addresses, bcc_addresses, 
-             date_received, body_text, body_html, flags, headers_json, seen
+             date_received, {BODY_TEXT}, {BODY_HTML}, flags, headers_json, seen, snippet
              FROM emails 
              WHERE account_email = ?1 AND folder = ?2 AND date_received > ?3
              ORDER BY date_received DESC"
-        )?;
+        ))?;
         
         let email_data: Result<Vec<_>, _> = stmt.query_map(params![account_email, folder, since_timestamp], |row| {
             Ok((
@@ -862,6 +3195,7 @@ impl EmailDatabase {
                 row.get::<_, String>(10)?,  // flags
                 row.get::<_, String>(11)?,  // headers_json
                 row.get::<_, bool>(12)?,    // seen
+                row.get::<_, Option<String>>(13)?, // snippet
             ))
         })?.collect();
         
@@ -876,7 +3210,7 @@ impl EmailDatabase {
         let uid_placeholders = uids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
         
         let attachment_query = format!(
-            "SELECT email_uid, filename, content_type, data FROM attachments 
+            "SELECT email_uid, filename, content_type, data, path, size FROM attachments 
              WHERE account_email = ? AND folder = ? AND email_uid IN ({})",
             uid_placeholders
         );
@@ -893,6 +3227,8 @@ impl EmailDatabase {
                     filename: row.get(1)?,
                     content_type: row.get(2)?,
                     data: row.get(3)?,
+                    path: row.get::<_, Option<String>>(4)?.map(std::path::PathBuf::from),
+                    size: row.get::<_, i64>(5)?.max(0) as usize,
                 };
                 Ok((email_uid, attachment))
             }
@@ -911,7 +3247,7 @@ impl EmailDatabase {
         let mut emails = Vec::new();
         
         for (uid, _message_id, subject, from_json, to_json, cc_json, bcc_json,
-             date_timestamp, body_text, body_html, flags_str, headers_str, seen) in email_data {
+             date_timestamp, body_text, body_html, flags_str, headers_str, seen, snippet) in email_data {
             
             let from_addresses: Vec<EmailAddress> = 
                 serde_json::from_str(&from_json).unwrap_or_default();
@@ -946,6 +3282,8 @@ impl EmailDatabase {
                 headers,
                 seen,
                 folder: folder.to_string(),
+                snippet,
+                gmail_id: None,
             };
             
             emails.push(email);
@@ -954,3 +3292,27 @@ impl EmailDatabase {
         Ok(emails)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sender_preferences_are_remembered() {
+        let dir = std::env::temp_dir().join(format!("tuimail-database-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        assert_eq!(db.get_sender_preferences("me@example.com", "ann@example.com").unwrap(), None);
+
+        let preferences = SenderPreferences { remote_content: true, html: true, ..SenderPreferences::default() };
+        db.set_sender_preferences("me@example.com", "Ann@Example.com", &preferences).unwrap();
+        assert_eq!(db.get_sender_preferences("me@example.com", "ann@example.com").unwrap(), Some(preferences));
+        assert_eq!(db.get_sender_preferences("other@example.com", "ann@example.com").unwrap(), None);
+
+        // Back at the defaults there is nothing left to remember
+        db.set_sender_preferences("me@example.com", "ann@example.com", &SenderPreferences::default()).unwrap();
+        assert_eq!(db.get_sender_preferences("me@example.com", "ann@example.com").unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::email::Email;
+
+/// Headers of the digest copied to its messages that lack them, so replies and list
+/// actions still know which list they belong to
+const LIST_HEADERS: &[&str] = &["List-Id", "List-Post", "List-Unsubscribe", "List-Archive"];
+
+/// Whether a line separates the messages of a plain-text digest (RFC 1153): 70 dashes
+/// before the first message, 30 between the others
+fn is_separator(line: &str) -> bool {
+    let line = line.trim_end();
+    (line.len() == 30 || line.len() == 70) && line.bytes().all(|b| b == b'-')
+}
+
+/// One message of a plain-text digest: its header block and body. `None` for the
+/// table of contents, the footer and anything else without a sender.
+fn plain_part(chunk: &str) -> Option<Vec<u8>> {
+    let chunk = chunk.trim_start_matches(['\r', '\n']);
+    let (head, body) = chunk.split_once("\n\n").or_else(|| chunk.split_once("\r\n\r\n")).unwrap_or((chunk, ""));
+    let mut headers = Vec::new();
+    for line in head.lines() {
+        let continued = line.starts_with([' ', '\t']);
+        match line.split_once(':') {
+            _ if continued => headers.push(line.to_string()),
+            Some((name, _)) if !name.is_empty() && !name.contains(char::is_whitespace) => headers.push(line.to_string()),
+            // Not a header block after all
+            _ => return None,
+        }
+    }
+    if !headers.iter().any(|header| header.split_once(':').is_some_and(|(name, _)| name.eq_ignore_ascii_case("From"))) {
+        return None;
+    }
+
+    // The text is already decoded, so the part's own encoding no longer applies
+    let mut raw = String::new();
+    let mut skipping = false;
+    for header in &headers {
+        if !header.starts_with([' ', '\t']) {
+            let name = header.split(':').next().unwrap_or_default().to_ascii_lowercase();
+            skipping = matches!(name.as_str(), "message" | "content-type" | "content-transfer-encoding" | "mime-version");
+        }
+        if !skipping {
+            raw.push_str(header);
+            raw.push_str("\r\n");
+        }
+    }
+    raw.push_str("Content-Type: text/plain; charset=utf-8\r\n\r\n");
+    raw.push_str(body.trim_end());
+    raw.push('\n');
+    Some(raw.into_bytes())
+}
+
+/// The raw messages a digest carries: the `message/rfc822` parts of a MIME digest, or
+/// the messages of a plain-text one
+fn raw_parts(email: &Email) -> Vec<Vec<u8>> {
+    let attached: Vec<Vec<u8>> = email
+        .attachments
+        .iter()
+        .filter(|attachment| attachment.content_type == "message/rfc822" && attachment.is_available())
+        .filter_map(|attachment| attachment.load_data().ok())
+        .collect();
+    if attached.len() >= 2 {
+        return attached;
+    }
+    let Some(body) = email.body_text.as_deref() else {
+        return Vec::new();
+    };
+    let mut chunks = vec![String::new()];
+    for line in body.lines() {
+        if is_separator(line) {
+            chunks.push(String::new());
+        } else if let Some(chunk) = chunks.last_mut() {
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+    }
+    // Whatever comes before the first separator is the introduction and table of contents
+    chunks.iter().skip(1).filter_map(|chunk| plain_part(chunk)).collect()
+}
+
+/// Split a digest, such as a Mailman daily digest, into the messages it collects, oldest
+/// first as the digest has them. Empty when `email` holds fewer than two messages.
+///
+/// The messages are not on the server: they keep the digest's folder, take its ID with
+/// their number appended, and have no attachments.
+pub fn burst(email: &Email) -> Vec<Email> {
+    let parts = raw_parts(email);
+    if parts.len() < 2 {
+        return Vec::new();
+    }
+    parts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, raw)| {
+            let parsed = crate::mime::parse_message(raw)?;
+            let mut message = Email::from_parsed_email(&parsed, &format!("{}#{}", email.id, i + 1), &email.folder, Vec::new()).ok()?;
+            message.seen = true;
+            message.attachments.clear();
+            if parsed.date().is_none() {
+                message.date = email.date;
+            }
+            if message.to.is_empty() {
+                message.to = email.to.clone();
+            }
+            for name in LIST_HEADERS {
+                if let (None, Some(value)) = (message.header(name), email.header(name)) {
+                    message.headers.insert(name.to_string(), value.to_string());
+                }
+            }
+            Some(message)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailAttachment;
+
+    #[test]
+    fn plain_digests_are_split() {
+        let mut digest = Email::new();
+        digest.id = "42".to_string();
+        digest.folder = "Lists".to_string();
+        digest.to = vec![crate::email::EmailAddress { name: None, address: "dev@lists.example.org".to_string() }];
+        digest.headers.insert("List-Post".to_string(), "<mailto:dev@lists.example.org>".to_string());
+        digest.body_text = Some(
+            "Send dev mailing list submissions to\n\tdev@lists.example.org\n\nToday's Topics:\n\n   1. Build failure (Ann Smith)\n\n\
+             ----------------------------------------------------------------------\n\n\
+             Message: 1\nDate: Tue, 5 Mar 2024 10:00:00 +0100\nFrom: Ann Smith <ann@example.com>\nSubject: Build failure\n\
+             Message-ID: <a1@example.com>\nContent-Type: text/plain; charset=\"iso-8859-1\"\n\nThe build fails on ARM — again.\n\n\
+             ------------------------------\n\n\
+             Message: 2\nDate: Tue, 5 Mar 2024 11:30:00 +0100\nFrom: bob@example.net\nTo: dev@lists.example.org\nSubject: Re: Build\n\
+             \tfailure\nIn-Reply-To: <a1@example.com>\n\nFixed in main.\n\n\
+             ------------------------------\n\nSubject: Digest Footer\n\n_______________________________________________\n\
+             dev mailing list\n\n------------------------------\n\nEnd of dev Digest, Vol 3, Issue 7\n*********************************\n"
+                .to_string(),
+        );
+
+        let messages = burst(&digest);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].id, "42#1");
+        assert_eq!(messages[0].from[0].address, "ann@example.com");
+        assert_eq!(messages[0].body_text.as_deref(), Some("The build fails on ARM — again.\n"));
+        assert_eq!(messages[0].message_id(), "a1@example.com");
+        assert_eq!(messages[0].to[0].address, "dev@lists.example.org");
+        assert_eq!(messages[1].subject, "Re: Build failure");
+        assert_eq!(messages[1].header("List-Post"), Some("<mailto:dev@lists.example.org>"));
+        assert!(messages[1].date > messages[0].date);
+
+        digest.body_text = Some("Just one\n\n------------------------------\n\nFrom: a@example.com\n\nHi\n".to_string());
+        assert!(burst(&digest).is_empty());
+    }
+
+    #[test]
+    fn mime_digests_are_split() {
+        let raw = b"From: dev-request@lists.example.org\r\nSubject: dev Digest\r\nMIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\r\n--outer\r\nContent-Type: text/plain\r\n\r\nToday's Topics\r\n\
+--outer\r\nContent-Type: multipart/digest; boundary=\"inner\"\r\n\r\n--inner\r\n\r\nFrom: ann@example.com\r\n\
+Subject: One\r\n\r\nFirst\r\n--inner\r\n\r\nFrom: bob@example.net\r\nSubject: Two\r\n\r\nSecond\r\n--inner--\r\n--outer--\r\n";
+        let parsed = mail_parser::Message::parse(raw).unwrap();
+        let mut digest = Email::from_parsed_email(&parsed, "7", "INBOX", Vec::new()).unwrap();
+        digest.attachments = parsed.parts.iter().filter_map(crate::mime::attachment_from_part).collect::<Vec<EmailAttachment>>();
+        let messages = burst(&digest);
+        let subjects: Vec<&str> = messages.iter().map(|m| m.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["One", "Two"]);
+        assert_eq!(messages[1].body_text.as_deref().map(str::trim), Some("Second"));
+    }
+}
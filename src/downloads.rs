@@ -0,0 +1,434 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use crate::email::{debug_log, Email, EmailAttachment, EmailClient, EmailError};
+
+/// Size of each partial IMAP fetch used to report download progress
+const DOWNLOAD_CHUNK_SIZE: usize = 256 * 1024;
+
+pub type DownloadId = u64;
+
+/// Where the download worker fetches messages from: the account's `EmailClient`, or a
+/// stand-in in tests
+pub trait AttachmentSource: Send + 'static {
+    /// The raw message, fetched in chunks; `on_progress` gets the bytes received and the
+    /// total, and returns false to stop
+    fn fetch_raw_message_chunked<F>(&self, folder: &str, uid: &str, chunk_size: usize, on_progress: F) -> Result<Vec<u8>, EmailError>
+    where
+        F: FnMut(usize, usize) -> bool;
+
+    /// Keep a downloaded attachment in the cache
+    fn update_cached_attachment(&self, folder: &str, uid: &str, attachment: &EmailAttachment) -> Result<(), EmailError>;
+}
+
+impl AttachmentSource for EmailClient {
+    fn fetch_raw_message_chunked<F>(&self, folder: &str, uid: &str, chunk_size: usize, on_progress: F) -> Result<Vec<u8>, EmailError>
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        EmailClient::fetch_raw_message_chunked(self, folder, uid, chunk_size, on_progress)
+    }
+
+    fn update_cached_attachment(&self, folder: &str, uid: &str, attachment: &EmailAttachment) -> Result<(), EmailError> {
+        EmailClient::update_cached_attachment(self, folder, uid, attachment)
+    }
+}
+
+/// State of a single queued attachment download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadStatus {
+    Queued,
+    InProgress { received: usize, total: usize },
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+impl DownloadStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(self, DownloadStatus::Queued | DownloadStatus::InProgress { .. })
+    }
+
+    /// Short label for the attachment list
+    pub fn label(&self) -> String {
+        match self {
+            DownloadStatus::Queued => "queued".to_string(),
+            DownloadStatus::InProgress { received, total } => {
+                let percent = if *total > 0 { received * 100 / total } else { 0 };
+                format!("downloading {}%", percent)
+            }
+            DownloadStatus::Completed => "downloaded".to_string(),
+            DownloadStatus::Failed(error) => format!("failed: {}", error),
+            DownloadStatus::Cancelled => "cancelled".to_string(),
+        }
+    }
+}
+
+/// An attachment download tracked by the queue
+#[derive(Debug, Clone)]
+pub struct DownloadJob {
+    pub id: DownloadId,
+    pub account_email: String,
+    pub folder: String,
+    pub email_id: String,
+    pub attachment_idx: usize,
+    pub filename: String,
+    pub status: DownloadStatus,
+}
+
+impl DownloadJob {
+    fn matches(&self, account_email: &str, folder: &str, email_id: &str, attachment_idx: usize) -> bool {
+        self.account_email == account_email
+            && self.folder == folder
+            && self.email_id == email_id
+            && self.attachment_idx == attachment_idx
+    }
+}
+
+/// Events sent from the download worker back to the UI thread
+enum DownloadEvent {
+    Progress { id: DownloadId, received: usize, total: usize },
+    Finished { id: DownloadId, attachment: EmailAttachment },
+    Failed { id: DownloadId, error: String },
+    Cancelled { id: DownloadId },
+}
+
+struct DownloadRequest<S> {
+    job: DownloadJob,
+    client: S,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Queue that downloads uncached attachments one at a time on a background thread,
+/// so the user can keep reading while large files arrive.
+pub struct AttachmentDownloadQueue<S: AttachmentSource = EmailClient> {
+    jobs: Vec<DownloadJob>,
+    cancel_flags: HashMap<DownloadId, Arc<AtomicBool>>,
+    request_sender: Option<mpsc::Sender<DownloadRequest<S>>>,
+    event_sender: mpsc::Sender<DownloadEvent>,
+    event_receiver: mpsc::Receiver<DownloadEvent>,
+    next_id: DownloadId,
+}
+
+impl<S: AttachmentSource> Default for AttachmentDownloadQueue<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: AttachmentSource> AttachmentDownloadQueue<S> {
+    pub fn new() -> Self {
+        let (event_sender, event_receiver) = mpsc::channel();
+        Self {
+            jobs: Vec::new(),
+            cancel_flags: HashMap::new(),
+            request_sender: None,
+            event_sender,
+            event_receiver,
+            next_id: 1,
+        }
+    }
+
+    /// Queue a download for one attachment of `email`. Returns the existing job id if the
+    /// attachment is already queued or downloading.
+    pub fn enqueue(&mut self, client: S, account_email: &str, email: &Email, attachment_idx: usize) -> Option<DownloadId> {
+        let attachment = email.attachments.get(attachment_idx)?;
+
+        if let Some(job) = self.jobs.iter().find(|job| {
+            job.status.is_active() && job.matches(account_email, &email.folder, &email.id, attachment_idx)
+        }) {
+            return Some(job.id);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let job = DownloadJob {
+            id,
+            account_email: account_email.to_string(),
+            folder: email.folder.clone(),
+            email_id: email.id.clone(),
+            attachment_idx,
+            filename: attachment.filename.clone(),
+            status: DownloadStatus::Queued,
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let request = DownloadRequest {
+            job: job.clone(),
+            client,
+            cancel: cancel.clone(),
+        };
+
+        let sender = self.request_sender.get_or_insert_with(|| Self::spawn_worker(self.event_sender.clone()));
+        if sender.send(request).is_err() {
+            debug_log("Attachment download worker is gone, dropping request");
+            self.request_sender = None;
+            return None;
+        }
+
+        debug_log(&format!("Queued attachment download {} for '{}' (email {})", id, job.filename, job.email_id));
+        self.cancel_flags.insert(id, cancel);
+        self.jobs.push(job);
+        Some(id)
+    }
+
+    /// Request cancellation of a queued or running download
+    pub fn cancel(&mut self, id: DownloadId) -> bool {
+        let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+            return false;
+        };
+        if !job.status.is_active() {
+            return false;
+        }
+        if let Some(flag) = self.cancel_flags.get(&id) {
+            flag.store(true, Ordering::Relaxed);
+        }
+        job.status = DownloadStatus::Cancelled;
+        true
+    }
+
+    /// Find the most recent job for a given attachment
+    pub fn job_for(&self, account_email: &str, folder: &str, email_id: &str, attachment_idx: usize) -> Option<&DownloadJob> {
+        self.jobs
+            .iter()
+            .rev()
+            .find(|job| job.matches(account_email, folder, email_id, attachment_idx))
+    }
+
+    /// Drain worker events, updating job state. Returns the finished downloads so the
+    /// caller can splice the attachment data into the emails it is displaying.
+    pub fn poll(&mut self) -> Vec<(DownloadJob, EmailAttachment)> {
+        let mut finished = Vec::new();
+
+        while let Ok(event) = self.event_receiver.try_recv() {
+            let id = match &event {
+                DownloadEvent::Progress { id, .. }
+                | DownloadEvent::Finished { id, .. }
+                | DownloadEvent::Failed { id, .. }
+                | DownloadEvent::Cancelled { id } => *id,
+            };
+            let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+                continue;
+            };
+
+            match event {
+                DownloadEvent::Progress { received, total, .. } => {
+                    // A late progress event must not resurrect a cancelled job
+                    if job.status.is_active() {
+                        job.status = DownloadStatus::InProgress { received, total };
+                    }
+                }
+                DownloadEvent::Finished { attachment, .. } => {
+                    self.cancel_flags.remove(&id);
+                    if job.status == DownloadStatus::Cancelled {
+                        continue;
+                    }
+                    job.status = DownloadStatus::Completed;
+                    finished.push((job.clone(), attachment));
+                }
+                DownloadEvent::Failed { error, .. } => {
+                    self.cancel_flags.remove(&id);
+                    job.status = DownloadStatus::Failed(error);
+                }
+                DownloadEvent::Cancelled { .. } => {
+                    self.cancel_flags.remove(&id);
+                    job.status = DownloadStatus::Cancelled;
+                }
+            }
+        }
+
+        finished
+    }
+
+    /// Forget jobs that are no longer running
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(|job| job.status.is_active());
+    }
+
+    fn spawn_worker(events: mpsc::Sender<DownloadEvent>) -> mpsc::Sender<DownloadRequest<S>> {
+        let (request_sender, request_receiver) = mpsc::channel::<DownloadRequest<S>>();
+
+        thread::spawn(move || {
+            debug_log("Attachment download worker started");
+            while let Ok(request) = request_receiver.recv() {
+                let event = Self::run_download(&request, &events);
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+            debug_log("Attachment download worker stopped");
+        });
+
+        request_sender
+    }
+
+    fn run_download(request: &DownloadRequest<S>, events: &mpsc::Sender<DownloadEvent>) -> DownloadEvent {
+        let job = &request.job;
+        let id = job.id;
+
+        if request.cancel.load(Ordering::Relaxed) {
+            return DownloadEvent::Cancelled { id };
+        }
+
+        let raw = request.client.fetch_raw_message_chunked(&job.folder, &job.email_id, DOWNLOAD_CHUNK_SIZE, |received, total| {
+            let _ = events.send(DownloadEvent::Progress { id, received, total });
+            !request.cancel.load(Ordering::Relaxed)
+        });
+
+        let raw = match raw {
+            Ok(raw) => raw,
+            Err(_) if request.cancel.load(Ordering::Relaxed) => return DownloadEvent::Cancelled { id },
+            Err(e) => {
+                debug_log(&format!("Attachment download {} failed: {}", id, e));
+                return DownloadEvent::Failed { id, error: e.to_string() };
+            }
+        };
+
+        let Some(parsed) = crate::mime::parse_message(&raw) else {
+            return DownloadEvent::Failed { id, error: "Could not parse message".to_string() };
+        };
+        let email = match Email::from_parsed_email(&parsed, &job.email_id, &job.folder, Vec::new()) {
+            Ok(email) => email,
+            Err(e) => return DownloadEvent::Failed { id, error: e.to_string() },
+        };
+
+        // Prefer a filename match; fall back to position if the names were normalized
+        let attachment = email
+            .attachments
+            .iter()
+            .find(|attachment| attachment.filename == job.filename)
+            .or_else(|| email.attachments.get(job.attachment_idx))
+            .cloned();
+
+        match attachment {
+            Some(attachment) => {
+                if let Err(e) = request.client.update_cached_attachment(&job.folder, &job.email_id, &attachment) {
+                    debug_log(&format!("Could not cache downloaded attachment '{}': {}", attachment.filename, e));
+                }
+                DownloadEvent::Finished { id, attachment }
+            }
+            None => DownloadEvent::Failed {
+                id,
+                error: format!("Attachment '{}' not found in message", job.filename),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    const RAW: &str = "From: alice@example.com\r\nTo: bob@example.com\r\nSubject: Notes\r\nMIME-Version: 1.0\r\n\
+        Content-Type: multipart/mixed; boundary=\"b\"\r\n\r\n--b\r\nContent-Type: text/plain\r\n\r\nSee attached\r\n\
+        --b\r\nContent-Type: text/plain; name=\"notes.txt\"\r\nContent-Disposition: attachment; filename=\"notes.txt\"\r\n\r\n\
+        the notes\r\n--b--\r\n";
+
+    /// Serves `RAW`, or keeps reporting no progress until cancelled when `hold` is set
+    struct FakeSource {
+        hold: bool,
+    }
+
+    impl AttachmentSource for FakeSource {
+        fn fetch_raw_message_chunked<F>(&self, _folder: &str, _uid: &str, _chunk_size: usize, mut on_progress: F) -> Result<Vec<u8>, EmailError>
+        where
+            F: FnMut(usize, usize) -> bool,
+        {
+            if self.hold {
+                while on_progress(0, RAW.len()) {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                return Err(EmailError::ConnectionError("stopped".to_string()));
+            }
+            on_progress(RAW.len(), RAW.len());
+            Ok(RAW.as_bytes().to_vec())
+        }
+
+        fn update_cached_attachment(&self, _folder: &str, _uid: &str, _attachment: &EmailAttachment) -> Result<(), EmailError> {
+            Ok(())
+        }
+    }
+
+    fn email(id: &str) -> Email {
+        let attachment = EmailAttachment {
+            filename: "notes.txt".to_string(),
+            content_type: "text/plain".to_string(),
+            data: Vec::new(),
+            path: None,
+            size: 0,
+        };
+        Email { id: id.to_string(), attachments: vec![attachment], ..Email::new() }
+    }
+
+    fn status(queue: &AttachmentDownloadQueue<FakeSource>, id: &str) -> Option<DownloadStatus> {
+        queue.job_for("me@example.com", "INBOX", id, 0).map(|job| job.status.clone())
+    }
+
+    /// Poll until no job is active, returning what finished meanwhile
+    fn settle(queue: &mut AttachmentDownloadQueue<FakeSource>) -> Vec<(DownloadJob, EmailAttachment)> {
+        let started = Instant::now();
+        let mut finished = Vec::new();
+        while queue.jobs.iter().any(|job| job.status.is_active()) || !queue.cancel_flags.is_empty() {
+            assert!(started.elapsed() < Duration::from_secs(5), "downloads did not finish");
+            finished.extend(queue.poll());
+            thread::sleep(Duration::from_millis(5));
+        }
+        finished
+    }
+
+    #[test]
+    fn downloads_are_queued_once() {
+        let mut queue = AttachmentDownloadQueue::new();
+        let id = queue.enqueue(FakeSource { hold: false }, "me@example.com", &email("1"), 0).unwrap();
+        assert_eq!(queue.enqueue(FakeSource { hold: false }, "me@example.com", &email("1"), 0), Some(id));
+        assert_eq!(queue.enqueue(FakeSource { hold: false }, "me@example.com", &email("1"), 1), None);
+        assert!(status(&queue, "1").is_some_and(|status| status.is_active()));
+
+        let finished = settle(&mut queue);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].0.id, id);
+        assert_eq!(finished[0].1.data, b"the notes");
+        assert_eq!(status(&queue, "1"), Some(DownloadStatus::Completed));
+
+        // Once done, asking again downloads it again
+        let again = queue.enqueue(FakeSource { hold: false }, "me@example.com", &email("1"), 0).unwrap();
+        assert_ne!(again, id);
+        settle(&mut queue);
+    }
+
+    #[test]
+    fn running_and_waiting_downloads_can_be_cancelled() {
+        let mut queue = AttachmentDownloadQueue::new();
+        let running = queue.enqueue(FakeSource { hold: true }, "me@example.com", &email("1"), 0).unwrap();
+        let waiting = queue.enqueue(FakeSource { hold: false }, "me@example.com", &email("2"), 0).unwrap();
+        assert!(queue.cancel(waiting));
+        assert!(queue.cancel(running));
+        assert!(!queue.cancel(running));
+        assert!(!queue.cancel(99));
+
+        assert!(settle(&mut queue).is_empty());
+        assert_eq!(status(&queue, "1"), Some(DownloadStatus::Cancelled));
+        assert_eq!(status(&queue, "2"), Some(DownloadStatus::Cancelled));
+    }
+
+    #[test]
+    fn clear_finished_keeps_active_downloads() {
+        let mut queue = AttachmentDownloadQueue::new();
+        queue.enqueue(FakeSource { hold: false }, "me@example.com", &email("1"), 0).unwrap();
+        settle(&mut queue);
+        let running = queue.enqueue(FakeSource { hold: true }, "me@example.com", &email("2"), 0).unwrap();
+
+        queue.clear_finished();
+        assert_eq!(status(&queue, "1"), None);
+        assert!(status(&queue, "2").is_some_and(|status| status.is_active()));
+
+        queue.cancel(running);
+        settle(&mut queue);
+        queue.clear_finished();
+        assert!(queue.jobs.is_empty());
+    }
+}
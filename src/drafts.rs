@@ -0,0 +1,262 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::database::{Draft, EmailDatabase};
+use crate::email::{debug_log, ensure_writable, Email, EmailAddress, EmailError};
+use crate::transport::{build_draft, ImapBackend};
+
+/// Flags a draft is stored with in the Drafts folder
+const DRAFT_FLAGS: [&str; 2] = ["\\Draft", "\\Seen"];
+
+/// What a draft sync changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DraftSyncReport {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    pub removed: usize,
+    /// Drafts edited both here and elsewhere, waiting for the user to choose
+    pub conflicts: usize,
+}
+
+/// How to settle a draft edited both here and on another device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep this device's version, replacing the server copy
+    Local,
+    /// Keep the server version, dropping local edits
+    Server,
+    /// Keep the server version under the draft's Message-ID and the local one as a new draft
+    Both,
+}
+
+/// A new Message-ID (without angle brackets) for a draft started here
+pub fn new_message_id(account_email: &str) -> String {
+    format!("draft.{}", crate::transport::new_message_id(account_email))
+}
+
+/// A fresh local draft of `email`, not yet uploaded
+pub fn local_draft(message_id: &str, email: &Email, now: i64) -> Draft {
+    let mut email = email.clone();
+    email.headers.insert("Message-ID".to_string(), message_id.to_string());
+    Draft {
+        message_id: message_id.to_string(),
+        email,
+        modified: now,
+        server_uid: None,
+        synced: 0,
+        deleted: false,
+        conflict: None,
+    }
+}
+
+fn database_error(e: anyhow::Error) -> EmailError {
+    EmailError::ConnectionError(format!("Database error: {}", e))
+}
+
+/// The drafts in the Drafts folder by Message-ID. A draft saved without one is keyed by
+/// its UID, which is then written into the local copy so later syncs recognise it.
+fn server_drafts(imap: &mut dyn ImapBackend, folder: &str) -> Result<HashMap<String, (u32, Email)>, EmailError> {
+    let uids = imap.uids(folder)?;
+    let mut drafts = HashMap::new();
+    if uids.is_empty() {
+        return Ok(drafts);
+    }
+    for message in imap.fetch(folder, &uids)? {
+        let Some(parsed) = crate::mime::parse_message(&message.raw) else {
+            debug_log(&format!("Could not parse draft UID {} in {}", message.uid, folder));
+            continue;
+        };
+        let mut email = Email::from_parsed_email(&parsed, &message.uid.to_string(), folder, message.flags)?;
+        let message_id = match email.header("Message-ID").map(|id| id.trim().trim_matches(['<', '>'])) {
+            Some(id) if !id.is_empty() => id.to_string(),
+            _ => format!("draft-uid-{}@localhost", message.uid),
+        };
+        email.headers.insert("Message-ID".to_string(), message_id.clone());
+        drafts.insert(message_id, (message.uid, email));
+    }
+    Ok(drafts)
+}
+
+/// A local draft taken over from the server copy with `uid`
+fn downloaded(message_id: String, uid: u32, email: Email, now: i64) -> Draft {
+    Draft {
+        message_id,
+        email,
+        modified: now,
+        server_uid: Some(uid),
+        synced: now,
+        deleted: false,
+        conflict: None,
+    }
+}
+
+/// Two-way sync between the local drafts and the Drafts folder. Drafts are matched by
+/// Message-ID; a server copy with a different UID than the last sync was saved again
+/// elsewhere. Local edits are uploaded as a new copy replacing the old one, server edits
+/// replace unchanged local drafts, and a draft changed on both sides is kept as a
+/// conflict for `resolve_conflict`.
+pub fn sync_drafts(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    default_from: &EmailAddress,
+    folder: &str,
+    now: i64,
+) -> Result<DraftSyncReport, EmailError> {
+    ensure_writable("sync drafts")?;
+
+    let mut server = server_drafts(imap, folder)?;
+    let mut report = DraftSyncReport::default();
+    let mut uploaded = Vec::new();
+
+    for mut draft in database.get_drafts(account_email).map_err(database_error)? {
+        let on_server = server.remove(&draft.message_id);
+
+        if draft.deleted {
+            if let Some((uid, _)) = on_server {
+                imap.delete(folder, uid)?;
+            }
+            database.delete_draft(account_email, &draft.message_id).map_err(database_error)?;
+            report.removed += 1;
+            continue;
+        }
+
+        if draft.conflict.is_some() {
+            // Still waiting for the user; follow further edits of the server copy
+            if let Some((uid, email)) = on_server {
+                if draft.conflict.as_ref().is_some_and(|(known, _)| *known != uid) {
+                    draft.conflict = Some((uid, email));
+                    database.save_draft(account_email, &draft).map_err(database_error)?;
+                }
+            }
+            report.conflicts += 1;
+            continue;
+        }
+
+        match on_server {
+            Some((uid, _)) if draft.server_uid == Some(uid) => {
+                if draft.has_local_changes() {
+                    upload(imap, folder, &draft, default_from)?;
+                    imap.delete(folder, uid)?;
+                    uploaded.push(draft);
+                }
+            }
+            Some((uid, email)) if draft.has_local_changes() => {
+                debug_log(&format!("Draft {} was edited here and elsewhere", draft.message_id));
+                draft.conflict = Some((uid, email));
+                database.save_draft(account_email, &draft).map_err(database_error)?;
+                report.conflicts += 1;
+            }
+            Some((uid, email)) => {
+                database
+                    .save_draft(account_email, &downloaded(draft.message_id, uid, email, now))
+                    .map_err(database_error)?;
+                report.downloaded += 1;
+            }
+            None if draft.server_uid.is_some() && !draft.has_local_changes() => {
+                // Sent or discarded on another device
+                database.delete_draft(account_email, &draft.message_id).map_err(database_error)?;
+                report.removed += 1;
+            }
+            None => {
+                upload(imap, folder, &draft, default_from)?;
+                uploaded.push(draft);
+            }
+        }
+    }
+
+    // Drafts started on another device
+    for (message_id, (uid, email)) in server {
+        database
+            .save_draft(account_email, &downloaded(message_id, uid, email, now))
+            .map_err(database_error)?;
+        report.downloaded += 1;
+    }
+
+    report.uploaded = uploaded.len();
+    if !uploaded.is_empty() {
+        record_uploaded_uids(imap, database, account_email, folder, uploaded)?;
+    }
+
+    debug_log(&format!("sync_drafts: {:?} for {}/{}", report, account_email, folder));
+    Ok(report)
+}
+
+fn upload(imap: &mut dyn ImapBackend, folder: &str, draft: &Draft, default_from: &EmailAddress) -> Result<(), EmailError> {
+    let raw = build_draft(&draft.email, default_from)?;
+    let flags: Vec<String> = DRAFT_FLAGS.iter().map(|f| f.to_string()).collect();
+    imap.append(folder, &raw, &flags)
+}
+
+/// APPEND does not report the new UID, so look the uploaded drafts up by Message-ID
+fn record_uploaded_uids(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    uploaded: Vec<Draft>,
+) -> Result<(), EmailError> {
+    let known: HashSet<u32> = database
+        .get_drafts(account_email)
+        .map_err(database_error)?
+        .iter()
+        .filter_map(|draft| draft.server_uid)
+        .collect();
+    let new_uids: Vec<u32> = imap.uids(folder)?.into_iter().filter(|uid| !known.contains(uid)).collect();
+    let mut uids = HashMap::new();
+    if !new_uids.is_empty() {
+        for message in imap.fetch_headers(folder, &new_uids)? {
+            let id = crate::mime::parse_message(&message.raw)
+                .and_then(|parsed| parsed.message_id().map(str::to_string));
+            if let Some(id) = id {
+                uids.insert(id, message.uid);
+            }
+        }
+    }
+
+    for mut draft in uploaded {
+        draft.server_uid = uids.get(&draft.message_id).copied();
+        draft.synced = draft.modified;
+        database.save_draft(account_email, &draft).map_err(database_error)?;
+    }
+    Ok(())
+}
+
+/// Settle a conflict found by `sync_drafts`. Only the local database changes; the choice
+/// reaches the server at the next sync.
+pub fn resolve_conflict(
+    database: &EmailDatabase,
+    account_email: &str,
+    message_id: &str,
+    resolution: Resolution,
+    now: i64,
+) -> Result<(), EmailError> {
+    let drafts = database.get_drafts(account_email).map_err(database_error)?;
+    let Some(mut draft) = drafts.into_iter().find(|draft| draft.message_id == message_id) else {
+        return Err(EmailError::NotFound(format!("Draft {}", message_id)));
+    };
+    let Some((uid, server_email)) = draft.conflict.take() else {
+        return Ok(());
+    };
+
+    match resolution {
+        Resolution::Local => {
+            // The server copy is replaced by the local one at the next sync
+            draft.server_uid = Some(uid);
+            draft.modified = draft.modified.max(draft.synced + 1);
+            database.save_draft(account_email, &draft).map_err(database_error)?;
+        }
+        Resolution::Server => {
+            database
+                .save_draft(account_email, &downloaded(draft.message_id, uid, server_email, now))
+                .map_err(database_error)?;
+        }
+        Resolution::Both => {
+            let copy = local_draft(&new_message_id(account_email), &draft.email, now);
+            database.save_draft(account_email, &copy).map_err(database_error)?;
+            database
+                .save_draft(account_email, &downloaded(draft.message_id, uid, server_email, now))
+                .map_err(database_error)?;
+        }
+    }
+    Ok(())
+}
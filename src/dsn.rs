@@ -0,0 +1,190 @@
+use crate::database::{BounceRecord, EmailDatabase, SuppressedAddress};
+use crate::email::{debug_log, Email};
+
+/// One recipient a delivery status notification reports as failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryFailure {
+    pub recipient: String,
+    /// Enhanced status code such as `5.1.1`
+    pub status: String,
+    pub reason: String,
+}
+
+/// What a bounce (RFC 3464 delivery status notification) says about a sent message
+#[derive(Debug, Clone, Default)]
+pub struct DeliveryReport {
+    pub failures: Vec<DeliveryFailure>,
+    /// Message-ID of the message that bounced, from the returned headers
+    pub original_message_id: Option<String>,
+    pub original_subject: Option<String>,
+}
+
+/// Message-IDs are compared without their angle brackets
+pub fn normalize_message_id(id: &str) -> String {
+    id.trim().trim_start_matches('<').trim_end_matches('>').to_string()
+}
+
+/// Whether an enhanced status code says the address itself is dead: bad mailbox,
+/// domain or address syntax (`5.1.x`), or a disabled mailbox (`5.2.1`). A full mailbox,
+/// a message that is too large or a spam rejection is permanent too but says nothing
+/// about the address.
+pub fn is_hard(status: &str) -> bool {
+    status.starts_with("5.1.") || status == "5.2.1"
+}
+
+/// Read a delivery status notification. `None` if the email is not one or reports no
+/// failed recipient (e.g. a delay warning).
+pub fn parse(email: &Email) -> Option<DeliveryReport> {
+    let status = email
+        .attachments
+        .iter()
+        .find(|a| matches!(a.content_type.as_str(), "message/delivery-status" | "message/global-delivery-status"))?;
+    let status = String::from_utf8_lossy(&status.load_data().ok()?).into_owned();
+
+    let failures: Vec<DeliveryFailure> = field_groups(&status)
+        .iter()
+        .filter_map(|fields| {
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.as_str())
+            };
+            let recipient = field("Final-Recipient").or_else(|| field("Original-Recipient"))?;
+            let status = field("Status").unwrap_or_default().trim().to_string();
+            let failed = match field("Action") {
+                Some(action) => action.trim().eq_ignore_ascii_case("failed"),
+                None => status.starts_with('5'),
+            };
+            if !failed {
+                return None;
+            }
+            let reason = field("Diagnostic-Code").map(after_type).unwrap_or_default();
+            Some(DeliveryFailure {
+                recipient: after_type(recipient).trim_matches(['<', '>']).to_string(),
+                reason: if reason.is_empty() { format!("Status {}", status) } else { reason },
+                status,
+            })
+        })
+        .collect();
+    if failures.is_empty() {
+        return None;
+    }
+
+    // The returned message, or just its headers
+    let original = email
+        .attachments
+        .iter()
+        .find(|a| matches!(a.content_type.as_str(), "message/rfc822" | "text/rfc822-headers" | "message/global"))
+        .and_then(|a| a.load_data().ok())
+        .map(|data| String::from_utf8_lossy(&data).into_owned())
+        .unwrap_or_default();
+    let headers = field_groups(&original).into_iter().next().unwrap_or_default();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().to_string())
+    };
+
+    Some(DeliveryReport {
+        failures,
+        original_message_id: header("Message-ID").map(|id| normalize_message_id(&id)),
+        original_subject: header("Subject"),
+    })
+}
+
+/// Header-style fields split into the blank-line separated groups of a status report,
+/// with folded lines joined
+fn field_groups(text: &str) -> Vec<Vec<(String, String)>> {
+    let mut groups = Vec::new();
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !fields.is_empty() {
+                groups.push(std::mem::take(&mut fields));
+            }
+        } else if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    if !fields.is_empty() {
+        groups.push(fields);
+    }
+    groups
+}
+
+/// The value of a typed field such as `rfc822; bob@example.com`, without the type
+fn after_type(value: &str) -> String {
+    value.split_once(';').map_or(value, |(_, rest)| rest).trim().to_string()
+}
+
+/// Find bounces among newly stored emails, match each to the sent message it reports
+/// on, and record the failed recipients against that message. Returns how many sent
+/// messages were marked as bounced.
+pub fn record_bounces(database: &EmailDatabase, account_email: &str, emails: &[Email]) -> usize {
+    let mut marked = 0;
+    for email in emails {
+        let Some(report) = parse(email) else {
+            continue;
+        };
+        let original = match &report.original_message_id {
+            Some(id) => database.find_email_by_message_id(account_email, id),
+            // Without returned headers, the latest message sent to the first failed
+            // recipient before the bounce arrived is the best guess
+            None => database.find_latest_sent_to(account_email, &report.failures[0].recipient, email.date.timestamp()),
+        };
+        let original = match original {
+            Ok(Some(original)) => original,
+            Ok(None) => {
+                debug_log(&format!(
+                    "Bounce {} in {} (for \"{}\") matches no stored message",
+                    email.id,
+                    email.folder,
+                    report.original_subject.as_deref().unwrap_or_default()
+                ));
+                continue;
+            }
+            Err(e) => {
+                debug_log(&format!("Failed to look up the message bounce {} reports on: {}", email.id, e));
+                continue;
+            }
+        };
+        let Some(message_id) = original.header("Message-ID").map(normalize_message_id) else {
+            continue;
+        };
+
+        for failure in &report.failures {
+            let record = BounceRecord {
+                message_id: message_id.clone(),
+                recipient: failure.recipient.clone(),
+                status: failure.status.clone(),
+                reason: failure.reason.clone(),
+                bounce_folder: email.folder.clone(),
+                bounce_uid: email.id.parse().unwrap_or_default(),
+                detected_at: email.date.timestamp(),
+            };
+            if let Err(e) = database.record_bounce(account_email, &record) {
+                debug_log(&format!("Failed to record bounce for {}: {}", failure.recipient, e));
+            }
+            if is_hard(&failure.status) {
+                let suppressed = SuppressedAddress {
+                    address: failure.recipient.to_lowercase(),
+                    status: failure.status.clone(),
+                    reason: failure.reason.clone(),
+                    bounced_at: record.detected_at,
+                };
+                if let Err(e) = database.suppress_address(account_email, &suppressed) {
+                    debug_log(&format!("Failed to suppress {}: {}", failure.recipient, e));
+                }
+            }
+        }
+        marked += 1;
+    }
+    marked
+}
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, mpsc};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::Write;
@@ -7,35 +8,17 @@ use std::io::Write;
 use anyhow::Result;
 use chrono::{DateTime, Local, Utc};
 use imap::Session;
-use lettre::message::{Mailbox, MultiPart, SinglePart, Attachment};
+use lettre::message::Mailbox;
 use lettre::transport::smtp::authentication::Credentials;
-use lettre::{Address, Message, SmtpTransport, Transport};
+use lettre::{Address, SmtpTransport};
 use native_tls::{TlsConnector, TlsStream};
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
 
 use crate::config::{EmailAccount, ImapSecurity, SmtpSecurity};
 use crate::credentials::SecureCredentials;
-use crate::database::EmailDatabase;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FolderMetadata {
-    pub last_uid: u32,
-    pub total_messages: u32,
-    pub last_sync: DateTime<Local>,
-    pub downloaded_uids: HashSet<u32>,
-}
-
-impl FolderMetadata {
-    fn new() -> Self {
-        Self {
-            last_uid: 0,
-            total_messages: 0,
-            last_sync: Local::now(),
-            downloaded_uids: std::collections::HashSet::new(),
-        }
-    }
-}
+use crate::database::{ContactSummary, EmailDatabase};
+use crate::transport::{ImapBackend, SmtpBackend};
 
 // Helper function to log debug information to a file
 pub fn debug_log(message: &str) {
@@ -124,6 +107,135 @@ fn parse_email_addresses(value: &str) -> Vec<EmailAddress> {
     addresses
 }
 
+/// Turn a list of UIDs into a compact IMAP sequence set such as `1:4,7,9:10`
+pub fn compress_uid_set(uids: &[u32]) -> String {
+    let mut sorted: Vec<u32> = uids.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut iter = sorted.into_iter();
+    let Some(first) = iter.next() else {
+        return String::new();
+    };
+    let (mut start, mut end) = (first, first);
+    for uid in iter {
+        if uid == end + 1 {
+            end = uid;
+            continue;
+        }
+        ranges.push(if start == end { start.to_string() } else { format!("{}:{}", start, end) });
+        start = uid;
+        end = uid;
+    }
+    ranges.push(if start == end { start.to_string() } else { format!("{}:{}", start, end) });
+    ranges.join(",")
+}
+
+// Global sync tracker for efficient new email detection
+static GLOBAL_SYNC_TIMESTAMPS: std::sync::OnceLock<std::sync::RwLock<HashMap<String, DateTime<Utc>>>> = std::sync::OnceLock::new();
+
+fn get_global_sync_timestamps() -> &'static std::sync::RwLock<HashMap<String, DateTime<Utc>>> {
+    GLOBAL_SYNC_TIMESTAMPS.get_or_init(|| std::sync::RwLock::new(HashMap::new()))
+}
+
+pub fn update_global_sync_timestamp(account_email: &str, folder: &str, timestamp: DateTime<Utc>) {
+    let key = format!("{}:{}", account_email, folder);
+    if let Ok(mut timestamps) = get_global_sync_timestamps().write() {
+        timestamps.insert(key, timestamp);
+    }
+}
+
+pub fn has_new_emails_since_global(account_email: &str, folder: &str, ui_timestamp: DateTime<Utc>) -> bool {
+    let key = format!("{}:{}", account_email, folder);
+    if let Ok(timestamps) = get_global_sync_timestamps().read() {
+        if let Some(latest_sync) = timestamps.get(&key) {
+            *latest_sync > ui_timestamp
+        } else {
+            true // If we don't have a sync timestamp, assume there might be new emails
+        }
+    } else {
+        true
+    }
+}
+
+pub fn get_global_sync_timestamp(account_email: &str, folder: &str) -> Option<DateTime<Utc>> {
+    let key = format!("{}:{}", account_email, folder);
+    if let Ok(timestamps) = get_global_sync_timestamps().read() {
+        timestamps.get(&key).copied()
+    } else {
+        None
+    }
+}
+
+/// Process-wide read-only switch: no sends, flag changes, deletes or moves, and
+/// folders are opened with EXAMINE so fetching never marks mail as seen
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+pub(crate) fn ensure_writable(action: &str) -> Result<(), EmailError> {
+    if is_read_only() {
+        debug_log(&format!("Blocked '{}' in read-only mode", action));
+        return Err(EmailError::ReadOnly(action.to_string()));
+    }
+    Ok(())
+}
+
+/// Process-wide offline switch (`--no-network`): nothing connects to a mail server and
+/// everything shown comes from the local database
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Accounts the user chose not to connect this session
+static OFFLINE_ACCOUNTS: std::sync::OnceLock<std::sync::RwLock<HashSet<String>>> = std::sync::OnceLock::new();
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Keep one account offline for the rest of the session
+pub fn set_account_offline(account_email: &str) {
+    if let Ok(mut accounts) = OFFLINE_ACCOUNTS.get_or_init(Default::default).write() {
+        accounts.insert(account_email.to_string());
+    }
+}
+
+pub fn is_account_offline(account_email: &str) -> bool {
+    is_offline()
+        || OFFLINE_ACCOUNTS
+            .get()
+            .and_then(|accounts| accounts.read().ok().map(|accounts| accounts.contains(account_email)))
+            .unwrap_or(false)
+}
+
+pub(crate) fn ensure_online(account_email: &str, server: &str) -> Result<(), EmailError> {
+    if is_account_offline(account_email) {
+        debug_log(&format!("Not connecting to {} for {}: offline", server, account_email));
+        return Err(EmailError::ConnectionError(format!("Offline: not connecting to {}", server)));
+    }
+    Ok(())
+}
+
+/// Open a folder for reading, without write access in read-only mode
+fn open_folder<T: std::io::Read + std::io::Write>(session: &mut Session<T>, folder: &str) -> imap::error::Result<imap::types::Mailbox> {
+    let folder = crate::utf7::encode(folder);
+    if is_read_only() {
+        session.examine(folder)
+    } else {
+        session.select(folder)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum EmailError {
     #[error("IMAP error: {0}")]
@@ -140,9 +252,18 @@ pub enum EmailError {
     
     #[error("Connection error: {0}")]
     ConnectionError(String),
+
+    #[error("Cannot {0} in read-only mode")]
+    ReadOnly(String),
+
+    #[error("Authentication failed: {0}")]
+    AuthError(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EmailAddress {
     pub name: Option<String>,
     pub address: String,
@@ -171,15 +292,49 @@ impl From<EmailAddress> for Mailbox {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EmailAttachment {
     pub filename: String,
     pub content_type: String,
     #[serde(with = "serde_bytes")]
     pub data: Vec<u8>,
+    /// Cache file holding the payload when it is too large to keep in memory
+    #[serde(default)]
+    pub path: Option<std::path::PathBuf>,
+    /// Size of the payload in bytes, also known while it is in a cache file or after it
+    /// was evicted
+    #[serde(default)]
+    pub size: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl EmailAttachment {
+    /// Whether the payload is available locally, either in memory or in a cache file
+    pub fn is_available(&self) -> bool {
+        !self.data.is_empty() || self.path.is_some()
+    }
+
+    /// Size of the payload in bytes
+    pub fn size(&self) -> usize {
+        if self.data.is_empty() {
+            self.size
+        } else {
+            self.data.len()
+        }
+    }
+
+    /// Read the payload, going to the cache file if it was spilled to disk
+    pub fn load_data(&self) -> std::io::Result<Vec<u8>> {
+        match &self.path {
+            Some(path) if self.data.is_empty() => fs::read(path),
+            _ => Ok(self.data.clone()),
+        }
+    }
+}
+
+/// IMAP keyword that queues a message for reading later, kept apart from \Seen
+pub const READ_LATER_KEYWORD: &str = "$ReadLater";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Email {
     pub id: String,
     pub subject: String,
@@ -196,6 +351,13 @@ pub struct Email {
     pub headers: HashMap<String, String>,
     pub seen: bool,
     pub folder: String,
+    /// Preview line for the list, stored at sync time (see `snippet`)
+    #[serde(default)]
+    pub snippet: Option<String>,
+    /// Gmail's id of the message, the same under every label (folder) it shows up in;
+    /// set while syncing from Gmail only
+    #[serde(default)]
+    pub gmail_id: Option<String>,
 }
 
 // Custom serialization for DateTime<Local>
@@ -236,9 +398,42 @@ impl Email {
             headers: HashMap::new(),
             seen: false,
             folder: "INBOX".to_string(),
+            snippet: None,
+            gmail_id: None,
         }
     }
     
+    /// Look up a header value by name, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Priority the sender set, from `X-Priority`, `Importance`, `Priority` or
+    /// `X-MSMail-Priority`. `None` when the message does not say.
+    pub fn priority(&self) -> Option<crate::config::Priority> {
+        use crate::config::Priority;
+        if let Some(value) = self.header("X-Priority") {
+            return match value.trim().chars().next() {
+                Some('1' | '2') => Some(Priority::High),
+                Some('3') => Some(Priority::Normal),
+                Some('4' | '5') => Some(Priority::Low),
+                _ => None,
+            };
+        }
+        let named = ["Importance", "Priority", "X-MSMail-Priority"]
+            .iter()
+            .find_map(|name| self.header(name))?;
+        match named.trim().to_ascii_lowercase().as_str() {
+            "high" | "urgent" => Some(Priority::High),
+            "normal" => Some(Priority::Normal),
+            "low" | "non-urgent" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
     /// Get Reply-To addresses from headers
     pub fn reply_to(&self) -> Vec<EmailAddress> {
         if let Some(reply_to_str) = self.headers.get("Reply-To") {
@@ -375,7 +570,12 @@ impl Email {
             };
             
             if let Some(value_str) = value {
-                email.headers.insert(name.clone(), value_str.clone());
+                // The receiving server's results come first; later ones could be forged
+                let repeated_results = name.eq_ignore_ascii_case("Authentication-Results")
+                    && email.header("Authentication-Results").is_some();
+                if !repeated_results {
+                    email.headers.insert(name.clone(), value_str.clone());
+                }
                 debug_log(&format!("Header[{}]: '{}' = '{}'", header_count, name, value_str));
                 
                 // Parse basic from/to information from headers
@@ -471,7 +671,7 @@ impl Email {
             }
             
             // Check if this part is an attachment
-            if let Some(attachment) = Self::extract_attachment_from_part(part) {
+            if let Some(attachment) = crate::mime::attachment_from_part(part) {
                 debug_log(&format!("=== FOUND ATTACHMENT IN PART {}: {} ===", i, attachment.filename));
                 attachments.push(attachment);
             } else {
@@ -482,241 +682,6 @@ impl Email {
         debug_log(&format!("=== TOTAL ATTACHMENTS FOUND: {} ===", attachments.len()));
         attachments
     }
-    
-    /// Extract attachment from a message part if it is an attachment
-    fn extract_attachment_from_part(part: &mail_parser::MessagePart) -> Option<EmailAttachment> {
-        debug_log("Checking part for attachment...");
-        
-        // Check if this part has a filename (indicating it's an attachment)
-        let mut filename = None;
-        let mut content_type = "application/octet-stream".to_string();
-        let mut is_attachment = false;
-        
-        // Look through headers to find content-disposition and content-type
-        debug_log(&format!("Processing {} headers for attachment detection", part.headers.len()));
-        for (header_idx, header) in part.headers.iter().enumerate() {
-            // Convert header name to string for comparison
-            let header_name_str = format!("{:?}", header.name).to_lowercase();
-            debug_log(&format!("Header {}: {} = {:?}", header_idx, header_name_str, header.value));
-            
-            // Try different ways to extract header value
-            let header_value_str = match &header.value {
-                mail_parser::HeaderValue::Text(text) => {
-                    debug_log(&format!("Header value is Text: {}", text.as_ref()));
-                    Some(text.as_ref())
-                }
-                mail_parser::HeaderValue::TextList(list) => {
-                    if let Some(first) = list.first() {
-                        debug_log(&format!("Header value is TextList: {}", first.as_ref()));
-                        Some(first.as_ref())
-                    } else {
-                        debug_log("Header value is empty TextList");
-                        None
-                    }
-                }
-                _ => {
-                    debug_log(&format!("Header value is structured type: {:?}", header.value));
-                    None
-                }
-            };
-            
-            // Handle structured headers specially
-            match &header.value {
-                mail_parser::HeaderValue::ContentType(ct) => {
-                    debug_log(&format!("Found structured ContentType: {:?}", ct));
-                    // Build full content type string
-                    if let Some(subtype) = ct.subtype() {
-                        content_type = format!("{}/{}", ct.ctype(), subtype);
-                    } else {
-                        content_type = ct.ctype().to_string();
-                    }
-                    debug_log(&format!("Extracted content type: {}", content_type));
-                    
-                    // Check for name parameter in content-type
-                    if let Some(name) = ct.attribute("name") {
-                        filename = Some(name.to_string());
-                        debug_log(&format!("Found filename in content-type: {}", name));
-                    }
-                    // Also check for filename parameter in content-type
-                    if filename.is_none() {
-                        if let Some(fname) = ct.attribute("filename") {
-                            filename = Some(fname.to_string());
-                            debug_log(&format!("Found filename parameter in content-type: {}", fname));
-                        }
-                    }
-                }
-                _ => {
-                    // Handle text-based headers
-                    if let Some(header_value) = header_value_str {
-                        debug_log(&format!("Checking header: {} = {}", header_name_str, header_value));
-                        
-                        if header_name_str.contains("contentdisposition") || header_name_str.contains("content-disposition") {
-                            debug_log("Found content-disposition header");
-                            // Simple parsing for filename parameter
-                            if header_value.contains("attachment") || header_value.contains("inline") {
-                                is_attachment = true;
-                                debug_log("Part is marked as attachment or inline");
-                                
-                                // Try multiple filename patterns
-                                let filename_patterns = [
-                                    "filename=",
-                                    "filename*=",
-                                    "name=",
-                                ];
-                                
-                                for pattern in &filename_patterns {
-                                    if filename.is_none() {
-                                        if let Some(start) = header_value.find(pattern) {
-                                            let filename_part = &header_value[start + pattern.len()..];
-                                            let filename_part = filename_part.trim_start_matches('"').trim_start_matches('\'');
-                                            
-                                            let extracted_name = if let Some(end) = filename_part.find('"') {
-                                                filename_part[..end].to_string()
-                                            } else if let Some(end) = filename_part.find('\'') {
-                                                filename_part[..end].to_string()
-                                            } else if let Some(end) = filename_part.find(';') {
-                                                filename_part[..end].trim().to_string()
-                                            } else {
-                                                filename_part.trim().to_string()
-                                            };
-                                            
-                                            if !extracted_name.is_empty() {
-                                                filename = Some(extracted_name);
-                                                debug_log(&format!("Extracted filename using pattern '{}': {:?}", pattern, filename));
-                                                break;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        } else if header_name_str.contains("contenttype") || header_name_str.contains("content-type") {
-                            debug_log("Found content-type header");
-                            if let Some(semicolon_pos) = header_value.find(';') {
-                                content_type = header_value[..semicolon_pos].trim().to_string();
-                            } else {
-                                content_type = header_value.trim().to_string();
-                            }
-                            debug_log(&format!("Content type: {}", content_type));
-                            
-                            // Also check for name parameter in content-type
-                            if filename.is_none() {
-                                let name_patterns = ["name=", "filename="];
-                                
-                                for pattern in &name_patterns {
-                                    if let Some(start) = header_value.find(pattern) {
-                                        let name_part = &header_value[start + pattern.len()..];
-                                        let name_part = name_part.trim_start_matches('"').trim_start_matches('\'');
-                                        
-                                        let extracted_name = if let Some(end) = name_part.find('"') {
-                                            name_part[..end].to_string()
-                                        } else if let Some(end) = name_part.find('\'') {
-                                            name_part[..end].to_string()
-                                        } else if let Some(end) = name_part.find(';') {
-                                            name_part[..end].trim().to_string()
-                                        } else {
-                                            name_part.trim().to_string()
-                                        };
-                                        
-                                        if !extracted_name.is_empty() {
-                                            filename = Some(extracted_name);
-                                            debug_log(&format!("Extracted filename from content-type using pattern '{}': {:?}", pattern, filename));
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        // If we have a filename or it's marked as attachment, try to extract it
-        // Be conservative: require explicit attachment markers or non-text content
-        let is_likely_attachment = is_attachment || 
-            (filename.is_some() && !content_type.starts_with("text/plain")) || 
-            (!content_type.starts_with("text/") && 
-             !content_type.starts_with("multipart/") && 
-             content_type != "application/octet-stream");
-        
-        if is_likely_attachment {
-            debug_log(&format!("Final attachment analysis: content_type={}, filename={:?}, is_attachment={}", 
-                content_type, filename, is_attachment));
-            
-            let final_filename = filename.unwrap_or_else(|| {
-                debug_log("WARNING: No filename found in email headers - this should not happen for proper attachments");
-                // Only use simple fallback - the real fix is to extract the filename properly
-                match content_type.as_str() {
-                    "application/pdf" => "document.pdf".to_string(),
-                    "image/jpeg" => "image.jpg".to_string(),
-                    "image/png" => "image.png".to_string(),
-                    "image/gif" => "image.gif".to_string(),
-                    "application/zip" => "archive.zip".to_string(),
-                    "application/msword" => "document.doc".to_string(),
-                    "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "document.docx".to_string(),
-                    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "spreadsheet.xlsx".to_string(),
-                    "application/vnd.ms-excel" => "spreadsheet.xls".to_string(),
-                    "text/plain" => "text.txt".to_string(),
-                    "text/csv" => "data.csv".to_string(),
-                    _ => {
-                        let extension = content_type.split('/').last().unwrap_or("bin");
-                        format!("attachment.{}", extension)
-                    }
-                }
-            });
-            
-            debug_log(&format!("Treating as attachment: content_type={}, filename={}, is_attachment={}", 
-                content_type, final_filename, is_attachment));
-            
-            // Get the body data
-            let data = match &part.body {
-                mail_parser::PartType::Text(text) => {
-                    debug_log("Part body is text");
-                    text.as_bytes().to_vec()
-                }
-                mail_parser::PartType::Html(html) => {
-                    debug_log("Part body is HTML");
-                    html.as_bytes().to_vec()
-                }
-                mail_parser::PartType::Binary(binary) => {
-                    debug_log("Part body is binary");
-                    binary.to_vec()
-                }
-                mail_parser::PartType::InlineBinary(binary) => {
-                    debug_log("Part body is inline binary");
-                    binary.to_vec()
-                }
-                mail_parser::PartType::Message(_) => {
-                    debug_log("Part body is nested message - skipping");
-                    Vec::new()
-                }
-                mail_parser::PartType::Multipart(_) => {
-                    debug_log("Part body is multipart container - skipping");
-                    Vec::new()
-                }
-            };
-            
-            debug_log(&format!("Extracted {} bytes of data", data.len()));
-            
-            if !data.is_empty() {
-                debug_log(&format!("Creating attachment: {} ({} bytes, {})", 
-                    final_filename, data.len(), content_type));
-                
-                return Some(EmailAttachment {
-                    filename: final_filename,
-                    content_type,
-                    data,
-                });
-            } else {
-                debug_log("No data found in part body");
-            }
-        } else {
-            debug_log(&format!("Part not treated as attachment: content_type={}, filename={:?}, is_attachment={}", 
-                content_type, filename, is_attachment));
-        }
-        
-        None
-    }
 }
 
 #[derive(Clone)]
@@ -731,17 +696,15 @@ impl EmailClient {
         init_debug_log();
         debug_log(&format!("Creating EmailClient for account: {}", account.email));
         
-        let cache_dir = format!("{}/.cache/tuimail/{}", 
-            dirs::home_dir().unwrap_or_default().display(), 
-            account.email.replace('@', "_at_").replace('.', "_"));
+        let cache_dir = crate::profile::account_cache_dir(&account.email);
         
         // Create cache directory if it doesn't exist
         if let Err(e) = fs::create_dir_all(&cache_dir) {
-            debug_log(&format!("Warning: Could not create cache directory {}: {}", cache_dir, e));
+            debug_log(&format!("Warning: Could not create cache directory {}: {}", cache_dir.display(), e));
         }
 
         // Set up database path
-        let db_path = std::path::PathBuf::from(&cache_dir).join("emails.db");
+        let db_path = cache_dir.join("emails.db");
         
         Self { account, credentials, db_path }
     }
@@ -751,160 +714,131 @@ impl EmailClient {
             .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
     }
     
-    fn load_folder_metadata(&self, folder: &str) -> FolderMetadata {
-        match self.get_database() {
-            Ok(db) => {
-                match db.load_folder_metadata(&self.account.email, folder) {
-                    Ok((last_uid, total_messages, _last_sync)) => {
-                        debug_log(&format!("Loaded metadata from database: last_uid={}, total_messages={}", last_uid, total_messages));
-                        FolderMetadata {
-                            last_uid,
-                            total_messages,
-                            last_sync: Local::now(),
-                            downloaded_uids: std::collections::HashSet::new(),
-                        }
-                    }
-                    Err(e) => {
-                        debug_log(&format!("Failed to load metadata from database: {}", e));
-                        FolderMetadata::new()
-                    }
-                }
-            }
-            Err(e) => {
-                debug_log(&format!("Failed to open database for metadata: {}", e));
-                FolderMetadata::new()
-            }
-        }
-    }
-    
-    fn save_folder_metadata(&self, folder: &str, metadata: &FolderMetadata) {
-        match self.get_database() {
-            Ok(db) => {
-                if let Err(e) = db.save_folder_metadata(&self.account.email, folder, metadata.last_uid, metadata.total_messages) {
-                    debug_log(&format!("Warning: Could not save folder metadata to database: {}", e));
-                } else {
-                    debug_log(&format!("Saved metadata to database: last_uid={}, total_messages={}", metadata.last_uid, metadata.total_messages));
-                }
-            }
-            Err(e) => {
-                debug_log(&format!("Failed to open database for saving metadata: {}", e));
-            }
-        }
-    }
-    
-    fn load_cached_emails(&self, folder: &str) -> Vec<Email> {
-        match self.get_database() {
-            Ok(db) => {
-                match db.load_emails(&self.account.email, folder) {
-                    Ok(emails) => {
-                        debug_log(&format!("Loaded {} emails from database for folder: {}", emails.len(), folder));
-                        emails
-                    }
-                    Err(e) => {
-                        debug_log(&format!("Failed to load emails from database: {}", e));
-                        Vec::new()
-                    }
-                }
-            }
-            Err(e) => {
-                debug_log(&format!("Failed to open database: {}", e));
-                Vec::new()
-            }
-        }
-    }
-    
-    fn save_cached_emails(&self, folder: &str, emails: &[Email]) {
-        match self.get_database() {
-            Ok(db) => {
-                if let Err(e) = db.save_emails(&self.account.email, folder, emails) {
-                    log::warn!("Could not save emails to database: {}", e);
-                    debug_log(&format!("Database save error: {}", e));
-                } else {
-                    debug_log(&format!("Saved {} emails to database for folder: {}", emails.len(), folder));
-                }
-            }
-            Err(e) => {
-                log::warn!("Could not open database for saving: {}", e);
-                debug_log(&format!("Database open error: {}", e));
-            }
-        }
-    }
-    
     #[allow(dead_code)]
     pub fn force_full_sync(&self, folder: &str) -> Result<Vec<Email>, EmailError> {
         debug_log(&format!("force_full_sync called for folder: {}", folder));
-        
-        // Reset metadata to force full sync
-        let mut metadata = FolderMetadata::new();
-        
-        let new_emails = match self.account.imap_security {
-            ImapSecurity::SSL | ImapSecurity::StartTLS => {
-                self.fetch_emails_incrementally_secure(folder, &mut metadata)
-            }
-            ImapSecurity::None => {
-                self.fetch_emails_incrementally_plain(folder, &mut metadata)
-            }
-        };
-        
-        match new_emails {
-            Ok(emails) => {
-                // Save the emails and metadata
-                self.save_cached_emails(folder, &emails);
-                self.save_folder_metadata(folder, &metadata);
-                debug_log(&format!("Full sync completed: {} emails", emails.len()));
-                Ok(emails)
-            }
-            Err(e) => {
-                debug_log(&format!("Full sync failed: {}", e));
-                Err(e)
-            }
-        }
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::initial_sync(imap, &db, &self.account.email, folder))
     }
     
-    fn merge_emails(&self, cached: Vec<Email>, new: Vec<Email>) -> Vec<Email> {
-        let mut email_map: HashMap<String, Email> = HashMap::new();
-        
-        // Add cached emails first
-        for email in cached {
-            email_map.insert(email.id.clone(), email);
+    /// Local Maildir accounts have no server, and Gmail API accounts are not reached over
+    /// IMAP; the operations that need an IMAP session fail here
+    fn ensure_imap_account(&self) -> Result<(), EmailError> {
+        if self.account.gmail_api.is_some() {
+            return Err(EmailError::ImapError(format!("{} uses the Gmail API, not IMAP", self.account.email)));
         }
-        
-        // Add/update with new emails
-        for email in new {
-            email_map.insert(email.id.clone(), email);
+        if self.account.exchange.is_some() {
+            return Err(EmailError::ImapError(format!("{} is an Exchange account reached through EWS, not IMAP", self.account.email)));
+        }
+        match &self.account.maildir {
+            Some(path) => Err(EmailError::ImapError(format!("{} is a local Maildir account ({}) without an IMAP server", self.account.email, path))),
+            None => Ok(()),
         }
-        
-        // Convert back to vector and sort by date
-        let mut emails: Vec<Email> = email_map.into_values().collect();
-        emails.sort_by(|a, b| b.date.cmp(&a.date));
-        
-        emails
     }
-    
+
+    /// The Gmail API connection of an account set up for it, with a current access token
+    fn gmail_backend<'a>(&self, db: &'a EmailDatabase, gmail: &crate::config::GmailApiConfig) -> Result<crate::gmail::GmailBackend<'a, crate::gmail::GmailHttpClient>, EmailError> {
+        ensure_online(&self.account.email, "gmail.googleapis.com")?;
+        let token = crate::gmail::access_token(&self.account.email, &gmail.client_id, &self.credentials)?;
+        Ok(crate::gmail::GmailBackend::new(crate::gmail::GmailHttpClient::new(token), db, &self.account.email))
+    }
+
+    /// The Maildir tree of a local account
+    fn maildir_store(&self) -> Option<Result<crate::maildir::MaildirStore, EmailError>> {
+        self.account.maildir_path().map(crate::maildir::MaildirStore::open)
+    }
+
+    /// The EWS connection of an Exchange account, signing in with the IMAP username and
+    /// password
+    fn ews_backend<'a>(&self, db: &'a EmailDatabase, exchange: &crate::config::ExchangeConfig) -> Result<crate::ews::EwsBackend<'a, crate::ews::EwsHttpClient>, EmailError> {
+        ensure_online(&self.account.email, &exchange.url)?;
+        let password = self.account.get_imap_password(&self.credentials)
+            .map_err(|e| EmailError::AuthError(format!("Failed to get IMAP password: {}", e)))?;
+        let http = crate::ews::EwsHttpClient::new(&exchange.url, &self.account.imap_username, &password)
+            .map_err(|e| EmailError::ConnectionError(e.to_string()))?;
+        Ok(crate::ews::EwsBackend::new(http, db, &self.account.email))
+    }
+
+    /// The account's CalDAV calendar, signing in with the IMAP password
+    pub fn caldav(&self) -> Result<crate::caldav::CalDav<crate::caldav::CalDavHttpClient>, EmailError> {
+        let caldav = self.account.caldav.as_ref()
+            .ok_or_else(|| EmailError::ConnectionError("No CalDAV calendar is set up for this account".to_string()))?;
+        ensure_online(&self.account.email, &caldav.url)?;
+        let password = self.account.get_imap_password(&self.credentials)
+            .map_err(|e| EmailError::AuthError(format!("Failed to get IMAP password: {}", e)))?;
+        let username = caldav.username.as_deref().unwrap_or(&self.account.imap_username);
+        let http = crate::caldav::CalDavHttpClient::new(&caldav.url, username, &password)
+            .map_err(|e| EmailError::ConnectionError(e.to_string()))?;
+        Ok(crate::caldav::CalDav::new(http))
+    }
+
+    /// People in the account's LDAP directory whose name or address starts with `text`
+    pub fn search_directory(&self, text: &str) -> Result<Vec<ContactSummary>, EmailError> {
+        let ldap = self.account.ldap.as_ref()
+            .ok_or_else(|| EmailError::ConnectionError("No LDAP directory is set up for this account".to_string()))?;
+        let template = ldap.filter.as_deref().unwrap_or(crate::ldap::DEFAULT_FILTER);
+        let filter = crate::ldap::filter(&template.replace("{}", &crate::ldap::escape(text.trim())))
+            .map_err(|e| EmailError::ConnectionError(format!("Invalid LDAP filter: {}", e)))?;
+        ensure_online(&self.account.email, &ldap.url)?;
+
+        let mut directory = crate::ldap::connect(&ldap.url)?;
+        if let Some(bind_dn) = &ldap.bind_dn {
+            let password = self.account.get_imap_password(&self.credentials)
+                .map_err(|e| EmailError::AuthError(format!("Failed to get IMAP password: {}", e)))?;
+            directory.bind(bind_dn, &password)?;
+        }
+        let entries = directory.search(&ldap.base_dn, &filter, &["displayName", "cn", "mail"])?;
+        directory.unbind();
+
+        Ok(entries
+            .iter()
+            .flat_map(|entry| {
+                let name = entry.first("displayName").or_else(|| entry.first("cn")).map(str::to_string);
+                entry.values("mail").iter().map(move |address| ContactSummary {
+                    address: address.clone(),
+                    name: name.clone(),
+                    last_activity: 0,
+                    message_count: 0,
+                    saved: false,
+                })
+            })
+            .collect())
+    }
+
+    /// Accounts read through a backend of their own rather than an IMAP session, which
+    /// are synced like a server that never drops the connection
+    fn has_direct_backend(&self) -> bool {
+        self.account.maildir.is_some() || self.account.gmail_api.is_some() || self.account.exchange.is_some()
+    }
+
     fn connect_imap_secure(&self) -> Result<Session<TlsStream<std::net::TcpStream>>, EmailError> {
+        self.ensure_imap_account()?;
         let domain = &self.account.imap_server;
         let port = self.account.imap_port;
+        ensure_online(&self.account.email, domain)?;
         let username = &self.account.imap_username;
         let password = self.account.get_imap_password(&self.credentials)
-            .map_err(|e| EmailError::ImapError(format!("Failed to get IMAP password: {}", e)))?;
+            .map_err(|e| EmailError::AuthError(format!("Failed to get IMAP password: {}", e)))?;
         
         let tls = TlsConnector::builder().build()?;
         let client = imap::connect((domain.as_str(), port), domain, &tls)
-            .map_err(|e| EmailError::ImapError(e.to_string()))?;
+            .map_err(|e| EmailError::ConnectionError(e.to_string()))?;
         
         let session = client
             .login(username, &password)
-            .map_err(|e| EmailError::ImapError(e.0.to_string()))?;
+            .map_err(|e| EmailError::AuthError(e.0.to_string()))?;
         
         Ok(session)
     }
     
     fn connect_imap_plain(&self) -> Result<Session<std::net::TcpStream>, EmailError> {
+        self.ensure_imap_account()?;
         let domain = &self.account.imap_server;
         let port = self.account.imap_port;
+        ensure_online(&self.account.email, domain)?;
         let username = &self.account.imap_username;
         let password = self.account.get_imap_password(&self.credentials)
-            .map_err(|e| EmailError::ImapError(format!("Failed to get IMAP password: {}", e)))?;
+            .map_err(|e| EmailError::AuthError(format!("Failed to get IMAP password: {}", e)))?;
         
         let tcp_stream = std::net::TcpStream::connect((domain.as_str(), port))
             .map_err(|e| EmailError::IoError(e))?;
@@ -912,25 +846,31 @@ impl EmailClient {
         let client = imap::Client::new(tcp_stream);
         let session = client
             .login(username, &password)
-            .map_err(|e| EmailError::ImapError(e.0.to_string()))?;
+            .map_err(|e| EmailError::AuthError(e.0.to_string()))?;
         
         Ok(session)
     }
     
     pub fn list_folders(&self) -> Result<Vec<String>, EmailError> {
-        match self.account.imap_security {
+        if let Some(store) = self.maildir_store() {
+            return store?.list_folders();
+        }
+        if let Some(gmail) = &self.account.gmail_api {
+            let db = self.get_database()?;
+            return self.gmail_backend(&db, gmail)?.list_folders();
+        }
+        if let Some(exchange) = &self.account.exchange {
+            let db = self.get_database()?;
+            return self.ews_backend(&db, exchange)?.list_folders();
+        }
+        let folder_names: Vec<String> = match self.account.imap_security {
             ImapSecurity::SSL | ImapSecurity::StartTLS => {
                 let mut session = self.connect_imap_secure()?;
                 let folders = session
                     .list(None, Some("*"))
                     .map_err(|e| EmailError::ImapError(e.to_string()))?;
                 
-                let folder_names = folders
-                    .iter()
-                    .map(|f| String::from_utf8_lossy(f.name().as_bytes()).into_owned())
-                    .collect();
-                
-                Ok(folder_names)
+                folders.iter().map(|f| crate::utf7::decode(f.name())).collect()
             }
             ImapSecurity::None => {
                 let mut session = self.connect_imap_plain()?;
@@ -938,379 +878,76 @@ impl EmailClient {
                     .list(None, Some("*"))
                     .map_err(|e| EmailError::ImapError(e.to_string()))?;
                 
-                let folder_names = folders
-                    .iter()
-                    .map(|f| String::from_utf8_lossy(f.name().as_bytes()).into_owned())
-                    .collect();
-                
-                Ok(folder_names)
-            }
-        }
-    }
-    
-    pub fn fetch_emails(&self, folder: &str, limit: usize) -> Result<Vec<Email>, EmailError> {
-        debug_log(&format!("fetch_emails called: folder='{}', limit={}", folder, limit));
-        
-        // Load cached emails and metadata
-        let cached_emails = self.load_cached_emails(folder);
-        let mut metadata = self.load_folder_metadata(folder);
-        debug_log(&format!("Loaded {} cached emails, last_uid={}, total_messages={}", 
-            cached_emails.len(), metadata.last_uid, metadata.total_messages));
-        
-        // Fetch new emails from server incrementally
-        debug_log(&format!("Fetching new emails from server using security: {:?}", self.account.imap_security));
-        let new_emails = match self.account.imap_security {
-            ImapSecurity::SSL | ImapSecurity::StartTLS => {
-                self.fetch_emails_incrementally_secure(folder, &mut metadata)
-            }
-            ImapSecurity::None => {
-                self.fetch_emails_incrementally_plain(folder, &mut metadata)
+                folders.iter().map(|f| crate::utf7::decode(f.name())).collect()
             }
         };
-        
-        match new_emails {
-            Ok(new) => {
-                debug_log(&format!("Successfully fetched {} new emails from server", new.len()));
-                
-                // Merge cached and new emails
-                let merged = self.merge_emails(cached_emails, new);
-                debug_log(&format!("After merging: {} total emails", merged.len()));
-                
-                // Update metadata
-                metadata.last_sync = Local::now();
-                metadata.total_messages = merged.len() as u32;
-                
-                // Save updated cache and metadata
-                self.save_cached_emails(folder, &merged);
-                self.save_folder_metadata(folder, &metadata);
-                debug_log("Saved updated cache and metadata");
-                
-                // Update sync tracker with latest timestamp
-                if let Some(latest_email) = merged.first() {
-                    let email_time = latest_email.date.with_timezone(&chrono::Utc);
-                    crate::app::update_global_sync_timestamp(
-                        &self.account.email, 
-                        folder, 
-                        email_time
-                    );
-                    debug_log(&format!("Updated sync tracker timestamp for {}/{}", self.account.email, folder));
-                }
-                
-                // Return all emails (or limited for display)
-                let display_limit = if limit == 0 { merged.len() } else { std::cmp::max(limit, 100) }; // Show all if limit is 0
-                let result_count = std::cmp::min(display_limit, merged.len());
-                debug_log(&format!("Returning {} emails for display (limit={}, merged={})", result_count, limit, merged.len()));
-                Ok(merged.into_iter().take(result_count).collect())
-            }
-            Err(e) => {
-                debug_log(&format!("Server fetch failed: {}", e));
-                // If server fetch fails, return cached emails
-                if !cached_emails.is_empty() {
-                    debug_log(&format!("Using {} cached emails due to server error", cached_emails.len()));
-                    Ok(cached_emails)
-                } else {
-                    debug_log("No cached emails available, returning error");
-                    Err(e)
-                }
-            }
-        }
-    }
-    
-    fn fetch_emails_incrementally_secure(&self, folder: &str, metadata: &mut FolderMetadata) -> Result<Vec<Email>, EmailError> {
-        let tls = TlsConnector::builder().build().unwrap();
-        let client = imap::connect(
-            (self.account.imap_server.as_str(), self.account.imap_port),
-            &self.account.imap_server,
-            &tls,
-        ).map_err(|e| EmailError::ImapError(e.to_string()))?;
-
-        let password = self.account.get_imap_password(&self.credentials)
-            .map_err(|e| EmailError::ImapError(format!("Failed to get IMAP password: {}", e)))?;
-
-        let mut session = client
-            .login(&self.account.imap_username, &password)
-            .map_err(|e| EmailError::ImapError(e.0.to_string()))?;
-
-        session
-            .select(folder)
-            .map_err(|e| EmailError::ImapError(e.to_string()))?;
 
-        // Get current folder status
-        let mailbox = session.examine(folder)
-            .map_err(|e| EmailError::ImapError(e.to_string()))?;
-        
-        let current_total = mailbox.exists;
-        debug_log(&format!("Folder '{}' has {} total messages, we have {} cached", 
-            folder, current_total, metadata.downloaded_uids.len()));
-
-        // First time sync - fetch ALL messages
-        if metadata.last_uid == 0 {
-            debug_log("First time sync - fetching ALL messages");
-            
-            // Check if the folder is empty
-            if current_total == 0 {
-                debug_log("Folder is empty, skipping fetch");
-                return Ok(Vec::new()); // Return empty vector for empty folders
-            }
-            
-            // For initial sync, fetch ALL messages in batches to avoid memory issues
-            let batch_size = 500; // Fetch in batches of 500
-            let mut all_emails = Vec::new();
-            let mut current_seq = 1;
-            
-            while current_seq <= current_total {
-                let end_seq = std::cmp::min(current_seq + batch_size - 1, current_total);
-                let sequence = format!("{}:{}", current_seq, end_seq);
-                
-                debug_log(&format!("Initial sync batch: fetching messages {} (batch {}/{})", 
-                    sequence, (current_seq - 1) / batch_size + 1, (current_total + batch_size - 1) / batch_size));
-                
-                let messages = session
-                    .fetch(&sequence, "(RFC822 FLAGS UID)")
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-
-                debug_log(&format!("Fetched {} messages in this batch", messages.len()));
-                
-                let batch_emails = self.parse_messages(&messages, folder)?;
-                all_emails.extend(batch_emails);
-                
-                // Update metadata with all fetched UIDs
-                for message in &messages {
-                    if let Some(uid) = message.uid {
-                        metadata.downloaded_uids.insert(uid);
-                        if uid > metadata.last_uid {
-                            metadata.last_uid = uid;
-                        }
-                    }
-                }
-                
-                current_seq = end_seq + 1;
-                
-                // Small delay between batches to be nice to the server
-                std::thread::sleep(std::time::Duration::from_millis(100));
-            }
-            
-            metadata.total_messages = current_total;
-            debug_log(&format!("Initial sync complete: fetched {} total emails", all_emails.len()));
-            
-            return Ok(all_emails);
-        }
-
-        // Incremental sync - fetch only new messages
-        if current_total <= metadata.total_messages {
-            debug_log("No new messages to fetch");
-            return Ok(Vec::new());
-        }
-
-        let start_uid = metadata.last_uid + 1;
-        debug_log(&format!("Incremental sync: fetching messages with UID >= {}", start_uid));
-
-        // Use UID FETCH to get only new messages
-        let sequence = format!("{}:*", start_uid);
-        let messages = session
-            .uid_fetch(sequence, "(RFC822 FLAGS UID)")
-            .map_err(|e| EmailError::ImapError(e.to_string()))?;
-
-        debug_log(&format!("Incremental sync: fetched {} new messages", messages.len()));
-
-        let new_emails = self.parse_messages(&messages, folder)?;
-        
-        // Update metadata with new UIDs
-        for message in &messages {
-            if let Some(uid) = message.uid {
-                metadata.downloaded_uids.insert(uid);
-                if uid > metadata.last_uid {
-                    metadata.last_uid = uid;
+        // Earlier versions cached these folders under their encoded names
+        if let Ok(db) = self.get_database() {
+            for name in &folder_names {
+                let encoded = crate::utf7::encode(name);
+                if encoded != *name && db.get_email_count(&self.account.email, &encoded).unwrap_or(0) > 0 {
+                    debug_log(&format!("Dropping cache of '{}', now stored as '{}'", encoded, name));
+                    let _ = db.clear_folder_emails(&self.account.email, &encoded);
                 }
             }
         }
-        metadata.total_messages = current_total;
 
-        Ok(new_emails)
+        Ok(folder_names)
     }
+    
+    pub fn fetch_emails(&self, folder: &str, limit: usize) -> Result<Vec<Email>, EmailError> {
+        debug_log(&format!("fetch_emails called: folder='{}', limit={}", folder, limit));
 
-    fn fetch_emails_incrementally_plain(&self, folder: &str, metadata: &mut FolderMetadata) -> Result<Vec<Email>, EmailError> {
-        let mut session = self.connect_imap_plain()?;
-        
-        session
-            .select(folder)
-            .map_err(|e| EmailError::ImapError(e.to_string()))?;
-
-        // Get current folder status
-        let mailbox = session.examine(folder)
-            .map_err(|e| EmailError::ImapError(e.to_string()))?;
-        
-        let current_total = mailbox.exists;
-        debug_log(&format!("Folder '{}' has {} total messages, we have {} cached", 
-            folder, current_total, metadata.downloaded_uids.len()));
-
-        // First time sync - fetch ALL messages
-        if metadata.last_uid == 0 {
-            debug_log("First time sync - fetching ALL messages");
-            
-            // Check if the folder is empty
-            if current_total == 0 {
-                debug_log("Folder is empty, skipping fetch");
-                return Ok(Vec::new()); // Return empty vector for empty folders
+        let db = self.get_database()?;
+        // A folder never synced over IMAP, or whose first sync was interrupted, is
+        // downloaded in batches that survive a dropped connection
+        let first_sync = !self.has_direct_backend()
+            && (db.get_last_uid(&self.account.email, folder).unwrap_or(0) == 0 || self.initial_sync_unfinished(folder));
+        let synced = self.with_imap(|imap| {
+            if first_sync {
+                crate::transport::initial_sync(imap, &db, &self.account.email, folder).map(|emails| emails.len())
+            } else {
+                crate::transport::sync_folder(imap, &db, &self.account.email, folder)
             }
-            
-            // For initial sync, fetch ALL messages in batches to avoid memory issues
-            let batch_size = 500; // Fetch in batches of 500
-            let mut all_emails = Vec::new();
-            let mut current_seq = 1;
-            
-            while current_seq <= current_total {
-                let end_seq = std::cmp::min(current_seq + batch_size - 1, current_total);
-                let sequence = format!("{}:{}", current_seq, end_seq);
-                
-                debug_log(&format!("Initial sync batch: fetching messages {} (batch {}/{})", 
-                    sequence, (current_seq - 1) / batch_size + 1, (current_total + batch_size - 1) / batch_size));
-                
-                let messages = session
-                    .fetch(&sequence, "(RFC822 FLAGS UID)")
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-
-                debug_log(&format!("Fetched {} messages in this batch", messages.len()));
-                
-                let batch_emails = self.parse_messages(&messages, folder)?;
-                all_emails.extend(batch_emails);
-                
-                // Update metadata with all fetched UIDs
-                for message in &messages {
-                    if let Some(uid) = message.uid {
-                        metadata.downloaded_uids.insert(uid);
-                        if uid > metadata.last_uid {
-                            metadata.last_uid = uid;
-                        }
+        });
+        let emails = db
+            .get_all_emails(&self.account.email, folder)
+            .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))?;
+
+        match synced {
+            Ok(added) => {
+                debug_log(&format!("Stored {} new emails, {} cached in {}", added, emails.len(), folder));
+                if !self.has_direct_backend() {
+                    let last_uid = db.get_last_uid(&self.account.email, folder).unwrap_or(0);
+                    if let Err(e) = db.save_folder_metadata(&self.account.email, folder, last_uid, emails.len() as u32) {
+                        debug_log(&format!("Warning: Could not save folder metadata to database: {}", e));
                     }
                 }
-                
-                current_seq = end_seq + 1;
-                
-                // Small delay between batches to be nice to the server
-                std::thread::sleep(std::time::Duration::from_millis(100));
             }
-            
-            metadata.total_messages = current_total;
-            debug_log(&format!("Initial sync complete: fetched {} total emails", all_emails.len()));
-            
-            return Ok(all_emails);
+            // Offline or refused by the server: what is cached is still worth showing
+            Err(e) if !emails.is_empty() => debug_log(&format!("Server fetch failed, using {} cached emails: {}", emails.len(), e)),
+            Err(e) => return Err(e),
         }
 
-        // Incremental sync - fetch only new messages
-        if current_total <= metadata.total_messages {
-            debug_log("No new messages to fetch");
-            return Ok(Vec::new());
+        if let Some(latest_email) = emails.first() {
+            update_global_sync_timestamp(&self.account.email, folder, latest_email.date.with_timezone(&chrono::Utc));
         }
-
-        let start_uid = metadata.last_uid + 1;
-        debug_log(&format!("Incremental sync: fetching messages with UID >= {}", start_uid));
-
-        // Use UID FETCH to get only new messages
-        let sequence = format!("{}:*", start_uid);
-        let messages = session
-            .uid_fetch(sequence, "(RFC822 FLAGS UID)")
-            .map_err(|e| EmailError::ImapError(e.to_string()))?;
-
-        debug_log(&format!("Incremental sync: fetched {} new messages", messages.len()));
-
-        let new_emails = self.parse_messages(&messages, folder)?;
-        
-        // Update metadata with new UIDs
-        for message in &messages {
-            if let Some(uid) = message.uid {
-                metadata.downloaded_uids.insert(uid);
-                if uid > metadata.last_uid {
-                    metadata.last_uid = uid;
-                }
-            }
-        }
-        metadata.total_messages = current_total;
-
-        Ok(new_emails)
+        let shown = if limit == 0 { emails.len() } else { limit.max(100) };
+        Ok(emails.into_iter().take(shown).collect())
     }
     
-    fn parse_messages(&self, messages: &[imap::types::Fetch], folder: &str) -> Result<Vec<Email>, EmailError> {
-        let mut emails = Vec::new();
-        
-        debug_log(&format!("Starting to parse {} messages from folder '{}'", messages.len(), folder));
-        
-        for (i, message) in messages.iter().enumerate() {
-            if let Some(body) = message.body() {
-                // Skip messages without valid UIDs
-                let uid = match message.uid {
-                    Some(uid) if uid > 0 => uid.to_string(),
-                    _ => {
-                        debug_log(&format!("Message {} has invalid UID ({:?}), skipping", i + 1, message.uid));
-                        continue;
-                    }
-                };
-                
-                let flags: Vec<String> = message
-                    .flags()
-                    .iter()
-                    .map(|f| f.to_string()) // Use consistent flag parsing
-                    .collect();
-                
-                debug_log(&format!("Message {}: UID={}, body_length={}, flags={:?}", 
-                    i + 1, uid, body.len(), flags));
-                
-                if body.len() > 100 {
-                    let preview = String::from_utf8_lossy(&body[..200.min(body.len())]);
-                    debug_log(&format!("Message {} body preview: {}", i + 1, preview));
-                }
-                
-                match mail_parser::Message::parse(body) {
-                    Some(parsed) => {
-                        debug_log(&format!("Message {} parsed successfully by mail_parser", i + 1));
-                        match Email::from_parsed_email(&parsed, &uid, folder, flags) {
-                            Ok(mut email) => {
-                                debug_log(&format!("Email parsed: subject='{}', from_count={}", 
-                                    email.subject, email.from.len()));
-                                
-                                for (j, addr) in email.from.iter().enumerate() {
-                                    debug_log(&format!("  From[{}]: name={:?}, address='{}'", 
-                                        j, addr.name, addr.address));
-                                }
-                                
-                                // Fallback: if we still don't have from addresses, try to extract from raw headers
-                                if email.from.is_empty() {
-                                    debug_log("No from addresses found, trying header fallback");
-                                    if let Some(from_header) = email.headers.get("From").or_else(|| email.headers.get("from")) {
-                                        debug_log(&format!("Found From header in fallback: '{}'", from_header));
-                                        let addresses = parse_email_addresses(from_header);
-                                        debug_log(&format!("Fallback parsed {} addresses", addresses.len()));
-                                        email.from.extend(addresses);
-                                    } else {
-                                        debug_log("No From header found in fallback either");
-                                        debug_log(&format!("Available headers: {:?}", email.headers.keys().collect::<Vec<_>>()));
-                                    }
-                                }
-                                
-                                emails.push(email);
-                            }
-                            Err(e) => {
-                                debug_log(&format!("Error parsing email {}: {}", i + 1, e));
-                            }
-                        }
-                    }
-                    None => {
-                        debug_log(&format!("Message {} failed to parse with mail_parser", i + 1));
-                    }
-                }
-            } else {
-                debug_log(&format!("Message {} has no body", i + 1));
-            }
-        }
-        
-        // Sort by date, newest first
-        emails.sort_by(|a, b| b.date.cmp(&a.date));
-        
-        debug_log(&format!("Finished parsing, returning {} emails", emails.len()));
-        Ok(emails)
+    /// Replace the account settings, e.g. after SMTP settings were changed in the client
+    pub fn set_account(&mut self, account: EmailAccount) {
+        self.account = account;
     }
-    
-    pub fn send_email(&self, email: &Email) -> Result<(), EmailError> {
+
+    /// Send an email, falling back to other SMTP settings as the account allows.
+    /// `protection` signs or encrypts it with the account's PGP keys. Returns the route
+    /// used if it wasn't the configured one.
+    pub fn send_email(&self, email: &Email, protection: crate::pgp::Protection) -> Result<Option<crate::transport::SmtpRoute>, EmailError> {
+        ensure_writable("send email")?;
+
         // Debug: Log attachment info
         if !email.attachments.is_empty() {
             debug_log(&format!("DEBUG: Sending email with {} attachments:", email.attachments.len()));
@@ -1318,265 +955,200 @@ impl EmailClient {
                 debug_log(&format!("  {}: {} ({} bytes, {})", 
                     i + 1, 
                     attachment.filename, 
-                    attachment.data.len(), 
+                    attachment.size(), 
                     attachment.content_type
                 ));
             }
         }
         
-        let mut message_builder = Message::builder()
-            .subject(&email.subject);
-        
-        // Add From
-        if let Some(from) = email.from.first() {
-            message_builder = message_builder.from(from.clone().into());
-        } else {
-            // Use account email if no from address is specified
-            let from = EmailAddress {
-                name: Some(self.account.name.clone()),
-                address: self.account.email.clone(),
-            };
-            message_builder = message_builder.from(from.into());
-        }
-        
-        // Add To
-        for to in &email.to {
-            message_builder = message_builder.to(to.clone().into());
-        }
-        
-        // Add CC
-        for cc in &email.cc {
-            message_builder = message_builder.cc(cc.clone().into());
+        let default_from = EmailAddress {
+            name: Some(self.account.name.clone()),
+            address: self.account.email.clone(),
+        };
+        // Signed or encrypted messages are built here; plain ones by the transport
+        let pgp = match &self.account.pgp {
+            _ if !protection.any() => None,
+            Some(pgp) => Some(pgp),
+            None => return Err(EmailError::SmtpError("No PGP key is set up for this account".to_string())),
+        };
+        let send = |smtp: &mut dyn SmtpBackend| match pgp {
+            Some(pgp) => {
+                let keep_bcc = smtp.keeps_bcc();
+                smtp.send(&crate::transport::build_protected_message(email, &default_from, pgp, protection, keep_bcc)?)
+            }
+            None => crate::transport::send_email(smtp, email, &default_from),
+        };
+
+        if let Some(gmail) = &self.account.gmail_api {
+            let db = self.get_database()?;
+            send(&mut self.gmail_backend(&db, gmail)?)?;
+            return Ok(None);
         }
-        
-        // Add BCC
-        for bcc in &email.bcc {
-            message_builder = message_builder.bcc(bcc.clone().into());
+        if let Some(exchange) = &self.account.exchange {
+            let db = self.get_database()?;
+            send(&mut self.ews_backend(&db, exchange)?)?;
+            return Ok(None);
         }
-        
-        // Build the email body with attachments
-        let body_part = MultiPart::alternative()
-            .singlepart(
-                SinglePart::plain(email.body_text.clone().unwrap_or_default())
-            );
-        
-        let final_multipart = if email.attachments.is_empty() {
-            // No attachments, just use the body
-            body_part
-        } else {
-            // Has attachments, create mixed multipart
-            let mut mixed_part = MultiPart::mixed()
-                .multipart(body_part);
-            
-            // Add attachments
-            for attachment in &email.attachments {
-                let attachment_part = Attachment::new(attachment.filename.clone())
-                    .body(attachment.data.clone(), attachment.content_type.parse().unwrap_or("application/octet-stream".parse().unwrap()));
-                mixed_part = mixed_part.singlepart(attachment_part);
+
+        let routes = crate::transport::smtp_routes(&self.account);
+        // A route counts as working once the server has greeted us, TLS is up and the
+        // login went through; nothing is sent before that
+        let connect = |route: &crate::transport::SmtpRoute| {
+            let transport = self.smtp_transport_for(route)?;
+            match transport.test_connection() {
+                Ok(true) => Ok(Box::new(transport) as Box<dyn SmtpBackend>),
+                Ok(false) => Err(EmailError::ConnectionError(format!("{} did not answer", route))),
+                Err(e) => Err(crate::transport::smtp_error(e)),
             }
-            
-            mixed_part
         };
-        
-        // Build the final message
-        let message = message_builder
-            .multipart(final_multipart)
-            .map_err(|e| EmailError::SmtpError(e.to_string()))?;
-        
-        // Configure SMTP transport
+        let route = match pgp {
+            Some(pgp) => {
+                let message = crate::transport::build_protected_message(email, &default_from, pgp, protection, false)?;
+                crate::transport::send_message_with_fallback(&routes, connect, &message)?
+            }
+            None => crate::transport::send_with_fallback(&routes, connect, email, &default_from)?,
+        };
+        if route != &routes[0] {
+            debug_log(&format!("Sent via {} after {} failed", route, routes[0]));
+            return Ok(Some(route.clone()));
+        }
+        Ok(None)
+    }
+
+    /// Bounce (redirect) a stored message to new recipients, unchanged apart from the
+    /// Resent-* headers. The original is fetched from the server, attachments included.
+    pub fn bounce_email(&self, email: &Email, to: &[EmailAddress]) -> Result<(), EmailError> {
+        ensure_writable("bounce email")?;
+        let uid: u32 = email
+            .id
+            .parse()
+            .map_err(|_| EmailError::ImapError(format!("Invalid email UID '{}'", email.id)))?;
+        let raw = self
+            .with_imap(|imap| imap.fetch(&email.folder, &[uid]))?
+            .into_iter()
+            .next()
+            .map(|message| message.raw)
+            .ok_or_else(|| EmailError::NotFound(format!("UID {} in {}", uid, email.folder)))?;
+
+        let from = EmailAddress { name: None, address: self.account.email.clone() };
+        let mut mailer = self.smtp_transport()?;
+        crate::transport::bounce_email(&mut mailer, &raw, &from, to)
+    }
+
+    /// SMTP connection settings of the account
+    fn smtp_transport(&self) -> Result<SmtpTransport, EmailError> {
+        self.smtp_transport_for(&crate::transport::smtp_routes(&self.account)[0])
+    }
+
+    fn smtp_transport_for(&self, route: &crate::transport::SmtpRoute) -> Result<SmtpTransport, EmailError> {
+        ensure_online(&self.account.email, &self.account.smtp_server)?;
         let smtp_password = self.account.get_smtp_password(&self.credentials)
-            .map_err(|e| EmailError::SmtpError(format!("Failed to get SMTP password: {}", e)))?;
+            .map_err(|e| EmailError::AuthError(format!("Failed to get SMTP password: {}", e)))?;
             
         let creds = Credentials::new(
             self.account.smtp_username.clone(),
             smtp_password,
         );
-        
-        let mailer = match self.account.smtp_security {
-            SmtpSecurity::SSL => {
-                let tls_params = lettre::transport::smtp::client::TlsParameters::new(self.account.smtp_server.clone())
-                    .map_err(|e| EmailError::SmtpError(e.to_string()))?;
-                
-                SmtpTransport::relay(&self.account.smtp_server)
-                    .map_err(|e| EmailError::SmtpError(e.to_string()))?
-                    .credentials(creds)
-                    .port(self.account.smtp_port)
-                    .tls(lettre::transport::smtp::client::Tls::Wrapper(tls_params))
-                    .build()
-            }
-            SmtpSecurity::StartTLS => {
-                let tls_params = lettre::transport::smtp::client::TlsParameters::new(self.account.smtp_server.clone())
-                    .map_err(|e| EmailError::SmtpError(e.to_string()))?;
-                
-                SmtpTransport::relay(&self.account.smtp_server)
-                    .map_err(|e| EmailError::SmtpError(e.to_string()))?
-                    .credentials(creds)
-                    .port(self.account.smtp_port)
-                    .tls(lettre::transport::smtp::client::Tls::Required(tls_params))
-                    .build()
-            }
-            SmtpSecurity::None => {
-                SmtpTransport::relay(&self.account.smtp_server)
-                    .map_err(|e| EmailError::SmtpError(e.to_string()))?
-                    .credentials(creds)
-                    .port(self.account.smtp_port)
-                    .build()
-            }
+
+        let server = &self.account.smtp_server;
+        // Pinning an address family means connecting to a resolved address; TLS still
+        // verifies the certificate against the host name
+        let host = match route.family {
+            Some(family) => {
+                use std::net::ToSocketAddrs;
+                (server.as_str(), route.port)
+                    .to_socket_addrs()
+                    .map_err(|e| EmailError::ConnectionError(format!("Failed to resolve {}: {}", server, e)))?
+                    .find(|address| address.is_ipv4() == (family == crate::config::IpFamily::V4))
+                    .ok_or_else(|| EmailError::ConnectionError(format!("{} has no {}", server, route)))?
+                    .ip()
+                    .to_string()
+            }
+            None => server.clone(),
         };
-        
-        // Send the email
-        mailer.send(&message)
-            .map_err(|e| EmailError::SmtpError(e.to_string()))?;
-        
-        Ok(())
+        let tls_params = || {
+            lettre::transport::smtp::client::TlsParameters::new(server.clone())
+                .map_err(|e| EmailError::ConnectionError(e.to_string()))
+        };
+        let tls = match route.security {
+            SmtpSecurity::SSL => lettre::transport::smtp::client::Tls::Wrapper(tls_params()?),
+            SmtpSecurity::StartTLS => lettre::transport::smtp::client::Tls::Required(tls_params()?),
+            SmtpSecurity::None => lettre::transport::smtp::client::Tls::None,
+        };
+
+        let mut builder = SmtpTransport::builder_dangerous(host)
+            .credentials(creds)
+            .port(route.port)
+            .tls(tls);
+        // Don't wait the full default minute on each fallback
+        if route.port != self.account.smtp_port
+            || route.security != self.account.smtp_security
+            || route.family != self.account.smtp_ip_family
+        {
+            builder = builder.timeout(Some(std::time::Duration::from_secs(20)));
+        }
+        Ok(builder.build())
     }
     
     pub fn mark_as_read(&self, email: &Email) -> Result<(), EmailError> {
         debug_log(&format!("Marking email as read: {} in folder {}", email.id, email.folder));
-        
+        self.set_seen(email, true)
+    }
+    
+    pub fn mark_as_unread(&self, email: &Email) -> Result<(), EmailError> {
+        self.set_seen(email, false)
+    }
+
+    /// Set or clear \Seen through `transport::set_seen`, retrying a dropped connection
+    fn set_seen(&self, email: &Email, seen: bool) -> Result<(), EmailError> {
         // Validate email ID before attempting STORE operation
         if email.id.is_empty() || email.id == "0" {
-            debug_log(&format!("Invalid email ID '{}', skipping mark as read", email.id));
+            debug_log(&format!("Invalid email ID '{}', skipping STORE", email.id));
             return Err(EmailError::ImapError("Invalid email ID for STORE operation".to_string()));
         }
-        
-        // Add retry logic for IMAP connection issues
-        let mut attempts = 0;
+
+        let db = self.get_database()?;
         let max_attempts = 3;
-        
-        while attempts < max_attempts {
+        let mut attempts = 0;
+        loop {
             attempts += 1;
-            
-            let result = match self.account.imap_security {
-                ImapSecurity::SSL | ImapSecurity::StartTLS => {
-                    match self.connect_imap_secure() {
-                        Ok(mut session) => {
-                            match session.select(&email.folder) {
-                                Ok(_) => {
-                                    debug_log(&format!("Attempting STORE command with UID: {}", email.id));
-                                    session.uid_store(&email.id, "+FLAGS (\\Seen)")
-                                        .map_err(|e| EmailError::ImapError(e.to_string()))
-                                }
-                                Err(e) => Err(EmailError::ImapError(e.to_string()))
-                            }
-                        }
-                        Err(e) => Err(e)
-                    }
-                }
-                ImapSecurity::None => {
-                    match self.connect_imap_plain() {
-                        Ok(mut session) => {
-                            match session.select(&email.folder) {
-                                Ok(_) => {
-                                    debug_log(&format!("Attempting STORE command with UID: {}", email.id));
-                                    session.uid_store(&email.id, "+FLAGS (\\Seen)")
-                                        .map_err(|e| EmailError::ImapError(e.to_string()))
-                                }
-                                Err(e) => Err(EmailError::ImapError(e.to_string()))
-                            }
-                        }
-                        Err(e) => Err(e)
-                    }
-                }
-            };
-            
-            match result {
-                Ok(_) => {
-                    debug_log(&format!("Successfully marked email {} as read", email.id));
-                    return Ok(());
-                }
-                Err(e) => {
-                    debug_log(&format!("Attempt {} failed to mark email as read: {}", attempts, e));
-                    if attempts >= max_attempts {
-                        return Err(e);
-                    }
-                    // Wait a bit before retrying
+            match self.with_imap(|imap| crate::transport::set_seen(imap, &db, &self.account.email, email, seen)) {
+                Err(e @ (EmailError::ConnectionError(_) | EmailError::ImapError(_))) if attempts < max_attempts => {
+                    debug_log(&format!("Attempt {} failed to set \\Seen on {}: {}", attempts, email.id, e));
                     std::thread::sleep(std::time::Duration::from_millis(500));
                 }
-            }
-        }
-        
-        Err(EmailError::ImapError("Failed to mark email as read after retries".to_string()))
-    }
-    
-    #[allow(dead_code)]
-    pub fn mark_as_unread(&self, email: &Email) -> Result<(), EmailError> {
-        // Validate email ID before attempting STORE operation
-        if email.id.is_empty() || email.id == "0" {
-            debug_log(&format!("Invalid email ID '{}', skipping mark as unread", email.id));
-            return Err(EmailError::ImapError("Invalid email ID for STORE operation".to_string()));
-        }
-        
-        match self.account.imap_security {
-            ImapSecurity::SSL | ImapSecurity::StartTLS => {
-                let mut session = self.connect_imap_secure()?;
-                session
-                    .select(&email.folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .uid_store(&email.id, "-FLAGS (\\Seen)")
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                Ok(())
-            }
-            ImapSecurity::None => {
-                let mut session = self.connect_imap_plain()?;
-                session
-                    .select(&email.folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .uid_store(&email.id, "-FLAGS (\\Seen)")
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                Ok(())
+                result => return result,
             }
         }
     }
     
     pub fn delete_email(&self, email: &Email) -> Result<(), EmailError> {
+        ensure_writable("delete email")?;
+
         // Validate email ID before attempting STORE operation
-        if email.id.is_empty() || email.id == "0" {
-            debug_log(&format!("Invalid email ID '{}', skipping delete", email.id));
-            return Err(EmailError::ImapError("Invalid email ID for STORE operation".to_string()));
-        }
-        
-        match self.account.imap_security {
-            ImapSecurity::SSL | ImapSecurity::StartTLS => {
-                let mut session = self.connect_imap_secure()?;
-                session
-                    .select(&email.folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .uid_store(&email.id, "+FLAGS (\\Deleted)")
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .expunge()
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                Ok(())
+        let uid: u32 = match email.id.parse() {
+            Ok(uid) if uid != 0 => uid,
+            _ => {
+                debug_log(&format!("Invalid email ID '{}', skipping delete", email.id));
+                return Err(EmailError::ImapError("Invalid email ID for STORE operation".to_string()));
             }
-            ImapSecurity::None => {
-                let mut session = self.connect_imap_plain()?;
-                session
-                    .select(&email.folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .uid_store(&email.id, "+FLAGS (\\Deleted)")
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .expunge()
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                Ok(())
+        };
+
+        let db = self.get_database()?;
+        // With a Trash folder the server marks, deleting moves the message there as other
+        // clients do; in the Trash itself it is removed for good
+        match self.account.special_folders.trash.as_deref() {
+            Some(trash) if trash != email.folder => {
+                self.with_imap(|imap| crate::transport::move_email(imap, &db, &self.account.email, email, trash))
             }
+            _ => self.with_imap(|imap| crate::transport::delete_message(imap, &db, &self.account.email, &email.folder, uid)),
         }
     }
+
+    /// The folders the server marks for a special use, see `ImapBackend::special_use_folders`
+    pub fn special_folders(&self) -> Result<crate::config::SpecialFolders, EmailError> {
+        self.with_imap(|imap| imap.special_use_folders())
+    }
     
     /// Fetch only new emails since the last known count
     fn fetch_new_emails_since_count(&self, folder: &str, last_count: usize) -> Result<Vec<Email>, EmailError> {
@@ -1594,7 +1166,7 @@ impl EmailClient {
     
     fn fetch_new_emails_since_count_secure(&self, folder: &str, last_count: usize) -> Result<Vec<Email>, EmailError> {
         let mut session = self.connect_imap_secure()?;
-        session.select(folder)
+        open_folder(&mut session, folder)
             .map_err(|e| EmailError::ImapError(e.to_string()))?;
         
         // Get current message count
@@ -1633,7 +1205,7 @@ impl EmailClient {
                 debug_log(&format!("Processing new email {}: UID={}, size={} bytes, flags={:?}", 
                     i + 1, uid, body.len(), flags));
                 
-                match mail_parser::Message::parse(body) {
+                match crate::mime::parse_message(body) {
                     Some(parsed) => {
                         match Email::from_parsed_email(&parsed, &uid, folder, flags) {
                             Ok(email) => {
@@ -1657,7 +1229,7 @@ impl EmailClient {
     
     fn fetch_new_emails_since_count_plain(&self, folder: &str, last_count: usize) -> Result<Vec<Email>, EmailError> {
         let mut session = self.connect_imap_plain()?;
-        session.select(folder)
+        open_folder(&mut session, folder)
             .map_err(|e| EmailError::ImapError(e.to_string()))?;
         
         // Get current message count
@@ -1696,7 +1268,7 @@ impl EmailClient {
                 debug_log(&format!("Processing new email {}: UID={}, size={} bytes, flags={:?}", 
                     i + 1, uid, body.len(), flags));
                 
-                match mail_parser::Message::parse(body) {
+                match crate::mime::parse_message(body) {
                     Some(parsed) => {
                         match Email::from_parsed_email(&parsed, &uid, folder, flags) {
                             Ok(email) => {
@@ -1756,7 +1328,7 @@ impl EmailClient {
         let current_count = match self.account.imap_security {
             ImapSecurity::SSL | ImapSecurity::StartTLS => {
                 let mut session = self.connect_imap_secure()?;
-                session.select(folder)
+                open_folder(&mut session, folder)
                     .map_err(|e| EmailError::ImapError(e.to_string()))?;
                 session.search("ALL")
                     .map_err(|e| EmailError::ImapError(e.to_string()))?
@@ -1764,7 +1336,7 @@ impl EmailClient {
             }
             ImapSecurity::None => {
                 let mut session = self.connect_imap_plain()?;
-                session.select(folder)
+                open_folder(&mut session, folder)
                     .map_err(|e| EmailError::ImapError(e.to_string()))?;
                 session.search("ALL")
                     .map_err(|e| EmailError::ImapError(e.to_string()))?
@@ -1804,6 +1376,49 @@ impl EmailClient {
         Ok(current_count)
     }
     
+    /// The server's CAPABILITY list, e.g. `IDLE` or `AUTH=PLAIN`
+    pub fn imap_capabilities(&self) -> Result<Vec<String>, EmailError> {
+        let response = match self.account.imap_security {
+            ImapSecurity::SSL | ImapSecurity::StartTLS => {
+                let mut session = self.connect_imap_secure()?;
+                let response = session.run_command_and_read_response("CAPABILITY");
+                let _ = session.logout();
+                response
+            }
+            ImapSecurity::None => {
+                let mut session = self.connect_imap_plain()?;
+                let response = session.run_command_and_read_response("CAPABILITY");
+                let _ = session.logout();
+                response
+            }
+        }
+        .map_err(|e| EmailError::ImapError(format!("CAPABILITY failed: {}", e)))?;
+
+        Ok(String::from_utf8_lossy(&response)
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("* CAPABILITY "))
+            .flat_map(str::split_whitespace)
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Connect and run raw commands for the IMAP console until `commands` is closed
+    pub fn run_console(&self, commands: mpsc::Receiver<String>, output: impl FnMut(Vec<String>)) -> Result<(), EmailError> {
+        match self.account.imap_security {
+            ImapSecurity::SSL | ImapSecurity::StartTLS => {
+                let mut session = self.connect_imap_secure()?;
+                crate::imap_console::serve(&mut session, commands, output);
+                let _ = session.logout();
+            }
+            ImapSecurity::None => {
+                let mut session = self.connect_imap_plain()?;
+                crate::imap_console::serve(&mut session, commands, output);
+                let _ = session.logout();
+            }
+        }
+        Ok(())
+    }
+
     pub fn supports_idle(&self) -> bool {
         // Try to connect and check capabilities
         match self.account.imap_security {
@@ -1895,7 +1510,7 @@ impl EmailClient {
         last_known_count: &mut usize,
     ) -> Result<(), EmailError> {
         let mut session = self.connect_imap_secure()?;
-        session.select(folder)
+        open_folder(&mut session, folder)
             .map_err(|e| EmailError::ImapError(e.to_string()))?;
         
         debug_log("IDLE session: connected and folder selected");
@@ -2064,7 +1679,7 @@ impl EmailClient {
         last_known_count: &mut usize,
     ) -> Result<(), EmailError> {
         let mut session = self.connect_imap_plain()?;
-        session.select(folder)
+        open_folder(&mut session, folder)
             .map_err(|e| EmailError::ImapError(e.to_string()))?;
         
         debug_log("IDLE session (plain): connected and folder selected");
@@ -2180,36 +1795,382 @@ impl EmailClient {
     
 
 
-    #[allow(dead_code)]
+    /// List a folder from headers alone; see `transport::sync_folder_headers`
+    pub fn sync_folder_headers(&self, folder: &str) -> Result<usize, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::sync_folder_headers(imap, &db, &self.account.email, folder))
+    }
+
+    /// Download bodies of messages cached with headers only; returns the UIDs stored
+    pub fn fetch_bodies(&self, folder: &str, uids: &[u32]) -> Result<Vec<u32>, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::fetch_bodies(imap, &db, &self.account.email, folder, uids))
+    }
+
+    /// Cached messages of the folder still without a body, newest first
+    pub fn pending_bodies(&self, folder: &str, limit: Option<usize>) -> Result<Vec<u32>, EmailError> {
+        self.get_database()?
+            .get_pending_body_uids(&self.account.email, folder, limit)
+            .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+    }
+
+    /// Whether anything of the folder is cached yet
+    pub fn has_cached_emails(&self, folder: &str) -> bool {
+        self.get_database()
+            .ok()
+            .and_then(|db| db.get_email_count(&self.account.email, folder).ok())
+            .is_some_and(|count| count > 0)
+    }
+
+    /// Whether an initial sync of the folder was interrupted and has yet to be finished
+    pub fn initial_sync_unfinished(&self, folder: &str) -> bool {
+        self.get_database()
+            .ok()
+            .and_then(|db| db.get_sync_progress(&self.account.email, folder).ok())
+            .is_some_and(|progress| progress.is_some())
+    }
+
+    /// Move a message to another folder on the server and drop it from the local cache
     pub fn move_email(&self, email: &Email, target_folder: &str) -> Result<(), EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::move_email(imap, &db, &self.account.email, email, target_folder))
+    }
+
+    /// Create a folder on the server; see `transport::create_folder`
+    pub fn create_folder(&self, folder: &str) -> Result<(), EmailError> {
+        self.with_imap(|imap| crate::transport::create_folder(imap, folder))
+    }
+
+    /// Rename a folder on the server and in the cache; see `transport::rename_folder`
+    pub fn rename_folder(&self, folder: &str, new_name: &str) -> Result<(), EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::rename_folder(imap, &db, &self.account.email, folder, new_name))
+    }
+
+    /// Delete a folder on the server and forget its cache; see `transport::delete_folder`
+    pub fn delete_folder(&self, folder: &str) -> Result<(), EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::delete_folder(imap, &db, &self.account.email, folder))
+    }
+
+    /// Carry out an operation from the queue; see `transport::apply_operation`
+    pub fn apply_operation(&self, op: &crate::database::PendingOperation) -> Result<(), EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::apply_operation(imap, &db, op))
+    }
+
+    /// Bring in the notmuch tags of synced messages; see `notmuch::sync_tags`
+    pub fn sync_notmuch_tags(&self, config: &crate::config::NotmuchConfig, emails: &[Email]) -> Result<usize, EmailError> {
+        let db = self.get_database()?;
+        crate::notmuch::sync_tags(config, &db, &self.account.email, emails).map_err(EmailError::ConnectionError)
+    }
+
+    /// Mark read, or archive, the messages of `emails` that arrived in conversations
+    /// muted since, over one connection. Returns the UIDs handled and what was done; on
+    /// an error the ones before it stay done.
+    pub fn silence_muted(&self, emails: &[Email], archive_folder: &str) -> Result<Vec<(u32, crate::mute::Silence)>, EmailError> {
+        let db = self.get_database()?;
+        let muted = db
+            .get_muted_threads(&self.account.email)
+            .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))?;
+        let pending = crate::mute::to_silence(emails, &muted, archive_folder);
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_imap(|imap| {
+            let mut done = Vec::new();
+            for (email, silence) in pending {
+                match silence {
+                    crate::mute::Silence::MarkRead => crate::transport::set_seen(imap, &db, &self.account.email, email, true)?,
+                    crate::mute::Silence::Archive => crate::transport::move_email(imap, &db, &self.account.email, email, archive_folder)?,
+                }
+                if let Ok(uid) = email.id.parse() {
+                    done.push((uid, silence));
+                }
+            }
+            Ok(done)
+        })
+    }
+
+    /// Move many messages of a folder to `target`, or delete them when it is `None`
+    pub fn remove_messages(&self, folder: &str, uids: &[u32], target: Option<&str>) -> Result<usize, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::remove_messages(imap, &db, &self.account.email, folder, uids, target))
+    }
+
+    /// Bring over the flag changes and expunges since the last sync of the folder; see
+    /// `transport::sync_changes`
+    pub fn sync_changes(&self, folder: &str) -> Result<Option<crate::transport::SyncedChanges>, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::sync_changes(imap, &db, &self.account.email, folder))
+    }
+
+    /// The server's conversations of a folder by UID, `None` when it can't thread them
+    pub fn threads(&self, folder: &str) -> Result<Option<Vec<Vec<u32>>>, EmailError> {
+        self.with_imap(|imap| imap.threads(folder))
+    }
+
+    /// Search the server for messages of `folders` the local search can't see; see
+    /// `transport::search_server`
+    pub fn search_server(&self, folders: &[String], criteria: &str) -> Result<Vec<Email>, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::search_server(imap, &db, &self.account.email, folders, criteria))
+    }
+
+    /// Keep cached messages another client expunged from a folder; see `transport::keep_expunged`
+    pub fn keep_expunged(&self, folder: &str) -> Result<usize, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::keep_expunged(imap, &db, &self.account.email, folder))
+    }
+
+    /// Upload a recently deleted message back to its folder
+    pub fn restore_deleted(&self, message: &crate::database::DeletedMessage) -> Result<(), EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::restore_deleted(imap, &db, message))
+    }
+
+    /// Export messages of a folder, or the whole folder, into a bundle
+    pub fn export_bundle(&self, folder: &str, uids: Option<&[u32]>) -> Result<crate::bundle::Bundle, EmailError> {
+        self.with_imap(|imap| crate::bundle::export(imap, &self.account.email, folder, uids))
+    }
+
+    /// Copy the messages of a local Maildir tree to this account; see `maildir::import`
+    pub fn import_maildir(&self, path: &std::path::Path, only: Option<&str>, into: Option<&str>) -> Result<Vec<(String, usize)>, EmailError> {
+        let mut source = crate::maildir::MaildirStore::open(path)?;
+        self.with_imap(|imap| crate::maildir::import(&mut source, imap, only, into))
+    }
+
+    /// Write the messages of a folder to an mbox file, as the server has them; see
+    /// `mbox::export`
+    pub fn export_mbox(
+        &self,
+        folder: &str,
+        filter: &crate::mbox::ExportFilter,
+        out: &mut impl std::io::Write,
+    ) -> Result<crate::mbox::Exported, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| {
+            crate::mbox::export(imap, &db, &self.account.email, folder, filter, out).map_err(|e| match e.downcast::<EmailError>() {
+                Ok(e) => e,
+                Err(e) => EmailError::ConnectionError(format!("Export failed: {}", e)),
+            })
+        })
+    }
+
+    /// Save the people on `cards` to the address book, one entry for each address.
+    /// Returns how many addresses were saved.
+    pub fn import_contacts(&self, cards: &[crate::vcard::Card]) -> Result<usize, EmailError> {
+        let db = self.get_database()?;
+        let mut saved = 0;
+        for card in cards {
+            for address in &card.emails {
+                let contact = crate::database::SavedContact {
+                    address: address.clone(),
+                    name: card.name.clone(),
+                    organization: card.organization.clone(),
+                    phone: card.phones.first().cloned(),
+                };
+                db.save_contact(&self.account.email, &contact)
+                    .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))?;
+                saved += 1;
+            }
+        }
+        Ok(saved)
+    }
+
+    /// The address book as cards; with `correspondents`, also everyone the account has
+    /// exchanged mail with
+    pub fn contact_cards(&self, correspondents: bool) -> Result<Vec<crate::vcard::Card>, EmailError> {
+        let db = self.get_database()?;
+        let database_error = |e: anyhow::Error| EmailError::ConnectionError(format!("Database error: {}", e));
+        let mut cards: Vec<crate::vcard::Card> = db
+            .get_address_book(&self.account.email)
+            .map_err(database_error)?
+            .into_iter()
+            .map(|contact| crate::vcard::Card {
+                name: contact.name,
+                emails: vec![contact.address],
+                phones: contact.phone.into_iter().collect(),
+                organization: contact.organization,
+            })
+            .collect();
+        if correspondents {
+            for contact in db.get_contacts(&self.account.email).map_err(database_error)? {
+                if !contact.saved {
+                    cards.push(crate::vcard::Card { name: contact.name, emails: vec![contact.address], ..Default::default() });
+                }
+            }
+        }
+        Ok(cards)
+    }
+
+    /// Append the messages of an mbox file to a folder on the server
+    pub fn import_mbox(&self, input: impl std::io::BufRead, folder: &str) -> Result<usize, EmailError> {
+        self.with_imap(|imap| crate::mbox::import(input, imap, folder))
+    }
+
+    /// Append the messages of a bundle to the server
+    pub fn import_bundle(&self, bundle: &crate::bundle::Bundle, folder: Option<&str>) -> Result<usize, EmailError> {
+        self.with_imap(|imap| crate::bundle::import(imap, bundle, folder))
+    }
+
+    /// Two-way sync of the locally saved drafts with the Drafts folder
+    pub fn sync_drafts(&self, folder: &str) -> Result<crate::drafts::DraftSyncReport, EmailError> {
+        let db = self.get_database()?;
+        let from = EmailAddress { name: Some(self.account.name.clone()), address: self.account.email.clone() };
+        let now = chrono::Local::now().timestamp();
+        self.with_imap(|imap| crate::drafts::sync_drafts(imap, &db, &self.account.email, &from, folder, now))
+    }
+
+    /// Add or remove a flag such as `\Flagged`, on the server and in the local cache
+    pub fn set_flag(&self, email: &Email, flag: &str, set: bool) -> Result<(), EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::set_flag(imap, &db, &self.account.email, email, flag, set))
+    }
+
+    
+    /// Download a raw message in fixed-size partial fetches so callers can report progress.
+    /// `on_progress` is called with (received, total) bytes after every chunk and returns
+    /// `false` to abort the transfer.
+    pub fn fetch_raw_message_chunked<F>(&self, folder: &str, uid: &str, chunk_size: usize, mut on_progress: F) -> Result<Vec<u8>, EmailError>
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        debug_log(&format!("fetch_raw_message_chunked: folder='{}', uid={}, chunk_size={}", folder, uid, chunk_size));
+
+        if self.has_direct_backend() {
+            let uid: u32 = uid.parse().map_err(|_| EmailError::ImapError(format!("Invalid email UID '{}'", uid)))?;
+            let message = self.with_imap(|imap| imap.fetch(folder, &[uid]))?.pop().ok_or_else(|| EmailError::NotFound(format!("UID {} in {}", uid, folder)))?;
+            let _ = on_progress(message.raw.len(), message.raw.len());
+            return Ok(message.raw);
+        }
+
         match self.account.imap_security {
             ImapSecurity::SSL | ImapSecurity::StartTLS => {
                 let mut session = self.connect_imap_secure()?;
-                session
-                    .select(&email.folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .mv(&email.id, target_folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                Ok(())
+                let result = Self::fetch_message_chunks(&mut session, folder, uid, chunk_size, on_progress);
+                let _ = session.logout();
+                result
             }
             ImapSecurity::None => {
                 let mut session = self.connect_imap_plain()?;
-                session
-                    .select(&email.folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                session
-                    .mv(&email.id, target_folder)
-                    .map_err(|e| EmailError::ImapError(e.to_string()))?;
-                
-                Ok(())
+                let result = Self::fetch_message_chunks(&mut session, folder, uid, chunk_size, on_progress);
+                let _ = session.logout();
+                result
             }
         }
     }
-    
+
+    fn fetch_message_chunks<T, F>(session: &mut Session<T>, folder: &str, uid: &str, chunk_size: usize, mut on_progress: F) -> Result<Vec<u8>, EmailError>
+    where
+        T: std::io::Read + std::io::Write,
+        F: FnMut(usize, usize) -> bool,
+    {
+        open_folder(session, folder)
+            .map_err(|e| EmailError::ImapError(format!("Failed to select folder {}: {}", folder, e)))?;
+
+        let total = session.uid_fetch(uid, "RFC822.SIZE")
+            .map_err(|e| EmailError::ImapError(format!("Failed to fetch size of message {}: {}", uid, e)))?
+            .iter()
+            .find_map(|message| message.size)
+            .ok_or_else(|| EmailError::ImapError(format!("Message {} not found in {}", uid, folder)))? as usize;
+
+        let mut data = Vec::with_capacity(total);
+        if !on_progress(0, total) {
+            return Err(EmailError::ConnectionError("Download cancelled".to_string()));
+        }
+
+        while data.len() < total {
+            let query = format!("BODY.PEEK[]<{}.{}>", data.len(), chunk_size);
+            let messages = session.uid_fetch(uid, &query)
+                .map_err(|e| EmailError::ImapError(format!("Failed to fetch message {} at offset {}: {}", uid, data.len(), e)))?;
+
+            let chunk = messages.iter().find_map(|message| message.body()).unwrap_or_default();
+            if chunk.is_empty() {
+                // Server reported a larger size than it delivered; keep what we have
+                debug_log(&format!("Empty chunk for message {} at offset {}/{}", uid, data.len(), total));
+                break;
+            }
+            data.extend_from_slice(chunk);
+
+            if !on_progress(data.len().min(total), total) {
+                return Err(EmailError::ConnectionError("Download cancelled".to_string()));
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Fetch only the flags of the given UIDs. Used to bring read state in line with the
+    /// server without downloading message bodies.
+    pub fn fetch_flags(&self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, Vec<String>>, EmailError> {
+        if uids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        debug_log(&format!("fetch_flags: folder='{}', {} uids", folder, uids.len()));
+        self.with_imap(|imap| imap.flags(folder, uids))
+    }
+
+    /// Refresh read state of cached messages from the server and write changes to the
+    /// local database. Returns the new seen state of every message whose state changed.
+    pub fn sync_seen_flags(&self, folder: &str, cached: &[(u32, bool)]) -> Result<HashMap<u32, bool>, EmailError> {
+        let db = self.get_database()?;
+        self.with_imap(|imap| crate::transport::sync_seen_flags(imap, &db, &self.account.email, folder, cached))
+    }
+
+    /// Unread messages in a folder according to the server
+    pub fn unread_count(&self, folder: &str) -> Result<u32, EmailError> {
+        self.with_imap(|imap| imap.unseen_count(folder))
+    }
+
+    /// Run `f` against a fresh IMAP session for this account, logging out afterwards
+    fn with_imap<R>(&self, f: impl FnOnce(&mut dyn ImapBackend) -> Result<R, EmailError>) -> Result<R, EmailError> {
+        if let Some(store) = self.maildir_store() {
+            return f(&mut store?);
+        }
+        if let Some(gmail) = &self.account.gmail_api {
+            let db = self.get_database()?;
+            return f(&mut self.gmail_backend(&db, gmail)?);
+        }
+        if let Some(exchange) = &self.account.exchange {
+            let db = self.get_database()?;
+            return f(&mut self.ews_backend(&db, exchange)?);
+        }
+        match self.account.imap_security {
+            ImapSecurity::SSL | ImapSecurity::StartTLS => {
+                let mut session = self.connect_imap_secure()?;
+                let result = f(&mut session);
+                let _ = session.logout();
+                result
+            }
+            ImapSecurity::None => {
+                let mut session = self.connect_imap_plain()?;
+                let result = f(&mut session);
+                let _ = session.logout();
+                result
+            }
+        }
+    }
+
+    /// Replace the cached payload of a single attachment in the local database
+    pub fn update_cached_attachment(&self, folder: &str, uid: &str, attachment: &EmailAttachment) -> Result<(), EmailError> {
+        let uid: u32 = uid.parse()
+            .map_err(|_| EmailError::ImapError(format!("Invalid email UID '{}'", uid)))?;
+        let db = self.get_database()?;
+        db.update_attachment_data(&self.account.email, folder, uid, attachment)
+            .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+    }
+
+    /// Note that the attachments of a message were opened, which keeps them in the cache
+    /// longer
+    pub fn touch_cached_attachments(&self, folder: &str, uid: &str) -> Result<(), EmailError> {
+        let uid: u32 = uid.parse()
+            .map_err(|_| EmailError::ImapError(format!("Invalid email UID '{}'", uid)))?;
+        let db = self.get_database()?;
+        db.touch_attachments(&self.account.email, folder, uid)
+            .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+    }
+
     /// Get the latest UID from the server (lightweight check for new mail)
     pub fn get_latest_uid(&self, folder: &str) -> Result<u32, EmailError> {
         debug_log(&format!("get_latest_uid called for folder: {}", folder));
@@ -2219,7 +2180,7 @@ impl EmailClient {
                 let mut session = self.connect_imap_secure()?;
 
                 // Select folder
-                session.select(folder)
+                open_folder(&mut session, folder)
                     .map_err(|e| EmailError::ImapError(format!("Failed to select folder {}: {}", folder, e)))?;
 
                 // Get the highest UID using SEARCH
@@ -2243,7 +2204,7 @@ impl EmailClient {
                 let mut session = self.connect_imap_plain()?;
 
                 // Select folder
-                session.select(folder)
+                open_folder(&mut session, folder)
                     .map_err(|e| EmailError::ImapError(format!("Failed to select folder {}: {}", folder, e)))?;
 
                 // Get the highest UID using SEARCH
@@ -2275,7 +2236,7 @@ impl EmailClient {
                 let mut session = self.connect_imap_secure()?;
 
                 // Select folder
-                session.select(folder)
+                open_folder(&mut session, folder)
                     .map_err(|e| EmailError::ImapError(format!("Failed to select folder {}: {}", folder, e)))?;
 
                 // Search for emails with UID greater than since_uid
@@ -2299,7 +2260,7 @@ impl EmailClient {
                 let mut emails = Vec::new();
                 for message in messages.iter() {
                     if let Some(body) = message.body() {
-                        match mail_parser::Message::parse(body) {
+                        match crate::mime::parse_message(body) {
                             Some(parsed) => {
                                 let uid = message.uid.unwrap_or(0).to_string();
                                 let flags = message.flags().iter().map(|f| f.to_string()).collect();
@@ -2325,7 +2286,7 @@ impl EmailClient {
                 let mut session = self.connect_imap_plain()?;
 
                 // Select folder
-                session.select(folder)
+                open_folder(&mut session, folder)
                     .map_err(|e| EmailError::ImapError(format!("Failed to select folder {}: {}", folder, e)))?;
 
                 // Search for emails with UID greater than since_uid
@@ -2349,7 +2310,7 @@ impl EmailClient {
                 let mut emails = Vec::new();
                 for message in messages.iter() {
                     if let Some(body) = message.body() {
-                        match mail_parser::Message::parse(body) {
+                        match crate::mime::parse_message(body) {
                             Some(parsed) => {
                                 let uid = message.uid.unwrap_or(0).to_string();
                                 let flags = message.flags().iter().map(|f| f.to_string()).collect();
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Everything that can change application state. Terminal input, timers and
+/// background threads all post these to the app's `EventBus`, and `App::dispatch`
+/// is the single place they are handled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    Key(KeyEvent),
+    /// Periodic housekeeping: message timeouts, download progress, due spelling and grammar checks
+    Tick,
+    /// Time to pick up mail the background sync stored in the database
+    PollDatabase,
+    /// A flags-only refresh found messages whose read state changed on the server
+    FlagsSynced {
+        account_email: String,
+        folder: String,
+        changed: HashMap<u32, bool>,
+    },
+    /// Free disk space fell below `cache.min_free_mb`, so sync lists headers only, or
+    /// there is room again (`None`)
+    DiskSpaceChanged { low: Option<u64> },
+    /// A folder opened for the first time was listed from message headers
+    HeadersSynced { account_email: String, folder: String },
+    /// Bodies of messages listed from headers only were downloaded
+    BodiesSynced { account_email: String, folder: String, uids: Vec<u32> },
+    /// Cached messages of a folder were found expunged by another client and kept locally
+    ExpungedElsewhere { account_email: String, folder: String, count: usize },
+    /// A sync the app asked for ahead of schedule (e.g. on opening a folder) finished
+    FolderSynced { account_email: String, folder: String },
+    /// Connecting an account in the background finished with its folder list, or the
+    /// error and whether it was a refused login
+    AccountConnected { account_idx: usize, folders: Result<Vec<String>, (String, bool)> },
+    /// The folders the server marks as Sent, Drafts, Trash, Junk and Archive were found
+    SpecialFoldersFound { account_idx: usize, folders: crate::config::SpecialFolders },
+    /// A draft sync of the account finished, with what changed or the error
+    DraftsSynced { account_idx: usize, result: Result<crate::drafts::DraftSyncReport, String> },
+    /// A cleanup of whole senders finished: the groups done with their message counts,
+    /// and the error that stopped it, if any
+    CleanupFinished {
+        account_idx: usize,
+        folder: String,
+        action: String,
+        done: Vec<(String, usize)>,
+        error: Option<String>,
+    },
+    /// A large message body was made ready for the viewer off the UI thread
+    BodyPrepared { key: String, body: crate::body_view::PreparedBody },
+    /// The PGP-protected message with this `body_view::body_key` was decrypted or had
+    /// its signature checked, or that failed
+    PgpOpened { key: String, opened: Result<crate::pgp::Opened, String> },
+    /// The calendar was asked what clashes with the invitation with this
+    /// `body_view::body_key`: its busy events at that time, or why that failed
+    CalendarChecked { key: String, conflicts: Result<Vec<crate::calendar::Event>, String> },
+    /// The server threaded a folder; `None` when it can't or the request failed
+    ThreadsFetched { account_idx: usize, folder: String, threads: Option<Vec<Vec<u32>>> },
+    /// A folder was created, renamed or deleted on the server, or why that failed
+    FolderEdited { edit: crate::app::FolderEdit, result: Result<(), String> },
+    /// A search of the server for the search `query` finished, with the messages found
+    /// that the local search could not see
+    ServerSearched { account_idx: usize, query: String, emails: Result<Vec<crate::email::Email>, String> },
+    /// The account's LDAP directory was searched for `query`, typed in an address field
+    DirectorySearched { account_idx: usize, query: String, contacts: Result<Vec<crate::database::ContactSummary>, String> },
+    /// The servers of an account were asked what they support
+    CapabilitiesChecked { account_idx: usize, capabilities: crate::capabilities::ServerCapabilities },
+    /// Commands and replies of the IMAP console
+    ConsoleOutput { lines: Vec<String> },
+    /// A spell check of `region` (as from `spellcheck::dirty_paragraphs`) of the compose
+    /// field `text` finished
+    SpellChecked {
+        generation: u64,
+        text: String,
+        region: (usize, usize, usize),
+        errors: Vec<crate::spellcheck::SpellError>,
+    },
+    /// The daily look for new releases finished
+    ReleasesFetched { releases: Result<Vec<crate::updates::Release>, String> },
+    /// The background sync worked through due queued operations: the folders they touched,
+    /// and what went wrong with those that failed for the first time
+    OperationsProcessed { folders: Vec<(String, String)>, failed: Vec<String> },
+    /// An avatar or brand logo lookup finished, for the cache key in `key`; `None` means
+    /// there is no usable image, and an error that the services could not be reached
+    AvatarFetched { key: String, image: Result<Option<Vec<u8>>, String> },
+}
+
+/// A user-level command, independent of the key that triggered it. Keeping these
+/// as data lets them be recorded, replayed or bound to other inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Compose,
+    Refresh,
+    FullResync,
+    OpenFolders,
+    OpenSettings,
+    OpenHelp,
+    ToggleSyncPause,
+    OpenPeople,
+    OpenAttachments,
+    RunRulesDryRun,
+    OpenDiagnostics,
+    /// Show high-priority messages first, then only those, then all in date order
+    CycleImportanceView,
+    /// Group the list into conversations, or back to one message per row
+    ToggleThreads,
+    /// Pick a folder to move the selected message to
+    MoveToFolder,
+    /// Mark the selected message, or unmark it, to move several at once
+    ToggleMark,
+    /// Tag the selected message to read later, or clear the tag when it is done
+    ToggleReadLater,
+    /// List saved drafts to resume one
+    OpenDrafts,
+    /// List recently deleted messages to restore one
+    OpenRecentlyDeleted,
+    /// List the operations waiting to be carried out on the server
+    OpenPendingOperations,
+    /// Add or remove tags of the selected message
+    EditTags,
+    /// Ask when to be reminded of the selected message
+    RemindMe,
+    /// List reminders, due ones first
+    OpenReminders,
+    /// Show the release notes and whether a newer version is out
+    OpenChangelog,
+    /// Group the folder by sender to clean up whole senders at once
+    OpenCleanup,
+    /// Open the search bar
+    Search,
+    /// Run the quick action bound to this number key
+    RunQuick(u8),
+    CloseContactThread,
+    SelectPrevious,
+    SelectNext,
+    OpenSelected,
+    NextAccount,
+    ConfirmDelete,
+    TestFileBrowser,
+}
+
+/// Channel carrying `AppEvent`s to the UI thread
+pub struct EventBus {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    /// A handle background threads can post events with
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+
+    pub fn send(&self, event: AppEvent) {
+        // The receiver lives as long as the bus, so this cannot fail
+        let _ = self.sender.send(event);
+    }
+
+    pub fn try_recv(&self) -> Option<AppEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Key bindings of the email list. `cross_folder` is set while the list shows a single
+/// conversation or the read-later queue, where Esc goes back to the folder.
+pub fn normal_mode_action(key: &KeyEvent, cross_folder: bool) -> Option<Action> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let action = match key.code {
+        KeyCode::Char('t') if ctrl => Action::TestFileBrowser,
+        KeyCode::Char('r') if ctrl => Action::FullResync,
+        KeyCode::Char('q') => Action::Quit,
+        KeyCode::Char('c') => Action::Compose,
+        KeyCode::Char('r') => Action::Refresh,
+        KeyCode::Char('f') => Action::OpenFolders,
+        KeyCode::Char('m') => Action::MoveToFolder,
+        KeyCode::Char(' ') => Action::ToggleMark,
+        KeyCode::Char('s') => Action::OpenSettings,
+        KeyCode::Char('?') => Action::OpenHelp,
+        KeyCode::Char('p') => Action::ToggleSyncPause,
+        KeyCode::Char('P') => Action::OpenPeople,
+        KeyCode::Char('A') => Action::OpenAttachments,
+        KeyCode::Char('R') => Action::RunRulesDryRun,
+        KeyCode::Char('D') => Action::OpenDiagnostics,
+        KeyCode::Char('I') => Action::CycleImportanceView,
+        KeyCode::Char('H') => Action::ToggleThreads,
+        KeyCode::Char('L') => Action::ToggleReadLater,
+        KeyCode::Char('W') => Action::OpenDrafts,
+        KeyCode::Char('U') => Action::OpenRecentlyDeleted,
+        KeyCode::Char('O') => Action::OpenPendingOperations,
+        KeyCode::Char('T') => Action::EditTags,
+        KeyCode::Char('z') => Action::RemindMe,
+        KeyCode::Char('Z') => Action::OpenReminders,
+        KeyCode::Char('V') => Action::OpenChangelog,
+        KeyCode::Char('C') => Action::OpenCleanup,
+        KeyCode::Char('/') => Action::Search,
+        KeyCode::Char(c @ '1'..='9') => Action::RunQuick(c as u8 - b'0'),
+        KeyCode::Char('n') => Action::NextAccount,
+        KeyCode::Esc if cross_folder => Action::CloseContactThread,
+        KeyCode::Up => Action::SelectPrevious,
+        KeyCode::Down => Action::SelectNext,
+        KeyCode::Enter => Action::OpenSelected,
+        KeyCode::Delete => Action::ConfirmDelete,
+        _ => return None,
+    };
+    Some(action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_normal_mode_action() {
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('r'), KeyModifiers::NONE), false), Some(Action::Refresh));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('r'), KeyModifiers::CONTROL), false), Some(Action::FullResync));
+        assert_eq!(normal_mode_action(&key(KeyCode::Esc, KeyModifiers::NONE), false), None);
+        assert_eq!(normal_mode_action(&key(KeyCode::Esc, KeyModifiers::NONE), true), Some(Action::CloseContactThread));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('x'), KeyModifiers::NONE), false), None);
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('3'), KeyModifiers::NONE), false), Some(Action::RunQuick(3)));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('0'), KeyModifiers::NONE), false), None);
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('I'), KeyModifiers::SHIFT), false), Some(Action::CycleImportanceView));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('H'), KeyModifiers::SHIFT), false), Some(Action::ToggleThreads));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('m'), KeyModifiers::NONE), false), Some(Action::MoveToFolder));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char(' '), KeyModifiers::NONE), false), Some(Action::ToggleMark));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('L'), KeyModifiers::SHIFT), true), Some(Action::ToggleReadLater));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('/'), KeyModifiers::NONE), false), Some(Action::Search));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('W'), KeyModifiers::SHIFT), false), Some(Action::OpenDrafts));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('U'), KeyModifiers::SHIFT), false), Some(Action::OpenRecentlyDeleted));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('O'), KeyModifiers::SHIFT), false), Some(Action::OpenPendingOperations));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('T'), KeyModifiers::SHIFT), false), Some(Action::EditTags));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('z'), KeyModifiers::NONE), false), Some(Action::RemindMe));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('V'), KeyModifiers::SHIFT), false), Some(Action::OpenChangelog));
+        assert_eq!(normal_mode_action(&key(KeyCode::Char('C'), KeyModifiers::SHIFT), false), Some(Action::OpenCleanup));
+
+        let bus = EventBus::new();
+        bus.sender().send(AppEvent::PollDatabase).unwrap();
+        bus.send(AppEvent::Tick);
+        assert_eq!(bus.try_recv(), Some(AppEvent::PollDatabase));
+        assert_eq!(bus.try_recv(), Some(AppEvent::Tick));
+        assert_eq!(bus.try_recv(), None);
+    }
+}
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use lettre::address::Envelope;
+use lettre::Message;
+use roxmltree::{Document, Node};
+
+use crate::database::EmailDatabase;
+use crate::email::{debug_log, EmailError};
+use crate::transport::{FetchedMessage, ImapBackend, SmtpBackend};
+
+const TYPES_NS: &str = "http://schemas.microsoft.com/exchange/services/2006/types";
+const MESSAGES_NS: &str = "http://schemas.microsoft.com/exchange/services/2006/messages";
+
+/// Largest EWS response read; MIME content comes base64 encoded inside XML
+const RESPONSE_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// Items listed per FindItem page, and fetched per GetItem request
+const PAGE_SIZE: usize = 500;
+const FETCH_BATCH: usize = 50;
+
+/// `PR_MESSAGE_FLAGS`: 1 marks a message read, and leaving out `MSGFLAG_UNSENT` keeps an
+/// uploaded message from showing as a draft
+const MESSAGE_FLAGS_TAG: &str = "3591";
+
+/// One SOAP request to the account's EWS endpoint
+pub trait EwsHttp {
+    fn call(&mut self, envelope: &str) -> Result<(u16, Vec<u8>)>;
+}
+
+/// Talks to an EWS endpoint over HTTPS with basic authentication
+pub struct EwsHttpClient {
+    host: String,
+    path: String,
+    authorization: String,
+}
+
+impl EwsHttpClient {
+    pub fn new(url: &str, username: &str, password: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("https://")
+            .ok_or_else(|| anyhow!("EWS URL must start with https://: {}", url))?;
+        let (host, path) = rest.split_once('/').map_or((rest, "/".to_string()), |(host, path)| (host, format!("/{}", path)));
+        crate::http::split_authority(host).map_err(|e| anyhow!("Bad EWS URL {}: {}", url, e))?;
+        let authorization = format!("Basic {}", BASE64.encode(format!("{}:{}", username, password)));
+        Ok(Self { host: host.to_string(), path, authorization })
+    }
+}
+
+impl EwsHttp for EwsHttpClient {
+    fn call(&mut self, envelope: &str) -> Result<(u16, Vec<u8>)> {
+        crate::http::https_request(
+            "POST",
+            &self.host,
+            &self.path,
+            &[("Authorization", &self.authorization), ("Content-Type", "text/xml; charset=utf-8")],
+            Some(envelope.as_bytes()),
+            RESPONSE_LIMIT,
+        )
+    }
+}
+
+/// Escape text for an XML element or attribute
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn soap(body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+         <soap:Envelope xmlns:soap=\"http://schemas.xmlsoap.org/soap/envelope/\" xmlns:t=\"{}\" xmlns:m=\"{}\">\
+         <soap:Header><t:RequestServerVersion Version=\"Exchange2013\"/></soap:Header>\
+         <soap:Body>{}</soap:Body></soap:Envelope>",
+        TYPES_NS, MESSAGES_NS, body
+    )
+}
+
+fn item_ids(ids: &[String]) -> String {
+    ids.iter().map(|id| format!("<t:ItemId Id=\"{}\"/>", escape(id))).collect()
+}
+
+/// Child element in the EWS types namespace
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name((TYPES_NS, name)))
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    child(node, name).and_then(|n| n.text()).map(str::to_string)
+}
+
+/// IMAP flags of a message element
+fn flags_of(item: Node) -> Vec<String> {
+    let mut flags = Vec::new();
+    if child_text(item, "IsRead").as_deref() == Some("true") {
+        flags.push("\\Seen".to_string());
+    }
+    let flagged = child(item, "Flag").and_then(|flag| child_text(flag, "FlagStatus"));
+    if flagged.as_deref() == Some("Flagged") {
+        flags.push("\\Flagged".to_string());
+    }
+    flags
+}
+
+fn database_error(e: anyhow::Error) -> EmailError {
+    EmailError::ConnectionError(format!("Database error: {}", e))
+}
+
+/// An item as `GetItem` returned it: its UID, the extra properties asked for by field
+/// name, and its flags
+type FetchedItem = (u32, HashMap<String, String>, Vec<String>);
+
+/// An Exchange mailbox reached through Exchange Web Services, seen as an IMAP server.
+/// Folders are named by their path of display names, with the inbox as `INBOX`; items
+/// get UIDs in the database the first time they are listed.
+pub struct EwsBackend<'a, H> {
+    http: H,
+    db: &'a EmailDatabase,
+    account: String,
+    /// Folder paths and EWS folder IDs, loaded when first needed
+    folders: Option<Vec<(String, String)>>,
+}
+
+impl<'a, H: EwsHttp> EwsBackend<'a, H> {
+    pub fn new(http: H, db: &'a EmailDatabase, account: &str) -> Self {
+        Self { http, db, account: account.to_string(), folders: None }
+    }
+
+    /// Send a request and hand its response messages to `f`. A response message with an
+    /// error fails the whole call.
+    fn request<R>(&mut self, body: &str, f: impl FnOnce(Vec<Node>) -> R) -> Result<R, EmailError> {
+        let (status, response) = self
+            .http
+            .call(&soap(body))
+            .map_err(|e| EmailError::ConnectionError(format!("EWS: {}", e)))?;
+        if status == 401 {
+            return Err(EmailError::AuthError("Exchange refused the username or password".to_string()));
+        }
+        let text = String::from_utf8_lossy(&response);
+        let document = Document::parse(&text).map_err(|e| EmailError::ImapError(format!("Malformed EWS response (HTTP {}): {}", status, e)))?;
+        if let Some(fault) = document.descendants().find(|n| n.has_tag_name("faultstring")) {
+            return Err(EmailError::ImapError(format!("EWS fault: {}", fault.text().unwrap_or_default())));
+        }
+
+        let messages: Vec<Node> = document
+            .descendants()
+            .filter(|n| n.tag_name().namespace() == Some(MESSAGES_NS) && n.tag_name().name().ends_with("ResponseMessage"))
+            .collect();
+        for message in &messages {
+            if message.attribute("ResponseClass") == Some("Error") {
+                let text_of = |name: &str| {
+                    message
+                        .children()
+                        .find(|n| n.has_tag_name((MESSAGES_NS, name)))
+                        .and_then(|n| n.text())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let (code, text) = (text_of("ResponseCode"), text_of("MessageText"));
+                return Err(match code.as_str() {
+                    "ErrorItemNotFound" | "ErrorFolderNotFound" => EmailError::NotFound(text),
+                    "ErrorAccessDenied" => EmailError::AuthError(text),
+                    _ => EmailError::ImapError(format!("{}: {}", code, text)),
+                });
+            }
+        }
+        if !(200..300).contains(&status) {
+            return Err(EmailError::ImapError(format!("EWS returned HTTP {}", status)));
+        }
+        Ok(f(messages))
+    }
+
+    fn load_folders(&mut self) -> Result<&[(String, String)], EmailError> {
+        if self.folders.is_none() {
+            let body = "<m:FindFolder Traversal=\"Deep\"><m:FolderShape><t:BaseShape>IdOnly</t:BaseShape>\
+                        <t:AdditionalProperties><t:FieldURI FieldURI=\"folder:DisplayName\"/>\
+                        <t:FieldURI FieldURI=\"folder:ParentFolderId\"/><t:FieldURI FieldURI=\"folder:FolderClass\"/>\
+                        </t:AdditionalProperties></m:FolderShape>\
+                        <m:ParentFolderIds><t:DistinguishedFolderId Id=\"msgfolderroot\"/></m:ParentFolderIds></m:FindFolder>";
+            // (id, parent id, display name) of every mail folder
+            let found: Vec<(String, String, String)> = self.request(body, |messages| {
+                messages
+                    .iter()
+                    .flat_map(|m| m.descendants())
+                    .filter(|n| n.has_tag_name((TYPES_NS, "Folder")))
+                    .filter(|folder| child_text(*folder, "FolderClass").is_none_or(|class| class.starts_with("IPF.Note")))
+                    .filter_map(|folder| {
+                        let id = child(folder, "FolderId")?.attribute("Id")?.to_string();
+                        let parent = child(folder, "ParentFolderId").and_then(|p| p.attribute("Id")).unwrap_or_default().to_string();
+                        Some((id, parent, child_text(folder, "DisplayName").unwrap_or_default()))
+                    })
+                    .collect()
+            })?;
+            let inbox = self.request(
+                "<m:GetFolder><m:FolderShape><t:BaseShape>IdOnly</t:BaseShape></m:FolderShape>\
+                 <m:FolderIds><t:DistinguishedFolderId Id=\"inbox\"/></m:FolderIds></m:GetFolder>",
+                |messages| {
+                    messages
+                        .iter()
+                        .flat_map(|m| m.descendants())
+                        .find(|n| n.has_tag_name((TYPES_NS, "FolderId")))
+                        .and_then(|n| n.attribute("Id"))
+                        .map(str::to_string)
+                },
+            )?;
+
+            let by_id: HashMap<&str, (&str, &str)> = found.iter().map(|(id, parent, name)| (id.as_str(), (parent.as_str(), name.as_str()))).collect();
+            let path_of = |id: &str| {
+                let mut parts = Vec::new();
+                let mut current = id;
+                // Parents outside the listing (the root) end the path
+                while let Some((parent, name)) = by_id.get(current) {
+                    parts.push(if Some(current) == inbox.as_deref() { "INBOX" } else { name });
+                    current = parent;
+                    if parts.len() > 32 {
+                        break;
+                    }
+                }
+                parts.reverse();
+                parts.join("/")
+            };
+            let mut folders: Vec<(String, String)> = found
+                .iter()
+                .map(|(id, _, _)| (path_of(id), id.clone()))
+                .collect();
+            folders.sort_by(|a, b| (a.0 != "INBOX", &a.0).cmp(&(b.0 != "INBOX", &b.0)));
+            self.folders = Some(folders);
+        }
+        Ok(self.folders.as_deref().unwrap_or_default())
+    }
+
+    fn folder_id(&mut self, folder: &str) -> Result<String, EmailError> {
+        self.load_folders()?
+            .iter()
+            .find(|(name, _)| name == folder)
+            .map(|(_, id)| id.clone())
+            .ok_or_else(|| EmailError::NotFound(format!("Exchange folder {}", folder)))
+    }
+
+    /// Mail folders of the mailbox, the inbox first
+    pub fn list_folders(&mut self) -> Result<Vec<String>, EmailError> {
+        Ok(self.load_folders()?.iter().map(|(name, _)| name.clone()).collect())
+    }
+
+    fn item_id(&self, folder: &str, uid: u32) -> Result<String, EmailError> {
+        self.db
+            .exchange_item_id(&self.account, folder, uid)
+            .map_err(database_error)?
+            .ok_or_else(|| EmailError::NotFound(format!("UID {} in {}", uid, folder)))
+    }
+
+    /// Items of `uids` with the given extra properties, by UID
+    fn get_items(&mut self, folder: &str, uids: &[u32], mime: bool, properties: &[&str]) -> Result<Vec<FetchedItem>, EmailError> {
+        let mut ids = Vec::new();
+        for &uid in uids {
+            ids.push((uid, self.item_id(folder, uid)?));
+        }
+        let mut items = Vec::new();
+        for batch in ids.chunks(FETCH_BATCH) {
+            let fields: String = ["message:IsRead", "item:Flag"]
+                .iter()
+                .chain(properties)
+                .map(|field| format!("<t:FieldURI FieldURI=\"{}\"/>", field))
+                .collect();
+            let body = format!(
+                "<m:GetItem><m:ItemShape><t:BaseShape>IdOnly</t:BaseShape><t:IncludeMimeContent>{}</t:IncludeMimeContent>\
+                 <t:AdditionalProperties>{}</t:AdditionalProperties></m:ItemShape><m:ItemIds>{}</m:ItemIds></m:GetItem>",
+                mime,
+                fields,
+                item_ids(&batch.iter().map(|(_, id)| id.clone()).collect::<Vec<_>>())
+            );
+            let found: Vec<(String, HashMap<String, String>, Vec<String>)> = self.request(&body, |messages| {
+                messages
+                    .iter()
+                    .flat_map(|m| m.descendants())
+                    .filter(|n| n.tag_name().namespace() == Some(TYPES_NS) && child(*n, "ItemId").is_some() && n.parent().is_some_and(|p| p.has_tag_name((MESSAGES_NS, "Items"))))
+                    .filter_map(|item| {
+                        let id = child(item, "ItemId")?.attribute("Id")?.to_string();
+                        let mut values = HashMap::new();
+                        if let Some(mime) = child_text(item, "MimeContent") {
+                            values.insert("MimeContent".to_string(), mime);
+                        }
+                        if let Some(headers) = child(item, "InternetMessageHeaders") {
+                            let block: String = headers
+                                .children()
+                                .filter(|h| h.is_element())
+                                .map(|h| format!("{}: {}\r\n", h.attribute("HeaderName").unwrap_or_default(), h.text().unwrap_or_default()))
+                                .collect();
+                            values.insert("InternetMessageHeaders".to_string(), block);
+                        }
+                        Some((id, values, flags_of(item)))
+                    })
+                    .collect()
+            })?;
+            for (id, values, flags) in found {
+                if let Some((uid, _)) = batch.iter().find(|(_, wanted)| *wanted == id) {
+                    items.push((*uid, values, flags));
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    fn update_item(&mut self, folder: &str, uid: u32, update: &str) -> Result<(), EmailError> {
+        let id = self.item_id(folder, uid)?;
+        let body = format!(
+            "<m:UpdateItem ConflictResolution=\"AlwaysOverwrite\" MessageDisposition=\"SaveOnly\">\
+             <m:ItemChanges><t:ItemChange>{}<t:Updates>{}</t:Updates></t:ItemChange></m:ItemChanges></m:UpdateItem>",
+            item_ids(&[id]),
+            update
+        );
+        self.request(&body, |_| ())
+    }
+
+    /// Upload a MIME message, to save it in a folder or to send it
+    fn create_item(&mut self, disposition: &str, folder: &str, raw: &[u8], read: bool) -> Result<(), EmailError> {
+        let body = format!(
+            "<m:CreateItem MessageDisposition=\"{}\"><m:SavedItemFolderId>{}</m:SavedItemFolderId><m:Items><t:Message>\
+             <t:MimeContent CharacterSet=\"UTF-8\">{}</t:MimeContent>\
+             <t:ExtendedProperty><t:ExtendedFieldURI PropertyTag=\"{}\" PropertyType=\"Integer\"/><t:Value>{}</t:Value></t:ExtendedProperty>\
+             </t:Message></m:Items></m:CreateItem>",
+            disposition,
+            folder,
+            BASE64.encode(raw),
+            MESSAGE_FLAGS_TAG,
+            u8::from(read)
+        );
+        self.request(&body, |_| ())
+    }
+}
+
+impl<H: EwsHttp> ImapBackend for EwsBackend<'_, H> {
+    fn uids(&mut self, folder: &str) -> Result<Vec<u32>, EmailError> {
+        let folder_id = self.folder_id(folder)?;
+        let mut ids = Vec::new();
+        loop {
+            let body = format!(
+                "<m:FindItem Traversal=\"Shallow\"><m:ItemShape><t:BaseShape>IdOnly</t:BaseShape></m:ItemShape>\
+                 <m:IndexedPageItemView MaxEntriesReturned=\"{}\" Offset=\"{}\" BasePoint=\"Beginning\"/>\
+                 <m:SortOrder><t:FieldOrder Order=\"Ascending\"><t:FieldURI FieldURI=\"item:DateTimeReceived\"/></t:FieldOrder></m:SortOrder>\
+                 <m:ParentFolderIds><t:FolderId Id=\"{}\"/></m:ParentFolderIds></m:FindItem>",
+                PAGE_SIZE,
+                ids.len(),
+                escape(&folder_id)
+            );
+            let (page, last): (Vec<String>, bool) = self.request(&body, |messages| {
+                let root = messages.iter().find_map(|m| m.descendants().find(|n| n.has_tag_name((MESSAGES_NS, "RootFolder"))));
+                let last = root.and_then(|r| r.attribute("IncludesLastItemInRange")).is_none_or(|v| v == "true");
+                let page = root
+                    .into_iter()
+                    .flat_map(|r| r.descendants())
+                    .filter(|n| n.has_tag_name((TYPES_NS, "ItemId")))
+                    .filter_map(|n| n.attribute("Id").map(str::to_string))
+                    .collect();
+                (page, last)
+            })?;
+            let empty = page.is_empty();
+            ids.extend(page);
+            if last || empty {
+                break;
+            }
+        }
+        debug_log(&format!("EWS: {} items in {} for {}", ids.len(), folder, self.account));
+        self.db.set_exchange_items(&self.account, folder, &ids).map_err(database_error)
+    }
+
+    fn fetch(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        let mut messages = Vec::new();
+        for (uid, values, flags) in self.get_items(folder, uids, true, &[])? {
+            let mime = values.get("MimeContent").map(String::as_str).unwrap_or_default();
+            let raw = BASE64
+                .decode(mime.trim())
+                .map_err(|e| EmailError::ImapError(format!("Malformed MIME content from EWS: {}", e)))?;
+            messages.push(FetchedMessage { uid, flags, raw });
+        }
+        Ok(messages)
+    }
+
+    fn fetch_headers(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        Ok(self
+            .get_items(folder, uids, false, &["item:InternetMessageHeaders"])?
+            .into_iter()
+            .map(|(uid, values, flags)| {
+                let mut raw = values.get("InternetMessageHeaders").cloned().unwrap_or_default().into_bytes();
+                raw.extend_from_slice(b"\r\n");
+                FetchedMessage { uid, flags, raw }
+            })
+            .collect())
+    }
+
+    fn flags(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, Vec<String>>, EmailError> {
+        Ok(self.get_items(folder, uids, false, &[])?.into_iter().map(|(uid, _, flags)| (uid, flags)).collect())
+    }
+
+    fn store_flag(&mut self, folder: &str, uid: u32, flag: &str, set: bool) -> Result<(), EmailError> {
+        let update = match flag {
+            "\\Seen" => format!(
+                "<t:SetItemField><t:FieldURI FieldURI=\"message:IsRead\"/><t:Message><t:IsRead>{}</t:IsRead></t:Message></t:SetItemField>",
+                set
+            ),
+            "\\Flagged" => format!(
+                "<t:SetItemField><t:FieldURI FieldURI=\"item:Flag\"/><t:Message><t:Flag><t:FlagStatus>{}</t:FlagStatus></t:Flag></t:Message></t:SetItemField>",
+                if set { "Flagged" } else { "NotFlagged" }
+            ),
+            _ => return Err(EmailError::ImapError(format!("Exchange has no equivalent of the {} flag", flag))),
+        };
+        self.update_item(folder, uid, &update)
+    }
+
+    fn delete(&mut self, folder: &str, uid: u32) -> Result<(), EmailError> {
+        let id = self.item_id(folder, uid)?;
+        let body = format!("<m:DeleteItem DeleteType=\"HardDelete\"><m:ItemIds>{}</m:ItemIds></m:DeleteItem>", item_ids(&[id]));
+        self.request(&body, |_| ())?;
+        self.db.forget_exchange_item(&self.account, folder, uid).map_err(database_error)
+    }
+
+    fn move_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        let target_id = self.folder_id(target)?;
+        let id = self.item_id(folder, uid)?;
+        let body = format!(
+            "<m:MoveItem><m:ToFolderId><t:FolderId Id=\"{}\"/></m:ToFolderId><m:ItemIds>{}</m:ItemIds></m:MoveItem>",
+            escape(&target_id),
+            item_ids(&[id])
+        );
+        self.request(&body, |_| ())?;
+        self.db.forget_exchange_item(&self.account, folder, uid).map_err(database_error)
+    }
+
+    fn unseen_count(&mut self, folder: &str) -> Result<u32, EmailError> {
+        let folder_id = self.folder_id(folder)?;
+        let body = format!(
+            "<m:GetFolder><m:FolderShape><t:BaseShape>IdOnly</t:BaseShape><t:AdditionalProperties>\
+             <t:FieldURI FieldURI=\"folder:UnreadCount\"/></t:AdditionalProperties></m:FolderShape>\
+             <m:FolderIds><t:FolderId Id=\"{}\"/></m:FolderIds></m:GetFolder>",
+            escape(&folder_id)
+        );
+        self.request(&body, |messages| {
+            messages
+                .iter()
+                .flat_map(|m| m.descendants())
+                .find(|n| n.has_tag_name((TYPES_NS, "UnreadCount")))
+                .and_then(|n| n.text())
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(0)
+        })
+    }
+
+    fn append(&mut self, folder: &str, raw: &[u8], flags: &[String]) -> Result<(), EmailError> {
+        let folder_id = self.folder_id(folder)?;
+        let read = flags.iter().any(|flag| flag == "\\Seen");
+        self.create_item("SaveOnly", &format!("<t:FolderId Id=\"{}\"/>", escape(&folder_id)), raw, read)
+    }
+}
+
+impl<H: EwsHttp> SmtpBackend for EwsBackend<'_, H> {
+    /// Exchange sends the message and keeps a copy in Sent Items
+    fn send(&mut self, message: &Message) -> Result<(), EmailError> {
+        self.create_item("SendAndSaveCopy", "<t:DistinguishedFolderId Id=\"sentitems\"/>", &message.formatted(), true)
+    }
+
+    /// Exchange reads the Bcc recipients from the MIME content and keeps them only on
+    /// the copy in Sent Items
+    fn keeps_bcc(&self) -> bool {
+        true
+    }
+
+    /// Exchange sends to the recipients in the message's headers, so a message can't be
+    /// redirected to other addresses
+    fn send_raw(&mut self, _envelope: &Envelope, _raw: &[u8]) -> Result<(), EmailError> {
+        Err(EmailError::SmtpError("Bouncing messages needs SMTP; EWS can only send".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Answers requests with canned responses in order, checking each request contains
+    /// the expected operation
+    struct Script {
+        answers: VecDeque<(&'static str, String)>,
+        requests: Vec<String>,
+    }
+
+    impl EwsHttp for &mut Script {
+        fn call(&mut self, envelope: &str) -> Result<(u16, Vec<u8>)> {
+            let (operation, answer) = self.answers.pop_front().expect("unexpected request");
+            assert!(envelope.contains(&format!("<m:{}", operation)), "expected {} in {}", operation, envelope);
+            self.requests.push(envelope.to_string());
+            Ok((200, soap(&answer).into_bytes()))
+        }
+    }
+
+    fn response(operation: &str, content: &str) -> String {
+        format!(
+            "<m:{op}Response><m:ResponseMessages><m:{op}ResponseMessage ResponseClass=\"Success\">\
+             <m:ResponseCode>NoError</m:ResponseCode>{content}</m:{op}ResponseMessage></m:ResponseMessages></m:{op}Response>",
+            op = operation,
+            content = content
+        )
+    }
+
+    fn folder(id: &str, parent: &str, name: &str) -> String {
+        format!(
+            "<t:Folder><t:FolderId Id=\"{}\"/><t:ParentFolderId Id=\"{}\"/><t:FolderClass>IPF.Note</t:FolderClass>\
+             <t:DisplayName>{}</t:DisplayName></t:Folder>",
+            id, parent, name
+        )
+    }
+
+    #[test]
+    fn test_folders_items_and_flags() {
+        let dir = std::env::temp_dir().join(format!("tuimail-ews-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+
+        let folders = format!(
+            "<m:RootFolder><t:Folders>{}{}{}<t:Folder><t:FolderId Id=\"cal\"/><t:FolderClass>IPF.Appointment</t:FolderClass>\
+             <t:DisplayName>Calendar</t:DisplayName></t:Folder></t:Folders></m:RootFolder>",
+            folder("in", "root", "Inbox"),
+            folder("proj", "in", "Projects"),
+            folder("sent", "root", "Sent Items")
+        );
+        let mime = BASE64.encode("Subject: Hello\r\n\r\nHi\r\n");
+        let mut script = Script {
+            answers: VecDeque::from(vec![
+                ("FindFolder", response("FindFolder", &folders)),
+                ("GetFolder", response("GetFolder", "<m:Folders><t:Folder><t:FolderId Id=\"in\"/></t:Folder></m:Folders>")),
+                ("FindItem", response("FindItem", "<m:RootFolder IncludesLastItemInRange=\"true\"><t:Items>\
+                    <t:Message><t:ItemId Id=\"a\"/></t:Message><t:Message><t:ItemId Id=\"b\"/></t:Message></t:Items></m:RootFolder>")),
+                ("GetItem", response("GetItem", &format!("<m:Items><t:Message><t:MimeContent>{}</t:MimeContent><t:ItemId Id=\"b\"/>\
+                    <t:IsRead>true</t:IsRead><t:Flag><t:FlagStatus>Flagged</t:FlagStatus></t:Flag></t:Message></m:Items>", mime))),
+                ("FindItem", response("FindItem", "<m:RootFolder IncludesLastItemInRange=\"true\"><t:Items>\
+                    <t:Message><t:ItemId Id=\"b\"/></t:Message><t:Message><t:ItemId Id=\"c\"/></t:Message></t:Items></m:RootFolder>")),
+                ("UpdateItem", "<m:UpdateItemResponse><m:ResponseMessages><m:UpdateItemResponseMessage ResponseClass=\"Error\">\
+                    <m:MessageText>The specified object was not found in the store.</m:MessageText><m:ResponseCode>ErrorItemNotFound</m:ResponseCode>\
+                    </m:UpdateItemResponseMessage></m:ResponseMessages></m:UpdateItemResponse>".to_string()),
+            ]),
+            requests: Vec::new(),
+        };
+        let mut ews = EwsBackend::new(&mut script, &db, "me@corp.example");
+        assert_eq!(ews.list_folders().unwrap(), vec!["INBOX", "INBOX/Projects", "Sent Items"]);
+
+        let uids = ews.uids("INBOX").unwrap();
+        assert_eq!(uids.len(), 2);
+        let fetched = ews.fetch("INBOX", &uids[1..]).unwrap();
+        assert_eq!(fetched[0].raw, b"Subject: Hello\r\n\r\nHi\r\n".to_vec());
+        assert_eq!(fetched[0].flags, vec!["\\Seen", "\\Flagged"]);
+
+        // "a" is gone, "b" keeps its UID and "c" gets a new one
+        let again = ews.uids("INBOX").unwrap();
+        assert_eq!(again[0], uids[1]);
+        assert!(again[1] > uids[1]);
+        assert!(matches!(ews.store_flag("INBOX", again[0], "\\Seen", true), Err(EmailError::NotFound(_))));
+        assert!(ews.store_flag("INBOX", again[0], "$Later", true).is_err());
+        assert!(script.answers.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_send_keeps_bcc() {
+        let dir = std::env::temp_dir().join(format!("tuimail-ews-send-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let address = |address: &str| crate::email::EmailAddress { name: None, address: address.to_string() };
+        let mut email = crate::email::Email::new();
+        email.subject = "Hi".to_string();
+        email.to = vec![address("to@corp.example")];
+        email.bcc = vec![address("hidden@corp.example")];
+
+        let mut script = Script { answers: VecDeque::from(vec![("CreateItem", response("CreateItem", ""))]), requests: Vec::new() };
+        let mut ews = EwsBackend::new(&mut script, &db, "me@corp.example");
+        crate::transport::send_email(&mut ews, &email, &address("me@corp.example")).unwrap();
+        let request = &script.requests[0];
+        assert!(request.contains("MessageDisposition=\"SendAndSaveCopy\""));
+        let mime = request.split("<t:MimeContent CharacterSet=\"UTF-8\">").nth(1).and_then(|rest| rest.split('<').next()).unwrap();
+        let raw = String::from_utf8(BASE64.decode(mime).unwrap()).unwrap();
+        assert!(raw.contains("Bcc: hidden@corp.example"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
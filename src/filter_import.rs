@@ -0,0 +1,718 @@
+use serde::Serialize;
+
+use crate::config::{FilterRule, RuleAction};
+use crate::rules::describe_action;
+
+/// Rules translated from another program's filters, and what could not be carried over
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub rules: Vec<FilterRule>,
+    /// One line per filter, or part of one, that was left out, naming the filter
+    pub unsupported: Vec<String>,
+}
+
+/// Which header a condition looks at; `To` also matches Cc, as rules do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    From,
+    To,
+    Subject,
+}
+
+/// Substrings one message has to contain, as a rule checks them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Conditions {
+    from: Option<String>,
+    to: Option<String>,
+    subject: Option<String>,
+}
+
+impl Conditions {
+    fn single(field: Field, value: &str) -> Self {
+        let mut conditions = Self::default();
+        *conditions.slot(field) = Some(value.to_string());
+        conditions
+    }
+
+    fn slot(&mut self, field: Field) -> &mut Option<String> {
+        match field {
+            Field::From => &mut self.from,
+            Field::To => &mut self.to,
+            Field::Subject => &mut self.subject,
+        }
+    }
+
+    /// Both sets at once. A rule holds one substring per header, so two different ones
+    /// for the same header can't be expressed.
+    fn and(&self, other: &Conditions) -> Result<Conditions, String> {
+        let mut merged = self.clone();
+        for (field, value) in [(Field::From, &other.from), (Field::To, &other.to), (Field::Subject, &other.subject)] {
+            let Some(value) = value.clone() else {
+                continue;
+            };
+            match merged.slot(field) {
+                Some(existing) if *existing != value => {
+                    return Err(format!("two conditions on the same header (\"{}\" and \"{}\")", existing, value));
+                }
+                slot => *slot = Some(value),
+            }
+        }
+        Ok(merged)
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Header names a rule can match on
+fn field(header: &str) -> Option<Field> {
+    match header.trim().to_lowercase().as_str() {
+        "from" | "sender" => Some(Field::From),
+        "to" | "cc" | "to or cc" => Some(Field::To),
+        "subject" => Some(Field::Subject),
+        _ => None,
+    }
+}
+
+impl ImportReport {
+    fn skip(&mut self, name: &str, why: impl AsRef<str>) {
+        self.unsupported.push(format!("{}: {}", name, why.as_ref()));
+    }
+
+    /// Add the rules for one filter: one per alternative set of conditions, all doing the
+    /// first supported action. A filter with any condition that can't be expressed is left
+    /// out entirely rather than imported looser or stricter than it was.
+    fn add(&mut self, name: &str, conditions: Result<Vec<Conditions>, String>, actions: Vec<Result<RuleAction, String>>) {
+        let alternatives = match conditions {
+            Ok(alternatives) => alternatives,
+            Err(why) => return self.skip(name, format!("not imported, unsupported condition: {}", why)),
+        };
+        if alternatives.is_empty() || alternatives.iter().any(Conditions::is_empty) {
+            return self.skip(name, "not imported, it matches every message");
+        }
+
+        let mut supported = Vec::new();
+        for action in actions {
+            match action {
+                Ok(action) => supported.push(action),
+                Err(why) => self.skip(name, format!("unsupported action left out: {}", why)),
+            }
+        }
+        let mut supported = supported.into_iter();
+        let Some(action) = supported.next() else {
+            return self.skip(name, "not imported, none of its actions are supported");
+        };
+        for extra in supported {
+            self.skip(name, format!("only the first action is kept, \"{}\" left out", describe_action(&extra)));
+        }
+
+        for conditions in alternatives {
+            self.rules.push(FilterRule {
+                name: name.to_string(),
+                account: None,
+                folder: None,
+                from_contains: conditions.from,
+                to_contains: conditions.to,
+                subject_contains: conditions.subject,
+                action: action.clone(),
+            });
+        }
+    }
+}
+
+/// Whether `text` looks like Thunderbird's `msgFilterRules.dat` rather than a Sieve script
+pub fn looks_like_thunderbird(text: &str) -> bool {
+    text.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("version=\"") || line.starts_with("name=\"")
+    })
+}
+
+/// `"value"` of a `key="value"` line, unescaped
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+    value.replace("\\\"", "\"").replace("\\\\", "\\")
+}
+
+/// The folder path of a Thunderbird folder URI such as
+/// `imap://me%40example.com@imap.example.com/INBOX/Lists`
+fn folder_of_uri(uri: &str) -> String {
+    let path = uri.split_once("://").map_or(uri, |(_, rest)| rest.split_once('/').map_or("", |(_, path)| path));
+    crate::mailto::percent_decode(path).unwrap_or_else(|_| path.to_string())
+}
+
+/// One `(header,operator,value)` term of a Thunderbird condition
+type Term = (String, String, String);
+
+/// The terms of a Thunderbird condition and whether they are joined by OR. `None` for
+/// `ALL`.
+fn thunderbird_terms(condition: &str) -> Option<(bool, Vec<Term>)> {
+    let condition = condition.trim();
+    if condition.eq_ignore_ascii_case("ALL") {
+        return None;
+    }
+    let any = condition.starts_with("OR");
+    let mut terms = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        if c != '(' {
+            continue;
+        }
+        // Up to the closing parenthesis outside quotes, split at the first two commas
+        let (mut parts, mut quoted, mut escaped) = (vec![String::new()], false, false);
+        for c in chars.by_ref() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' && quoted {
+                escaped = true;
+                continue;
+            } else if c == '"' {
+                quoted = !quoted;
+                continue;
+            } else if c == ')' && !quoted {
+                break;
+            } else if c == ',' && !quoted && parts.len() < 3 {
+                parts.push(String::new());
+                continue;
+            }
+            if let Some(part) = parts.last_mut() {
+                part.push(c);
+            }
+        }
+        if let [header, operator, value] = &parts[..] {
+            terms.push((header.trim().to_string(), operator.trim().to_string(), value.to_string()));
+        }
+    }
+    Some((any, terms))
+}
+
+fn thunderbird_conditions(condition: &str) -> Result<Vec<Conditions>, String> {
+    let Some((any, terms)) = thunderbird_terms(condition) else {
+        return Ok(Vec::new());
+    };
+    let mut singles = Vec::new();
+    for (header, operator, value) in terms {
+        if operator != "contains" {
+            return Err(format!("{} {} \"{}\"", header, operator, value));
+        }
+        let field = field(&header).ok_or_else(|| format!("{} contains \"{}\"", header, value))?;
+        singles.push(Conditions::single(field, &value));
+    }
+    if any {
+        return Ok(singles);
+    }
+    singles
+        .iter()
+        .try_fold(Conditions::default(), |all, single| all.and(single))
+        .map(|all| vec![all])
+}
+
+/// Filter types (`type=`) that run on incoming mail: new mail, after junk
+/// classification and periodically
+const THUNDERBIRD_INCOMING: u32 = 0x1 | 0x20 | 0x100;
+
+/// Translate Thunderbird's `msgFilterRules.dat` of one account into rules. Conditions
+/// on the sender, recipients and subject with "contains" are understood, and the move,
+/// mark read and delete actions; disabled filters and ones that only run by hand or on
+/// sent mail are left out.
+pub fn from_thunderbird(text: &str) -> ImportReport {
+    struct Filter {
+        name: String,
+        enabled: bool,
+        kind: Option<u32>,
+        condition: String,
+        actions: Vec<(String, String)>,
+    }
+
+    let mut filters: Vec<Filter> = Vec::new();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = unquote(value);
+        if key.trim() == "name" {
+            filters.push(Filter { name: value, enabled: true, kind: None, condition: String::new(), actions: Vec::new() });
+            continue;
+        }
+        let Some(filter) = filters.last_mut() else {
+            continue;
+        };
+        match key.trim() {
+            "enabled" => filter.enabled = value == "yes",
+            "type" => filter.kind = value.parse().ok(),
+            "condition" => filter.condition = value,
+            "action" => filter.actions.push((value, String::new())),
+            "actionValue" => {
+                if let Some(action) = filter.actions.last_mut() {
+                    action.1 = value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut report = ImportReport::default();
+    for filter in filters {
+        if !filter.enabled {
+            report.skip(&filter.name, "not imported, it is disabled");
+            continue;
+        }
+        if filter.kind.is_some_and(|kind| kind & THUNDERBIRD_INCOMING == 0) {
+            report.skip(&filter.name, "not imported, it doesn't run on incoming mail");
+            continue;
+        }
+        let actions = filter
+            .actions
+            .iter()
+            // Rules already stop at the first match
+            .filter(|(action, _)| action != "Stop execution")
+            .map(|(action, value)| match action.as_str() {
+                "Move to folder" => Ok(RuleAction::Move { folder: folder_of_uri(value) }),
+                "Mark read" => Ok(RuleAction::MarkRead),
+                "Delete" => Ok(RuleAction::Delete),
+                _ if value.is_empty() => Err(action.clone()),
+                _ => Err(format!("{} {}", action, value)),
+            })
+            .collect();
+        report.add(&filter.name, thunderbird_conditions(&filter.condition), actions);
+    }
+    report
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Tag(String),
+    Str(String),
+    Number(u64),
+    Punct(char),
+    /// A `# rule:[name]` comment, as Roundcube and other editors write before each rule
+    Label(String),
+}
+
+fn tokenize(script: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = script.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' {
+            let end = chars[i..].iter().position(|&c| c == '\n').map_or(chars.len(), |n| i + n);
+            let comment: String = chars[i + 1..end].iter().collect();
+            if let Some(name) = comment.trim().strip_prefix("rule:[").and_then(|rest| rest.strip_suffix(']')) {
+                tokens.push(Token::Label(name.to_string()));
+            }
+            i = end;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            let rest: String = chars[i + 2..].iter().collect();
+            let end = rest.find("*/").ok_or("unterminated comment")?;
+            i += 2 + rest[..end].chars().count() + 2;
+        } else if c == '"' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("unterminated string".to_string()),
+                    Some('"') => break,
+                    Some('\\') => {
+                        if let Some(&next) = chars.get(i + 1) {
+                            value.push(next);
+                        }
+                        i += 2;
+                    }
+                    Some(&c) => {
+                        value.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Str(value));
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while chars.get(i).is_some_and(char::is_ascii_digit) {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            let number: u64 = digits.parse().map_err(|_| format!("number out of range: {}", digits))?;
+            let scale = match chars.get(i).map(char::to_ascii_uppercase) {
+                Some('K') => 1 << 10,
+                Some('M') => 1 << 20,
+                Some('G') => 1 << 30,
+                _ => 1,
+            };
+            if scale > 1 {
+                i += 1;
+            }
+            tokens.push(Token::Number(number.saturating_mul(scale)));
+        } else if c == ':' || c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            i += 1;
+            while chars.get(i).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if word == "text" && chars.get(i) == Some(&':') {
+                // Multi-line string: up to a line holding a single dot, dots doubled
+                let rest: String = chars[i + 1..].iter().collect();
+                let body = rest.split_once('\n').map_or("", |(_, body)| body);
+                let mut value = String::new();
+                let mut consumed = rest.len() - body.len();
+                let mut terminated = false;
+                for line in body.split_inclusive('\n') {
+                    consumed += line.len();
+                    let trimmed = line.trim_end_matches(['\r', '\n']);
+                    if trimmed == "." {
+                        terminated = true;
+                        break;
+                    }
+                    value.push_str(trimmed.strip_prefix('.').filter(|l| l.starts_with('.')).unwrap_or(trimmed));
+                    value.push('\n');
+                }
+                if !terminated {
+                    return Err("unterminated text: block".to_string());
+                }
+                tokens.push(Token::Str(value));
+                i += 1 + rest[..consumed].chars().count();
+            } else if let Some(tag) = word.strip_prefix(':') {
+                tokens.push(Token::Tag(tag.to_lowercase()));
+            } else {
+                tokens.push(Token::Word(word.to_lowercase()));
+            }
+        } else if "[](),;{}".contains(c) {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        } else {
+            return Err(format!("unexpected '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Argument {
+    Tag(String),
+    Strings(Vec<String>),
+    Number(u64),
+}
+
+/// A Sieve test, such as `header :contains "subject" "offer"` or `anyof (...)`
+#[derive(Debug, Clone)]
+struct Test {
+    name: String,
+    arguments: Vec<Argument>,
+    tests: Vec<Test>,
+}
+
+/// A Sieve command: an action, or a control such as `if` with its test and block
+#[derive(Debug, Clone)]
+struct Command {
+    name: String,
+    label: Option<String>,
+    arguments: Vec<Argument>,
+    tests: Vec<Test>,
+    block: Option<Vec<Command>>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Punct(c)) if c == punct => Ok(()),
+            other => Err(format!("expected '{}', found {:?}", punct, other)),
+        }
+    }
+
+    fn word(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            other => Err(format!("expected a command or test, found {:?}", other)),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<(Vec<Argument>, Vec<Test>), String> {
+        let mut arguments = Vec::new();
+        loop {
+            match self.peek().cloned() {
+                Some(Token::Tag(tag)) => arguments.push(Argument::Tag(tag)),
+                Some(Token::Number(number)) => arguments.push(Argument::Number(number)),
+                Some(Token::Str(value)) => arguments.push(Argument::Strings(vec![value])),
+                Some(Token::Punct('[')) => {
+                    self.position += 1;
+                    let mut list = Vec::new();
+                    loop {
+                        match self.next() {
+                            Some(Token::Str(value)) => list.push(value),
+                            other => return Err(format!("expected a string, found {:?}", other)),
+                        }
+                        match self.next() {
+                            Some(Token::Punct(',')) => continue,
+                            Some(Token::Punct(']')) => break,
+                            other => return Err(format!("expected ',' or ']', found {:?}", other)),
+                        }
+                    }
+                    arguments.push(Argument::Strings(list));
+                    continue;
+                }
+                _ => break,
+            }
+            self.position += 1;
+        }
+        let tests = match self.peek() {
+            Some(Token::Word(_)) => vec![self.test()?],
+            Some(Token::Punct('(')) => {
+                self.position += 1;
+                let mut tests = vec![self.test()?];
+                while self.peek() == Some(&Token::Punct(',')) {
+                    self.position += 1;
+                    tests.push(self.test()?);
+                }
+                self.expect(')')?;
+                tests
+            }
+            _ => Vec::new(),
+        };
+        Ok((arguments, tests))
+    }
+
+    fn test(&mut self) -> Result<Test, String> {
+        let name = self.word()?;
+        let (arguments, tests) = self.arguments()?;
+        Ok(Test { name, arguments, tests })
+    }
+
+    /// Commands up to the end of the script, or of the block when `nested`
+    fn commands(&mut self, nested: bool) -> Result<Vec<Command>, String> {
+        let mut commands = Vec::new();
+        let mut label = None;
+        loop {
+            match self.peek() {
+                None if nested => return Err("missing '}'".to_string()),
+                None => return Ok(commands),
+                Some(Token::Punct('}')) if nested => {
+                    self.position += 1;
+                    return Ok(commands);
+                }
+                Some(Token::Label(_)) => {
+                    if let Some(Token::Label(name)) = self.next() {
+                        label = Some(name);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            let name = self.word()?;
+            let (arguments, tests) = self.arguments()?;
+            let block = match self.next() {
+                Some(Token::Punct(';')) => None,
+                Some(Token::Punct('{')) => Some(self.commands(true)?),
+                other => return Err(format!("expected ';' or '{{' after {}, found {:?}", name, other)),
+            };
+            commands.push(Command { name, label: label.take(), arguments, tests, block });
+        }
+    }
+}
+
+/// The string lists of a test or command, in order
+fn string_lists(arguments: &[Argument]) -> Vec<&Vec<String>> {
+    arguments
+        .iter()
+        .filter_map(|argument| match argument {
+            Argument::Strings(list) => Some(list),
+            _ => None,
+        })
+        .collect()
+}
+
+fn sieve_conditions(test: &Test) -> Result<Vec<Conditions>, String> {
+    match test.name.as_str() {
+        "true" => Ok(vec![Conditions::default()]),
+        "anyof" => {
+            let mut alternatives = Vec::new();
+            for test in &test.tests {
+                alternatives.extend(sieve_conditions(test)?);
+            }
+            Ok(alternatives)
+        }
+        "allof" => test.tests.iter().try_fold(vec![Conditions::default()], |all, test| {
+            let mut combined = Vec::new();
+            for alternative in sieve_conditions(test)? {
+                for conditions in &all {
+                    combined.push(conditions.and(&alternative)?);
+                }
+            }
+            Ok(combined)
+        }),
+        "header" | "address" => {
+            let mut matching = None;
+            for argument in &test.arguments {
+                if let Argument::Tag(tag) = argument {
+                    match tag.as_str() {
+                        // Address parts and the default comparator still work as substrings
+                        "all" | "localpart" | "domain" | "comparator" => {}
+                        _ => matching = Some(tag.as_str()),
+                    }
+                }
+            }
+            let mut lists = string_lists(&test.arguments);
+            // The comparator's name is a string argument of its own
+            if test.arguments.contains(&Argument::Tag("comparator".to_string())) && lists.len() == 3 {
+                lists.remove(0);
+            }
+            let [headers, keys] = lists[..] else {
+                return Err(format!("{} with unexpected arguments", test.name));
+            };
+            let fields: Vec<Option<Field>> = headers.iter().map(|header| field(header)).collect();
+            let field = match fields[..] {
+                [Some(first), ..] if fields.iter().all(|f| *f == Some(first)) => first,
+                _ => return Err(format!("{} \"{}\"", test.name, headers.join("\", \""))),
+            };
+            keys.iter()
+                .map(|key| {
+                    let substring = match matching {
+                        Some("contains") => Some(key.as_str()),
+                        Some("matches") => key
+                            .strip_prefix('*')
+                            .and_then(|k| k.strip_suffix('*'))
+                            .filter(|k| !k.is_empty() && !k.contains(['*', '?', '\\'])),
+                        _ => None,
+                    };
+                    substring
+                        .map(|substring| Conditions::single(field, substring))
+                        .ok_or_else(|| format!("{} :{} \"{}\"", test.name, matching.unwrap_or("is"), key))
+                })
+                .collect()
+        }
+        other => Err(other.to_string()),
+    }
+}
+
+fn sieve_actions(block: &[Command]) -> Vec<Result<RuleAction, String>> {
+    block
+        .iter()
+        // Rules already stop at the first match and leave the message where it is otherwise
+        .filter(|command| !matches!(command.name.as_str(), "stop" | "keep"))
+        .map(|command| {
+            let strings = string_lists(&command.arguments);
+            let copy = command.arguments.contains(&Argument::Tag("copy".to_string()));
+            match (command.name.as_str(), strings.last().and_then(|list| list.first())) {
+                ("fileinto", Some(folder)) if !copy => Ok(RuleAction::Move { folder: folder.clone() }),
+                ("discard", _) => Ok(RuleAction::Delete),
+                ("addflag" | "setflag", _) if strings.iter().flat_map(|list| list.iter()).any(|flag| flag.eq_ignore_ascii_case("\\Seen")) => {
+                    Ok(RuleAction::MarkRead)
+                }
+                (name, Some(value)) => Err(format!("{} \"{}\"", name, value)),
+                (name, None) => Err(name.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Translate a Sieve script (RFC 5228) into rules, one per `if` and `elsif`, named by a
+/// `# rule:[name]` comment before it when there is one. Header and address tests on the
+/// sender, recipients and subject with `:contains` are understood, combined with `allof`
+/// and `anyof`, and the `fileinto`, `discard` and `addflag "\\Seen"` actions.
+pub fn from_sieve(script: &str) -> Result<ImportReport, String> {
+    let mut parser = Parser { tokens: tokenize(script)?, position: 0 };
+    let commands = parser.commands(false)?;
+
+    let mut report = ImportReport::default();
+    let mut number = 0;
+    for command in commands {
+        number += 1;
+        let name = command.label.clone().unwrap_or_else(|| format!("Sieve rule {}", number));
+        match (command.name.as_str(), &command.block) {
+            ("require", _) => number -= 1,
+            ("if" | "elsif", Some(block)) => {
+                let conditions = match &command.tests[..] {
+                    [test] => sieve_conditions(test),
+                    _ => Err(format!("{} without a single test", command.name)),
+                };
+                report.add(&name, conditions, sieve_actions(block));
+            }
+            ("else", Some(_)) => report.skip(&name, "not imported, an else branch matches every message the others don't"),
+            (other, _) => report.skip(&name, format!("not imported, \"{}\" outside a test applies to every message", other)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thunderbird_filters_become_rules() {
+        let report = from_thunderbird(
+            "version=\"9\"\nlogging=\"no\"\n\
+             name=\"Lists\"\nenabled=\"yes\"\ntype=\"17\"\naction=\"Move to folder\"\n\
+             actionValue=\"imap://me%40example.com@imap.example.com/INBOX/Lists\"\naction=\"Mark read\"\n\
+             condition=\"OR (to or cc,contains,dev@lists.example) OR (subject,contains,\\\"[dev, announce]\\\")\"\n\
+             name=\"Receipts\"\nenabled=\"yes\"\ntype=\"1\"\naction=\"Delete\"\n\
+             condition=\"AND (from,contains,shop.example) AND (body,contains,receipt)\"\n\
+             name=\"Old\"\nenabled=\"no\"\ntype=\"1\"\naction=\"Delete\"\ncondition=\"ALL\"\n",
+        );
+        let rules: Vec<_> = report.rules.iter().map(|r| (r.to_contains.as_deref(), r.subject_contains.as_deref(), &r.action)).collect();
+        let lists = RuleAction::Move { folder: "INBOX/Lists".to_string() };
+        assert_eq!(rules, vec![(Some("dev@lists.example"), None, &lists), (None, Some("[dev, announce]"), &lists)]);
+        assert_eq!(
+            report.unsupported,
+            vec![
+                "Lists: only the first action is kept, \"mark as read\" left out",
+                "Receipts: not imported, unsupported condition: body contains \"receipt\"",
+                "Old: not imported, it is disabled",
+            ]
+        );
+    }
+
+    #[test]
+    fn sieve_scripts_become_rules() {
+        let report = from_sieve(
+            "require [\"fileinto\", \"imap4flags\"];\n\
+             # rule:[Newsletters]\n\
+             if allof (address :contains \"from\" \"news@\", anyof (header :contains \"subject\" [\"Weekly\", \"Digest\"])) {\n\
+               fileinto \"News\"; addflag \"\\\\Seen\"; stop;\n\
+             } elsif header :matches [\"to\", \"cc\"] \"*@lists.example*\" { setflag \"\\\\Seen\"; }\n\
+             elsif header :regex \"subject\" \"^\\\\[spam\\\\]\" { discard; }\n\
+             /* everything else */\n\
+             else { redirect \"me@elsewhere.example\"; }\n",
+        )
+        .unwrap();
+        let rules: Vec<_> = report
+            .rules
+            .iter()
+            .map(|r| (r.name.as_str(), r.from_contains.as_deref(), r.to_contains.as_deref(), r.subject_contains.as_deref()))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("Newsletters", Some("news@"), None, Some("Weekly")),
+                ("Newsletters", Some("news@"), None, Some("Digest")),
+                ("Sieve rule 2", None, Some("@lists.example"), None),
+            ]
+        );
+        assert_eq!(report.rules[0].action, RuleAction::Move { folder: "News".to_string() });
+        assert_eq!(report.rules[2].action, RuleAction::MarkRead);
+        assert_eq!(report.unsupported.len(), 3);
+        assert!(report.unsupported[1].starts_with("Sieve rule 3: not imported, unsupported condition: header :regex"));
+        assert!(from_sieve("if header :contains \"from\" \"x\" { fileinto \"A\";").is_err());
+    }
+}
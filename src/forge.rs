@@ -0,0 +1,299 @@
+use crate::email::Email;
+
+/// The code hosting service a notification comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+}
+
+impl Forge {
+    pub fn name(self) -> &'static str {
+        match self {
+            Forge::GitHub => "GitHub",
+            Forge::GitLab => "GitLab",
+        }
+    }
+}
+
+/// What a notification is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    PullRequest,
+    MergeRequest,
+    Issue,
+    Discussion,
+    Commit,
+    Pipeline,
+    Release,
+    Other,
+}
+
+impl Subject {
+    pub fn label(self) -> &'static str {
+        match self {
+            Subject::PullRequest => "pull request",
+            Subject::MergeRequest => "merge request",
+            Subject::Issue => "issue",
+            Subject::Discussion => "discussion",
+            Subject::Commit => "commit",
+            Subject::Pipeline => "pipeline",
+            Subject::Release => "release",
+            Subject::Other => "notification",
+        }
+    }
+}
+
+/// A GitHub or GitLab notification, as told by its headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub forge: Forge,
+    /// `owner/repo`, or the GitLab project path
+    pub repository: String,
+    pub subject: Subject,
+    /// Pull request, merge request, issue or discussion number
+    pub number: Option<u64>,
+    /// What happened, such as `commented` or `reviewed`
+    pub action: String,
+    /// Why it was sent, such as `review requested` or `mention`
+    pub reason: Option<String>,
+    /// Page of the pull request, issue or pipeline
+    pub url: Option<String>,
+    /// Address that unsubscribes from the thread when written to, from `List-Unsubscribe`
+    pub unsubscribe: Option<String>,
+}
+
+impl Notification {
+    /// The number as the forge writes it: `#12` for issues and pull requests, `!12` for
+    /// GitLab merge requests
+    pub fn reference(&self) -> Option<String> {
+        let sigil = if self.subject == Subject::MergeRequest { '!' } else { '#' };
+        self.number.map(|number| format!("{}{}", sigil, number))
+    }
+
+    /// One line for the email list and viewer: `owner/repo#12 pull request · reviewed`
+    pub fn summary(&self) -> String {
+        format!(
+            "{}{} {} · {}",
+            self.repository,
+            self.reference().unwrap_or_default(),
+            self.subject.label(),
+            self.action
+        )
+    }
+}
+
+/// The notification `email` is, if it comes from GitHub or GitLab
+pub fn detect(email: &Email) -> Option<Notification> {
+    github(email).or_else(|| gitlab(email))
+}
+
+/// Message-IDs of GitHub notifications name what they are about:
+/// `<owner/repo/pull/12/review/345@github.com>`. Replies without a Message-ID of that
+/// form still refer to the first notification of the thread.
+fn github(email: &Email) -> Option<Notification> {
+    let reason = email.header("X-GitHub-Reason");
+    let (own, path) = ["Message-ID", "In-Reply-To"]
+        .iter()
+        .filter_map(|header| Some((*header == "Message-ID", email.header(header)?)))
+        .find_map(|(own, id)| Some((own, id.trim().trim_matches(['<', '>']).strip_suffix("@github.com")?)))?;
+    if reason.is_none() && email.header("X-GitHub-Sender").is_none() {
+        return None;
+    }
+
+    let parts: Vec<&str> = path.split('/').collect();
+    let [owner, repo, kind, rest @ ..] = parts.as_slice() else {
+        return None;
+    };
+    let repository = format!("{}/{}", owner, repo);
+    let (subject, segment) = match *kind {
+        "pull" => (Subject::PullRequest, "pull"),
+        "issues" => (Subject::Issue, "issues"),
+        "discussions" => (Subject::Discussion, "discussions"),
+        "commit" => (Subject::Commit, "commit"),
+        "releases" => (Subject::Release, "releases"),
+        "actions" => (Subject::Pipeline, "actions"),
+        _ => (Subject::Other, ""),
+    };
+    let number = rest.first().and_then(|n| n.parse().ok());
+    let action = match rest.get(1).copied() {
+        _ if !own => "updated",
+        None => "opened",
+        Some("review") => "reviewed",
+        Some("push") => "pushed",
+        Some("issue_event") => "updated",
+        Some(_) => "commented",
+    };
+    let url = match (segment, rest.first()) {
+        ("", _) | (_, None) => format!("https://github.com/{}", repository),
+        (segment, Some(item)) => format!("https://github.com/{}/{}/{}", repository, segment, item),
+    };
+
+    Some(Notification {
+        forge: Forge::GitHub,
+        repository,
+        subject,
+        number: if matches!(subject, Subject::Commit | Subject::Release | Subject::Pipeline) { None } else { number },
+        action: action.to_string(),
+        reason: reason.map(humanize),
+        url: Some(url),
+        unsubscribe: unsubscribe_address(email),
+    })
+}
+
+/// GitLab says what a notification is about in `X-GitLab-*` headers; the page is found
+/// among the links of the body
+fn gitlab(email: &Email) -> Option<Notification> {
+    let repository = email.header("X-GitLab-Project-Path")?.trim().to_string();
+    let iid = |header: &str| email.header(header).and_then(|n| n.trim().parse().ok());
+    let (subject, number, page) = if let Some(number) = iid("X-GitLab-MergeRequest-IID") {
+        (Subject::MergeRequest, Some(number), format!("/{}/-/merge_requests/{}", repository, number))
+    } else if let Some(number) = iid("X-GitLab-Issue-IID") {
+        (Subject::Issue, Some(number), format!("/{}/-/issues/{}", repository, number))
+    } else if let Some(pipeline) = email.header("X-GitLab-Pipeline-Id") {
+        (Subject::Pipeline, None, format!("/{}/-/pipelines/{}", repository, pipeline.trim()))
+    } else {
+        (Subject::Other, None, format!("/{}", repository))
+    };
+    let action = match email.header("X-GitLab-Pipeline-Status") {
+        Some(status) if subject == Subject::Pipeline => status.trim().to_string(),
+        _ if email.header("In-Reply-To").is_some() => "commented".to_string(),
+        _ => "updated".to_string(),
+    };
+
+    Some(Notification {
+        forge: Forge::GitLab,
+        repository,
+        subject,
+        number,
+        action,
+        reason: email.header("X-GitLab-NotificationReason").map(humanize),
+        url: email.body_text.as_deref().and_then(|body| find_link(body, &page)),
+        unsubscribe: unsubscribe_address(email),
+    })
+}
+
+/// `review_requested` as `review requested`
+fn humanize(reason: &str) -> String {
+    reason.trim().replace('_', " ")
+}
+
+/// The first link in `body` whose path starts with `page`
+fn find_link(body: &str, page: &str) -> Option<String> {
+    body.split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '(' | ')' | '"'))
+        .filter(|word| word.starts_with("https://") || word.starts_with("http://"))
+        .find(|link| {
+            link.split_once("://")
+                .and_then(|(_, rest)| rest.find('/').map(|slash| &rest[slash..]))
+                .is_some_and(|path| path.starts_with(page))
+        })
+        .map(|link| link.trim_end_matches(['.', ',']).to_string())
+}
+
+/// The `mailto:` address of `List-Unsubscribe`, without its query
+fn unsubscribe_address(email: &Email) -> Option<String> {
+    email
+        .header("List-Unsubscribe")?
+        .split(',')
+        .map(|entry| entry.trim().trim_matches(['<', '>']))
+        .find_map(|entry| entry.strip_prefix("mailto:"))
+        .map(|address| address.split('?').next().unwrap_or(address).to_string())
+        .filter(|address| address.contains('@'))
+}
+
+/// Notifications grouped by repository, repositories by name and each newest first
+pub fn group_by_repository(emails: Vec<Email>) -> Vec<Email> {
+    let mut notifications: Vec<(String, Email)> = emails
+        .into_iter()
+        .filter_map(|email| Some((detect(&email)?.repository.to_lowercase(), email)))
+        .collect();
+    notifications.sort_by(|(a, a_email), (b, b_email)| a.cmp(b).then(b_email.date.cmp(&a_email.date)));
+    notifications.into_iter().map(|(_, email)| email).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(headers: &[(&str, &str)], body: &str) -> Email {
+        let mut email = Email::new();
+        email.headers = headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        email.body_text = Some(body.to_string());
+        email
+    }
+
+    #[test]
+    fn github_notifications_are_recognised() {
+        let review = email(
+            &[
+                ("Message-ID", "<octo/hello/pull/12/review/345@github.com>"),
+                ("In-Reply-To", "<octo/hello/pull/12@github.com>"),
+                ("X-GitHub-Reason", "review_requested"),
+                ("List-Unsubscribe", "<mailto:unsub+abc@reply.github.com>, <https://github.com/notifications/unsubscribe/abc>"),
+            ],
+            "",
+        );
+        let notification = detect(&review).unwrap();
+        assert_eq!(notification.forge, Forge::GitHub);
+        assert_eq!(notification.summary(), "octo/hello#12 pull request · reviewed");
+        assert_eq!(notification.reason.as_deref(), Some("review requested"));
+        assert_eq!(notification.url.as_deref(), Some("https://github.com/octo/hello/pull/12"));
+        assert_eq!(notification.unsubscribe.as_deref(), Some("unsub+abc@reply.github.com"));
+
+        let opened = email(&[("Message-ID", "<octo/hello/issues/7@github.com>"), ("X-GitHub-Reason", "mention")], "");
+        assert_eq!(detect(&opened).unwrap().summary(), "octo/hello#7 issue · opened");
+
+        // Anyone can pick such a Message-ID; GitHub's own headers are required
+        let lookalike = email(&[("Message-ID", "<octo/hello/issues/7@github.com>")], "");
+        assert_eq!(detect(&lookalike), None);
+    }
+
+    #[test]
+    fn gitlab_notifications_are_recognised() {
+        let comment = email(
+            &[
+                ("X-GitLab-Project-Path", "group/app"),
+                ("X-GitLab-MergeRequest-IID", "42"),
+                ("X-GitLab-NotificationReason", "mentioned"),
+                ("In-Reply-To", "<merge_request_1@gitlab.example.com>"),
+            ],
+            "Bob commented:\n\nLooks good\n\n-- \nReply to this email directly or view it on GitLab: https://gitlab.example.com/group/app/-/merge_requests/42#note_9\n",
+        );
+        let notification = detect(&comment).unwrap();
+        assert_eq!(notification.summary(), "group/app!42 merge request · commented");
+        assert_eq!(notification.url.as_deref(), Some("https://gitlab.example.com/group/app/-/merge_requests/42#note_9"));
+
+        let pipeline = email(
+            &[("X-GitLab-Project-Path", "group/app"), ("X-GitLab-Pipeline-Id", "77"), ("X-GitLab-Pipeline-Status", "failed")],
+            "",
+        );
+        assert_eq!(detect(&pipeline).unwrap().summary(), "group/app pipeline · failed");
+        assert_eq!(detect(&email(&[("Subject", "Hello")], "")), None);
+    }
+
+    #[test]
+    fn cached_notifications_are_grouped_by_repository() {
+        let dir = std::env::temp_dir().join(format!("tuimail-forge-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = crate::database::EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let emails: Vec<Email> = [
+            email(&[("X-GitHub-Reason", "subscribed"), ("In-Reply-To", "<octo/zeta/pull/1@github.com>")], ""),
+            email(&[("X-GitHub-Reason", "subscribed"), ("In-Reply-To", "<octo/alpha/pull/2@github.com>")], ""),
+            email(&[("Subject", "Hi")], "hello"),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(n, mut email)| {
+            email.id = (n + 1).to_string();
+            email
+        })
+        .collect();
+        db.save_emails("me@example.com", "INBOX", &emails).unwrap();
+
+        let grouped = group_by_repository(db.get_forge_notifications("me@example.com").unwrap());
+        let repositories: Vec<String> = grouped.iter().map(|email| detect(email).unwrap().repository).collect();
+        assert_eq!(repositories, vec!["octo/alpha", "octo/zeta"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,731 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
+use lettre::address::Envelope;
+use lettre::Message;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::credentials::SecureCredentials;
+use crate::database::EmailDatabase;
+use crate::email::{debug_log, EmailError};
+use crate::transport::{FetchedMessage, ImapBackend, SmtpBackend};
+
+const API_HOST: &str = "gmail.googleapis.com";
+const API_PATH: &str = "/gmail/v1/users/me/";
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_HOST: &str = "oauth2.googleapis.com";
+const SCOPE: &str = "https://www.googleapis.com/auth/gmail.modify";
+
+/// Largest API response read; a raw message is base64 encoded inside JSON
+const RESPONSE_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// Keyring entries of an account using the Gmail API
+pub const CLIENT_SECRET_KIND: &str = "gmail_client_secret";
+pub const REFRESH_TOKEN_KIND: &str = "gmail_refresh_token";
+
+/// Gmail's system labels shown as folders, and the folder names they are shown under.
+/// The rest (UNREAD, CHAT, the inbox categories) are states or views, not folders.
+const SYSTEM_FOLDERS: &[(&str, &str)] = &[
+    ("INBOX", "INBOX"),
+    ("SENT", "Sent"),
+    ("DRAFT", "Drafts"),
+    ("STARRED", "Starred"),
+    ("IMPORTANT", "Important"),
+    ("SPAM", "Spam"),
+    ("TRASH", "Trash"),
+];
+
+/// IMAP flags kept as Gmail labels: the label, and whether the flag is the label's absence
+const FLAG_LABELS: &[(&str, &str, bool)] = &[
+    ("\\Seen", "UNREAD", true),
+    ("\\Flagged", "STARRED", false),
+    ("\\Draft", "DRAFT", false),
+];
+
+/// One request to the Gmail REST API, by path below `users/me/`
+pub trait GmailHttp {
+    fn call(&mut self, method: &str, path: &str, body: Option<&Value>) -> Result<(u16, Vec<u8>)>;
+}
+
+/// Talks to gmail.googleapis.com with an OAuth access token
+pub struct GmailHttpClient {
+    access_token: String,
+}
+
+impl GmailHttpClient {
+    pub fn new(access_token: String) -> Self {
+        Self { access_token }
+    }
+}
+
+impl GmailHttp for GmailHttpClient {
+    fn call(&mut self, method: &str, path: &str, body: Option<&Value>) -> Result<(u16, Vec<u8>)> {
+        let authorization = format!("Bearer {}", self.access_token);
+        let body = body.map(serde_json::to_vec).transpose()?;
+        let mut headers = vec![("Authorization", authorization.as_str()), ("Accept", "application/json")];
+        if body.is_some() {
+            headers.push(("Content-Type", "application/json"));
+        }
+        crate::http::https_request(method, API_HOST, &format!("{}{}", API_PATH, path), &headers, body.as_deref(), RESPONSE_LIMIT)
+    }
+}
+
+/// Percent-encode a query or form value
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Decode a percent-encoded query value
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match u8::from_str_radix(&value[i + 1..i + 3], 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Gmail returns base64url, with or without padding
+fn decode_raw(raw: &str) -> Result<Vec<u8>, EmailError> {
+    BASE64_URL
+        .decode(raw.trim_end_matches('='))
+        .map_err(|e| EmailError::ImapError(format!("Malformed message from Gmail: {}", e)))
+}
+
+/// IMAP flags of a message with these labels
+pub fn flags_of(labels: &[String]) -> Vec<String> {
+    FLAG_LABELS
+        .iter()
+        .filter(|(_, label, absent)| labels.iter().any(|l| l == label) != *absent)
+        .map(|(flag, _, _)| flag.to_string())
+        .collect()
+}
+
+fn label_ids(message: &Value) -> Vec<String> {
+    message["labelIds"]
+        .as_array()
+        .map(|labels| labels.iter().filter_map(|l| l.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+fn database_error(e: anyhow::Error) -> EmailError {
+    EmailError::ConnectionError(format!("Database error: {}", e))
+}
+
+/// A Gmail account seen through the REST API as an IMAP server: labels are folders, and
+/// the messages of each label get UIDs in the order they are first seen, oldest first,
+/// kept in the database. After the first listing of a label, changes are caught up with
+/// through the history API rather than by listing it again.
+pub struct GmailBackend<'a, H> {
+    http: H,
+    db: &'a EmailDatabase,
+    account: String,
+    /// Folder names and label IDs, loaded when first needed
+    labels: Option<Vec<(String, String)>>,
+    /// The history was applied during this session
+    caught_up: bool,
+}
+
+impl<'a, H: GmailHttp> GmailBackend<'a, H> {
+    pub fn new(http: H, db: &'a EmailDatabase, account: &str) -> Self {
+        Self { http, db, account: account.to_string(), labels: None, caught_up: false }
+    }
+
+    /// Call the API and parse its JSON answer
+    fn api(&mut self, method: &str, path: &str, body: Option<&Value>) -> Result<Value, EmailError> {
+        let (status, response) = self
+            .http
+            .call(method, path, body)
+            .map_err(|e| EmailError::ConnectionError(format!("Gmail API: {}", e)))?;
+        let value: Value = if response.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&response).map_err(|e| EmailError::ImapError(format!("Malformed Gmail API response: {}", e)))?
+        };
+        if (200..300).contains(&status) {
+            return Ok(value);
+        }
+        let message = value["error"]["message"].as_str().unwrap_or("no details").to_string();
+        Err(match status {
+            401 | 403 => EmailError::AuthError(format!("Gmail API refused {}: {}", path, message)),
+            404 => EmailError::NotFound(format!("Gmail API {}: {}", path, message)),
+            _ => EmailError::ImapError(format!("Gmail API returned HTTP {} for {}: {}", status, path, message)),
+        })
+    }
+
+    fn load_labels(&mut self) -> Result<&[(String, String)], EmailError> {
+        if self.labels.is_none() {
+            let response = self.api("GET", "labels", None)?;
+            let mut labels = Vec::new();
+            for label in response["labels"].as_array().into_iter().flatten() {
+                let (Some(id), Some(name)) = (label["id"].as_str(), label["name"].as_str()) else {
+                    continue;
+                };
+                if label["type"] == "system" {
+                    if let Some((_, folder)) = SYSTEM_FOLDERS.iter().find(|(system, _)| *system == id) {
+                        labels.push((folder.to_string(), id.to_string()));
+                    }
+                } else {
+                    labels.push((name.to_string(), id.to_string()));
+                }
+            }
+            self.labels = Some(labels);
+        }
+        Ok(self.labels.as_deref().unwrap_or_default())
+    }
+
+    fn label_id(&mut self, folder: &str) -> Result<String, EmailError> {
+        self.load_labels()?
+            .iter()
+            .find(|(name, _)| name == folder)
+            .map(|(_, id)| id.clone())
+            .ok_or_else(|| EmailError::NotFound(format!("Gmail label {}", folder)))
+    }
+
+    /// Folders of the account: the system labels that work as folders, then user labels
+    pub fn list_folders(&mut self) -> Result<Vec<String>, EmailError> {
+        Ok(self.load_labels()?.iter().map(|(name, _)| name.clone()).collect())
+    }
+
+    fn message_id(&self, label: &str, uid: u32) -> Result<String, EmailError> {
+        self.db
+            .gmail_message_id(&self.account, label, uid)
+            .map_err(database_error)?
+            .ok_or_else(|| EmailError::NotFound(format!("UID {} in Gmail label {}", uid, label)))
+    }
+
+    fn record_labels(&self, message_id: &str, labels: Vec<String>) -> Result<(), EmailError> {
+        self.db
+            .set_gmail_labels(&self.account, &[(message_id.to_string(), labels)])
+            .map_err(database_error)
+    }
+
+    fn current_history_id(&mut self) -> Result<String, EmailError> {
+        let profile = self.api("GET", "profile", None)?;
+        profile["historyId"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| EmailError::ImapError("Gmail profile without a history ID".to_string()))
+    }
+
+    /// Apply the label changes made since the last sync. When Gmail no longer has history
+    /// that old, every label is listed again instead.
+    fn catch_up(&mut self) -> Result<(), EmailError> {
+        let Some(start) = self.db.gmail_history_id(&self.account).map_err(database_error)? else {
+            let history_id = self.current_history_id()?;
+            return self.db.set_gmail_history_id(&self.account, &history_id).map_err(database_error);
+        };
+
+        let mut changes: Vec<(String, Vec<String>)> = Vec::new();
+        let mut latest = start.clone();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut path = format!("history?startHistoryId={}&maxResults=500", encode(&start));
+            if let Some(token) = &page_token {
+                path.push_str(&format!("&pageToken={}", encode(token)));
+            }
+            let response = match self.api("GET", &path, None) {
+                Err(EmailError::NotFound(_)) => {
+                    debug_log(&format!("Gmail history of {} expired; listing labels again", self.account));
+                    self.db.reset_gmail_history(&self.account).map_err(database_error)?;
+                    let history_id = self.current_history_id()?;
+                    return self.db.set_gmail_history_id(&self.account, &history_id).map_err(database_error);
+                }
+                response => response?,
+            };
+            for record in response["history"].as_array().into_iter().flatten() {
+                for kind in ["messagesAdded", "labelsAdded", "labelsRemoved"] {
+                    for change in record[kind].as_array().into_iter().flatten() {
+                        if let Some(id) = change["message"]["id"].as_str() {
+                            changes.push((id.to_string(), label_ids(&change["message"])));
+                        }
+                    }
+                }
+                for change in record["messagesDeleted"].as_array().into_iter().flatten() {
+                    if let Some(id) = change["message"]["id"].as_str() {
+                        changes.push((id.to_string(), Vec::new()));
+                    }
+                }
+            }
+            if let Some(history_id) = response["historyId"].as_str() {
+                latest = history_id.to_string();
+            }
+            match response["nextPageToken"].as_str() {
+                Some(token) => page_token = Some(token.to_string()),
+                None => break,
+            }
+        }
+
+        // Only the last state of each message matters
+        let mut seen = std::collections::HashSet::new();
+        let mut last: Vec<(String, Vec<String>)> = changes.into_iter().rev().filter(|(id, _)| seen.insert(id.clone())).collect();
+        last.reverse();
+        if !last.is_empty() {
+            debug_log(&format!("Applying {} Gmail history changes for {}", last.len(), self.account));
+        }
+        self.db.set_gmail_labels(&self.account, &last).map_err(database_error)?;
+        self.db.set_gmail_history_id(&self.account, &latest).map_err(database_error)
+    }
+
+    /// Bring the UIDs of a label up to date: catch up with the history once per session,
+    /// and list the label's messages the first time it is opened
+    fn refresh(&mut self, label: &str) -> Result<(), EmailError> {
+        if !self.caught_up {
+            self.catch_up()?;
+            self.caught_up = true;
+        }
+        if self.db.gmail_label_listed(&self.account, label).map_err(database_error)? {
+            return Ok(());
+        }
+
+        let mut ids = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut path = format!("messages?labelIds={}&includeSpamTrash=true&maxResults=500", encode(label));
+            if let Some(token) = &page_token {
+                path.push_str(&format!("&pageToken={}", encode(token)));
+            }
+            let response = self.api("GET", &path, None)?;
+            for message in response["messages"].as_array().into_iter().flatten() {
+                if let Some(id) = message["id"].as_str() {
+                    ids.push(id.to_string());
+                }
+            }
+            match response["nextPageToken"].as_str() {
+                Some(token) => page_token = Some(token.to_string()),
+                None => break,
+            }
+        }
+        debug_log(&format!("Listed {} messages of Gmail label {} for {}", ids.len(), label, self.account));
+        self.db.set_gmail_label_listing(&self.account, label, &ids).map_err(database_error)
+    }
+
+    /// A message in the given format, with its labels recorded
+    fn get_message(&mut self, id: &str, format: &str) -> Result<Value, EmailError> {
+        let message = self.api("GET", &format!("messages/{}?format={}", encode(id), format), None)?;
+        self.record_labels(id, label_ids(&message))?;
+        Ok(message)
+    }
+
+    fn modify(&mut self, id: &str, add: &[&str], remove: &[&str]) -> Result<(), EmailError> {
+        let message = self.api(
+            "POST",
+            &format!("messages/{}/modify", encode(id)),
+            Some(&json!({ "addLabelIds": add, "removeLabelIds": remove })),
+        )?;
+        self.record_labels(id, label_ids(&message))
+    }
+
+    fn trash(&mut self, id: &str) -> Result<(), EmailError> {
+        let message = self.api("POST", &format!("messages/{}/trash", encode(id)), None)?;
+        self.record_labels(id, label_ids(&message))
+    }
+}
+
+impl<H: GmailHttp> ImapBackend for GmailBackend<'_, H> {
+    fn uids(&mut self, folder: &str) -> Result<Vec<u32>, EmailError> {
+        let label = self.label_id(folder)?;
+        self.refresh(&label)?;
+        let uids = self.db.gmail_uids(&self.account, &label).map_err(database_error)?;
+        Ok(uids.into_iter().map(|(uid, _)| uid).collect())
+    }
+
+    fn fetch(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        let label = self.label_id(folder)?;
+        let mut messages = Vec::new();
+        for &uid in uids {
+            let id = self.message_id(&label, uid)?;
+            let message = self.get_message(&id, "raw")?;
+            let raw = decode_raw(message["raw"].as_str().unwrap_or_default())?;
+            messages.push(FetchedMessage { uid, flags: flags_of(&label_ids(&message)), raw });
+        }
+        Ok(messages)
+    }
+
+    fn gmail_ids(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, String>, EmailError> {
+        let label = self.label_id(folder)?;
+        let mut ids = HashMap::new();
+        for &uid in uids {
+            if let Some(id) = self.db.gmail_message_id(&self.account, &label, uid).map_err(database_error)? {
+                ids.insert(uid, id);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn fetch_headers(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        let label = self.label_id(folder)?;
+        let mut messages = Vec::new();
+        for &uid in uids {
+            let id = self.message_id(&label, uid)?;
+            let message = self.get_message(&id, "metadata")?;
+            let mut raw = Vec::new();
+            for header in message["payload"]["headers"].as_array().into_iter().flatten() {
+                let name = header["name"].as_str().unwrap_or_default();
+                let value = header["value"].as_str().unwrap_or_default();
+                raw.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+            }
+            raw.extend_from_slice(b"\r\n");
+            messages.push(FetchedMessage { uid, flags: flags_of(&label_ids(&message)), raw });
+        }
+        Ok(messages)
+    }
+
+    fn flags(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, Vec<String>>, EmailError> {
+        let label = self.label_id(folder)?;
+        self.refresh(&label)?;
+        let mut flags = HashMap::new();
+        for &uid in uids {
+            let Some(id) = self.db.gmail_message_id(&self.account, &label, uid).map_err(database_error)? else {
+                continue;
+            };
+            let mut labels = self.db.gmail_labels(&self.account, &id).map_err(database_error)?;
+            if labels.is_empty() {
+                labels = label_ids(&self.get_message(&id, "minimal")?);
+            }
+            flags.insert(uid, flags_of(&labels));
+        }
+        Ok(flags)
+    }
+
+    fn store_flag(&mut self, folder: &str, uid: u32, flag: &str, set: bool) -> Result<(), EmailError> {
+        let Some((_, flag_label, absent)) = FLAG_LABELS.iter().find(|(f, _, _)| f.eq_ignore_ascii_case(flag)) else {
+            return Err(EmailError::ImapError(format!("Gmail has no label for the {} flag", flag)));
+        };
+        let label = self.label_id(folder)?;
+        let id = self.message_id(&label, uid)?;
+        if set != *absent {
+            self.modify(&id, &[flag_label], &[])
+        } else {
+            self.modify(&id, &[], &[flag_label])
+        }
+    }
+
+    /// Moves the message to the trash: permanent deletion needs full access to the mailbox,
+    /// and Gmail empties the trash after 30 days
+    fn delete(&mut self, folder: &str, uid: u32) -> Result<(), EmailError> {
+        let label = self.label_id(folder)?;
+        let id = self.message_id(&label, uid)?;
+        self.trash(&id)
+    }
+
+    fn move_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        let label = self.label_id(folder)?;
+        let target = self.label_id(target)?;
+        let id = self.message_id(&label, uid)?;
+        if target == "TRASH" {
+            return self.trash(&id);
+        }
+        self.modify(&id, &[&target], &[&label])
+    }
+
+    fn unseen_count(&mut self, folder: &str) -> Result<u32, EmailError> {
+        let label = self.label_id(folder)?;
+        let response = self.api("GET", &format!("labels/{}", encode(&label)), None)?;
+        Ok(response["messagesUnread"].as_u64().unwrap_or(0) as u32)
+    }
+
+    fn append(&mut self, folder: &str, raw: &[u8], flags: &[String]) -> Result<(), EmailError> {
+        let label = self.label_id(folder)?;
+        let has = |flag: &str| flags.iter().any(|f| f.eq_ignore_ascii_case(flag));
+        let mut labels = vec![label];
+        if !has("\\Seen") {
+            labels.push("UNREAD".to_string());
+        }
+        if has("\\Flagged") {
+            labels.push("STARRED".to_string());
+        }
+        let message = self.api(
+            "POST",
+            "messages?internalDateSource=dateHeader",
+            Some(&json!({ "raw": BASE64_URL.encode(raw), "labelIds": labels })),
+        )?;
+        if let Some(id) = message["id"].as_str() {
+            self.record_labels(id, label_ids(&message))?;
+        }
+        Ok(())
+    }
+}
+
+impl<H: GmailHttp> SmtpBackend for GmailBackend<'_, H> {
+    fn send(&mut self, message: &Message) -> Result<(), EmailError> {
+        let sent = self.api("POST", "messages/send", Some(&json!({ "raw": BASE64_URL.encode(message.formatted()) })))?;
+        if let Some(id) = sent["id"].as_str() {
+            self.record_labels(id, label_ids(&sent))?;
+        }
+        Ok(())
+    }
+
+    fn keeps_bcc(&self) -> bool {
+        true
+    }
+
+    /// Gmail sends to the recipients in the message's headers, so a message can't be
+    /// redirected to other addresses
+    fn send_raw(&mut self, _envelope: &Envelope, _raw: &[u8]) -> Result<(), EmailError> {
+        Err(EmailError::SmtpError("Bouncing messages needs SMTP; the Gmail API can only send".to_string()))
+    }
+}
+
+/// Access tokens by account address, and when they stop working
+fn token_cache() -> &'static Mutex<HashMap<String, (String, Instant)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (String, Instant)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// POST a form to Google's token endpoint
+fn token_request(form: &[(&str, &str)]) -> Result<Value> {
+    let body: Vec<String> = form.iter().map(|(name, value)| format!("{}={}", name, encode(value))).collect();
+    let (status, response) = crate::http::https_request(
+        "POST",
+        TOKEN_HOST,
+        "/token",
+        &[("Content-Type", "application/x-www-form-urlencoded"), ("Accept", "application/json")],
+        Some(body.join("&").as_bytes()),
+        1024 * 1024,
+    )?;
+    let value: Value = serde_json::from_slice(&response).unwrap_or(Value::Null);
+    if status != 200 {
+        let reason = value["error_description"].as_str().or(value["error"].as_str()).unwrap_or("no details");
+        return Err(anyhow!("Google token endpoint returned HTTP {}: {}", status, reason));
+    }
+    Ok(value)
+}
+
+/// An access token for the account, refreshed with the stored refresh token when the
+/// cached one is about to expire
+pub fn access_token(account_email: &str, client_id: &str, credentials: &SecureCredentials) -> Result<String, EmailError> {
+    if let Some((token, expires)) = token_cache().lock().ok().and_then(|cache| cache.get(account_email).cloned()) {
+        if Instant::now() < expires {
+            return Ok(token);
+        }
+    }
+
+    let secret = |kind: &str| {
+        credentials
+            .get_password(account_email, kind)
+            .ok()
+            .flatten()
+            .ok_or_else(|| EmailError::AuthError(format!("No Gmail API login stored for {}; run `tuimail gmail-login`", account_email)))
+    };
+    let (client_secret, refresh_token) = (secret(CLIENT_SECRET_KIND)?, secret(REFRESH_TOKEN_KIND)?);
+    let response = token_request(&[
+        ("client_id", client_id),
+        ("client_secret", &client_secret),
+        ("refresh_token", &refresh_token),
+        ("grant_type", "refresh_token"),
+    ])
+    .map_err(|e| EmailError::AuthError(format!("Failed to refresh the Gmail access token: {}", e)))?;
+
+    let token = response["access_token"]
+        .as_str()
+        .ok_or_else(|| EmailError::AuthError("Google returned no access token".to_string()))?
+        .to_string();
+    // Renew a minute early so a token doesn't expire in the middle of a sync
+    let lifetime = response["expires_in"].as_u64().unwrap_or(3600).saturating_sub(60);
+    if let Ok(mut cache) = token_cache().lock() {
+        cache.insert(account_email.to_string(), (token.clone(), Instant::now() + Duration::from_secs(lifetime)));
+    }
+    Ok(token)
+}
+
+/// Random URL-safe text for the PKCE verifier and the state parameter
+fn random_token() -> Result<String> {
+    Ok(BASE64_URL.encode(crate::config_crypto::random_bytes(32)?))
+}
+
+/// Sign in to Google in the browser (OAuth with PKCE and a loopback redirect) and return
+/// the refresh token. `show_url` is given the address to open.
+pub fn login(client_id: &str, client_secret: &str, show_url: impl FnOnce(&str)) -> Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let redirect_uri = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+    let verifier = random_token()?;
+    let challenge = BASE64_URL.encode(Sha256::digest(verifier.as_bytes()));
+    let state = random_token()?;
+
+    let url = format!(
+        "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&code_challenge={}&code_challenge_method=S256&access_type=offline&prompt=consent&state={}",
+        AUTH_URL,
+        encode(client_id),
+        encode(&redirect_uri),
+        encode(SCOPE),
+        challenge,
+        state
+    );
+    show_url(&url);
+
+    // Wait for the browser to come back with the code
+    let (mut stream, _) = listener.accept()?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let query = request_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|target| target.split_once('?'))
+        .map(|(_, query)| query.to_string())
+        .unwrap_or_default();
+    let params: HashMap<String, String> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.to_string(), decode(value)))
+        .collect();
+    let page = "<html><body>tuimail is signed in. You can close this window.</body></html>";
+    let _ = write!(stream, "HTTP/1.0 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}", page.len(), page);
+
+    if let Some(error) = params.get("error") {
+        return Err(anyhow!("Google sign-in failed: {}", error));
+    }
+    if params.get("state") != Some(&state) {
+        return Err(anyhow!("Google sign-in answered with a different state; try again"));
+    }
+    let code = params.get("code").ok_or_else(|| anyhow!("Google sign-in returned no code"))?;
+
+    let response = token_request(&[
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+        ("code", code),
+        ("code_verifier", &verifier),
+        ("redirect_uri", &redirect_uri),
+        ("grant_type", "authorization_code"),
+    ])?;
+    response["refresh_token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Google returned no refresh token"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Answers requests from a script of (method and path, status, JSON) in order
+    struct Script {
+        answers: VecDeque<(String, u16, Value)>,
+        bodies: Vec<Value>,
+    }
+
+    impl GmailHttp for &mut Script {
+        fn call(&mut self, method: &str, path: &str, body: Option<&Value>) -> Result<(u16, Vec<u8>)> {
+            let (expected, status, answer) = self.answers.pop_front().expect("unexpected request");
+            assert_eq!(expected, format!("{} {}", method, path));
+            self.bodies.extend(body.cloned());
+            Ok((status, serde_json::to_vec(&answer)?))
+        }
+    }
+
+    fn script(answers: Vec<(&str, u16, Value)>) -> Script {
+        Script {
+            answers: answers.into_iter().map(|(request, status, answer)| (request.to_string(), status, answer)).collect(),
+            bodies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_labels_uids_and_history() {
+        let dir = std::env::temp_dir().join(format!("tuimail-gmail-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let labels = json!({"labels": [
+            {"id": "INBOX", "name": "INBOX", "type": "system"},
+            {"id": "UNREAD", "name": "UNREAD", "type": "system"},
+            {"id": "CATEGORY_SOCIAL", "name": "CATEGORY_SOCIAL", "type": "system"},
+            {"id": "SENT", "name": "SENT", "type": "system"},
+            {"id": "Label_1", "name": "Work/Reports", "type": "user"}
+        ]});
+
+        let mut first = script(vec![
+            ("GET labels", 200, labels.clone()),
+            ("GET profile", 200, json!({"historyId": "100"})),
+            ("GET messages?labelIds=INBOX&includeSpamTrash=true&maxResults=500", 200, json!({"messages": [{"id": "m2"}, {"id": "m1"}]})),
+        ]);
+        let mut gmail = GmailBackend::new(&mut first, &db, "me@gmail.com");
+        assert_eq!(gmail.list_folders().unwrap(), vec!["INBOX", "Sent", "Work/Reports"]);
+        assert_eq!(gmail.uids("INBOX").unwrap(), vec![1, 2]);
+        assert_eq!(db.gmail_message_id("me@gmail.com", "INBOX", 1).unwrap().as_deref(), Some("m1"));
+
+        // A new message arrives, m1 is archived and m2 is read
+        let mut second = script(vec![
+            ("GET labels", 200, labels.clone()),
+            ("GET history?startHistoryId=100&maxResults=500", 200, json!({"historyId": "120", "history": [
+                {"messagesAdded": [{"message": {"id": "m3", "labelIds": ["INBOX", "UNREAD"]}}]},
+                {"labelsRemoved": [{"message": {"id": "m1", "labelIds": []}, "labelIds": ["INBOX"]}]},
+                {"labelsRemoved": [{"message": {"id": "m2", "labelIds": ["INBOX"]}, "labelIds": ["UNREAD"]}]}
+            ]})),
+        ]);
+        let mut gmail = GmailBackend::new(&mut second, &db, "me@gmail.com");
+        assert_eq!(gmail.uids("INBOX").unwrap(), vec![2, 3]);
+        let flags = gmail.flags("INBOX", &[2, 3]).unwrap();
+        assert_eq!(flags[&2], vec!["\\Seen"]);
+        assert!(flags[&3].is_empty());
+        assert_eq!(db.gmail_history_id("me@gmail.com").unwrap().as_deref(), Some("120"));
+
+        // Expired history: the label is listed again and m2 keeps its UID
+        let mut third = script(vec![
+            ("GET labels", 200, labels),
+            ("GET history?startHistoryId=120&maxResults=500", 404, json!({"error": {"message": "Requested entity was not found."}})),
+            ("GET profile", 200, json!({"historyId": "200"})),
+            ("GET messages?labelIds=INBOX&includeSpamTrash=true&maxResults=500", 200, json!({"messages": [{"id": "m4"}, {"id": "m2"}]})),
+        ]);
+        let mut gmail = GmailBackend::new(&mut third, &db, "me@gmail.com");
+        assert_eq!(gmail.uids("INBOX").unwrap(), vec![2, 4]);
+        assert_eq!(db.gmail_message_id("me@gmail.com", "INBOX", 4).unwrap().as_deref(), Some("m4"));
+        assert!(third.answers.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_send_keeps_bcc() {
+        let dir = std::env::temp_dir().join(format!("tuimail-gmail-send-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let address = |address: &str| crate::email::EmailAddress { name: None, address: address.to_string() };
+        let mut email = crate::email::Email::new();
+        email.subject = "Hi".to_string();
+        email.to = vec![address("to@example.com")];
+        email.bcc = vec![address("hidden@example.com")];
+
+        let mut http = script(vec![("POST messages/send", 200, json!({"id": "m9", "labelIds": ["SENT"]}))]);
+        let mut gmail = GmailBackend::new(&mut http, &db, "me@gmail.com");
+        crate::transport::send_email(&mut gmail, &email, &address("me@gmail.com")).unwrap();
+        let raw = decode_raw(http.bodies[0]["raw"].as_str().unwrap()).unwrap();
+        assert!(String::from_utf8(raw).unwrap().contains("Bcc: hidden@example.com"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_flags_and_encoding() {
+        assert_eq!(flags_of(&["INBOX".to_string(), "STARRED".to_string()]), vec!["\\Seen", "\\Flagged"]);
+        assert!(flags_of(&["UNREAD".to_string()]).is_empty());
+        assert_eq!(encode("a b/c@d"), "a%20b%2Fc%40d");
+        assert_eq!(decode("4%2F0Ab-x%20y+z"), "4/0Ab-x y z");
+        assert_eq!(decode_raw("U3ViamVjdDogSGk=").unwrap(), b"Subject: Hi");
+    }
+}
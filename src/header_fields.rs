@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{OnceLock, RwLock};
+
+use crate::config::HeaderField;
+use crate::email::Email;
+
+/// The header fields of the configuration, set once at startup
+static FIELDS: OnceLock<RwLock<Vec<HeaderField>>> = OnceLock::new();
+
+/// Check the configured fields: names are lowercase letters, digits, `-` and `_`, each is
+/// used once, and none shadows a built-in search filter such as `from:`
+pub fn validate(fields: &[HeaderField]) -> Result<(), String> {
+    for (i, field) in fields.iter().enumerate() {
+        let valid = !field.name.is_empty()
+            && field.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("Header field name '{}' may only use lowercase letters, digits, '-' and '_'", field.name));
+        }
+        if field.header.trim().is_empty() {
+            return Err(format!("Header field '{}' names no header", field.name));
+        }
+        if crate::search::FILTERS.iter().any(|filter| filter.split(':').next() == Some(field.name.as_str())) {
+            return Err(format!("Header field name '{}' is a search filter already", field.name));
+        }
+        if fields[..i].iter().any(|other| other.name == field.name) {
+            return Err(format!("Header field '{}' is configured twice", field.name));
+        }
+    }
+    Ok(())
+}
+
+/// Use these fields for the rest of the session
+pub fn configure(fields: &[HeaderField]) {
+    if let Ok(mut configured) = FIELDS.get_or_init(Default::default).write() {
+        *configured = fields.to_vec();
+    }
+}
+
+/// The configured fields
+pub fn fields() -> Vec<HeaderField> {
+    FIELDS.get().and_then(|fields| fields.read().ok()).map(|fields| fields.clone()).unwrap_or_default()
+}
+
+pub fn is_field(name: &str) -> bool {
+    fields().iter().any(|field| field.name == name)
+}
+
+/// The values of the configured fields in a message's headers, by field name, for the
+/// `header_fields` column of the database
+pub fn extract(headers: &HashMap<String, String>) -> BTreeMap<String, String> {
+    fields()
+        .iter()
+        .filter_map(|field| Some((field.name.clone(), header_value(headers, &field.header)?)))
+        .collect()
+}
+
+fn header_value(headers: &HashMap<String, String>, header: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(header.trim()))
+        .map(|(_, value)| value.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|value| !value.is_empty())
+}
+
+/// The fields shown as list columns, with their value in `email` (empty when it has none)
+pub fn columns(email: &Email) -> Vec<(usize, String)> {
+    fields()
+        .iter()
+        .filter_map(|field| Some((field.column_width?, header_value(&email.headers, &field.header).unwrap_or_default())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, header: &str) -> HeaderField {
+        HeaderField { name: name.to_string(), header: header.to_string(), column_width: None }
+    }
+
+    #[test]
+    fn fields_are_validated_and_extracted() {
+        assert!(validate(&[field("reason", "X-GitHub-Reason"), field("jira", "X-Jira-Ticket")]).is_ok());
+        assert!(validate(&[field("Reason", "X-GitHub-Reason")]).is_err());
+        assert!(validate(&[field("from", "X-Original-From")]).is_err());
+        assert!(validate(&[field("jira", "X-Jira-Ticket"), field("jira", "X-Jira-Key")]).is_err());
+        assert!(validate(&[field("jira", " ")]).is_err());
+
+        // The same fields as the search tests, which may run at the same time
+        configure(&[field("reason", "X-GitHub-Reason"), field("jira", "X-Jira-Ticket")]);
+        let headers: HashMap<String, String> = [
+            ("x-github-reason".to_string(), " review_requested ".to_string()),
+            ("Subject".to_string(), "PR".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let values = extract(&headers);
+        assert_eq!(values.len(), 1);
+        assert_eq!(values.get("reason").map(String::as_str), Some("review_requested"));
+        assert!(is_field("jira") && !is_field("subject"));
+    }
+
+    #[test]
+    fn cached_messages_are_reindexed() {
+        let dir = std::env::temp_dir().join(format!("tuimail-header-fields-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = crate::database::EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        configure(&[field("reason", "X-GitHub-Reason"), field("jira", "X-Jira-Ticket")]);
+        let emails: Vec<Email> = ["mention", "review_requested"]
+            .iter()
+            .enumerate()
+            .map(|(n, reason)| {
+                let mut email = Email::new();
+                email.id = (n + 1).to_string();
+                email.subject = reason.to_string();
+                email.headers.insert("X-GitHub-Reason".to_string(), reason.to_string());
+                email
+            })
+            .collect();
+
+        // Saved messages get their values straight away
+        db.save_emails("me@example.com", "INBOX", &emails).unwrap();
+        let query = crate::search::SearchQuery::parse("reason:review").unwrap();
+        assert_eq!(db.search_emails("me@example.com", &query).unwrap().len(), 1);
+
+        // Cached before the field was configured, so only a reindex fills it in
+        db.execute_sql("UPDATE emails SET header_fields = '{}'").unwrap();
+        db.execute_sql("DELETE FROM header_field_index").unwrap();
+        assert!(db.search_emails("me@example.com", &query).unwrap().is_empty());
+        assert_eq!(db.reindex_header_fields().unwrap(), 2);
+        assert_eq!(db.reindex_header_fields().unwrap(), 0);
+        let found = db.search_emails("me@example.com", &query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].subject, "review_requested");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
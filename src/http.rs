@@ -0,0 +1,118 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use native_tls::TlsConnector;
+
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+const HTTPS_PORT: u16 = 443;
+
+/// Split `host` or `host:port` into the host name and the port, 443 when none is given.
+/// IPv6 addresses are written in brackets, as in URLs: `[::1]:8443`.
+pub(crate) fn split_authority(authority: &str) -> Result<(&str, u16)> {
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(rest) => {
+            let (host, after) = rest.split_once(']').ok_or_else(|| anyhow!("unclosed '[' in {}", authority))?;
+            match after {
+                "" => (host, None),
+                _ => (host, Some(after.strip_prefix(':').ok_or_else(|| anyhow!("bad host {}", authority))?)),
+            }
+        }
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err(anyhow!("missing host in {}", authority));
+    }
+    let port = match port {
+        Some(port) => port.parse::<u16>().map_err(|_| anyhow!("bad port in {}", authority))?,
+        None => HTTPS_PORT,
+    };
+    Ok((host, port))
+}
+
+/// Minimal HTTP/1.0 GET over TLS; HTTP/1.0 keeps the server from using chunked encoding
+pub(crate) fn https_get(host: &str, path: &str) -> Result<(u16, Vec<u8>)> {
+    https_request("GET", host, path, &[], None, 1024 * 1024)
+}
+
+/// Minimal HTTP/1.0 request over TLS with extra headers and an optional body, reading at
+/// most `limit` bytes of the response. `host` may name a port (`host:8443`). Returns the
+/// status and the response body.
+pub(crate) fn https_request(
+    method: &str,
+    host: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+    limit: u64,
+) -> Result<(u16, Vec<u8>)> {
+    if crate::email::is_offline() {
+        return Err(anyhow!("offline: not connecting to {}", host));
+    }
+    let (name, port) = split_authority(host)?;
+    let addr = (name, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("could not resolve {}", host))?;
+    let tcp = TcpStream::connect_timeout(&addr, NETWORK_TIMEOUT)?;
+    tcp.set_read_timeout(Some(NETWORK_TIMEOUT))?;
+    tcp.set_write_timeout(Some(NETWORK_TIMEOUT))?;
+
+    let connector = TlsConnector::new()?;
+    let mut stream = connector
+        .connect(name, tcp)
+        .map_err(|e| anyhow!("TLS handshake with {} failed: {}", host, e))?;
+
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: tuimail\r\nConnection: close\r\n",
+        method, path, host
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if let Some(body) = body {
+        request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+    if let Some(body) = body {
+        stream.write_all(body)?;
+    }
+
+    let mut response = Vec::new();
+    stream.take(limit).read_to_end(&mut response)?;
+
+    let header_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| anyhow!("malformed HTTP response from {}", host))?;
+    let status_line = String::from_utf8_lossy(&response[..header_end]);
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| anyhow!("missing HTTP status from {}", host))?;
+
+    Ok((status, response[header_end + 4..].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_authority() {
+        assert_eq!(split_authority("dav.example.com").unwrap(), ("dav.example.com", 443));
+        assert_eq!(split_authority("dav.example.com:8443").unwrap(), ("dav.example.com", 8443));
+        assert_eq!(split_authority("[::1]").unwrap(), ("::1", 443));
+        assert_eq!(split_authority("[::1]:8443").unwrap(), ("::1", 8443));
+        assert!(split_authority("dav.example.com:https").is_err());
+        assert!(split_authority("dav.example.com:70000").is_err());
+        assert!(split_authority(":8443").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+use crate::email::debug_log;
+
+/// Translations shipped with the binary, as `(language, Fluent source)`. English is the
+/// reference every other catalog falls back to.
+const CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../resources/locales/en.ftl")),
+    ("de", include_str!("../resources/locales/de.ftl")),
+];
+
+struct Translations {
+    messages: Option<FluentBundle<FluentResource>>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+static TRANSLATIONS: OnceLock<Translations> = OnceLock::new();
+
+/// A bundle of one catalog. Placeables are not wrapped in Unicode isolation marks, which
+/// terminals would draw as stray characters.
+fn bundle(language: &str, source: &str) -> FluentBundle<FluentResource> {
+    let id: LanguageIdentifier = language.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![id]);
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+        debug_log(&format!("Errors in the {} translations: {:?}", language, errors));
+        resource
+    });
+    if let Err(errors) = bundle.add_resource(resource) {
+        debug_log(&format!("Errors in the {} translations: {:?}", language, errors));
+    }
+    bundle
+}
+
+/// The language part of a locale such as `de_DE.UTF-8`
+fn language_of(locale: &str) -> String {
+    locale.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase()
+}
+
+/// Choose the UI language: `configured` (the `ui.locale` setting) when set, otherwise
+/// the usual locale environment variables. Unknown languages fall back to English.
+/// Only the first call has an effect.
+pub fn init(configured: Option<&str>) {
+    let requested = configured
+        .map(str::to_string)
+        .or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|value| !value.is_empty())
+        })
+        .map(|locale| language_of(&locale))
+        .unwrap_or_default();
+    TRANSLATIONS.get_or_init(|| load(&requested));
+}
+
+fn load(requested: &str) -> Translations {
+    let (language, source) = CATALOGS[0];
+    let fallback = bundle(language, source);
+    match CATALOGS.iter().find(|(language, _)| *language == requested) {
+        Some((language, source)) => Translations { messages: Some(bundle(language, source)), fallback },
+        None => {
+            if !requested.is_empty() && requested != "c" && requested != "posix" {
+                debug_log(&format!("No translation for '{}', using English", requested));
+            }
+            Translations { messages: None, fallback }
+        }
+    }
+}
+
+fn translations() -> &'static Translations {
+    TRANSLATIONS.get_or_init(|| load("en"))
+}
+
+/// The message `key` in the UI language, or in English when it has no translation yet
+pub fn tr(key: &str) -> String {
+    tr_args(key, &[])
+}
+
+/// Like `tr`, with `{ $name }` placeables filled in from `args`. Values that are numbers
+/// are passed as numbers, so messages can choose plural forms by them.
+pub fn tr_args(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let translations = translations();
+    let found = translations
+        .messages
+        .iter()
+        .chain([&translations.fallback])
+        .find_map(|bundle| Some((bundle, bundle.get_message(key)?.value()?)));
+    let Some((bundle, pattern)) = found else {
+        debug_log(&format!("Missing UI string '{}'", key));
+        return key.to_string();
+    };
+
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        let value = value.to_string();
+        match value.parse::<i64>() {
+            Ok(number) => fluent_args.set(*name, number),
+            Err(_) => fluent_args.set(*name, FluentValue::from(value)),
+        }
+    }
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+    if !errors.is_empty() {
+        debug_log(&format!("Errors formatting UI string '{}': {:?}", key, errors));
+    }
+    text.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluent_syntax::ast;
+
+    /// Each message of a catalog with the variables its value refers to
+    fn messages(source: &str) -> Vec<(String, Vec<String>)> {
+        fn variables(elements: &[ast::PatternElement<&str>], names: &mut Vec<String>) {
+            for element in elements {
+                if let ast::PatternElement::Placeable { expression } = element {
+                    expression_variables(expression, names);
+                }
+            }
+        }
+        fn expression_variables(expression: &ast::Expression<&str>, names: &mut Vec<String>) {
+            match expression {
+                ast::Expression::Select { selector, variants } => {
+                    inline_variables(selector, names);
+                    for variant in variants {
+                        variables(&variant.value.elements, names);
+                    }
+                }
+                ast::Expression::Inline(inline) => inline_variables(inline, names),
+            }
+        }
+        fn inline_variables(inline: &ast::InlineExpression<&str>, names: &mut Vec<String>) {
+            match inline {
+                ast::InlineExpression::VariableReference { id } => names.push(id.name.to_string()),
+                ast::InlineExpression::Placeable { expression } => expression_variables(expression, names),
+                _ => {}
+            }
+        }
+
+        let resource = fluent_syntax::parser::parse(source).expect("catalog should parse");
+        resource
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => {
+                    let mut names = Vec::new();
+                    variables(&message.value.as_ref()?.elements, &mut names);
+                    names.sort();
+                    names.dedup();
+                    Some((message.id.name.to_string(), names))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_catalogs_cover_english() {
+        let english = messages(CATALOGS[0].1);
+        assert!(english.len() > 50);
+        for (language, source) in &CATALOGS[1..] {
+            let translated = messages(source);
+            for (key, names) in &english {
+                let Some((_, translation)) = translated.iter().find(|(other, _)| other == key) else {
+                    panic!("'{}' has no {} translation", key, language);
+                };
+                assert_eq!(translation, names, "placeables of '{}' in {}", key, language);
+            }
+            assert!(
+                translated.iter().all(|(key, _)| english.iter().any(|(other, _)| other == key)),
+                "stale keys in {}",
+                language
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_messages() {
+        let bundle = bundle("en", "plain = Hello\nlong = First\n    second\nargs = Hi { $name }!\ncount = { $count ->\n    [one] One message\n   *[other] { $count } messages\n}\n");
+        let format = |key: &str, args: Option<&FluentArgs>| {
+            let pattern = bundle.get_message(key).and_then(|message| message.value()).unwrap();
+            bundle.format_pattern(pattern, args, &mut Vec::new()).into_owned()
+        };
+        assert_eq!(format("plain", None), "Hello");
+        assert_eq!(format("long", None), "First\nsecond");
+        let mut args = FluentArgs::new();
+        args.set("name", "Ann");
+        args.set("count", 1);
+        assert_eq!(format("args", Some(&args)), "Hi Ann!");
+        assert_eq!(format("count", Some(&args)), "One message");
+        assert_eq!(language_of("de_DE.UTF-8"), "de");
+    }
+}
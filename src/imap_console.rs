@@ -0,0 +1,104 @@
+use std::io::{Read, Write};
+use std::sync::mpsc::Receiver;
+
+use imap::Session;
+
+use crate::email::debug_log;
+
+/// Commands that would log in again, wait for more input than one line, or hang the
+/// session; the console runs on an already authenticated connection
+const UNSUPPORTED: [&str; 5] = ["LOGIN", "AUTHENTICATE", "APPEND", "IDLE", "STARTTLS"];
+
+/// Commands that change mail or folders, refused in read-only mode
+const WRITES: [&str; 10] = ["SELECT", "STORE", "COPY", "MOVE", "EXPUNGE", "DELETE", "RENAME", "CREATE", "SUBSCRIBE", "UNSUBSCRIBE"];
+
+/// The command name, looking past a `UID` prefix, in upper case
+fn verb(command: &str) -> String {
+    let mut words = command.split_whitespace();
+    let first = words.next().unwrap_or_default().to_uppercase();
+    if first == "UID" {
+        words.next().unwrap_or_default().to_uppercase()
+    } else {
+        first
+    }
+}
+
+/// `command` as it may be written to logs: arguments of LOGIN and AUTHENTICATE hidden
+pub fn redact(command: &str) -> String {
+    let first = command.split_whitespace().next().unwrap_or_default();
+    match first.to_uppercase().as_str() {
+        "LOGIN" | "AUTHENTICATE" => format!("{} <redacted>", first),
+        _ => command.to_string(),
+    }
+}
+
+/// Why the console won't send `command`, if it won't
+pub fn refusal(command: &str, read_only: bool) -> Option<String> {
+    let verb = verb(command);
+    if verb.is_empty() {
+        return Some("Empty command".to_string());
+    }
+    if UNSUPPORTED.contains(&verb.as_str()) {
+        return Some(format!("{} is not supported in the console", verb));
+    }
+    if read_only && WRITES.contains(&verb.as_str()) {
+        let hint = if verb == "SELECT" { "; use EXAMINE" } else { "" };
+        return Some(format!("{} is disabled in read-only mode{}", verb, hint));
+    }
+    None
+}
+
+/// The lines to show for the reply to one command: the untagged responses, then how the
+/// command completed
+pub fn reply_lines(reply: imap::error::Result<Vec<u8>>) -> Vec<String> {
+    match reply {
+        Ok(data) => {
+            let mut lines: Vec<String> = String::from_utf8_lossy(&data).lines().map(str::to_string).collect();
+            lines.push("OK".to_string());
+            lines
+        }
+        Err(imap::error::Error::No(text)) => vec![format!("NO {}", text)],
+        Err(imap::error::Error::Bad(text)) => vec![format!("BAD {}", text)],
+        Err(e) => vec![format!("Error: {}", e)],
+    }
+}
+
+/// Run the commands arriving on `commands` against `session`, passing each command and
+/// its reply to `output`, until the sending side is dropped or the connection fails
+pub fn serve<T: Read + Write>(session: &mut Session<T>, commands: Receiver<String>, mut output: impl FnMut(Vec<String>)) {
+    output(vec!["Connected and logged in; type a command without its tag".to_string()]);
+    for command in commands {
+        let command = command.trim().to_string();
+        let mut lines = vec![format!("> {}", redact(&command))];
+        if let Some(reason) = refusal(&command, crate::email::is_read_only()) {
+            lines.push(reason);
+            output(lines);
+            continue;
+        }
+        debug_log(&format!("IMAP console: {}", redact(&command)));
+        let reply = session.run_command_and_read_response(&command);
+        let broken = matches!(reply, Err(imap::error::Error::Io(_)) | Err(imap::error::Error::ConnectionLost));
+        lines.extend(reply_lines(reply));
+        output(lines);
+        if broken {
+            output(vec!["Connection closed".to_string()]);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_are_checked_and_redacted() {
+        assert_eq!(redact("login alice hunter2"), "login <redacted>");
+        assert_eq!(redact("UID FETCH 1:* FLAGS"), "UID FETCH 1:* FLAGS");
+        assert_eq!(refusal("CAPABILITY", true), None);
+        assert!(refusal("idle", false).is_some());
+        assert!(refusal("uid store 4 +FLAGS (\\Seen)", true).is_some());
+        assert_eq!(refusal("UID STORE 4 +FLAGS (\\Seen)", false), None);
+        assert_eq!(reply_lines(Ok(b"* 3 EXISTS\r\n".to_vec())), vec!["* 3 EXISTS", "OK"]);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::email::EmailAddress;
+
+/// A sender whose display name belongs to a known contact writing from another address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpersonationWarning {
+    pub claimed_name: String,
+    /// Addresses the contact was written to before, sorted
+    pub known_addresses: Vec<String>,
+}
+
+/// The people the account has written to, by display name. Only addresses the user sent
+/// mail to count as known: anyone can send mail under any name, so a name seen only on
+/// incoming mail proves nothing.
+#[derive(Debug, Clone, Default)]
+pub struct KnownContacts {
+    by_name: HashMap<String, HashSet<String>>,
+    addresses: HashSet<String>,
+}
+
+/// Compare display names ignoring case, quotes and spacing
+pub fn name_key(name: &str) -> String {
+    name.split_whitespace()
+        .map(|part| part.trim_matches(['"', '\'']).to_lowercase())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl KnownContacts {
+    /// Build from `(address, name, written_to)` rows as returned by
+    /// `EmailDatabase::get_named_addresses`
+    pub fn new(rows: &[(String, Option<String>, bool)]) -> Self {
+        let addresses: HashSet<String> = rows
+            .iter()
+            .filter(|(_, _, written_to)| *written_to)
+            .map(|(address, _, _)| address.to_lowercase())
+            .collect();
+        let mut by_name: HashMap<String, HashSet<String>> = HashMap::new();
+        for (address, name, _) in rows {
+            let address = address.to_lowercase();
+            let Some(key) = name.as_deref().map(name_key).filter(|key| !key.is_empty()) else {
+                continue;
+            };
+            if addresses.contains(&address) {
+                by_name.entry(key).or_default().insert(address);
+            }
+        }
+        Self { by_name, addresses }
+    }
+
+    /// Warn when `from` uses the name of a known contact, or shows a known contact's
+    /// address as its name, while sending from an address never written to
+    pub fn check(&self, from: &EmailAddress) -> Option<ImpersonationWarning> {
+        let address = from.address.to_lowercase();
+        if self.addresses.contains(&address) {
+            return None;
+        }
+        let name = from.name.as_deref()?.trim();
+
+        let mut known: Vec<String> = match self.by_name.get(&name_key(name)) {
+            Some(addresses) => addresses.iter().cloned().collect(),
+            None => {
+                // "ceo@example.com" <someone@elsewhere.net>
+                let shown = name.trim_matches(['"', '\'', '<', '>']).to_lowercase();
+                if !self.addresses.contains(&shown) {
+                    return None;
+                }
+                vec![shown]
+            }
+        };
+        known.sort();
+        Some(ImpersonationWarning { claimed_name: name.to_string(), known_addresses: known })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from(name: &str, address: &str) -> EmailAddress {
+        EmailAddress { name: Some(name.to_string()), address: address.to_string() }
+    }
+
+    #[test]
+    fn test_name_of_known_contact_from_new_address() {
+        let known = KnownContacts::new(&[
+            ("jane@corp.example".to_string(), Some("Jane Doe".to_string()), true),
+            ("Jane@Corp.example".to_string(), Some("\"Jane  Doe\"".to_string()), false),
+            ("stranger@news.example".to_string(), Some("News".to_string()), false),
+        ]);
+
+        let warning = known.check(&from("jane doe", "jane.doe.ceo@gmail.example")).unwrap();
+        assert_eq!(warning.known_addresses, vec!["jane@corp.example".to_string()]);
+        assert!(known.check(&from("Jane Doe", "JANE@corp.example")).is_none());
+        // Names only seen on incoming mail are not trusted contacts
+        assert!(known.check(&from("News", "other@news.example")).is_none());
+        assert!(known.check(&from("jane@corp.example", "x@evil.example")).is_some());
+        assert!(known.check(&EmailAddress { name: None, address: "x@evil.example".to_string() }).is_none());
+    }
+
+    #[test]
+    fn test_contacts_come_from_the_cache() {
+        let dir = std::env::temp_dir().join(format!("tuimail-impersonation-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = crate::database::EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let mut sent = crate::email::Email::new();
+        sent.id = "1".to_string();
+        sent.from = vec![from("", "me@example.com")];
+        sent.to = vec![from("Jane Doe", "jane@corp.example")];
+        let mut urgent = crate::email::Email::new();
+        urgent.id = "1".to_string();
+        urgent.from = vec![from("Jane Doe", "jane.doe.ceo@mail.example")];
+        db.save_emails("me@example.com", "Sent", &[sent]).unwrap();
+        db.save_emails("me@example.com", "INBOX", &[urgent.clone()]).unwrap();
+
+        let known = KnownContacts::new(&db.get_named_addresses("me@example.com").unwrap());
+        let warning = known.check(&urgent.from[0]).unwrap();
+        assert_eq!(warning.known_addresses, vec!["jane@corp.example".to_string()]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
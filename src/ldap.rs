@@ -0,0 +1,515 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use native_tls::{TlsConnector, TlsStream};
+
+use crate::email::EmailError;
+
+/// Searched when the account sets no filter of its own; `{}` stands for the text typed
+pub const DEFAULT_FILTER: &str = "(&(mail=*)(|(cn={}*)(displayName={}*)(givenName={}*)(sn={}*)(mail={}*)))";
+
+/// Largest LDAP message read
+const MESSAGE_LIMIT: usize = 4 * 1024 * 1024;
+/// Entries asked for per search; enough to pick from while typing
+const SIZE_LIMIT: i64 = 20;
+/// Seconds the server may spend on a search
+const TIME_LIMIT: i64 = 5;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const START_TLS_OID: &str = "1.3.6.1.4.1.1466.20037";
+
+const BOOLEAN: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const ENUMERATED: u8 = 0x0a;
+const SEQUENCE: u8 = 0x30;
+
+// Protocol operations (RFC 4511), as application tags
+const BIND_REQUEST: u8 = 0x60;
+const BIND_RESPONSE: u8 = 0x61;
+const UNBIND_REQUEST: u8 = 0x42;
+const SEARCH_REQUEST: u8 = 0x63;
+const SEARCH_RESULT_ENTRY: u8 = 0x64;
+const SEARCH_RESULT_DONE: u8 = 0x65;
+const SEARCH_RESULT_REFERENCE: u8 = 0x73;
+const EXTENDED_REQUEST: u8 = 0x77;
+const EXTENDED_RESPONSE: u8 = 0x78;
+
+// Result codes that still come with usable entries
+const SUCCESS: i64 = 0;
+const TIME_LIMIT_EXCEEDED: i64 = 3;
+const SIZE_LIMIT_EXCEEDED: i64 = 4;
+const INVALID_CREDENTIALS: i64 = 49;
+
+fn encode_length(length: usize, out: &mut Vec<u8>) {
+    if length < 0x80 {
+        out.push(length as u8);
+    } else {
+        let bytes = length.to_be_bytes();
+        let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+}
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    encode_length(content.len(), &mut out);
+    out.extend_from_slice(content);
+    out
+}
+
+fn constructed(tag: u8, parts: &[Vec<u8>]) -> Vec<u8> {
+    tlv(tag, &parts.concat())
+}
+
+fn integer(tag: u8, value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // Shortest two's complement form
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0) || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    tlv(tag, &bytes[start..])
+}
+
+/// Split the first element off `data`: its tag, its content and what follows
+fn element(data: &[u8]) -> Result<(u8, &[u8], &[u8]), EmailError> {
+    let malformed = || EmailError::ConnectionError("Malformed LDAP message".to_string());
+    let (&tag, rest) = data.split_first().ok_or_else(malformed)?;
+    let (&first, rest) = rest.split_first().ok_or_else(malformed)?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return Err(malformed());
+        }
+        (rest[..count].iter().fold(0, |length, byte| length << 8 | *byte as usize), &rest[count..])
+    };
+    if rest.len() < length {
+        return Err(malformed());
+    }
+    Ok((tag, &rest[..length], &rest[length..]))
+}
+
+/// The elements inside a constructed element's content
+fn elements(mut data: &[u8]) -> Result<Vec<(u8, &[u8])>, EmailError> {
+    let mut all = Vec::new();
+    while !data.is_empty() {
+        let (tag, content, rest) = element(data)?;
+        all.push((tag, content));
+        data = rest;
+    }
+    Ok(all)
+}
+
+fn to_integer(content: &[u8]) -> i64 {
+    let negative = content.first().is_some_and(|byte| byte & 0x80 != 0);
+    content.iter().fold(if negative { -1 } else { 0 }, |value, byte| value << 8 | *byte as i64)
+}
+
+/// Escape text for use as a value in a search filter
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '*' | '(' | ')' | '\\' | '\0' => escaped.push_str(&format!("\\{:02x}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> Result<Vec<u8>, String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            let byte = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("invalid escape at '{}'", &value[i..]))?;
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// Encode a search filter written as in RFC 4515, such as `(&(mail=*)(cn=ann*))`
+pub fn filter(text: &str) -> Result<Vec<u8>, String> {
+    let (encoded, rest) = filter_at(text.trim())?;
+    if !rest.trim().is_empty() {
+        return Err(format!("unexpected '{}' after the filter", rest));
+    }
+    Ok(encoded)
+}
+
+fn filter_at(text: &str) -> Result<(Vec<u8>, &str), String> {
+    let inner = text.strip_prefix('(').ok_or_else(|| format!("expected '(' at '{}'", text))?;
+    match inner.chars().next() {
+        Some(operator @ ('&' | '|')) => {
+            let mut rest = &inner[1..];
+            let mut parts = Vec::new();
+            while !rest.starts_with(')') {
+                if rest.is_empty() {
+                    return Err("missing ')'".to_string());
+                }
+                let (part, after) = filter_at(rest)?;
+                parts.push(part);
+                rest = after;
+            }
+            Ok((constructed(if operator == '&' { 0xa0 } else { 0xa1 }, &parts), &rest[1..]))
+        }
+        Some('!') => {
+            let (part, rest) = filter_at(&inner[1..])?;
+            let rest = rest.strip_prefix(')').ok_or("missing ')'")?;
+            Ok((tlv(0xa2, &part), rest))
+        }
+        _ => {
+            let end = inner.find(')').ok_or("missing ')'")?;
+            Ok((item(&inner[..end])?, &inner[end + 1..]))
+        }
+    }
+}
+
+/// One comparison, such as `cn=ann*`, `mail=*` or `age>=21`
+fn item(text: &str) -> Result<Vec<u8>, String> {
+    let (left, value) = text.split_once('=').ok_or_else(|| format!("expected '=' in '{}'", text))?;
+    let (attribute, operator) = match left.strip_suffix(['~', '>', '<']) {
+        Some(attribute) => (attribute, left.chars().last()),
+        None => (left, None),
+    };
+    if attribute.is_empty() {
+        return Err(format!("missing attribute in '{}'", text));
+    }
+    let name = tlv(OCTET_STRING, attribute.as_bytes());
+    let assertion = |tag: u8| Ok(constructed(tag, &[name.clone(), tlv(OCTET_STRING, &unescape(value)?)]));
+    match operator {
+        Some('>') => assertion(0xa5),
+        Some('<') => assertion(0xa6),
+        Some(_) => assertion(0xa8),
+        None if value == "*" => Ok(tlv(0x87, attribute.as_bytes())),
+        None if value.contains('*') => {
+            let pieces: Vec<&str> = value.split('*').collect();
+            let last = pieces.len() - 1;
+            let mut substrings = Vec::new();
+            for (i, piece) in pieces.iter().enumerate().filter(|(_, piece)| !piece.is_empty()) {
+                let tag = match i {
+                    0 => 0x80,
+                    i if i == last => 0x82,
+                    _ => 0x81,
+                };
+                substrings.push(tlv(tag, &unescape(piece)?));
+            }
+            Ok(constructed(0xa4, &[name, constructed(SEQUENCE, &substrings)]))
+        }
+        None => assertion(0xa3),
+    }
+}
+
+/// An entry found by a search, with its attributes as the server sent them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub dn: String,
+    pub attributes: Vec<(String, Vec<String>)>,
+}
+
+impl Entry {
+    /// Values of an attribute, its name matched ignoring case
+    pub fn values(&self, name: &str) -> &[String] {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute.eq_ignore_ascii_case(name))
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+
+    pub fn first(&self, name: &str) -> Option<&str> {
+        self.values(name).first().map(String::as_str)
+    }
+}
+
+/// An LDAP session (RFC 4511) over a connected stream
+pub struct Ldap<S> {
+    stream: S,
+    next_id: i64,
+}
+
+/// Connect to `ldaps://host[:port]`, or to `ldap://host[:port]` and switch to TLS with
+/// StartTLS before anything else is sent, so the directory is never read in the clear
+pub fn connect(url: &str) -> Result<Ldap<TlsStream<TcpStream>>, EmailError> {
+    let (implicit_tls, rest) = if let Some(rest) = url.strip_prefix("ldaps://") {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("ldap://") {
+        (false, rest)
+    } else {
+        return Err(EmailError::ConnectionError(format!("LDAP URL must start with ldaps:// or ldap://: {}", url)));
+    };
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port.parse().map_err(|_| EmailError::ConnectionError(format!("Invalid LDAP port in {}", url)))?;
+            (host, port)
+        }
+        None => (authority, if implicit_tls { 636 } else { 389 }),
+    };
+
+    let tcp = TcpStream::connect((host, port))
+        .map_err(|e| EmailError::ConnectionError(format!("Could not reach the directory at {}:{}: {}", host, port, e)))?;
+    tcp.set_read_timeout(Some(TIMEOUT))?;
+    tcp.set_write_timeout(Some(TIMEOUT))?;
+    let mut next_id = 1;
+    let tcp = if implicit_tls {
+        tcp
+    } else {
+        let mut plain = Ldap::new(tcp);
+        plain.start_tls()?;
+        next_id = plain.next_id;
+        plain.stream
+    };
+    let stream = TlsConnector::new()?
+        .connect(host, tcp)
+        .map_err(|e| EmailError::ConnectionError(format!("TLS with the directory failed: {}", e)))?;
+    Ok(Ldap { stream, next_id })
+}
+
+impl<S: Read + Write> Ldap<S> {
+    pub fn new(stream: S) -> Self {
+        Self { stream, next_id: 1 }
+    }
+
+    fn send(&mut self, operation: Vec<u8>) -> Result<(), EmailError> {
+        let message = constructed(SEQUENCE, &[integer(INTEGER, self.next_id), operation]);
+        self.next_id += 1;
+        self.stream.write_all(&message)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// The next message's protocol operation: its tag and content
+    fn receive(&mut self) -> Result<(u8, Vec<u8>), EmailError> {
+        let mut head = [0u8; 2];
+        self.stream.read_exact(&mut head)?;
+        let mut message = head.to_vec();
+        let length = if head[1] < 0x80 {
+            head[1] as usize
+        } else {
+            let mut bytes = vec![0u8; (head[1] & 0x7f) as usize];
+            if bytes.is_empty() || bytes.len() > 4 {
+                return Err(EmailError::ConnectionError("Malformed LDAP message".to_string()));
+            }
+            self.stream.read_exact(&mut bytes)?;
+            message.extend_from_slice(&bytes);
+            bytes.iter().fold(0, |length, byte| length << 8 | *byte as usize)
+        };
+        if length > MESSAGE_LIMIT {
+            return Err(EmailError::ConnectionError(format!("LDAP message of {} bytes is too large", length)));
+        }
+        let start = message.len();
+        message.resize(start + length, 0);
+        self.stream.read_exact(&mut message[start..])?;
+
+        let (_, content, _) = element(&message)?;
+        let (_, _, operation) = element(content)?;
+        let (tag, content, _) = element(operation)?;
+        Ok((tag, content.to_vec()))
+    }
+
+    /// Result code and diagnostic message of a response
+    fn result(content: &[u8]) -> Result<(i64, String), EmailError> {
+        match &elements(content)?[..] {
+            [(ENUMERATED, code), _, (OCTET_STRING, message), ..] => Ok((to_integer(code), String::from_utf8_lossy(message).into_owned())),
+            _ => Err(EmailError::ConnectionError("Malformed LDAP result".to_string())),
+        }
+    }
+
+    fn expect(&mut self, tag: u8, what: &str) -> Result<(), EmailError> {
+        let (received, content) = self.receive()?;
+        let (code, message) = Self::result(&content)?;
+        match (received == tag, code) {
+            (true, SUCCESS) => Ok(()),
+            (_, INVALID_CREDENTIALS) => Err(EmailError::AuthError("The directory refused the bind DN or password".to_string())),
+            _ => Err(EmailError::ConnectionError(format!("LDAP {} failed with code {}: {}", what, code, message))),
+        }
+    }
+
+    fn start_tls(&mut self) -> Result<(), EmailError> {
+        self.send(tlv(EXTENDED_REQUEST, &tlv(0x80, START_TLS_OID.as_bytes())))?;
+        self.expect(EXTENDED_RESPONSE, "StartTLS")
+    }
+
+    /// Authenticate with a DN (or, for Active Directory, a user principal name) and password
+    pub fn bind(&mut self, dn: &str, password: &str) -> Result<(), EmailError> {
+        // Servers take an empty password as an anonymous bind and report success
+        if password.is_empty() {
+            return Err(EmailError::AuthError("No password to bind to the directory with".to_string()));
+        }
+        self.send(constructed(
+            BIND_REQUEST,
+            &[integer(INTEGER, 3), tlv(OCTET_STRING, dn.as_bytes()), tlv(0x80, password.as_bytes())],
+        ))?;
+        self.expect(BIND_RESPONSE, "bind")
+    }
+
+    /// Entries below `base` matching an encoded `filter`, with the listed attributes.
+    /// The search is cut short after a few entries, which are returned.
+    pub fn search(&mut self, base: &str, filter: &[u8], attributes: &[&str]) -> Result<Vec<Entry>, EmailError> {
+        let attributes: Vec<Vec<u8>> = attributes.iter().map(|name| tlv(OCTET_STRING, name.as_bytes())).collect();
+        self.send(constructed(
+            SEARCH_REQUEST,
+            &[
+                tlv(OCTET_STRING, base.as_bytes()),
+                // Whole subtree, never dereferencing aliases
+                integer(ENUMERATED, 2),
+                integer(ENUMERATED, 0),
+                integer(INTEGER, SIZE_LIMIT),
+                integer(INTEGER, TIME_LIMIT),
+                tlv(BOOLEAN, &[0]),
+                filter.to_vec(),
+                constructed(SEQUENCE, &attributes),
+            ],
+        ))?;
+
+        let mut entries = Vec::new();
+        loop {
+            let (tag, content) = self.receive()?;
+            match tag {
+                SEARCH_RESULT_ENTRY => entries.push(Self::entry(&content)?),
+                SEARCH_RESULT_REFERENCE => {}
+                SEARCH_RESULT_DONE => {
+                    let (code, message) = Self::result(&content)?;
+                    return match code {
+                        SUCCESS | TIME_LIMIT_EXCEEDED | SIZE_LIMIT_EXCEEDED => Ok(entries),
+                        _ => Err(EmailError::ConnectionError(format!("LDAP search failed with code {}: {}", code, message))),
+                    };
+                }
+                _ => {
+                    let message = Self::result(&content).map(|(_, message)| message).unwrap_or_default();
+                    return Err(EmailError::ConnectionError(format!("Unexpected LDAP response: {}", message)));
+                }
+            }
+        }
+    }
+
+    fn entry(content: &[u8]) -> Result<Entry, EmailError> {
+        let malformed = || EmailError::ConnectionError("Malformed LDAP entry".to_string());
+        let [(OCTET_STRING, dn), (SEQUENCE, list)] = elements(content)?[..] else {
+            return Err(malformed());
+        };
+        let mut attributes = Vec::new();
+        for (_, attribute) in elements(list)? {
+            let [(OCTET_STRING, name), (_, values)] = elements(attribute)?[..] else {
+                return Err(malformed());
+            };
+            let values = elements(values)?.into_iter().map(|(_, value)| String::from_utf8_lossy(value).into_owned()).collect();
+            attributes.push((String::from_utf8_lossy(name).into_owned(), values));
+        }
+        Ok(Entry { dn: String::from_utf8_lossy(dn).into_owned(), attributes })
+    }
+
+    /// End the session; the server closes the connection without answering
+    pub fn unbind(mut self) {
+        let _ = self.send(tlv(UNBIND_REQUEST, &[]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers from a canned byte stream and records what is written
+    struct Canned {
+        input: std::io::Cursor<Vec<u8>>,
+        written: Vec<u8>,
+    }
+
+    impl Read for Canned {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Canned {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn message(id: i64, operation: Vec<u8>) -> Vec<u8> {
+        constructed(SEQUENCE, &[integer(INTEGER, id), operation])
+    }
+
+    fn result(tag: u8, code: i64) -> Vec<u8> {
+        constructed(tag, &[integer(ENUMERATED, code), tlv(OCTET_STRING, b""), tlv(OCTET_STRING, b"")])
+    }
+
+    fn entry(dn: &str, attributes: &[(&str, &[&str])]) -> Vec<u8> {
+        let attributes: Vec<Vec<u8>> = attributes
+            .iter()
+            .map(|(name, values)| {
+                let values: Vec<Vec<u8>> = values.iter().map(|value| tlv(OCTET_STRING, value.as_bytes())).collect();
+                constructed(SEQUENCE, &[tlv(OCTET_STRING, name.as_bytes()), constructed(0x31, &values)])
+            })
+            .collect();
+        constructed(SEARCH_RESULT_ENTRY, &[tlv(OCTET_STRING, dn.as_bytes()), constructed(SEQUENCE, &attributes)])
+    }
+
+    #[test]
+    fn filters_are_encoded() {
+        // The example of RFC 4511's appendix
+        assert_eq!(
+            filter("(cn=Babs Jensen)").unwrap(),
+            [&[0xa3, 0x11, 0x04, 0x02][..], b"cn", &[0x04, 0x0b], b"Babs Jensen"].concat()
+        );
+        assert_eq!(filter("(mail=*)").unwrap(), [&[0x87, 0x04][..], b"mail"].concat());
+        assert_eq!(
+            filter("(!(cn=a*b\\2a))").unwrap(),
+            [&[0xa2, 0x0f, 0xa4, 0x0d, 0x04, 0x02][..], b"cn", &[0x30, 0x07, 0x80, 0x01, b'a', 0x82, 0x02, b'b', b'*']].concat()
+        );
+        let default = DEFAULT_FILTER.replace("{}", &escape("o*(x)"));
+        assert!(default.contains("(cn=o\\2a\\28x\\29*)"));
+        assert!(filter(&default).is_ok());
+        assert!(filter("(&(cn=a)").is_err());
+        assert!(filter("cn=a").is_err());
+    }
+
+    #[test]
+    fn searches_are_answered() {
+        let responses = [
+            message(1, result(BIND_RESPONSE, 0)),
+            message(2, entry("uid=ann,ou=people,dc=example,dc=com", &[("displayName", &["Ann Smith"]), ("mail", &["ann@example.com"])])),
+            message(2, tlv(SEARCH_RESULT_REFERENCE, &tlv(OCTET_STRING, b"ldap://other.example.com/"))),
+            message(2, result(SEARCH_RESULT_DONE, SIZE_LIMIT_EXCEEDED)),
+            message(3, result(BIND_RESPONSE, INVALID_CREDENTIALS)),
+        ]
+        .concat();
+        let mut ldap = Ldap::new(Canned { input: std::io::Cursor::new(responses), written: Vec::new() });
+        ldap.bind("uid=me,dc=example,dc=com", "secret").unwrap();
+        let entries = ldap.search("dc=example,dc=com", &filter("(mail=ann*)").unwrap(), &["displayName", "mail"]).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].first("DISPLAYNAME"), Some("Ann Smith"));
+        assert_eq!(entries[0].values("mail"), ["ann@example.com".to_string()]);
+        assert!(matches!(ldap.bind("uid=me,dc=example,dc=com", "wrong"), Err(EmailError::AuthError(_))));
+        assert!(matches!(ldap.bind("uid=me,dc=example,dc=com", ""), Err(EmailError::AuthError(_))));
+
+        let (_, bind, rest) = element(&ldap.stream.written).unwrap();
+        assert_eq!(elements(bind).unwrap()[0], (INTEGER, &[1][..]));
+        let (_, search, _) = element(rest).unwrap();
+        let (tag, request, _) = element(&search[3..]).unwrap();
+        assert_eq!(tag, SEARCH_REQUEST);
+        assert_eq!(elements(request).unwrap()[3], (INTEGER, &[20][..]));
+    }
+}
@@ -1,15 +1,150 @@
-pub mod app;
+//! tuimail's mail engine and terminal client.
+//!
+//! With default features the crate builds the `tuimail` binary and exposes its UI
+//! modules. Disable default features to embed only the engine, which has no
+//! ratatui, crossterm or nlprule dependency:
+//!
+//! ```toml
+//! tuimail = { version = "0.1", default-features = false }
+//! ```
+//!
+//! The engine is made of:
+//!
+//! - [`config`]: accounts and settings, loaded from the same JSON file the client uses
+//! - [`credentials`]: passwords in the system keyring, with a file fallback
+//! - [`email`]: the message model and [`EmailClient`], which syncs, sends and changes flags
+//! - [`database`]: the SQLite cache that sync writes into
+//! - [`transport`]: the [`ImapBackend`]/[`SmtpBackend`] traits and the sync and send
+//!   logic written against them; `mock_transport`, with the `test-support` feature, is an
+//!   in-memory server for tests
+//! - [`mime`]: message parsing helpers
+//! - [`providers`]: server settings of common mail providers
+//! - [`bundle`]: encrypted export and import of messages
+//! - [`cache`]: keeping the locally held attachments under a size limit
+//! - [`ews`]: Exchange mailboxes through Exchange Web Services, as an [`ImapBackend`]
+//! - [`gmail`]: Gmail accounts through the Gmail REST API, as an [`ImapBackend`]
+//! - [`maildir`]: local Maildir trees, as an [`ImapBackend`] and for importing
+//! - [`mbox`]: export of cached folders to mbox files and import from them
+//! - [`notmuch`]: tags shared with notmuch (with the `notmuch` feature)
+//! - [`auth_results`]: DKIM, SPF and DMARC results the receiving server recorded
+//! - [`filter_import`]: rules from Thunderbird message filters and Sieve scripts
+//! - [`digest`]: splitting mailing list digests into the messages they collect
+//! - [`forge`]: recognising GitHub and GitLab notifications
+//! - [`snippet`]: the preview line shown under each message in two-line list rows
+//! - [`threads`]: grouping a folder's messages into conversations
+//! - [`ldap`]: searching an LDAP directory for recipients
+//! - [`mailto`]: `mailto:` links, for starting a message from one
+//! - [`pgp`]: OpenPGP signing, encryption and checking through gpg
+//! - [`vcard`]: reading and writing vCard contact cards for the address book
+//! - [`calendar`]: reading iCalendar invitations; [`caldav`] checks and adds them to a calendar
+//! - [`cli`]: exit codes and JSON output shared by the command-line subcommands
+//!
+//! ```no_run
+//! use tuimail::{Config, EmailClient, SecureCredentials};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let config = Config::load("config.json")?;
+//! let credentials = SecureCredentials::new()?;
+//! let client = EmailClient::new(config.accounts[0].clone(), credentials);
+//! for email in client.fetch_emails("INBOX", 20)? {
+//!     println!("{}: {}", email.date, email.subject);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+// Mail engine
+pub mod attachment_index;
+pub mod attachment_text;
+pub mod audit;
+pub mod auth_results;
+pub mod auth;
+pub mod autoconfig;
+pub mod avatar;
+pub mod bundle;
+pub mod cache;
+pub mod caldav;
+pub mod calendar;
+pub mod capabilities;
+pub mod cleanup;
+pub mod cli;
 pub mod config;
+pub mod config_crypto;
 pub mod credentials;
 pub mod database;
+pub mod digest;
+pub mod downloads;
+pub mod drafts;
+pub mod dsn;
 pub mod email;
-pub mod ui;
-pub mod spellcheck;
-pub mod grammarcheck;
+pub mod ews;
+pub mod filter_import;
+pub mod forge;
+pub mod gmail;
+pub mod header_fields;
+pub mod http;
+pub mod imap_console;
+pub mod impersonation;
+pub mod maildir;
+pub mod ldap;
+pub mod mailto;
+pub mod markdown;
+pub mod mbox;
+pub mod mime;
+pub mod mute;
+#[cfg(any(test, feature = "test-support"))]
+pub mod mock_transport;
+pub mod notmuch;
+pub mod pgp;
+pub mod profile;
+pub mod providers;
+pub mod recipients;
+pub mod reminders;
+pub mod reply_check;
+pub mod rules;
+pub mod search;
+pub mod snippet;
+pub mod tags;
+pub mod threads;
+pub mod transport;
+pub mod updates;
+pub mod utf7;
+pub mod vcard;
+pub mod vip;
+
+// Terminal client
+#[cfg(feature = "tui")]
+pub mod app;
+#[cfg(feature = "tui")]
+pub mod ascii_mode;
+#[cfg(feature = "tui")]
 pub mod async_grammar;
+#[cfg(feature = "tui")]
+pub mod body_view;
+#[cfg(feature = "tui")]
+pub mod clipboard;
+#[cfg(feature = "tui")]
+pub mod compose_stats;
+#[cfg(feature = "tui")]
+pub mod events;
+#[cfg(feature = "tui")]
+pub mod grammarcheck;
+#[cfg(feature = "tui")]
+pub mod i18n;
+#[cfg(feature = "tui")]
+pub mod onboarding;
+#[cfg(feature = "tui")]
+pub mod quoting;
+#[cfg(feature = "tui")]
+pub mod spellcheck;
+#[cfg(feature = "tui")]
+pub mod ui;
 
 // Re-export commonly used types
+#[cfg(feature = "tui")]
 pub use app::App;
-pub use config::{Config, EmailAccount};
+pub use config::{Config, EmailAccount, ImapSecurity, SmtpSecurity};
+pub use credentials::SecureCredentials;
 pub use database::EmailDatabase;
-pub use email::{Email, EmailClient, EmailError};
+pub use email::{Email, EmailAddress, EmailAttachment, EmailClient, EmailError};
+pub use transport::{ImapBackend, SmtpBackend};
@@ -0,0 +1,495 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::email::{ensure_writable, EmailError};
+use crate::transport::{FetchedMessage, ImapBackend};
+
+/// Per-folder file mapping message file names to the UIDs they were given, so UIDs stay
+/// the same as files move between `new` and `cur` and get renamed for flag changes
+const UID_LIST: &str = ".tuimail-uids";
+
+/// Messages are fetched from the source of an import in batches of this many
+const IMPORT_BATCH: usize = 100;
+
+/// Maildir info letters and the IMAP flags they stand for, in the (ASCII) order the
+/// letters must appear in a file name
+const FLAG_LETTERS: &[(char, &str)] = &[
+    ('D', "\\Draft"),
+    ('F', "\\Flagged"),
+    ('P', "$Forwarded"),
+    ('R', "\\Answered"),
+    ('S', "\\Seen"),
+    ('T', "\\Deleted"),
+];
+
+static DELIVERIES: AtomicU32 = AtomicU32::new(0);
+
+/// A tree of local Maildir folders, such as one kept by offlineimap or mbsync, used in
+/// place of an IMAP server. Both layouts are understood: Maildir++ (the root is INBOX and
+/// folders are `.Name` directories in it) and plain nested directories, one per folder.
+pub struct MaildirStore {
+    root: PathBuf,
+}
+
+fn io_error(context: &str, e: std::io::Error) -> EmailError {
+    EmailError::ImapError(format!("{}: {}", context, e))
+}
+
+fn is_maildir(path: &Path) -> bool {
+    path.join("cur").is_dir() && path.join("new").is_dir()
+}
+
+/// File name up to the info part (`:2,FLAGS`, or `!2,FLAGS` where `:` isn't allowed)
+fn unique_name(file_name: &str) -> &str {
+    file_name.split([':', '!']).next().unwrap_or(file_name)
+}
+
+/// IMAP flags of a message file, from the letters after `:2,`
+pub fn flags_of(file_name: &str) -> Vec<String> {
+    let Some(info) = file_name.split_once(":2,").or_else(|| file_name.split_once("!2,")).map(|(_, info)| info) else {
+        return Vec::new();
+    };
+    FLAG_LETTERS
+        .iter()
+        .filter(|(letter, _)| info.contains(*letter))
+        .map(|(_, flag)| flag.to_string())
+        .collect()
+}
+
+/// The `:2,` info part for `flags`; `None` if one of them has no Maildir letter
+fn info_for(flags: &[String]) -> Option<String> {
+    let mut letters = String::new();
+    for flag in flags {
+        if flag.eq_ignore_ascii_case("\\Recent") {
+            continue;
+        }
+        let (letter, _) = FLAG_LETTERS.iter().find(|(_, name)| name.eq_ignore_ascii_case(flag))?;
+        letters.push(*letter);
+    }
+    let mut letters: Vec<char> = letters.chars().collect();
+    letters.sort_unstable();
+    letters.dedup();
+    Some(format!(":2,{}", letters.into_iter().collect::<String>()))
+}
+
+impl MaildirStore {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, EmailError> {
+        let root = root.into();
+        if !root.is_dir() {
+            return Err(EmailError::NotFound(format!("Maildir {}", root.display())));
+        }
+        Ok(Self { root })
+    }
+
+    /// Maildir++: the root itself is the INBOX
+    fn is_plus_plus(&self) -> bool {
+        is_maildir(&self.root)
+    }
+
+    /// Every folder of the tree, INBOX first
+    pub fn list_folders(&self) -> Result<Vec<String>, EmailError> {
+        let mut folders = Vec::new();
+        if self.is_plus_plus() {
+            folders.push("INBOX".to_string());
+            let entries = fs::read_dir(&self.root).map_err(|e| io_error("Failed to list the Maildir", e))?;
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if let Some(folder) = name.strip_prefix('.').filter(|folder| !folder.is_empty() && *folder != ".") {
+                    if is_maildir(&entry.path()) {
+                        folders.push(folder.replace('.', "/"));
+                    }
+                }
+            }
+        } else {
+            self.collect_nested(&self.root, &mut folders, 0);
+        }
+        folders.sort_by_key(|folder| (folder != "INBOX", folder.to_lowercase()));
+        Ok(folders)
+    }
+
+    fn collect_nested(&self, dir: &Path, folders: &mut Vec<String>, depth: usize) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !path.is_dir() || name.starts_with('.') || ["cur", "new", "tmp"].contains(&name.as_str()) {
+                continue;
+            }
+            if is_maildir(&path) {
+                let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+                let folder = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+                folders.push(if folder.eq_ignore_ascii_case("INBOX") { "INBOX".to_string() } else { folder });
+            }
+            if depth < 8 {
+                self.collect_nested(&path, folders, depth + 1);
+            }
+        }
+    }
+
+    /// Directory a folder has or would have
+    fn folder_dir(&self, folder: &str) -> Result<PathBuf, EmailError> {
+        if folder.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+            return Err(EmailError::ImapError(format!("Invalid folder name: {}", folder)));
+        }
+        Ok(if !self.is_plus_plus() {
+            self.root.join(folder)
+        } else if folder.eq_ignore_ascii_case("INBOX") {
+            self.root.clone()
+        } else {
+            self.root.join(format!(".{}", folder.replace('/', ".")))
+        })
+    }
+
+    /// Directory of a folder, which has to exist
+    fn folder_path(&self, folder: &str) -> Result<PathBuf, EmailError> {
+        let path = self.folder_dir(folder)?;
+        if !is_maildir(&path) {
+            return Err(EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)));
+        }
+        Ok(path)
+    }
+
+    /// The folder's message files by UID. Files not seen before get the next UIDs, in
+    /// delivery order, and the UID list is brought up to date.
+    fn scan(&self, folder: &str) -> Result<BTreeMap<u32, PathBuf>, EmailError> {
+        let dir = self.folder_path(folder)?;
+        let list_path = dir.join(UID_LIST);
+
+        let mut next_uid = 1;
+        let mut known: HashMap<String, u32> = HashMap::new();
+        if let Ok(content) = fs::read_to_string(&list_path) {
+            let mut lines = content.lines();
+            if let Some(next) = lines.next().and_then(|line| line.strip_prefix("next ")).and_then(|n| n.parse().ok()) {
+                next_uid = next;
+            }
+            for line in lines {
+                if let Some((uid, name)) = line.split_once(' ').and_then(|(uid, name)| Some((uid.parse::<u32>().ok()?, name))) {
+                    known.insert(name.to_string(), uid);
+                }
+            }
+        }
+
+        let mut files = BTreeMap::new();
+        let mut unknown = Vec::new();
+        for sub in ["new", "cur"] {
+            let entries = fs::read_dir(dir.join(sub)).map_err(|e| io_error(&format!("Failed to list {}", folder), e))?;
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                if file_name.starts_with('.') {
+                    continue;
+                }
+                let name = unique_name(&file_name).to_string();
+                match known.get(&name) {
+                    Some(uid) => {
+                        files.insert(*uid, entry.path());
+                    }
+                    None => {
+                        let modified = entry.metadata().and_then(|m| m.modified()).ok();
+                        unknown.push((modified, name, entry.path()));
+                    }
+                }
+            }
+        }
+
+        let vanished = known.len() > files.len();
+        if unknown.is_empty() && !vanished {
+            return Ok(files);
+        }
+        unknown.sort();
+        for (_, _, path) in unknown {
+            files.insert(next_uid, path);
+            next_uid += 1;
+        }
+
+        let mut list = format!("next {}\n", next_uid);
+        for (uid, path) in &files {
+            let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            list.push_str(&format!("{} {}\n", uid, unique_name(&file_name)));
+        }
+        let temp = dir.join(format!("{}.tmp", UID_LIST));
+        fs::write(&temp, list)
+            .and_then(|_| fs::rename(&temp, &list_path))
+            .map_err(|e| io_error(&format!("Failed to save the UIDs of {}", folder), e))?;
+        Ok(files)
+    }
+
+    fn message_path(&self, folder: &str, uid: u32) -> Result<PathBuf, EmailError> {
+        self.scan(folder)?
+            .remove(&uid)
+            .ok_or_else(|| EmailError::NotFound(format!("UID {} in {}", uid, folder)))
+    }
+
+    fn read(&self, folder: &str, uids: &[u32], headers_only: bool) -> Result<Vec<FetchedMessage>, EmailError> {
+        let files = self.scan(folder)?;
+        let mut messages = Vec::new();
+        for uid in uids {
+            let Some(path) = files.get(uid) else {
+                continue;
+            };
+            let mut raw = fs::read(path).map_err(|e| io_error(&format!("Failed to read UID {} in {}", uid, folder), e))?;
+            if headers_only {
+                if let Some(end) = raw.windows(2).position(|w| w == b"\n\n").or_else(|| raw.windows(4).position(|w| w == b"\r\n\r\n")) {
+                    raw.truncate(end + 2);
+                }
+            }
+            let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            messages.push(FetchedMessage { uid: *uid, flags: flags_of(&file_name), raw });
+        }
+        Ok(messages)
+    }
+}
+
+/// A file name no other delivery uses, as the Maildir spec asks: time, process and a
+/// per-process counter, and the host
+fn delivery_name() -> String {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string()).replace(['/', ':'], "_");
+    format!(
+        "{}.M{}P{}Q{}.{}",
+        now.as_secs(),
+        now.subsec_micros(),
+        std::process::id(),
+        DELIVERIES.fetch_add(1, Ordering::Relaxed),
+        host
+    )
+}
+
+impl ImapBackend for MaildirStore {
+    fn uids(&mut self, folder: &str) -> Result<Vec<u32>, EmailError> {
+        Ok(self.scan(folder)?.into_keys().collect())
+    }
+
+    fn fetch(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        self.read(folder, uids, false)
+    }
+
+    fn fetch_headers(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        self.read(folder, uids, true)
+    }
+
+    fn flags(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, Vec<String>>, EmailError> {
+        let files = self.scan(folder)?;
+        Ok(uids
+            .iter()
+            .filter_map(|uid| {
+                let file_name = files.get(uid)?.file_name()?.to_string_lossy().into_owned();
+                Some((*uid, flags_of(&file_name)))
+            })
+            .collect())
+    }
+
+    fn store_flag(&mut self, folder: &str, uid: u32, flag: &str, set: bool) -> Result<(), EmailError> {
+        let path = self.message_path(folder, uid)?;
+        let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut flags = flags_of(&file_name);
+        flags.retain(|f| !f.eq_ignore_ascii_case(flag));
+        if set {
+            flags.push(flag.to_string());
+        }
+        let info = info_for(&flags).ok_or_else(|| EmailError::ImapError(format!("Maildir folders can't store the keyword {}", flag)))?;
+
+        // A message whose flags were changed has been seen by a client, so it lives in cur
+        let cur = path.parent().and_then(Path::parent).map(|dir| dir.join("cur")).unwrap_or_default();
+        let target = cur.join(format!("{}{}", unique_name(&file_name), info));
+        fs::rename(&path, &target).map_err(|e| io_error(&format!("Failed to flag UID {} in {}", uid, folder), e))
+    }
+
+    fn delete(&mut self, folder: &str, uid: u32) -> Result<(), EmailError> {
+        let path = self.message_path(folder, uid)?;
+        fs::remove_file(&path).map_err(|e| io_error(&format!("Failed to delete UID {} in {}", uid, folder), e))
+    }
+
+    fn move_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        let target_dir = self.folder_path(target)?;
+        let path = self.message_path(folder, uid)?;
+        let sub = path.parent().and_then(Path::file_name).map(|n| n.to_os_string()).unwrap_or_else(|| "cur".into());
+        let file_name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        fs::rename(&path, target_dir.join(sub).join(file_name))
+            .map_err(|e| io_error(&format!("Failed to move UID {} to {}", uid, target), e))
+    }
+
+    fn unseen_count(&mut self, folder: &str) -> Result<u32, EmailError> {
+        let files = self.scan(folder)?;
+        let unseen = files
+            .values()
+            .filter_map(|path| path.file_name())
+            .filter(|name| !flags_of(&name.to_string_lossy()).iter().any(|f| f == "\\Seen"))
+            .count();
+        Ok(unseen as u32)
+    }
+
+    fn create_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        let dir = self.folder_dir(folder)?;
+        if is_maildir(&dir) {
+            return Err(EmailError::ImapError(format!("Mailbox already exists: {}", folder)));
+        }
+        for sub in ["cur", "new", "tmp"] {
+            fs::create_dir_all(dir.join(sub)).map_err(|e| io_error(&format!("Failed to create {}", folder), e))?;
+        }
+        Ok(())
+    }
+
+    fn rename_folder(&mut self, folder: &str, new_name: &str) -> Result<(), EmailError> {
+        let from = self.folder_path(folder)?;
+        let to = self.folder_dir(new_name)?;
+        if to.exists() {
+            return Err(EmailError::ImapError(format!("Mailbox already exists: {}", new_name)));
+        }
+        let renamed = |from: &Path, to: &Path| {
+            fs::create_dir_all(to.parent().unwrap_or(&self.root))?;
+            fs::rename(from, to)
+        };
+        renamed(&from, &to).map_err(|e| io_error(&format!("Failed to rename {} to {}", folder, new_name), e))?;
+        if self.is_plus_plus() {
+            // Folders below it are its siblings, named after it
+            let prefix = format!(".{}.", folder.replace('/', "."));
+            let entries = fs::read_dir(&self.root).map_err(|e| io_error("Failed to list the Maildir", e))?;
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if let Some(rest) = name.strip_prefix(&prefix) {
+                    let target = self.root.join(format!(".{}.{}", new_name.replace('/', "."), rest));
+                    renamed(&entry.path(), &target).map_err(|e| io_error(&format!("Failed to rename {}", name), e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        let dir = self.folder_path(folder)?;
+        if self.is_plus_plus() {
+            return fs::remove_dir_all(&dir).map_err(|e| io_error(&format!("Failed to delete {}", folder), e));
+        }
+        // Folders below it live inside its directory and stay
+        for sub in ["cur", "new", "tmp"] {
+            fs::remove_dir_all(dir.join(sub)).map_err(|e| io_error(&format!("Failed to delete {}", folder), e))?;
+        }
+        let _ = fs::remove_file(dir.join(UID_LIST));
+        // Fails while folders below it are left, which is fine
+        let _ = fs::remove_dir(&dir);
+        Ok(())
+    }
+
+    fn append(&mut self, folder: &str, raw: &[u8], flags: &[String]) -> Result<(), EmailError> {
+        let dir = self.folder_path(folder)?;
+        // Keywords have no place in a Maildir file name and are dropped
+        let flags: Vec<String> = flags.iter().filter(|flag| info_for(std::slice::from_ref(flag)).is_some()).cloned().collect();
+        let name = delivery_name();
+        let temp = dir.join("tmp").join(&name);
+        let target = dir.join("cur").join(format!("{}{}", name, info_for(&flags).unwrap_or_default()));
+
+        let write = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&temp)?;
+            file.write_all(raw)?;
+            file.sync_all()?;
+            fs::rename(&temp, &target)
+        };
+        write().map_err(|e| {
+            let _ = fs::remove_file(&temp);
+            io_error(&format!("Failed to store a message in {}", folder), e)
+        })
+    }
+}
+
+/// Copy the messages of a Maildir tree (or one folder of it, `only`) to an account, into
+/// `into` or a folder of the same name. Messages marked for deletion are left out.
+/// Returns how many messages each folder had imported.
+pub fn import(
+    source: &mut MaildirStore,
+    target: &mut dyn ImapBackend,
+    only: Option<&str>,
+    into: Option<&str>,
+) -> Result<Vec<(String, usize)>, EmailError> {
+    ensure_writable("import messages")?;
+
+    let folders = match only {
+        Some(folder) => vec![folder.to_string()],
+        None => source.list_folders()?,
+    };
+    let mut imported = Vec::new();
+    for folder in folders {
+        let destination = into.unwrap_or(&folder).to_string();
+        let uids = source.uids(&folder)?;
+        let mut count = 0;
+        for batch in uids.chunks(IMPORT_BATCH) {
+            for message in source.fetch(&folder, batch)? {
+                if message.flags.iter().any(|flag| flag == "\\Deleted") {
+                    continue;
+                }
+                target.append(&destination, &message.raw, &message.flags)?;
+                count += 1;
+            }
+        }
+        crate::email::debug_log(&format!("import_maildir: {} messages from {} into {}", count, folder, destination));
+        imported.push((folder, count));
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maildir(root: &Path) {
+        for sub in ["cur", "new", "tmp"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_flags_in_file_names() {
+        assert_eq!(flags_of("1700000000.M1P2.host:2,FRS"), vec!["\\Flagged", "\\Answered", "\\Seen"]);
+        assert_eq!(flags_of("1700000000.M1P2.host!2,S"), vec!["\\Seen"]);
+        assert!(flags_of("1700000000.M1P2.host").is_empty());
+        assert_eq!(info_for(&["\\Seen".to_string(), "\\Draft".to_string()]).as_deref(), Some(":2,DS"));
+        assert!(info_for(&["$ReadLater".to_string()]).is_none());
+        assert_eq!(unique_name("abc:2,S"), "abc");
+    }
+
+    #[test]
+    fn test_folders_and_stable_uids() {
+        let root = std::env::temp_dir().join(format!("tuimail-maildir-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        maildir(&root);
+        maildir(&root.join(".Sent"));
+        maildir(&root.join(".Work.Projects"));
+        let mut store = MaildirStore::open(&root).unwrap();
+        assert_eq!(store.list_folders().unwrap(), vec!["INBOX", "Sent", "Work/Projects"]);
+        assert!(store.uids("Missing").is_err());
+
+        fs::write(root.join("new/1.one.host"), "Subject: one\n\nbody\n").unwrap();
+        fs::write(root.join("cur/2.two.host:2,S"), "Subject: two\n\nbody\n").unwrap();
+        let uids = store.uids("INBOX").unwrap();
+        assert_eq!(uids.len(), 2);
+        assert_eq!(store.unseen_count("INBOX").unwrap(), 1);
+
+        // Flag changes rename the file but keep its UID
+        let unread = *uids.iter().find(|uid| store.flags("INBOX", &[**uid]).unwrap()[uid].is_empty()).unwrap();
+        store.store_flag("INBOX", unread, "\\Seen", true).unwrap();
+        assert_eq!(store.uids("INBOX").unwrap(), uids);
+        assert_eq!(store.unseen_count("INBOX").unwrap(), 0);
+        assert!(store.store_flag("INBOX", unread, "$ReadLater", true).is_err());
+
+        let headers = store.fetch_headers("INBOX", &[unread]).unwrap();
+        assert_eq!(headers[0].raw, b"Subject: one\n\n");
+
+        store.move_message("INBOX", unread, "Sent").unwrap();
+        assert_eq!(store.uids("INBOX").unwrap().len(), 1);
+        assert_eq!(store.uids("Sent").unwrap(), vec![1]);
+        store.append("Work/Projects", b"Subject: new\n\nhi\n", &["\\Flagged".to_string()]).unwrap();
+        let appended = store.fetch("Work/Projects", &[1]).unwrap();
+        assert_eq!(appended[0].flags, vec!["\\Flagged"]);
+
+        // A new file after a deleted one never reuses its UID
+        store.delete("Sent", 1).unwrap();
+        fs::write(root.join(".Sent/new/3.three.host"), "Subject: three\n\nbody\n").unwrap();
+        assert_eq!(store.uids("Sent").unwrap(), vec![2]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
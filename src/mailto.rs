@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+/// A message to start from a `mailto:` link (RFC 6068)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mailto {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+    /// Files from `attach=` or `attachment=`, which some programs add though the RFC
+    /// has no such field
+    pub attachments: Vec<PathBuf>,
+    pub in_reply_to: Option<String>,
+}
+
+/// Undo `%xx` escapes; `+` stays a plus sign, as it is in mailto links
+pub(crate) fn percent_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = text
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("Invalid escape at '{}'", &text[i..]))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| "The link is not valid UTF-8".to_string())
+}
+
+/// Addresses of a comma-separated list, empty entries left out
+fn addresses(list: &str) -> Vec<String> {
+    list.split(',').map(str::trim).filter(|address| !address.is_empty()).map(str::to_string).collect()
+}
+
+/// A path from an attachment field, given as a path or a `file://` URL
+fn attachment_path(value: &str) -> PathBuf {
+    let path = value.strip_prefix("file://").unwrap_or(value);
+    PathBuf::from(shellexpand::tilde(path).into_owned())
+}
+
+/// Parse a `mailto:` link. Unknown fields are ignored, as the RFC asks; `to=` adds to
+/// the addresses before the `?`.
+pub fn parse(uri: &str) -> Result<Mailto, String> {
+    let uri = uri.trim();
+    let rest = uri
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+        .map(|_| &uri[7..])
+        .ok_or_else(|| format!("'{}' is not a mailto: link", uri))?;
+    let (recipients, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let mut mailto = Mailto { to: addresses(&percent_decode(recipients)?), ..Default::default() };
+    for field in query.split('&').filter(|field| !field.is_empty()) {
+        let (name, value) = field.split_once('=').unwrap_or((field, ""));
+        let value = percent_decode(value)?;
+        match name.to_ascii_lowercase().as_str() {
+            "to" => mailto.to.extend(addresses(&value)),
+            "cc" => mailto.cc.extend(addresses(&value)),
+            "bcc" => mailto.bcc.extend(addresses(&value)),
+            "subject" => mailto.subject = Some(value),
+            // Line breaks are written as %0D%0A
+            "body" => mailto.body = Some(value.replace("\r\n", "\n")),
+            "in-reply-to" => mailto.in_reply_to = Some(value),
+            "attach" | "attachment" if !value.is_empty() => mailto.attachments.push(attachment_path(&value)),
+            _ => {}
+        }
+    }
+    Ok(mailto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_are_parsed() {
+        let mailto = parse("mailto:alice@example.com,bob@example.com?subject=Hi%20there&body=Line%201%0D%0ALine+2&cc=carol@example.com&X-Unknown=1").unwrap();
+        assert_eq!(mailto.to, vec!["alice@example.com", "bob@example.com"]);
+        assert_eq!(mailto.cc, vec!["carol@example.com"]);
+        assert_eq!(mailto.subject.as_deref(), Some("Hi there"));
+        assert_eq!(mailto.body.as_deref(), Some("Line 1\nLine+2"));
+
+        let mailto = parse("MAILTO:?to=dave%40example.com&attach=file:///tmp/report.pdf&In-Reply-To=%3Cabc@example.com%3E").unwrap();
+        assert_eq!(mailto.to, vec!["dave@example.com"]);
+        assert_eq!(mailto.attachments, vec![PathBuf::from("/tmp/report.pdf")]);
+        assert_eq!(mailto.in_reply_to.as_deref(), Some("<abc@example.com>"));
+
+        assert_eq!(parse("mailto:").unwrap(), Mailto::default());
+        assert!(parse("https://example.com").is_err());
+        assert!(parse("mailto:a@example.com?subject=%zz").is_err());
+        assert!(parse("mailto:a@example.com?subject=%C3").is_err());
+    }
+}
@@ -1,28 +1,90 @@
 mod app;
+mod ascii_mode;
 mod async_grammar;
+mod attachment_index;
+mod attachment_text;
+mod audit;
+mod auth_results;
+mod auth;
+mod autoconfig;
+mod avatar;
+mod body_view;
+mod bundle;
+mod cache;
+mod caldav;
+mod calendar;
+mod capabilities;
+mod cleanup;
+mod cli;
+mod clipboard;
+mod compose_stats;
 mod config;
+mod config_crypto;
 mod credentials;
 mod database;
+mod digest;
+mod downloads;
+mod drafts;
+mod dsn;
 mod email;
+mod events;
+mod ews;
+mod filter_import;
+mod forge;
+mod gmail;
 mod grammarcheck;
+mod header_fields;
+mod http;
+mod i18n;
+mod imap_console;
+mod impersonation;
+mod maildir;
+mod ldap;
+mod mailto;
+mod markdown;
+mod mbox;
+mod mime;
+mod mute;
+mod notmuch;
+mod onboarding;
+mod pgp;
+mod profile;
+mod providers;
+mod quoting;
+mod recipients;
+mod reminders;
+mod reply_check;
+mod rules;
+mod search;
+mod snippet;
 mod spellcheck;
+mod tags;
+mod threads;
+mod transport;
 mod ui;
+mod updates;
+mod utf7;
+mod vcard;
+mod vip;
 mod test_parsing;
 
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use chrono::Local;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use crossterm::event::{self, Event, KeyEventKind};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::ExecutableCommand;
 
-use crate::app::App;
-use crate::config::Config;
+use crate::app::{App, AppMode};
+use crate::cli::{format_unread, overall_status, AccountStatus, ArchiveFormat, ExitStatus, FilterFormat, Output, UnreadCount, UnreadFormat};
+use crate::config::{Config, ConfigError};
 use crate::credentials::SecureCredentials;
-use crate::email::EmailClient;
+use crate::database::EmailDatabase;
+use crate::email::{EmailClient, EmailError};
+use crate::events::AppEvent;
 use log::error;
 use ratatui::prelude::*;
 
@@ -34,14 +96,30 @@ use crate::ui::ui;
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
 struct Args {
-    /// Path to config file
-    #[clap(short, long, default_value = "~/.config/tuimail/config.json")]
-    config: String,
+    /// Path to config file (defaults to the profile's config.json)
+    #[clap(short, long)]
+    config: Option<String>,
+
+    /// Profile with its own config, cache, databases and keyring entries
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Disable sending, deleting, moving and flag changes; folders are opened read-only
+    #[clap(long)]
+    read_only: bool,
+
+    /// Never connect to mail servers; only show what is in the local database
+    #[clap(long, global = true)]
+    no_network: bool,
     
     /// Enable debug logging
     #[clap(short, long)]
     debug: bool,
 
+    /// Print subcommand results as JSON
+    #[clap(long, global = true)]
+    json: bool,
+
     #[clap(subcommand)]
     command: Option<Commands>,
 }
@@ -57,46 +135,64 @@ enum Commands {
         /// Email address
         #[clap(short, long)]
         email: String,
+
+        /// Fill in the server settings for a provider (gmail, outlook, fastmail, yahoo, icloud);
+        /// detected from the email address when omitted
+        #[clap(long)]
+        provider: Option<String>,
         
-        /// IMAP server address
+        /// IMAP server address (looked up from the email address when omitted)
         #[clap(long)]
-        imap_server: String,
+        imap_server: Option<String>,
         
-        /// IMAP server port
-        #[clap(long, default_value = "993")]
-        imap_port: u16,
+        /// IMAP server port [default: 993]
+        #[clap(long)]
+        imap_port: Option<u16>,
         
-        /// IMAP security (None, StartTLS, SSL)
-        #[clap(long, default_value = "SSL")]
-        imap_security: String,
+        /// IMAP security (None, StartTLS, SSL) [default: SSL]
+        #[clap(long)]
+        imap_security: Option<String>,
         
-        /// IMAP username
+        /// IMAP username [default: the email address]
         #[clap(long)]
-        imap_username: String,
+        imap_username: Option<String>,
         
         /// IMAP password
+        #[clap(long, required_unless_present = "maildir")]
+        imap_password: Option<String>,
+
+        /// Read mail from this local Maildir tree instead of an IMAP server
+        #[clap(long)]
+        maildir: Option<String>,
+
+        /// Read and send mail through this Exchange Web Services endpoint instead of IMAP
+        /// and SMTP, such as https://mail.example.com/EWS/Exchange.asmx
+        #[clap(long, conflicts_with = "maildir")]
+        ews_url: Option<String>,
+        
+        /// SMTP server address (looked up from the email address when omitted)
         #[clap(long)]
-        imap_password: String,
+        smtp_server: Option<String>,
         
-        /// SMTP server address
+        /// SMTP server port [default: 587]
         #[clap(long)]
-        smtp_server: String,
+        smtp_port: Option<u16>,
         
-        /// SMTP server port
-        #[clap(long, default_value = "587")]
-        smtp_port: u16,
+        /// SMTP security (None, StartTLS, SSL) [default: StartTLS]
+        #[clap(long)]
+        smtp_security: Option<String>,
         
-        /// SMTP security (None, StartTLS, SSL)
-        #[clap(long, default_value = "StartTLS")]
-        smtp_security: String,
+        /// SMTP username [default: the email address]
+        #[clap(long)]
+        smtp_username: Option<String>,
         
-        /// SMTP username
+        /// SMTP password [default: the IMAP password]
         #[clap(long)]
-        smtp_username: String,
+        smtp_password: Option<String>,
         
-        /// SMTP password
+        /// Seconds between background syncs (defaults to the global interval)
         #[clap(long)]
-        smtp_password: String,
+        sync_interval: Option<u64>,
     },
     
     /// List configured accounts
@@ -109,12 +205,239 @@ enum Commands {
         index: usize,
     },
     
+    /// Report connectivity and unread counts of every account
+    Status {
+        /// Folder to count unread messages in
+        #[clap(long, default_value = "INBOX")]
+        folder: String,
+
+        /// Use the cached counts without connecting to the servers
+        #[clap(long)]
+        offline: bool,
+    },
+    
+    /// Print unread counts from the local database; with --watch, again whenever they change
+    Unread {
+        /// Folder to count unread messages in
+        #[clap(long, default_value = "INBOX")]
+        folder: String,
+
+        /// Keep running and print a new line each time the counts change
+        #[clap(long)]
+        watch: bool,
+
+        /// Seconds between checks in watch mode
+        #[clap(long, default_value = "5")]
+        interval: u64,
+
+        /// Output style for status lines
+        #[clap(long, value_enum, default_value = "plain")]
+        format: UnreadFormat,
+    },
+    
+    /// Show the log of deletes, moves, flag changes and sends, newest first
+    Audit {
+        /// Only show entries of this account (email address)
+        #[clap(long)]
+        account: Option<String>,
+
+        /// Number of entries to show
+        #[clap(long, default_value = "50")]
+        limit: usize,
+
+        /// Print CSV for exporting
+        #[clap(long)]
+        csv: bool,
+    },
+    
+    /// Export messages, attachments included, to a passphrase-encrypted bundle or an mbox file
+    Export {
+        /// Account to export from (email address or index); the default account if omitted
+        #[clap(long)]
+        account: Option<String>,
+
+        /// Folder to export from
+        #[clap(long, default_value = "INBOX")]
+        folder: String,
+
+        /// Only these messages (comma-separated UIDs); the whole folder if omitted
+        #[clap(long, value_delimiter = ',')]
+        uids: Vec<u32>,
+
+        /// Only messages from this day on (YYYY-MM-DD); mbox only
+        #[clap(long, value_parser = attachment_index::parse_date)]
+        after: Option<i64>,
+
+        /// Only messages from before this day (YYYY-MM-DD); mbox only
+        #[clap(long, value_parser = attachment_index::parse_date)]
+        before: Option<i64>,
+
+        /// File to write
+        #[clap(short, long)]
+        output: String,
+
+        /// Both fetch the messages from the server unchanged; a bundle is encrypted,
+        /// mbox (mboxrd) is read by mutt, Thunderbird and most other programs
+        #[clap(long, value_enum, default_value = "bundle")]
+        format: ArchiveFormat,
+    },
+
+    /// Import the messages of an encrypted bundle or an mbox file into an account on the server
+    Import {
+        /// Bundle file written by export, or an mbox file
+        path: String,
+
+        /// Account to import into (email address or index); the default account if omitted
+        #[clap(long)]
+        account: Option<String>,
+
+        /// Folder to import into; each message's original folder if omitted (INBOX for mbox)
+        #[clap(long)]
+        folder: Option<String>,
+
+        /// Format of the file
+        #[clap(long, value_enum, default_value = "bundle")]
+        format: ArchiveFormat,
+
+        /// Only list the bundle's contents
+        #[clap(long)]
+        list: bool,
+    },
+    
+    /// Copy the mail of a local Maildir tree (offlineimap, mbsync) into an account
+    ImportMaildir {
+        /// Root of the Maildir tree
+        path: String,
+
+        /// Account to import into (email address or index); the default account if omitted
+        #[clap(long)]
+        account: Option<String>,
+
+        /// Only this folder of the tree, such as "INBOX" or "Archive/2019"
+        #[clap(long)]
+        source_folder: Option<String>,
+
+        /// Folder to import into; a folder of the same name as each source folder if omitted
+        #[clap(long)]
+        folder: Option<String>,
+    },
+
+    /// Add the message filters of another mail program to the rules: Thunderbird's
+    /// msgFilterRules.dat or a Sieve script
+    ImportFilters {
+        /// The filter file
+        path: String,
+
+        /// Format of the file; guessed from its contents if omitted
+        #[clap(long, value_enum)]
+        format: Option<FilterFormat>,
+
+        /// Only apply the imported rules to this account (email address or index)
+        #[clap(long)]
+        account: Option<String>,
+
+        /// Show the rules that would be added without changing the config
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Sign an account in to the Gmail API in the browser and sync it through the API
+    /// instead of IMAP and SMTP
+    GmailLogin {
+        /// Account to sign in (email address or index); the default account if omitted
+        #[clap(long)]
+        account: Option<String>,
+
+        /// Client ID of your OAuth client (Google Cloud console, "Desktop app" type);
+        /// the client secret is asked for
+        #[clap(long)]
+        client_id: String,
+    },
+
     /// Set default account
     SetDefaultAccount {
         /// Account index (starting from 0)
         #[clap(short, long)]
         index: usize,
     },
+
+    /// Encrypt the config file in place as an age file (passphrase, or an identity held in the system keyring)
+    EncryptConfig {
+        /// Keep a random key in the system keyring instead of asking for a passphrase
+        #[clap(long)]
+        keyring: bool,
+    },
+
+    /// Decrypt the config file in place
+    DecryptConfig,
+
+    /// List available profiles
+    ListProfiles,
+
+    /// Open the compose form, filled in from a mailto: link when one is given, so
+    /// tuimail can be registered as the system's mail handler
+    Compose {
+        /// Link such as "mailto:alice@example.com?subject=Hi&body=..."
+        #[clap(long)]
+        mailto: Option<String>,
+    },
+
+    /// Manage the local cache of attachments
+    Cache {
+        #[clap(subcommand)]
+        command: CacheCommand,
+    },
+
+    /// Move contacts in and out of the address book as vCards
+    Contacts {
+        #[clap(subcommand)]
+        command: ContactsCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ContactsCommand {
+    /// Add the people in a .vcf file to an account's address book
+    Import {
+        /// The vCard file
+        path: String,
+
+        /// Account to add them to (email address or index); the default account if omitted
+        #[clap(long)]
+        account: Option<String>,
+    },
+
+    /// Write an account's address book as a vCard file
+    Export {
+        /// File to write; standard output if omitted
+        output: Option<String>,
+
+        /// Account to export (email address or index); the default account if omitted
+        #[clap(long)]
+        account: Option<String>,
+
+        /// Include everyone the account has exchanged mail with, not only saved contacts
+        #[clap(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// Report the size of the cache and shrink it to its limit, dropping the least
+    /// recently opened attachments of messages still on the server
+    Gc {
+        /// Limit in megabytes; the `cache.max_attachment_mb` setting when omitted
+        #[clap(long)]
+        max_mb: Option<u64>,
+    },
+}
+
+impl Commands {
+    /// Whether the command opens the terminal interface rather than printing a result
+    fn opens_ui(&self) -> bool {
+        matches!(self, Commands::Compose { .. })
+    }
 }
 
 #[tokio::main]
@@ -145,81 +468,202 @@ async fn main() -> Result<()> {
     }
     
     // Initialize logger - only for CLI commands, not TUI mode
-    if matches!(args.command, Some(_)) {
+    if args.command.as_ref().is_some_and(|command| !command.opens_ui()) {
         env_logger::Builder::new()
             .filter_level(if args.debug { log::LevelFilter::Debug } else { log::LevelFilter::Info })
             .init();
     }
     
+    let out = Output::new(args.json);
+
+    // Select the profile before any config, cache or keyring path is used
+    let profile = match args.profile {
+        Some(name) => Some(name),
+        None if args.command.as_ref().is_none_or(Commands::opens_ui) => pick_profile()?,
+        None => None,
+    };
+    if let Some(name) = &profile {
+        if let Err(e) = profile::validate_name(name) {
+            out.fail(ExitStatus::Config, &e);
+        }
+    }
+    profile::set_active(profile);
+    crate::email::set_read_only(args.read_only);
+    crate::email::set_offline(args.no_network);
+
     // Load configuration
-    let config_path = shellexpand::tilde(&args.config).into_owned();
-    let mut config = Config::load(&config_path).unwrap_or_else(|_| {
-        println!("No config found at {}. Creating default config.", config_path);
-        Config::default()
-    });
+    let config_path = match &args.config {
+        Some(path) => shellexpand::tilde(path).into_owned(),
+        None => profile::config_path().to_string_lossy().into_owned(),
+    };
+    let mut config = match Config::load(&config_path) {
+        Ok(config) => config,
+        // Never fall back to defaults here: the save below would overwrite the encrypted file
+        Err(e @ ConfigError::DecryptError(_)) => {
+            out.fail(ExitStatus::Config, &format!("{} ({})", e, config_path));
+        }
+        Err(_) => {
+            if !out.is_json() {
+                println!("No config found at {}. Creating default config.", config_path);
+            }
+            Config::default()
+        }
+    };
+    if let Err(e) = header_fields::validate(&config.header_fields) {
+        out.fail(ExitStatus::Config, &e);
+    }
+    header_fields::configure(&config.header_fields);
     
     // Handle subcommands
+    let mut compose = None;
     if let Some(cmd) = args.command {
         match cmd {
+            Commands::Compose { mailto } => {
+                let mailto = match mailto.as_deref().map(mailto::parse).transpose() {
+                    Ok(mailto) => mailto.unwrap_or_default(),
+                    Err(e) => Args::command().error(clap::error::ErrorKind::InvalidValue, e).exit(),
+                };
+                compose = Some(mailto);
+            }
             Commands::AddAccount {
                 name,
                 email,
+                provider,
                 imap_server,
                 imap_port,
                 imap_security,
                 imap_username,
                 imap_password,
+                maildir,
+                ews_url,
                 smtp_server,
                 smtp_port,
                 smtp_security,
                 smtp_username,
                 smtp_password,
+                sync_interval,
             } => {
                 // Initialize secure credential storage
-                let credentials = SecureCredentials::new()
-                    .context("Failed to initialize secure credential storage")?;
-
-                // Parse security settings
-                let imap_security = match imap_security.to_lowercase().as_str() {
-                    "none" => ImapSecurity::None,
-                    "starttls" => ImapSecurity::StartTLS,
-                    "ssl" => ImapSecurity::SSL,
-                    _ => {
-                        println!("Invalid IMAP security setting. Using SSL.");
-                        ImapSecurity::SSL
+                let credentials = SecureCredentials::new().unwrap_or_else(|e| {
+                    out.fail(ExitStatus::Failure, &format!("Failed to initialize secure credential storage: {}", e))
+                });
+
+                // Start from the provider's settings, then apply anything given explicitly
+                let preset = provider.as_deref().map(|id| {
+                    providers::find(id).unwrap_or_else(|| {
+                        let known: Vec<&str> = providers::PRESETS.iter().map(|p| p.id).collect();
+                        out.fail(ExitStatus::NotFound, &format!("Unknown provider '{}'. Known providers: {}", id, known.join(", ")))
+                    })
+                }).or_else(|| providers::for_address(&email));
+                let mut account = match preset {
+                    // A local account only needs the SMTP settings, and those only to send
+                    _ if maildir.is_some() => EmailAccount {
+                        name,
+                        email: email.clone(),
+                        imap_server: String::new(),
+                        imap_username: String::new(),
+                        smtp_server: smtp_server.clone().unwrap_or_default(),
+                        smtp_username: email.clone(),
+                        maildir: maildir.clone(),
+                        ..EmailAccount::default()
+                    },
+                    // Exchange needs neither IMAP nor SMTP settings
+                    _ if ews_url.is_some() => EmailAccount {
+                        name,
+                        email: email.clone(),
+                        imap_server: String::new(),
+                        imap_username: email.clone(),
+                        smtp_server: String::new(),
+                        smtp_username: email.clone(),
+                        exchange: ews_url.clone().map(|url| config::ExchangeConfig { url }),
+                        ..EmailAccount::default()
+                    },
+                    Some(preset) => preset.account(&name, &email),
+                    // Without a provider, look the servers up unless both were given
+                    None if imap_server.is_none() || smtp_server.is_none() => {
+                        if !out.is_json() {
+                            println!("Looking up mail servers for {}...", email);
+                        }
+                        let discovered = autoconfig::discover(&email).unwrap_or_else(|e| {
+                            out.fail(ExitStatus::NotFound, &format!("{}. Pass --imap-server and --smtp-server.", e))
+                        });
+                        if !out.is_json() {
+                            println!("Found {}:{} and {}:{} via {}", discovered.imap_server, discovered.imap_port,
+                                discovered.smtp_server, discovered.smtp_port, discovered.source);
+                        }
+                        let to_confirm = discovered.hosts_to_confirm(&email);
+                        if !to_confirm.is_empty() {
+                            let message = format!("DNS points outside the address's domain, to {}", to_confirm.join(" and "));
+                            if out.is_json() || !io::stdin().is_terminal() || !confirm(&format!("{}. Use these servers?", message)) {
+                                out.fail(ExitStatus::NotFound, &format!("{}. Pass --imap-server and --smtp-server.", message));
+                            }
+                        }
+                        discovered.account(&name, &email)
                     }
+                    None => EmailAccount {
+                        name,
+                        email: email.clone(),
+                        imap_username: email.clone(),
+                        smtp_username: email.clone(),
+                        ..EmailAccount::default()
+                    },
                 };
-                
-                let smtp_security = match smtp_security.to_lowercase().as_str() {
-                    "none" => SmtpSecurity::None,
-                    "starttls" => SmtpSecurity::StartTLS,
-                    "ssl" => SmtpSecurity::SSL,
-                    _ => {
-                        println!("Invalid SMTP security setting. Using StartTLS.");
+                account.signature = Some("Sent from Email Client".to_string());
+                account.sync_interval = sync_interval;
+                if let Some(server) = imap_server {
+                    account.imap_server = server;
+                }
+                if let Some(port) = imap_port {
+                    account.imap_port = port;
+                }
+                if let Some(security) = imap_security {
+                    account.imap_security = security.parse().unwrap_or_else(|e| {
+                        eprintln!("{}. Using SSL.", e);
+                        ImapSecurity::SSL
+                    });
+                }
+                if let Some(username) = imap_username {
+                    account.imap_username = username;
+                }
+                if let Some(server) = smtp_server {
+                    account.smtp_server = server;
+                }
+                if let Some(port) = smtp_port {
+                    account.smtp_port = port;
+                }
+                if let Some(security) = smtp_security {
+                    account.smtp_security = security.parse().unwrap_or_else(|e| {
+                        eprintln!("{}. Using StartTLS.", e);
                         SmtpSecurity::StartTLS
+                    });
+                }
+                if let Some(username) = smtp_username {
+                    account.smtp_username = username;
+                }
+                let smtp_password = smtp_password.or_else(|| imap_password.clone());
+                if let Some(preset) = preset.filter(|_| maildir.is_none() && ews_url.is_none()) {
+                    if !out.is_json() {
+                        println!("{}: {}", preset.name, preset.notes);
                     }
-                };
-                
-                // Create account (without passwords in config)
-                let account = EmailAccount {
-                    name,
-                    email: email.clone(),
-                    imap_server,
-                    imap_port,
-                    imap_security,
-                    imap_username,
-                    smtp_server,
-                    smtp_port,
-                    smtp_security,
-                    smtp_username,
-                    signature: Some("Sent from Email Client".to_string()),
-                };
+                }
+
+                if let Some(path) = account.maildir_path() {
+                    if let Err(e) = maildir::MaildirStore::open(&path) {
+                        out.fail(ExitStatus::NotFound, &format!("No Maildir at {}: {}", path.display(), e));
+                    }
+                }
 
                 // Store passwords securely
-                account.store_imap_password(&credentials, &imap_password)
-                    .context("Failed to store IMAP password securely")?;
-                account.store_smtp_password(&credentials, &smtp_password)
-                    .context("Failed to store SMTP password securely")?;
+                if let Some(imap_password) = &imap_password {
+                    if let Err(e) = account.store_imap_password(&credentials, imap_password) {
+                        out.fail(ExitStatus::Failure, &format!("Failed to store IMAP password securely: {}", e));
+                    }
+                }
+                if let Some(smtp_password) = &smtp_password {
+                    if let Err(e) = account.store_smtp_password(&credentials, smtp_password) {
+                        out.fail(ExitStatus::Failure, &format!("Failed to store SMTP password securely: {}", e));
+                    }
+                }
                 
                 // Add account to config
                 config.accounts.push(account);
@@ -231,95 +675,550 @@ async fn main() -> Result<()> {
                 
                 // Save config
                 if let Err(e) = config.save(&config_path) {
-                    println!("Failed to save config: {}", e);
-                    return Ok(());
+                    out.fail(ExitStatus::Config, &format!("Failed to save config: {}", e));
                 }
                 
-                println!("✓ Account added successfully with secure password storage!");
+                out.success("Account added successfully with secure password storage!");
                 return Ok(());
             }
             Commands::ListAccounts => {
-                println!("Configured accounts:");
-                for (i, account) in config.accounts.iter().enumerate() {
-                    println!("{}. {} <{}> ({})", 
-                        i, 
-                        account.name, 
-                        account.email,
-                        if i == config.default_account { "default" } else { "" }
-                    );
-                }
+                let accounts: Vec<serde_json::Value> = config.accounts.iter().enumerate().map(|(i, account)| {
+                    serde_json::json!({
+                        "index": i,
+                        "name": account.name,
+                        "email": account.email,
+                        "default": i == config.default_account,
+                    })
+                }).collect();
+                out.print(&accounts, |_| {
+                    println!("Configured accounts:");
+                    for (i, account) in config.accounts.iter().enumerate() {
+                        println!("{}. {} <{}> ({})", 
+                            i, 
+                            account.name, 
+                            account.email,
+                            if i == config.default_account { "default" } else { "" }
+                        );
+                    }
+                });
                 return Ok(());
             }
             Commands::TestAccount { index } => {
-                if index >= config.accounts.len() {
-                    eprintln!("Error: Account index {} not found. Use 'list-accounts' to see available accounts.", index);
-                    std::process::exit(1);
+                let Some(account) = config.accounts.get(index) else {
+                    out.fail(ExitStatus::NotFound, &format!("Account index {} not found. Use 'list-accounts' to see available accounts.", index));
+                };
+                if !out.is_json() {
+                    println!("Testing account: {} <{}>", account.name, account.email);
+                    println!("IMAP Server: {}:{}", account.imap_server, account.imap_port);
                 }
                 
-                let account = &config.accounts[index];
-                println!("Testing account: {} <{}>", account.name, account.email);
-                println!("IMAP Server: {}:{}", account.imap_server, account.imap_port);
-                
                 // Test credential retrieval
-                let credentials = SecureCredentials::new()
-                    .expect("Failed to initialize credential storage");
+                let credentials = SecureCredentials::new().unwrap_or_else(|e| {
+                    out.fail(ExitStatus::Failure, &format!("Failed to initialize credential storage: {}", e))
+                });
                 
                 match credentials.get_password(&account.email, "imap") {
-                    Ok(_password) => {
-                        println!("✓ Password found in credential store");
-                        
-                        // Test IMAP connection
-                        println!("Testing IMAP connection...");
-                        let client = EmailClient::new(account.clone(), credentials);
-                        
-                        match client.list_folders() {
-                            Ok(folders) => {
-                                println!("✓ IMAP connection successful!");
-                                println!("Found {} folders:", folders.len());
-                                for folder in folders.iter().take(5) {
-                                    println!("  - {}", folder);
-                                }
-                                if folders.len() > 5 {
-                                    println!("  ... and {} more", folders.len() - 5);
-                                }
-                            }
-                            Err(e) => {
-                                println!("✗ IMAP connection failed: {}", e);
-                                std::process::exit(1);
+                    Ok(Some(_)) => {}
+                    Ok(None) => out.fail(ExitStatus::Auth, "Password not found. Please add the account password using 'add-account' command"),
+                    Err(e) => out.fail(ExitStatus::Auth, &format!("Password not found: {}. Please add the account password using 'add-account' command", e)),
+                }
+                if !out.is_json() {
+                    println!("✓ Password found in credential store");
+                    println!("Testing IMAP connection...");
+                }
+
+                // Test IMAP connection
+                let client = EmailClient::new(account.clone(), credentials);
+                let folders = client.list_folders().unwrap_or_else(|e| {
+                    if let EmailError::AuthError(message) = &e {
+                        let failure = auth::classify(message, account);
+                        out.fail(
+                            ExitStatus::Auth,
+                            &format!("{}: {}\n{}", failure.headline(), message, failure.guidance(account)),
+                        )
+                    }
+                    out.fail(ExitStatus::from(&e), &format!("IMAP connection failed: {}", e))
+                });
+
+                let result = serde_json::json!({
+                    "name": account.name,
+                    "email": account.email,
+                    "imap_server": account.imap_server,
+                    "connected": true,
+                    "folders": folders,
+                });
+                out.print(&result, |_| {
+                    println!("✓ IMAP connection successful!");
+                    println!("Found {} folders:", folders.len());
+                    for folder in folders.iter().take(5) {
+                        println!("  - {}", folder);
+                    }
+                    if folders.len() > 5 {
+                        println!("  ... and {} more", folders.len() - 5);
+                    }
+                });
+                return Ok(());
+            }
+            Commands::Status { folder, offline } => {
+                let credentials = if offline {
+                    None
+                } else {
+                    Some(SecureCredentials::new().unwrap_or_else(|e| {
+                        out.fail(ExitStatus::Failure, &format!("Failed to initialize credential storage: {}", e))
+                    }))
+                };
+                // Only read the cache if the client has created it
+                let db_path = profile::database_path();
+                let database = if db_path.exists() { EmailDatabase::new(&db_path).ok() } else { None };
+
+                let statuses: Vec<AccountStatus> = config.accounts.iter()
+                    .map(|account| account_status(account, &folder, credentials.as_ref(), database.as_ref()))
+                    .collect();
+                out.print(&statuses, |statuses| {
+                    for status in statuses {
+                        println!("{}", status.line());
+                    }
+                });
+                std::process::exit(overall_status(&statuses).code());
+            }
+            Commands::Unread { folder, watch, interval, format } => {
+                let db_path = profile::database_path();
+                let mut database = None;
+                let mut last: Option<Vec<UnreadCount>> = None;
+                loop {
+                    // The database appears once the client has synced for the first time
+                    if database.is_none() && db_path.exists() {
+                        database = EmailDatabase::new(&db_path).ok();
+                    }
+                    match database.as_ref() {
+                        Some(db) => match unread_counts(&config, db, &folder) {
+                            Some(counts) if last.as_ref() != Some(&counts) => {
+                                out.print(&counts, |counts| println!("{}", format_unread(counts, format)));
+                                io::stdout().flush()?;
+                                last = Some(counts);
                             }
+                            Some(_) => {}
+                            None if !watch => out.fail(ExitStatus::Failure, "Failed to read the mail database"),
+                            // The client may be holding a write lock; try again next round
+                            None => {}
+                        },
+                        None if !watch => {
+                            out.fail(ExitStatus::NotFound, &format!("No mail database at {}. Run tuimail once to sync.", db_path.display()));
+                        }
+                        None => {}
+                    }
+                    if !watch {
+                        return Ok(());
+                    }
+                    tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+                }
+            }
+            Commands::Audit { account, limit, csv } => {
+                let db_path = profile::database_path();
+                if !db_path.exists() {
+                    out.fail(ExitStatus::NotFound, &format!("No mail database at {}. Run tuimail once to sync.", db_path.display()));
+                }
+                let entries = EmailDatabase::new(&db_path)
+                    .and_then(|db| db.get_audit_log(account.as_deref(), Some(limit)))
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to read the audit log: {}", e)));
+                out.print(&entries, |entries| {
+                    if csv {
+                        print!("{}", audit::to_csv(entries));
+                        return;
+                    }
+                    for entry in entries {
+                        let uid = entry.uid.map(|uid| uid.to_string()).unwrap_or_default();
+                        println!(
+                            "{}  {:<4} {:<24} {:<12} {:>6}  {:<16} {}",
+                            audit::format_time(entry.timestamp),
+                            entry.origin,
+                            entry.account_email,
+                            entry.folder,
+                            uid,
+                            entry.action,
+                            entry.detail
+                        );
+                    }
+                });
+                return Ok(());
+            }
+            Commands::Export { account, folder, uids, after, before, output, format: ArchiveFormat::Mbox } => {
+                let (_, client) = bundle_client(&config, account.as_deref(), &out);
+                let filter = mbox::ExportFilter { uids: (!uids.is_empty()).then_some(uids.as_slice()), after, before };
+                let output = shellexpand::tilde(&output).into_owned();
+                let file = std::fs::File::create(&output)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to create {}: {}", output, e)));
+                let exported = client
+                    .export_mbox(&folder, &filter, &mut std::io::BufWriter::new(file))
+                    .unwrap_or_else(|e| out.fail(ExitStatus::from(&e), &e.to_string()));
+                for uid in &exported.missing {
+                    eprintln!("Warning: UID {} in {} is no longer on the server and was left out", uid, folder);
+                }
+                out.success(&format!("Exported {} messages from {} to {}", exported.written, folder, output));
+                return Ok(());
+            }
+            Commands::Export { account, folder, uids, after, before, output, format: ArchiveFormat::Bundle } => {
+                if after.is_some() || before.is_some() {
+                    out.fail(ExitStatus::Failure, "--after and --before only apply to --format mbox");
+                }
+                let (_, client) = bundle_client(&config, account.as_deref(), &out);
+                let uids = (!uids.is_empty()).then_some(uids.as_slice());
+                let bundle = client
+                    .export_bundle(&folder, uids)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::from(&e), &format!("Export failed: {}", e)));
+
+                let passphrase = bundle::read_passphrase("Bundle passphrase: ")
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &e.to_string()));
+                if passphrase.is_empty() {
+                    out.fail(ExitStatus::Failure, "Passphrase must not be empty");
+                }
+                if std::env::var(bundle::PASSPHRASE_ENV).is_err() {
+                    let confirmation = bundle::read_passphrase("Repeat passphrase: ")
+                        .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &e.to_string()));
+                    if passphrase != confirmation {
+                        out.fail(ExitStatus::Failure, "Passphrases do not match");
+                    }
+                }
+
+                let output = shellexpand::tilde(&output).into_owned();
+                crate::config_crypto::ConfigEncryption::with_passphrase(&passphrase)
+                    .and_then(|encryption| bundle.seal(&encryption))
+                    .and_then(|sealed| bundle::write_file(std::path::Path::new(&output), &sealed))
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to write bundle: {}", e)));
+
+                out.success(&format!("Exported {} messages from {} to {}", bundle.index.len(), folder, output));
+                return Ok(());
+            }
+            Commands::Import { path, account, folder, list, format: ArchiveFormat::Mbox } => {
+                if list {
+                    out.fail(ExitStatus::Failure, "--list only works with bundles");
+                }
+                let path = shellexpand::tilde(&path).into_owned();
+                let file = std::fs::File::open(&path)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::NotFound, &format!("Failed to read {}: {}", path, e)));
+                let (account, client) = bundle_client(&config, account.as_deref(), &out);
+                let target = folder.as_deref().unwrap_or("INBOX");
+                let imported = client
+                    .import_mbox(std::io::BufReader::new(file), target)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::from(&e), &format!("Import failed: {}", e)));
+
+                let db_path = profile::database_path();
+                if let Ok(database) = EmailDatabase::new(&db_path) {
+                    let detail = format!("{} messages from mbox {}", imported, path);
+                    audit::record(&database, &account.email, target, None, "import", audit::Origin::Cli, &detail);
+                }
+
+                out.success(&format!("Imported {} messages into {}/{}", imported, account.email, target));
+                return Ok(());
+            }
+            Commands::Import { path, account, folder, list, format: ArchiveFormat::Bundle } => {
+                let path = shellexpand::tilde(&path).into_owned();
+                let content = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::NotFound, &format!("Failed to read {}: {}", path, e)));
+                let bundle = bundle::Bundle::open(&content, || bundle::read_passphrase("Bundle passphrase: "))
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to open bundle: {}", e)));
+
+                if list {
+                    out.print(&bundle.index, |index| {
+                        println!("Bundle of {} messages from {}, created {}", index.len(), bundle.account, audit::format_time(bundle.created));
+                        for entry in index {
+                            let attachments = if entry.attachments.is_empty() {
+                                String::new()
+                            } else {
+                                format!("  [{}]", entry.attachments.join(", "))
+                            };
+                            println!(
+                                "{}  {:<12} {:<28} {}{}",
+                                audit::format_time(entry.date),
+                                entry.folder,
+                                entry.from,
+                                entry.subject,
+                                attachments
+                            );
                         }
+                    });
+                    return Ok(());
+                }
+
+                let (account, client) = bundle_client(&config, account.as_deref(), &out);
+                let imported = client
+                    .import_bundle(&bundle, folder.as_deref())
+                    .unwrap_or_else(|e| out.fail(ExitStatus::from(&e), &format!("Import failed: {}", e)));
+
+                let db_path = profile::database_path();
+                if let Ok(database) = EmailDatabase::new(&db_path) {
+                    let target = folder.as_deref().unwrap_or("");
+                    let detail = format!("{} messages from {}", imported, path);
+                    audit::record(&database, &account.email, target, None, "import", audit::Origin::Cli, &detail);
+                }
+
+                out.success(&format!("Imported {} messages into {}", imported, account.email));
+                return Ok(());
+            }
+            Commands::ImportMaildir { path, account, source_folder, folder } => {
+                let path = std::path::PathBuf::from(shellexpand::tilde(&path).into_owned());
+                let (account, client) = bundle_client(&config, account.as_deref(), &out);
+                let imported = client
+                    .import_maildir(&path, source_folder.as_deref(), folder.as_deref())
+                    .unwrap_or_else(|e| out.fail(ExitStatus::from(&e), &format!("Import failed: {}", e)));
+                let total: usize = imported.iter().map(|(_, count)| count).sum();
+
+                let db_path = profile::database_path();
+                if let Ok(database) = EmailDatabase::new(&db_path) {
+                    let target = folder.as_deref().unwrap_or("");
+                    let detail = format!("{} messages from Maildir {}", total, path.display());
+                    audit::record(&database, &account.email, target, None, "import", audit::Origin::Cli, &detail);
+                }
+
+                if !out.is_json() {
+                    for (source, count) in &imported {
+                        println!("{:>7}  {}", count, source);
                     }
-                    Err(e) => {
-                        println!("✗ Password not found: {}", e);
-                        println!("Please add the account password using 'add-account' command");
-                        std::process::exit(1);
+                }
+                out.success(&format!("Imported {} messages into {}", total, account.email));
+                return Ok(());
+            }
+            Commands::ImportFilters { path, format, account, dry_run } => {
+                let path = shellexpand::tilde(&path).into_owned();
+                let text = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to read {}: {}", path, e)));
+                let account = account.map(|account| {
+                    let found = match account.parse::<usize>() {
+                        Ok(index) => config.accounts.get(index),
+                        Err(_) => config.accounts.iter().find(|a| a.email.eq_ignore_ascii_case(&account)),
+                    };
+                    found.map(|a| a.email.clone()).unwrap_or_else(|| {
+                        out.fail(ExitStatus::NotFound, "Account not found. Use 'list-accounts' to see available accounts.")
+                    })
+                });
+
+                let format = format.unwrap_or(if filter_import::looks_like_thunderbird(&text) {
+                    FilterFormat::Thunderbird
+                } else {
+                    FilterFormat::Sieve
+                });
+                let mut report = match format {
+                    FilterFormat::Thunderbird => filter_import::from_thunderbird(&text),
+                    FilterFormat::Sieve => filter_import::from_sieve(&text)
+                        .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to read the Sieve script: {}", e))),
+                };
+                for rule in &mut report.rules {
+                    rule.account = account.clone();
+                }
+
+                if !dry_run && !report.rules.is_empty() {
+                    config.rules.extend(report.rules.iter().cloned());
+                    if let Err(e) = config.save(&config_path) {
+                        out.fail(ExitStatus::Config, &format!("Failed to save config: {}", e));
                     }
                 }
+                out.print(&report, |report| {
+                    for rule in &report.rules {
+                        let conditions: Vec<String> = [("from", &rule.from_contains), ("to", &rule.to_contains), ("subject", &rule.subject_contains)]
+                            .into_iter()
+                            .filter_map(|(header, needle)| needle.as_ref().map(|needle| format!("{} contains \"{}\"", header, needle)))
+                            .collect();
+                        println!("  {}: {} -> {}", rule.name, conditions.join(" and "), rules::describe_action(&rule.action));
+                    }
+                    if !report.unsupported.is_empty() {
+                        println!("Left out:");
+                        for line in &report.unsupported {
+                            println!("  {}", line);
+                        }
+                    }
+                    if dry_run {
+                        println!("{} rules would be added", report.rules.len());
+                    } else {
+                        println!("✓ Added {} rules to {}", report.rules.len(), config_path);
+                    }
+                });
+                return Ok(());
+            }
+            Commands::GmailLogin { account, client_id } => {
+                let (account, _) = bundle_client(&config, account.as_deref(), &out);
+                let client_secret = crate::config_crypto::prompt_hidden("OAuth client secret: ")
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &e.to_string()));
+                let refresh_token = gmail::login(&client_id, client_secret.trim(), |url| {
+                    eprintln!("Open this address in a browser and sign in as {}:\n\n  {}\n", account.email, url);
+                })
+                .unwrap_or_else(|e| out.fail(ExitStatus::Auth, &format!("Gmail sign-in failed: {}", e)));
+
+                let credentials = SecureCredentials::new().unwrap_or_else(|e| {
+                    out.fail(ExitStatus::Failure, &format!("Failed to initialize credential storage: {}", e))
+                });
+                for (kind, secret) in [(gmail::CLIENT_SECRET_KIND, client_secret.trim()), (gmail::REFRESH_TOKEN_KIND, refresh_token.as_str())] {
+                    if let Err(e) = credentials.store_password(&account.email, kind, secret) {
+                        out.fail(ExitStatus::Failure, &format!("Failed to store the Gmail login securely: {}", e));
+                    }
+                }
+
+                if let Some(entry) = config.accounts.iter_mut().find(|a| a.email == account.email) {
+                    entry.gmail_api = Some(config::GmailApiConfig { client_id });
+                }
+                if let Err(e) = config.save(&config_path) {
+                    out.fail(ExitStatus::Config, &format!("Failed to save config: {}", e));
+                }
+
+                out.success(&format!("{} now syncs through the Gmail API", account.email));
                 return Ok(());
             }
             Commands::SetDefaultAccount { index } => {
                 if index >= config.accounts.len() {
-                    println!("Error: Account index out of bounds");
-                    return Ok(());
+                    out.fail(ExitStatus::NotFound, &format!("Account index {} not found. Use 'list-accounts' to see available accounts.", index));
                 }
                 
                 config.default_account = index;
                 
                 // Save config
                 if let Err(e) = config.save(&config_path) {
-                    println!("Failed to save config: {}", e);
-                    return Ok(());
+                    out.fail(ExitStatus::Config, &format!("Failed to save config: {}", e));
                 }
                 
-                println!("Default account set to: {} <{}>", 
+                out.success(&format!("Default account set to: {} <{}>", 
                     config.accounts[index].name, 
                     config.accounts[index].email
-                );
+                ));
+                return Ok(());
+            }
+            Commands::EncryptConfig { keyring } => {
+                if config.encryption.is_some() {
+                    out.fail(ExitStatus::Failure, "Config is already encrypted. Run decrypt-config first to change the key.");
+                }
+
+                let encryption = if keyring {
+                    crate::config_crypto::ConfigEncryption::with_keyring_key()
+                } else {
+                    let passphrase = crate::config_crypto::read_passphrase("New config passphrase: ")
+                        .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &e.to_string()));
+                    if passphrase.is_empty() {
+                        out.fail(ExitStatus::Failure, "Passphrase must not be empty");
+                    }
+                    let confirmation = crate::config_crypto::read_passphrase("Repeat passphrase: ")
+                        .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &e.to_string()));
+                    if passphrase != confirmation {
+                        out.fail(ExitStatus::Failure, "Passphrases do not match");
+                    }
+                    crate::config_crypto::ConfigEncryption::with_passphrase(&passphrase)
+                };
+                let encryption = encryption.unwrap_or_else(|e| out.fail(ExitStatus::Config, &e.to_string()));
+
+                config.encryption = Some(encryption);
+                if let Err(e) = config.save(&config_path) {
+                    out.fail(ExitStatus::Config, &format!("Failed to save config: {}", e));
+                }
+
+                out.success(&format!("Config encrypted: {}", config_path));
+                if !keyring && !out.is_json() {
+                    println!("  Set {} to skip the passphrase prompt.", crate::config_crypto::PASSPHRASE_ENV);
+                }
+                return Ok(());
+            }
+            Commands::Cache { command: CacheCommand::Gc { max_mb } } => {
+                let databases = cache::open_databases(&config);
+                let size = |bytes: u64| attachment_index::format_size(bytes as usize);
+                let Some(max_bytes) = max_mb.map(|mb| mb.saturating_mul(1024 * 1024)).or_else(|| config.cache.max_bytes()) else {
+                    let held = cache::usage(&databases)
+                        .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to read the cache: {}", e)));
+                    out.print(&serde_json::json!({ "before": held, "after": held }), |_| {
+                        println!("Attachments held locally: {}. No limit is set; pass --max-mb to shrink the cache.", size(held));
+                    });
+                    return Ok(());
+                };
+                let report = cache::collect(&databases, max_bytes)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to shrink the cache: {}", e)));
+                out.print(&report, |report| {
+                    println!("Attachments held locally: {} -> {} (limit {})", size(report.before), size(report.after), size(max_bytes));
+                    println!(
+                        "Dropped {} attachments, downloaded again when opened, and {} leftover files",
+                        report.evicted, report.stray_files
+                    );
+                    println!("Databases: {}", size(report.database_size));
+                });
+                return Ok(());
+            }
+            Commands::Contacts { command: ContactsCommand::Import { path, account } } => {
+                let path = shellexpand::tilde(&path).into_owned();
+                let text = std::fs::read(&path)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::NotFound, &format!("Failed to read {}: {}", path, e)));
+                let cards = vcard::parse(&String::from_utf8_lossy(&text));
+                if cards.is_empty() {
+                    out.fail(ExitStatus::Failure, &format!("{} holds no vCards", path));
+                }
+                let (account, client) = bundle_client(&config, account.as_deref(), &out);
+                let saved = client
+                    .import_contacts(&cards)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::from(&e), &format!("Import failed: {}", e)));
+                let without_address = cards.iter().filter(|card| card.emails.is_empty()).count();
+                let mut message = format!("Added {} addresses from {} cards to the address book of {}", saved, cards.len(), account.email);
+                if without_address > 0 {
+                    message.push_str(&format!("; {} cards without an email address were left out", without_address));
+                }
+                out.success(&message);
+                return Ok(());
+            }
+            Commands::Contacts { command: ContactsCommand::Export { output, account, all } } => {
+                let (account, client) = bundle_client(&config, account.as_deref(), &out);
+                let cards = client
+                    .contact_cards(all)
+                    .unwrap_or_else(|e| out.fail(ExitStatus::from(&e), &format!("Export failed: {}", e)));
+                let text = vcard::write(&cards);
+                match output {
+                    Some(output) => {
+                        let output = shellexpand::tilde(&output).into_owned();
+                        std::fs::write(&output, text)
+                            .unwrap_or_else(|e| out.fail(ExitStatus::Failure, &format!("Failed to write {}: {}", output, e)));
+                        out.success(&format!("Exported {} contacts of {} to {}", cards.len(), account.email, output));
+                    }
+                    None => out.print(&cards, |_| print!("{}", text)),
+                }
+                return Ok(());
+            }
+            Commands::ListProfiles => {
+                let profiles = profile::list_profiles();
+                let result = serde_json::json!({
+                    "default": profile::default_profile_exists(),
+                    "profiles": profiles,
+                });
+                out.print(&result, |_| {
+                    if profile::default_profile_exists() {
+                        println!("  (default)");
+                    }
+                    for name in &profiles {
+                        println!("  {}", name);
+                    }
+                    if profiles.is_empty() {
+                        println!("No named profiles. Create one with: tuimail --profile <name> add-account ...");
+                    }
+                });
+                return Ok(());
+            }
+            Commands::DecryptConfig => {
+                let Some(encryption) = config.encryption.take() else {
+                    out.success("Config is not encrypted.");
+                    return Ok(());
+                };
+
+                if let Err(e) = config.save(&config_path) {
+                    out.fail(ExitStatus::Config, &format!("Failed to save config: {}", e));
+                }
+                if let Err(e) = encryption.forget_key() {
+                    eprintln!("Warning: Failed to remove config key from keyring: {}", e);
+                }
+
+                out.success(&format!("Config decrypted: {}", config_path));
                 return Ok(());
             }
         }
     }
     
+    // Walk new users through adding their first account
+    if config.accounts.is_empty() && io::stdin().is_terminal() {
+        if let Some(account) = run_onboarding()? {
+            config.accounts.push(account);
+            config.default_account = 0;
+        }
+    }
+
     // Check if we have any accounts configured
     if config.accounts.is_empty() {
         println!("No email accounts configured. Please add an account first:");
@@ -338,6 +1237,10 @@ async fn main() -> Result<()> {
         println!("You may need to re-add your accounts with secure password storage.");
     }
     
+    if !args.no_network {
+        confirm_network_access(&mut config, &config_path)?;
+    }
+
     // Setup terminal
     enable_raw_mode().context("Failed to enable raw mode")?;
     io::stdout()
@@ -350,18 +1253,20 @@ async fn main() -> Result<()> {
     terminal.clear().context("Failed to clear terminal")?;
     
     // Create database
-    let cache_dir = dirs::cache_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("tuimail");
+    let cache_dir = profile::cache_dir();
     std::fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
-    let db_path = cache_dir.join("emails.db");
+    let db_path = profile::database_path();
     let database = std::sync::Arc::new(
         crate::database::EmailDatabase::new(&db_path)
             .context("Failed to initialize database")?
     );
     
+    i18n::init(config.ui.locale.as_deref());
+
     // Create app state
     let mut app = App::new(config, database.clone());
+    app.config_path = Some(config_path.clone());
+    app.pending_compose = compose;
     
     // Initialize sync tracker with database data (simplified approach)
     // The sync tracker will be populated as emails are fetched
@@ -399,6 +1304,194 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Cached unread counts of every configured account, or `None` if the database could
+/// not be read
+fn unread_counts(config: &Config, database: &EmailDatabase, folder: &str) -> Option<Vec<UnreadCount>> {
+    config.accounts.iter().map(|account| {
+        let unread = database.get_unread_count(&account.email, folder).ok()?;
+        Some(UnreadCount { name: account.name.clone(), email: account.email.clone(), unread })
+    }).collect()
+}
+
+/// Client for `export` and `import`: the account with the given address or index, or the default
+fn bundle_client(config: &Config, email: Option<&str>, out: &Output) -> (EmailAccount, EmailClient) {
+    let account = match email {
+        Some(account) => match account.parse::<usize>() {
+            Ok(index) => config.accounts.get(index),
+            Err(_) => config.accounts.iter().find(|a| a.email.eq_ignore_ascii_case(account)),
+        },
+        None => config.accounts.get(config.default_account),
+    };
+    let Some(account) = account.cloned() else {
+        out.fail(ExitStatus::NotFound, "Account not found. Use 'list-accounts' to see available accounts.");
+    };
+    let credentials = SecureCredentials::new().unwrap_or_else(|e| {
+        out.fail(ExitStatus::Failure, &format!("Failed to initialize credential storage: {}", e))
+    });
+    let client = EmailClient::new(account.clone(), credentials);
+    (account, client)
+}
+
+/// Check one account for `tuimail status`. Without credentials (`--offline`), or when
+/// the server cannot be reached, the unread count comes from the local cache.
+fn account_status(
+    account: &EmailAccount,
+    folder: &str,
+    credentials: Option<&SecureCredentials>,
+    database: Option<&EmailDatabase>,
+) -> AccountStatus {
+    let mut status = AccountStatus {
+        name: account.name.clone(),
+        email: account.email.clone(),
+        folder: folder.to_string(),
+        connected: false,
+        unread: None,
+        cached: false,
+        status: ExitStatus::Success,
+        error: None,
+    };
+
+    let result = match credentials {
+        Some(credentials) => EmailClient::new(account.clone(), credentials.clone()).unread_count(folder),
+        None => Err(EmailError::ConnectionError("offline".to_string())),
+    };
+    match result {
+        Ok(unread) => {
+            status.connected = true;
+            status.unread = Some(unread as usize);
+        }
+        Err(e) => {
+            if credentials.is_some() {
+                status.status = ExitStatus::from(&e);
+                // The server answered, it just could not report on the folder
+                status.connected = matches!(e, EmailError::ImapError(_) | EmailError::NotFound(_));
+                status.error = Some(e.to_string());
+            }
+            status.unread = database.and_then(|db| db.get_unread_count(&account.email, folder).ok());
+            status.cached = status.unread.is_some();
+        }
+    }
+    status
+}
+
+/// Show the first-run setup screens in the alternate screen
+fn run_onboarding() -> Result<Option<EmailAccount>> {
+    let credentials = SecureCredentials::new()
+        .context("Failed to initialize secure credential storage")?;
+
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    io::stdout()
+        .execute(EnterAlternateScreen)
+        .context("Failed to enter alternate screen")?;
+    let result = Terminal::new(CrosstermBackend::new(io::stdout()))
+        .and_then(|mut terminal| onboarding::run(&mut terminal, &credentials));
+
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    io::stdout()
+        .execute(LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    Ok(result?)
+}
+
+/// Ask which profile to use when several exist and none was given on the command line
+fn pick_profile() -> Result<Option<String>> {
+    let mut choices: Vec<Option<String>> = Vec::new();
+    if profile::default_profile_exists() {
+        choices.push(None);
+    }
+    choices.extend(profile::list_profiles().into_iter().map(Some));
+
+    if choices.len() <= 1 || !io::stdin().is_terminal() {
+        return Ok(choices.into_iter().next().flatten());
+    }
+
+    println!("Select a profile:");
+    for (i, choice) in choices.iter().enumerate() {
+        println!("  {}) {}", i + 1, choice.as_deref().unwrap_or("(default)"));
+    }
+
+    loop {
+        print!("Profile [1-{}]: ", choices.len());
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Err(anyhow::anyhow!("No profile selected"));
+        }
+        match line.trim().parse::<usize>() {
+            Ok(n) if (1..=choices.len()).contains(&n) => return Ok(choices[n - 1].clone()),
+            _ => println!("Please enter a number between 1 and {}", choices.len()),
+        }
+    }
+}
+
+/// Ask a yes or no question on the terminal; anything but yes is no
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N]: ", question);
+    let _ = io::stdout().flush();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).is_ok() && matches!(line.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Before the first connection, list the servers each account will connect to and ask
+/// whether that is fine. Accounts the user declines stay offline for the session; "always"
+/// is remembered in the config.
+fn confirm_network_access(config: &mut Config, config_path: &str) -> Result<()> {
+    let accounts: Vec<EmailAccount> = config
+        .accounts
+        .iter()
+        .filter(|account| !config.unapproved_servers(account).is_empty())
+        .cloned()
+        .collect();
+    if accounts.is_empty() {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        // Nobody to ask; connecting silently is what the prompt is there to prevent
+        for account in &accounts {
+            crate::email::set_account_offline(&account.email);
+        }
+        return Ok(());
+    }
+
+    let mut remembered = false;
+    for account in &accounts {
+        println!("{} <{}> will connect to:", account.name, account.email);
+        println!("  IMAP {}", account.servers()[0]);
+        println!("  SMTP {}", account.servers()[1]);
+        loop {
+            print!("Connect? [y]es / [a]lways / [n]o, stay offline: ");
+            io::stdout().flush()?;
+
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line)? == 0 {
+                crate::email::set_account_offline(&account.email);
+                break;
+            }
+            match line.trim().to_lowercase().as_str() {
+                "y" | "yes" => break,
+                "a" | "always" => {
+                    config.approve_servers(account);
+                    remembered = true;
+                    break;
+                }
+                "n" | "no" => {
+                    crate::email::set_account_offline(&account.email);
+                    break;
+                }
+                _ => println!("Please answer y, a or n"),
+            }
+        }
+    }
+
+    if remembered {
+        if let Err(e) = config.save(config_path) {
+            println!("Failed to save config: {}", e);
+        }
+    }
+    Ok(())
+}
+
 /// Migrate passwords from old config format to secure storage
 fn migrate_passwords_if_needed(config: &mut Config, config_path: &str) -> Result<()> {
     // Check if any account has passwords in the config (old format)
@@ -506,25 +1599,7 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> AppRe
     loop {
         // Poll database for changes periodically
         if last_db_poll.elapsed() >= DB_POLL_INTERVAL {
-            // Check for new emails from background fetcher (legacy)
-            app.check_for_new_emails();
-            
-            if let Err(e) = app.refresh_emails_from_database() {
-                // Log error but don't fail the UI
-                if std::env::var("EMAIL_DEBUG").is_ok() {
-                    let log_file = "/tmp/tuimail_debug.log";
-                    if let Ok(mut file) = std::fs::OpenOptions::new()
-                        .create(true)
-                        .write(true)
-                        .append(true)
-                        .open(log_file) 
-                    {
-                        use std::io::Write;
-                        let _ = writeln!(file, "[{}] Database poll error: {}", 
-                            Local::now().format("%Y-%m-%d %H:%M:%S"), e);
-                    }
-                }
-            }
+            app.post(AppEvent::PollDatabase);
             last_db_poll = std::time::Instant::now();
         }
         
@@ -543,31 +1618,11 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> AppRe
         // Reset consecutive error counter on successful draw
         consecutive_errors = 0;
         
-        // Handle events
+        // Queue terminal input, then the periodic tick
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
-                    // Handle input with error recovery
-                    if let Err(e) = app.handle_key_event(key) {
-                        app.show_error(&format!("Error: {}", e));
-                        consecutive_errors += 1;
-                        
-                        // If we have too many consecutive errors, exit
-                        if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
-                            return Err(e);
-                        }
-                    } else {
-                        // Reset error counter on successful operation
-                        consecutive_errors = 0;
-                    }
-                    
-                    // Check if we should exit
-                    if app.should_quit {
-                        // Cleanup is already called in the quit handler
-                        // Stop legacy background email fetching before exiting
-                        app.stop_background_email_fetching();
-                        return Ok(());
-                    }
+                    app.post(AppEvent::Key(key));
                 }
             }
         }
@@ -575,19 +1630,31 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> AppRe
         // Process any pending grammar check responses
         app.process_grammar_responses().await;
         
-        // Update app state with error handling
-        if let Err(e) = app.tick() {
-            app.show_error(&format!("Update error: {}", e));
+        app.post(AppEvent::Tick);
+        
+        // Handle everything queued since the last frame, with error recovery
+        if let Err(e) = app.process_events() {
+            // A refused login is already explained by the sign-in help dialog
+            if app.mode != AppMode::AuthHelp {
+                app.show_error(&crate::i18n::tr_args("msg-error", &[("error", &e)]));
+            }
             consecutive_errors += 1;
             
+            // If we have too many consecutive errors, exit
             if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
                 return Err(e);
             }
         } else {
-            // Reset error counter on successful tick
-            if consecutive_errors > 0 {
-                consecutive_errors = 0;
-            }
+            // Reset error counter on successful operation
+            consecutive_errors = 0;
+        }
+        
+        // Check if we should exit
+        if app.should_quit {
+            // Cleanup is already called in the quit handler
+            // Stop legacy background email fetching before exiting
+            app.stop_background_email_fetching();
+            return Ok(());
         }
     }
 }
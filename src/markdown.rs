@@ -0,0 +1,183 @@
+/// Render the Markdown most people type in mail to HTML: paragraphs (keeping line
+/// breaks), headings, lists, quotes, fenced code, `code`, **bold**, *emphasis* and
+/// [links](https://example.com). Anything else is shown as typed, HTML-escaped.
+pub fn to_html(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut html = String::new();
+    render_blocks(&lines, &mut html);
+    html
+}
+
+fn render_blocks(lines: &[&str], html: &mut String) {
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+
+        if trimmed.is_empty() {
+            i += 1;
+        } else if trimmed.starts_with("```") {
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with("```"))
+                .map(|p| i + 1 + p)
+                .unwrap_or(lines.len());
+            html.push_str("<pre><code>");
+            html.push_str(&escape(&lines[i + 1..end].join("\n")));
+            html.push_str("</code></pre>\n");
+            i = end + 1;
+        } else if let Some((level, text)) = heading(trimmed) {
+            html.push_str(&format!("<h{level}>{}</h{level}>\n", inline(text)));
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let end = block_end(lines, i, |l| l.trim_start().starts_with('>'));
+            let inner: Vec<&str> = lines[i..end]
+                .iter()
+                .map(|l| {
+                    let l = &l.trim_start()[1..];
+                    l.strip_prefix(' ').unwrap_or(l)
+                })
+                .collect();
+            html.push_str("<blockquote>\n");
+            render_blocks(&inner, html);
+            html.push_str("</blockquote>\n");
+            i = end;
+        } else if list_item(trimmed).is_some() {
+            let ordered = list_item(trimmed).is_some_and(|(ordered, _)| ordered);
+            let end = block_end(lines, i, |l| list_item(l.trim_start()).is_some_and(|(o, _)| o == ordered));
+            let tag = if ordered { "ol" } else { "ul" };
+            html.push_str(&format!("<{}>\n", tag));
+            for item in &lines[i..end] {
+                if let Some((_, text)) = list_item(item.trim_start()) {
+                    html.push_str(&format!("<li>{}</li>\n", inline(text)));
+                }
+            }
+            html.push_str(&format!("</{}>\n", tag));
+            i = end;
+        } else {
+            let end = block_end(lines, i, |l| {
+                let t = l.trim_start();
+                !t.is_empty() && !t.starts_with("```") && !t.starts_with('>') && heading(t).is_none() && list_item(t).is_none()
+            });
+            let text: Vec<String> = lines[i..end].iter().map(|l| inline(l.trim())).collect();
+            html.push_str(&format!("<p>{}</p>\n", text.join("<br>\n")));
+            i = end;
+        }
+    }
+}
+
+/// Index of the first line from `start` on that does not continue the block
+fn block_end(lines: &[&str], start: usize, continues: impl Fn(&str) -> bool) -> usize {
+    lines[start..]
+        .iter()
+        .position(|l| !continues(l))
+        .map(|p| start + p)
+        .unwrap_or(lines.len())
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some((level, line[level..].trim()))
+    } else {
+        None
+    }
+}
+
+/// Whether a line is a list item, and whether the list is ordered
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(bullet) {
+            return Some((false, text));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        if let Some(text) = line[digits..].strip_prefix(". ") {
+            return Some((true, text));
+        }
+    }
+    None
+}
+
+fn inline(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    let mut prev: Option<char> = None;
+    while let Some(c) = rest.chars().next() {
+        if let Some((rendered, used)) = inline_span(rest, prev) {
+            html.push_str(&rendered);
+            prev = rest[..used].chars().last();
+            rest = &rest[used..];
+            continue;
+        }
+        html.push_str(&escape(&c.to_string()));
+        prev = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    html
+}
+
+/// A span starting at the beginning of `text`, rendered, with the bytes it used
+fn inline_span(text: &str, prev: Option<char>) -> Option<(String, usize)> {
+    if let Some(body) = text.strip_prefix('`') {
+        let end = body.find('`')?;
+        return Some((format!("<code>{}</code>", escape(&body[..end])), end + 2));
+    }
+    if let Some(body) = text.strip_prefix("**") {
+        let end = body.find("**").filter(|end| *end > 0)?;
+        return Some((format!("<strong>{}</strong>", inline(&body[..end])), end + 4));
+    }
+    if let Some(body) = text.strip_prefix('[') {
+        let close = body.find("](")?;
+        let end = body[close + 2..].find(')')?;
+        let url = &body[close + 2..close + 2 + end];
+        if !["https://", "http://", "mailto:"].iter().any(|scheme| url.starts_with(scheme)) {
+            return None;
+        }
+        let link = format!("<a href=\"{}\">{}</a>", escape(url), inline(&body[..close]));
+        return Some((link, close + end + 4));
+    }
+
+    // Emphasis never starts inside a word, so snake_case and 2*3*4 stay as typed
+    let marker = text.chars().next().filter(|c| *c == '*' || *c == '_')?;
+    if prev.is_some_and(|p| p.is_alphanumeric()) {
+        return None;
+    }
+    let body = &text[1..];
+    let end = body.find(marker).filter(|end| *end > 0)?;
+    let inner = &body[..end];
+    if inner.starts_with(' ') || inner.ends_with(' ') {
+        return None;
+    }
+    Some((format!("<em>{}</em>", inline(inner)), end + 2))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_to_html() {
+        let html = to_html("# Plan\n\nHi **team**,\nsee `cargo test` and *notes*.\n\n- one\n- two\n\n> quoted <b>\n\n```\nfn main() {}\n```\n");
+        assert_eq!(
+            html,
+            "<h1>Plan</h1>\n<p>Hi <strong>team</strong>,<br>\nsee <code>cargo test</code> and <em>notes</em>.</p>\n\
+             <ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<blockquote>\n<p>quoted &lt;b&gt;</p>\n</blockquote>\n\
+             <pre><code>fn main() {}</code></pre>\n"
+        );
+        assert_eq!(to_html("my_var_name and 2*3*4"), "<p>my_var_name and 2*3*4</p>\n");
+        assert_eq!(
+            to_html("[docs](https://example.com/?a=1&b=2) [x](javascript:alert(1))"),
+            "<p><a href=\"https://example.com/?a=1&amp;b=2\">docs</a> [x](javascript:alert(1))</p>\n"
+        );
+        assert_eq!(to_html("1. first\n2. second"), "<ol>\n<li>first</li>\n<li>second</li>\n</ol>\n");
+    }
+}
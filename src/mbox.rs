@@ -0,0 +1,324 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::database::EmailDatabase;
+use crate::email::{ensure_writable, Email, EmailError};
+use crate::transport::ImapBackend;
+
+/// mutt's `X-Status` letters and the IMAP flags they stand for
+const X_STATUS_LETTERS: &[(char, &str)] = &[
+    ('A', "\\Answered"),
+    ('F', "\\Flagged"),
+    ('T', "\\Draft"),
+    ('D', "\\Deleted"),
+];
+
+/// Thunderbird's `X-Mozilla-Status` bits and the IMAP flags they stand for
+const MOZILLA_STATUS_BITS: &[(u32, &str)] = &[
+    (0x0001, "\\Seen"),
+    (0x0002, "\\Answered"),
+    (0x0004, "\\Flagged"),
+    (0x0008, "\\Deleted"),
+];
+
+/// Headers that describe a message's state in one mbox file and are not carried over
+const STATE_HEADERS: &[&str] = &["status", "x-status", "x-mozilla-status", "x-mozilla-status2"];
+
+/// A message read from an mbox file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MboxMessage {
+    /// The message with CRLF line endings and the mbox state headers removed
+    pub raw: Vec<u8>,
+    pub flags: Vec<String>,
+}
+
+/// Whether a line is a `From ` line, unescaped or escaped with `>` (mboxrd)
+fn is_from_line(line: &[u8]) -> bool {
+    let start = line.iter().take_while(|b| **b == b'>').count();
+    line[start..].starts_with(b"From ")
+}
+
+/// Append one message to an mbox file in the mboxrd format used by mutt and Thunderbird:
+/// a `From ` line, the message with `From ` lines in the body quoted with `>`, the flags as
+/// `Status` and `X-Status` headers, and a blank line.
+pub fn write_message(out: &mut impl Write, raw: &[u8], sender: &str, date: DateTime<Utc>, flags: &[String]) -> std::io::Result<()> {
+    let sender = if sender.is_empty() { "MAILER-DAEMON" } else { sender };
+    writeln!(out, "From {} {}", sender, date.format("%a %b %e %H:%M:%S %Y"))?;
+
+    let has = |flag: &str| flags.iter().any(|f| f.eq_ignore_ascii_case(flag));
+    let x_status: String = X_STATUS_LETTERS.iter().filter(|(_, flag)| has(flag)).map(|(letter, _)| *letter).collect();
+
+    let mut in_headers = true;
+    for line in raw.split_inclusive(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if in_headers && line.is_empty() {
+            in_headers = false;
+            writeln!(out, "Status: {}", if has("\\Seen") { "RO" } else { "O" })?;
+            if !x_status.is_empty() {
+                writeln!(out, "X-Status: {}", x_status)?;
+            }
+        } else if !in_headers && is_from_line(line) {
+            out.write_all(b">")?;
+        }
+        out.write_all(line)?;
+        out.write_all(b"\n")?;
+    }
+    if in_headers {
+        // No body: the state headers still end the header section
+        writeln!(out, "Status: {}", if has("\\Seen") { "RO" } else { "O" })?;
+        out.write_all(b"\n")?;
+    }
+    out.write_all(b"\n")
+}
+
+/// Which cached messages of a folder `export` writes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportFilter<'a> {
+    /// Only these UIDs; every message if `None`
+    pub uids: Option<&'a [u32]>,
+    /// Only messages dated at or after this Unix timestamp
+    pub after: Option<i64>,
+    /// Only messages dated before this Unix timestamp
+    pub before: Option<i64>,
+}
+
+impl ExportFilter<'_> {
+    /// Whether the UID is wanted; the dates are left to the database query
+    fn wants(&self, email: &Email) -> bool {
+        email.id.parse::<u32>().is_ok_and(|uid| self.uids.is_none_or(|uids| uids.contains(&uid)))
+    }
+}
+
+/// Messages fetched from the server at a time by `export`
+const FETCH_BATCH: usize = 100;
+
+/// What `export` wrote
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Exported {
+    pub written: usize,
+    /// UIDs of cached messages the server no longer has, which are left out
+    pub missing: Vec<u32>,
+}
+
+/// Write the messages of a folder that `filter` lets through as an mbox file, oldest
+/// first. The cache picks the messages; each one is fetched from the server as it was
+/// received, so all its headers, its MIME structure and its attachments are kept.
+pub fn export(
+    imap: &mut dyn ImapBackend,
+    db: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    filter: &ExportFilter,
+    out: &mut impl Write,
+) -> Result<Exported> {
+    let mut wanted = Vec::new();
+    db.for_each_email_in_folder(account_email, folder, filter.after, filter.before, |email: Email| {
+        if filter.wants(&email) {
+            let sender = email.from.first().map(|from| from.address.clone()).unwrap_or_default();
+            wanted.push((email.id.parse::<u32>()?, sender, email.date.with_timezone(&Utc)));
+        }
+        Ok(())
+    })?;
+
+    let mut exported = Exported::default();
+    for batch in wanted.chunks(FETCH_BATCH) {
+        let uids: Vec<u32> = batch.iter().map(|(uid, _, _)| *uid).collect();
+        let mut fetched: std::collections::HashMap<u32, _> =
+            imap.fetch(folder, &uids)?.into_iter().map(|message| (message.uid, message)).collect();
+        for (uid, sender, date) in batch {
+            match fetched.remove(uid) {
+                Some(message) => {
+                    write_message(out, &message.raw, sender, *date, &message.flags)?;
+                    exported.written += 1;
+                }
+                None => exported.missing.push(*uid),
+            }
+        }
+    }
+    out.flush()?;
+    Ok(exported)
+}
+
+/// Reads the messages of an mbox file one at a time. `From ` lines start a message at the
+/// beginning of the file or after a blank line; `>From ` lines are unquoted (mboxrd), and
+/// flags are taken from the `Status`, `X-Status` and `X-Mozilla-Status` headers.
+pub struct MboxReader<R> {
+    input: R,
+    /// The `From ` line that ended the previous message was already read
+    started: bool,
+}
+
+impl<R: BufRead> MboxReader<R> {
+    pub fn new(input: R) -> Self {
+        Self { input, started: false }
+    }
+
+    fn read_line(&mut self, line: &mut Vec<u8>) -> std::io::Result<bool> {
+        line.clear();
+        Ok(self.input.read_until(b'\n', line)? > 0)
+    }
+
+    fn next_message(&mut self) -> std::io::Result<Option<MboxMessage>> {
+        let mut line = Vec::new();
+        if !self.started {
+            // Anything before the first `From ` line is not part of a message
+            loop {
+                if !self.read_line(&mut line)? {
+                    return Ok(None);
+                }
+                if line.starts_with(b"From ") {
+                    break;
+                }
+            }
+            self.started = true;
+        }
+
+        let mut raw = Vec::new();
+        let mut flags = Vec::new();
+        let mut in_headers = true;
+        let mut skipping_header = false;
+        let mut blank_before = false;
+        let mut more = false;
+        let mut lines = 0;
+        while self.read_line(&mut line)? {
+            let text = line.strip_suffix(b"\n").unwrap_or(&line);
+            let text = text.strip_suffix(b"\r").unwrap_or(text).to_vec();
+            if blank_before && text.starts_with(b"From ") {
+                more = true;
+                break;
+            }
+            if blank_before {
+                // The blank line was part of the body after all
+                raw.extend_from_slice(b"\r\n");
+            }
+            blank_before = !in_headers && text.is_empty();
+            lines += 1;
+            if blank_before {
+                continue;
+            }
+
+            if in_headers {
+                if text.is_empty() {
+                    in_headers = false;
+                } else if text.starts_with(b" ") || text.starts_with(b"\t") {
+                    if skipping_header {
+                        continue;
+                    }
+                } else {
+                    let header = String::from_utf8_lossy(&text).into_owned();
+                    let (name, value) = header.split_once(':').unwrap_or((&header, ""));
+                    skipping_header = STATE_HEADERS.contains(&name.trim().to_lowercase().as_str());
+                    if skipping_header {
+                        state_flags(name.trim(), value.trim(), &mut flags);
+                        continue;
+                    }
+                }
+                raw.extend_from_slice(&text);
+            } else if text.starts_with(b">") && is_from_line(&text) {
+                raw.extend_from_slice(&text[1..]);
+            } else {
+                raw.extend_from_slice(&text);
+            }
+            raw.extend_from_slice(b"\r\n");
+        }
+        self.started = more;
+
+        if lines == 0 && !more {
+            return Ok(None);
+        }
+        flags.sort();
+        flags.dedup();
+        Ok(Some(MboxMessage { raw, flags }))
+    }
+}
+
+impl<R: BufRead> Iterator for MboxReader<R> {
+    type Item = std::io::Result<MboxMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message().transpose()
+    }
+}
+
+/// IMAP flags a state header of an mbox file stands for
+fn state_flags(name: &str, value: &str, flags: &mut Vec<String>) {
+    match name.to_lowercase().as_str() {
+        "status" if value.contains('R') => flags.push("\\Seen".to_string()),
+        "x-status" => {
+            for (letter, flag) in X_STATUS_LETTERS {
+                if value.contains(*letter) {
+                    flags.push(flag.to_string());
+                }
+            }
+        }
+        "x-mozilla-status" => {
+            let bits = u32::from_str_radix(value, 16).unwrap_or(0);
+            for (bit, flag) in MOZILLA_STATUS_BITS {
+                if bits & bit != 0 {
+                    flags.push(flag.to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Append the messages of an mbox file to `folder` on the server with their flags,
+/// reading the file one message at a time. Messages marked deleted are left out. Returns
+/// how many messages were imported; they appear at the next sync.
+pub fn import(input: impl BufRead, imap: &mut dyn ImapBackend, folder: &str) -> Result<usize, EmailError> {
+    ensure_writable("import messages")?;
+
+    let mut imported = 0;
+    for message in MboxReader::new(input) {
+        let message = message?;
+        if message.flags.iter().any(|flag| flag == "\\Deleted") {
+            continue;
+        }
+        imap.append(folder, &message.raw, &message.flags)?;
+        imported += 1;
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read_back() {
+        let raw = b"From: alice@example.com\r\nSubject: Hi\r\n\r\nFrom now on\r\n>From the top\r\n\r\nFrom here\r\n";
+        let date = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut mbox = Vec::new();
+        write_message(&mut mbox, raw, "alice@example.com", date, &["\\Seen".to_string(), "\\Flagged".to_string()]).unwrap();
+        write_message(&mut mbox, b"Subject: Second\r\n\r\nBody\r\n", "", date, &[]).unwrap();
+
+        let text = String::from_utf8(mbox.clone()).unwrap();
+        assert!(text.starts_with("From alice@example.com Tue Nov 14 22:13:20 2023\n"));
+        assert!(text.contains("Status: RO\nX-Status: F\n\n>From now on\n>>From the top\n\n>From here\n\n"));
+        assert!(text.contains("From MAILER-DAEMON "));
+
+        let messages: Vec<MboxMessage> = MboxReader::new(mbox.as_slice()).collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].raw, raw.to_vec());
+        assert_eq!(messages[0].flags, vec!["\\Flagged", "\\Seen"]);
+        assert_eq!(messages[1].raw, b"Subject: Second\r\n\r\nBody\r\n".to_vec());
+        assert!(messages[1].flags.is_empty());
+    }
+
+    #[test]
+    fn test_read_thunderbird_mbox() {
+        let mbox = "From - Mon Mar  4 10:00:00 2024\nX-Mozilla-Status: 0005\nX-Mozilla-Status2: 00000000\nSubject: A\n\nOne\n\n\
+                    From - Mon Mar  4 11:00:00 2024\nX-Mozilla-Status: 0009\nSubject: B\n\nTwo\nFrom inside a paragraph\n";
+        let messages: Vec<MboxMessage> = MboxReader::new(mbox.as_bytes()).collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].raw, b"Subject: A\r\n\r\nOne\r\n".to_vec());
+        assert_eq!(messages[0].flags, vec!["\\Flagged", "\\Seen"]);
+        assert_eq!(messages[1].flags, vec!["\\Deleted", "\\Seen"]);
+        assert_eq!(messages[1].raw, b"Subject: B\r\n\r\nTwo\r\nFrom inside a paragraph\r\n".to_vec());
+        assert_eq!(MboxReader::new(&b""[..]).count(), 0);
+    }
+}
@@ -0,0 +1,144 @@
+use mail_parser::{Message, MessagePart, MimeHeaders, PartType};
+
+use crate::email::{debug_log, EmailAttachment};
+
+/// Longest filename kept from a message; longer names are cut at a character boundary
+const MAX_FILENAME_LEN: usize = 255;
+
+/// Parse a raw RFC 822 message. mail-parser panics on some malformed headers (for
+/// example `Content-Type: ;`), so a panic is treated like any other unparseable message
+/// instead of taking down the sync thread.
+pub fn parse_message(raw: &[u8]) -> Option<Message<'_>> {
+    match std::panic::catch_unwind(|| Message::parse(raw)) {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            debug_log(&format!("mail-parser panicked on a {} byte message", raw.len()));
+            None
+        }
+    }
+}
+
+/// Extract the attachment carried by a single MIME part, if it is one.
+///
+/// A part is an attachment when its Content-Disposition says so, when it names a file,
+/// or when it is a non-text leaf (binary data or a forwarded message). Text and HTML
+/// parts without a filename are message bodies, and multipart containers never carry
+/// data themselves. Parts with an empty payload are skipped.
+pub fn attachment_from_part(part: &MessagePart) -> Option<EmailAttachment> {
+    let content_type = part
+        .content_type()
+        .map(|ct| match ct.subtype() {
+            Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+            None => ct.ctype().to_string(),
+        })
+        .map(|ct| ct.to_ascii_lowercase())
+        .unwrap_or_else(|| match part.body {
+            PartType::Text(_) => "text/plain".to_string(),
+            PartType::Html(_) => "text/html".to_string(),
+            PartType::Message(_) => "message/rfc822".to_string(),
+            _ => "application/octet-stream".to_string(),
+        });
+
+    let disposition_attachment = part.content_disposition().is_some_and(|cd| cd.is_attachment());
+    let filename = part
+        .attachment_name()
+        .or_else(|| part.content_disposition().and_then(|cd| cd.attribute("name")))
+        .or_else(|| part.content_type().and_then(|ct| ct.attribute("filename")))
+        .map(sanitize_filename)
+        .filter(|name| !name.is_empty());
+
+    let is_attachment = match part.body {
+        PartType::Multipart(_) => false,
+        // Headers returned with a bounce are kept for matching it to the sent message
+        PartType::Text(_) if content_type == "text/rfc822-headers" => true,
+        // Invitations, so they can be read and answered
+        PartType::Text(_) if content_type == "text/calendar" => true,
+        PartType::Text(_) | PartType::Html(_) => disposition_attachment || filename.is_some(),
+        PartType::Binary(_) | PartType::InlineBinary(_) | PartType::Message(_) => true,
+    };
+    if !is_attachment {
+        return None;
+    }
+
+    // For a forwarded message, contents() is the whole message enclosing it
+    let data = match &part.body {
+        PartType::Message(message) => message.raw_message().to_vec(),
+        _ => part.contents().to_vec(),
+    };
+    if data.is_empty() {
+        return None;
+    }
+
+    Some(EmailAttachment {
+        filename: filename.unwrap_or_else(|| fallback_filename(&content_type)),
+        content_type,
+        size: data.len(),
+        data,
+        path: None,
+    })
+}
+
+/// Make a filename from a message safe to show and to save: drop any directory part,
+/// control characters and leading dots, and bound its length. May return an empty
+/// string when nothing usable is left.
+pub fn sanitize_filename(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base.chars().filter(|c| !c.is_control()).collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim();
+
+    let mut end = cleaned.len().min(MAX_FILENAME_LEN);
+    while !cleaned.is_char_boundary(end) {
+        end -= 1;
+    }
+    cleaned[..end].trim_end().to_string()
+}
+
+/// Name for an attachment whose headers carry none
+pub fn fallback_filename(content_type: &str) -> String {
+    match content_type {
+        "application/pdf" => "document.pdf".to_string(),
+        "image/jpeg" => "image.jpg".to_string(),
+        "image/png" => "image.png".to_string(),
+        "image/gif" => "image.gif".to_string(),
+        "application/zip" => "archive.zip".to_string(),
+        "text/calendar" => "invite.ics".to_string(),
+        "application/msword" => "document.doc".to_string(),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "document.docx".to_string(),
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "spreadsheet.xlsx".to_string(),
+        "application/vnd.ms-excel" => "spreadsheet.xls".to_string(),
+        "application/ms-tnef" | "application/vnd.ms-tnef" => "winmail.dat".to_string(),
+        "message/rfc822" => "message.eml".to_string(),
+        "text/rfc822-headers" => "headers.txt".to_string(),
+        "text/plain" => "text.txt".to_string(),
+        "text/csv" => "data.csv".to_string(),
+        _ => {
+            let extension: String = content_type
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect();
+            if extension.is_empty() {
+                "attachment.bin".to_string()
+            } else {
+                format!("attachment.{}", extension)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("report.pdf"), "report.pdf");
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("C:\\Users\\me\\cv.docx"), "cv.docx");
+        assert_eq!(sanitize_filename(" .hidden\u{0}name "), "hiddenname");
+        assert_eq!(sanitize_filename(".."), "");
+        assert_eq!(sanitize_filename(&"é".repeat(200)).len(), 254);
+    }
+}
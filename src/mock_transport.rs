@@ -0,0 +1,563 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use lettre::Message;
+
+use crate::config::SpecialFolders;
+use crate::email::EmailError;
+use crate::transport::{FetchedMessage, FolderChanges, ImapBackend, SmtpBackend};
+
+#[derive(Debug, Clone)]
+struct MockMessage {
+    flags: Vec<String>,
+    raw: Vec<u8>,
+    /// Mod-sequence of the last change
+    modseq: u64,
+}
+
+#[derive(Default)]
+struct MockState {
+    folders: HashMap<String, BTreeMap<u32, MockMessage>>,
+    next_uid: u32,
+    /// Mod-sequence of the latest change to any message
+    modseq: u64,
+    /// Folder, UID and mod-sequence of each message removed
+    vanished: Vec<(String, u32, u64)>,
+    /// Whether changes are reported as a QRESYNC server would
+    qresync: bool,
+    /// Whether the server sorts and threads folders (SORT and THREAD=REFERENCES)
+    sort_and_thread: bool,
+    /// Whether moves go by copy and expunge, as on a server without MOVE or UIDPLUS
+    without_move: bool,
+    /// Special-use attributes such as `\\Trash`, by folder
+    special_use: Vec<(String, String)>,
+    /// Gmail ids by folder and UID, as a Gmail server reports them
+    gmail_ids: HashMap<(String, u32), String>,
+    sent: Vec<Vec<u8>>,
+    /// Calls to let through before failing with the error
+    fail_next: Option<(usize, String)>,
+}
+
+/// In-memory IMAP and SMTP server for tests and offline development. Clones share the
+/// same mailbox, so a test can hand one clone to the code under test and inspect another.
+#[derive(Clone, Default)]
+pub struct MockMailServer {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockMailServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put a raw RFC 822 message into a folder, returning its UID
+    pub fn deliver(&self, folder: &str, raw: &[u8], flags: &[&str]) -> u32 {
+        let mut state = self.lock();
+        state.next_uid += 1;
+        state.modseq += 1;
+        let (uid, modseq) = (state.next_uid, state.modseq);
+        state.folders.entry(folder.to_string()).or_default().insert(
+            uid,
+            MockMessage {
+                flags: flags.iter().map(|f| f.to_string()).collect(),
+                raw: raw.to_vec(),
+                modseq,
+            },
+        );
+        uid
+    }
+
+    /// Report flag changes and expunges since a mod-sequence, as a server with
+    /// CONDSTORE and QRESYNC does
+    pub fn enable_qresync(&self) {
+        self.lock().qresync = true;
+    }
+
+    /// Give a message the Gmail id a Gmail server would report for it; copies of one
+    /// message under several labels share their id
+    pub fn set_gmail_id(&self, folder: &str, uid: u32, id: &str) {
+        self.lock().gmail_ids.insert((folder.to_string(), uid), id.to_string());
+    }
+
+    /// Sort and thread folders on request, as a server with SORT and THREAD=REFERENCES does
+    pub fn enable_sort_and_thread(&self) {
+        self.lock().sort_and_thread = true;
+    }
+
+    /// Move messages by copying and expunging, as a server without MOVE or UIDPLUS does
+    pub fn disable_move(&self) {
+        self.lock().without_move = true;
+    }
+
+    /// Mark a folder for a special use, as in `mark_special_use("Bin", "\\Trash")`
+    pub fn mark_special_use(&self, folder: &str, attribute: &str) {
+        self.create_folder(folder);
+        self.lock().special_use.push((folder.to_string(), attribute.to_string()));
+    }
+
+    pub fn create_folder(&self, folder: &str) {
+        self.lock().folders.entry(folder.to_string()).or_default();
+    }
+
+    /// Flags of a message as the server sees them
+    pub fn message_flags(&self, folder: &str, uid: u32) -> Option<Vec<String>> {
+        self.lock()
+            .folders
+            .get(folder)
+            .and_then(|messages| messages.get(&uid))
+            .map(|message| message.flags.clone())
+    }
+
+    /// Change flags behind the client's back, as another mail client would
+    pub fn set_message_flags(&self, folder: &str, uid: u32, flags: &[&str]) {
+        let mut state = self.lock();
+        let modseq = state.modseq + 1;
+        if let Some(message) = state.folders.get_mut(folder).and_then(|messages| messages.get_mut(&uid)) {
+            message.flags = flags.iter().map(|f| f.to_string()).collect();
+            message.modseq = modseq;
+            state.modseq = modseq;
+        }
+    }
+
+    pub fn contains(&self, folder: &str, uid: u32) -> bool {
+        self.lock().folders.get(folder).is_some_and(|messages| messages.contains_key(&uid))
+    }
+
+    /// Raw messages accepted over SMTP, in order
+    pub fn sent(&self) -> Vec<Vec<u8>> {
+        self.lock().sent.clone()
+    }
+
+    /// Make the next IMAP or SMTP call fail with a connection error
+    pub fn fail_next(&self, error: &str) {
+        self.fail_after(0, error);
+    }
+
+    /// Let `calls` IMAP or SMTP calls succeed, then fail the next one, as a connection
+    /// dropping part way through would
+    pub fn fail_after(&self, calls: usize, error: &str) {
+        self.lock().fail_next = Some((calls, error.to_string()));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        // A panicking test must not poison the server for the others
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn check_failure(state: &mut MockState) -> Result<(), EmailError> {
+        match state.fail_next.take() {
+            Some((0, error)) => Err(EmailError::ConnectionError(error)),
+            Some((calls, error)) => {
+                state.fail_next = Some((calls - 1, error));
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn with_folder<R>(&self, folder: &str, f: impl FnOnce(&mut BTreeMap<u32, MockMessage>) -> R) -> Result<R, EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        let messages = state
+            .folders
+            .get_mut(folder)
+            .ok_or_else(|| EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)))?;
+        Ok(f(messages))
+    }
+
+    /// Take a message out of a folder, noting it as vanished
+    fn remove(state: &mut MockState, folder: &str, uid: u32) -> Option<MockMessage> {
+        let message = state.folders.get_mut(folder)?.remove(&uid)?;
+        state.modseq += 1;
+        let modseq = state.modseq;
+        state.vanished.push((folder.to_string(), uid, modseq));
+        Some(message)
+    }
+}
+
+/// The messages of a folder parsed, in UID order
+fn parsed(server: &MockMailServer, folder: &str) -> Result<Vec<(u32, crate::email::Email)>, EmailError> {
+    server.with_folder(folder, |messages| {
+        messages
+            .iter()
+            .filter_map(|(uid, message)| {
+                let parsed = crate::mime::parse_message(&message.raw)?;
+                let email = crate::email::Email::from_parsed_email(&parsed, &uid.to_string(), folder, message.flags.clone()).ok()?;
+                Some((*uid, email))
+            })
+            .collect()
+    })
+}
+
+/// Split IMAP search criteria into atoms and the contents of quoted strings
+fn search_terms(criteria: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut chars = criteria.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' => {}
+            '"' => {
+                let mut term = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => term.extend(chars.next()),
+                        '"' => break,
+                        c => term.push(c),
+                    }
+                }
+                terms.push(term);
+            }
+            c => {
+                let mut term = c.to_string();
+                while let Some(c) = chars.next_if(|c| *c != ' ') {
+                    term.push(c);
+                }
+                terms.push(term);
+            }
+        }
+    }
+    terms
+}
+
+/// Whether a message matches the search key at the front of `terms`, which it takes.
+/// Covers the keys `SearchQuery::to_imap` uses, with substring matching.
+fn matches_key(message: &MockMessage, terms: &mut std::collections::VecDeque<String>) -> Result<bool, EmailError> {
+    let key = terms.pop_front().unwrap_or_default().to_ascii_uppercase();
+    let mut argument = || terms.pop_front().ok_or_else(|| EmailError::ImapError(format!("{} needs an argument", key)));
+    let raw = String::from_utf8_lossy(&message.raw).to_lowercase();
+    let (head, body) = raw.split_once("\r\n\r\n").unwrap_or((&raw, ""));
+    let header = |name: &str, text: &str| {
+        head.lines().any(|line| line.strip_prefix(&format!("{}:", name.to_lowercase())).is_some_and(|value| value.contains(&text.to_lowercase())))
+    };
+    let date = || {
+        mail_parser::Message::parse(&message.raw).and_then(|parsed| parsed.date().map(|date| date.to_timestamp())).unwrap_or(0)
+    };
+    let day = |value: String| {
+        chrono::NaiveDate::parse_from_str(&value, "%d-%b-%Y")
+            .map(|day| day.and_hms_opt(0, 0, 0).expect("midnight is a valid time").and_utc().timestamp())
+            .map_err(|_| EmailError::ImapError(format!("Bad date {}", value)))
+    };
+    Ok(match key.as_str() {
+        "ALL" => true,
+        "SEEN" => message.flags.iter().any(|flag| flag == "\\Seen"),
+        "UNSEEN" => !message.flags.iter().any(|flag| flag == "\\Seen"),
+        "DELETED" => message.flags.iter().any(|flag| flag == "\\Deleted"),
+        "FROM" | "TO" | "CC" | "SUBJECT" => header(&key, &argument()?),
+        "HEADER" => {
+            let name = argument()?;
+            header(&name, &argument()?)
+        }
+        "BODY" => body.contains(&argument()?.to_lowercase()),
+        "TEXT" => raw.contains(&argument()?.to_lowercase()),
+        "SINCE" => date() >= day(argument()?)?,
+        "BEFORE" => date() < day(argument()?)?,
+        "OR" => {
+            let first = matches_key(message, terms)?;
+            matches_key(message, terms)? || first
+        }
+        other => return Err(EmailError::ImapError(format!("Unsupported search key {}", other))),
+    })
+}
+
+impl ImapBackend for MockMailServer {
+    fn uids(&mut self, folder: &str) -> Result<Vec<u32>, EmailError> {
+        self.with_folder(folder, |messages| messages.keys().copied().collect())
+    }
+
+    fn fetch(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        self.with_folder(folder, |messages| {
+            uids.iter()
+                .filter_map(|uid| {
+                    messages.get(uid).map(|message| FetchedMessage {
+                        uid: *uid,
+                        flags: message.flags.clone(),
+                        raw: message.raw.clone(),
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn fetch_headers(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        let mut messages = self.fetch(folder, uids)?;
+        for message in &mut messages {
+            if let Some(end) = message.raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                message.raw.truncate(end + 4);
+            }
+        }
+        Ok(messages)
+    }
+
+    fn flags(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, Vec<String>>, EmailError> {
+        self.with_folder(folder, |messages| {
+            uids.iter()
+                .filter_map(|uid| messages.get(uid).map(|message| (*uid, message.flags.clone())))
+                .collect()
+        })
+    }
+
+    fn store_flag(&mut self, folder: &str, uid: u32, flag: &str, set: bool) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        let modseq = state.modseq + 1;
+        let message = state
+            .folders
+            .get_mut(folder)
+            .ok_or_else(|| EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)))?
+            .get_mut(&uid)
+            .ok_or_else(|| EmailError::ImapError(format!("No message with UID {}", uid)))?;
+        message.flags.retain(|f| f != flag);
+        if set {
+            message.flags.push(flag.to_string());
+        }
+        message.modseq = modseq;
+        state.modseq = modseq;
+        Ok(())
+    }
+
+    fn delete(&mut self, folder: &str, uid: u32) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        if !state.folders.contains_key(folder) {
+            return Err(EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)));
+        }
+        Self::remove(&mut state, folder, uid);
+        Ok(())
+    }
+
+    fn move_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        if self.lock().without_move {
+            return crate::transport::move_by_copy(self, folder, uid, target);
+        }
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        if !state.folders.contains_key(target) {
+            return Err(EmailError::ImapError(format!("[TRYCREATE] Mailbox doesn't exist: {}", target)));
+        }
+        let mut message = Self::remove(&mut state, folder, uid)
+            .ok_or_else(|| EmailError::ImapError(format!("No message with UID {} in {}", uid, folder)))?;
+        // Like a real server, the moved message gets a new UID in the target folder
+        state.next_uid += 1;
+        let new_uid = state.next_uid;
+        message.modseq = state.modseq;
+        if let Some(messages) = state.folders.get_mut(target) {
+            messages.insert(new_uid, message);
+        }
+        Ok(())
+    }
+
+    fn copy_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        if !state.folders.contains_key(target) {
+            return Err(EmailError::ImapError(format!("[TRYCREATE] Mailbox doesn't exist: {}", target)));
+        }
+        let mut message = state
+            .folders
+            .get(folder)
+            .and_then(|messages| messages.get(&uid))
+            .cloned()
+            .ok_or_else(|| EmailError::ImapError(format!("No message with UID {} in {}", uid, folder)))?;
+        state.next_uid += 1;
+        state.modseq += 1;
+        let new_uid = state.next_uid;
+        message.modseq = state.modseq;
+        if let Some(messages) = state.folders.get_mut(target) {
+            messages.insert(new_uid, message);
+        }
+        Ok(())
+    }
+
+    fn expunge_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        let deleted: Vec<u32> = state
+            .folders
+            .get(folder)
+            .ok_or_else(|| EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)))?
+            .iter()
+            .filter(|(_, message)| message.flags.iter().any(|flag| flag == "\\Deleted"))
+            .map(|(uid, _)| *uid)
+            .collect();
+        for uid in deleted {
+            Self::remove(&mut state, folder, uid);
+        }
+        Ok(())
+    }
+
+    fn unseen_count(&mut self, folder: &str) -> Result<u32, EmailError> {
+        self.with_folder(folder, |messages| {
+            messages.values().filter(|m| !m.flags.iter().any(|f| f == "\\Seen")).count() as u32
+        })
+    }
+
+    fn create_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        if state.folders.contains_key(folder) {
+            return Err(EmailError::ImapError(format!("Mailbox already exists: {}", folder)));
+        }
+        state.folders.insert(folder.to_string(), BTreeMap::new());
+        Ok(())
+    }
+
+    fn rename_folder(&mut self, folder: &str, new_name: &str) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        if !state.folders.contains_key(folder) {
+            return Err(EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)));
+        }
+        if state.folders.contains_key(new_name) {
+            return Err(EmailError::ImapError(format!("Mailbox already exists: {}", new_name)));
+        }
+        // Folders below it, with `/` as the hierarchy delimiter, go along
+        let below = format!("{}/", folder);
+        let renamed: Vec<String> = state.folders.keys().filter(|name| *name == folder || name.starts_with(&below)).cloned().collect();
+        for name in renamed {
+            let messages = state.folders.remove(&name).unwrap_or_default();
+            state.folders.insert(format!("{}{}", new_name, &name[folder.len()..]), messages);
+        }
+        Ok(())
+    }
+
+    fn delete_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        state
+            .folders
+            .remove(folder)
+            .map(|_| ())
+            .ok_or_else(|| EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)))
+    }
+
+    fn append(&mut self, folder: &str, raw: &[u8], flags: &[String]) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        state.next_uid += 1;
+        state.modseq += 1;
+        let (uid, modseq) = (state.next_uid, state.modseq);
+        let messages = state
+            .folders
+            .get_mut(folder)
+            .ok_or_else(|| EmailError::ImapError(format!("[TRYCREATE] Mailbox doesn't exist: {}", folder)))?;
+        messages.insert(
+            uid,
+            MockMessage {
+                flags: flags.to_vec(),
+                raw: raw.to_vec(),
+                modseq,
+            },
+        );
+        Ok(())
+    }
+
+    fn changes_since(&mut self, folder: &str, since: Option<(u32, u64)>) -> Result<Option<FolderChanges>, EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        if !state.qresync {
+            return Ok(None);
+        }
+        let messages = state
+            .folders
+            .get(folder)
+            .ok_or_else(|| EmailError::ImapError(format!("Mailbox doesn't exist: {}", folder)))?;
+        // The mock never renumbers a folder
+        let mut changes = FolderChanges { uid_validity: 1, highest_modseq: state.modseq.max(1), ..FolderChanges::default() };
+        if let Some((1, modseq)) = since {
+            changes.flags = messages
+                .iter()
+                .filter(|(_, message)| message.modseq > modseq)
+                .map(|(uid, message)| (*uid, message.flags.clone()))
+                .collect();
+            changes.vanished = Some(
+                state
+                    .vanished
+                    .iter()
+                    .filter(|(name, _, removed)| name == folder && *removed > modseq)
+                    .map(|(_, uid, _)| *uid..=*uid)
+                    .collect(),
+            );
+        }
+        Ok(Some(changes))
+    }
+
+    fn search(&mut self, folder: &str, criteria: &str) -> Result<Option<Vec<u32>>, EmailError> {
+        let mut terms = search_terms(criteria);
+        if terms.first().is_some_and(|term| term.eq_ignore_ascii_case("CHARSET")) {
+            terms.drain(..2.min(terms.len()));
+        }
+        self.with_folder(folder, |messages| {
+            let mut found = Vec::new();
+            for (uid, message) in messages.iter() {
+                let mut keys: std::collections::VecDeque<String> = terms.iter().cloned().collect();
+                let mut matched = true;
+                while !keys.is_empty() {
+                    matched &= matches_key(message, &mut keys)?;
+                }
+                if matched {
+                    found.push(*uid);
+                }
+            }
+            Ok(Some(found))
+        })?
+    }
+
+    fn gmail_ids(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, String>, EmailError> {
+        let state = self.lock();
+        Ok(uids
+            .iter()
+            .filter_map(|uid| Some((*uid, state.gmail_ids.get(&(folder.to_string(), *uid))?.clone())))
+            .collect())
+    }
+
+    fn sort(&mut self, folder: &str) -> Result<Option<Vec<u32>>, EmailError> {
+        if !self.lock().sort_and_thread {
+            return Ok(None);
+        }
+        let mut dated: Vec<(u32, i64)> = parsed(self, folder)?.iter().map(|(uid, email)| (*uid, email.date.timestamp())).collect();
+        dated.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+        Ok(Some(dated.into_iter().map(|(uid, _)| uid).collect()))
+    }
+
+    fn threads(&mut self, folder: &str) -> Result<Option<Vec<Vec<u32>>>, EmailError> {
+        if !self.lock().sort_and_thread {
+            return Ok(None);
+        }
+        // Grouped by the first message each one refers to, which is enough for tests
+        let mut threads: Vec<(Option<String>, Vec<u32>)> = Vec::new();
+        for (uid, email) in parsed(self, folder)? {
+            let root = crate::mute::thread_root(&email);
+            match threads.iter_mut().find(|(known, _)| root.is_some() && *known == root) {
+                Some((_, uids)) => uids.push(uid),
+                None => threads.push((root, vec![uid])),
+            }
+        }
+        Ok(Some(threads.into_iter().map(|(_, uids)| uids).collect()))
+    }
+
+    fn special_use_folders(&mut self) -> Result<SpecialFolders, EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        let mut special = SpecialFolders::default();
+        for (folder, attribute) in &state.special_use {
+            special.assign(attribute, folder);
+        }
+        Ok(special)
+    }
+}
+
+impl SmtpBackend for MockMailServer {
+    fn send(&mut self, message: &Message) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        state.sent.push(message.formatted());
+        Ok(())
+    }
+
+    fn send_raw(&mut self, _envelope: &lettre::address::Envelope, raw: &[u8]) -> Result<(), EmailError> {
+        let mut state = self.lock();
+        Self::check_failure(&mut state)?;
+        state.sent.push(raw.to_vec());
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use crate::email::Email;
+
+/// A muted conversation, identified by the Message-ID its first message has
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MutedThread {
+    pub root: String,
+    /// Subject when it was muted, for display
+    pub subject: String,
+    /// Whether new messages are also moved to the archive folder
+    pub archive: bool,
+    /// Messages dated before this are left as they are
+    pub muted_at: i64,
+}
+
+fn normalize(id: &str) -> String {
+    id.trim().trim_matches(['<', '>']).to_string()
+}
+
+/// Every Message-ID that places `email` in a conversation: its own, the ones it
+/// replies to and the ones listed in its References
+pub fn thread_ids(email: &Email) -> Vec<String> {
+    let mut ids: Vec<String> = email
+        .header("References")
+        .into_iter()
+        .chain(email.header("In-Reply-To"))
+        .chain(email.header("Message-ID"))
+        .flat_map(str::split_whitespace)
+        .map(normalize)
+        .filter(|id| !id.is_empty())
+        .collect();
+    ids.dedup();
+    ids
+}
+
+/// The Message-ID of the first message of the conversation `email` is part of: the
+/// oldest reference, else what it replies to, else its own
+pub fn thread_root(email: &Email) -> Option<String> {
+    thread_ids(email).into_iter().next()
+}
+
+/// The muted conversation `email` belongs to, if any. Any of its references counts, so
+/// replies from clients that shorten References are still caught.
+pub fn muted_thread<'a>(email: &Email, muted: &'a [MutedThread]) -> Option<&'a MutedThread> {
+    let ids = thread_ids(email);
+    muted.iter().find(|thread| ids.contains(&thread.root))
+}
+
+/// What to do with a message that arrived in a muted conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Silence {
+    MarkRead,
+    Archive,
+}
+
+/// The messages of a folder that arrived in muted conversations after they were muted,
+/// and what to do with each. Messages already read and not to be archived are skipped,
+/// as is everything in the archive folder itself.
+pub fn to_silence<'a>(emails: &'a [Email], muted: &[MutedThread], archive_folder: &str) -> Vec<(&'a Email, Silence)> {
+    emails
+        .iter()
+        .filter_map(|email| {
+            let thread = muted_thread(email, muted)?;
+            if email.date.timestamp() < thread.muted_at || email.folder.eq_ignore_ascii_case(archive_folder) {
+                return None;
+            }
+            match (thread.archive, email.seen) {
+                (true, _) => Some((email, Silence::Archive)),
+                (false, false) => Some((email, Silence::MarkRead)),
+                (false, true) => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn email(id: &str, headers: &[(&str, &str)], date: i64, seen: bool) -> Email {
+        let mut email = Email::new();
+        email.id = id.to_string();
+        email.folder = "INBOX".to_string();
+        email.date = chrono::Local.timestamp_opt(date, 0).unwrap();
+        email.seen = seen;
+        for (name, value) in headers {
+            email.headers.insert(name.to_string(), value.to_string());
+        }
+        email
+    }
+
+    #[test]
+    fn test_muted_replies_are_silenced() {
+        let root = email("1", &[("Message-ID", "<root@example.com>")], 100, true);
+        assert_eq!(thread_root(&root).as_deref(), Some("root@example.com"));
+        let reply = email("2", &[("Message-ID", "<b@x>"), ("In-Reply-To", "<root@example.com>")], 300, false);
+        assert_eq!(thread_root(&reply).as_deref(), Some("root@example.com"));
+
+        let muted = vec![MutedThread { root: "root@example.com".to_string(), subject: "Lunch".to_string(), archive: false, muted_at: 200 }];
+        let late = email("3", &[("References", "<root@example.com> <b@x>"), ("Message-ID", "<c@x>")], 400, false);
+        let early = email("4", &[("In-Reply-To", "<root@example.com>")], 150, false);
+        let other = email("5", &[("Message-ID", "<other@x>")], 400, false);
+        let emails = vec![root, reply, late, early, other];
+
+        let silenced: Vec<_> = to_silence(&emails, &muted, "Archive").into_iter().map(|(e, s)| (e.id.as_str(), s)).collect();
+        assert_eq!(silenced, vec![("2", Silence::MarkRead), ("3", Silence::MarkRead)]);
+
+        let archiving = vec![MutedThread { archive: true, ..muted[0].clone() }];
+        let silenced: Vec<_> = to_silence(&emails, &archiving, "Archive").into_iter().map(|(e, s)| (e.id.as_str(), s)).collect();
+        assert_eq!(silenced, vec![("2", Silence::Archive), ("3", Silence::Archive)]);
+    }
+}
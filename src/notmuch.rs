@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::config::NotmuchConfig;
+use crate::database::EmailDatabase;
+use crate::email::{debug_log, Email};
+
+/// Tags are shared with notmuch only in builds with the `notmuch` feature
+pub const ENABLED: bool = cfg!(feature = "notmuch");
+
+/// Messages asked about in one notmuch command, to keep the command line short
+const QUERY_BATCH: usize = 100;
+
+/// The notmuch query for one message
+fn id_query(message_id: &str) -> String {
+    format!("id:\"{}\"", message_id.replace('"', "\"\""))
+}
+
+/// Undo the `%xx` encoding of `notmuch dump`
+fn hex_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = text.get(i + 1..i + 3).filter(|_| bytes[i] == b'%').and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// A line of `notmuch dump --format=batch-tag`: `+tag +tag -- id:<message-id>`
+fn parse_dump_line(line: &str) -> Option<(String, Vec<String>)> {
+    let (tags, id) = line.split_once(" -- ").or_else(|| line.strip_prefix("-- ").map(|id| ("", id)))?;
+    let id = hex_decode(id.trim().strip_prefix("id:")?);
+    let mut tags: Vec<String> = tags.split_whitespace().filter_map(|tag| tag.strip_prefix('+')).map(hex_decode).collect();
+    tags.sort();
+    Some((id, tags))
+}
+
+/// `+tag`/`-tag` arguments turning the tags `old` into `new`
+fn tag_arguments(old: &[String], new: &[String]) -> Vec<String> {
+    let added = new.iter().filter(|tag| !old.contains(tag)).map(|tag| format!("+{}", tag));
+    let removed = old.iter().filter(|tag| !new.contains(tag)).map(|tag| format!("-{}", tag));
+    added.chain(removed).collect()
+}
+
+/// The notmuch program, run once per command
+pub struct Notmuch<'a> {
+    config: &'a NotmuchConfig,
+}
+
+impl<'a> Notmuch<'a> {
+    pub fn new(config: &'a NotmuchConfig) -> Self {
+        Self { config }
+    }
+
+    fn run(&self, args: &[String], input: Option<&[u8]>) -> Result<String, String> {
+        let mut child = Command::new(&self.config.command)
+            .args(args)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.config.command, e))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input).map_err(|e| format!("Failed to write to notmuch: {}", e))?;
+        }
+        let output = child.wait_with_output().map_err(|e| format!("notmuch failed: {}", e))?;
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("notmuch {} failed: {}", args.first().map(String::as_str).unwrap_or_default(), error.trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// The tags of those of the messages notmuch has indexed, by Message-ID
+    pub fn tags(&self, message_ids: &[String]) -> Result<HashMap<String, Vec<String>>, String> {
+        let mut tags = HashMap::new();
+        for batch in message_ids.chunks(QUERY_BATCH) {
+            let query = batch.iter().map(|id| id_query(id)).collect::<Vec<_>>().join(" or ");
+            let output = self.run(&["dump".to_string(), "--format=batch-tag".to_string(), "--".to_string(), query], None)?;
+            tags.extend(output.lines().filter_map(parse_dump_line));
+        }
+        Ok(tags)
+    }
+
+    /// Change the tags of a message from `old` to `new`
+    pub fn retag(&self, message_id: &str, old: &[String], new: &[String]) -> Result<(), String> {
+        let mut args = vec!["tag".to_string()];
+        args.extend(tag_arguments(old, new));
+        if args.len() == 1 {
+            return Ok(());
+        }
+        args.extend(["--".to_string(), id_query(message_id)]);
+        self.run(&args, None).map(|_| ())
+    }
+
+    /// Add a message to `folder` under the notmuch database root and index it with `tags`
+    pub fn insert(&self, folder: &str, raw: &[u8], tags: &[String]) -> Result<(), String> {
+        let mut args = vec!["insert".to_string(), "--create-folder".to_string(), format!("--folder={}", folder)];
+        args.extend(tags.iter().map(|tag| format!("+{}", tag)));
+        self.run(&args, Some(raw)).map(|_| ())
+    }
+}
+
+/// Bring in the notmuch tags of synced messages, and add those notmuch doesn't know to
+/// the configured index folder with the tags they have here; their tags come in at the
+/// next sync. Messages without a Message-ID, and those listed from headers only, are
+/// left out. Returns how many messages' tags changed.
+pub fn sync_tags(config: &NotmuchConfig, db: &EmailDatabase, account_email: &str, emails: &[Email]) -> Result<usize, String> {
+    let notmuch = Notmuch::new(config);
+    let local = db.get_tags(account_email).map_err(|e| e.to_string())?;
+    let keyed: Vec<(String, &Email)> = emails
+        .iter()
+        .filter_map(|email| Some((crate::database::message_key(email)?, email)))
+        .collect();
+    let ids: Vec<String> = keyed.iter().map(|(id, _)| id.clone()).collect();
+    let indexed = notmuch.tags(&ids)?;
+
+    let mut synced = 0;
+    for (message_id, email) in keyed {
+        match (indexed.get(&message_id), &config.index_folder) {
+            (Some(tags), _) => {
+                if local.get(&message_id) != Some(tags) {
+                    db.set_tags(account_email, &message_id, tags).map_err(|e| e.to_string())?;
+                    synced += 1;
+                }
+            }
+            (None, Some(folder)) if email.body_text.is_some() || email.body_html.is_some() => {
+                let raw = crate::transport::rebuild_message(email).map_err(|e| e.to_string())?;
+                let tags = local.get(&message_id).cloned().unwrap_or_default();
+                notmuch.insert(&format!("{}/{}/{}", folder, account_email, email.folder), &raw, &tags)?;
+                debug_log(&format!("notmuch: indexed {} from {}/{}", message_id, account_email, email.folder));
+            }
+            (None, _) => {}
+        }
+    }
+    Ok(synced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_and_tag_changes() {
+        assert_eq!(id_query("abc@example.com"), "id:\"abc@example.com\"");
+        assert_eq!(id_query("a\"b@x"), "id:\"a\"\"b@x\"");
+
+        let old = vec!["inbox".to_string(), "todo".to_string()];
+        let new = vec!["todo".to_string(), "work".to_string()];
+        assert_eq!(tag_arguments(&old, &new), vec!["+work", "-inbox"]);
+        assert!(tag_arguments(&new, &new).is_empty());
+
+        assert_eq!(
+            parse_dump_line("+inbox +to%20do -- id:abc%25x@example.com"),
+            Some(("abc%x@example.com".to_string(), vec!["inbox".to_string(), "to do".to_string()]))
+        );
+        assert_eq!(parse_dump_line("-- id:untagged@example.com"), Some(("untagged@example.com".to_string(), Vec::new())));
+        assert_eq!(parse_dump_line("#@ some comment"), None);
+    }
+}
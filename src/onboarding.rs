@@ -0,0 +1,536 @@
+use std::io;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use ratatui::backend::Backend;
+
+use crate::autoconfig::Discovered;
+use crate::config::{EmailAccount, ImapSecurity, SmtpSecurity, SpecialFolders};
+use crate::credentials::SecureCredentials;
+use crate::email::{EmailClient, EmailError};
+use crate::providers::{ProviderPreset, PRESETS};
+
+/// Fields of the account form, in tab order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Email,
+    Username,
+    Password,
+    ImapServer,
+    ImapPort,
+    ImapSecurity,
+    SmtpServer,
+    SmtpPort,
+    SmtpSecurity,
+}
+
+const FIELDS: [Field; 10] = [
+    Field::Name,
+    Field::Email,
+    Field::Username,
+    Field::Password,
+    Field::ImapServer,
+    Field::ImapPort,
+    Field::ImapSecurity,
+    Field::SmtpServer,
+    Field::SmtpPort,
+    Field::SmtpSecurity,
+];
+
+impl Field {
+    /// Fields picked from a fixed set of values rather than typed
+    fn is_choice(self) -> bool {
+        matches!(self, Field::ImapSecurity | Field::SmtpSecurity)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Field::Name => "Account name",
+            Field::Email => "Email address",
+            Field::Username => "Username",
+            Field::Password => "Password",
+            Field::ImapServer => "IMAP server",
+            Field::ImapPort => "IMAP port",
+            Field::ImapSecurity => "IMAP security",
+            Field::SmtpServer => "SMTP server",
+            Field::SmtpPort => "SMTP port",
+            Field::SmtpSecurity => "SMTP security",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Provider,
+    Details,
+}
+
+/// What the caller should do after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Continue,
+    Cancel,
+    /// Look the server settings up from the email address
+    Discover,
+    /// Create the account, checking the login first when `test` is set
+    Submit { test: bool },
+}
+
+/// First-run setup: pick a provider, then fill in the few fields it cannot pre-fill
+pub struct Onboarding {
+    step: Step,
+    /// Highlighted provider; `PRESETS.len()` is "Other"
+    provider_idx: usize,
+    field_idx: usize,
+    name: String,
+    email: String,
+    username: String,
+    password: String,
+    imap_server: String,
+    imap_port: String,
+    imap_security: ImapSecurity,
+    smtp_server: String,
+    smtp_port: String,
+    smtp_security: SmtpSecurity,
+    message: Option<String>,
+}
+
+impl Onboarding {
+    pub fn new() -> Self {
+        Self {
+            step: Step::Provider,
+            provider_idx: 0,
+            field_idx: 0,
+            name: String::new(),
+            email: String::new(),
+            username: String::new(),
+            password: String::new(),
+            imap_server: String::new(),
+            imap_port: "993".to_string(),
+            imap_security: ImapSecurity::SSL,
+            smtp_server: String::new(),
+            smtp_port: "587".to_string(),
+            smtp_security: SmtpSecurity::StartTLS,
+            message: None,
+        }
+    }
+
+    fn preset(&self) -> Option<&'static ProviderPreset> {
+        PRESETS.get(self.provider_idx)
+    }
+
+    fn field(&self) -> Field {
+        FIELDS[self.field_idx]
+    }
+
+    fn choose_provider(&mut self) {
+        if let Some(preset) = self.preset() {
+            self.imap_server = preset.imap_server.to_string();
+            self.imap_port = preset.imap_port.to_string();
+            self.imap_security = preset.imap_security.clone();
+            self.smtp_server = preset.smtp_server.to_string();
+            self.smtp_port = preset.smtp_port.to_string();
+            self.smtp_security = preset.smtp_security.clone();
+        }
+        self.step = Step::Details;
+        self.field_idx = 0;
+        self.message = None;
+    }
+
+    fn text_mut(&mut self, field: Field) -> Option<&mut String> {
+        match field {
+            Field::Name => Some(&mut self.name),
+            Field::Email => Some(&mut self.email),
+            Field::Username => Some(&mut self.username),
+            Field::Password => Some(&mut self.password),
+            Field::ImapServer => Some(&mut self.imap_server),
+            Field::ImapPort => Some(&mut self.imap_port),
+            Field::SmtpServer => Some(&mut self.smtp_server),
+            Field::SmtpPort => Some(&mut self.smtp_port),
+            Field::ImapSecurity | Field::SmtpSecurity => None,
+        }
+    }
+
+    fn cycle_security(&mut self, forward: bool) {
+        match self.field() {
+            Field::ImapSecurity => {
+                self.imap_security = match (&self.imap_security, forward) {
+                    (ImapSecurity::SSL, true) | (ImapSecurity::None, false) => ImapSecurity::StartTLS,
+                    (ImapSecurity::StartTLS, true) | (ImapSecurity::SSL, false) => ImapSecurity::None,
+                    (ImapSecurity::None, true) | (ImapSecurity::StartTLS, false) => ImapSecurity::SSL,
+                };
+            }
+            Field::SmtpSecurity => {
+                self.smtp_security = match (&self.smtp_security, forward) {
+                    (SmtpSecurity::SSL, true) | (SmtpSecurity::None, false) => SmtpSecurity::StartTLS,
+                    (SmtpSecurity::StartTLS, true) | (SmtpSecurity::SSL, false) => SmtpSecurity::None,
+                    (SmtpSecurity::None, true) | (SmtpSecurity::StartTLS, false) => SmtpSecurity::SSL,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        match self.step {
+            Step::Provider => match key.code {
+                KeyCode::Up => self.provider_idx = self.provider_idx.saturating_sub(1),
+                KeyCode::Down => self.provider_idx = (self.provider_idx + 1).min(PRESETS.len()),
+                KeyCode::Enter => self.choose_provider(),
+                KeyCode::Esc => return Outcome::Cancel,
+                _ => {}
+            },
+            Step::Details => match key.code {
+                KeyCode::Esc => {
+                    self.step = Step::Provider;
+                    self.message = None;
+                }
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Outcome::Submit { test: false };
+                }
+                KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => return Outcome::Discover,
+                KeyCode::Enter => return Outcome::Submit { test: true },
+                KeyCode::Tab | KeyCode::Down => self.field_idx = (self.field_idx + 1) % FIELDS.len(),
+                KeyCode::BackTab | KeyCode::Up => {
+                    self.field_idx = (self.field_idx + FIELDS.len() - 1) % FIELDS.len();
+                }
+                KeyCode::Left => self.cycle_security(false),
+                KeyCode::Right | KeyCode::Char(' ') if self.field().is_choice() => {
+                    self.cycle_security(true);
+                }
+                KeyCode::Backspace => {
+                    if let Some(text) = self.text_mut(self.field()) {
+                        text.pop();
+                    }
+                }
+                KeyCode::Char(c) => {
+                    let field = self.field();
+                    if let Some(text) = self.text_mut(field) {
+                        if !matches!(field, Field::ImapPort | Field::SmtpPort) || c.is_ascii_digit() {
+                            text.push(c);
+                        }
+                    }
+                }
+                _ => {}
+            },
+        }
+        Outcome::Continue
+    }
+
+    fn needs_servers(&self) -> bool {
+        self.imap_server.trim().is_empty() || self.smtp_server.trim().is_empty()
+    }
+
+    /// Fill the server fields from the result of `autoconfig::discover`
+    fn apply_discovery(&mut self, result: anyhow::Result<Discovered>) {
+        match result {
+            Ok(found) => {
+                let to_confirm = found.hosts_to_confirm(self.email.trim()).join(" and ");
+                self.imap_server = found.imap_server;
+                self.imap_port = found.imap_port.to_string();
+                self.imap_security = found.imap_security;
+                self.smtp_server = found.smtp_server;
+                self.smtp_port = found.smtp_port.to_string();
+                self.smtp_security = found.smtp_security;
+                self.message = Some(if to_confirm.is_empty() {
+                    format!("Found the servers via {}. Enter to test and save.", found.source)
+                } else {
+                    format!("DNS points outside your domain, to {}. Check the servers, then Enter to test and save.", to_confirm)
+                });
+                if found.username != self.email.trim() {
+                    self.username = found.username;
+                }
+            }
+            Err(e) => self.message = Some(format!("{}. Enter them below.", e)),
+        }
+    }
+
+    /// The account described by the form, or what is missing
+    fn account(&self) -> Result<EmailAccount, String> {
+        let email = self.email.trim();
+        if !email.contains('@') {
+            return Err("Enter your email address".to_string());
+        }
+        if self.password.is_empty() {
+            return Err("Enter your password".to_string());
+        }
+        if self.imap_server.trim().is_empty() || self.smtp_server.trim().is_empty() {
+            return Err("Enter the IMAP and SMTP servers".to_string());
+        }
+        let imap_port = self.imap_port.parse().map_err(|_| "IMAP port must be a number".to_string())?;
+        let smtp_port = self.smtp_port.parse().map_err(|_| "SMTP port must be a number".to_string())?;
+
+        let name = match self.name.trim() {
+            "" => self.preset().map(|p| p.name).unwrap_or(email).to_string(),
+            name => name.to_string(),
+        };
+        let username = match self.username.trim() {
+            "" => email.to_string(),
+            username => username.to_string(),
+        };
+
+        Ok(EmailAccount {
+            name,
+            email: email.to_string(),
+            imap_server: self.imap_server.trim().to_string(),
+            imap_port,
+            imap_security: self.imap_security.clone(),
+            imap_username: username.clone(),
+            smtp_server: self.smtp_server.trim().to_string(),
+            smtp_port,
+            smtp_security: self.smtp_security.clone(),
+            smtp_username: username,
+            signature: None,
+            sync_interval: None,
+            compose: crate::config::ComposeDefaults::default(),
+            smtp_ip_family: None,
+            smtp_fallback: crate::config::SmtpFallback::default(),
+            internal_domains: Vec::new(),
+            maildir: None,
+            folder_options: Default::default(),
+            gmail_api: None,
+            exchange: None,
+            pgp: None,
+            caldav: None,
+            ldap: None,
+            special_folders: SpecialFolders::default(),
+        })
+    }
+
+    /// Store the passwords and, if asked, check that the server accepts them
+    fn finish(&self, credentials: &SecureCredentials, test: bool) -> Result<EmailAccount, String> {
+        let account = self.account()?;
+        account
+            .store_imap_password(credentials, &self.password)
+            .and_then(|_| account.store_smtp_password(credentials, &self.password))
+            .map_err(|e| format!("Failed to store password: {}", e))?;
+
+        if test {
+            let client = EmailClient::new(account.clone(), credentials.clone());
+            if let Err(e) = client.list_folders() {
+                let hint = match (&e, self.preset()) {
+                    (EmailError::AuthError(_), Some(_)) => "Check the password (see the note above).",
+                    (EmailError::AuthError(_), None) => "Check the username and password.",
+                    _ => "Check the server settings.",
+                };
+                return Err(format!("{} {} Ctrl+S saves without testing.", e, hint));
+            }
+        }
+        Ok(account)
+    }
+
+    fn render(&self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let title = Paragraph::new("Welcome to tuimail. Let's set up your first account.")
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(title, chunks[0]);
+
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        match self.step {
+            Step::Provider => self.render_providers(f, body[0]),
+            Step::Details => self.render_form(f, body[0]),
+        }
+        self.render_notes(f, body[1]);
+
+        let status = match (&self.message, self.step) {
+            (Some(message), _) => message.clone(),
+            (None, Step::Provider) => "↑↓ to choose your provider, Enter to continue, Esc to quit".to_string(),
+            (None, Step::Details) => {
+                "Tab/↑↓ to move, ←→ to change security, Ctrl+D to detect servers, Enter to test and save, Ctrl+S to save, Esc to go back".to_string()
+            }
+        };
+        f.render_widget(Paragraph::new(status).style(Style::default().bg(Color::Blue).fg(Color::White)), chunks[2]);
+    }
+
+    fn render_providers(&self, f: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = PRESETS
+            .iter()
+            .map(|p| ListItem::new(p.name))
+            .chain(std::iter::once(ListItem::new("Other (enter server settings)")))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().title("Provider").borders(Borders::ALL))
+            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
+            .highlight_symbol("> ");
+        let mut state = ListState::default();
+        state.select(Some(self.provider_idx));
+        f.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_form(&self, f: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let value = match field {
+                    Field::Name => self.name.clone(),
+                    Field::Email => self.email.clone(),
+                    Field::Username if self.username.is_empty() => "(same as email address)".to_string(),
+                    Field::Username => self.username.clone(),
+                    Field::Password => "•".repeat(self.password.chars().count()),
+                    Field::ImapServer => self.imap_server.clone(),
+                    Field::ImapPort => self.imap_port.clone(),
+                    Field::ImapSecurity => format!("‹ {:?} ›", self.imap_security),
+                    Field::SmtpServer => self.smtp_server.clone(),
+                    Field::SmtpPort => self.smtp_port.clone(),
+                    Field::SmtpSecurity => format!("‹ {:?} ›", self.smtp_security),
+                };
+                let selected = i == self.field_idx;
+                let value_style = if selected {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:<15}", field.label()), Style::default().fg(Color::Gray)),
+                    Span::styled(value, value_style),
+                    Span::raw(if selected && !field.is_choice() { "_" } else { "" }),
+                ])
+            })
+            .collect();
+
+        let title = self.preset().map(|p| p.name).unwrap_or("Other provider");
+        let form = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(form, area);
+    }
+
+    fn render_notes(&self, f: &mut Frame, area: Rect) {
+        let text = match self.preset() {
+            Some(preset) => vec![
+                Line::from(Span::styled(preset.name, Style::default().add_modifier(Modifier::BOLD))),
+                Line::from(""),
+                Line::from(preset.notes),
+                Line::from(""),
+                Line::from(format!("IMAP: {}:{}", preset.imap_server, preset.imap_port)),
+                Line::from(format!("SMTP: {}:{}", preset.smtp_server, preset.smtp_port)),
+            ],
+            None => vec![
+                Line::from(Span::styled("Other provider", Style::default().add_modifier(Modifier::BOLD))),
+                Line::from(""),
+                Line::from("Enter your email address and press Ctrl+D to look up the server settings, \
+                            or copy them from your provider's help pages. Most use port 993 with SSL \
+                            for IMAP and 587 with StartTLS for SMTP."),
+                Line::from(""),
+                Line::from("If your account uses two-factor authentication, you will usually need an app password."),
+            ],
+        };
+        let notes = Paragraph::new(text)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().title("Before you start").borders(Borders::ALL));
+        f.render_widget(notes, area);
+    }
+}
+
+impl Default for Onboarding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run the setup screens until an account is created (`Some`) or the user quits (`None`).
+/// Passwords are stored in `credentials`; the caller adds the account to the config.
+pub fn run<B: Backend>(terminal: &mut Terminal<B>, credentials: &SecureCredentials) -> io::Result<Option<EmailAccount>> {
+    let mut onboarding = Onboarding::new();
+    loop {
+        terminal.draw(|f| onboarding.render(f))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match onboarding.handle_key(key) {
+            Outcome::Continue => {}
+            Outcome::Cancel => return Ok(None),
+            Outcome::Discover => discover(terminal, &mut onboarding)?,
+            Outcome::Submit { test } => {
+                // Let the user review what discovery found before anything is saved
+                if onboarding.needs_servers() && onboarding.email.contains('@') {
+                    discover(terminal, &mut onboarding)?;
+                    continue;
+                }
+                if test {
+                    onboarding.message = Some("Testing connection...".to_string());
+                    terminal.draw(|f| onboarding.render(f))?;
+                }
+                match onboarding.finish(credentials, test) {
+                    Ok(account) => return Ok(Some(account)),
+                    Err(message) => onboarding.message = Some(message),
+                }
+            }
+        }
+    }
+}
+
+fn discover<B: Backend>(terminal: &mut Terminal<B>, onboarding: &mut Onboarding) -> io::Result<()> {
+    onboarding.message = Some("Looking up mail servers...".to_string());
+    terminal.draw(|f| onboarding.render(f))?;
+    let result = crate::autoconfig::discover(onboarding.email.trim());
+    onboarding.apply_discovery(result);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(onboarding: &mut Onboarding, code: KeyCode) -> Outcome {
+        onboarding.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(onboarding: &mut Onboarding, text: &str) {
+        for c in text.chars() {
+            press(onboarding, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_onboarding_form() {
+        let mut onboarding = Onboarding::new();
+        press(&mut onboarding, KeyCode::Enter); // Gmail
+        press(&mut onboarding, KeyCode::Tab);
+        type_text(&mut onboarding, "me@gmail.com");
+        assert_eq!(press(&mut onboarding, KeyCode::Enter), Outcome::Submit { test: true });
+        assert_eq!(onboarding.account().unwrap_err(), "Enter your password");
+
+        press(&mut onboarding, KeyCode::Tab);
+        press(&mut onboarding, KeyCode::Tab);
+        type_text(&mut onboarding, "app pass");
+        let account = onboarding.account().unwrap();
+        assert_eq!(account.name, "Gmail");
+        assert_eq!(account.imap_server, "imap.gmail.com");
+        assert_eq!(account.imap_username, "me@gmail.com");
+        assert_eq!(account.smtp_security, SmtpSecurity::StartTLS);
+
+        // Ports only take digits; security cycles with the arrow keys
+        press(&mut onboarding, KeyCode::Tab);
+        press(&mut onboarding, KeyCode::Tab);
+        type_text(&mut onboarding, "x1");
+        assert_eq!(onboarding.imap_port, "9931");
+        press(&mut onboarding, KeyCode::Tab);
+        press(&mut onboarding, KeyCode::Right);
+        assert_eq!(onboarding.imap_security, ImapSecurity::StartTLS);
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| onboarding.render(f)).unwrap();
+
+        assert_eq!(press(&mut onboarding, KeyCode::Esc), Outcome::Continue);
+        terminal.draw(|f| onboarding.render(f)).unwrap();
+        assert_eq!(press(&mut onboarding, KeyCode::Esc), Outcome::Cancel);
+    }
+}
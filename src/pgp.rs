@@ -0,0 +1,485 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use lettre::message::header::{ContentDisposition, ContentType};
+use lettre::message::{MultiPart, SinglePart};
+use mail_parser::{MimeHeaders, PartType};
+
+use crate::config::PgpConfig;
+use crate::email::{debug_log, Email};
+
+const SIGNATURE_TYPE: &str = "application/pgp-signature";
+const ENCRYPTED_TYPE: &str = "application/pgp-encrypted";
+const MESSAGE_BEGIN: &str = "-----BEGIN PGP MESSAGE-----";
+const MESSAGE_END: &str = "-----END PGP MESSAGE-----";
+const SIGNED_BEGIN: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+const SIGNATURE_END: &str = "-----END PGP SIGNATURE-----";
+
+/// Whether a message being sent is signed and encrypted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Protection {
+    pub sign: bool,
+    pub encrypt: bool,
+}
+
+impl Protection {
+    /// What new messages of an account start with
+    pub fn defaults(config: Option<&PgpConfig>) -> Self {
+        config.map_or_else(Self::default, |config| Self { sign: config.sign_by_default, encrypt: config.encrypt_by_default })
+    }
+
+    pub fn any(self) -> bool {
+        self.sign || self.encrypt
+    }
+}
+
+/// How a received message is protected, as far as the cache tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// PGP/MIME `multipart/encrypted`
+    Encrypted,
+    /// PGP/MIME `multipart/signed`; the signature covers the part as sent, so checking
+    /// it needs the raw message
+    Signed,
+    /// An armored message or clear-signed text in the body
+    Inline,
+}
+
+/// The kind of protection of `email`, if it has any. The cache keeps no Content-Type of
+/// the message, but PGP/MIME control and signature parts are kept as attachments.
+pub fn kind(email: &Email) -> Option<Kind> {
+    let has = |content_type: &str| email.attachments.iter().any(|a| a.content_type == content_type);
+    if has(ENCRYPTED_TYPE) {
+        Some(Kind::Encrypted)
+    } else if has(SIGNATURE_TYPE) {
+        Some(Kind::Signed)
+    } else if email.body_text.as_deref().is_some_and(|body| armored_block(body).is_some()) {
+        Some(Kind::Inline)
+    } else {
+        None
+    }
+}
+
+/// What the check of a signature found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Good,
+    Bad,
+    /// Made with a key, or a signature, that has expired since
+    Expired,
+    Revoked,
+    /// The key that made it is not in the keyring
+    UnknownKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub verdict: Verdict,
+    /// User ID of the key, or its key ID when the key is unknown
+    pub signer: String,
+    pub fingerprint: Option<String>,
+    /// Whether the key is trusted fully or ultimately in the keyring
+    pub trusted: bool,
+}
+
+impl Signature {
+    /// Whether the user ID of the signing key names `address`
+    pub fn is_from(&self, address: &str) -> bool {
+        self.signer.to_lowercase().contains(&address.to_lowercase())
+    }
+
+    /// One line for the viewer, such as `Good signature from Alice <alice@example.com>`
+    pub fn summary(&self) -> String {
+        let text = match self.verdict {
+            Verdict::Good if self.trusted => "Good signature from",
+            Verdict::Good => "Good signature from untrusted key",
+            Verdict::Bad => "BAD signature claiming to be from",
+            Verdict::Expired => "Signature by expired key",
+            Verdict::Revoked => "Signature by REVOKED key",
+            Verdict::UnknownKey => "Signed with unknown key",
+        };
+        format!("{} {}", text, self.signer)
+    }
+}
+
+/// A received message after decryption and checking its signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opened {
+    pub decrypted: bool,
+    pub signature: Option<Signature>,
+    /// Text shown instead of the cached body, for decrypted messages
+    pub body: Option<String>,
+    /// Attachments inside the encrypted part, which are not listed
+    pub hidden_attachments: usize,
+}
+
+/// What gpg printed on its status channel and output
+struct Outcome {
+    output: Vec<u8>,
+    status: Vec<String>,
+}
+
+/// The gpg program, run once per operation in batch mode. Passphrases come from
+/// gpg-agent, so a key that is locked needs a graphical pinentry or a cached passphrase.
+pub struct Gpg<'a> {
+    config: &'a PgpConfig,
+}
+
+impl<'a> Gpg<'a> {
+    pub fn new(config: &'a PgpConfig) -> Self {
+        Self { config }
+    }
+
+    /// Run gpg with `input` on stdin. Status lines go to a file of their own, so text
+    /// gpg prints on stderr (user IDs, notes from the message) can't pass for them; they
+    /// are returned with their `[GNUPG:]` prefix removed.
+    fn run(&self, args: &[String], input: &[u8]) -> Result<Outcome, String> {
+        let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let status_path = std::env::temp_dir().join(format!("tuimail-gpg-status-{}-{}", std::process::id(), nanos));
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&status_path)
+            .map_err(|e| format!("Failed to create the gpg status file: {}", e))?;
+        let output = self.run_with_status_file(args, input, &status_path);
+        let status_text = std::fs::read_to_string(&status_path).unwrap_or_default();
+        let _ = std::fs::remove_file(&status_path);
+        let output = output?;
+
+        let status: Vec<String> = status_text
+            .lines()
+            .filter_map(|line| line.strip_prefix("[GNUPG:] "))
+            .map(str::to_string)
+            .collect();
+        if !output.status.success() && signature(&status).is_none() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = failure(&status).unwrap_or_else(|| {
+                stderr.lines().rfind(|line| !line.trim().is_empty()).unwrap_or("gpg failed").trim().to_string()
+            });
+            return Err(message);
+        }
+        Ok(Outcome { output: output.stdout, status })
+    }
+
+    fn run_with_status_file(&self, args: &[String], input: &[u8], status_path: &std::path::Path) -> Result<std::process::Output, String> {
+        let mut child = Command::new(&self.config.command)
+            .args(["--batch", "--no-tty", "--status-file"])
+            .arg(status_path)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.config.command, e))?;
+        let stdin = child.stdin.take();
+        // stdin is fed from another thread while this one drains stdout and stderr, so a
+        // large message can't leave gpg and us both waiting on a full pipe
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                if let Some(mut stdin) = stdin {
+                    // gpg stops reading once it knows it can't go on; its error says why
+                    let _ = stdin.write_all(input);
+                }
+            });
+            child.wait_with_output()
+        })
+        .map_err(|e| format!("gpg failed: {}", e))
+    }
+
+    /// Key to sign with and encrypt to, the account address when none is configured
+    fn own_key(&self, sender: &str) -> String {
+        self.config.key.clone().unwrap_or_else(|| sender.to_string())
+    }
+
+    /// An armored detached signature of `data`, and the `micalg` of the hash it used
+    fn sign_detached(&self, sender: &str, data: &[u8]) -> Result<(Vec<u8>, String), String> {
+        let args = ["--armor".to_string(), "--detach-sign".to_string(), "--local-user".to_string(), self.own_key(sender)];
+        let outcome = self.run(&args, data)?;
+        let micalg = outcome
+            .status
+            .iter()
+            .find_map(|line| line.strip_prefix("SIG_CREATED "))
+            .and_then(|created| created.split_whitespace().nth(2))
+            .and_then(micalg)
+            .unwrap_or("pgp-sha256");
+        Ok((outcome.output, micalg.to_string()))
+    }
+
+    /// `data` encrypted to the recipients and to yourself, and signed too when `sign` is
+    /// set. Bcc recipients are left out of the list of keys others can see.
+    fn encrypt(&self, sender: &str, recipients: &[String], hidden: &[String], sign: bool, data: &[u8]) -> Result<Vec<u8>, String> {
+        let own_key = self.own_key(sender);
+        let mut args = vec!["--armor".to_string(), "--encrypt".to_string()];
+        if sign {
+            args.extend(["--sign".to_string(), "--local-user".to_string(), own_key.clone()]);
+        }
+        for recipient in recipients.iter().chain(std::iter::once(&own_key)) {
+            args.extend(["--recipient".to_string(), recipient.clone()]);
+        }
+        for recipient in hidden {
+            args.extend(["--hidden-recipient".to_string(), recipient.clone()]);
+        }
+        Ok(self.run(&args, data)?.output)
+    }
+
+    /// The plaintext of an armored or binary message, and its signature if it has one.
+    /// Clear-signed text is checked and returned the same way.
+    fn decrypt(&self, data: &[u8]) -> Result<(Vec<u8>, Option<Signature>), String> {
+        let outcome = self.run(&["--decrypt".to_string()], data)?;
+        Ok((outcome.output, signature(&outcome.status)))
+    }
+
+    /// Check a detached signature of `data`. gpg reads the signature from a file, which
+    /// is removed again straight after.
+    fn verify_detached(&self, data: &[u8], signature_data: &[u8]) -> Result<Signature, String> {
+        let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let path = std::env::temp_dir().join(format!("tuimail-signature-{}-{}.asc", std::process::id(), nanos));
+        std::fs::write(&path, signature_data).map_err(|e| format!("Failed to write the signature: {}", e))?;
+        let outcome = self.run(&["--verify".to_string(), path.to_string_lossy().into_owned(), "-".to_string()], data);
+        let _ = std::fs::remove_file(&path);
+        signature(&outcome?.status).ok_or_else(|| "gpg found no signature".to_string())
+    }
+}
+
+/// The `micalg` parameter for an OpenPGP hash algorithm number (RFC 4880, 9.4)
+fn micalg(algorithm: &str) -> Option<&'static str> {
+    match algorithm {
+        "2" => Some("pgp-sha1"),
+        "8" => Some("pgp-sha256"),
+        "9" => Some("pgp-sha384"),
+        "10" => Some("pgp-sha512"),
+        "11" => Some("pgp-sha224"),
+        _ => None,
+    }
+}
+
+/// The signature gpg reported on its status channel, if any
+fn signature(status: &[String]) -> Option<Signature> {
+    let mut found: Option<Signature> = None;
+    let mut fingerprint = None;
+    let mut trusted = false;
+    for line in status {
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let verdict = match keyword {
+            "GOODSIG" => Verdict::Good,
+            "BADSIG" => Verdict::Bad,
+            "EXPSIG" | "EXPKEYSIG" => Verdict::Expired,
+            "REVKEYSIG" => Verdict::Revoked,
+            "ERRSIG" => Verdict::UnknownKey,
+            "VALIDSIG" => {
+                fingerprint = rest.split_whitespace().next().map(str::to_string);
+                continue;
+            }
+            "TRUST_FULLY" | "TRUST_ULTIMATE" => {
+                trusted = true;
+                continue;
+            }
+            _ => continue,
+        };
+        // The key ID comes first, then the user ID (not given with ERRSIG)
+        let (key_id, user_id) = rest.split_once(' ').unwrap_or((rest, ""));
+        let signer = if verdict == Verdict::UnknownKey || user_id.is_empty() {
+            format!("0x{}", key_id)
+        } else {
+            user_id.to_string()
+        };
+        // A second signature doesn't make up for a bad first one
+        if found.as_ref().is_none_or(|first| first.verdict == Verdict::Good) {
+            found = Some(Signature { verdict, signer, fingerprint: None, trusted: false });
+        }
+    }
+    found.map(|signature| Signature { fingerprint, trusted: trusted && signature.verdict == Verdict::Good, ..signature })
+}
+
+/// Why gpg failed, in words, when its status channel tells
+fn failure(status: &[String]) -> Option<String> {
+    status.iter().find_map(|line| {
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match keyword {
+            "NO_SECKEY" => Some(format!("No secret key 0x{} to decrypt with", rest.trim())),
+            "INV_RECP" => Some(format!("No usable key for {}", rest.split_once(' ').map_or(rest, |(_, who)| who))),
+            "INV_SGNR" => Some(format!("No usable signing key {}", rest.split_once(' ').map_or(rest, |(_, who)| who))),
+            "KEYEXPIRED" => Some("A key has expired".to_string()),
+            "DECRYPTION_FAILED" => Some("Decryption failed".to_string()),
+            _ => None,
+        }
+    })
+}
+
+/// Sign or encrypt the body of a message being sent as PGP/MIME (RFC 3156). `body` is
+/// the whole content of the message: text, HTML and attachments.
+pub fn protect(config: &PgpConfig, sender: &str, recipients: &[String], hidden: &[String], protection: Protection, body: MultiPart) -> Result<MultiPart, String> {
+    let gpg = Gpg::new(config);
+    // The signed part ends before the line break that comes with the next boundary
+    let mut entity = body.formatted();
+    entity.truncate(entity.len().saturating_sub(2));
+
+    if protection.encrypt {
+        let encrypted = gpg.encrypt(sender, recipients, hidden, protection.sign, &entity)?;
+        return Ok(MultiPart::encrypted(ENCRYPTED_TYPE.to_string())
+            .singlepart(SinglePart::builder().header(ContentType::parse(ENCRYPTED_TYPE).expect("valid content type")).body("Version: 1\r\n".to_string()))
+            .singlepart(
+                SinglePart::builder()
+                    .header(ContentType::parse("application/octet-stream; name=\"encrypted.asc\"").expect("valid content type"))
+                    .header(ContentDisposition::inline_with_name("encrypted.asc"))
+                    .body(String::from_utf8_lossy(&encrypted).into_owned()),
+            ));
+    }
+
+    let (signature, micalg) = gpg.sign_detached(sender, &entity)?;
+    Ok(MultiPart::signed(SIGNATURE_TYPE.to_string(), micalg)
+        .multipart(body)
+        .singlepart(
+            SinglePart::builder()
+                .header(ContentType::parse("application/pgp-signature; name=\"signature.asc\"").expect("valid content type"))
+                .header(ContentDisposition::attachment("signature.asc"))
+                .body(String::from_utf8_lossy(&signature).into_owned()),
+        ))
+}
+
+/// The first armored message or clear-signed text in `body`, by byte range
+fn armored_block(body: &str) -> Option<std::ops::Range<usize>> {
+    [(MESSAGE_BEGIN, MESSAGE_END), (SIGNED_BEGIN, SIGNATURE_END)]
+        .iter()
+        .filter_map(|(begin, end)| {
+            let start = body.find(begin)?;
+            let stop = start + body[start..].find(end)? + end.len();
+            Some(start..stop)
+        })
+        .min_by_key(|range| range.start)
+}
+
+/// The signed part of the first `multipart/signed` of a raw message, as the bytes the
+/// signature covers, and the signature
+fn signed_parts(raw: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let message = crate::mime::parse_message(raw)?;
+    message.parts.iter().find_map(|part| {
+        let content_type = part.content_type()?;
+        if !content_type.ctype().eq_ignore_ascii_case("multipart") || !content_type.subtype()?.eq_ignore_ascii_case("signed") {
+            return None;
+        }
+        let PartType::Multipart(children) = &part.body else {
+            return None;
+        };
+        let signed = message.parts.get(*children.first()?)?;
+        let signature = message.parts.get(*children.get(1)?)?;
+        let mut data = raw.get(signed.offset_header..signed.offset_end)?.to_vec();
+        // The line break before the boundary belongs to the boundary
+        if data.ends_with(b"\r\n") {
+            data.truncate(data.len() - 2);
+        } else if data.ends_with(b"\n") {
+            data.truncate(data.len() - 1);
+        }
+        Some((data, signature.contents().to_vec()))
+    })
+}
+
+/// Text and number of attachments of a decrypted MIME part
+fn decrypted_content(plaintext: &[u8]) -> (Option<String>, usize) {
+    let Some(message) = crate::mime::parse_message(plaintext) else {
+        return (Some(String::from_utf8_lossy(plaintext).into_owned()), 0);
+    };
+    let attachments = message.parts.iter().filter_map(crate::mime::attachment_from_part).count();
+    (message.body_text(0).map(|text| text.into_owned()), attachments)
+}
+
+/// Decrypt `email` or check its signature. `fetch_raw` gets the raw message from the
+/// server, which PGP/MIME signatures are checked against.
+pub fn open(config: &PgpConfig, email: &Email, kind: Kind, fetch_raw: impl FnOnce() -> Result<Vec<u8>, String>) -> Result<Opened, String> {
+    let gpg = Gpg::new(config);
+    match kind {
+        Kind::Encrypted => {
+            let ciphertext = email
+                .attachments
+                .iter()
+                .filter(|a| a.content_type != ENCRYPTED_TYPE)
+                .find_map(|a| a.load_data().ok().filter(|data| !data.is_empty()))
+                .ok_or_else(|| "The encrypted part is not downloaded yet".to_string())?;
+            let (plaintext, signature) = gpg.decrypt(&ciphertext)?;
+            // Signed and then encrypted as two layers rather than in one go
+            let (signature, plaintext) = match (signature, signed_parts(&plaintext)) {
+                (None, Some((signed, signature_data))) => (Some(gpg.verify_detached(&signed, &signature_data)?), signed),
+                (signature, _) => (signature, plaintext),
+            };
+            let (body, hidden_attachments) = decrypted_content(&plaintext);
+            Ok(Opened { decrypted: true, signature, body, hidden_attachments })
+        }
+        Kind::Signed => {
+            let raw = fetch_raw()?;
+            let (signed, signature_data) = signed_parts(&raw).ok_or_else(|| "No signed part found in the message".to_string())?;
+            let signature = gpg.verify_detached(&signed, &signature_data)?;
+            Ok(Opened { decrypted: false, signature: Some(signature), body: None, hidden_attachments: 0 })
+        }
+        Kind::Inline => {
+            let body = email.body_text.as_deref().unwrap_or_default();
+            let block = armored_block(body).ok_or_else(|| "No PGP block in the message".to_string())?;
+            let encrypted = body[block.clone()].starts_with(MESSAGE_BEGIN);
+            let (plaintext, signature) = gpg.decrypt(body[block.clone()].as_bytes())?;
+            let text = format!("{}{}{}", &body[..block.start], String::from_utf8_lossy(&plaintext), &body[block.end..]);
+            debug_log(&format!("pgp: opened inline block of {} bytes", block.len()));
+            Ok(Opened { decrypted: encrypted, signature, body: encrypted.then_some(text), hidden_attachments: 0 })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn signatures_are_read_from_gpg_status() {
+        let good = signature(&status(&[
+            "NEWSIG",
+            "GOODSIG 1234ABCD5678EF90 Alice <alice@example.com>",
+            "VALIDSIG 0123456789ABCDEF0123456789ABCDEF01234567 2024-03-06 1709730000 0 4 0 22 8 00 0123456789ABCDEF0123456789ABCDEF01234567",
+            "TRUST_ULTIMATE 0 pgp",
+        ]))
+        .unwrap();
+        assert_eq!(good.verdict, Verdict::Good);
+        assert!(good.trusted);
+        assert!(good.is_from("ALICE@example.com"));
+        assert_eq!(good.summary(), "Good signature from Alice <alice@example.com>");
+        assert_eq!(good.fingerprint.as_deref(), Some("0123456789ABCDEF0123456789ABCDEF01234567"));
+
+        let unknown = signature(&status(&["ERRSIG 1234ABCD5678EF90 22 8 00 1709730000 9 -", "NO_PUBKEY 1234ABCD5678EF90"])).unwrap();
+        assert_eq!(unknown.verdict, Verdict::UnknownKey);
+        assert_eq!(unknown.signer, "0x1234ABCD5678EF90");
+
+        let bad = signature(&status(&["BADSIG 1234ABCD5678EF90 Mallory <alice@example.com>", "TRUST_FULLY 0 pgp"])).unwrap();
+        assert_eq!(bad.verdict, Verdict::Bad);
+        assert!(!bad.trusted);
+        assert_eq!(signature(&status(&["DECRYPTION_OKAY"])), None);
+
+        assert_eq!(failure(&status(&["INV_RECP 0 bob@example.com"])).as_deref(), Some("No usable key for bob@example.com"));
+        assert_eq!(micalg("10"), Some("pgp-sha512"));
+    }
+
+    #[test]
+    fn signed_parts_and_armored_blocks_are_found() {
+        let raw = concat!(
+            "From: alice@example.com\r\n",
+            "Content-Type: multipart/signed; micalg=pgp-sha256; protocol=\"application/pgp-signature\"; boundary=\"b1\"\r\n",
+            "\r\n",
+            "--b1\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Hello\r\n",
+            "--b1\r\n",
+            "Content-Type: application/pgp-signature\r\n",
+            "\r\n",
+            "-----BEGIN PGP SIGNATURE-----\r\nabc\r\n-----END PGP SIGNATURE-----\r\n",
+            "--b1--\r\n",
+        );
+        let (signed, signature) = signed_parts(raw.as_bytes()).unwrap();
+        assert_eq!(signed, b"Content-Type: text/plain\r\n\r\nHello");
+        assert!(String::from_utf8_lossy(&signature).starts_with("-----BEGIN PGP SIGNATURE-----"));
+
+        let body = "Hi,\n-----BEGIN PGP MESSAGE-----\n\nhQEM\n-----END PGP MESSAGE-----\nBye";
+        assert_eq!(&body[armored_block(body).unwrap()], "-----BEGIN PGP MESSAGE-----\n\nhQEM\n-----END PGP MESSAGE-----");
+        assert_eq!(armored_block("-----BEGIN PGP MESSAGE-----\nunterminated"), None);
+    }
+}
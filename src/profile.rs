@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Profile selected for this process; `None` is the default (unnamed) profile
+static ACTIVE_PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Words added to the spell checker, in sections per language
+pub fn dictionary_path() -> PathBuf {
+    scoped(config_base()).join("dictionary.txt")
+}
+
+/// Keyring service prefix used by the default profile
+const DEFAULT_KEYRING_APP_NAME: &str = "email-client";
+
+/// Select the profile for this process. Must be called before any config, cache or
+/// credential paths are used; later calls are ignored.
+pub fn set_active(name: Option<String>) {
+    let _ = ACTIVE_PROFILE.set(name);
+}
+
+pub fn active() -> Option<&'static str> {
+    ACTIVE_PROFILE.get().and_then(|name| name.as_deref())
+}
+
+/// Profile names become directory and keyring service names, so keep them simple
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Profile name must be 1-64 characters".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid profile name '{}': use letters, digits, '-' and '_'", name));
+    }
+    Ok(())
+}
+
+/// Scope a tuimail base directory to the active profile
+fn scoped(base: PathBuf) -> PathBuf {
+    match active() {
+        Some(name) => base.join("profiles").join(name),
+        None => base,
+    }
+}
+
+/// `~/.config/tuimail` on every platform, where the config file has always been, rather
+/// than the platform's config directory (`~/Library/Application Support` on macOS)
+fn config_base() -> PathBuf {
+    dirs::home_dir().map(|home| home.join(".config")).unwrap_or_else(|| PathBuf::from(".")).join("tuimail")
+}
+
+/// Config file of the active profile
+pub fn config_path() -> PathBuf {
+    scoped(config_base()).join("config.json")
+}
+
+/// Directory holding the shared email database
+pub fn cache_dir() -> PathBuf {
+    scoped(dirs::cache_dir().unwrap_or_else(|| PathBuf::from(".")).join("tuimail"))
+}
+
+/// The shared email database the client syncs into
+pub fn database_path() -> PathBuf {
+    cache_dir().join("emails.db")
+}
+
+/// Per-account cache directory (account database and spilled attachments)
+pub fn account_cache_dir(account_email: &str) -> PathBuf {
+    let base = dirs::home_dir().unwrap_or_default().join(".cache").join("tuimail");
+    scoped(base).join(account_email.replace('@', "_at_").replace('.', "_"))
+}
+
+/// Directory for the file-based credential fallback, which has always been in the
+/// platform's config directory
+pub fn credentials_dir() -> PathBuf {
+    let base = dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("tuimail");
+    scoped(base).join("credentials")
+}
+
+/// Keyring service prefix, so profiles never share stored passwords
+pub fn keyring_app_name() -> String {
+    match active() {
+        Some(name) => format!("{}-{}", DEFAULT_KEYRING_APP_NAME, name),
+        None => DEFAULT_KEYRING_APP_NAME.to_string(),
+    }
+}
+
+/// Named profiles that have a config file, sorted by name
+pub fn list_profiles() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(config_base().join("profiles")) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().join("config.json").is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| validate_name(name).is_ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Whether the default (unnamed) profile has been set up
+pub fn default_profile_exists() -> bool {
+    config_base().join("config.json").is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("work").is_ok());
+        assert!(validate_name("home_2-old").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("my profile").is_err());
+    }
+
+    #[test]
+    fn test_config_stays_in_dot_config() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(config_base(), home.join(".config").join("tuimail"));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::config::{EmailAccount, ImapSecurity, SmtpSecurity, SpecialFolders};
+
+/// Server settings of a well-known mail provider, used to pre-fill new accounts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderPreset {
+    /// Short name accepted by `add-account --provider`
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Address domains served by this provider
+    pub domains: &'static [&'static str],
+    pub imap_server: &'static str,
+    pub imap_port: u16,
+    pub imap_security: ImapSecurity,
+    pub smtp_server: &'static str,
+    pub smtp_port: u16,
+    pub smtp_security: SmtpSecurity,
+    /// What the user has to do on the provider's side before a password login works
+    pub notes: &'static str,
+}
+
+pub const PRESETS: &[ProviderPreset] = &[
+    ProviderPreset {
+        id: "gmail",
+        name: "Gmail",
+        domains: &["gmail.com", "googlemail.com"],
+        imap_server: "imap.gmail.com",
+        imap_port: 993,
+        imap_security: ImapSecurity::SSL,
+        smtp_server: "smtp.gmail.com",
+        smtp_port: 587,
+        smtp_security: SmtpSecurity::StartTLS,
+        notes: "Gmail does not accept your normal password here. Turn on 2-Step Verification, \
+                then create an app password at myaccount.google.com/apppasswords and use that.",
+    },
+    ProviderPreset {
+        id: "outlook",
+        name: "Outlook / Microsoft 365",
+        domains: &["outlook.com", "hotmail.com", "live.com", "msn.com"],
+        imap_server: "outlook.office365.com",
+        imap_port: 993,
+        imap_security: ImapSecurity::SSL,
+        smtp_server: "smtp-mail.outlook.com",
+        smtp_port: 587,
+        smtp_security: SmtpSecurity::StartTLS,
+        notes: "Microsoft has switched most accounts to OAuth sign-in, which tuimail does not support yet. \
+                Password login only works where your organisation still allows it, usually with an app password.",
+    },
+    ProviderPreset {
+        id: "fastmail",
+        name: "Fastmail",
+        domains: &["fastmail.com", "fastmail.fm"],
+        imap_server: "imap.fastmail.com",
+        imap_port: 993,
+        imap_security: ImapSecurity::SSL,
+        smtp_server: "smtp.fastmail.com",
+        smtp_port: 465,
+        smtp_security: SmtpSecurity::SSL,
+        notes: "Create an app password under Settings > Privacy & Security > Integrations, \
+                with IMAP and SMTP access.",
+    },
+    ProviderPreset {
+        id: "yahoo",
+        name: "Yahoo Mail",
+        domains: &["yahoo.com", "ymail.com", "rocketmail.com"],
+        imap_server: "imap.mail.yahoo.com",
+        imap_port: 993,
+        imap_security: ImapSecurity::SSL,
+        smtp_server: "smtp.mail.yahoo.com",
+        smtp_port: 465,
+        smtp_security: SmtpSecurity::SSL,
+        notes: "Generate an app password under Account Info > Account Security and use it instead \
+                of your normal password.",
+    },
+    ProviderPreset {
+        id: "icloud",
+        name: "iCloud Mail",
+        domains: &["icloud.com", "me.com", "mac.com"],
+        imap_server: "imap.mail.me.com",
+        imap_port: 993,
+        imap_security: ImapSecurity::SSL,
+        smtp_server: "smtp.mail.me.com",
+        smtp_port: 587,
+        smtp_security: SmtpSecurity::StartTLS,
+        notes: "Create an app-specific password at account.apple.com (Sign-In and Security) \
+                and use it instead of your Apple Account password.",
+    },
+];
+
+/// Look up a preset by its id, ignoring case
+pub fn find(id: &str) -> Option<&'static ProviderPreset> {
+    PRESETS.iter().find(|p| p.id.eq_ignore_ascii_case(id))
+}
+
+/// The preset whose domains include the address's domain
+pub fn for_address(email: &str) -> Option<&'static ProviderPreset> {
+    let domain = email.rsplit_once('@')?.1.to_ascii_lowercase();
+    PRESETS.iter().find(|p| p.domains.contains(&domain.as_str()))
+}
+
+impl ProviderPreset {
+    /// A new account on this provider; these providers all log in with the full address
+    pub fn account(&self, name: &str, email: &str) -> EmailAccount {
+        EmailAccount {
+            name: name.to_string(),
+            email: email.to_string(),
+            imap_server: self.imap_server.to_string(),
+            imap_port: self.imap_port,
+            imap_security: self.imap_security.clone(),
+            imap_username: email.to_string(),
+            smtp_server: self.smtp_server.to_string(),
+            smtp_port: self.smtp_port,
+            smtp_security: self.smtp_security.clone(),
+            smtp_username: email.to_string(),
+            signature: None,
+            sync_interval: None,
+            compose: crate::config::ComposeDefaults::default(),
+            smtp_ip_family: None,
+            smtp_fallback: crate::config::SmtpFallback::default(),
+            internal_domains: Vec::new(),
+            maildir: None,
+            folder_options: Default::default(),
+            gmail_api: None,
+            exchange: None,
+            pgp: None,
+            caldav: None,
+            ldap: None,
+            special_folders: SpecialFolders::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_lookup() {
+        assert_eq!(find("Gmail").map(|p| p.imap_server), Some("imap.gmail.com"));
+        assert!(find("aol").is_none());
+        assert_eq!(for_address("someone@GoogleMail.com").map(|p| p.id), Some("gmail"));
+        assert_eq!(for_address("me@icloud.com").map(|p| p.id), Some("icloud"));
+        assert!(for_address("me@example.com").is_none());
+        assert!(for_address("not-an-address").is_none());
+
+        let account = find("fastmail").unwrap().account("Personal", "me@fastmail.com");
+        assert_eq!(account.imap_username, "me@fastmail.com");
+        assert_eq!((account.smtp_server.as_str(), account.smtp_port), ("smtp.fastmail.com", 465));
+    }
+}
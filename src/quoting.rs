@@ -0,0 +1,187 @@
+// Editing helpers for interleaved replies. Each takes the compose body and the cursor's
+// byte offset and returns the new body and cursor.
+
+fn is_quoted(line: &str) -> bool {
+    line.starts_with('>')
+}
+
+/// The `> ` or `> > ` prefix of a quoted line
+fn quote_prefix(line: &str) -> &str {
+    let end = line
+        .char_indices()
+        .find(|(_, c)| *c != '>' && *c != ' ')
+        .map(|(i, _)| i)
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+/// Clamp a cursor to the body and to a character boundary
+fn clamp(body: &str, cursor: usize) -> usize {
+    let mut cursor = cursor.min(body.len());
+    while !body.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    cursor
+}
+
+/// Byte range of the line holding `cursor`, without its newline
+fn line_at(body: &str, cursor: usize) -> (usize, usize) {
+    let start = body[..cursor].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let end = body[cursor..].find('\n').map(|i| cursor + i).unwrap_or(body.len());
+    (start, end)
+}
+
+/// Split the quoted text at the cursor and open an unquoted paragraph there for the
+/// answer. The rest of a line split in the middle stays quoted below the answer.
+pub fn split_quote(body: &str, cursor: usize) -> (String, usize) {
+    let cursor = clamp(body, cursor);
+    let (start, end) = line_at(body, cursor);
+    let line = &body[start..end];
+    if !is_quoted(line) {
+        return (body.to_string(), cursor);
+    }
+
+    let prefix = quote_prefix(line);
+    let (before, after) = if cursor <= start + prefix.len() {
+        // At the start of the line: answer above it
+        (&body[..start], body[start..].to_string())
+    } else {
+        let rest = body[cursor..].trim_start_matches(' ');
+        let before = body[..cursor].trim_end_matches(' ');
+        if cursor == end {
+            (before, rest.strip_prefix('\n').unwrap_or(rest).to_string())
+        } else {
+            (before, format!("{}{}", prefix, rest))
+        }
+    };
+
+    let mut result = before.to_string();
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push('\n');
+    let new_cursor = result.len();
+    result.push('\n');
+    if !after.is_empty() {
+        result.push('\n');
+        result.push_str(&after);
+    }
+    (result, new_cursor)
+}
+
+/// Quote the paragraph around the cursor, one level deeper if it is quoted already
+pub fn quote_paragraph(body: &str, cursor: usize) -> (String, usize) {
+    let cursor = clamp(body, cursor);
+    let lines: Vec<&str> = body.split('\n').collect();
+    let current = body[..cursor].matches('\n').count();
+    if lines[current].trim().is_empty() {
+        return (body.to_string(), cursor);
+    }
+
+    let mut first = current;
+    while first > 0 && !lines[first - 1].trim().is_empty() {
+        first -= 1;
+    }
+    let mut last = current;
+    while last + 1 < lines.len() && !lines[last + 1].trim().is_empty() {
+        last += 1;
+    }
+
+    let mut result = Vec::with_capacity(lines.len());
+    let mut new_cursor = cursor;
+    for (i, line) in lines.iter().enumerate() {
+        if i < first || i > last {
+            result.push(line.to_string());
+            continue;
+        }
+        let prefix = if is_quoted(line) { ">" } else { "> " };
+        if i <= current {
+            new_cursor += prefix.len();
+        }
+        result.push(format!("{}{}", prefix, line));
+    }
+    (result.join("\n"), new_cursor)
+}
+
+/// Remove the quoted text below the cursor's line, with the "On ... wrote:" lines that
+/// introduce it, leaving single blank lines between the remaining paragraphs
+pub fn strip_quotes_below(body: &str, cursor: usize) -> (String, usize) {
+    let cursor = clamp(body, cursor);
+    let (_, end) = line_at(body, cursor);
+    let below: Vec<&str> = body[end..].split('\n').skip(1).collect();
+
+    let mut kept: Vec<&str> = Vec::new();
+    for (i, line) in below.iter().enumerate() {
+        if is_quoted(line) {
+            continue;
+        }
+        let introduces_quote = line.trim_end().ends_with("wrote:") && below.get(i + 1).is_some_and(|next| is_quoted(next));
+        if introduces_quote {
+            continue;
+        }
+        if line.trim().is_empty() && kept.last().is_some_and(|last| last.trim().is_empty()) {
+            continue;
+        }
+        kept.push(line);
+    }
+    while kept.last().is_some_and(|last| last.trim().is_empty()) {
+        kept.pop();
+    }
+
+    let mut result = body[..end].to_string();
+    for line in kept {
+        result.push('\n');
+        result.push_str(line);
+    }
+    result.push('\n');
+    (result, cursor)
+}
+
+/// Put a signature under the space left for typing, above any quoted or forwarded text
+pub fn with_signature(body: &str, signature: &str) -> String {
+    let rest = body.trim_start_matches('\n');
+    let mut result = format!("\n\n-- \n{}\n", signature.trim_end());
+    if !rest.is_empty() {
+        result.push('\n');
+        result.push_str(rest);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interleaved_reply_editing() {
+        let body = "Hi\n\nOn Monday Bob wrote:\n> First point.\n> Second point.\n";
+
+        // At the start of a quoted line the answer goes above it
+        let cursor = body.find("Second").unwrap() - 2;
+        let (split, cursor) = split_quote(body, cursor);
+        assert_eq!(split, "Hi\n\nOn Monday Bob wrote:\n> First point.\n\n\n\n> Second point.\n");
+        assert_eq!(&split[cursor..cursor + 1], "\n");
+        assert_eq!(split[..cursor].lines().last(), Some(""));
+
+        // In the middle of a line the rest stays quoted below the answer
+        let middle = body.find("point.\n> Second").unwrap();
+        let (split, _) = split_quote(body, middle);
+        assert!(split.contains("> First\n\n\n\n> point.\n> Second point.\n"));
+
+        // Outside quoted text nothing happens
+        assert_eq!(split_quote(body, 1).0, body);
+
+        let (quoted, cursor) = quote_paragraph("Intro\n\nmy answer\nsecond line\n", 12);
+        assert_eq!(quoted, "Intro\n\n> my answer\n> second line\n");
+        assert_eq!(cursor, 14);
+        assert_eq!(quote_paragraph("> old\n", 0).0, ">> old\n");
+
+        let (stripped, cursor) = strip_quotes_below(body, 1);
+        assert_eq!(stripped, "Hi\n");
+        assert_eq!(cursor, 1);
+        let answered = "> Question?\nAnswer.\n\nOn Monday Bob wrote:\n> Old\n\nThanks\n";
+        assert_eq!(with_signature("", "Ann\n"), "\n\n-- \nAnn\n");
+        assert_eq!(with_signature("\n\n\n\nOn Monday Bob wrote:\n> Hi", "Ann"), "\n\n-- \nAnn\n\nOn Monday Bob wrote:\n> Hi");
+        assert_eq!(strip_quotes_below(answered, answered.find("Answer").unwrap()).0, "> Question?\nAnswer.\n\nThanks\n");
+    }
+}
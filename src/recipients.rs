@@ -0,0 +1,111 @@
+use crate::database::ContactSummary;
+
+/// Whether two strings differ by exactly one insertion, deletion, substitution or swap
+/// of adjacent characters
+pub fn one_edit_apart(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a == b || a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    match a.len().cmp(&b.len()) {
+        std::cmp::Ordering::Less => a == &b[1..],
+        std::cmp::Ordering::Greater => &a[1..] == b,
+        std::cmp::Ordering::Equal => {
+            a[1..] == b[1..] || (a.len() >= 2 && a[0] == b[1] && a[1] == b[0] && a[2..] == b[2..])
+        }
+    }
+}
+
+/// A known address the typed one is probably a typo of: unknown itself and one edit
+/// away from a known address. `known` is in order of preference, e.g. most
+/// corresponded-with first.
+pub fn suggest_correction<'a>(typed: &str, known: &'a [String]) -> Option<&'a str> {
+    let typed = typed.trim().to_lowercase();
+    if !typed.contains('@') || known.iter().any(|address| address.eq_ignore_ascii_case(&typed)) {
+        return None;
+    }
+    known
+        .iter()
+        .find(|address| one_edit_apart(&typed, &address.to_lowercase()))
+        .map(String::as_str)
+}
+
+/// Contacts a name typed in place of an address could mean: every word typed starts a
+/// word of the contact's name, or the query starts the local part of the address.
+/// Matching ignores case; `contacts` keep their order.
+pub fn find_by_name<'a>(query: &str, contacts: &'a [ContactSummary]) -> Vec<&'a ContactSummary> {
+    let query = query.trim().to_lowercase();
+    let typed: Vec<&str> = query.split_whitespace().collect();
+    if typed.is_empty() {
+        return Vec::new();
+    }
+    contacts
+        .iter()
+        .filter(|contact| {
+            let name = contact.name.as_deref().unwrap_or_default().to_lowercase();
+            let words: Vec<&str> = name.split(|c: char| c.is_whitespace() || matches!(c, ',' | '.' | '-' | '"')).collect();
+            let by_name = typed.iter().all(|part| words.iter().any(|word| word.starts_with(part)));
+            let local = contact.address.split('@').next().unwrap_or_default();
+            by_name || (typed.len() == 1 && local.starts_with(typed[0]))
+        })
+        .collect()
+}
+
+/// Byte range of the comma-separated entry of an address field the cursor is in,
+/// without the spaces around it
+pub fn entry_at(text: &str, cursor: usize) -> std::ops::Range<usize> {
+    let cursor = cursor.min(text.len());
+    let start = text[..cursor].rfind(',').map_or(0, |comma| comma + 1);
+    let end = text[cursor..].find(',').map_or(text.len(), |comma| cursor + comma);
+    let entry = &text[start..end];
+    let leading = entry.len() - entry.trim_start().len();
+    start + leading..start + entry.trim_end().len().max(leading)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(name: &str, address: &str) -> ContactSummary {
+        ContactSummary { address: address.to_string(), name: Some(name.to_string()), last_activity: 0, message_count: 1, saved: false }
+    }
+
+    #[test]
+    fn names_find_contacts() {
+        let contacts = vec![
+            contact("John Smith", "john@example.com"),
+            contact("Smith, Ann", "ann.smith@example.org"),
+            contact("Johanna Berg", "jberg@example.net"),
+        ];
+        let addresses = |query: &str| find_by_name(query, &contacts).iter().map(|c| c.address.as_str()).collect::<Vec<_>>();
+        assert_eq!(addresses("smith"), vec!["john@example.com", "ann.smith@example.org"]);
+        assert_eq!(addresses("Jo"), vec!["john@example.com", "jberg@example.net"]);
+        assert_eq!(addresses("ann sm"), vec!["ann.smith@example.org"]);
+        assert_eq!(addresses("jberg"), vec!["jberg@example.net"]);
+        assert!(addresses("  ").is_empty());
+        assert!(addresses("john berg").is_empty());
+
+        let text = "a@example.com,  john , b@example.com";
+        assert_eq!(&text[entry_at(text, 17)], "john");
+        assert_eq!(&text[entry_at(text, 0)], "a@example.com");
+        assert_eq!(&text[entry_at(text, text.len())], "b@example.com");
+        assert_eq!(entry_at("a, ", 3), 3..3);
+    }
+
+    #[test]
+    fn near_misses_of_known_addresses_are_suggested() {
+        let known = vec!["john@example.com".to_string(), "ann@example.org".to_string()];
+        assert_eq!(suggest_correction("jhon@example.com", &known), Some("john@example.com"));
+        assert_eq!(suggest_correction("John@exmple.com", &known), Some("john@example.com"));
+        assert_eq!(suggest_correction("ann@example.orgg", &known), Some("ann@example.org"));
+        assert_eq!(suggest_correction("anne@example.org", &known), Some("ann@example.org"));
+
+        assert_eq!(suggest_correction("JOHN@example.com", &known), None);
+        assert_eq!(suggest_correction("joan@example.org", &known), None);
+        assert_eq!(suggest_correction("jo", &known), None);
+    }
+}
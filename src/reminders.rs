@@ -0,0 +1,177 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Weekday};
+
+use crate::email::{debug_log, Email};
+
+/// A reminder about a message, kept in the local database until it is dismissed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reminder {
+    pub id: i64,
+    pub account_email: String,
+    /// Where the message was when the reminder was set, and its Message-ID to find it
+    /// again after it has been moved
+    pub folder: String,
+    pub uid: u32,
+    pub message_id: Option<String>,
+    pub subject: String,
+    pub sender: String,
+    /// Unix time the reminder is due
+    pub remind_at: i64,
+    /// Whether it has gone off
+    pub fired: bool,
+}
+
+impl Reminder {
+    /// A reminder about `email` due at `remind_at`, not yet stored
+    pub fn new(account_email: &str, email: &Email, remind_at: DateTime<Local>) -> Self {
+        let sender = email
+            .from
+            .first()
+            .map(|from| from.name.clone().filter(|name| !name.is_empty()).unwrap_or_else(|| from.address.clone()))
+            .unwrap_or_default();
+        Self {
+            id: 0,
+            account_email: account_email.to_string(),
+            folder: email.folder.clone(),
+            uid: email.id.parse().unwrap_or(0),
+            message_id: crate::database::message_key(email),
+            subject: email.subject.clone(),
+            sender,
+            remind_at: remind_at.timestamp(),
+            fired: false,
+        }
+    }
+}
+
+/// Mornings start at nine for `tomorrow`, `next week` and weekdays
+fn morning() -> NaiveTime {
+    NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default()
+}
+
+fn at(date: chrono::NaiveDate, time: NaiveTime) -> Result<DateTime<Local>, String> {
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| "That time does not exist here".to_string())
+}
+
+/// When a reminder typed as `30m`, `2h`, `in 2 days`, `1 week`, `tomorrow`, `next week`,
+/// a weekday such as `friday` (the next one, at nine) or a time such as `17:30` (today,
+/// or tomorrow once it has passed) is due, counting from `now`
+pub fn parse_when(text: &str, now: DateTime<Local>) -> Result<DateTime<Local>, String> {
+    let text = text.trim().to_lowercase();
+    let text = text.strip_prefix("in ").unwrap_or(&text).trim();
+    let invalid = || format!("Can't tell when '{}' is; try 30m, 2h, 2 days, tomorrow, friday or 17:30", text);
+
+    match text {
+        "tomorrow" => return at(now.date_naive() + Duration::days(1), morning()),
+        "next week" => {
+            let days = 7 - i64::from(now.weekday().num_days_from_monday());
+            return at(now.date_naive() + Duration::days(days), morning());
+        }
+        _ => {}
+    }
+    if let Ok(weekday) = text.parse::<Weekday>() {
+        let days = (7 + i64::from(weekday.num_days_from_monday()) - i64::from(now.weekday().num_days_from_monday())) % 7;
+        return at(now.date_naive() + Duration::days(if days == 0 { 7 } else { days }), morning());
+    }
+    if let Ok(time) = NaiveTime::parse_from_str(text, "%H:%M") {
+        let today = at(now.date_naive(), time)?;
+        return if today > now { Ok(today) } else { at(now.date_naive() + Duration::days(1), time) };
+    }
+
+    let split = text.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let count: i64 = text[..split].parse().map_err(|_| invalid())?;
+    let duration = match text[split..].trim() {
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::minutes(count),
+        "h" | "hour" | "hours" => Duration::hours(count),
+        "d" | "day" | "days" => Duration::days(count),
+        "w" | "week" | "weeks" => Duration::weeks(count),
+        _ => return Err(invalid()),
+    };
+    if count == 0 || count > 10_000 {
+        return Err(invalid());
+    }
+    Ok(now + duration)
+}
+
+/// Show a desktop notification through the system's tool for it, if there is one;
+/// the in-app message is shown either way
+pub fn notify_desktop(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            body.replace(['"', '\\'], ""),
+            title.replace(['"', '\\'], "")
+        );
+        let mut command = std::process::Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = std::process::Command::new("notify-send");
+        command.args(["--app-name=tuimail", title, body]);
+        command
+    };
+    let spawned = command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+    if let Err(e) = spawned {
+        debug_log(&format!("No desktop notification: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminder_times_are_parsed() {
+        // A Wednesday afternoon
+        let now = Local.with_ymd_and_hms(2024, 3, 6, 15, 0, 0).unwrap();
+        assert_eq!(parse_when("30m", now).unwrap(), now + Duration::minutes(30));
+        assert_eq!(parse_when("in 2 days", now).unwrap(), now + Duration::days(2));
+        assert_eq!(parse_when("1 Week", now).unwrap(), now + Duration::weeks(1));
+        assert_eq!(parse_when("tomorrow", now).unwrap(), Local.with_ymd_and_hms(2024, 3, 7, 9, 0, 0).unwrap());
+        assert_eq!(parse_when("next week", now).unwrap(), Local.with_ymd_and_hms(2024, 3, 11, 9, 0, 0).unwrap());
+        assert_eq!(parse_when("friday", now).unwrap(), Local.with_ymd_and_hms(2024, 3, 8, 9, 0, 0).unwrap());
+        assert_eq!(parse_when("wednesday", now).unwrap(), Local.with_ymd_and_hms(2024, 3, 13, 9, 0, 0).unwrap());
+        assert_eq!(parse_when("17:30", now).unwrap(), Local.with_ymd_and_hms(2024, 3, 6, 17, 30, 0).unwrap());
+        assert_eq!(parse_when("08:00", now).unwrap(), Local.with_ymd_and_hms(2024, 3, 7, 8, 0, 0).unwrap());
+        assert!(parse_when("soon", now).is_err());
+        assert!(parse_when("0h", now).is_err());
+        assert!(parse_when("2 fortnights", now).is_err());
+    }
+
+    #[test]
+    fn reminders_go_off_once_and_are_dismissed() {
+        let dir = std::env::temp_dir().join(format!("tuimail-reminders-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = crate::database::EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        let mut email = Email::new();
+        email.id = "1".to_string();
+        email.folder = "INBOX".to_string();
+        email.subject = "Contract".to_string();
+        email.headers.insert("Message-ID".to_string(), "<Contract@example.com>".to_string());
+
+        let now = Local::now();
+        let soon = Reminder::new("me@example.com", &email, now + Duration::hours(2));
+        let later = Reminder::new("me@example.com", &email, now + Duration::days(2));
+        db.add_reminder(&later).unwrap();
+        let id = db.add_reminder(&soon).unwrap();
+        assert!(db.fire_due_reminders(now.timestamp()).unwrap().is_empty());
+
+        let due = db.fire_due_reminders((now + Duration::hours(3)).timestamp()).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!((due[0].id, due[0].subject.as_str(), due[0].message_id.as_deref()), (id, "Contract", Some("Contract@example.com")));
+        assert!(db.fire_due_reminders((now + Duration::hours(3)).timestamp()).unwrap().is_empty());
+
+        // Due ones are listed first until dismissed
+        let listed = db.get_reminders().unwrap();
+        assert!(listed[0].fired && !listed[1].fired);
+        db.dismiss_reminder(id).unwrap();
+        assert_eq!(db.get_reminders().unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,168 @@
+use chrono::{DateTime, Datelike, Local};
+
+use crate::config::ReplyWarningConfig;
+use crate::email::{Email, EmailAddress};
+
+/// A reply waiting for confirmation
+#[derive(Debug, Clone)]
+pub struct PendingReply {
+    /// Whether it is a reply to all
+    pub all: bool,
+    /// Why the reply may be a mistake
+    pub warnings: Vec<String>,
+    /// For a guarded reply to all, its recipients, each with whether it is outside your
+    /// domains
+    pub recipients: Vec<(EmailAddress, bool)>,
+}
+
+/// Whole months from `date` to `now`
+pub fn months_between(date: DateTime<Local>, now: DateTime<Local>) -> u32 {
+    let months = (now.year() - date.year()) * 12 + now.month() as i32 - date.month() as i32;
+    let months = if now.day() < date.day() { months - 1 } else { months };
+    months.max(0) as u32
+}
+
+/// Whether an address matches one of the no-reply patterns
+pub fn is_no_reply(address: &str, patterns: &[String]) -> bool {
+    let normalize = |text: &str| -> String {
+        text.chars()
+            .filter(|c| !matches!(c, '.' | '-' | '_'))
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let address = normalize(address);
+    patterns
+        .iter()
+        .map(|pattern| normalize(pattern))
+        .any(|pattern| !pattern.is_empty() && address.contains(&pattern))
+}
+
+/// Reasons a reply to this message may be pointless, to confirm before composing. Empty
+/// when nothing stands out.
+pub fn warnings(email: &Email, config: &ReplyWarningConfig, now: DateTime<Local>) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if let Some(limit) = config.older_than_months {
+        let months = months_between(email.date, now);
+        if months >= limit.max(1) {
+            let age = match months {
+                m if m >= 24 && m % 12 == 0 => format!("{} years", m / 12),
+                1 => "1 month".to_string(),
+                m => format!("{} months", m),
+            };
+            warnings.push(format!("This message is {} old", age));
+        }
+    }
+
+    // A reply goes to Reply-To when there is one
+    let reply_to = email.reply_to();
+    let recipients = if reply_to.is_empty() { &email.from } else { &reply_to };
+    for recipient in recipients {
+        if is_no_reply(&recipient.address, &config.no_reply_patterns) {
+            warnings.push(format!("{} is likely unmonitored", recipient.address));
+        }
+    }
+    warnings
+}
+
+/// The To and Cc of a reply to all: the sender (or Reply-To) and every recipient of
+/// `email` except `own_address`, each address once
+pub fn reply_all_recipients(email: &Email, own_address: &str) -> (Vec<EmailAddress>, Vec<EmailAddress>) {
+    let reply_to = email.reply_to();
+    let senders = if reply_to.is_empty() { &email.from } else { &reply_to };
+    let mut to: Vec<EmailAddress> = Vec::new();
+    let mut cc: Vec<EmailAddress> = Vec::new();
+    for addr in senders.iter().chain(&email.to) {
+        if addr.address != own_address && !to.iter().any(|existing| existing.address == addr.address) {
+            to.push(addr.clone());
+        }
+    }
+    for addr in &email.cc {
+        if addr.address != own_address && !cc.iter().any(|existing| existing.address == addr.address) {
+            cc.push(addr.clone());
+        }
+    }
+    (to, cc)
+}
+
+/// Whether `address` belongs to one of `domains` or a subdomain of one
+pub fn is_own_domain(address: &str, domains: &[String]) -> bool {
+    let Some((_, domain)) = address.rsplit_once('@') else {
+        return false;
+    };
+    let domain = domain.trim_end_matches('>').to_lowercase();
+    domains.iter().map(|own| own.trim().trim_start_matches('@').to_lowercase()).any(|own| {
+        !own.is_empty() && (domain == own || domain.ends_with(&format!(".{}", own)))
+    })
+}
+
+/// Reasons to show the recipient list before a reply to all: too many recipients, or
+/// some outside `own_domains`
+pub fn reply_all_warnings(recipients: &[EmailAddress], config: &ReplyWarningConfig, own_domains: &[String]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(limit) = config.reply_all_limit {
+        if recipients.len() > limit {
+            warnings.push(format!("Reply to all would address {} recipients", recipients.len()));
+        }
+    }
+    let outside = recipients.iter().filter(|r| !is_own_domain(&r.address, own_domains)).count();
+    if outside > 0 && !own_domains.is_empty() {
+        warnings.push(match outside {
+            1 => "1 recipient is outside your domains".to_string(),
+            n => format!("{} recipients are outside your domains", n),
+        });
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailAddress;
+    use chrono::TimeZone;
+
+    #[test]
+    fn old_and_no_reply_messages_are_flagged() {
+        let now = Local.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap();
+        let config = ReplyWarningConfig::default();
+        let mut email = Email::new();
+        email.from = vec![EmailAddress { name: None, address: "alice@example.com".to_string() }];
+        email.date = Local.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+        assert!(warnings(&email, &config, now).is_empty());
+
+        email.date = Local.with_ymd_and_hms(2024, 4, 20, 9, 0, 0).unwrap();
+        assert_eq!(months_between(email.date, now), 13);
+        assert_eq!(warnings(&email, &config, now), vec!["This message is 13 months old"]);
+
+        email.date = now;
+        email.from[0].address = "No_Reply@shop.example".to_string();
+        assert_eq!(warnings(&email, &config, now), vec!["No_Reply@shop.example is likely unmonitored"]);
+
+        // Replies go to Reply-To, which is monitored here
+        email.headers.insert("Reply-To".to_string(), "support@shop.example".to_string());
+        assert!(warnings(&email, &config, now).is_empty());
+    }
+
+    #[test]
+    fn large_and_external_reply_alls_are_flagged() {
+        let address = |address: &str| EmailAddress { name: None, address: address.to_string() };
+        let mut email = Email::new();
+        email.from = vec![address("boss@corp.example")];
+        email.to = vec![address("me@corp.example"), address("team@lists.corp.example")];
+        email.cc = vec![address("boss@corp.example"), address("partner@other.example")];
+
+        let (to, cc) = reply_all_recipients(&email, "me@corp.example");
+        let addresses = |list: &[EmailAddress]| list.iter().map(|a| a.address.clone()).collect::<Vec<_>>();
+        assert_eq!(addresses(&to), vec!["boss@corp.example", "team@lists.corp.example"]);
+        assert_eq!(addresses(&cc), vec!["boss@corp.example", "partner@other.example"]);
+
+        let own = vec!["corp.example".to_string()];
+        let config = ReplyWarningConfig::default();
+        let recipients: Vec<EmailAddress> = to.iter().chain(&cc).cloned().collect();
+        assert_eq!(reply_all_warnings(&recipients, &config, &own), vec!["1 recipient is outside your domains"]);
+        assert!(reply_all_warnings(&recipients[..2], &config, &own).is_empty());
+
+        let crowd: Vec<EmailAddress> = (0..11).map(|i| address(&format!("user{}@corp.example", i))).collect();
+        assert_eq!(reply_all_warnings(&crowd, &config, &own), vec!["Reply to all would address 11 recipients"]);
+    }
+}
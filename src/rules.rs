@@ -0,0 +1,156 @@
+use chrono::{DateTime, Duration, Local};
+
+use crate::config::{Config, FilterRule, RuleAction};
+use crate::email::{Email, EmailAddress};
+
+/// Days of stored mail evaluated by the dry-run report unless the user changes it
+pub const DEFAULT_DRY_RUN_DAYS: u32 = 30;
+
+/// One action the rules or the retention policy would have taken
+#[derive(Debug, Clone)]
+pub struct DryRunEntry {
+    pub folder: String,
+    pub date: DateTime<Local>,
+    pub from: String,
+    pub subject: String,
+    pub action: String,
+    /// Rule name, or "auto-archive"
+    pub source: String,
+}
+
+/// Whether a rule applies to a message of the given account
+pub fn rule_matches(rule: &FilterRule, account_email: &str, email: &Email) -> bool {
+    if rule.account.as_ref().is_some_and(|account| !account.eq_ignore_ascii_case(account_email)) {
+        return false;
+    }
+    if rule.folder.as_ref().is_some_and(|folder| *folder != email.folder) {
+        return false;
+    }
+    if let Some(needle) = &rule.from_contains {
+        if !addresses_contain(&email.from, needle) {
+            return false;
+        }
+    }
+    if let Some(needle) = &rule.to_contains {
+        if !addresses_contain(&email.to, needle) && !addresses_contain(&email.cc, needle) {
+            return false;
+        }
+    }
+    if let Some(needle) = &rule.subject_contains {
+        if !email.subject.to_lowercase().contains(&needle.to_lowercase()) {
+            return false;
+        }
+    }
+    true
+}
+
+fn addresses_contain(addresses: &[EmailAddress], needle: &str) -> bool {
+    let needle = needle.to_lowercase();
+    addresses.iter().any(|addr| {
+        addr.address.to_lowercase().contains(&needle)
+            || addr.name.as_ref().is_some_and(|name| name.to_lowercase().contains(&needle))
+    })
+}
+
+pub fn describe_action(action: &RuleAction) -> String {
+    match action {
+        RuleAction::Move { folder } => format!("move to {}", folder),
+        RuleAction::MarkRead => "mark as read".to_string(),
+        RuleAction::Delete => "delete".to_string(),
+    }
+}
+
+/// Report what the filter rules and the auto-archive policy would have done to `emails`
+/// over the last `days` days, without touching anything.
+///
+/// Rules are evaluated for mail received in the window; the first matching rule wins.
+/// Auto-archive reports messages whose archive date fell inside the window and that
+/// no rule had already moved or deleted.
+pub fn dry_run(config: &Config, account_email: &str, emails: &[Email], now: DateTime<Local>, days: u32) -> Vec<DryRunEntry> {
+    let window_start = now - Duration::days(days as i64);
+    let retention = &config.retention;
+    let mut entries = Vec::new();
+
+    for email in emails {
+        let entry = |action: String, source: &str| DryRunEntry {
+            folder: email.folder.clone(),
+            date: email.date,
+            from: email
+                .from
+                .first()
+                .map(|addr| addr.name.clone().filter(|n| !n.is_empty()).unwrap_or_else(|| addr.address.clone()))
+                .unwrap_or_default(),
+            subject: email.subject.clone(),
+            action,
+            source: source.to_string(),
+        };
+
+        let mut moved = false;
+        if email.date >= window_start {
+            if let Some(rule) = config.rules.iter().find(|rule| rule_matches(rule, account_email, email)) {
+                moved = matches!(rule.action, RuleAction::Move { .. } | RuleAction::Delete);
+                entries.push(entry(describe_action(&rule.action), &rule.name));
+            }
+        }
+
+        if let Some(after_days) = retention.archive_after_days {
+            let archive_date = email.date + Duration::days(after_days as i64);
+            let eligible = !moved
+                && retention.folders.contains(&email.folder)
+                && email.folder != retention.archive_folder
+                && (email.seen || !retention.only_read)
+                && archive_date >= window_start
+                && archive_date <= now;
+            if eligible {
+                entries.push(entry(format!("archive to {}", retention.archive_folder), "auto-archive"));
+            }
+        }
+    }
+
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.date));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(from: &str, subject: &str, age_days: i64, seen: bool, now: DateTime<Local>) -> Email {
+        let mut email = Email::new();
+        email.id = subject.to_string();
+        email.folder = "INBOX".to_string();
+        email.from = vec![EmailAddress { name: None, address: from.to_string() }];
+        email.subject = subject.to_string();
+        email.date = now - Duration::days(age_days);
+        email.seen = seen;
+        email
+    }
+
+    #[test]
+    fn test_dry_run_rules_and_archive() {
+        let now = Local::now();
+        let mut config = Config::default();
+        config.rules.push(FilterRule {
+            name: "newsletters".to_string(),
+            account: None,
+            folder: Some("INBOX".to_string()),
+            from_contains: Some("news@".to_string()),
+            to_contains: None,
+            subject_contains: None,
+            action: RuleAction::Move { folder: "News".to_string() },
+        });
+        config.retention.archive_after_days = Some(60);
+
+        let emails = vec![
+            email("news@shop.example", "Weekly deals", 2, false, now),
+            email("friend@example.com", "Lunch?", 3, true, now),
+            email("boss@example.com", "Old report", 70, true, now),  // archived 10 days ago
+            email("boss@example.com", "Older report", 100, true, now), // archived before the window
+            email("boss@example.com", "Unread report", 70, false, now), // unread mail stays
+        ];
+
+        let report = dry_run(&config, "me@example.com", &emails, now, 30);
+        let summary: Vec<(&str, &str)> = report.iter().map(|e| (e.subject.as_str(), e.action.as_str())).collect();
+        assert_eq!(summary, vec![("Weekly deals", "move to News"), ("Old report", "archive to Archive")]);
+    }
+}
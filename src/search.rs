@@ -0,0 +1,339 @@
+use chrono::TimeZone;
+use rusqlite::types::Value;
+
+/// Filter names offered by autocomplete, in the order they are listed
+pub const FILTERS: &[&str] = &[
+    "from:",
+    "to:",
+    "subject:",
+    "has:attachment",
+    "is:unread",
+    "is:read",
+    "before:",
+    "after:",
+    "folder:",
+    "account:",
+    "tag:",
+    "on:server",
+];
+
+/// A mail search parsed from a query such as
+/// `from:alice subject:"quarterly report" has:attachment after:2024-03-01 invoice`.
+///
+/// Supported terms:
+/// - `from:<text>` / `to:<text>`   sender, or To/Cc recipient, name or address contains the text
+/// - `subject:<text>`   subject contains the text
+/// - `has:attachment`, `is:unread`, `is:read`
+/// - `after:<date>` / `before:<date>`   message date bounds (YYYY-MM-DD)
+/// - `folder:<name>`   only this folder (any case)
+/// - `account:<text>`  search the account whose address or name contains the text
+/// - `tag:<tag>`   tagged with exactly this tag
+/// - `<field>:<text>`  the configured header field (see `header_fields`) contains the text
+/// - `on:server`   also search the server, for folders that are not fully synced
+///
+/// Values with spaces are quoted. Any other word must appear in the subject, the
+/// body, the sender, or the name or text of an attachment (see `attachment_text`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+    pub subject: Vec<String>,
+    pub has_attachment: bool,
+    pub unread: Option<bool>,
+    pub after: Option<i64>,
+    pub before: Option<i64>,
+    pub folder: Option<String>,
+    pub account: Option<String>,
+    pub tags: Vec<String>,
+    /// Header field names with the text their value must contain
+    pub fields: Vec<(String, String)>,
+    pub words: Vec<String>,
+    /// Also ask the server with `to_imap`
+    pub server: bool,
+}
+
+impl SearchQuery {
+    /// Parse a search query. Unknown `has:`/`is:` values and bad dates are errors.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut search = SearchQuery::default();
+
+        for term in tokenize(query) {
+            let Some((key, value)) = term.split_once(':').filter(|(_, value)| !value.is_empty()) else {
+                search.words.push(term.to_lowercase());
+                continue;
+            };
+
+            match key.to_lowercase().as_str() {
+                "from" => search.from.push(value.to_lowercase()),
+                "to" => search.to.push(value.to_lowercase()),
+                "subject" => search.subject.push(value.to_lowercase()),
+                "has" if value.eq_ignore_ascii_case("attachment") => search.has_attachment = true,
+                "is" if value.eq_ignore_ascii_case("unread") => search.unread = Some(true),
+                "is" if value.eq_ignore_ascii_case("read") => search.unread = Some(false),
+                "on" if value.eq_ignore_ascii_case("server") => search.server = true,
+                "has" | "is" | "on" => return Err(format!("Unknown filter '{}'", term)),
+                "after" => search.after = Some(crate::attachment_index::parse_date(value)?),
+                "before" => search.before = Some(crate::attachment_index::parse_date(value)?),
+                "folder" => search.folder = Some(value.to_string()),
+                "account" => search.account = Some(value.to_lowercase()),
+                "tag" => search.tags.push(value.to_string()),
+                field if crate::header_fields::is_field(field) => {
+                    search.fields.push((field.to_string(), value.to_lowercase()))
+                }
+                _ => search.words.push(term.to_lowercase()),
+            }
+        }
+
+        Ok(search)
+    }
+
+    /// SQL condition on the `emails` table and its parameters, numbered from `?2`
+    /// (`?1` is the account). `account:` is not part of it: each account has its own
+    /// database.
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut params: Vec<Value> = Vec::new();
+        let mut param = |value: Value| {
+            params.push(value);
+            format!("?{}", params.len() + 1)
+        };
+        let like = |text: &str| Value::Text(format!("%{}%", text));
+        let addresses = |column: &str, p: &str| {
+            format!(
+                "EXISTS (SELECT 1 FROM json_each(COALESCE({}, '[]')) WHERE lower(COALESCE(json_extract(value, '$.name'), '') || ' ' || json_extract(value, '$.address')) LIKE {})",
+                column, p
+            )
+        };
+
+        for from in &self.from {
+            let p = param(like(from));
+            conditions.push(addresses("from_addresses", &p));
+        }
+        for to in &self.to {
+            let p = param(like(to));
+            conditions.push(format!("({} OR {})", addresses("to_addresses", &p), addresses("cc_addresses", &p)));
+        }
+        for subject in &self.subject {
+            conditions.push(format!("lower(subject) LIKE {}", param(like(subject))));
+        }
+        if self.has_attachment {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM attachments a WHERE a.account_email = emails.account_email
+                   AND a.folder = emails.folder AND a.email_uid = emails.uid)"
+                    .to_string(),
+            );
+        }
+        if let Some(unread) = self.unread {
+            conditions.push(format!("seen = {}", param(Value::Integer(i64::from(!unread)))));
+        }
+        if let Some(after) = self.after {
+            conditions.push(format!("date_received >= {}", param(Value::Integer(after))));
+        }
+        if let Some(before) = self.before {
+            conditions.push(format!("date_received < {}", param(Value::Integer(before))));
+        }
+        if let Some(folder) = &self.folder {
+            conditions.push(format!("folder = {} COLLATE NOCASE", param(Value::Text(folder.clone()))));
+        }
+        for tag in &self.tags {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM tags t WHERE t.account_email = emails.account_email
+                   AND t.message_id = emails.message_id AND t.tag = {})",
+                param(Value::Text(tag.clone()))
+            ));
+        }
+        for (field, text) in &self.fields {
+            let path = param(Value::Text(format!("$.\"{}\"", field)));
+            conditions.push(format!("lower(json_extract(COALESCE(header_fields, '{{}}'), {})) LIKE {}", path, param(like(text))));
+        }
+        let body = crate::database::BODY_TEXT;
+        for word in &self.words {
+            let p = param(like(word));
+            conditions.push(format!(
+                "(lower(subject) LIKE {p} OR lower(COALESCE({body}, '')) LIKE {p} OR lower(from_addresses) LIKE {p}
+                  OR EXISTS (SELECT 1 FROM attachments a WHERE a.account_email = emails.account_email
+                     AND a.folder = emails.folder AND a.email_uid = emails.uid
+                     AND lower(a.filename || ' ' || COALESCE(a.text, '')) LIKE {p}))"
+            ));
+        }
+
+        if conditions.is_empty() {
+            conditions.push("1".to_string());
+        }
+        (conditions.join(" AND "), params)
+    }
+}
+
+impl SearchQuery {
+    /// The query as IMAP `SEARCH` criteria (RFC 3501) for `on:server`. The server
+    /// matches whole words of its own choosing, and `has:attachment` becomes a
+    /// multipart/mixed check, so hits are close to the local ones rather than the same.
+    /// `None` with `tag:`, as tags are only kept locally.
+    pub fn to_imap(&self) -> Option<String> {
+        if !self.tags.is_empty() {
+            return None;
+        }
+        let mut criteria = Vec::new();
+        for from in &self.from {
+            criteria.push(format!("FROM {}", imap_quote(from)));
+        }
+        for to in &self.to {
+            criteria.push(format!("OR TO {0} CC {0}", imap_quote(to)));
+        }
+        for subject in &self.subject {
+            criteria.push(format!("SUBJECT {}", imap_quote(subject)));
+        }
+        if self.has_attachment {
+            criteria.push("HEADER Content-Type \"multipart/mixed\"".to_string());
+        }
+        match self.unread {
+            Some(true) => criteria.push("UNSEEN".to_string()),
+            Some(false) => criteria.push("SEEN".to_string()),
+            None => {}
+        }
+        let day = |timestamp: i64| chrono::Local.timestamp_opt(timestamp, 0).earliest().map(|d| d.format("%-d-%b-%Y").to_string());
+        if let Some(after) = self.after.and_then(day) {
+            criteria.push(format!("SINCE {}", after));
+        }
+        if let Some(before) = self.before.and_then(day) {
+            criteria.push(format!("BEFORE {}", before));
+        }
+        let fields = crate::header_fields::fields();
+        for (name, text) in &self.fields {
+            if let Some(field) = fields.iter().find(|field| field.name == *name) {
+                criteria.push(format!("HEADER {} {}", imap_quote(&field.header), imap_quote(text)));
+            }
+        }
+        for word in &self.words {
+            criteria.push(format!("TEXT {}", imap_quote(word)));
+        }
+        if criteria.is_empty() {
+            criteria.push("ALL".to_string());
+        }
+        let criteria = criteria.join(" ");
+        Some(if criteria.is_ascii() { criteria } else { format!("CHARSET UTF-8 {}", criteria) })
+    }
+}
+
+/// `text` as an IMAP quoted string
+fn imap_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Split on whitespace, keeping double-quoted parts (`subject:"two words"`) together
+fn tokenize(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    terms
+}
+
+/// Filter names, including those of the configured header fields, the word being typed
+/// at the end of `input` could become
+pub fn completions(input: &str) -> Vec<String> {
+    let word = input.rsplit(char::is_whitespace).next().unwrap_or_default().to_lowercase();
+    if word.is_empty() || (word.contains(':') && !word.starts_with("has:") && !word.starts_with("is:") && !word.starts_with("on:")) {
+        return Vec::new();
+    }
+    let fields = crate::header_fields::fields().into_iter().map(|field| format!("{}:", field.name));
+    FILTERS
+        .iter()
+        .map(|filter| filter.to_string())
+        .chain(fields)
+        .filter(|filter| filter.starts_with(&word) && *filter != word)
+        .collect()
+}
+
+/// `input` with the word being typed completed as far as the matching filter names agree
+pub fn complete(input: &str) -> String {
+    let candidates = completions(input);
+    let Some(first) = candidates.first() else {
+        return input.to_string();
+    };
+    let common = candidates.iter().fold(first.len(), |len, candidate| {
+        first.bytes().zip(candidate.bytes()).take(len).take_while(|(a, b)| a == b).count()
+    });
+    let word_start = input.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    format!("{}{}", &input[..word_start], &first[..common])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_parse_into_filters_and_words() {
+        let query = SearchQuery::parse(r#"from:Alice subject:"Quarterly Report" has:attachment is:unread folder:Sent account:work tag:todo budget"#)
+            .unwrap();
+        assert_eq!(query.from, vec!["alice"]);
+        assert_eq!(query.subject, vec!["quarterly report"]);
+        assert!(query.has_attachment);
+        assert_eq!(query.unread, Some(true));
+        assert_eq!(query.folder.as_deref(), Some("Sent"));
+        assert_eq!(query.account.as_deref(), Some("work"));
+        assert_eq!(query.tags, vec!["todo"]);
+        assert_eq!(query.words, vec!["budget"]);
+
+        let (sql, params) = query.to_sql();
+        assert_eq!(params.len(), 6);
+        assert!(sql.contains("?7") && !sql.contains("?8"));
+
+        assert!(SearchQuery::parse("before:yesterday").is_err());
+        assert!(SearchQuery::parse("is:starred").is_err());
+    }
+
+    #[test]
+    fn queries_become_imap_criteria() {
+        let query = SearchQuery::parse(r#"from:alice to:bob subject:"say hi" is:read after:2024-03-05 café on:server"#).unwrap();
+        assert!(query.server);
+        assert_eq!(
+            query.to_imap().as_deref(),
+            Some(r#"CHARSET UTF-8 FROM "alice" OR TO "bob" CC "bob" SUBJECT "say hi" SEEN SINCE 5-Mar-2024 TEXT "café""#)
+        );
+        assert_eq!(SearchQuery::parse("on:server").unwrap().to_imap().as_deref(), Some("ALL"));
+        assert_eq!(SearchQuery::parse("tag:todo").unwrap().to_imap(), None);
+        assert!(SearchQuery::parse("on:phone").is_err());
+    }
+
+    #[test]
+    fn header_fields_are_filters() {
+        // The same fields as the header_fields tests, which may run at the same time
+        let field = |name: &str, header: &str| crate::config::HeaderField {
+            name: name.to_string(),
+            header: header.to_string(),
+            column_width: None,
+        };
+        crate::header_fields::configure(&[field("reason", "X-GitHub-Reason"), field("jira", "X-Jira-Ticket")]);
+        let query = SearchQuery::parse("reason:Mention ticket:ABC-1").unwrap();
+        assert_eq!(query.fields, vec![("reason".to_string(), "mention".to_string())]);
+        assert_eq!(query.words, vec!["ticket:abc-1"]);
+        let (sql, params) = query.to_sql();
+        assert!(sql.contains("json_extract"));
+        assert_eq!(params[0], Value::Text("$.\"reason\"".to_string()));
+        assert_eq!(completions("budget rea"), vec!["reason:"]);
+    }
+
+    #[test]
+    fn filter_names_complete() {
+        assert_eq!(complete("budget f"), "budget f");
+        assert_eq!(completions("budget f"), vec!["from:", "folder:"]);
+        assert_eq!(complete("budget fr"), "budget from:");
+        assert_eq!(complete("has:a"), "has:attachment");
+        assert_eq!(complete("is:"), "is:");
+        assert_eq!(complete("from:al"), "from:al");
+    }
+}
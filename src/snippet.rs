@@ -0,0 +1,192 @@
+use crate::email::Email;
+
+/// Longest snippet kept, in characters
+pub const MAX_LEN: usize = 120;
+
+/// Raised whenever snippets are made differently, so the stored ones are made again
+pub const VERSION: i64 = 1;
+
+/// Elements whose content is not text worth previewing, or only quotes an earlier message
+const SKIPPED_ELEMENTS: &[&str] = &["head", "style", "script", "title", "blockquote"];
+
+/// Elements that start a new line of text
+const BLOCK_ELEMENTS: &[&str] = &["br", "p", "div", "tr", "li", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "table"];
+
+/// Characters newsletters pad their preheader with to push other text out of previews
+const INVISIBLE: &[char] = &['\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}', '\u{feff}', '\u{034f}', '\u{00ad}'];
+
+/// A plain-text preview of `email` for the two-line list rows and new mail notices,
+/// stored when the body is saved. `None` while only the headers are downloaded.
+pub fn generate(email: &Email) -> Option<String> {
+    from_bodies(email.body_text.as_deref(), email.body_html.as_deref())
+}
+
+/// The preview of a message with these bodies: the text one, or the HTML one with its
+/// markup stripped, without quoted text or the signature, whitespace collapsed and cut
+/// to `MAX_LEN` characters
+pub fn from_bodies(text: Option<&str>, html: Option<&str>) -> Option<String> {
+    let text = match (text.filter(|text| !text.trim().is_empty()), html) {
+        (Some(text), _) => text.to_string(),
+        (None, Some(html)) => strip_html(html),
+        (None, None) => return None,
+    };
+    let new_text = new_text(&text).replace(INVISIBLE, "");
+    let collapsed = new_text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let snippet: String = collapsed.chars().take(MAX_LEN).collect();
+    Some(snippet.trim_end().to_string())
+}
+
+/// The lines of a text body written for this message: quoted lines and attributions
+/// are dropped, and the signature and a forwarded or quoted original end it
+fn new_text(text: &str) -> String {
+    let mut kept: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed == "--" || line.trim_end_matches('\r') == "-- " || trimmed.starts_with("Sent from my ") {
+            break;
+        }
+        // Outlook puts the original below a rule and a header block instead of quoting it
+        if trimmed.starts_with("-----Original Message-----")
+            || trimmed.starts_with("---------- Forwarded message")
+            || (trimmed.len() >= 10 && trimmed.chars().all(|c| c == '_'))
+        {
+            break;
+        }
+        if trimmed.starts_with('>') {
+            continue;
+        }
+        if trimmed.ends_with("wrote:") || trimmed.ends_with("schrieb:") {
+            // An attribution wrapped over two lines
+            if kept.last().is_some_and(|last| last.trim_start().starts_with("On ") || last.trim_start().starts_with("Am ")) {
+                kept.pop();
+            }
+            continue;
+        }
+        kept.push(line);
+    }
+    kept.join("\n")
+}
+
+/// The text of an HTML body: tags dropped, line breaks at block elements, entities
+/// decoded, and comments and the content of `SKIPPED_ELEMENTS` left out
+fn strip_html(html: &str) -> String {
+    // ASCII lowercasing keeps byte offsets, so positions found in one hold in the other
+    let lower = html.to_ascii_lowercase();
+    let mut text = String::new();
+    let mut rest = 0;
+    let mut skipping = String::new();
+    let mut depth = 0;
+    while let Some(open) = html[rest..].find('<') {
+        let start = rest + open;
+        if depth == 0 {
+            text.push_str(&decode_entities(&html[rest..start]));
+        }
+        let end = if lower[start..].starts_with("<!--") {
+            lower[start..].find("-->").map(|close| start + close + 3)
+        } else {
+            lower[start..].find('>').map(|close| start + close + 1)
+        };
+        let Some(end) = end else {
+            return text;
+        };
+        rest = end;
+
+        let tag = &lower[start + 1..end - 1];
+        let closing = tag.starts_with('/');
+        let name: String = tag.trim_start_matches('/').chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        if depth > 0 {
+            if name == skipping {
+                if closing {
+                    depth -= 1;
+                } else {
+                    depth += 1;
+                }
+            }
+        } else if SKIPPED_ELEMENTS.contains(&name.as_str()) && !closing && !tag.ends_with('/') {
+            skipping = name;
+            depth = 1;
+        } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            text.push('\n');
+        }
+    }
+    if depth == 0 {
+        text.push_str(&decode_entities(&html[rest..]));
+    }
+    text
+}
+
+/// Replace the named entities common in mail and numeric character references
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest.find(';').filter(|end| *end <= 10).map(|end| &rest[1..end]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (character, entity) {
+            (Some(character), Some(entity)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_and_signatures_are_left_out() {
+        let mut email = Email::new();
+        assert_eq!(generate(&email), None);
+        email.body_text = Some(
+            "On Tue, 5 Mar 2024 at 10:00, Ann Smith\n<ann@example.com> wrote:\n> What time?\n>\n   Around\tten,   I think.\n\
+             See you there.\n\n-- \nBob\nExample Inc.\n"
+                .to_string(),
+        );
+        assert_eq!(generate(&email).as_deref(), Some("Around ten, I think. See you there."));
+
+        email.body_text = Some("Sounds good.\n\n-----Original Message-----\nFrom: Ann\nSubject: Lunch\n".to_string());
+        assert_eq!(generate(&email).as_deref(), Some("Sounds good."));
+
+        email.body_text = Some("word ".repeat(50));
+        let snippet = generate(&email).unwrap();
+        assert_eq!(snippet.chars().count(), MAX_LEN - 1);
+        assert!(snippet.ends_with("word"));
+    }
+
+    #[test]
+    fn html_bodies_are_stripped() {
+        let html = "<html><head><style>p { color: red; }</style><title>Newsletter</title></head><body>\
+                    <div style=\"display:none\">Big news&nbsp;inside\u{200c}\u{200c}</div><!-- tracking <img> -->\
+                    <p>Hello &amp; welcome,<br>caf&#233; &#x2615; &lt;3</p><blockquote><p>Quoted</p><blockquote>deeper</blockquote>\
+                    still quoted</blockquote><p>Bye</p></body></html>";
+        assert_eq!(from_bodies(None, Some(html)).as_deref(), Some("Big news inside Hello & welcome, café ☕ <3 Bye"));
+        // The text body wins when there is one
+        assert_eq!(from_bodies(Some("Plain"), Some(html)).as_deref(), Some("Plain"));
+        assert_eq!(from_bodies(Some("  \n"), Some("<p>Only &bogus; here &")).as_deref(), Some("Only &bogus; here &"));
+    }
+}
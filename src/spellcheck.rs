@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 // Embed the practical word lists at compile time
 const COMMON_WORDS: &str = include_str!("../resources/google-10000-english.txt");
@@ -8,13 +8,16 @@ const ADDITIONAL_COMMON: &str = include_str!("../resources/additional-common.txt
 
 /// Spell checker for email composition
 /// This is a basic implementation that can be extended with proper dictionary support
+#[derive(Clone)]
 pub struct SpellChecker {
     personal_dictionary: HashSet<String>,
     common_words: HashSet<String>,
+    /// Section of the personal dictionary file this checker reads and writes
+    language: String,
 }
 
 /// Represents a misspelled word with suggestions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SpellError {
     pub word: String,
     pub position: usize,
@@ -49,7 +52,7 @@ impl SpellChecker {
     /// Create a new spell checker with the given configuration
     pub fn new(config: &SpellCheckConfig) -> Result<Self> {
         let personal_dictionary = if let Some(path) = &config.personal_dictionary_path {
-            Self::load_personal_dictionary(path)?
+            Self::load_personal_dictionary(path, &config.language)?
         } else {
             HashSet::new()
         };
@@ -59,6 +62,7 @@ impl SpellChecker {
         Ok(Self {
             personal_dictionary,
             common_words,
+            language: config.language.clone(),
         })
     }
 
@@ -131,20 +135,15 @@ impl SpellChecker {
         words
     }
 
-    /// Load personal dictionary from file
-    fn load_personal_dictionary(path: &str) -> Result<HashSet<String>> {
-        let mut words = HashSet::new();
-        
-        if let Ok(content) = std::fs::read_to_string(path) {
-            for line in content.lines() {
-                let word = line.trim();
-                if !word.is_empty() {
-                    words.insert(word.to_lowercase());
-                }
-            }
-        }
-        
-        Ok(words)
+    /// Load the words for `language` from a personal dictionary file
+    fn load_personal_dictionary(path: &str, language: &str) -> Result<HashSet<String>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+            Err(e) => return Err(e).context("Failed to read personal dictionary"),
+        };
+        let mut sections = parse_dictionary(&content, language);
+        Ok(sections.remove(language).unwrap_or_default().into_iter().collect())
     }
 
     /// Check if a word is spelled correctly
@@ -434,14 +433,31 @@ impl SpellChecker {
         self.personal_dictionary.insert(word.to_lowercase());
     }
 
-    /// Save personal dictionary to file
+    /// Remove a word from the personal dictionary; false if it was not in it
+    pub fn remove_from_personal_dictionary(&mut self, word: &str) -> bool {
+        self.personal_dictionary.remove(&word.to_lowercase())
+    }
+
+    /// The personal dictionary in alphabetical order
+    pub fn personal_words(&self) -> Vec<String> {
+        let mut words: Vec<String> = self.personal_dictionary.iter().cloned().collect();
+        words.sort();
+        words
+    }
+
+    /// Save personal dictionary to file, replacing this checker's language section and
+    /// keeping the others
     pub fn save_personal_dictionary(&self, path: &str) -> Result<()> {
-        let words: Vec<String> = self.personal_dictionary.iter().cloned().collect();
-        let mut sorted_words = words;
-        sorted_words.sort();
-        
-        let content = sorted_words.join("\n");
-        std::fs::write(path, content)
+        let mut sections = match std::fs::read_to_string(path) {
+            Ok(content) => parse_dictionary(&content, &self.language),
+            Err(_) => BTreeMap::new(),
+        };
+        sections.insert(self.language.clone(), self.personal_dictionary.iter().cloned().collect());
+
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+        std::fs::write(path, format_dictionary(&sections))
             .context("Failed to save personal dictionary")
     }
 
@@ -479,6 +495,87 @@ pub struct SpellCheckStats {
     pub accuracy: f64,
 }
 
+/// The words of a personal dictionary file by language. Each section starts with the
+/// language in brackets, e.g. `[en_US]`, followed by one word per line; words before
+/// the first header (files from before there were sections) belong to `default_language`.
+pub fn parse_dictionary(content: &str, default_language: &str) -> BTreeMap<String, BTreeSet<String>> {
+    let mut sections: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut language = default_language.to_string();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            language = header.trim().to_string();
+            sections.entry(language.clone()).or_default();
+        } else if !line.is_empty() {
+            sections.entry(language.clone()).or_default().insert(line.to_lowercase());
+        }
+    }
+    sections
+}
+
+/// A personal dictionary file with the given sections; empty sections are left out
+pub fn format_dictionary(sections: &BTreeMap<String, BTreeSet<String>>) -> String {
+    let mut content = String::new();
+    for (language, words) in sections.iter().filter(|(_, words)| !words.is_empty()) {
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(&format!("[{}]\n", language));
+        for word in words {
+            content.push_str(word);
+            content.push('\n');
+        }
+    }
+    content
+}
+
+/// The part of `new` that needs checking again after it was edited from `old`, widened
+/// to whole paragraphs (separated by blank lines): `(start, old_end, new_end)`, where
+/// `old[start..old_end]` became `new[start..new_end]`. `None` when nothing changed.
+pub fn dirty_paragraphs(old: &str, new: &str) -> Option<(usize, usize, usize)> {
+    if old == new {
+        return None;
+    }
+    let shortest = old.len().min(new.len());
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or(shortest);
+    let mut suffix = 0;
+    for (a, b) in old.chars().rev().zip(new.chars().rev()) {
+        if a != b || suffix + a.len_utf8() > shortest - prefix {
+            break;
+        }
+        suffix += a.len_utf8();
+    }
+
+    let start = new[..prefix].rfind("\n\n").map(|i| i + 2).unwrap_or(0);
+    let changed_end = new.len() - suffix;
+    let new_end = new[changed_end..].find("\n\n").map(|i| changed_end + i).unwrap_or(new.len());
+    Some((start, old.len() - (new.len() - new_end), new_end))
+}
+
+/// `errors` found in the old text, updated for a re-check of the dirty region from
+/// `dirty_paragraphs`: errors inside it are replaced by `fresh`, and those after it
+/// move with the edit
+pub fn merge_errors(errors: Vec<SpellError>, (start, old_end, new_end): (usize, usize, usize), fresh: Vec<SpellError>) -> Vec<SpellError> {
+    let mut merged: Vec<SpellError> = errors
+        .into_iter()
+        .filter(|e| e.position < start || e.position >= old_end)
+        .map(|mut e| {
+            if e.position >= old_end {
+                e.position = e.position - old_end + new_end;
+            }
+            e
+        })
+        .chain(fresh)
+        .collect();
+    merged.sort_by_key(|e| e.position);
+    merged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -559,4 +656,41 @@ mod tests {
         assert_eq!(stats.misspelled_words, 0, "Stats should show 0 misspelled words");
         assert_eq!(stats.accuracy, 100.0, "Accuracy should be 100% for correct text");
     }
+
+    #[test]
+    fn test_dirty_paragraphs() {
+        let old = "First paragraph.\n\nSecond one here.\n\nThird.";
+        let new = "First paragraph.\n\nSecond onne here.\n\nThird.";
+        // Only the second paragraph is checked again
+        let region = dirty_paragraphs(old, new).unwrap();
+        assert_eq!(region, (18, 34, 35));
+        assert_eq!(&new[region.0..region.2], "Second onne here.");
+        assert_eq!(dirty_paragraphs(old, old), None);
+        // Removing the blank line joins two paragraphs into one region
+        assert_eq!(dirty_paragraphs("ab\n\ncd", "ab\ncd"), Some((0, 6, 5)));
+
+        let error = |word: &str, position| SpellError { word: word.to_string(), position, suggestions: Vec::new() };
+        let merged = merge_errors(
+            vec![error("Frist", 0), error("Secnd", 18), error("Thrid", 36)],
+            region,
+            vec![error("onne", 25)],
+        );
+        let positions: Vec<_> = merged.iter().map(|e| (e.word.as_str(), e.position)).collect();
+        assert_eq!(positions, vec![("Frist", 0), ("onne", 25), ("Thrid", 37)]);
+    }
+
+    #[test]
+    fn test_dictionary_sections() {
+        // A file from before sections: every word belongs to the default language
+        let legacy = parse_dictionary("tuimail\nRatatui\n", "en_US");
+        assert_eq!(legacy["en_US"].iter().collect::<Vec<_>>(), vec!["ratatui", "tuimail"]);
+
+        let content = "[de_DE]\nkrawatte\n\n[en_US]\nimap\nsmtp\n";
+        let mut sections = parse_dictionary(content, "en_US");
+        assert_eq!(format_dictionary(&sections), content);
+
+        sections.insert("fr_FR".to_string(), BTreeSet::new());
+        sections.get_mut("en_US").unwrap().remove("smtp");
+        assert_eq!(format_dictionary(&sections), "[de_DE]\nkrawatte\n\n[en_US]\nimap\n");
+    }
 }
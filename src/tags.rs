@@ -0,0 +1,47 @@
+/// Tags notmuch keeps in step with maildir flags and message content by itself; they
+/// are stored but not shown in the email list, which shows the same state already
+pub const AUTOMATIC_TAGS: &[&str] = &["attachment", "draft", "encrypted", "flagged", "passed", "replied", "signed", "unread"];
+
+/// `current` with the changes typed as `+tag -tag`, where a word without a sign adds
+/// the tag, sorted and without duplicates. Tags can't contain whitespace or quotes.
+pub fn apply_changes(current: &[String], changes: &str) -> Result<Vec<String>, String> {
+    let mut tags = current.to_vec();
+    for change in changes.split_whitespace() {
+        let (add, tag) = match change.strip_prefix('-') {
+            Some(tag) => (false, tag),
+            None => (true, change.strip_prefix('+').unwrap_or(change)),
+        };
+        if tag.is_empty() || tag.contains(['"', '\'']) {
+            return Err(format!("'{}' is not a valid tag", change));
+        }
+        tags.retain(|existing| existing != tag);
+        if add {
+            tags.push(tag.to_string());
+        }
+    }
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+/// The tags worth showing next to a message
+pub fn shown(tags: &[String]) -> Vec<&str> {
+    tags.iter().map(String::as_str).filter(|tag| !AUTOMATIC_TAGS.contains(tag)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_add_and_remove_tags() {
+        let current = vec!["inbox".to_string(), "todo".to_string()];
+        assert_eq!(apply_changes(&current, "+work -inbox urgent").unwrap(), vec!["todo", "urgent", "work"]);
+        assert_eq!(apply_changes(&current, "+todo").unwrap(), vec!["inbox", "todo"]);
+        assert!(apply_changes(&current, "-").is_err());
+        assert!(apply_changes(&current, "+\"x\"").is_err());
+
+        let tags = vec!["unread".to_string(), "work".to_string(), "attachment".to_string()];
+        assert_eq!(shown(&tags), vec!["work"]);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::email::Email;
+
+/// What places a message in a conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Conversation {
+    /// A thread the server reported, by its position in the reply
+    Server(usize),
+    /// Messages linked by Message-ID, In-Reply-To and References
+    Local(usize),
+    /// A message without any of those headers, on its own
+    Alone(usize),
+}
+
+/// How the email list groups messages into conversations, toggled with `H`
+#[derive(Debug, Clone, Default)]
+pub struct ThreadView {
+    pub enabled: bool,
+    /// The conversations of one folder of an account as the server threaded them, by
+    /// UID. Other folders are grouped from the headers.
+    server: Option<(String, String, Vec<Vec<u32>>)>,
+    /// Messages shown below an earlier one of their conversation, by folder and ID
+    replies: HashSet<(String, String)>,
+}
+
+impl ThreadView {
+    /// Use the server's threads of `folder` (from `ImapBackend::threads`), or forget them
+    pub fn set_server_threads(&mut self, account_email: &str, folder: &str, threads: Option<Vec<Vec<u32>>>) {
+        self.server = threads.map(|threads| (account_email.to_string(), folder.to_string(), threads));
+    }
+
+    /// Forget the server's threads if they are of another account, whose folders may
+    /// have the same names
+    pub fn keep_server_threads_of(&mut self, account_email: &str) {
+        if self.server.as_ref().is_some_and(|(account, _, _)| account != account_email) {
+            self.server = None;
+        }
+    }
+
+    /// Whether `email` is shown indented below the first message of its conversation
+    pub fn is_reply(&self, email: &Email) -> bool {
+        self.enabled && self.replies.contains(&(email.folder.clone(), email.id.clone()))
+    }
+
+    /// The emails as the list shows them. With threading on, each conversation takes the
+    /// place of its first message in `emails` and lists its messages oldest first.
+    pub fn arrange(&mut self, emails: Vec<Email>) -> Vec<Email> {
+        self.replies.clear();
+        if !self.enabled {
+            return emails;
+        }
+        let conversations = self.conversations(&emails);
+        let mut groups: Vec<Vec<Email>> = Vec::new();
+        let mut positions: HashMap<Conversation, usize> = HashMap::new();
+        for (email, conversation) in emails.into_iter().zip(conversations) {
+            let position = *positions.entry(conversation).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[position].push(email);
+        }
+
+        let mut arranged = Vec::new();
+        for mut group in groups {
+            group.sort_by_key(|email| email.date);
+            self.replies.extend(group.iter().skip(1).map(|email| (email.folder.clone(), email.id.clone())));
+            arranged.extend(group);
+        }
+        arranged
+    }
+
+    /// The conversation of each of `emails`: the server's thread when its folder was
+    /// threaded there, else the messages it shares a Message-ID with through its headers
+    fn conversations(&self, emails: &[Email]) -> Vec<Conversation> {
+        let mut server_threads: HashMap<u32, usize> = HashMap::new();
+        let mut server_folder = None;
+        if let Some((_, folder, threads)) = &self.server {
+            server_folder = Some(folder.as_str());
+            for (thread, uids) in threads.iter().enumerate() {
+                server_threads.extend(uids.iter().map(|uid| (*uid, thread)));
+            }
+        }
+
+        // Message-IDs joined into sets, each set a conversation
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut parents: Vec<usize> = Vec::new();
+        let mut first_ids = Vec::with_capacity(emails.len());
+        for email in emails {
+            let linked: Vec<usize> = crate::mute::thread_ids(email)
+                .into_iter()
+                .map(|id| {
+                    *ids.entry(id).or_insert_with(|| {
+                        parents.push(parents.len());
+                        parents.len() - 1
+                    })
+                })
+                .collect();
+            if let Some(first) = linked.first() {
+                let root = find(&mut parents, *first);
+                for other in &linked[1..] {
+                    let other = find(&mut parents, *other);
+                    parents[other] = root;
+                }
+            }
+            first_ids.push(linked.first().copied());
+        }
+
+        emails
+            .iter()
+            .zip(first_ids)
+            .enumerate()
+            .map(|(i, (email, first_id))| {
+                let server_thread = Some(email.folder.as_str())
+                    .filter(|folder| server_folder == Some(*folder))
+                    .and_then(|_| email.id.parse::<u32>().ok())
+                    .and_then(|uid| server_threads.get(&uid));
+                match (server_thread, first_id) {
+                    (Some(thread), _) => Conversation::Server(*thread),
+                    (None, Some(id)) => Conversation::Local(find(&mut parents, id)),
+                    (None, None) => Conversation::Alone(i),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The set `id` belongs to, shortening the path on the way
+fn find(parents: &mut [usize], mut id: usize) -> usize {
+    while parents[id] != id {
+        parents[id] = parents[parents[id]];
+        id = parents[id];
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(id: &str, hours: i64, headers: &[(&str, &str)]) -> Email {
+        let mut email = Email::new();
+        email.id = id.to_string();
+        email.folder = "INBOX".to_string();
+        email.date = chrono::Local::now() - chrono::Duration::hours(hours);
+        for (name, value) in headers {
+            email.headers.insert(name.to_string(), value.to_string());
+        }
+        email
+    }
+
+    #[test]
+    fn conversations_are_grouped_from_headers_or_the_server() {
+        // Newest first, as folders are listed
+        let emails = vec![
+            email("5", 1, &[("Message-ID", "<c@x>"), ("References", "<a@x> <b@x>")]),
+            email("4", 2, &[("Message-ID", "<d@x>")]),
+            email("3", 3, &[]),
+            email("2", 4, &[("Message-ID", "<b@x>"), ("In-Reply-To", "<a@x>")]),
+            email("1", 5, &[("Message-ID", "<a@x>")]),
+        ];
+        let ids = |list: &[Email]| list.iter().map(|email| email.id.clone()).collect::<Vec<_>>();
+
+        let mut view = ThreadView::default();
+        assert_eq!(ids(&view.arrange(emails.clone())), vec!["5", "4", "3", "2", "1"]);
+
+        view.enabled = true;
+        let arranged = view.arrange(emails.clone());
+        assert_eq!(ids(&arranged), vec!["1", "2", "5", "4", "3"]);
+        assert!(!view.is_reply(&arranged[0]) && view.is_reply(&arranged[1]) && view.is_reply(&arranged[2]));
+        assert!(!view.is_reply(&arranged[3]));
+
+        // The server's threads win for the folder they were made for
+        view.set_server_threads("me@example.com", "INBOX", Some(vec![vec![1, 2], vec![4, 5], vec![3]]));
+        assert_eq!(ids(&view.arrange(emails.clone())), vec!["4", "5", "3", "1", "2"]);
+        view.keep_server_threads_of("me@example.com");
+        assert_eq!(ids(&view.arrange(emails.clone())), vec!["4", "5", "3", "1", "2"]);
+        view.keep_server_threads_of("other@example.com");
+        assert_eq!(ids(&view.arrange(emails)), vec!["1", "2", "5", "4", "3"]);
+    }
+}
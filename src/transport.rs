@@ -0,0 +1,1658 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use imap::Session;
+use lettre::address::{Address, Envelope};
+use lettre::{Message, SmtpTransport, Transport};
+use lettre::message::header::{ContentType, Header, HeaderName, HeaderValue};
+use lettre::message::{Attachment, MultiPart, SinglePart};
+
+use crate::config::{EmailAccount, IpFamily, SmtpFallback, SmtpSecurity, SpecialFolders};
+use crate::database::EmailDatabase;
+use crate::email::{compress_uid_set, debug_log, ensure_writable, Email, EmailAddress, EmailAttachment, EmailError};
+
+/// A message as returned by the IMAP server
+#[derive(Debug, Clone)]
+pub struct FetchedMessage {
+    pub uid: u32,
+    pub flags: Vec<String>,
+    pub raw: Vec<u8>,
+}
+
+/// What changed in a folder since a known mod-sequence, from CONDSTORE and QRESYNC
+/// (RFC 7162)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FolderChanges {
+    pub uid_validity: u32,
+    pub highest_modseq: u64,
+    /// Current flags of the messages changed since
+    pub flags: HashMap<u32, Vec<String>>,
+    /// UID ranges expunged since; `None` without QRESYNC, when expunges have to be found
+    /// by comparing UIDs
+    pub vanished: Option<Vec<std::ops::RangeInclusive<u32>>>,
+}
+
+impl FolderChanges {
+    pub fn is_vanished(&self, uid: u32) -> bool {
+        self.vanished.iter().flatten().any(|range| range.contains(&uid))
+    }
+}
+
+/// Operations the client performs against an IMAP server. Implemented for real sessions
+/// and for the in-memory `MockMailServer`, so sync and flag logic can be tested offline.
+pub trait ImapBackend {
+    /// UIDs of every message in the folder
+    fn uids(&mut self, folder: &str) -> Result<Vec<u32>, EmailError>;
+
+    /// Full messages for the given UIDs, without setting \Seen
+    fn fetch(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError>;
+
+    /// Headers only of the given UIDs, without setting \Seen; `raw` holds the header block
+    fn fetch_headers(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError>;
+
+    /// Current flags of the given UIDs
+    fn flags(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, Vec<String>>, EmailError>;
+
+    /// Add (`set`) or remove a flag such as `\Seen`
+    fn store_flag(&mut self, folder: &str, uid: u32, flag: &str, set: bool) -> Result<(), EmailError>;
+
+    /// Permanently remove a message
+    fn delete(&mut self, folder: &str, uid: u32) -> Result<(), EmailError>;
+
+    /// Move a message to another folder
+    fn move_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError>;
+
+    /// Copy a message to another folder, keeping the original
+    fn copy_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        Err(EmailError::ImapError(format!("Can't copy message {} from {} to {}", uid, folder, target)))
+    }
+
+    /// Permanently remove every message in the folder flagged `\Deleted`
+    fn expunge_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        Err(EmailError::ImapError(format!("Can't expunge {}", folder)))
+    }
+
+    /// Number of messages in the folder without `\Seen`
+    fn unseen_count(&mut self, folder: &str) -> Result<u32, EmailError>;
+
+    /// Store a raw RFC 822 message in a folder with the given flags
+    fn append(&mut self, folder: &str, raw: &[u8], flags: &[String]) -> Result<(), EmailError>;
+
+    /// Create a folder. Backends that map folders onto something else refuse.
+    fn create_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        Err(EmailError::ImapError(format!("Can't create {}: this account's folders are managed elsewhere", folder)))
+    }
+
+    /// Rename a folder, and the folders below it with it
+    fn rename_folder(&mut self, folder: &str, new_name: &str) -> Result<(), EmailError> {
+        Err(EmailError::ImapError(format!("Can't rename {} to {}: this account's folders are managed elsewhere", folder, new_name)))
+    }
+
+    /// Delete a folder with the messages in it
+    fn delete_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        Err(EmailError::ImapError(format!("Can't delete {}: this account's folders are managed elsewhere", folder)))
+    }
+
+    /// Changes to the folder since `since`, the UIDVALIDITY and HIGHESTMODSEQ of an
+    /// earlier call. With `since` unset or from another UIDVALIDITY only those two are
+    /// filled in, as a starting point. `None` when the server has no mod-sequences.
+    fn changes_since(&mut self, _folder: &str, _since: Option<(u32, u64)>) -> Result<Option<FolderChanges>, EmailError> {
+        Ok(None)
+    }
+
+    /// UIDs of the messages in the folder matching IMAP `SEARCH` criteria, in order.
+    /// `None` for backends that can't search on the server.
+    fn search(&mut self, _folder: &str, _criteria: &str) -> Result<Option<Vec<u32>>, EmailError> {
+        Ok(None)
+    }
+
+    /// UIDs of every message in the folder, newest first by date as the server sorts them
+    /// (`SORT`, RFC 5256). `None` when the server can't sort; callers then sort locally.
+    fn sort(&mut self, _folder: &str) -> Result<Option<Vec<u32>>, EmailError> {
+        Ok(None)
+    }
+
+    /// The conversations of the folder as the server threads them by References (`THREAD`,
+    /// RFC 5256), each as the UIDs of its messages, root first. `None` when the server
+    /// can't thread; callers then group by the headers themselves.
+    fn threads(&mut self, _folder: &str) -> Result<Option<Vec<Vec<u32>>>, EmailError> {
+        Ok(None)
+    }
+
+    /// The folders the server marks as Sent, Drafts, Trash, Junk and Archive. Empty for
+    /// backends without such marks, whose folders are then found by name.
+    fn special_use_folders(&mut self) -> Result<SpecialFolders, EmailError> {
+        Ok(SpecialFolders::default())
+    }
+
+    /// Gmail's id of each of the given UIDs, in hex as the Gmail API writes it: one id per
+    /// message, under whichever labels it appears (`X-GM-MSGID`). Empty for other servers.
+    fn gmail_ids(&mut self, _folder: &str, _uids: &[u32]) -> Result<HashMap<u32, String>, EmailError> {
+        Ok(HashMap::new())
+    }
+}
+
+/// Operations the client performs against an SMTP server
+pub trait SmtpBackend {
+    fn send(&mut self, message: &Message) -> Result<(), EmailError>;
+
+    /// Whether the service takes the recipients from the message's headers, so Bcc has
+    /// to stay in them; it removes the header itself before delivery
+    fn keeps_bcc(&self) -> bool {
+        false
+    }
+
+    /// Submit an already formatted message to the envelope's recipients
+    fn send_raw(&mut self, envelope: &Envelope, raw: &[u8]) -> Result<(), EmailError>;
+}
+
+/// Move a message on a server with neither MOVE nor UIDPLUS: copy it, flag the original
+/// `\Deleted` and expunge the folder. EXPUNGE removes every message flagged `\Deleted`,
+/// so the others already flagged are cleared first and flagged again afterwards.
+pub(crate) fn move_by_copy<B: ImapBackend + ?Sized>(imap: &mut B, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+    imap.copy_message(folder, uid, target)?;
+    let flagged = match imap.search(folder, "DELETED")? {
+        Some(uids) => uids,
+        None => {
+            let uids = imap.uids(folder)?;
+            imap.flags(folder, &uids)?
+                .into_iter()
+                .filter(|(_, flags)| flags.iter().any(|flag| flag == "\\Deleted"))
+                .map(|(uid, _)| uid)
+                .collect()
+        }
+    };
+    let others: Vec<u32> = flagged.into_iter().filter(|&other| other != uid).collect();
+    for &other in &others {
+        imap.store_flag(folder, other, "\\Deleted", false)?;
+    }
+    imap.store_flag(folder, uid, "\\Deleted", true)?;
+    let expunged = imap.expunge_folder(folder);
+    // Flag the others again even when the expunge failed, so nothing is lost either way
+    for &other in &others {
+        imap.store_flag(folder, other, "\\Deleted", true)?;
+    }
+    expunged
+}
+
+fn imap_error(context: &str, e: impl std::fmt::Display) -> EmailError {
+    EmailError::ImapError(format!("{}: {}", context, e))
+}
+
+/// A UID set such as `1:3,7,9:*`, as ranges; `*` stands for the largest UID
+fn parse_uid_ranges(set: &str) -> Vec<std::ops::RangeInclusive<u32>> {
+    let bound = |value: &str| if value == "*" { Some(u32::MAX) } else { value.parse().ok() };
+    set.split(',')
+        .filter_map(|part| match part.split_once(':') {
+            Some((a, b)) => {
+                let (a, b) = (bound(a)?, bound(b)?);
+                Some(a.min(b)..=a.max(b))
+            }
+            None => bound(part).map(|uid| uid..=uid),
+        })
+        .collect()
+}
+
+/// The parenthesised list that follows `name` in a FETCH response, e.g. the flags in
+/// `FLAGS (\Seen $Forwarded)`
+fn fetch_item<'a>(items: &'a str, name: &str) -> Option<&'a str> {
+    let start = items.find(&format!("{} (", name))? + name.len() + 2;
+    let end = start + items[start..].find(')')?;
+    Some(&items[start..end])
+}
+
+/// The first quoted string or atom of `input`, and what follows it
+fn imap_string(input: &str) -> Option<(String, &str)> {
+    let input = input.trim_start();
+    if let Some(quoted) = input.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => value.push(chars.next()?.1),
+                '"' => return Some((value, &quoted[i + 1..])),
+                _ => value.push(c),
+            }
+        }
+        return None;
+    }
+    let end = input.find(' ').unwrap_or(input.len());
+    (end > 0).then(|| (input[..end].to_string(), &input[end..]))
+}
+
+/// The attributes and decoded name of each folder in the untagged `LIST` or `XLIST`
+/// responses the server sent. Names sent as literals are read from the following line.
+fn parse_list_response(response: &[u8]) -> Vec<(Vec<String>, String)> {
+    let text = String::from_utf8_lossy(response);
+    let mut lines = text.split("\r\n");
+    let mut folders = Vec::new();
+    while let Some(line) = lines.next() {
+        let Some(rest) = line.strip_prefix("* LIST ").or_else(|| line.strip_prefix("* XLIST ")) else {
+            continue;
+        };
+        let Some((attributes, rest)) = rest.strip_prefix('(').and_then(|rest| rest.split_once(')')) else {
+            continue;
+        };
+        // The hierarchy delimiter, or NIL
+        let Some((_, rest)) = imap_string(rest) else {
+            continue;
+        };
+        let literal = rest.trim().strip_prefix('{').and_then(|rest| rest.strip_suffix('}')).and_then(|n| n.parse::<usize>().ok());
+        let name = match literal {
+            Some(length) => lines.next().map(|next| next.chars().take(length).collect()),
+            None => imap_string(rest).map(|(name, _)| name),
+        };
+        if let Some(name) = name {
+            let attributes = attributes.split_whitespace().map(str::to_string).collect();
+            folders.push((attributes, crate::utf7::decode(&name)));
+        }
+    }
+    folders
+}
+
+/// Pick the UIDVALIDITY, HIGHESTMODSEQ, VANISHED and FETCH responses of a CONDSTORE or
+/// QRESYNC exchange out of the untagged lines the server sent
+fn parse_changes(response: &[u8], changes: &mut FolderChanges) {
+    let code = |line: &str, name: &str| {
+        let rest = line.strip_prefix("* OK [")?.strip_prefix(name)?.strip_prefix(' ')?;
+        rest.split(']').next().map(str::trim).map(str::to_string)
+    };
+    for line in String::from_utf8_lossy(response).lines() {
+        let line = line.trim_end();
+        if let Some(value) = code(line, "UIDVALIDITY").and_then(|v| v.parse().ok()) {
+            changes.uid_validity = value;
+        } else if let Some(value) = code(line, "HIGHESTMODSEQ").and_then(|v| v.parse().ok()) {
+            changes.highest_modseq = value;
+        } else if let Some(rest) = line.strip_prefix("* VANISHED ") {
+            let set = rest.strip_prefix("(EARLIER) ").unwrap_or(rest);
+            changes.vanished.get_or_insert_with(Vec::new).extend(parse_uid_ranges(set.trim()));
+        } else if let Some((_, items)) = line.strip_prefix("* ").and_then(|rest| rest.split_once(" FETCH (")) {
+            let uid = items
+                .split_once("UID ")
+                .and_then(|(_, rest)| rest.split([' ', ')']).next())
+                .and_then(|uid| uid.parse().ok());
+            if let (Some(uid), Some(flags)) = (uid, fetch_item(items, "FLAGS")) {
+                changes.flags.insert(uid, flags.split_whitespace().map(str::to_string).collect());
+            }
+        }
+    }
+}
+
+/// The `X-GM-MSGID` of each UID in a `UID FETCH (UID X-GM-MSGID)` response, in hex
+fn parse_gmail_ids(response: &[u8]) -> HashMap<u32, String> {
+    let number = |items: &str, name: &str| -> Option<u64> {
+        items.split_once(name)?.1.split([' ', ')']).next()?.parse().ok()
+    };
+    String::from_utf8_lossy(response)
+        .lines()
+        .filter_map(|line| line.trim_end().strip_prefix("* ")?.split_once(" FETCH (").map(|(_, items)| items.to_string()))
+        .filter_map(|items| Some((number(&items, "UID ")? as u32, format!("{:x}", number(&items, "X-GM-MSGID ")?))))
+        .collect()
+}
+
+/// The UIDs of a `* SORT` response, in the server's order
+fn parse_sort_response(response: &[u8]) -> Vec<u32> {
+    String::from_utf8_lossy(response)
+        .lines()
+        .filter_map(|line| line.trim_end().strip_prefix("* SORT"))
+        .flat_map(|rest| rest.split_whitespace().filter_map(|uid| uid.parse().ok()).collect::<Vec<u32>>())
+        .collect()
+}
+
+/// The conversations of a `* THREAD` response: every top-level parenthesized thread,
+/// flattened in the order its messages are listed
+fn parse_thread_response(response: &[u8]) -> Vec<Vec<u32>> {
+    let mut threads: Vec<Vec<u32>> = Vec::new();
+    for line in String::from_utf8_lossy(response).lines() {
+        let Some(rest) = line.trim_end().strip_prefix("* THREAD") else {
+            continue;
+        };
+        let mut depth = 0;
+        let mut number = String::new();
+        for c in rest.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            if let (Ok(uid), Some(thread)) = (number.parse::<u32>(), threads.last_mut()) {
+                thread.push(uid);
+            }
+            number.clear();
+            match c {
+                '(' => {
+                    if depth == 0 {
+                        threads.push(Vec::new());
+                    }
+                    depth += 1;
+                }
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    threads.retain(|thread| !thread.is_empty());
+    threads
+}
+
+impl<T: Read + Write> ImapBackend for Session<T> {
+    fn uids(&mut self, folder: &str) -> Result<Vec<u32>, EmailError> {
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let mut uids: Vec<u32> = self
+            .uid_search("ALL")
+            .map_err(|e| imap_error("Failed to search emails", e))?
+            .into_iter()
+            .collect();
+        uids.sort_unstable();
+        Ok(uids)
+    }
+
+    fn fetch(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let messages = self
+            .uid_fetch(compress_uid_set(uids), "(BODY.PEEK[] FLAGS UID)")
+            .map_err(|e| imap_error("Failed to fetch messages", e))?;
+
+        Ok(messages
+            .iter()
+            .filter_map(|message| {
+                Some(FetchedMessage {
+                    uid: message.uid?,
+                    flags: message.flags().iter().map(|f| f.to_string()).collect(),
+                    raw: message.body()?.to_vec(),
+                })
+            })
+            .collect())
+    }
+
+    fn fetch_headers(&mut self, folder: &str, uids: &[u32]) -> Result<Vec<FetchedMessage>, EmailError> {
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let messages = self
+            .uid_fetch(compress_uid_set(uids), "(BODY.PEEK[HEADER] FLAGS UID)")
+            .map_err(|e| imap_error("Failed to fetch headers", e))?;
+
+        Ok(messages
+            .iter()
+            .filter_map(|message| {
+                Some(FetchedMessage {
+                    uid: message.uid?,
+                    flags: message.flags().iter().map(|f| f.to_string()).collect(),
+                    raw: message.header()?.to_vec(),
+                })
+            })
+            .collect())
+    }
+
+    fn flags(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, Vec<String>>, EmailError> {
+        if uids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        // EXAMINE keeps the mailbox read-only so the check itself never changes flags
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let messages = self
+            .uid_fetch(compress_uid_set(uids), "FLAGS")
+            .map_err(|e| imap_error("Failed to fetch flags", e))?;
+
+        Ok(messages
+            .iter()
+            .filter_map(|message| {
+                let uid = message.uid?;
+                let flags = message.flags().iter().map(|f| f.to_string()).collect();
+                Some((uid, flags))
+            })
+            .collect())
+    }
+
+    fn store_flag(&mut self, folder: &str, uid: u32, flag: &str, set: bool) -> Result<(), EmailError> {
+        self.select(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to select folder {}", folder), e))?;
+        let query = format!("{}FLAGS ({})", if set { "+" } else { "-" }, flag);
+        self.uid_store(uid.to_string(), query)
+            .map_err(|e| imap_error("Failed to store flags", e))?;
+        Ok(())
+    }
+
+    fn delete(&mut self, folder: &str, uid: u32) -> Result<(), EmailError> {
+        self.store_flag(folder, uid, "\\Deleted", true)?;
+        self.expunge().map_err(|e| imap_error("Failed to expunge", e))?;
+        Ok(())
+    }
+
+    fn move_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        self.select(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to select folder {}", folder), e))?;
+        let (has_move, has_uidplus) =
+            self.capabilities().map(|c| (c.has_str("MOVE"), c.has_str("UIDPLUS"))).unwrap_or((false, false));
+        if has_move {
+            self.uid_mv(uid.to_string(), crate::utf7::encode(target))
+                .map_err(|e| imap_error(&format!("Failed to move message to {}", target), e))?;
+            return Ok(());
+        }
+        if !has_uidplus {
+            return move_by_copy(self, folder, uid, target);
+        }
+        // Servers without MOVE (RFC 6851): copy, then flag the original \Deleted and
+        // expunge it. With UIDPLUS only this message goes, not others already flagged.
+        self.copy_message(folder, uid, target)?;
+        self.store_flag(folder, uid, "\\Deleted", true)?;
+        self.uid_expunge(uid.to_string()).map_err(|e| imap_error("Failed to expunge", e))?;
+        Ok(())
+    }
+
+    fn copy_message(&mut self, folder: &str, uid: u32, target: &str) -> Result<(), EmailError> {
+        self.select(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to select folder {}", folder), e))?;
+        self.uid_copy(uid.to_string(), crate::utf7::encode(target))
+            .map_err(|e| imap_error(&format!("Failed to copy message to {}", target), e))?;
+        Ok(())
+    }
+
+    fn expunge_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        self.select(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to select folder {}", folder), e))?;
+        self.expunge().map_err(|e| imap_error("Failed to expunge", e))?;
+        Ok(())
+    }
+
+    fn unseen_count(&mut self, folder: &str) -> Result<u32, EmailError> {
+        // STATUS works without selecting the folder, so it is cheap enough for status bars
+        let mailbox = self.status(crate::utf7::encode(folder), "(UNSEEN)").map_err(|e| match e {
+            imap::error::Error::No(_) => EmailError::NotFound(format!("Folder {}", folder)),
+            e => imap_error(&format!("Failed to get status of folder {}", folder), e),
+        })?;
+        Ok(mailbox.unseen.unwrap_or(0))
+    }
+
+    fn changes_since(&mut self, folder: &str, since: Option<(u32, u64)>) -> Result<Option<FolderChanges>, EmailError> {
+        let capabilities = self.capabilities().map_err(|e| imap_error("Failed to get capabilities", e))?;
+        let qresync = capabilities.has_str("QRESYNC");
+        if !qresync && !capabilities.has_str("CONDSTORE") {
+            return Ok(None);
+        }
+        if qresync {
+            self.run_command_and_read_response("ENABLE QRESYNC").map_err(|e| imap_error("Failed to enable QRESYNC", e))?;
+        }
+
+        let mailbox = format!("\"{}\"", crate::utf7::encode(folder).replace('\\', "\\\\").replace('"', "\\\""));
+        // QRESYNC reports the flag changes and expunges since the known state on opening
+        let command = match since {
+            Some((uid_validity, modseq)) if qresync => format!("EXAMINE {} (QRESYNC ({} {}))", mailbox, uid_validity, modseq),
+            _ => format!("EXAMINE {} (CONDSTORE)", mailbox),
+        };
+        let response = self.run_command_and_read_response(&command).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let mut changes = FolderChanges::default();
+        parse_changes(&response, &mut changes);
+        if changes.highest_modseq == 0 {
+            // NOMODSEQ: the folder doesn't keep mod-sequences
+            return Ok(None);
+        }
+
+        match since {
+            Some((uid_validity, _)) if uid_validity != changes.uid_validity => {
+                changes.flags.clear();
+                changes.vanished = None;
+            }
+            Some(_) if qresync => {
+                changes.vanished.get_or_insert_with(Vec::new);
+            }
+            Some((_, modseq)) => {
+                let response = self
+                    .run_command_and_read_response(format!("UID FETCH 1:* (UID FLAGS) (CHANGEDSINCE {})", modseq))
+                    .map_err(|e| imap_error("Failed to fetch changed flags", e))?;
+                parse_changes(&response, &mut changes);
+            }
+            None => {}
+        }
+        Ok(Some(changes))
+    }
+
+    fn search(&mut self, folder: &str, criteria: &str) -> Result<Option<Vec<u32>>, EmailError> {
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let mut uids: Vec<u32> = self
+            .uid_search(criteria)
+            .map_err(|e| imap_error("Failed to search the server", e))?
+            .into_iter()
+            .collect();
+        uids.sort_unstable();
+        Ok(Some(uids))
+    }
+
+    fn sort(&mut self, folder: &str) -> Result<Option<Vec<u32>>, EmailError> {
+        let capabilities = self.capabilities().map_err(|e| imap_error("Failed to get capabilities", e))?;
+        if !capabilities.has_str("SORT") {
+            return Ok(None);
+        }
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let response = self
+            .run_command_and_read_response("UID SORT (REVERSE DATE) UTF-8 ALL")
+            .map_err(|e| imap_error(&format!("Failed to sort folder {}", folder), e))?;
+        Ok(Some(parse_sort_response(&response)))
+    }
+
+    fn threads(&mut self, folder: &str) -> Result<Option<Vec<Vec<u32>>>, EmailError> {
+        let capabilities = self.capabilities().map_err(|e| imap_error("Failed to get capabilities", e))?;
+        if !capabilities.has_str("THREAD=REFERENCES") {
+            return Ok(None);
+        }
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let response = self
+            .run_command_and_read_response("UID THREAD REFERENCES UTF-8 ALL")
+            .map_err(|e| imap_error(&format!("Failed to thread folder {}", folder), e))?;
+        Ok(Some(parse_thread_response(&response)))
+    }
+
+    fn special_use_folders(&mut self) -> Result<SpecialFolders, EmailError> {
+        let capabilities = self.capabilities().map_err(|e| imap_error("Failed to get capabilities", e))?;
+        // Servers without SPECIAL-USE often mark the folders in a plain LIST anyway
+        let command = if capabilities.has_str("SPECIAL-USE") {
+            "LIST \"\" \"*\" RETURN (SPECIAL-USE)"
+        } else if capabilities.has_str("XLIST") {
+            "XLIST \"\" \"*\""
+        } else {
+            "LIST \"\" \"*\""
+        };
+        let response = self.run_command_and_read_response(command).map_err(|e| imap_error("Failed to list folders", e))?;
+        let mut special = SpecialFolders::default();
+        for (attributes, name) in parse_list_response(&response) {
+            for attribute in &attributes {
+                special.assign(attribute, &name);
+            }
+        }
+        Ok(special)
+    }
+
+    fn gmail_ids(&mut self, folder: &str, uids: &[u32]) -> Result<HashMap<u32, String>, EmailError> {
+        let capabilities = self.capabilities().map_err(|e| imap_error("Failed to get capabilities", e))?;
+        if uids.is_empty() || !capabilities.has_str("X-GM-EXT-1") {
+            return Ok(HashMap::new());
+        }
+        self.examine(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to examine folder {}", folder), e))?;
+        let response = self
+            .run_command_and_read_response(format!("UID FETCH {} (UID X-GM-MSGID)", compress_uid_set(uids)))
+            .map_err(|e| imap_error("Failed to fetch Gmail message ids", e))?;
+        Ok(parse_gmail_ids(&response))
+    }
+
+    fn append(&mut self, folder: &str, raw: &[u8], flags: &[String]) -> Result<(), EmailError> {
+        // \Recent can only be set by the server
+        let flags: Vec<imap::types::Flag<'_>> = flags
+            .iter()
+            .filter(|f| f.as_str() != "\\Recent")
+            .map(|f| imap::types::Flag::from(f.as_str()))
+            .collect();
+        self.append_with_flags(crate::utf7::encode(folder), raw, &flags)
+            .map_err(|e| imap_error(&format!("Failed to append message to {}", folder), e))
+    }
+
+    fn create_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        self.create(crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to create folder {}", folder), e))
+    }
+
+    fn rename_folder(&mut self, folder: &str, new_name: &str) -> Result<(), EmailError> {
+        self.rename(crate::utf7::encode(folder), crate::utf7::encode(new_name))
+            .map_err(|e| imap_error(&format!("Failed to rename folder {} to {}", folder, new_name), e))
+    }
+
+    fn delete_folder(&mut self, folder: &str) -> Result<(), EmailError> {
+        // The inherent DELETE, not the trait's message delete
+        Session::delete(self, crate::utf7::encode(folder)).map_err(|e| imap_error(&format!("Failed to delete folder {}", folder), e))
+    }
+}
+
+/// Server replies stay `SmtpError`; failing to connect or to negotiate TLS is a
+/// `ConnectionError`, so sending can fall back to other settings
+pub(crate) fn smtp_error(e: lettre::transport::smtp::Error) -> EmailError {
+    if e.is_transient() || e.is_permanent() {
+        EmailError::SmtpError(e.to_string())
+    } else {
+        EmailError::ConnectionError(e.to_string())
+    }
+}
+
+impl SmtpBackend for SmtpTransport {
+    fn send(&mut self, message: &Message) -> Result<(), EmailError> {
+        Transport::send(self, message).map_err(smtp_error)?;
+        Ok(())
+    }
+
+    fn send_raw(&mut self, envelope: &Envelope, raw: &[u8]) -> Result<(), EmailError> {
+        Transport::send_raw(self, envelope, raw).map_err(smtp_error)?;
+        Ok(())
+    }
+}
+
+/// Fetch messages newer than the newest cached one and store them. Returns how many
+/// messages were added.
+pub fn sync_folder(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+) -> Result<usize, EmailError> {
+    let last_uid = database
+        .get_last_uid(account_email, folder)
+        .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))?;
+
+    let new_uids: Vec<u32> = imap.uids(folder)?.into_iter().filter(|uid| *uid > last_uid).collect();
+    if new_uids.is_empty() {
+        return Ok(0);
+    }
+
+    let emails = fetch_for_storage(imap, database, account_email, folder, &new_uids)?;
+    database
+        .save_emails(account_email, folder, &emails)
+        .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))?;
+    crate::dsn::record_bounces(database, account_email, &emails);
+
+    debug_log(&format!("sync_folder: stored {} new emails for {}/{}", emails.len(), account_email, folder));
+    Ok(emails.len())
+}
+
+/// Headers are fetched in batches of this many messages
+const HEADER_BATCH: usize = 500;
+
+/// Full messages of an initial sync are fetched in batches of this many
+const INITIAL_BATCH: usize = 500;
+
+fn parse_fetched(messages: Vec<FetchedMessage>, folder: &str) -> Result<Vec<Email>, EmailError> {
+    let mut emails = Vec::new();
+    for message in messages {
+        let Some(parsed) = crate::mime::parse_message(&message.raw) else {
+            debug_log(&format!("Could not parse UID {} in {}", message.uid, folder));
+            continue;
+        };
+        emails.push(Email::from_parsed_email(&parsed, &message.uid.to_string(), folder, message.flags)?);
+    }
+    Ok(emails)
+}
+
+/// Fetch and parse messages about to be stored: large attachments go to cache files as
+/// each message is parsed, and on Gmail each message gets its Gmail id
+fn fetch_for_storage(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    uids: &[u32],
+) -> Result<Vec<Email>, EmailError> {
+    let messages = imap.fetch(folder, uids)?;
+    let mut gmail_ids = imap.gmail_ids(folder, uids)?;
+    let mut emails = Vec::new();
+    for message in messages {
+        let gmail_id = gmail_ids.remove(&message.uid);
+        for mut email in parse_fetched(vec![message], folder)? {
+            database.spill_attachments(account_email, folder, &mut email).map_err(database_error)?;
+            email.gmail_id = gmail_id.clone();
+            emails.push(email);
+        }
+    }
+    Ok(emails)
+}
+
+fn database_error(e: anyhow::Error) -> EmailError {
+    EmailError::ConnectionError(format!("Database error: {}", e))
+}
+
+/// Store the headers of messages newer than the newest cached one, so a large folder can
+/// be listed before any bodies have been downloaded. The bodies are recorded as pending
+/// for `fetch_bodies`. Returns how many messages were added.
+///
+/// When the server can sort, the newest messages by date are listed first, so a huge
+/// folder shows its recent mail after the first batch; otherwise they go in UID order.
+pub fn sync_folder_headers(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+) -> Result<usize, EmailError> {
+    // An interrupted listing carries on after the last batch it stored
+    let last_uid = match database.get_sync_progress(account_email, folder).map_err(database_error)? {
+        Some(uid) => uid,
+        None => database.get_last_uid(account_email, folder).map_err(database_error)?,
+    };
+    let sorted = imap.sort(folder)?;
+    let (all_uids, new_uids) = match sorted {
+        Some(sorted) => {
+            // Batches no longer end at the highest UID listed so far, so an interrupted
+            // listing resumes from where this one started and skips what it stored
+            database.save_sync_progress(account_email, folder, last_uid).map_err(database_error)?;
+            let cached: std::collections::HashSet<u32> = database.get_uids(account_email, folder).map_err(database_error)?.into_iter().collect();
+            let new_uids: Vec<u32> = sorted.iter().copied().filter(|uid| *uid > last_uid && !cached.contains(uid)).collect();
+            let mut all_uids = sorted;
+            all_uids.sort_unstable();
+            (all_uids, new_uids)
+        }
+        None => {
+            let all_uids = imap.uids(folder)?;
+            let new_uids = all_uids.iter().copied().filter(|uid| *uid > last_uid).collect();
+            (all_uids, new_uids)
+        }
+    };
+    // Only batches listed in UID order can record the last one as how far they got
+    let in_uid_order = new_uids.windows(2).all(|pair| pair[0] < pair[1]);
+
+    let mut added = 0;
+    for batch in new_uids.chunks(HEADER_BATCH) {
+        let mut emails = parse_fetched(imap.fetch_headers(folder, batch)?, folder)?;
+        let mut gmail_ids = imap.gmail_ids(folder, batch)?;
+        // A header block parses as an empty body; keep it unset until the body arrives
+        for email in &mut emails {
+            email.body_text = None;
+            email.body_html = None;
+            email.gmail_id = email.id.parse().ok().and_then(|uid| gmail_ids.remove(&uid));
+        }
+        // A message already stored under another Gmail label shares its body, so only the
+        // others wait for a download
+        let mut uids = Vec::new();
+        for email in &emails {
+            if !database.has_shared_content(account_email, email).map_err(database_error)? {
+                uids.extend(email.id.parse::<u32>().ok());
+            }
+        }
+        database.save_emails(account_email, folder, &emails).map_err(database_error)?;
+        database.mark_bodies_pending(account_email, folder, &uids).map_err(database_error)?;
+        if let Some(batch_end) = batch.last().filter(|_| in_uid_order) {
+            database.save_sync_progress(account_email, folder, *batch_end).map_err(database_error)?;
+        }
+        added += emails.len();
+    }
+
+    // Let the regular incremental sync carry on from here instead of starting over
+    if let Some(newest) = all_uids.last() {
+        database
+            .save_folder_metadata(account_email, folder, *newest, all_uids.len() as u32)
+            .map_err(database_error)?;
+    }
+    database.clear_sync_progress(account_email, folder).map_err(database_error)?;
+
+    debug_log(&format!("sync_folder_headers: stored {} headers for {}/{}", added, account_email, folder));
+    Ok(added)
+}
+
+/// First download of a folder, in batches by UID. Each batch is stored as soon as it
+/// arrives and the progress recorded, so an interrupted sync continues after the last
+/// stored batch instead of starting over. Returns the messages fetched by this call.
+pub fn initial_sync(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+) -> Result<Vec<Email>, EmailError> {
+    let resume_after = database.get_sync_progress(account_email, folder).map_err(database_error)?;
+    let all_uids = imap.uids(folder)?;
+    let todo: Vec<u32> = all_uids.iter().copied().filter(|uid| *uid > resume_after.unwrap_or(0)).collect();
+    if let Some(uid) = resume_after {
+        debug_log(&format!("initial_sync: resuming {}/{} after UID {}, {} left", account_email, folder, uid, todo.len()));
+    }
+
+    let mut fetched = Vec::new();
+    for batch in todo.chunks(INITIAL_BATCH) {
+        let emails = fetch_for_storage(imap, database, account_email, folder, batch)?;
+        database.save_emails(account_email, folder, &emails).map_err(database_error)?;
+        crate::dsn::record_bounces(database, account_email, &emails);
+        if let Some(batch_end) = batch.last() {
+            database.save_sync_progress(account_email, folder, *batch_end).map_err(database_error)?;
+        }
+        fetched.extend(emails);
+    }
+
+    if let Some(newest) = all_uids.last() {
+        database
+            .save_folder_metadata(account_email, folder, *newest, all_uids.len() as u32)
+            .map_err(database_error)?;
+    }
+    database.clear_sync_progress(account_email, folder).map_err(database_error)?;
+
+    debug_log(&format!("initial_sync: stored {} emails for {}/{}", fetched.len(), account_email, folder));
+    Ok(fetched)
+}
+
+/// Download the full messages for UIDs stored with headers only. Returns the UIDs whose
+/// bodies were stored.
+pub fn fetch_bodies(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    uids: &[u32],
+) -> Result<Vec<u32>, EmailError> {
+    if uids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let emails = fetch_for_storage(imap, database, account_email, folder, uids)?;
+    let stored: Vec<u32> = emails.iter().filter_map(|e| e.id.parse().ok()).collect();
+    database.save_emails(account_email, folder, &emails).map_err(database_error)?;
+    crate::dsn::record_bounces(database, account_email, &emails);
+    // Messages gone from the server are not pending anymore either
+    database.clear_bodies_pending(account_email, folder, uids).map_err(database_error)?;
+
+    debug_log(&format!("fetch_bodies: stored {} of {} bodies for {}/{}", stored.len(), uids.len(), account_email, folder));
+    Ok(stored)
+}
+
+/// Send the server the read state set here while it could not be reached: the queued
+/// `mark_read` and `mark_unread` operations of the folder, carried out in order. Returns
+/// the local read state of every message that had one queued, pushed or not; the
+/// server's state of those is older and must not replace it.
+fn push_seen_changes(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+) -> Result<HashMap<u32, bool>, EmailError> {
+    let mut local = HashMap::new();
+    let pending = database.get_pending_operations().map_err(database_error)?;
+    let seen_changes = pending.iter().filter(|op| {
+        op.account_email == account_email && op.folder == folder && matches!(op.operation_type.as_str(), "mark_read" | "mark_unread")
+    });
+    for op in seen_changes {
+        local.insert(op.email_uid, op.operation_type == "mark_read");
+        if crate::email::is_read_only() {
+            continue;
+        }
+        match apply_operation(imap, database, op) {
+            Ok(()) => database.mark_operation_processed(op.id).map_err(database_error)?,
+            // Left queued for the background worker to retry
+            Err(e) => debug_log(&format!("Failed to send the read state of UID {} in {}: {}", op.email_uid, folder, e)),
+        }
+    }
+    Ok(local)
+}
+
+/// Two-way read-state sync of cached messages: read state changed here and not sent
+/// yet goes to the server first, then the server's state of the others is written to
+/// the local database. Returns the new seen state of every message whose state changed.
+pub fn sync_seen_flags(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    cached: &[(u32, bool)],
+) -> Result<HashMap<u32, bool>, EmailError> {
+    let local = push_seen_changes(imap, database, account_email, folder)?;
+    let uids: Vec<u32> = cached.iter().map(|(uid, _)| *uid).filter(|uid| !local.contains_key(uid)).collect();
+    let server_flags = imap.flags(folder, &uids)?;
+
+    let mut changed = HashMap::new();
+    for (uid, was_seen) in cached {
+        let Some(flags) = server_flags.get(uid) else {
+            continue;
+        };
+        let seen = flags.iter().any(|f| f == "\\Seen");
+        if seen != *was_seen {
+            if let Err(e) = database.update_email_flags(account_email, folder, *uid, flags, seen) {
+                debug_log(&format!("Failed to store flags for UID {}: {}", uid, e));
+            }
+            changed.insert(*uid, seen);
+        }
+    }
+
+    debug_log(&format!("sync_seen_flags: {} of {} messages changed in {}", changed.len(), cached.len(), folder));
+    Ok(changed)
+}
+
+/// Set or clear \Seen on the server and mirror it in the local database
+pub fn set_seen(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    email: &Email,
+    seen: bool,
+) -> Result<(), EmailError> {
+    ensure_writable(if seen { "mark email as read" } else { "mark email as unread" })?;
+
+    let uid: u32 = email
+        .id
+        .parse()
+        .map_err(|_| EmailError::ImapError(format!("Invalid email UID '{}'", email.id)))?;
+    imap.store_flag(&email.folder, uid, "\\Seen", seen)?;
+    database
+        .update_email_seen_status(account_email, &email.folder, uid, seen)
+        .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+}
+
+/// Add or remove a flag such as `\Flagged` on the server and mirror it in the local database
+pub fn set_flag(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    email: &Email,
+    flag: &str,
+    set: bool,
+) -> Result<(), EmailError> {
+    ensure_writable(if set { "flag email" } else { "unflag email" })?;
+
+    let uid = parse_uid(email)?;
+    imap.store_flag(&email.folder, uid, flag, set)?;
+
+    let mut flags: Vec<String> = email.flags.iter().filter(|f| *f != flag).cloned().collect();
+    if set {
+        flags.push(flag.to_string());
+    }
+    let seen = flags.iter().any(|f| f == "\\Seen");
+    database
+        .update_email_flags(account_email, &email.folder, uid, &flags, seen)
+        .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+}
+
+/// Move a message to another folder and drop it from the source folder's cache. The
+/// copy in the target folder is picked up by that folder's next sync.
+pub fn move_email(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    email: &Email,
+    target: &str,
+) -> Result<(), EmailError> {
+    ensure_writable("move email")?;
+
+    let uid = parse_uid(email)?;
+    imap.move_message(&email.folder, uid, target)?;
+    database
+        .delete_email(account_email, &email.folder, uid)
+        .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+}
+
+/// Create a folder on the server
+pub fn create_folder(imap: &mut dyn ImapBackend, folder: &str) -> Result<(), EmailError> {
+    ensure_writable("create folders")?;
+    imap.create_folder(folder)
+}
+
+/// Rename a folder on the server, and move what is cached of it and the folders below
+/// it to the new name
+pub fn rename_folder(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    new_name: &str,
+) -> Result<(), EmailError> {
+    ensure_writable("rename folders")?;
+    if folder.eq_ignore_ascii_case("INBOX") {
+        return Err(EmailError::ImapError("The INBOX can't be renamed".to_string()));
+    }
+    imap.rename_folder(folder, new_name)?;
+    database
+        .rename_folder(account_email, folder, new_name)
+        .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+}
+
+/// Delete a folder on the server with its messages, and forget what was cached of it
+pub fn delete_folder(imap: &mut dyn ImapBackend, database: &EmailDatabase, account_email: &str, folder: &str) -> Result<(), EmailError> {
+    ensure_writable("delete folders")?;
+    if folder.eq_ignore_ascii_case("INBOX") {
+        return Err(EmailError::ImapError("The INBOX can't be deleted".to_string()));
+    }
+    imap.delete_folder(folder)?;
+    database
+        .forget_folder(account_email, folder)
+        .map_err(|e| EmailError::ConnectionError(format!("Database error: {}", e)))
+}
+
+/// Move messages of a folder to `target`, or delete them when it is `None`, over one
+/// connection, dropping each from the cache as it goes. On an error the messages before
+/// the failing one stay removed.
+pub fn remove_messages(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    uids: &[u32],
+    target: Option<&str>,
+) -> Result<usize, EmailError> {
+    ensure_writable(if target.is_some() { "move emails" } else { "delete emails" })?;
+
+    for (done, uid) in uids.iter().enumerate() {
+        let result = match target {
+            Some(target) => imap
+                .move_message(folder, *uid, target)
+                .and_then(|_| database.delete_email(account_email, folder, *uid).map_err(database_error)),
+            None => delete_message(imap, database, account_email, folder, *uid),
+        };
+        if let Err(e) = result {
+            debug_log(&format!("remove_messages: stopped after {} of {} in {}: {}", done, uids.len(), folder, e));
+            return Err(e);
+        }
+    }
+    Ok(uids.len())
+}
+
+fn deleted_record(email: &Email, uid: u32, elsewhere: bool) -> crate::database::DeletedMessage {
+    crate::database::DeletedMessage {
+        id: 0,
+        folder: email.folder.clone(),
+        uid,
+        subject: email.subject.clone(),
+        sender: email
+            .from
+            .first()
+            .map(|from| from.name.clone().filter(|name| !name.is_empty()).unwrap_or_else(|| from.address.clone()))
+            .unwrap_or_default(),
+        flags: email.flags.iter().filter(|flag| *flag != "\\Deleted").cloned().collect(),
+        deleted_at: chrono::Local::now().timestamp(),
+        elsewhere,
+    }
+}
+
+/// Delete a message on the server and drop it from the cache, keeping its raw content
+/// in the recently deleted store first. Failing to keep a copy doesn't stop the delete.
+pub fn delete_message(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+    uid: u32,
+) -> Result<(), EmailError> {
+    ensure_writable("delete email")?;
+
+    let kept = imap.fetch(folder, &[uid]).map_err(|e| e.to_string()).and_then(|mut fetched| {
+        let message = fetched.pop().ok_or_else(|| "not on the server".to_string())?;
+        let raw = message.raw.clone();
+        let email = parse_fetched(vec![message], folder)
+            .map_err(|e| e.to_string())?
+            .pop()
+            .ok_or_else(|| "unparsable message".to_string())?;
+        database.keep_deleted(account_email, &deleted_record(&email, uid, false), &raw).map_err(|e| e.to_string())
+    });
+    if let Err(e) = kept {
+        debug_log(&format!("delete_message: no copy kept of UID {} in {}: {}", uid, folder, e));
+    }
+
+    imap.delete(folder, uid)?;
+    database.delete_email(account_email, folder, uid).map_err(database_error)
+}
+
+/// Carry out an operation queued while browsing on the server and in the cache
+pub fn apply_operation(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    op: &crate::database::PendingOperation,
+) -> Result<(), EmailError> {
+    let (folder, uid) = (op.folder.as_str(), op.email_uid);
+    match (op.operation_type.as_str(), op.target_folder.as_deref()) {
+        ("mark_read" | "mark_unread", _) => {
+            let seen = op.operation_type == "mark_read";
+            ensure_writable(if seen { "mark email as read" } else { "mark email as unread" })?;
+            imap.store_flag(folder, uid, "\\Seen", seen)?;
+            database.update_email_seen_status(&op.account_email, folder, uid, seen).map_err(database_error)
+        }
+        ("mark_answered" | "mark_forwarded", _) => {
+            let flag = if op.operation_type == "mark_answered" { "\\Answered" } else { "$Forwarded" };
+            ensure_writable("flag email")?;
+            imap.store_flag(folder, uid, flag, true)?;
+            database.add_email_flag(&op.account_email, folder, uid, flag).map_err(database_error)
+        }
+        ("delete", _) => delete_message(imap, database, &op.account_email, folder, uid),
+        ("move", Some(target)) => {
+            ensure_writable("move email")?;
+            imap.move_message(folder, uid, target)?;
+            database.delete_email(&op.account_email, folder, uid).map_err(database_error)
+        }
+        ("move", None) => Err(EmailError::ImapError("No target folder for the move".to_string())),
+        (other, _) => Err(EmailError::ImapError(format!("Unknown operation '{}'", other))),
+    }
+}
+
+/// Messages a server search fetches per folder at most, newest first
+const SERVER_SEARCH_LIMIT: usize = 100;
+
+/// Search the server for what the local search can't see: messages of `folders` that
+/// match `criteria` (see `SearchQuery::to_imap`) and are not cached, fetched but not
+/// stored, and cached ones still waiting for their body. Folders with every message
+/// cached in full are not searched, nor are any when the backend can't search.
+pub fn search_server(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folders: &[String],
+    criteria: &str,
+) -> Result<Vec<Email>, EmailError> {
+    let mut found = Vec::new();
+    for folder in folders {
+        let cached: std::collections::HashSet<u32> = database.get_uids(account_email, folder).map_err(database_error)?.into_iter().collect();
+        let waiting: std::collections::HashSet<u32> =
+            database.get_pending_body_uids(account_email, folder, None).map_err(database_error)?.into_iter().collect();
+        let (_, total, last_sync) = database.load_folder_metadata(account_email, folder).map_err(database_error)?;
+        if last_sync > 0 && waiting.is_empty() && cached.len() >= total as usize {
+            continue;
+        }
+
+        let uids = match imap.search(folder, criteria) {
+            Ok(Some(uids)) => uids,
+            Ok(None) => break,
+            // Such as a folder that can't be selected; the others are still searched
+            Err(e) => {
+                debug_log(&format!("search_server: {}/{} failed: {}", account_email, folder, e));
+                continue;
+            }
+        };
+        let headers_only: Vec<u32> = uids.iter().copied().filter(|uid| waiting.contains(uid)).collect();
+        found.extend(database.get_emails_by_uids(account_email, folder, &headers_only).map_err(database_error)?);
+        let missing: Vec<u32> = uids.into_iter().filter(|uid| !cached.contains(uid)).collect();
+        let newest = &missing[missing.len().saturating_sub(SERVER_SEARCH_LIMIT)..];
+        found.extend(parse_fetched(imap.fetch(folder, newest)?, folder)?);
+    }
+    debug_log(&format!("search_server: {} messages for {} in {} folders", found.len(), account_email, folders.len()));
+    Ok(found)
+}
+
+/// Move cached messages that are no longer on the server, because another client
+/// expunged them, into the recently deleted store. Their content is rebuilt from the
+/// cache. Returns how many were found. Nothing is touched when none of the cached UIDs
+/// is on the server any more, as that means the folder was renumbered rather than emptied.
+pub fn keep_expunged(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+) -> Result<usize, EmailError> {
+    let on_server: std::collections::HashSet<u32> = imap.uids(folder)?.into_iter().collect();
+    let cached = database.get_uids(account_email, folder).map_err(database_error)?;
+    let newest = on_server.iter().max().copied().unwrap_or(0);
+    // Messages above the newest UID on the server can't have been expunged from it
+    let gone: Vec<u32> = cached.iter().copied().filter(|uid| !on_server.contains(uid) && *uid <= newest).collect();
+    if gone.is_empty() || gone.len() == cached.len() {
+        return Ok(0);
+    }
+    keep_gone(database, account_email, folder, &gone)?;
+    Ok(gone.len())
+}
+
+/// Move the cached messages with UIDs `gone`, expunged by another client, into the
+/// recently deleted store
+fn keep_gone(database: &EmailDatabase, account_email: &str, folder: &str, gone: &[u32]) -> Result<(), EmailError> {
+    for email in database.get_emails_by_uids(account_email, folder, gone).map_err(database_error)? {
+        let Ok(uid) = email.id.parse::<u32>() else {
+            continue;
+        };
+        match rebuild_message(&email) {
+            Ok(raw) => database.keep_deleted(account_email, &deleted_record(&email, uid, true), &raw).map_err(database_error)?,
+            Err(e) => debug_log(&format!("keep_expunged: could not rebuild UID {} in {}: {}", uid, folder, e)),
+        }
+    }
+    for uid in gone {
+        database.delete_email(account_email, folder, *uid).map_err(database_error)?;
+    }
+    debug_log(&format!("keep_expunged: {} messages of {}/{} were expunged elsewhere", gone.len(), account_email, folder));
+    Ok(())
+}
+
+/// What `sync_changes` brought over from the server
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncedChanges {
+    /// New read state of the messages whose read state changed
+    pub seen: HashMap<u32, bool>,
+    /// Messages expunged by another client, now in the recently deleted store
+    pub expunged: usize,
+}
+
+/// Bring the cached flags of a folder up to date and keep the messages another client
+/// expunged, asking the server only for what changed since the mod-sequence stored last
+/// time (CONDSTORE, and QRESYNC for expunges). `None` when the server can't tell or there
+/// is no earlier state to start from; the caller then compares every message, and the
+/// next call starts from the state stored now. Read state queued here goes to the server
+/// first, as in `sync_seen_flags`.
+pub fn sync_changes(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    account_email: &str,
+    folder: &str,
+) -> Result<Option<SyncedChanges>, EmailError> {
+    let local = push_seen_changes(imap, database, account_email, folder)?;
+    let since = database.get_modseq(account_email, folder).map_err(database_error)?;
+    let Some(changes) = imap.changes_since(folder, since)? else {
+        return Ok(None);
+    };
+    let known = since.filter(|(uid_validity, _)| *uid_validity == changes.uid_validity);
+    if known.is_none() {
+        database
+            .save_modseq(account_email, folder, changes.uid_validity, changes.highest_modseq)
+            .map_err(database_error)?;
+        return Ok(None);
+    }
+
+    let mut synced = SyncedChanges::default();
+    let changed: Vec<u32> = changes.flags.keys().copied().filter(|uid| !local.contains_key(uid)).collect();
+    for email in database.get_emails_by_uids(account_email, folder, &changed).map_err(database_error)? {
+        let Some((uid, flags)) = email.id.parse::<u32>().ok().and_then(|uid| changes.flags.get_key_value(&uid)) else {
+            continue;
+        };
+        let seen = flags.iter().any(|f| f == "\\Seen");
+        database.update_email_flags(account_email, folder, *uid, flags, seen).map_err(database_error)?;
+        if seen != email.seen {
+            synced.seen.insert(*uid, seen);
+        }
+    }
+
+    synced.expunged = match &changes.vanished {
+        Some(_) => {
+            let cached = database.get_uids(account_email, folder).map_err(database_error)?;
+            let gone: Vec<u32> = cached.into_iter().filter(|uid| changes.is_vanished(*uid)).collect();
+            if !gone.is_empty() {
+                keep_gone(database, account_email, folder, &gone)?;
+            }
+            gone.len()
+        }
+        None => keep_expunged(imap, database, account_email, folder)?,
+    };
+
+    database
+        .save_modseq(account_email, folder, changes.uid_validity, changes.highest_modseq)
+        .map_err(database_error)?;
+    debug_log(&format!(
+        "sync_changes: {} flag changes and {} expunges in {}/{} up to modseq {}",
+        changes.flags.len(),
+        synced.expunged,
+        account_email,
+        folder,
+        changes.highest_modseq
+    ));
+    Ok(Some(synced))
+}
+
+/// Put a kept message back into the folder it was deleted from with APPEND, and drop
+/// the kept copy. The restored message gets a new UID and is cached by the next sync.
+pub fn restore_deleted(
+    imap: &mut dyn ImapBackend,
+    database: &EmailDatabase,
+    message: &crate::database::DeletedMessage,
+) -> Result<(), EmailError> {
+    ensure_writable("restore email")?;
+
+    let raw = database
+        .get_deleted_raw(message.id)
+        .map_err(database_error)?
+        .ok_or_else(|| EmailError::ImapError("The kept copy is gone".to_string()))?;
+    imap.append(&message.folder, &raw, &message.flags)?;
+    database.forget_deleted(message.id).map_err(database_error)
+}
+
+fn parse_uid(email: &Email) -> Result<u32, EmailError> {
+    email
+        .id
+        .parse()
+        .map_err(|_| EmailError::ImapError(format!("Invalid email UID '{}'", email.id)))
+}
+
+/// Headers of a composed email that are passed through to the sent message as they are
+const EXTRA_HEADERS: [&str; 3] = ["X-Priority", "Importance", "Disposition-Notification-To"];
+
+/// One of `EXTRA_HEADERS`. lettre stores a header under the name its `display()`
+/// returns, so one type serves them all; `name()` is only used for lookups.
+#[derive(Clone)]
+struct ExtraHeader {
+    name: &'static str,
+    value: String,
+}
+
+impl Header for ExtraHeader {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("X-Priority")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self { name: "X-Priority", value: s.to_string() })
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(HeaderName::new_from_ascii_str(self.name), self.value.clone())
+    }
+}
+
+/// Build the MIME message for an outgoing email. `default_from` is used when the email
+/// has no From address.
+pub fn build_message(email: &Email, default_from: &EmailAddress) -> Result<Message, EmailError> {
+    build(email, default_from, false, false, None, None)
+}
+
+/// Build an outgoing message signed or encrypted as `protection` asks, with the keys of
+/// `pgp`. `keep_bcc` leaves the Bcc header in, for a backend that [keeps
+/// it](SmtpBackend::keeps_bcc).
+pub fn build_protected_message(
+    email: &Email,
+    default_from: &EmailAddress,
+    pgp: &crate::config::PgpConfig,
+    protection: crate::pgp::Protection,
+    keep_bcc: bool,
+) -> Result<Message, EmailError> {
+    build(email, default_from, false, keep_bcc, None, Some((pgp, protection)))
+}
+
+/// The raw message a draft is stored as in the Drafts folder. Unlike a sent message it
+/// keeps Bcc and may have no recipients yet.
+pub fn build_draft(email: &Email, default_from: &EmailAddress) -> Result<Vec<u8>, EmailError> {
+    Ok(build(email, default_from, true, true, None, None)?.formatted())
+}
+
+/// A message put back together from what the cache holds of it, for one that is gone
+/// from the server. Headers other than the addresses, threading and priority are lost,
+/// as are attachments that were never downloaded.
+pub fn rebuild_message(email: &Email) -> Result<Vec<u8>, EmailError> {
+    let mut email = email.clone();
+    email.attachments.retain(|attachment| attachment.load_data().is_ok_and(|data| !data.is_empty()));
+    let from = email.from.first().cloned().unwrap_or(EmailAddress { name: None, address: "unknown@localhost".to_string() });
+    Ok(build(&email, &from, true, true, Some(email.date.into()), None)?.formatted())
+}
+
+fn build(
+    email: &Email,
+    default_from: &EmailAddress,
+    draft: bool,
+    keep_bcc: bool,
+    date: Option<std::time::SystemTime>,
+    pgp: Option<(&crate::config::PgpConfig, crate::pgp::Protection)>,
+) -> Result<Message, EmailError> {
+    let mut message_builder = Message::builder().subject(&email.subject);
+    if let Some(date) = date {
+        message_builder = message_builder.date(date);
+    }
+
+    let from = email.from.first().unwrap_or(default_from);
+    message_builder = message_builder.from(from.clone().into());
+    if draft {
+        let mailbox: lettre::message::Mailbox = from.clone().into();
+        let envelope = Envelope::new(Some(mailbox.email.clone()), vec![mailbox.email])
+            .map_err(|e| EmailError::SmtpError(e.to_string()))?;
+        message_builder = message_builder.envelope(envelope);
+    }
+    if keep_bcc {
+        message_builder = message_builder.keep_bcc();
+    }
+
+    for to in &email.to {
+        message_builder = message_builder.to(to.clone().into());
+    }
+    for cc in &email.cc {
+        message_builder = message_builder.cc(cc.clone().into());
+    }
+    for bcc in &email.bcc {
+        message_builder = message_builder.bcc(bcc.clone().into());
+    }
+
+    // Threading, priority and read-receipt headers set while composing
+    let header = |name: &str| email.headers.get(name).cloned();
+    // Only drafts carry a Message-ID while composing; it stays the same across saves
+    if let Some(id) = header("Message-ID") {
+        message_builder = message_builder.message_id(Some(format!("<{}>", id.trim_matches(['<', '>']))));
+    }
+    if let Some(id) = header("In-Reply-To") {
+        message_builder = message_builder.in_reply_to(id);
+    }
+    if let Some(ids) = header("References") {
+        message_builder = message_builder.references(ids);
+    }
+    for name in EXTRA_HEADERS {
+        if let Some(value) = header(name) {
+            message_builder = message_builder.header(ExtraHeader { name, value });
+        }
+    }
+
+    let mut body_part = MultiPart::alternative()
+        .singlepart(SinglePart::plain(email.body_text.clone().unwrap_or_default()));
+    if let Some(html) = &email.body_html {
+        body_part = body_part.singlepart(SinglePart::html(html.clone()));
+    }
+    // Invitation replies carry the calendar object as another form of the text (RFC 6047)
+    let (scheduling, attachments): (Vec<&EmailAttachment>, Vec<&EmailAttachment>) =
+        email.attachments.iter().partition(|attachment| is_scheduling_part(&attachment.content_type));
+    for part in scheduling {
+        let content_type = ContentType::parse(&part.content_type).map_err(|e| EmailError::SmtpError(e.to_string()))?;
+        body_part = body_part.singlepart(SinglePart::builder().header(content_type).body(part.load_data()?));
+    }
+
+    let final_multipart = if attachments.is_empty() {
+        body_part
+    } else {
+        let mut mixed_part = MultiPart::mixed().multipart(body_part);
+        for attachment in attachments {
+            let data = attachment.load_data()?;
+            let content_type = attachment
+                .content_type
+                .parse()
+                .unwrap_or_else(|_| "application/octet-stream".parse().expect("valid content type"));
+            mixed_part = mixed_part.singlepart(Attachment::new(attachment.filename.clone()).body(data, content_type));
+        }
+        mixed_part
+    };
+    let final_multipart = match pgp {
+        Some((config, protection)) if protection.any() => {
+            let addresses = |list: &[EmailAddress]| list.iter().map(|a| a.address.clone()).collect::<Vec<_>>();
+            let recipients = [addresses(&email.to), addresses(&email.cc)].concat();
+            crate::pgp::protect(config, &from.address, &recipients, &addresses(&email.bcc), protection, final_multipart)
+                .map_err(|e| EmailError::SmtpError(format!("PGP: {}", e)))?
+        }
+        _ => final_multipart,
+    };
+
+    message_builder
+        .multipart(final_multipart)
+        .map_err(|e| EmailError::SmtpError(e.to_string()))
+}
+
+/// An iTIP calendar object, `text/calendar` with a `method` parameter, rather than an
+/// attached `.ics` file
+fn is_scheduling_part(content_type: &str) -> bool {
+    let mut parts = content_type.split(';').map(str::trim);
+    parts.next().is_some_and(|kind| kind.eq_ignore_ascii_case("text/calendar"))
+        && parts.any(|param| param.to_ascii_lowercase().starts_with("method="))
+}
+
+/// A new Message-ID (without angle brackets) for a message written here
+pub fn new_message_id(account_email: &str) -> String {
+    let domain = account_email.rsplit('@').next().unwrap_or("localhost");
+    let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    format!("{:x}.{}@{}", nanos, std::process::id(), domain)
+}
+
+/// Build and send an email
+pub fn send_email(smtp: &mut dyn SmtpBackend, email: &Email, default_from: &EmailAddress) -> Result<(), EmailError> {
+    ensure_writable("send email")?;
+    let message = build(email, default_from, false, smtp.keeps_bcc(), None, None)?;
+    smtp.send(&message)
+}
+
+/// One way of reaching an SMTP server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpRoute {
+    pub port: u16,
+    pub security: SmtpSecurity,
+    /// `None` connects to whichever address the host name resolves to first
+    pub family: Option<IpFamily>,
+}
+
+impl std::fmt::Display for SmtpRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "port {} ({:?})", self.port, self.security)?;
+        match self.family {
+            Some(IpFamily::V4) => write!(f, " over IPv4"),
+            Some(IpFamily::V6) => write!(f, " over IPv6"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Routes to try in order, the account's configured one first. Unless the account turns
+/// fallback off, they swap between the two submission setups (587 with STARTTLS, 465
+/// with TLS) and then pin each to IPv4 and IPv6. An encrypted setup never falls back to
+/// an unencrypted one.
+pub fn smtp_routes(account: &EmailAccount) -> Vec<SmtpRoute> {
+    let configured = SmtpRoute {
+        port: account.smtp_port,
+        security: account.smtp_security.clone(),
+        family: account.smtp_ip_family,
+    };
+    if account.smtp_fallback == SmtpFallback::Off {
+        return vec![configured];
+    }
+
+    let mut setups = vec![(account.smtp_port, account.smtp_security.clone())];
+    for alternative in [(587, SmtpSecurity::StartTLS), (465, SmtpSecurity::SSL)] {
+        if !setups.contains(&alternative) {
+            setups.push(alternative);
+        }
+    }
+    let mut routes = vec![configured];
+    for family in [None, Some(IpFamily::V4), Some(IpFamily::V6)] {
+        for (port, security) in &setups {
+            let route = SmtpRoute { port: *port, security: security.clone(), family };
+            if !routes.contains(&route) {
+                routes.push(route);
+            }
+        }
+    }
+    routes
+}
+
+/// Send over the first route that works. Only `connect` failing with a connection error
+/// (no answer, no greeting, failed TLS negotiation) moves on to the next route; a server
+/// reply such as a refused login is returned straight away. Once connected, any failure
+/// ends the attempt: the server may have taken the message already, and sending it again
+/// elsewhere could deliver it twice. Returns the route the message went out on.
+pub fn send_with_fallback<'a>(
+    routes: &'a [SmtpRoute],
+    connect: impl FnMut(&SmtpRoute) -> Result<Box<dyn SmtpBackend>, EmailError>,
+    email: &Email,
+    default_from: &EmailAddress,
+) -> Result<&'a SmtpRoute, EmailError> {
+    ensure_writable("send email")?;
+    let message = build_message(email, default_from)?;
+    send_message_with_fallback(routes, connect, &message)
+}
+
+/// Send a built message over the first route that works, as `send_with_fallback` does
+pub fn send_message_with_fallback<'a>(
+    routes: &'a [SmtpRoute],
+    mut connect: impl FnMut(&SmtpRoute) -> Result<Box<dyn SmtpBackend>, EmailError>,
+    message: &Message,
+) -> Result<&'a SmtpRoute, EmailError> {
+    let mut first_error = None;
+    for route in routes {
+        match connect(route) {
+            Ok(mut smtp) => return smtp.send(message).map(|()| route),
+            Err(e @ EmailError::ConnectionError(_)) => {
+                debug_log(&format!("SMTP via {} failed: {}", route, e));
+                first_error.get_or_insert(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    match first_error {
+        Some(e) if routes.len() > 1 => Err(EmailError::ConnectionError(format!(
+            "{} (tried {} other port and address settings)",
+            e,
+            routes.len() - 1
+        ))),
+        Some(e) => Err(e),
+        None => Err(EmailError::ConnectionError("No SMTP route to try".to_string())),
+    }
+}
+
+/// Prepend the Resent-* block (RFC 5322 section 3.6.6) for redirecting a message. The
+/// original header and body follow unchanged.
+fn resent_message(raw: &[u8], from: &Address, to: &[Address], date: chrono::DateTime<chrono::Local>, message_id: &str) -> Vec<u8> {
+    let to: Vec<String> = to.iter().map(|address| address.to_string()).collect();
+    let mut resent = format!(
+        "Resent-From: {}\r\nResent-Date: {}\r\nResent-To: {}\r\nResent-Message-ID: {}\r\n",
+        from,
+        date.to_rfc2822(),
+        to.join(", "),
+        message_id
+    )
+    .into_bytes();
+    resent.extend_from_slice(raw);
+    resent
+}
+
+/// Bounce (redirect) a message: send the raw original to new recipients with Resent-*
+/// headers, keeping its From, Date and body. Unlike forwarding, replies go to the
+/// original sender.
+pub fn bounce_email(smtp: &mut dyn SmtpBackend, raw: &[u8], from: &EmailAddress, to: &[EmailAddress]) -> Result<(), EmailError> {
+    ensure_writable("bounce email")?;
+
+    let parse = |address: &EmailAddress| {
+        address
+            .address
+            .parse::<Address>()
+            .map_err(|e| EmailError::SmtpError(format!("Invalid address '{}': {}", address.address, e)))
+    };
+    let from = parse(from)?;
+    let to = to.iter().map(parse).collect::<Result<Vec<_>, _>>()?;
+    if to.is_empty() {
+        return Err(EmailError::SmtpError("No recipient to bounce to".to_string()));
+    }
+
+    let random = crate::config_crypto::random_bytes(12).map_err(|e| EmailError::SmtpError(e.to_string()))?;
+    let id: String = random.iter().map(|b| format!("{:02x}", b)).collect();
+    let message_id = format!("<{}@{}>", id, from.domain());
+
+    let envelope = Envelope::new(Some(from.clone()), to.clone()).map_err(|e| EmailError::SmtpError(e.to_string()))?;
+    let resent = resent_message(raw, &from, &to, chrono::Local::now(), &message_id);
+    smtp.send_raw(&envelope, &resent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qresync_responses_are_read() {
+        let response = b"* FLAGS (\\Answered \\Flagged \\Deleted \\Seen \\Draft)\r\n\
+* OK [UIDVALIDITY 3857529045] UIDs valid\r\n\
+* 172 EXISTS\r\n\
+* OK [HIGHESTMODSEQ 20010715194045007] Highest\r\n\
+* VANISHED (EARLIER) 41,43:116,118,120:211,214:540\r\n\
+* 49 FETCH (UID 117 FLAGS (\\Seen \\Answered) MODSEQ (90060115194045001))\r\n\
+* 50 FETCH (FLAGS () UID 119 MODSEQ (90060115194045308))\r\n";
+        let mut changes = FolderChanges::default();
+        parse_changes(response, &mut changes);
+        assert_eq!((changes.uid_validity, changes.highest_modseq), (3857529045, 20010715194045007));
+        assert_eq!(changes.flags.get(&117), Some(&vec!["\\Seen".to_string(), "\\Answered".to_string()]));
+        assert_eq!(changes.flags.get(&119), Some(&Vec::new()));
+        assert!(changes.is_vanished(41) && changes.is_vanished(300) && !changes.is_vanished(42) && !changes.is_vanished(117));
+        assert_eq!(parse_uid_ranges("5:*"), vec![5..=u32::MAX]);
+    }
+
+    #[test]
+    fn sort_and_thread_responses_are_parsed() {
+        assert_eq!(parse_sort_response(b"* SORT 5 3 4 1 2\r\nA1 OK SORT completed\r\n"), vec![5, 3, 4, 1, 2]);
+        assert_eq!(parse_sort_response(b"* SORT\r\nA1 OK SORT completed\r\n"), Vec::<u32>::new());
+        let threads = parse_thread_response(b"* THREAD (2)(3 6 (4 23)(44 7 96))((11)(12 13))\r\nA2 OK THREAD completed\r\n");
+        assert_eq!(threads, vec![vec![2], vec![3, 6, 4, 23, 44, 7, 96], vec![11, 12, 13]]);
+    }
+
+    #[test]
+    fn gmail_ids_are_parsed() {
+        let ids = parse_gmail_ids(b"* 1 FETCH (X-GM-MSGID 1278455344230334865 UID 7)\r\n* 2 FETCH (UID 9 X-GM-MSGID 255)\r\nA3 OK Success\r\n");
+        assert_eq!(ids.get(&7).map(String::as_str), Some("11bdfc5cae0c8191"));
+        assert_eq!(ids.get(&9).map(String::as_str), Some("ff"));
+        assert_eq!(ids.len(), 2);
+    }
+
+    #[test]
+    fn special_use_folders_are_read_from_list_responses() {
+        let response = b"* LIST (\\HasNoChildren) \"/\" INBOX\r\n\
+* LIST (\\HasNoChildren \\Sent) \"/\" \"Sent Items\"\r\n\
+* LIST (\\HasNoChildren \\Trash) \"/\" {11}\r\nPapierkorb \r\n\
+* XLIST (\\HasNoChildren \\AllMail) \"/\" \"[Gmail]/All Mail\"\r\n\
+* LIST (\\Noselect) NIL \"Entw&APw-rfe\"\r\n\
+* LIST (\\Junk \\HasNoChildren) \".\" \"Spam \\\"bulk\\\"\"\r\n";
+        let folders = parse_list_response(response);
+        let names: Vec<&str> = folders.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(names, vec!["INBOX", "Sent Items", "Papierkorb ", "[Gmail]/All Mail", "Entwürfe", "Spam \"bulk\""]);
+
+        let mut special = SpecialFolders::default();
+        for (attributes, name) in &folders {
+            for attribute in attributes {
+                special.assign(attribute, name);
+            }
+        }
+        special.assign("\\Archive", "Archive");
+        assert_eq!(special.sent.as_deref(), Some("Sent Items"));
+        assert_eq!(special.trash.as_deref(), Some("Papierkorb "));
+        assert_eq!(special.junk.as_deref(), Some("Spam \"bulk\""));
+        assert_eq!(special.archive.as_deref(), Some("Archive"));
+        assert_eq!(special.drafts, None);
+    }
+}
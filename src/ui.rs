@@ -2,12 +2,15 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Tabs, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Tabs, Wrap},
     Frame,
 };
 
 use crate::app::{App, AppMode};
+use crate::attachment_index::format_size;
+use crate::config::{ListDensity, UIConfig};
 use crate::email::Email;
+use crate::i18n::{tr, tr_args};
 
 pub fn ui(f: &mut Frame, app: &App) {
     // Create the layout
@@ -23,31 +26,37 @@ pub fn ui(f: &mut Frame, app: &App) {
     render_title_bar(f, app, chunks[0]);
     render_main_content(f, app, chunks[1]);
     render_status_bar(f, app, chunks[2]);
+
+    if app.config.ui.ascii {
+        crate::ascii_mode::asciify(f.buffer_mut());
+    }
 }
 
 fn render_title_bar(f: &mut Frame, app: &App, area: Rect) {
     // Get current account name for display
+    let unknown = tr("status-unknown-account");
     let current_account_name = if app.current_account_idx < app.config.accounts.len() {
         &app.config.accounts[app.current_account_idx].name
     } else {
-        "Unknown"
+        &unknown
     };
     
     let inbox_title = if app.config.accounts.len() > 1 {
-        format!("Inbox ({})", current_account_name)
+        tr_args("tab-inbox-account", &[("account", current_account_name)])
     } else {
-        "Inbox".to_string()
+        tr("tab-inbox")
     };
     
-    let titles = vec![inbox_title.as_str(), "Compose", "Settings", "Help"];
-    let tabs = Tabs::new(titles.iter().cloned().map(Line::from).collect())
+    let titles = vec![inbox_title, tr("tab-compose"), tr("tab-settings"), tr("tab-help")];
+    let tabs = Tabs::new(titles.into_iter().map(Line::from).collect())
         .block(Block::default().borders(Borders::BOTTOM))
         .highlight_style(Style::default().fg(Color::Yellow))
         .select(match app.mode {
-            AppMode::Normal | AppMode::ViewEmail | AppMode::FolderList | AppMode::DeleteConfirm => 0,
+            AppMode::Normal | AppMode::ViewEmail | AppMode::FolderList | AppMode::DeleteConfirm | AppMode::People | AppMode::Attachments | AppMode::RulesReport | AppMode::AuthHelp | AppMode::MissingSecrets | AppMode::Diagnostics | AppMode::Capabilities | AppMode::ImapConsole | AppMode::SmtpFallbackConfirm | AppMode::ReplyWarning | AppMode::Drafts | AppMode::Cleanup | AppMode::RecentlyDeleted | AppMode::PendingOperations | AppMode::Reminders => 0,
+            AppMode::DraftConflict => 1,
             AppMode::Compose => 1,
-            AppMode::AccountSettings => 2,
-            AppMode::Help => 3,
+            AppMode::AccountSettings | AppMode::Dictionary => 2,
+            AppMode::Help | AppMode::Changelog => 3,
         });
     f.render_widget(tabs, area);
 }
@@ -67,6 +76,24 @@ fn render_main_content(f: &mut Frame, app: &App, area: Rect) {
         AppMode::AccountSettings => render_settings_mode(f, app, area),
         AppMode::Help => render_help_mode(f, app, area),
         AppMode::DeleteConfirm => render_delete_confirm_mode(f, app, area),
+        AppMode::People => render_people_mode(f, app, area),
+        AppMode::Attachments => render_attachments_mode(f, app, area),
+        AppMode::RulesReport => render_rules_report_mode(f, app, area),
+        AppMode::AuthHelp => render_auth_help_mode(f, app, area),
+        AppMode::MissingSecrets => render_missing_secrets_mode(f, app, area),
+        AppMode::Diagnostics => render_diagnostics_mode(f, app, area),
+        AppMode::Capabilities => render_capabilities_mode(f, app, area),
+        AppMode::ImapConsole => render_imap_console_mode(f, app, area),
+        AppMode::SmtpFallbackConfirm => render_smtp_fallback_confirm_mode(f, app, area),
+        AppMode::ReplyWarning => render_reply_warning_mode(f, app, area),
+        AppMode::Drafts => render_drafts_mode(f, app, area),
+        AppMode::DraftConflict => render_draft_conflict_mode(f, app, area),
+        AppMode::Cleanup => render_cleanup_mode(f, app, area),
+        AppMode::Dictionary => render_dictionary_mode(f, app, area),
+        AppMode::RecentlyDeleted => render_recently_deleted_mode(f, app, area),
+        AppMode::PendingOperations => render_pending_operations_mode(f, app, area),
+        AppMode::Reminders => render_reminders_mode(f, app, area),
+        AppMode::Changelog => render_changelog_mode(f, app, area),
     }
 }
 
@@ -80,7 +107,118 @@ fn render_normal_mode(f: &mut Frame, app: &App, area: Rect) {
         .split(area);
 
     render_folder_list(f, app, horizontal_chunks[0]);
-    render_email_list(f, app, horizontal_chunks[1]);
+
+    let show_search = app.search_editing;
+    let show_tags = app.tag_input.is_some();
+    let show_reminder = app.reminder_input.is_some();
+    let show_quick_actions = !app.config.quick_actions.is_empty();
+    let mut constraints = Vec::new();
+    if show_search || show_tags || show_reminder {
+        constraints.push(Constraint::Length(3));
+    }
+    constraints.push(Constraint::Min(0));
+    if show_quick_actions {
+        constraints.push(Constraint::Length(1));
+    }
+    let list_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(horizontal_chunks[1]);
+
+    let mut chunks = list_chunks.iter();
+    if show_search {
+        render_search_bar(f, app, *chunks.next().unwrap());
+    } else if show_tags {
+        render_tag_bar(f, app, *chunks.next().unwrap());
+    } else if show_reminder {
+        let bar = Paragraph::new(Line::from(vec![reminder_prompt_span(app), reminder_hint_span()]))
+            .block(Block::default().title("Remind me").borders(Borders::ALL));
+        f.render_widget(bar, *chunks.next().unwrap());
+    }
+    render_email_list(f, app, *chunks.next().unwrap());
+    if show_quick_actions {
+        render_quick_action_bar(f, app, *chunks.next().unwrap());
+    }
+    if let Some(picker) = &app.folder_picker {
+        render_folder_picker(f, app, picker, area);
+    }
+}
+
+/// The folders the selected message can be moved to, narrowed by the typed filter
+fn render_folder_picker(f: &mut Frame, app: &App, picker: &crate::app::FolderPicker, area: Rect) {
+    let popup_area = centered_rect(50, 60, area);
+    f.render_widget(Clear, popup_area);
+
+    let choices = app.folder_picker_choices();
+    let items: Vec<ListItem> = if choices.is_empty() {
+        vec![ListItem::new(Span::styled("No folder matches", Style::default().fg(Color::Gray)))]
+    } else {
+        choices.iter().map(|folder| ListItem::new(folder.as_str())).collect()
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Move to: {}_ (Enter to move, Esc to cancel)", picker.filter))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    let mut state = ratatui::widgets::ListState::default();
+    state.select((!choices.is_empty()).then_some(picker.selected));
+    f.render_stateful_widget(list, popup_area, &mut state);
+}
+
+/// The query being typed, with the filter names the current word could complete to
+fn render_search_bar(f: &mut Frame, app: &App, area: Rect) {
+    let completions = crate::search::completions(&app.search_text);
+    let hint = if !completions.is_empty() {
+        format!("Tab: {}", completions.join(" "))
+    } else if app.search_text.is_empty() {
+        crate::search::FILTERS.join(" ")
+    } else {
+        "Enter to search, Esc to cancel".to_string()
+    };
+    let line = Line::from(vec![
+        Span::styled(format!("{}_", app.search_text), Style::default().fg(Color::Yellow)),
+        Span::styled(format!("   {}", hint), Style::default().fg(Color::DarkGray)),
+    ]);
+    let search = Paragraph::new(line).block(Block::default().title("Search").borders(Borders::ALL));
+    f.render_widget(search, area);
+}
+
+/// The tag changes being typed for the selected message, next to its tags
+fn render_tag_bar(f: &mut Frame, app: &App, area: Rect) {
+    let current = app
+        .selected_email_idx
+        .and_then(|idx| app.emails.get(idx))
+        .map(|email| app.tags_of(email).join(" "))
+        .unwrap_or_default();
+    let line = Line::from(vec![
+        Span::styled(format!("{}_", app.tag_input.as_deref().unwrap_or_default()), Style::default().fg(Color::Yellow)),
+        Span::styled(
+            format!("   +tag adds, -tag removes · now: {}", if current.is_empty() { "none" } else { &current }),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    let tags = Paragraph::new(line).block(Block::default().title("Tags").borders(Borders::ALL));
+    f.render_widget(tags, area);
+}
+
+/// One line of number keys and what they do to the selected message
+fn render_quick_action_bar(f: &mut Frame, app: &App, area: Rect) {
+    let mut actions: Vec<_> = app.config.quick_actions.iter().filter(|action| (1..=9).contains(&action.key)).collect();
+    actions.sort_by_key(|action| action.key);
+
+    let mut spans = Vec::new();
+    for action in actions {
+        spans.push(Span::styled(
+            format!(" {} ", action.key),
+            Style::default().fg(Color::Black).bg(Color::Cyan),
+        ));
+        spans.push(Span::raw(format!(" {}  ", action.label(&app.archive_folder()))));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
 fn render_folder_list(f: &mut Frame, app: &App, area: Rect) {
@@ -100,8 +238,8 @@ fn render_folder_list(f: &mut Frame, app: &App, area: Rect) {
                     };
                     (display_text, style)
                 }
-                crate::app::FolderItem::Folder { name, .. } => {
-                    let display_text = format!("  📁 {}", name);
+                crate::app::FolderItem::Folder { name, account_index, full_path } => {
+                    let display_text = format!("  📁 {}{}", name, folder_option_marks(app, *account_index, full_path));
                     let style = if i == app.selected_folder_item_idx {
                         Style::default().fg(Color::Yellow)
                     } else {
@@ -109,6 +247,30 @@ fn render_folder_list(f: &mut Frame, app: &App, area: Rect) {
                     };
                     (display_text, style)
                 }
+                crate::app::FolderItem::ReadLater { .. } => {
+                    let style = if i == app.selected_folder_item_idx {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::Magenta)
+                    };
+                    ("  🔖 Read Later".to_string(), style)
+                }
+                crate::app::FolderItem::Vip { .. } => {
+                    let style = if i == app.selected_folder_item_idx {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::LightRed)
+                    };
+                    ("  ★ VIP".to_string(), style)
+                }
+                crate::app::FolderItem::Notifications { .. } => {
+                    let style = if i == app.selected_folder_item_idx {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::LightBlue)
+                    };
+                    ("  🔔 Notifications".to_string(), style)
+                }
             };
             
             ListItem::new(text).style(style)
@@ -126,14 +288,61 @@ fn render_folder_list(f: &mut Frame, app: &App, area: Rect) {
     f.render_stateful_widget(folders, area, &mut state);
 }
 
+/// Markers after a folder name for folders that are never notified about (🔕) or only
+/// synced when opened (⏸)
+fn folder_option_marks(app: &App, account_index: usize, folder: &str) -> String {
+    let Some(account) = app.config.accounts.get(account_index) else {
+        return String::new();
+    };
+    let mut marks = String::new();
+    if !account.notifies(folder) {
+        marks.push_str(" 🔕");
+    }
+    if !account.auto_syncs(folder) {
+        marks.push_str(" ⏸");
+    }
+    marks
+}
+
+/// Pick the first visible row so the selection stays inside a viewport of `height` rows.
+/// The previous offset is kept while the selection is still visible, so the cursor moves
+/// within the page instead of dragging the whole list with it.
+fn email_list_window(previous_offset: usize, selected: Option<usize>, height: usize, len: usize) -> usize {
+    if height == 0 || len <= height {
+        return 0;
+    }
+    let max_offset = len - height;
+    let mut offset = previous_offset.min(max_offset);
+    if let Some(selected) = selected {
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+    }
+    offset.min(max_offset)
+}
+
 fn render_email_list(f: &mut Frame, app: &App, area: Rect) {
+    // Only materialize the rows that fit in the viewport so draw time doesn't grow with folder size
+    let density = app.config.ui.density;
+    let height = area.height.saturating_sub(2) as usize / density.row_height();
+    let total = app.emails.len();
+    let offset = email_list_window(app.email_list_offset.get(), app.selected_email_idx, height, total);
+    app.email_list_offset.set(offset);
+    let visible_end = (offset + height).min(total);
+
     let items: Vec<ListItem> = app
-        .emails
+        .emails[offset..visible_end]
         .iter()
         .enumerate()
-        .map(|(i, email)| {
+        .map(|(row, email)| {
+            let i = offset + row;
             let style = if Some(i) == app.selected_email_idx {
                 Style::default().fg(Color::Yellow)
+            } else if app.is_vip(email) {
+                let style = Style::default().fg(Color::LightMagenta);
+                if email.seen { style } else { style.add_modifier(Modifier::BOLD) }
             } else if !email.seen {
                 Style::default().fg(Color::Green)
             } else {
@@ -160,9 +369,69 @@ fn render_email_list(f: &mut Frame, app: &App, area: Rect) {
                 "   " // Three spaces to match the width of "📎 " (emoji takes 2 chars + 1 space)
             };
             
-            let content = format!("{}{:<12} {:<25} {}", 
-                attachment_indicator, date, from, email.subject);
-            ListItem::new(content).style(style)
+            // Configured header fields shown as columns between the sender and the subject
+            let columns: String = crate::header_fields::columns(email)
+                .iter()
+                .map(|(width, value)| format!("{:<width$.width$} ", value, width = width))
+                .collect();
+            // GitHub and GitLab notifications lead with what they are about
+            let subject = match crate::forge::detect(email) {
+                Some(notification) => format!(
+                    "[{}{} {}] {}",
+                    notification.repository,
+                    notification.reference().unwrap_or_default(),
+                    notification.action,
+                    email.subject
+                ),
+                None => email.subject.clone(),
+            };
+            // Replies sit below the first message of their conversation
+            let subject = if app.thread_view.is_reply(email) { format!("└ {}", subject) } else { subject };
+            // Two-line rows move the date down next to the preview
+            let mut content = match density {
+                ListDensity::Compact => format!("{}{:<12} {:<25} {}{}", attachment_indicator, date, from, columns, subject),
+                ListDensity::Comfortable => format!("{}{:<25} {}{}", attachment_indicator, from, columns, subject),
+            };
+            // A contact thread spans folders, so say where each message lives
+            if app.cross_folder_list() {
+                content.push_str(&format!("  [{}]", email.folder));
+            }
+            let bounced = if app.bounces_for(email).is_some() {
+                Span::styled("✗", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            } else {
+                Span::raw(" ")
+            };
+            let external = if app.is_external(email) {
+                Span::styled("E", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else {
+                Span::raw(" ")
+            };
+            let marked = if app.is_marked(email) {
+                Span::styled("*", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))
+            } else {
+                Span::raw(" ")
+            };
+            let tags = crate::tags::shown(app.tags_of(email));
+            let tags = if tags.is_empty() {
+                Span::raw("")
+            } else {
+                Span::styled(format!("  ({})", tags.join(" ")), Style::default().fg(Color::Cyan))
+            };
+            let line = Line::from(vec![marked, priority_marker(email), reply_marker(email), bounced, external, Span::raw(content), tags]);
+            match density {
+                ListDensity::Compact => ListItem::new(line),
+                ListDensity::Comfortable => {
+                    let preview = Line::from(vec![
+                        Span::raw("        "),
+                        Span::styled(
+                            format!("{:<12} {}", date, email.snippet.as_deref().unwrap_or_default()),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ]);
+                    ListItem::new(vec![line, preview])
+                }
+            }
+            .style(style)
         })
         .collect();
 
@@ -177,6 +446,32 @@ fn render_email_list(f: &mut Frame, app: &App, area: Rect) {
     } else {
         "Emails".to_string()
     };
+    let title = match &app.contact_filter {
+        Some(address) => format!("Conversation with {} (Esc to go back)", address),
+        None if app.search_query.is_some() => format!(
+            "Search: {} - {} found (/ to edit, Esc to go back)",
+            app.search_query.as_deref().unwrap_or_default(),
+            total
+        ),
+        None if app.read_later_view => format!(
+            "Read Later - {} to read, {} done (L: done, Esc to go back)",
+            total, app.read_later_done
+        ),
+        None if app.vip_view => format!("VIP - {} from VIP senders (Esc to go back)", total),
+        None if app.notifications_view => format!("Notifications - {} from GitHub and GitLab by repository (Esc to go back)", total),
+        None => title,
+    };
+    let title = match app.importance_view {
+        crate::app::ImportanceView::All => title,
+        crate::app::ImportanceView::HighFirst => format!("{} (high priority first)", title),
+        crate::app::ImportanceView::HighOnly => format!("{} (high priority only)", title),
+    };
+    let title = if total > height && height > 0 {
+        format!("{} [{}-{} of {}]", title, offset + 1, visible_end, total)
+    } else {
+        title
+    };
+    let title = if app.is_updating_shown_folder() { format!("{} · updating…", title) } else { title };
 
     let emails = List::new(items)
         .block(Block::default().title(title).borders(Borders::ALL))
@@ -185,26 +480,711 @@ fn render_email_list(f: &mut Frame, app: &App, area: Rect) {
     // Add scrolling support
     let mut state = ratatui::widgets::ListState::default();
     if let Some(selected) = app.selected_email_idx {
-        state.select(Some(selected));
+        if selected >= offset && selected < visible_end {
+            state.select(Some(selected - offset));
+        }
     }
 
     f.render_stateful_widget(emails, area, &mut state);
 }
 
+fn render_people_mode(f: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .contacts
+        .iter()
+        .enumerate()
+        .map(|(i, contact)| {
+            let style = if i == app.selected_contact_idx {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+
+            let who = match &contact.name {
+                Some(name) => format!("{} <{}>", name, contact.address),
+                None => contact.address.clone(),
+            };
+            // People from the address book never written with have no date
+            let last = chrono::DateTime::from_timestamp(contact.last_activity, 0)
+                .filter(|_| contact.message_count > 0)
+                .map(|date| date.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+
+            let vip = if app.vips.contains(&contact.address.to_lowercase()) { "★" } else { " " };
+            let content = format!("{:<12} {:>5} msgs {} {}", last, contact.message_count, vip, who);
+            ListItem::new(content).style(style)
+        })
+        .collect();
+
+    let title = format!("People ({}) - Enter to show conversation, v to mark VIP, Esc to go back", app.contacts.len());
+    let people = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.contacts.is_empty() {
+        state.select(Some(app.selected_contact_idx));
+    }
+
+    f.render_stateful_widget(people, area, &mut state);
+}
+
+fn render_cleanup_mode(f: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .cleanup_groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let marked = app.cleanup_marked.contains(&group.key);
+            let style = if i == app.selected_cleanup_idx {
+                Style::default().fg(Color::Yellow)
+            } else if marked {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default()
+            };
+            let who = match &group.name {
+                Some(name) => format!("{} <{}>", name, group.key),
+                None => group.key.clone(),
+            };
+            let content = format!(
+                "[{}] {:>5} msgs {:>5} unread {:>10}  {}",
+                if marked { "x" } else { " " },
+                group.count,
+                group.unread,
+                format_size(group.size),
+                who
+            );
+            ListItem::new(content).style(style)
+        })
+        .collect();
+
+    let folder = app.cleanup_folder.as_ref().map_or("", |(_, folder)| folder.as_str());
+    let by = match app.cleanup_by {
+        crate::cleanup::GroupBy::Sender => "sender",
+        crate::cleanup::GroupBy::Domain => "domain",
+    };
+    let running = if app.cleanup_running { " · cleaning up…" } else { "" };
+    let title = format!("Clean up {} by {} ({} marked){}", folder, by, app.cleanup_marked.len(), running);
+    let groups = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.cleanup_groups.is_empty() {
+        state.select(Some(app.selected_cleanup_idx));
+    }
+
+    f.render_stateful_widget(groups, area, &mut state);
+}
+
+fn render_drafts_mode(f: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .drafts
+        .iter()
+        .enumerate()
+        .map(|(i, draft)| {
+            let style = if draft.conflict.is_some() {
+                Style::default().fg(Color::Red)
+            } else if i == app.selected_draft_idx {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+
+            let edited = chrono::DateTime::from_timestamp(draft.modified, 0)
+                .map(|date| date.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            let state = if draft.conflict.is_some() {
+                "conflict"
+            } else if draft.has_local_changes() {
+                "not synced"
+            } else {
+                "synced"
+            };
+            let to: Vec<&str> = draft.email.to.iter().map(|a| a.address.as_str()).collect();
+            let subject = if draft.email.subject.is_empty() { "(no subject)" } else { &draft.email.subject };
+
+            let content = format!("{:<17} {:<11} {}  → {}", edited, state, subject, to.join(", "));
+            ListItem::new(content).style(style)
+        })
+        .collect();
+
+    let syncing = if app.drafts_syncing { " · syncing…" } else { "" };
+    let title = format!("Drafts ({}){} - Enter to resume, x to discard, Esc to go back", app.drafts.len(), syncing);
+    let drafts = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.drafts.is_empty() {
+        state.select(Some(app.selected_draft_idx));
+    }
+
+    f.render_stateful_widget(drafts, area, &mut state);
+}
+
+fn render_recently_deleted_mode(f: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = if app.deleted_messages.is_empty() {
+        vec![ListItem::new("Nothing deleted recently").style(Style::default().fg(Color::Gray))]
+    } else {
+        app.deleted_messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| {
+                let style = if i == app.selected_deleted_idx {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                let deleted = chrono::DateTime::from_timestamp(message.deleted_at, 0)
+                    .map(|date| date.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                let by = if message.elsewhere { "elsewhere" } else { "here" };
+                let subject = if message.subject.is_empty() { "(no subject)" } else { &message.subject };
+                let content = format!("{:<17} {:<9} {:<12} {:<25} {}", deleted, by, message.folder, message.sender, subject);
+                ListItem::new(content).style(style)
+            })
+            .collect()
+    };
+
+    let title = format!(
+        "Recently Deleted ({}, kept {} days) - Enter to restore, x to forget, Esc to go back",
+        app.deleted_messages.len(),
+        app.config.retention.keep_deleted_days
+    );
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.deleted_messages.is_empty() {
+        state.select(Some(app.selected_deleted_idx));
+    }
+
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// A number of seconds in its largest whole unit, such as `5m` or `2d`
+fn short_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+fn render_pending_operations_mode(f: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(if app.operation_target_input.is_some() { 3 } else { 0 })])
+        .split(area);
+
+    let now = chrono::Local::now().timestamp();
+    let items: Vec<ListItem> = if app.queued_operations.is_empty() {
+        vec![ListItem::new("Nothing queued").style(Style::default().fg(Color::Gray))]
+    } else {
+        app.queued_operations
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                let style = if i == app.selected_operation_idx {
+                    Style::default().fg(Color::Yellow)
+                } else if op.error.is_some() {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                };
+                let subject = match op.subject.as_deref() {
+                    Some("") => "(no subject)".to_string(),
+                    Some(subject) => subject.to_string(),
+                    None => format!("UID {}", op.email_uid),
+                };
+                let mut lines = vec![Line::from(format!(
+                    "{:<5} {:<20} {:<25} {:<12} {}",
+                    short_duration(now - op.created_at),
+                    op.describe(),
+                    op.account_email,
+                    op.folder,
+                    subject
+                ))];
+                if let Some(error) = &op.error {
+                    let retry = if op.retry_at > now {
+                        format!(", next try in {}", short_duration(op.retry_at - now))
+                    } else {
+                        String::new()
+                    };
+                    lines.push(Line::from(format!("      failed {} times{}: {}", op.attempts, retry, error)));
+                }
+                ListItem::new(lines).style(style)
+            })
+            .collect()
+    };
+
+    let title = format!("Queued Operations ({})", app.queued_operations.len());
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.queued_operations.is_empty() {
+        state.select(Some(app.selected_operation_idx));
+    }
+    f.render_stateful_widget(list, chunks[0], &mut state);
+
+    if let Some(target) = &app.operation_target_input {
+        let input = Paragraph::new(format!("{}_", target))
+            .block(Block::default().title("Move to folder").borders(Borders::ALL));
+        f.render_widget(input, chunks[1]);
+    }
+}
+
+/// Reminders of every account: those that went off first, then the coming ones with the
+/// time left
+fn render_reminders_mode(f: &mut Frame, app: &App, area: Rect) {
+    let now = chrono::Local::now().timestamp();
+    let items: Vec<ListItem> = if app.reminders.is_empty() {
+        vec![ListItem::new("No reminders; z on a message sets one").style(Style::default().fg(Color::Gray))]
+    } else {
+        app.reminders
+            .iter()
+            .enumerate()
+            .map(|(i, reminder)| {
+                let style = if i == app.selected_reminder_idx {
+                    Style::default().fg(Color::Yellow)
+                } else if reminder.fired {
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                let when = chrono::DateTime::from_timestamp(reminder.remind_at, 0)
+                    .map(|date| date.with_timezone(&chrono::Local).format("%a %d %b %H:%M").to_string())
+                    .unwrap_or_default();
+                let status = if reminder.fired { "⏰ due".to_string() } else { format!("in {}", short_duration(reminder.remind_at - now)) };
+                let subject = if reminder.subject.is_empty() { "(no subject)" } else { &reminder.subject };
+                ListItem::new(format!(
+                    "{:<17} {:<7} {:<25} {:<20} {}",
+                    when, status, reminder.account_email, reminder.sender, subject
+                ))
+                .style(style)
+            })
+            .collect()
+    };
+
+    let title = format!("Reminders ({})", app.reminders.len());
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.reminders.is_empty() {
+        state.select(Some(app.selected_reminder_idx));
+    }
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// The local and the server version of a draft edited on both sides, next to each other
+fn render_draft_conflict_mode(f: &mut Frame, app: &App, area: Rect) {
+    let Some(draft) = app.drafts.get(app.selected_draft_idx) else {
+        return;
+    };
+    let Some((_, server)) = &draft.conflict else {
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let version = |email: &crate::email::Email, title: String, area: Rect, f: &mut Frame| {
+        let to: Vec<&str> = email.to.iter().map(|a| a.address.as_str()).collect();
+        let mut lines = vec![
+            Line::from(format!("To: {}", to.join(", "))),
+            Line::from(format!("Subject: {}", email.subject)),
+            Line::from(""),
+        ];
+        lines.extend(email.body_text.as_deref().unwrap_or_default().lines().map(|line| Line::from(line.to_string())));
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+    };
+
+    let edited = chrono::DateTime::from_timestamp(draft.modified, 0)
+        .map(|date| date.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    version(&draft.email, format!("l: Keep this device's version (edited {})", edited), chunks[0], f);
+    version(
+        server,
+        format!("s: Keep the server version (saved {})", server.date.format("%Y-%m-%d %H:%M")),
+        chunks[1],
+        f,
+    );
+}
+
+fn render_attachments_mode(f: &mut Frame, app: &App, area: Rect) {
+    let constraints = if app.attachment_preview.is_some() {
+        vec![Constraint::Length(3), Constraint::Percentage(45), Constraint::Min(0)]
+    } else {
+        vec![Constraint::Length(3), Constraint::Min(0)]
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+
+    let filter_style = if app.attachment_filter_editing {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    let filter_text = if app.attachment_filter_text.is_empty() && !app.attachment_filter_editing {
+        "Press '/' to filter (type:pdf from:alice after:2024-03-01 larger:1m words...)".to_string()
+    } else {
+        app.attachment_filter_text.clone()
+    };
+    let filter = Paragraph::new(filter_text)
+        .style(filter_style)
+        .block(Block::default().title("Filter").borders(Borders::ALL));
+    f.render_widget(filter, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .attachment_index_visible
+        .iter()
+        .enumerate()
+        .filter_map(|(row, &i)| app.attachment_index.get(i).map(|entry| (row, entry)))
+        .map(|(row, entry)| {
+            let style = if row == app.attachment_index_selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            let date = chrono::DateTime::from_timestamp(entry.date, 0)
+                .map(|date| date.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            let content = format!(
+                "{:<10} {:>9}  {:<40} {:<30} {}",
+                date,
+                format_size(entry.size),
+                entry.filename,
+                entry.sender,
+                entry.folder
+            );
+            ListItem::new(content).style(style)
+        })
+        .collect();
+
+    let title = format!(
+        "Attachments ({} of {}) - Enter to preview, s to save, Esc to go back",
+        app.attachment_index_visible.len(),
+        app.attachment_index.len()
+    );
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.attachment_index_visible.is_empty() {
+        state.select(Some(app.attachment_index_selected));
+    }
+    f.render_stateful_widget(list, chunks[1], &mut state);
+
+    if let Some(preview) = &app.attachment_preview {
+        let title = app
+            .selected_index_attachment()
+            .map(|entry| format!("Preview - {}", entry.filename))
+            .unwrap_or_else(|| "Preview".to_string());
+        let preview = Paragraph::new(preview.as_str())
+            .wrap(Wrap { trim: false })
+            .block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(preview, chunks[2]);
+    }
+}
+
+fn render_rules_report_mode(f: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(area);
+
+    // Per-source totals so a noisy rule stands out before reading the details
+    let mut totals: Vec<(&str, usize)> = Vec::new();
+    for entry in &app.dry_run_report {
+        match totals.iter_mut().find(|(source, _)| *source == entry.source) {
+            Some((_, count)) => *count += 1,
+            None => totals.push((&entry.source, 1)),
+        }
+    }
+    let summary = if app.config.rules.is_empty() && app.config.retention.archive_after_days.is_none() {
+        "No filter rules or auto-archive policy configured (see \"rules\" and \"retention\" in config.json)".to_string()
+    } else if totals.is_empty() {
+        "Nothing would have been changed".to_string()
+    } else {
+        totals
+            .iter()
+            .map(|(source, count)| format!("{}: {}", source, count))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+    let header = Paragraph::new(vec![
+        Line::from(format!(
+            "Dry run over the last {} days: {} action(s). Nothing has been changed.",
+            app.dry_run_days,
+            app.dry_run_report.len()
+        )),
+        Line::from(summary),
+    ])
+    .block(Block::default().title("Rules dry run").borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .dry_run_report
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let style = if i == app.dry_run_selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            let content = format!(
+                "{:<12} {:<20} {:<12} {:<25} {:<30} {}",
+                entry.date.format("%Y-%m-%d"),
+                entry.action,
+                entry.folder,
+                entry.from,
+                entry.subject,
+                entry.source
+            );
+            ListItem::new(content).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title("Would have: +/- change window, Esc to go back").borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.dry_run_report.is_empty() {
+        state.select(Some(app.dry_run_selected));
+    }
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+fn render_diagnostics_mode(f: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(area);
+
+    let size = app
+        .database_size
+        .map(|bytes| format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0)))
+        .unwrap_or_else(|| "unknown size".to_string());
+    let header = Paragraph::new(vec![
+        Line::from(format!("Database: {} ({})", app.database.get_database_path(), size)),
+        Line::from(format!(
+            "Queued operations: {} | Audit entries shown: {}",
+            app.pending_operations,
+            app.audit_entries.len()
+        )),
+    ])
+    .block(Block::default().title("Diagnostics").borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .audit_entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let style = if i == app.audit_selected {
+                Style::default().fg(Color::Yellow)
+            } else if entry.action == "delete" {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            let content = format!(
+                "{:<20} {:<4} {:<25} {:<12} {:>6}  {:<16} {}",
+                crate::audit::format_time(entry.timestamp),
+                entry.origin,
+                entry.account_email,
+                entry.folder,
+                entry.uid.map(|uid| uid.to_string()).unwrap_or_default(),
+                entry.action,
+                entry.detail
+            );
+            ListItem::new(content).style(style)
+        })
+        .collect();
+
+    let title = if items.is_empty() {
+        "Audit log: nothing recorded yet"
+    } else {
+        "Audit log: deletes, moves, flag changes and sends, newest first"
+    };
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.audit_entries.is_empty() {
+        state.select(Some(app.audit_selected));
+    }
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+/// Lines for one server: the announced list, then each feature tuimail knows about with
+/// whether it works fully, is degraded, or is unused
+fn capability_lines<'a>(
+    protocol: &str,
+    server: String,
+    announced: &Result<Vec<String>, String>,
+    features: &[crate::capabilities::Feature],
+) -> Vec<Line<'a>> {
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{} {}", protocol, server),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    let announced = match announced {
+        Ok(announced) => announced,
+        Err(e) => {
+            lines.push(Line::from(Span::styled(format!("  Could not check: {}", e), Style::default().fg(Color::Red))));
+            lines.push(Line::from(""));
+            return lines;
+        }
+    };
+    lines.push(Line::from(Span::styled(format!("  {}", announced.join(" ")), Style::default().fg(Color::Gray))));
+    for feature in features {
+        let present = crate::capabilities::has_capability(announced, feature.capability);
+        let (state, detail, color) = match (feature.used_for, present) {
+            (Some(used_for), true) => ("enabled ", used_for, Color::Green),
+            (Some(_), false) => ("degraded", feature.without, Color::Yellow),
+            (None, true) => ("unused  ", "Offered, but tuimail does not use it", Color::DarkGray),
+            (None, false) => ("unused  ", feature.without, Color::DarkGray),
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("  {:<12} ", feature.capability)),
+            Span::styled(state, Style::default().fg(color)),
+            Span::raw(format!("  {}", detail)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines
+}
+
+fn render_capabilities_mode(f: &mut Frame, app: &App, area: Rect) {
+    let Some((account_idx, capabilities)) = &app.capabilities else {
+        return;
+    };
+    let Some(account) = app.config.accounts.get(*account_idx) else {
+        return;
+    };
+    let lines = match capabilities {
+        None => vec![Line::from(format!("Asking {} and {}...", account.imap_server, account.smtp_server))],
+        Some(capabilities) => {
+            let mut lines = capability_lines(
+                "IMAP",
+                format!("{}:{}", account.imap_server, account.imap_port),
+                &capabilities.imap,
+                &crate::capabilities::IMAP_FEATURES,
+            );
+            lines.extend(capability_lines(
+                "SMTP",
+                format!("{}:{}", account.smtp_server, account.smtp_port),
+                &capabilities.smtp,
+                &crate::capabilities::SMTP_FEATURES,
+            ));
+            lines
+        }
+    };
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title(format!("Server capabilities: {}", account.email)).borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, area);
+}
+
+fn render_imap_console_mode(f: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(area);
+
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    let end = app.console_lines.len().saturating_sub(app.console_scroll);
+    let start = end.saturating_sub(height);
+    let lines: Vec<Line> = app.console_lines[start..end]
+        .iter()
+        .map(|line| {
+            let style = if line.starts_with("> ") {
+                Style::default().fg(Color::Cyan)
+            } else if line.starts_with("NO ") || line.starts_with("BAD ") || line.starts_with("Error") {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            Line::from(Span::styled(line.clone(), style))
+        })
+        .collect();
+    let title = if app.console_scroll > 0 {
+        format!("IMAP console ({} lines back)", app.console_scroll)
+    } else {
+        "IMAP console".to_string()
+    };
+    f.render_widget(Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL)), chunks[0]);
+
+    let input = Paragraph::new(format!("{}_", app.console_input))
+        .block(Block::default().title("Command (without tag)").borders(Borders::ALL));
+    f.render_widget(input, chunks[1]);
+}
+
 fn render_view_email_mode(f: &mut Frame, app: &App, area: Rect) {
     if let Some(idx) = app.selected_email_idx {
         if idx < app.emails.len() {
             let email = &app.emails[idx];
+            if let Some(message) = app.digest_message() {
+                return render_digest_message(f, app, message, area);
+            }
+            let mut header_lines = muted_lines(app, email);
+            header_lines.extend(external_lines(app, email));
+            header_lines.extend(impersonation_lines(app, email));
+            header_lines.extend(authentication_failure_lines(email));
+            header_lines.extend(email_header_lines(&app.config.ui, app.headers_expanded, email));
+            header_lines.extend(notification_lines(email));
+            header_lines.extend(pgp_lines(app, email));
+            header_lines.extend(calendar_lines(app, email));
+            header_lines.extend(contact_card_lines(email));
+            header_lines.extend(delivery_failure_lines(app, email));
+            if let Some(digest) = app.digest() {
+                header_lines.push(Line::from(Span::styled(
+                    format!(" Digest of {} messages: B to read them one at a time ", digest.messages.len()),
+                    Style::default().fg(Color::Cyan),
+                )));
+            }
+            if app.bounce_editing {
+                header_lines.push(bounce_prompt_line(app));
+            }
+            if app.reminder_input.is_some() {
+                header_lines.push(Line::from(vec![
+                    Span::styled("Remind me: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                    reminder_prompt_span(app),
+                    reminder_hint_span(),
+                ]));
+            }
+            let header_height = email_header_height(app, email, &header_lines, area);
             
             // Determine layout based on whether there are attachments
             let constraints = if email.attachments.is_empty() {
                 vec![
-                    Constraint::Length(6), // Header
+                    Constraint::Length(header_height), // Header
                     Constraint::Min(0),    // Body
                 ]
             } else {
                 vec![
-                    Constraint::Length(6), // Header
+                    Constraint::Length(header_height), // Header
                     Constraint::Length(4 + email.attachments.len().min(5) as u16), // Attachments (max 5 visible)
                     Constraint::Min(0),    // Body
                 ]
@@ -215,31 +1195,64 @@ fn render_view_email_mode(f: &mut Frame, app: &App, area: Rect) {
                 .constraints(constraints)
                 .split(area);
             
-            render_email_header(f, email, chunks[0]);
+            render_email_header(f, app, email, header_lines, chunks[0]);
             
             if !email.attachments.is_empty() {
                 render_email_attachments(f, app, email, chunks[1]);
-                render_scrollable_email_body(f, email, chunks[2], app.email_view_scroll);
+                render_scrollable_email_body(f, app, email, chunks[2], app.email_view_scroll);
             } else {
-                render_scrollable_email_body(f, email, chunks[1], app.email_view_scroll);
+                render_scrollable_email_body(f, app, email, chunks[1], app.email_view_scroll);
             }
         }
     }
 }
 
+/// One message of the open digest, shown in its place
+fn render_digest_message(f: &mut Frame, app: &App, message: &Email, area: Rect) {
+    let (position, count) = app
+        .digest()
+        .map_or((0, 0), |digest| (digest.selected.unwrap_or(0) + 1, digest.messages.len()));
+    let mut header_lines = vec![Line::from(Span::styled(
+        format!(" Digest message {} of {} ([ and ] to move, B for the whole digest) ", position, count),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+    ))];
+    header_lines.extend(email_header_lines(&app.config.ui, app.headers_expanded, message));
+    let header_height = email_header_height(app, message, &header_lines, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(header_height), Constraint::Min(0)])
+        .split(area);
+    render_email_header(f, app, message, header_lines, chunks[0]);
+    render_scrollable_email_body(f, app, message, chunks[1], app.email_view_scroll);
+}
+
 fn render_email_attachments(f: &mut Frame, app: &App, email: &Email, area: Rect) {
+    let account_email = app.config.accounts
+        .get(app.current_account_idx)
+        .map(|account| account.email.as_str())
+        .unwrap_or("");
+    
     let items: Vec<ListItem> = email
         .attachments
         .iter()
         .enumerate()
         .map(|(i, attachment)| {
-            let size = format_file_size(attachment.data.len());
             let style = if Some(i) == app.selected_attachment_idx {
                 Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
             } else {
                 Style::default().fg(Color::Cyan)
             };
             
+            // Attachments without data are fetched by the background download queue
+            let size = if !attachment.is_available() {
+                app.attachment_downloads
+                    .job_for(account_email, &email.folder, &email.id, i)
+                    .map(|job| job.status.label())
+                    .unwrap_or_else(|| "not downloaded".to_string())
+            } else {
+                format_size(attachment.size())
+            };
+            
             let content = format!("📎 {} ({}) - {}", 
                 attachment.filename, 
                 size, 
@@ -249,83 +1262,602 @@ fn render_email_attachments(f: &mut Frame, app: &App, email: &Email, area: Rect)
         })
         .collect();
 
-    let attachments = List::new(items)
-        .block(Block::default()
-            .title("Attachments (Tab to select, 's' to save)")
-            .borders(Borders::ALL))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    let attachments = List::new(items)
+        .block(Block::default()
+            .title("Attachments (Tab to select, 's' to save, 'x' to cancel download)")
+            .borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if let Some(selected) = app.selected_attachment_idx {
+        state.select(Some(selected));
+    }
+
+    f.render_stateful_widget(attachments, area, &mut state);
+}
+
+fn render_scrollable_email_body(f: &mut Frame, app: &App, email: &Email, area: Rect, scroll_offset: usize) {
+    // Listed from headers only, the body is still on its way
+    let downloading = email.id.parse().is_ok_and(|uid: u32| app.pending_bodies.contains(&uid));
+    let placeholder = if downloading { "Downloading message..." } else { "No content" };
+    let mut content = email.body_text.as_deref().unwrap_or(placeholder);
+    // Decrypted text is kept in memory only, never in the cache
+    let mut decrypted = false;
+    if let Some(Ok(crate::pgp::Opened { body: Some(body), .. })) = app.pgp_opened(email) {
+        content = body;
+        decrypted = true;
+    }
+    let mut title = "Body (↑/↓ to scroll, PgUp/PgDn for fast scroll)".to_string();
+
+    // The HTML body as text, when that is how the sender's messages are read
+    let html;
+    let html_body = email.body_html.as_deref().filter(|html| !crate::body_view::is_large(html));
+    if let (true, false, Some(source)) = (app.view_preferences.html, decrypted, html_body) {
+        let blocked;
+        (html, blocked) = crate::body_view::html_text(source, app.view_preferences.remote_content);
+        content = &html;
+        title = match blocked {
+            0 => "Body as HTML ('H' for text, 'w' to open in the browser)".to_string(),
+            1 => "Body as HTML: 1 remote image blocked, 'R' to load".to_string(),
+            blocked => format!("Body as HTML: {} remote images blocked, 'R' to load", blocked),
+        };
+    }
+
+    // Large bodies are shown once prepared off the UI thread, possibly truncated
+    let preparing;
+    if crate::body_view::is_large(content) {
+        let key = crate::body_view::body_key(email, app.show_full_body);
+        match &app.view_body {
+            Some((prepared_key, prepared)) if *prepared_key == key => {
+                content = &prepared.text;
+                if prepared.truncated {
+                    title = format!(
+                        "Body: showing {} of {}, press 'F' for the full message",
+                        format_size(prepared.text.len()),
+                        format_size(prepared.total_len)
+                    );
+                }
+            }
+            _ => {
+                preparing = format!("Preparing large message ({})...", format_size(content.len()));
+                content = &preparing;
+            }
+        }
+    }
+
+    let folded;
+    if !app.view_preferences.expand_quotes {
+        folded = crate::body_view::fold_quotes(content);
+        content = &folded;
+    }
+    
+    let body = Paragraph::new(content)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title(title))
+        .wrap(Wrap { trim: false })
+        .scroll((scroll_offset as u16, 0));
+    
+    f.render_widget(body, area);
+}
+
+fn format_address_list(addresses: &[crate::email::EmailAddress]) -> String {
+    addresses.iter()
+        .map(|addr| match &addr.name {
+            Some(name) if !name.is_empty() => format!("{} <{}>", name, addr.address),
+            _ => addr.address.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn header_line<'a>(label: &str, value: String) -> Line<'a> {
+    Line::from(vec![
+        Span::styled(format!("{}: ", label), Style::default().fg(Color::Gray)),
+        Span::raw(value),
+    ])
+}
+
+/// Rows of the header box for `lines` wrapped to its width, borders included. It takes
+/// at most half of `area`, so long recipient lists can't push the body off screen.
+fn email_header_height(app: &App, email: &Email, lines: &[Line], area: Rect) -> u16 {
+    let mut width = area.width.saturating_sub(2);
+    if app.headers_expanded && header_avatar(app, email).is_some() {
+        width = width.saturating_sub(crate::avatar::AVATAR_PIXELS as u16 + 1);
+    }
+    (wrapped_height(lines, width) + 2).min((area.height / 2).max(3))
+}
+
+/// Rows `lines` take on screen when wrapped to `width` columns
+fn wrapped_height(lines: &[Line], width: u16) -> u16 {
+    let width = usize::from(width.max(1));
+    let rows: usize = lines.iter().map(|line| line.width().max(1).div_ceil(width)).sum();
+    rows.min(u16::MAX as usize) as u16
+}
+
+/// Header lines for the viewer: a single summary line when collapsed, the full
+/// recipient lists, Reply-To, zoned date and configured extra headers when expanded
+fn email_header_lines<'a>(ui: &UIConfig, expanded: bool, email: &Email) -> Vec<Line<'a>> {
+    let from = email.from.first().map_or("Unknown", |addr| {
+        addr.name.as_deref().unwrap_or(&addr.address)
+    });
+    
+    if !expanded {
+        return vec![
+            Line::from(vec![
+                priority_marker(email),
+                sender_badge(ui, email),
+                Span::raw(" "),
+                Span::styled(from.to_string(), Style::default().fg(Color::Cyan)),
+                authentication_badge(email),
+                Span::raw(format!(" — {} — {}", email.subject, email.date.format("%Y-%m-%d %H:%M"))),
+            ]),
+        ];
+    }
+    
+    let mut lines = vec![
+        Line::from(vec![
+            sender_badge(ui, email),
+            Span::raw(if ui.avatars { " " } else { "" }),
+            Span::styled("From: ", Style::default().fg(Color::Gray)),
+            Span::raw(format_address_list(&email.from)),
+            authentication_badge(email),
+        ]),
+        header_line("To", format_address_list(&email.to)),
+    ];
+    if !email.cc.is_empty() {
+        lines.push(header_line("Cc", format_address_list(&email.cc)));
+    }
+    if let Some(reply_to) = email.header("Reply-To") {
+        lines.push(header_line("Reply-To", reply_to.to_string()));
+    }
+    lines.push(header_line("Subject", email.subject.clone()));
+    lines.push(header_line("Date", email.date.format("%a, %d %b %Y %H:%M:%S %:z").to_string()));
+    if let Some(results) = crate::auth_results::results(email) {
+        let mut spans = vec![Span::styled("Authentication: ", Style::default().fg(Color::Gray))];
+        for (i, check) in results.checks.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" · "));
+            }
+            let color = match check.result.as_str() {
+                "pass" => Color::Green,
+                "fail" | "permerror" => Color::Red,
+                "softfail" | "temperror" => Color::Yellow,
+                _ => Color::Gray,
+            };
+            spans.push(Span::styled(check.summary(), Style::default().fg(color)));
+        }
+        spans.push(Span::styled(format!("  checked by {}", results.authserv_id), Style::default().fg(Color::DarkGray)));
+        lines.push(Line::from(spans));
+    }
+    match email.priority() {
+        Some(crate::config::Priority::High) => lines.push(Line::from(vec![
+            Span::styled("Priority: ", Style::default().fg(Color::Gray)),
+            Span::styled("High", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        ])),
+        Some(crate::config::Priority::Low) => lines.push(Line::from(vec![
+            Span::styled("Priority: ", Style::default().fg(Color::Gray)),
+            Span::styled("Low", Style::default().fg(Color::DarkGray)),
+        ])),
+        _ => {}
+    }
+    for name in &ui.extra_headers {
+        if let Some(value) = email.header(name) {
+            lines.push(header_line(name, value.to_string()));
+        }
+    }
+    lines
+}
+
+/// Note that the conversation is muted, and how to undo it
+fn muted_lines<'a>(app: &App, email: &Email) -> Vec<Line<'a>> {
+    let Some(thread) = app.muted_thread(email) else {
+        return Vec::new();
+    };
+    let (effect, key) = if thread.archive {
+        (format!("marked read and moved to {}", app.archive_folder()), 'M')
+    } else {
+        ("marked read".to_string(), 'm')
+    };
+    vec![Line::from(Span::styled(
+        format!(" 🔇 Muted conversation: new messages are {}; {} to unmute ", effect, key),
+        Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+    ))]
+}
+
+/// What a GitHub or GitLab notification is about, and the keys acting on it
+fn notification_lines<'a>(email: &Email) -> Vec<Line<'a>> {
+    let Some(notification) = crate::forge::detect(email) else {
+        return Vec::new();
+    };
+    let badge = Style::default().fg(Color::Black).bg(Color::LightBlue).add_modifier(Modifier::BOLD);
+    let mut spans = vec![
+        Span::styled(format!(" {} ", notification.forge.name()), badge),
+        Span::raw(" "),
+        Span::styled(notification.summary(), Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD)),
+    ];
+    if let Some(reason) = &notification.reason {
+        spans.push(Span::styled(format!(" ({})", reason), Style::default().fg(Color::Gray)));
+    }
+    let mut keys = Vec::new();
+    if notification.url.is_some() {
+        keys.push("o to open in the browser");
+    }
+    if notification.unsubscribe.is_some() {
+        keys.push("u to unsubscribe from the thread");
+    }
+    if !keys.is_empty() {
+        spans.push(Span::styled(format!("  {}", keys.join(", ")), Style::default().fg(Color::DarkGray)));
+    }
+    vec![Line::from(spans)]
+}
+
+/// The event of an invitation: when and where, who organizes it and who is invited,
+/// and, with a calendar set up, what it clashes with
+/// The people on contact cards attached to the email, with how to save them
+fn contact_card_lines<'a>(email: &Email) -> Vec<Line<'a>> {
+    let cards = crate::vcard::attached_cards(email);
+    if cards.is_empty() {
+        return Vec::new();
+    }
+    let people: Vec<String> = cards
+        .iter()
+        .map(|card| match &card.name {
+            Some(name) => format!("{} <{}>", name, card.emails[0]),
+            None => card.emails[0].clone(),
+        })
+        .collect();
+    vec![Line::from(vec![
+        Span::styled(" Contact ", Style::default().fg(Color::Black).bg(Color::LightGreen).add_modifier(Modifier::BOLD)),
+        Span::raw(format!(" {}", people.join(", "))),
+        Span::styled("  A to add to your contacts", Style::default().fg(Color::Gray)),
+    ])]
+}
+
+fn calendar_lines<'a>(app: &App, email: &Email) -> Vec<Line<'a>> {
+    let Some(invitation) = crate::calendar::invitation(email) else {
+        return Vec::new();
+    };
+    let Some(event) = invitation.event() else {
+        return Vec::new();
+    };
+    let (badge, color) = match invitation.calendar.method.as_deref() {
+        Some("CANCEL") => (" Cancelled ", Color::LightRed),
+        Some("REPLY") => (" Reply ", Color::LightBlue),
+        Some("REQUEST") | None => (" Invitation ", Color::LightMagenta),
+        Some(_) => (" Event ", Color::LightMagenta),
+    };
+    let mut spans = vec![
+        Span::styled(badge, Style::default().fg(Color::Black).bg(color).add_modifier(Modifier::BOLD)),
+        Span::raw(" "),
+        Span::styled(event.summary.clone(), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::raw(format!("  {}", event.when())),
+    ];
+    if let Some(location) = &event.location {
+        spans.push(Span::styled(format!(" · {}", location), Style::default().fg(Color::Gray)));
+    }
+    let mut lines = vec![Line::from(spans)];
+
+    let mut people = Vec::new();
+    if let Some(organizer) = &event.organizer {
+        people.push(Span::styled("Organizer: ", Style::default().fg(Color::Gray)));
+        people.push(Span::raw(organizer.clone()));
+    }
+    if !event.attendees.is_empty() {
+        people.push(Span::styled(if people.is_empty() { "Invited: " } else { "  Invited: " }, Style::default().fg(Color::Gray)));
+        for (i, attendee) in event.attendees.iter().enumerate() {
+            if i > 0 {
+                people.push(Span::raw(", "));
+            }
+            let (mark, color) = match attendee.status.as_str() {
+                "ACCEPTED" => ("✓", Color::Green),
+                "DECLINED" => ("✗", Color::Red),
+                "TENTATIVE" => ("?", Color::Yellow),
+                _ => ("", Color::Gray),
+            };
+            let name = attendee.name.clone().unwrap_or_else(|| attendee.address.clone());
+            let optional = if attendee.optional { " (optional)" } else { "" };
+            people.push(Span::styled(format!("{}{}{}", mark, name, optional), Style::default().fg(color)));
+        }
+    }
+    if !people.is_empty() {
+        lines.push(Line::from(people));
+    }
+
+    let answerable = invitation.calendar.method.as_deref().is_none_or(|m| m == "REQUEST");
+    if app.rsvp_prompt {
+        lines.push(Line::from(vec![
+            Span::styled("Answer: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            Span::raw("a accept, t tentative, d decline"),
+            Span::styled("  any other key to cancel", Style::default().fg(Color::DarkGray)),
+        ]));
+    } else if answerable {
+        lines.push(Line::from(Span::styled("i to accept, accept tentatively or decline", Style::default().fg(Color::DarkGray))));
+    }
+
+    let has_calendar = app.config.accounts.get(app.current_account_idx).is_some_and(|a| a.caldav.is_some());
+    if !has_calendar || !answerable {
+        return lines;
+    }
+    let label = Span::styled("Calendar: ", Style::default().fg(Color::Gray));
+    let status = match app.calendar_conflicts(email) {
+        None => Span::styled("checking…", Style::default().fg(Color::DarkGray)),
+        Some(Err(e)) => Span::styled(e.clone(), Style::default().fg(Color::Red)),
+        Some(Ok(conflicts)) if conflicts.is_empty() => Span::styled("✓ You are free then", Style::default().fg(Color::Green)),
+        Some(Ok(conflicts)) => {
+            let clashes: Vec<String> = conflicts
+                .iter()
+                .map(|other| format!("{} ({})", if other.summary.is_empty() { "busy" } else { &other.summary }, other.when()))
+                .collect();
+            Span::styled(format!("⚠ Conflicts with {}", clashes.join(", ")), Style::default().fg(Color::Yellow))
+        }
+    };
+    lines.push(Line::from(vec![
+        label,
+        status,
+        Span::styled("  c to add it to your calendar", Style::default().fg(Color::DarkGray)),
+    ]));
+    lines
+}
+
+/// Whether the message was decrypted and whose signature it carries
+fn pgp_lines<'a>(app: &App, email: &Email) -> Vec<Line<'a>> {
+    if crate::pgp::kind(email).is_none() {
+        return Vec::new();
+    }
+    let label = Span::styled("PGP: ", Style::default().fg(Color::Gray));
+    let opened = match app.pgp_opened(email) {
+        None => return vec![Line::from(vec![label, Span::styled("checking…", Style::default().fg(Color::DarkGray))])],
+        Some(Err(e)) => return vec![Line::from(vec![label, Span::styled(e.clone(), Style::default().fg(Color::Red))])],
+        Some(Ok(opened)) => opened,
+    };
+
+    let mut spans = vec![label];
+    if opened.decrypted {
+        spans.push(Span::styled("🔒 Decrypted", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)));
+        if opened.hidden_attachments > 0 {
+            spans.push(Span::styled(
+                format!(" ({} attachments inside are not listed)", opened.hidden_attachments),
+                Style::default().fg(Color::Gray),
+            ));
+        }
+    }
+    match &opened.signature {
+        Some(signature) => {
+            let sender = email.from.first().map(|from| from.address.as_str()).unwrap_or_default();
+            let color = match signature.verdict {
+                crate::pgp::Verdict::Good if signature.trusted && signature.is_from(sender) => Color::Green,
+                crate::pgp::Verdict::Good | crate::pgp::Verdict::Expired | crate::pgp::Verdict::UnknownKey => Color::Yellow,
+                crate::pgp::Verdict::Bad | crate::pgp::Verdict::Revoked => Color::Red,
+            };
+            if opened.decrypted {
+                spans.push(Span::raw(" · "));
+            }
+            spans.push(Span::styled(signature.summary(), Style::default().fg(color)));
+            if signature.verdict != crate::pgp::Verdict::UnknownKey && !signature.is_from(sender) {
+                spans.push(Span::styled(" (not the sender's key)", Style::default().fg(Color::Red)));
+            }
+        }
+        None if opened.decrypted => spans.push(Span::styled(" · not signed", Style::default().fg(Color::Gray))),
+        None => spans.push(Span::styled("No signature found", Style::default().fg(Color::Gray))),
+    }
+    vec![Line::from(spans)]
+}
+
+/// Banner for mail from outside the account's internal domains
+fn external_lines<'a>(app: &App, email: &Email) -> Vec<Line<'a>> {
+    if !app.is_external(email) {
+        return Vec::new();
+    }
+    let banner = Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD);
+    vec![Line::from(Span::styled(
+        " [External] This message comes from outside your organisation. Take care with links and attachments. ",
+        banner,
+    ))]
+}
+
+/// Banner for a sender using a known contact's name from an address never written to
+fn impersonation_lines<'a>(app: &App, email: &Email) -> Vec<Line<'a>> {
+    let Some(warning) = app.impersonation_warning(email) else {
+        return Vec::new();
+    };
+    let banner = Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD);
+    let from = email.from.first().map(|addr| addr.address.clone()).unwrap_or_default();
+    vec![
+        Line::from(Span::styled(
+            format!(" ⚠ Possible impersonation: \"{}\" is the name of a contact who writes from another address ", warning.claimed_name),
+            banner,
+        )),
+        Line::from(vec![
+            Span::styled("Sent from ", Style::default().fg(Color::Red)),
+            Span::styled(from, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+            Span::raw(format!("; you have written to them at {}", warning.known_addresses.join(", "))),
+        ]),
+    ]
+}
+
+/// The domain part of the sender's address
+fn sender_domain(email: &Email) -> String {
+    let address = email.from.first().map(|from| from.address.as_str()).unwrap_or_default();
+    address.rsplit('@').next().unwrap_or_default().to_lowercase()
+}
+
+/// A mark after the sender: whether the receiving server could confirm the message comes
+/// from the sender's domain
+fn authentication_badge<'a>(email: &Email) -> Span<'a> {
+    let Some(results) = crate::auth_results::results(email) else {
+        return Span::raw("");
+    };
+    match results.verdict(&sender_domain(email)) {
+        crate::auth_results::Verdict::Verified => Span::styled(" ✓", Style::default().fg(Color::Green)),
+        crate::auth_results::Verdict::Failed => Span::styled(" ✗", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        crate::auth_results::Verdict::Unverified => Span::styled(" ?", Style::default().fg(Color::Yellow)),
+    }
+}
+
+/// A warning when the sender's domain failed authentication
+fn authentication_failure_lines<'a>(email: &Email) -> Vec<Line<'a>> {
+    let Some(results) = crate::auth_results::results(email) else {
+        return Vec::new();
+    };
+    let domain = sender_domain(email);
+    if results.verdict(&domain) != crate::auth_results::Verdict::Failed {
+        return Vec::new();
+    }
+    let banner = Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD);
+    vec![Line::from(vec![
+        Span::styled(format!(" ⚠ {} could not confirm this message comes from {} ", results.authserv_id, domain), banner),
+        Span::styled("  h for details", Style::default().fg(Color::DarkGray)),
+    ])]
+}
+
+/// Failed recipients of a bounced sent message, or those a bounce notification reports
+fn delivery_failure_lines<'a>(app: &App, email: &Email) -> Vec<Line<'a>> {
+    let failures: Vec<(String, String, String)> = match app.bounces_for(email) {
+        Some(records) => records
+            .iter()
+            .map(|r| (r.recipient.clone(), r.status.clone(), r.reason.clone()))
+            .collect(),
+        None => crate::dsn::parse(email)
+            .map(|report| report.failures.into_iter().map(|f| (f.recipient, f.status, f.reason)).collect())
+            .unwrap_or_default(),
+    };
+    if failures.is_empty() {
+        return Vec::new();
+    }
+
+    let label = if app.bounces_for(email).is_some() { "Bounced: " } else { "Delivery failed: " };
+    let mut lines: Vec<Line> = failures
+        .into_iter()
+        .map(|(recipient, status, reason)| {
+            Line::from(vec![
+                Span::styled(label, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+                Span::styled(recipient, Style::default().fg(Color::Red)),
+                Span::raw(format!(" — {} ({})", reason, status)),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(Span::styled(
+        "Press 'e' to resend to a corrected address",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines
+}
+
+/// Prompt for the addresses to bounce the open email to
+/// When to be reminded, as typed after `z`
+fn reminder_prompt_span<'a>(app: &App) -> Span<'a> {
+    Span::styled(format!("{}_", app.reminder_input.as_deref().unwrap_or_default()), Style::default().fg(Color::Yellow))
+}
 
-    let mut state = ratatui::widgets::ListState::default();
-    if let Some(selected) = app.selected_attachment_idx {
-        state.select(Some(selected));
-    }
+fn reminder_hint_span<'a>() -> Span<'a> {
+    Span::styled("   30m, 2h, 2 days, tomorrow, friday or 17:30 · Enter to set, Esc to cancel", Style::default().fg(Color::DarkGray))
+}
 
-    f.render_stateful_widget(attachments, area, &mut state);
+fn bounce_prompt_line<'a>(app: &App) -> Line<'a> {
+    Line::from(vec![
+        Span::styled("Bounce to: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw(format!("{}_", app.bounce_text)),
+        Span::styled("  (Enter to send unchanged, Esc to cancel)", Style::default().fg(Color::DarkGray)),
+    ])
 }
 
-fn format_file_size(bytes: usize) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
-    
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
+/// Red `!` for high-priority messages, grey `↓` for low, a space otherwise
+fn priority_marker<'a>(email: &Email) -> Span<'a> {
+    match email.priority() {
+        Some(crate::config::Priority::High) => {
+            Span::styled("!", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        }
+        Some(crate::config::Priority::Low) => Span::styled("↓", Style::default().fg(Color::DarkGray)),
+        _ => Span::raw(" "),
     }
-    
-    if unit_index == 0 {
-        format!("{} {}", bytes, UNITS[unit_index])
-    } else {
-        format!("{:.1} {}", size, UNITS[unit_index])
+}
+
+/// Arrow for a message already replied to (`\Answered`), forwarded (`$Forwarded`) or both
+fn reply_marker<'a>(email: &Email) -> Span<'a> {
+    let answered = email.flags.iter().any(|flag| flag.eq_ignore_ascii_case("\\Answered"));
+    let forwarded = email.flags.iter().any(|flag| flag.eq_ignore_ascii_case("$Forwarded"));
+    let style = Style::default().fg(Color::Blue);
+    match (answered, forwarded) {
+        (true, true) => Span::styled("⇄", style),
+        (true, false) => Span::styled("↩", style),
+        (false, true) => Span::styled("→", style),
+        (false, false) => Span::raw(" "),
     }
 }
 
-fn render_scrollable_email_body(f: &mut Frame, email: &Email, area: Rect, scroll_offset: usize) {
-    let content = email.body_text.as_deref().unwrap_or("No content");
-    
-    let body = Paragraph::new(content)
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .title("Body (↑/↓ to scroll, PgUp/PgDn for fast scroll)"))
-        .wrap(Wrap { trim: false })
-        .scroll((scroll_offset as u16, 0));
-    
-    f.render_widget(body, area);
+/// Coloured initials shown when no avatar image is available
+fn sender_badge<'a>(ui: &UIConfig, email: &Email) -> Span<'a> {
+    let (name, address) = email.from.first()
+        .map(|addr| (addr.name.as_deref(), addr.address.as_str()))
+        .unwrap_or((None, ""));
+    if !ui.avatars {
+        return Span::raw("");
+    }
+    let (r, g, b) = crate::avatar::badge_color(address);
+    Span::styled(
+        format!(" {} ", crate::avatar::initials(name, address)),
+        Style::default().bg(Color::Rgb(r, g, b)).fg(Color::White).add_modifier(Modifier::BOLD),
+    )
 }
 
-fn render_email_header(f: &mut Frame, email: &Email, area: Rect) {
-    let from = email.from.first().map_or("Unknown", |addr| {
-        addr.name.as_deref().unwrap_or(&addr.address)
-    });
-    
-    let to = email.to.iter()
-        .map(|addr| addr.address.clone())
-        .collect::<Vec<_>>()
-        .join(", ");
+/// Render an avatar thumbnail with upper half blocks, two pixel rows per terminal row
+fn avatar_lines<'a>(image: &crate::avatar::AvatarImage) -> Vec<Line<'a>> {
+    (0..crate::avatar::AVATAR_PIXELS / 2)
+        .map(|row| {
+            let spans: Vec<Span> = (0..crate::avatar::AVATAR_PIXELS)
+                .map(|x| {
+                    let (tr, tg, tb) = image.pixel(x, row * 2);
+                    let (br, bg, bb) = image.pixel(x, row * 2 + 1);
+                    Span::styled("▀", Style::default().fg(Color::Rgb(tr, tg, tb)).bg(Color::Rgb(br, bg, bb)))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// The sender's avatar once fetched, or else their domain's brand logo
+fn header_avatar<'a>(app: &'a App, email: &Email) -> Option<&'a crate::avatar::AvatarImage> {
+    let own = email.from.first()
+        .and_then(|addr| app.avatars.get(&crate::avatar::normalize_address(&addr.address)))
+        .and_then(|image| image.as_ref());
+    own.or_else(|| {
+        let domain = crate::avatar::bimi_domain(email)?;
+        app.avatars.get(&crate::avatar::bimi_key(&domain))?.as_ref()
+    })
+}
+
+fn render_email_header(f: &mut Frame, app: &App, email: &Email, header_text: Vec<Line>, area: Rect) {
+    let title = if app.headers_expanded {
+        "Email ('h' to collapse headers)"
+    } else {
+        "Email ('h' to show all headers)"
+    };
     
-    let header_text = vec![
-        Line::from(vec![
-            Span::styled("From: ", Style::default().fg(Color::Gray)),
-            Span::raw(from),
-        ]),
-        Line::from(vec![
-            Span::styled("To: ", Style::default().fg(Color::Gray)),
-            Span::raw(to),
-        ]),
-        Line::from(vec![
-            Span::styled("Subject: ", Style::default().fg(Color::Gray)),
-            Span::raw(&email.subject),
-        ]),
-        Line::from(vec![
-            Span::styled("Date: ", Style::default().fg(Color::Gray)),
-            Span::raw(email.date.format("%Y-%m-%d %H:%M:%S").to_string()),
-        ]),
-    ];
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    
+    let text_area = match header_avatar(app, email) {
+        Some(image) if app.headers_expanded && inner.height as usize >= crate::avatar::AVATAR_PIXELS / 2 => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Length(crate::avatar::AVATAR_PIXELS as u16 + 1), // Avatar
+                    Constraint::Min(0),                                          // Headers
+                ])
+                .split(inner);
+            f.render_widget(Paragraph::new(avatar_lines(image)), columns[0]);
+            columns[1]
+        }
+        _ => inner,
+    };
     
     let header = Paragraph::new(header_text)
-        .block(Block::default().title("Email").borders(Borders::ALL));
+        .wrap(Wrap { trim: false });
     
-    f.render_widget(header, area);
+    f.render_widget(header, text_area);
 }
 
 #[allow(dead_code)]
@@ -340,6 +1872,9 @@ fn render_email_body(f: &mut Frame, email: &Email, area: Rect) {
     f.render_widget(body, area);
 }
 
+/// Rows the compose body keeps however far the header fields are grown
+const COMPOSE_BODY_MIN: u16 = 3;
+
 fn render_compose_mode(f: &mut Frame, app: &App, area: Rect) {
     // If showing spell suggestions, render the suggestion popup
     if app.show_spell_suggestions {
@@ -358,20 +1893,52 @@ fn render_compose_mode(f: &mut Frame, app: &App, area: Rect) {
         render_attachment_input_dialog(f, app, area);
         return;
     }
+
+    if let Some(choices) = &app.recipient_choices {
+        render_recipient_choices(f, choices, area);
+        return;
+    }
     
+    let status_height = if app.show_compose_stats { 3 } else { 2 };
+    if app.compose_fullscreen() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),             // Headers in one line
+                Constraint::Min(0),                // Body
+                Constraint::Length(status_height), // Status area (spell + grammar check, stats)
+            ])
+            .split(area);
+        f.render_widget(Paragraph::new(compose_summary(app)).style(Style::default().fg(Color::DarkGray)), chunks[0]);
+        render_compose_body(f, app, chunks[1]);
+        render_check_status(f, app, chunks[2]);
+        return;
+    }
+
+    let attachments_height = if app.compose_email.attachments.is_empty() {
+        0
+    } else {
+        4 + app.compose_email.attachments.len().min(3) as u16
+    };
+    // The header fields give up rows before the body drops below a few lines
+    let header_height = app
+        .compose_header_height
+        .get()
+        .min(area.height.saturating_sub(status_height + attachments_height + COMPOSE_BODY_MIN));
+    app.compose_header_height.set(header_height);
     // Determine layout based on whether there are attachments
     let constraints = if app.compose_email.attachments.is_empty() {
         vec![
-            Constraint::Length(12), // Header fields (To, CC, BCC, Subject)
+            Constraint::Length(header_height), // Header fields (To, CC, BCC, Subject)
             Constraint::Min(0),     // Body
-            Constraint::Length(2),  // Status area (spell + grammar check)
+            Constraint::Length(status_height), // Status area (spell + grammar check, stats)
         ]
     } else {
         vec![
-            Constraint::Length(12), // Header fields (To, CC, BCC, Subject)
-            Constraint::Length(4 + app.compose_email.attachments.len().min(3) as u16), // Attachments (max 3 visible)
+            Constraint::Length(header_height), // Header fields (To, CC, BCC, Subject)
+            Constraint::Length(attachments_height), // Attachments (max 3 visible)
             Constraint::Min(0),     // Body
-            Constraint::Length(2),  // Status area (spell + grammar check)
+            Constraint::Length(status_height), // Status area (spell + grammar check, stats)
         ]
     };
     
@@ -489,7 +2056,7 @@ fn render_compose_mode(f: &mut Frame, app: &App, area: Rect) {
         Line::from(app.compose_email.subject.clone()).into()
     };
     
-    let header_text = vec![
+    let mut header_text = vec![
         Line::from(""),
         Line::from(vec![
             Span::styled("To: ", to_style),
@@ -509,16 +2076,69 @@ fn render_compose_mode(f: &mut Frame, app: &App, area: Rect) {
         // Add the subject text with potential highlighting
         // We can't directly use Line::from(subject_text) because subject_text is already a Text
         Line::from(""),
-        Line::from("Tab/↑↓: Navigate fields | Ctrl+S: Send | Esc: Cancel"),
+        Line::from(format!(
+            "Tab/↑↓: Navigate fields | Ctrl+S: Send | Ctrl+D: Save draft | Esc: Cancel | Alt+F: Full screen | Alt+M: {} | Alt+I: {} priority",
+            match app.compose_format {
+                crate::config::ComposeFormat::Plain => "Plain text",
+                crate::config::ComposeFormat::Markdown => "Markdown",
+            },
+            match app.compose_priority() {
+                Some(crate::config::Priority::High) => "High",
+                Some(crate::config::Priority::Low) => "Low",
+                _ => "Normal",
+            }
+        )),
     ];
+    if app.config.accounts.get(app.current_account_idx).is_some_and(|account| account.pgp.is_some()) {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let style = if app.compose_pgp.any() { Style::default().fg(Color::Green) } else { Style::default() };
+        header_text.push(Line::from(Span::styled(
+            format!("PGP: Alt+K: sign {} | Alt+E: encrypt {}", on_off(app.compose_pgp.sign), on_off(app.compose_pgp.encrypt)),
+            style,
+        )));
+    }
+    for (typo, suggestion) in app.recipient_typos() {
+        header_text.push(Line::from(Span::styled(
+            format!("⚠ {}: did you mean {}? (Alt+C to correct)", typo, suggestion),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    for suppressed in app.suppressed_recipients() {
+        let day = chrono::DateTime::from_timestamp(suppressed.bounced_at, 0)
+            .map(|date| date.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        header_text.push(Line::from(Span::styled(
+            format!(
+                "⚠ {} hard-bounced on {} ({} {}) (Alt+U to remove it here and from contacts)",
+                suppressed.address, day, suppressed.status, suppressed.reason
+            ),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    let found = app.directory_suggestions();
+    if !found.is_empty() {
+        let people: Vec<String> = found
+            .iter()
+            .take(3)
+            .map(|contact| match &contact.name {
+                Some(name) => format!("{} <{}>", name, contact.address),
+                None => contact.address.clone(),
+            })
+            .collect();
+        let more = if found.len() > 3 { format!(" and {} more", found.len() - 3) } else { String::new() };
+        header_text.push(Line::from(Span::styled(
+            format!("Directory: {}{} (Enter to pick)", people.join(", "), more),
+            Style::default().fg(Color::Cyan),
+        )));
+    }
     
     let header = Paragraph::new(header_text)
         .block(Block::default().title("New Email").borders(Borders::ALL));
     
     f.render_widget(header, chunks[0]);
     
-    // Render subject text separately if it has highlighting
-    if app.spell_check_enabled && app.compose_field == crate::app::ComposeField::Subject {
+    // Render subject text separately if it has highlighting, and the pane still shows its line
+    if app.spell_check_enabled && app.compose_field == crate::app::ComposeField::Subject && chunks[0].height > 7 {
         let subject_area = Rect {
             x: chunks[0].x + 10, // Offset to align with "Subject: " text
             y: chunks[0].y + 6,  // Position after the "Subject: " line (adjusted for CC/BCC)
@@ -537,7 +2157,37 @@ fn render_compose_mode(f: &mut Frame, app: &App, area: Rect) {
         render_compose_attachments(f, app, chunks[1]);
         2
     };
+    render_compose_body(f, app, chunks[body_chunk_idx]);
+
+    // Render spell check status bar
+    let status_chunk_idx = if app.compose_email.attachments.is_empty() {
+        2
+    } else {
+        3
+    };
     
+    if status_chunk_idx < chunks.len() {
+        render_check_status(f, app, chunks[status_chunk_idx]);
+    }
+}
+
+/// The compose headers in one line, above a full-screen body
+fn compose_summary(app: &App) -> String {
+    let mut summary = format!("To: {}", app.compose_to_text);
+    if !app.compose_cc_text.trim().is_empty() {
+        summary.push_str(&format!(" | CC: {}", app.compose_cc_text));
+    }
+    summary.push_str(&format!(" | Subject: {}", app.compose_email.subject));
+    match app.compose_email.attachments.len() {
+        0 => {}
+        1 => summary.push_str(" | 1 attachment"),
+        n => summary.push_str(&format!(" | {} attachments", n)),
+    }
+    summary.push_str(" | Alt+F: Show headers");
+    summary
+}
+
+fn render_compose_body(f: &mut Frame, app: &App, area: Rect) {
     // Render compose form body with highlighting and cursor
     let content = app.compose_email.body_text.as_deref().unwrap_or("");
     
@@ -592,18 +2242,7 @@ fn render_compose_mode(f: &mut Frame, app: &App, area: Rect) {
         .style(body_style)
         .wrap(Wrap { trim: false });
     
-    f.render_widget(body, chunks[body_chunk_idx]);
-    
-    // Render spell check status bar
-    let status_chunk_idx = if app.compose_email.attachments.is_empty() {
-        2
-    } else {
-        3
-    };
-    
-    if status_chunk_idx < chunks.len() {
-        render_check_status(f, app, chunks[status_chunk_idx]);
-    }
+    f.render_widget(body, area);
 }
 
 fn render_check_status(f: &mut Frame, app: &App, area: Rect) {
@@ -613,6 +2252,7 @@ fn render_check_status(f: &mut Frame, app: &App, area: Rect) {
         .constraints([
             Constraint::Length(1), // Spell check status
             Constraint::Length(1), // Grammar check status
+            Constraint::Length(1), // Compose statistics, on demand
         ])
         .split(area);
 
@@ -685,6 +2325,28 @@ fn render_check_status(f: &mut Frame, app: &App, area: Rect) {
         .alignment(Alignment::Left);
 
     f.render_widget(grammar_status, status_chunks[1]);
+
+    if let Some(stats) = app.get_compose_stats() {
+        let reading = if stats.reading_secs < 60 {
+            format!("{} s", stats.reading_secs)
+        } else {
+            format!("{} min", stats.reading_secs.div_ceil(60))
+        };
+        let stats_text = format!(
+            "Stats: {} words | {} read | Readability: {:.0} ({}) | Passive: {} | Tone: {} | Alt+W: Hide",
+            stats.words,
+            reading,
+            stats.readability,
+            crate::compose_stats::readability_label(stats.readability),
+            stats.passive,
+            stats.tone.hint()
+        );
+        let color = match stats.tone {
+            crate::compose_stats::Tone::Demanding | crate::compose_stats::Tone::Shouting => Color::Yellow,
+            _ => Color::Cyan,
+        };
+        f.render_widget(Paragraph::new(stats_text).style(Style::default().fg(color)), status_chunks[2]);
+    }
 }
 
 fn render_spell_suggestions(f: &mut Frame, app: &App, area: Rect) {
@@ -747,6 +2409,42 @@ fn render_spell_suggestions(f: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+/// Contacts a name typed as a recipient could mean, most corresponded-with first
+fn render_recipient_choices(f: &mut Frame, choices: &crate::app::RecipientChoices, area: Rect) {
+    let popup_area = centered_rect(60, 50, area);
+    f.render_widget(Clear, popup_area);
+
+    let items: Vec<ListItem> = choices
+        .contacts
+        .iter()
+        .map(|contact| {
+            let name = contact.name.as_deref().unwrap_or_default();
+            let messages = match contact.message_count {
+                0 if contact.saved => "contacts".to_string(),
+                0 => "directory".to_string(),
+                1 => "1 message".to_string(),
+                count => format!("{} messages", count),
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} <{}>", name, contact.address)),
+                Span::styled(format!("  {}", messages), Style::default().fg(Color::Gray)),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Contacts matching '{}' (Enter to pick, Esc to keep typing)", choices.name))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    let mut state = ratatui::widgets::ListState::default();
+    state.select(Some(choices.selected));
+    f.render_stateful_widget(list, popup_area, &mut state);
+}
+
 fn render_grammar_suggestions(f: &mut Frame, app: &App, area: Rect) {
     // Find the current grammar error at cursor position
     let mut current_error: Option<&crate::grammarcheck::GrammarError> = None;
@@ -861,7 +2559,7 @@ fn render_file_browser(f: &mut Frame, app: &App, area: Rect) {
             };
             
             let size_str = if let Some(size) = item.size {
-                format!(" ({})", format_file_size(size as usize))
+                format!(" ({})", format_size(size as usize))
             } else {
                 String::new()
             };
@@ -975,7 +2673,7 @@ fn render_compose_attachments(f: &mut Frame, app: &App, area: Rect) {
         .iter()
         .enumerate()
         .map(|(i, attachment)| {
-            let size = format_file_size(attachment.data.len());
+            let size = format_size(attachment.size());
             let style = if Some(i) == app.selected_attachment_idx {
                 Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
             } else {
@@ -993,7 +2691,7 @@ fn render_compose_attachments(f: &mut Frame, app: &App, area: Rect) {
 
     let attachments = List::new(items)
         .block(Block::default()
-            .title("Attachments (Ctrl+A to add, Ctrl+X to remove)")
+            .title("Attachments (Ctrl+A to add, Ctrl+V to paste an image, Ctrl+X to remove)")
             .borders(Borders::ALL))
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
@@ -1022,8 +2720,8 @@ fn render_folder_list_mode(f: &mut Frame, app: &App, area: Rect) {
                     };
                     (display_text, style)
                 }
-                crate::app::FolderItem::Folder { name, .. } => {
-                    let display_text = format!("  📁 {}", name);
+                crate::app::FolderItem::Folder { name, account_index, full_path } => {
+                    let display_text = format!("  📁 {}{}", name, folder_option_marks(app, *account_index, full_path));
                     let style = if i == app.selected_folder_item_idx {
                         Style::default().fg(Color::Yellow)
                     } else {
@@ -1031,6 +2729,30 @@ fn render_folder_list_mode(f: &mut Frame, app: &App, area: Rect) {
                     };
                     (display_text, style)
                 }
+                crate::app::FolderItem::ReadLater { .. } => {
+                    let style = if i == app.selected_folder_item_idx {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::Magenta)
+                    };
+                    ("  🔖 Read Later".to_string(), style)
+                }
+                crate::app::FolderItem::Vip { .. } => {
+                    let style = if i == app.selected_folder_item_idx {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::LightRed)
+                    };
+                    ("  ★ VIP".to_string(), style)
+                }
+                crate::app::FolderItem::Notifications { .. } => {
+                    let style = if i == app.selected_folder_item_idx {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::LightBlue)
+                    };
+                    ("  🔔 Notifications".to_string(), style)
+                }
             };
             
             ListItem::new(text).style(style)
@@ -1039,7 +2761,7 @@ fn render_folder_list_mode(f: &mut Frame, app: &App, area: Rect) {
 
     let folders = List::new(items)
         .block(Block::default()
-            .title("Select Account or Folder (↑/↓: Navigate, Enter: Select/Expand, Esc: Cancel)")
+            .title("Select Account or Folder (↑/↓: Navigate, Enter: Select/Expand, N: Never notify, A: Auto-sync, c/r/d: Create/Rename/Delete, Esc: Cancel)")
             .borders(Borders::ALL))
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
@@ -1047,9 +2769,30 @@ fn render_folder_list_mode(f: &mut Frame, app: &App, area: Rect) {
     let mut state = ratatui::widgets::ListState::default();
     state.select(Some(app.selected_folder_item_idx));
 
-    // Center the folder list
+    // Center the folder list, with the name being typed or the question below it
     let centered_area = centered_rect(80, 80, area);
-    f.render_stateful_widget(folders, centered_area, &mut state);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(if app.folder_edit.is_some() { 3 } else { 0 })])
+        .split(centered_area);
+    f.render_stateful_widget(folders, chunks[0], &mut state);
+
+    let prompt = match &app.folder_edit {
+        Some(crate::app::FolderEdit::Create { name, .. }) => Some(("New folder".to_string(), format!("{}_", name))),
+        Some(crate::app::FolderEdit::Rename { folder, name, .. }) => Some((format!("Rename {}", folder), format!("{}_", name))),
+        Some(crate::app::FolderEdit::Delete { folder, .. }) => {
+            Some(("Delete folder".to_string(), format!("Delete {} and every message in it? (y/n)", folder)))
+        }
+        None => None,
+    };
+    if let Some((title, text)) = prompt {
+        let style = match app.folder_edit {
+            Some(crate::app::FolderEdit::Delete { .. }) => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        };
+        let input = Paragraph::new(text).style(style).block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(input, chunks[1]);
+    }
 }
 
 fn render_settings_mode(f: &mut Frame, app: &App, area: Rect) {
@@ -1072,6 +2815,18 @@ fn render_settings_mode(f: &mut Frame, app: &App, area: Rect) {
             Span::styled("SMTP Server: ", Style::default().fg(Color::Gray)),
             Span::raw(&account.smtp_server),
         ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Personal Dictionary: ", Style::default().fg(Color::Gray)),
+            Span::raw(format!("{} words", app.personal_words().len())),
+        ]),
+        Line::from(vec![
+            Span::styled("List Density: ", Style::default().fg(Color::Gray)),
+            Span::raw(match app.config.ui.density {
+                ListDensity::Compact => "compact, one line per message (l to change)",
+                ListDensity::Comfortable => "comfortable, two lines with a preview (l to change)",
+            }),
+        ]),
     ];
     
     let settings = Paragraph::new(settings_text)
@@ -1082,50 +2837,326 @@ fn render_settings_mode(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(settings, centered_area);
 }
 
+fn render_dictionary_mode(f: &mut Frame, app: &App, area: Rect) {
+    let words = app.personal_words();
+    let items: Vec<ListItem> = if words.is_empty() {
+        vec![ListItem::new("No words yet; add one while composing with Alt+D").style(Style::default().fg(Color::Gray))]
+    } else {
+        words.iter().map(|word| ListItem::new(word.as_str())).collect()
+    };
+
+    let title = format!("Personal Dictionary ({}) - x to remove, Esc to go back", words.len());
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+    let mut state = ratatui::widgets::ListState::default();
+    if !words.is_empty() {
+        state.select(Some(app.selected_dictionary_idx));
+    }
+
+    f.render_stateful_widget(list, centered_rect(60, 80, area), &mut state);
+}
+
+/// Release notes from the last update check, newest first
+fn render_changelog_mode(f: &mut Frame, app: &App, area: Rect) {
+    let mut lines = vec![Line::from(format!("This is tuimail {}", crate::updates::CURRENT_VERSION))];
+    if let Some(version) = &app.update_available {
+        lines.push(Line::styled(
+            format!("Version {} is available", version),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        ));
+    }
+    lines.push(Line::from(""));
+
+    if app.releases.is_empty() {
+        let reason = if app.config.ui.update_check {
+            "No release notes yet; they are looked up once a day"
+        } else {
+            "The update check is off; set \"update_check\": true in the \"ui\" settings to turn it on"
+        };
+        lines.push(Line::styled(reason, Style::default().fg(Color::Gray)));
+    }
+    for release in &app.releases {
+        let mut heading = format!("{} ({})", release.name, release.published);
+        if release.version == crate::updates::CURRENT_VERSION {
+            heading.push_str(" - installed");
+        }
+        lines.push(Line::styled(heading, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+        lines.push(Line::styled(release.url.clone(), Style::default().fg(Color::Gray)));
+        lines.extend(release.notes.lines().map(|line| Line::from(line.to_string())));
+        lines.push(Line::from(""));
+    }
+
+    let changelog = Paragraph::new(lines)
+        .block(Block::default().title("Changelog - ↑↓ to scroll, Esc to go back").borders(Borders::ALL))
+        .wrap(Wrap { trim: false })
+        .scroll((app.changelog_scroll, 0));
+    f.render_widget(changelog, area);
+}
+
 fn render_help_mode(f: &mut Frame, _app: &App, area: Rect) {
     let help_text = vec![
-        Line::from("Email Client Help"),
+        Line::from(tr("help-heading")),
         Line::from(""),
-        Line::from("Global:"),
-        Line::from("  q - Quit (in normal mode)"),
-        Line::from("  ? - Show/hide help"),
+        Line::from(tr("help-section-global")),
+        help_line("q", "help-quit"),
+        help_line("?", "help-toggle-help"),
         Line::from(""),
-        Line::from("Normal Mode:"),
-        Line::from("  c - Compose new email"),
-        Line::from("  r - Refresh emails"),
-        Line::from("  n - Next account (rotate)"),
-        Line::from("  f - Show folder list"),
-        Line::from("  s - Show settings"),
-        Line::from("  ↑/↓ - Navigate emails"),
-        Line::from("  Enter - View selected email"),
-        Line::from("  Delete - Delete selected email"),
+        Line::from(tr("help-section-normal")),
+        help_line("c", "help-compose"),
+        help_line("r", "help-refresh"),
+        help_line("n", "help-next-account"),
+        help_line("f", "help-folders"),
+        help_line("s", "help-settings"),
+        help_line("p", "help-pause-sync"),
+        help_line("P", "help-people"),
+        help_line("A", "help-attachments"),
+        help_line("R", "help-rules"),
+        help_line("D", "help-diagnostics"),
+        help_line("I", "help-importance"),
+        help_line("H", "help-threads"),
+        help_line("m", "help-move"),
+        help_line("Space", "help-mark"),
+        help_line("L", "help-read-later"),
+        help_line("W", "help-drafts"),
+        help_line("U", "help-recently-deleted"),
+        help_line("O", "help-pending-operations"),
+        help_line("T", "help-tags"),
+        help_line("z", "help-remind"),
+        help_line("Z", "help-reminders"),
+        help_line("V", "help-changelog"),
+        help_line("C", "help-cleanup"),
+        help_line("/", "help-search"),
+        help_line("1-9", "help-quick-actions"),
+        help_line("↑/↓", "help-navigate"),
+        help_line("Enter", "help-view"),
+        help_line("Delete", "help-delete-selected"),
         Line::from(""),
-        Line::from("View Email Mode:"),
-        Line::from("  Esc - Return to email list"),
-        Line::from("  r - Reply to email"),
-        Line::from("  a - Reply to all"),
-        Line::from("  f - Forward email"),
-        Line::from("  d - Delete email"),
-        Line::from("  s - Save selected attachment"),
-        Line::from("  Tab - Select next attachment"),
-        Line::from("  ↑↓ - Scroll email content"),
+        Line::from(tr("help-section-view")),
+        help_line("Esc", "help-back"),
+        help_line("r", "help-reply"),
+        help_line("a", "help-reply-all"),
+        help_line("f", "help-forward"),
+        help_line("d", "help-delete"),
+        help_line("b", "help-bounce"),
+        help_line("e", "help-resend"),
+        help_line("i", "help-answer-invitation"),
+        help_line("c", "help-add-to-calendar"),
+        help_line("A", "help-add-contacts"),
+        help_line("B", "help-burst-digest"),
+        help_line("[ ]", "help-digest-step"),
+        help_line("s", "help-save-attachment"),
+        help_line("x", "help-cancel-download"),
+        help_line("h", "help-headers"),
+        help_line("Q", "help-expand-quotes"),
+        help_line("H", "help-html-view"),
+        help_line("R", "help-remote-content"),
+        help_line("w", "help-open-html"),
+        help_line("F", "help-full-message"),
+        help_line("Tab", "help-next-attachment"),
+        help_line("↑↓", "help-scroll"),
         Line::from(""),
-        Line::from("Compose Mode:"),
-        Line::from("  Esc - Cancel"),
-        Line::from("  Ctrl+s - Send email"),
-        Line::from("  Ctrl+a - Add attachment (file browser)"),
-        Line::from("  Ctrl+x - Remove selected attachment"),
-        Line::from("  Tab - Switch between fields"),
+        Line::from(tr("help-section-compose")),
+        help_line("Esc", "help-cancel"),
+        help_line("Ctrl+s", "help-send"),
+        help_line("Ctrl+d", "help-save-draft"),
+        help_line("Ctrl+a", "help-attach"),
+        help_line("Ctrl+x", "help-remove-attachment"),
+        help_line("Tab", "help-switch-fields"),
+        help_line("Enter", "help-lookup-name"),
+        help_line("Alt+m", "help-markdown"),
+        help_line("Alt+i", "help-priority"),
+        help_line("Alt+k", "help-pgp-sign"),
+        help_line("Alt+e", "help-pgp-encrypt"),
+        help_line("Alt+q", "help-quote-split"),
+        help_line("Alt+p", "help-quote-paragraph"),
+        help_line("Alt+x", "help-strip-quote"),
     ];
     
     let help = Paragraph::new(help_text)
-        .block(Block::default().title("Help").borders(Borders::ALL));
+        .block(Block::default().title(tr("help-title")).borders(Borders::ALL));
     
     // Center the help text
     let centered_area = centered_rect(60, 80, area);
     f.render_widget(help, centered_area);
 }
 
+/// One key binding of the help screen, with its translated description
+fn help_line<'a>(keys: &str, description: &str) -> Line<'a> {
+    Line::from(format!("  {} - {}", keys, tr(description)))
+}
+
+fn render_auth_help_mode(f: &mut Frame, app: &App, area: Rect) {
+    let background = Block::default().style(Style::default().bg(Color::Black));
+    f.render_widget(background, area);
+
+    let Some(problem) = &app.auth_problem else {
+        return;
+    };
+    let Some(account) = app.config.accounts.get(problem.account_idx) else {
+        return;
+    };
+
+    let protocol = if problem.smtp { "SMTP (sending)" } else { "IMAP" };
+    let mut lines = vec![
+        Line::from(Span::styled(
+            problem.failure.headline(),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!("{} <{}>, {}", account.name, account.email, protocol)),
+        Line::from(""),
+    ];
+    lines.extend(problem.failure.guidance(account).lines().map(|l| Line::from(l.to_string())));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("Server said: {}", problem.server_message),
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines.push(Line::from(""));
+
+    match &problem.new_password {
+        Some(password) => {
+            lines.push(Line::from(format!("New {} password: {}│", protocol, "*".repeat(password.chars().count()))));
+            lines.push(Line::from("Enter to save and retry, Esc to go back"));
+        }
+        None => lines.push(Line::from("r: Retry | p: Enter a new password | Esc: Close")),
+    }
+
+    let dialog = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Sign-in failed")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red)),
+        )
+        .wrap(Wrap { trim: false })
+        .style(Style::default().bg(Color::Black));
+
+    f.render_widget(dialog, centered_rect(70, 60, area));
+}
+
+fn render_missing_secrets_mode(f: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0), Constraint::Length(3)])
+        .split(area);
+
+    let intro = Paragraph::new(vec![
+        Line::from(Span::styled("Some accounts need attention", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))),
+        Line::from("These passwords could not be read from the keyring; until they are entered the accounts cannot sync or send."),
+    ])
+    .block(Block::default().borders(Borders::ALL))
+    .wrap(Wrap { trim: false });
+    f.render_widget(intro, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .missing_secrets
+        .iter()
+        .enumerate()
+        .map(|(i, secret)| {
+            let account = app.config.accounts.get(secret.account_idx);
+            let name = account.map(|a| format!("{} <{}>", a.name, a.email)).unwrap_or_default();
+            let server = account
+                .map(|a| if secret.smtp { a.smtp_server.clone() } else { a.imap_server.clone() })
+                .unwrap_or_default();
+            let style = if i == app.selected_secret_idx { Style::default().fg(Color::Yellow) } else { Style::default() };
+            ListItem::new(format!(
+                "{:<40} {:<5} {:<28} {}",
+                name,
+                if secret.smtp { "SMTP" } else { "IMAP" },
+                server,
+                secret.reason
+            ))
+            .style(style)
+        })
+        .collect();
+    let mut state = ratatui::widgets::ListState::default();
+    if !app.missing_secrets.is_empty() {
+        state.select(Some(app.selected_secret_idx));
+    }
+    let list = List::new(items)
+        .block(Block::default().title("Missing passwords").borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    f.render_stateful_widget(list, chunks[1], &mut state);
+
+    let prompt = match &app.secret_input {
+        Some(password) => format!("Password: {}│", "*".repeat(password.chars().count())),
+        None => "Enter or p to type the selected password".to_string(),
+    };
+    f.render_widget(Paragraph::new(prompt).block(Block::default().borders(Borders::ALL)), chunks[2]);
+}
+
+fn render_smtp_fallback_confirm_mode(f: &mut Frame, app: &App, area: Rect) {
+    let background = Block::default().style(Style::default().bg(Color::Black));
+    f.render_widget(background, area);
+
+    let Some((account_idx, route)) = &app.smtp_fallback_offer else {
+        return;
+    };
+    let Some(account) = app.config.accounts.get(*account_idx) else {
+        return;
+    };
+    let configured = &crate::transport::smtp_routes(account)[0];
+
+    let lines = vec![
+        Line::from(Span::styled("Email sent", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from(format!("{} could not be reached on {}.", account.smtp_server, configured)),
+        Line::from(format!("The message went out via {} instead.", route)),
+        Line::from(""),
+        Line::from(format!("Use {} for {} from now on?", route, account.email)),
+        Line::from(""),
+        Line::from("y: Save to account settings | n/Esc: Keep the current settings"),
+    ];
+    let dialog = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("SMTP settings")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .wrap(Wrap { trim: false })
+        .style(Style::default().bg(Color::Black));
+
+    f.render_widget(dialog, centered_rect(60, 40, area));
+}
+
+fn render_reply_warning_mode(f: &mut Frame, app: &App, area: Rect) {
+    render_view_email_mode(f, app, area);
+
+    let Some(crate::reply_check::PendingReply { all, warnings, recipients }) = &app.reply_warning else {
+        return;
+    };
+    let mut lines = vec![Line::from("")];
+    lines.extend(warnings.iter().map(|warning| {
+        Line::from(Span::styled(format!("⚠ {}", warning), Style::default().fg(Color::Yellow)))
+    }));
+    if !recipients.is_empty() {
+        lines.push(Line::from(""));
+        lines.extend(recipients.iter().map(|(recipient, outside)| {
+            let style = if *outside { Style::default().fg(Color::Red) } else { Style::default() };
+            Line::from(Span::styled(format_address_list(std::slice::from_ref(recipient)), style))
+        }));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(if *all { "y: Reply to all anyway | n/Esc: Cancel" } else { "y: Reply anyway | n/Esc: Cancel" }));
+
+    let dialog_area = if recipients.is_empty() { centered_rect(60, 30, area) } else { centered_rect(70, 70, area) };
+    f.render_widget(Clear, dialog_area);
+    let dialog = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Reply?")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: false })
+        .style(Style::default().bg(Color::Black));
+    f.render_widget(dialog, dialog_area);
+}
+
 fn render_delete_confirm_mode(f: &mut Frame, _app: &App, area: Rect) {
     // DO NOT render normal mode in background - that's what makes it transparent!
     // Instead, render a solid background across the entire area first
@@ -1179,48 +3210,100 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     // Show current account and folder
     if let Some(account_data) = app.accounts.get(&app.current_account_idx) {
         if !account_data.folders.is_empty() {
-            text.push_str(&format!("Folder: {} | ", account_data.folders[account_data.selected_folder_idx]));
+            let folder = &account_data.folders[account_data.selected_folder_idx];
+            text.push_str(&format!("{} | ", tr_args("status-folder", &[("folder", folder)])));
         }
     }
     
     // Show email count
-    text.push_str(&format!("Emails: {} | ", app.emails.len()));
+    text.push_str(&format!("{} | ", tr_args("status-emails", &[("count", &(app.emails.len() + app.unloaded_email_count()))])));
     
     // Add account info if multiple accounts
     if app.config.accounts.len() > 1 {
+        let unknown = tr("status-unknown-account");
         let account_name = if app.current_account_idx < app.config.accounts.len() {
             &app.config.accounts[app.current_account_idx].name
         } else {
-            "Unknown"
+            &unknown
         };
-        text.push_str(&format!("Account: {} ({}/{}) | ", 
-            account_name,
-            app.current_account_idx + 1, 
-            app.config.accounts.len()));
+        let account = tr_args("status-account", &[
+            ("name", account_name),
+            ("index", &(app.current_account_idx + 1)),
+            ("total", &app.config.accounts.len()),
+        ]);
+        text.push_str(&format!("{} | ", account));
     }
     
+    if crate::email::is_read_only() {
+        text.push_str(&format!("{} | ", tr("status-read-only")));
+    }
+    let account_email = app.config.accounts.get(app.current_account_idx).map(|a| a.email.as_str()).unwrap_or_default();
+    if crate::email::is_account_offline(account_email) {
+        text.push_str(&format!("{} | ", tr("status-offline")));
+    }
+    if let Some(free) = app.disk_space_low {
+        let free = format_size(free as usize);
+        text.push_str(&format!("{} | ", tr_args("status-disk-low", &[("free", &free)])));
+    }
+
     // Show sync status
-    if app.is_syncing {
-        text.push_str("Syncing... | ");
+    if app.is_sync_paused() {
+        text.push_str(&format!("{} | ", tr("status-sync-paused")));
+    } else if app.is_syncing {
+        text.push_str(&format!("{} | ", tr("status-syncing")));
     } else if let Some(last_sync) = app.last_sync {
-        text.push_str(&format!("Last sync: {} | ", last_sync.format("%H:%M:%S")));
+        let time = last_sync.format("%H:%M:%S");
+        text.push_str(&format!("{} | ", tr_args("status-last-sync", &[("time", &time)])));
+    }
+    if let Some(version) = &app.update_available {
+        text.push_str(&format!("{} | ", tr_args("status-update-available", &[("version", version)])));
     }
     
     // Show current mode and help
-    match app.mode {
-        AppMode::Normal => text.push_str("Press 'r' to refresh, 'n' for next account, 'f' for folders, 'c' to compose, '?' for help"),
-        AppMode::FolderList => text.push_str("Use ↑↓ to navigate folders, Enter to select, Esc to cancel"),
-        AppMode::Compose => text.push_str("Tab to switch fields, Ctrl+S to send, Esc to cancel"),
-        AppMode::ViewEmail => text.push_str("r=Reply, a=Reply All, f=Forward, d=Delete, ↑↓=Scroll, Esc=Back"),
-        AppMode::DeleteConfirm => text.push_str("Delete email? Press 'y' to confirm, 'n' or Esc to cancel"),
-        _ => text.push_str(&format!("Mode: {:?}", app.mode)),
-    }
+    let hint = match app.mode {
+        AppMode::Normal if app.tag_input.is_some() => tr("hint-tags"),
+        AppMode::Normal => tr("hint-normal"),
+        AppMode::FolderList if app.folder_edit.is_some() => tr("hint-folder-edit"),
+        AppMode::FolderList => tr("hint-folder-list"),
+        AppMode::Compose => tr("hint-compose"),
+        AppMode::ViewEmail => tr("hint-view-email"),
+        AppMode::DeleteConfirm => tr("hint-delete-confirm"),
+        AppMode::ReplyWarning => tr("hint-reply-warning"),
+        AppMode::SmtpFallbackConfirm => tr("hint-smtp-fallback"),
+        AppMode::People => tr("hint-people"),
+        AppMode::Capabilities => tr("hint-capabilities"),
+        AppMode::MissingSecrets if app.secret_input.is_some() => tr("hint-missing-secrets-typing"),
+        AppMode::MissingSecrets => tr("hint-missing-secrets"),
+        AppMode::ImapConsole => tr("hint-imap-console"),
+        AppMode::Cleanup => match app.cleanup_confirm {
+            Some(crate::cleanup::CleanupAction::Delete) => tr("hint-cleanup-confirm-delete"),
+            Some(crate::cleanup::CleanupAction::Archive) => {
+                tr_args("hint-cleanup-confirm-archive", &[("folder", &app.archive_folder())])
+            }
+            None => tr("hint-cleanup"),
+        },
+        AppMode::Drafts => tr("hint-drafts"),
+        AppMode::DraftConflict => tr("hint-draft-conflict"),
+        AppMode::Attachments => tr("hint-attachments"),
+        AppMode::RulesReport => tr("hint-rules-report"),
+        AppMode::AuthHelp => tr("hint-auth-help"),
+        AppMode::Diagnostics => tr("hint-diagnostics"),
+        AppMode::AccountSettings => tr("hint-account-settings"),
+        AppMode::Dictionary => tr("hint-dictionary"),
+        AppMode::RecentlyDeleted => tr("hint-recently-deleted"),
+        AppMode::PendingOperations if app.operation_target_input.is_some() => tr("hint-operation-target"),
+        AppMode::PendingOperations => tr("hint-pending-operations"),
+        AppMode::Reminders => tr("hint-reminders"),
+        AppMode::Changelog => tr("hint-changelog"),
+        _ => tr_args("status-mode", &[("mode", &format!("{:?}", app.mode))]),
+    };
+    text.push_str(&hint);
     
     // Show error or info message if present (override other text)
     if let Some(error) = &app.error_message {
-        text = format!("ERROR: {}", error);
+        text = tr_args("status-error", &[("message", error)]);
     } else if let Some(info) = &app.info_message {
-        text = format!("INFO: {}", info);
+        text = tr_args("status-info", &[("message", info)]);
     }
     
     let status = Paragraph::new(text)
@@ -1467,3 +3550,67 @@ fn create_text_without_cursor(text: &str) -> ratatui::text::Text<'static> {
     
     ratatui::text::Text::from(result_lines)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_list_window_follows_the_selection() {
+        // Fewer rows than fit, or nothing to show
+        assert_eq!(email_list_window(5, Some(3), 10, 8), 0);
+        assert_eq!(email_list_window(0, None, 0, 100), 0);
+
+        // Selection at the top and bottom of the folder
+        assert_eq!(email_list_window(0, Some(0), 10, 100), 0);
+        assert_eq!(email_list_window(40, Some(0), 10, 100), 0);
+        assert_eq!(email_list_window(0, Some(99), 10, 100), 90);
+
+        // The offset stays put while the selection is on screen, and moves one row at a time
+        // when it steps past an edge
+        assert_eq!(email_list_window(20, Some(25), 10, 100), 20);
+        assert_eq!(email_list_window(20, Some(30), 10, 100), 21);
+        assert_eq!(email_list_window(20, Some(19), 10, 100), 19);
+
+        // A taller terminal never leaves empty rows below the last message, and a shorter
+        // one keeps the selection in view
+        assert_eq!(email_list_window(90, Some(95), 20, 100), 80);
+        assert_eq!(email_list_window(20, Some(29), 5, 100), 25);
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn email_header_lines_collapse_to_one() {
+        let address = |address: &str| crate::email::EmailAddress { name: None, address: address.to_string() };
+        let mut email = Email::new();
+        email.from = vec![address("alice@example.com")];
+        email.to = vec![address("bob@example.com"), address("carol@example.com")];
+        email.cc = vec![address("dave@example.com")];
+        email.subject = "Plan".to_string();
+        email.headers.insert("List-Id".to_string(), "<team.example.com>".to_string());
+        let ui = UIConfig { avatars: false, ..UIConfig::default() };
+
+        let collapsed = email_header_lines(&ui, false, &email);
+        assert_eq!(collapsed.len(), 1);
+        assert!(text(&collapsed[0]).contains("alice@example.com — Plan — "));
+
+        let expanded: Vec<String> = email_header_lines(&ui, true, &email).iter().map(text).collect();
+        assert_eq!(expanded[0], "From: alice@example.com");
+        assert_eq!(expanded[1], "To: bob@example.com, carol@example.com");
+        assert_eq!(expanded[2], "Cc: dave@example.com");
+        assert_eq!(expanded[3], "Subject: Plan");
+        assert!(expanded[4].starts_with("Date: "));
+        assert_eq!(expanded.last().map(String::as_str), Some("List-Id: <team.example.com>"));
+    }
+
+    #[test]
+    fn wrapped_height_counts_screen_rows() {
+        let lines = vec![Line::from("x".repeat(25)), Line::from(""), Line::from("short")];
+        assert_eq!(wrapped_height(&lines, 10), 5);
+        assert_eq!(wrapped_height(&lines, 80), 3);
+        assert_eq!(wrapped_height(&lines, 0), 31);
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Builds for distribution packages (the `distro` feature) are updated by the package
+/// manager, so they never look for releases
+pub const ENABLED: bool = !cfg!(feature = "distro");
+
+/// The version of this build
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Releases are looked up at most this often
+pub const CHECK_INTERVAL_SECS: i64 = 24 * 60 * 60;
+
+const RELEASES_HOST: &str = "api.github.com";
+const RELEASES_PATH: &str = "/repos/debasish-raychawdhuri/tuimail/releases?per_page=20";
+
+/// A published release and its notes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Release {
+    /// Version without the leading `v` of the tag, such as "0.2.0"
+    pub version: String,
+    pub name: String,
+    pub notes: String,
+    /// Publication date as `YYYY-MM-DD`
+    pub published: String,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    name: Option<String>,
+    body: Option<String>,
+    published_at: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+/// Whether the last check, at `last_checked`, is old enough to ask again
+pub fn check_due(last_checked: Option<i64>, now: i64) -> bool {
+    last_checked.is_none_or(|checked| now - checked >= CHECK_INTERVAL_SECS)
+}
+
+/// The published releases on GitHub, newest first
+pub fn fetch_releases() -> Result<Vec<Release>> {
+    let (status, body) = crate::http::https_get(RELEASES_HOST, RELEASES_PATH)?;
+    if status != 200 {
+        return Err(anyhow!("release lookup returned HTTP {}", status));
+    }
+    parse_releases(&body)
+}
+
+/// Releases from the GitHub API's JSON, leaving out drafts and pre-releases
+pub fn parse_releases(json: &[u8]) -> Result<Vec<Release>> {
+    let releases: Vec<GithubRelease> = serde_json::from_slice(json)?;
+    let mut releases: Vec<Release> = releases
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter(|release| parse_version(&release.tag_name).is_some())
+        .map(|release| {
+            let version = release.tag_name.trim_start_matches(['v', 'V']).to_string();
+            Release {
+                name: release.name.filter(|name| !name.trim().is_empty()).unwrap_or_else(|| format!("tuimail {}", version)),
+                version,
+                notes: release.body.unwrap_or_default().replace("\r\n", "\n"),
+                published: release.published_at.unwrap_or_default().chars().take(10).collect(),
+                url: release.html_url,
+            }
+        })
+        .collect();
+    releases.sort_by_key(|release| std::cmp::Reverse(parse_version(&release.version)));
+    Ok(releases)
+}
+
+/// `major.minor.patch` of a version or tag such as "v1.2.3"; a missing patch counts as 0
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.trim().trim_start_matches(['v', 'V']).split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = match parts.next() {
+        Some(patch) => patch.parse().ok()?,
+        None => 0,
+    };
+    Some((major, minor, patch))
+}
+
+/// The newest release if it is newer than `current`
+pub fn available_update<'a>(releases: &'a [Release], current: &str) -> Option<&'a Release> {
+    let current = parse_version(current)?;
+    releases.first().filter(|release| parse_version(&release.version).is_some_and(|version| version > current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"[
+        {"tag_name": "v0.3.0-rc1", "name": "RC", "body": "", "published_at": "2026-09-01T00:00:00Z", "html_url": "https://example.com/rc", "prerelease": true},
+        {"tag_name": "v0.1.0", "name": "", "body": "First", "published_at": "2026-01-02T10:00:00Z", "html_url": "https://example.com/1"},
+        {"tag_name": "v0.2.0", "name": "Spring", "body": "- Fixes\r\n- More", "published_at": "2026-05-03T10:00:00Z", "html_url": "https://example.com/2"},
+        {"tag_name": "nightly", "name": null, "body": null, "published_at": null, "html_url": "https://example.com/n"}
+    ]"#;
+
+    #[test]
+    fn test_parse_releases() {
+        let releases = parse_releases(JSON.as_bytes()).unwrap();
+        let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+        assert_eq!(versions, vec!["0.2.0", "0.1.0"]);
+        assert_eq!(releases[0].notes, "- Fixes\n- More");
+        assert_eq!(releases[0].published, "2026-05-03");
+        assert_eq!(releases[1].name, "tuimail 0.1.0");
+        assert!(parse_releases(b"{\"message\": \"rate limited\"}").is_err());
+    }
+
+    #[test]
+    fn test_available_update() {
+        let releases = parse_releases(JSON.as_bytes()).unwrap();
+        assert_eq!(available_update(&releases, "0.1.0").map(|r| r.version.as_str()), Some("0.2.0"));
+        assert!(available_update(&releases, "0.2.0").is_none());
+        assert!(available_update(&releases, "0.10.0").is_none());
+        assert!(available_update(&[], "0.1.0").is_none());
+        assert_eq!(parse_version("v1.2"), Some((1, 2, 0)));
+    }
+
+    #[test]
+    fn test_check_due() {
+        assert!(check_due(None, 1000));
+        assert!(!check_due(Some(1000), 1000 + CHECK_INTERVAL_SECS - 1));
+        assert!(check_due(Some(1000), 1000 + CHECK_INTERVAL_SECS));
+    }
+}
@@ -0,0 +1,97 @@
+use base64::alphabet::Alphabet;
+use base64::engine::general_purpose::NO_PAD;
+use base64::engine::GeneralPurpose;
+use base64::Engine;
+
+/// Base64 with `,` in place of `/`
+fn engine() -> GeneralPurpose {
+    let alphabet = Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+,")
+        .expect("valid base64 alphabet");
+    GeneralPurpose::new(&alphabet, NO_PAD)
+}
+
+/// A folder name in IMAP's modified UTF-7 (RFC 3501 section 5.1.3), the form it is sent
+/// to the server in: printable ASCII as is, `&` as `&-`, and runs of other characters as
+/// UTF-16 in base64 between `&` and `-`. Everywhere else folder names are kept decoded.
+pub fn encode(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    let mut pending: Vec<u16> = Vec::new();
+    let flush = |pending: &mut Vec<u16>, encoded: &mut String| {
+        if pending.is_empty() {
+            return;
+        }
+        let bytes: Vec<u8> = pending.iter().flat_map(|unit| unit.to_be_bytes()).collect();
+        encoded.push('&');
+        encoded.push_str(&engine().encode(bytes));
+        encoded.push('-');
+        pending.clear();
+    };
+    for c in name.chars() {
+        if (' '..='~').contains(&c) {
+            flush(&mut pending, &mut encoded);
+            if c == '&' {
+                encoded.push_str("&-");
+            } else {
+                encoded.push(c);
+            }
+        } else {
+            pending.extend(c.encode_utf16(&mut [0; 2]).iter());
+        }
+    }
+    flush(&mut pending, &mut encoded);
+    encoded
+}
+
+/// A folder name as the server lists it, decoded for display. Names that are not valid
+/// modified UTF-7 are returned unchanged, so they can still be selected.
+pub fn decode(name: &str) -> String {
+    let mut decoded = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let Some(length) = rest[start + 1..].find('-') else {
+            return name.to_string();
+        };
+        let shifted = &rest[start + 1..start + 1 + length];
+        if shifted.is_empty() {
+            decoded.push('&');
+        } else {
+            let Ok(bytes) = engine().decode(shifted) else {
+                return name.to_string();
+            };
+            if bytes.len() % 2 != 0 {
+                return name.to_string();
+            }
+            let units: Vec<u16> = bytes.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+            let Ok(text) = String::from_utf16(&units) else {
+                return name.to_string();
+            };
+            decoded.push_str(&text);
+        }
+        rest = &rest[start + 2 + length..];
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for (decoded, encoded) in [
+            ("Entwürfe", "Entw&APw-rfe"),
+            ("Tom & Jerry", "Tom &- Jerry"),
+            ("台北", "&U,BTFw-"),
+            ("INBOX/Gesendete Objekte", "INBOX/Gesendete Objekte"),
+            ("📁 Projects", "&2D3cwQ- Projects"),
+        ] {
+            assert_eq!(encode(decoded), encoded);
+            assert_eq!(decode(encoded), decoded);
+        }
+        // Not modified UTF-7: left alone rather than mangled
+        assert_eq!(decode("R&D"), "R&D");
+        assert_eq!(decode("a&!!-b"), "a&!!-b");
+    }
+}
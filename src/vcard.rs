@@ -0,0 +1,220 @@
+use serde::Serialize;
+
+use crate::calendar::{fold, parse_line, unescape, unfold};
+use crate::email::{Email, EmailAttachment};
+
+/// A person from a vCard (RFC 6350, and the older 2.1 and 3.0), with what the address
+/// book keeps of it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Card {
+    pub name: Option<String>,
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    pub organization: Option<String>,
+}
+
+/// Whether an attachment is a contact card
+pub fn is_vcard(attachment: &EmailAttachment) -> bool {
+    let filename = attachment.filename.to_lowercase();
+    matches!(attachment.content_type.as_str(), "text/vcard" | "text/x-vcard" | "text/directory")
+        || filename.ends_with(".vcf")
+        || filename.ends_with(".vcard")
+}
+
+/// The cards attached to `email` that have an email address
+pub fn attached_cards(email: &Email) -> Vec<Card> {
+    email
+        .attachments
+        .iter()
+        .filter(|attachment| is_vcard(attachment))
+        .filter_map(|attachment| attachment.load_data().ok())
+        .flat_map(|data| parse(&String::from_utf8_lossy(&data)))
+        .filter(|card| !card.emails.is_empty())
+        .collect()
+}
+
+/// Split a structured value such as `N` or `ORG` at the semicolons that are not escaped
+fn components(value: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        let part = parts.last_mut().expect("never empty");
+        match c {
+            ';' if !escaped => parts.push(String::new()),
+            _ => {
+                escaped = c == '\\' && !escaped;
+                part.push(c);
+            }
+        }
+    }
+    parts.iter().map(|part| unescape(part).trim().to_string()).collect()
+}
+
+/// Decode a vCard 2.1 quoted-printable value, read as UTF-8
+fn decode_quoted_printable(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'=', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The lines of `text` unfolded, with quoted-printable values that go on after a
+/// trailing `=` joined too
+fn content_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in unfold(text) {
+        match lines.last_mut() {
+            Some(last) if last.ends_with('=') && last.to_ascii_uppercase().contains("QUOTED-PRINTABLE") => {
+                last.pop();
+                last.push_str(&line);
+            }
+            _ => lines.push(line),
+        }
+    }
+    lines
+}
+
+/// The cards in a `.vcf` file, in order. Lines outside `BEGIN:VCARD`/`END:VCARD` and
+/// properties the address book has no use for are ignored.
+pub fn parse(text: &str) -> Vec<Card> {
+    let mut cards = Vec::new();
+    let mut card: Option<Card> = None;
+    // `N` as "Given Family", for cards without `FN`
+    let mut structured_name = None;
+    for line in content_lines(text) {
+        let Some(property) = parse_line(&line) else {
+            continue;
+        };
+        // Grouped properties, as in `item1.EMAIL`
+        let name = property.name.rsplit('.').next().unwrap_or_default();
+        let value = if property.param("ENCODING").is_some_and(|e| e.eq_ignore_ascii_case("QUOTED-PRINTABLE")) {
+            decode_quoted_printable(&property.value)
+        } else {
+            property.value.clone()
+        };
+        match (name, card.as_mut()) {
+            ("BEGIN", _) if value.trim().eq_ignore_ascii_case("VCARD") => {
+                card = Some(Card::default());
+                structured_name = None;
+            }
+            ("END", Some(_)) if value.trim().eq_ignore_ascii_case("VCARD") => {
+                let mut done = card.take().expect("checked above");
+                if done.name.is_none() {
+                    done.name = structured_name.take();
+                }
+                cards.push(done);
+            }
+            ("FN", Some(card)) => card.name = Some(unescape(&value).trim().to_string()).filter(|name| !name.is_empty()),
+            ("N", Some(_)) => {
+                let parts = components(&value);
+                let given = parts.get(1).map(String::as_str).unwrap_or_default();
+                let family = parts.first().map(String::as_str).unwrap_or_default();
+                let joined = format!("{} {}", given, family).trim().to_string();
+                structured_name = Some(joined).filter(|name| !name.is_empty());
+            }
+            ("EMAIL", Some(card)) => {
+                let address = unescape(&value).trim().trim_start_matches("mailto:").to_string();
+                if address.contains('@') && !card.emails.iter().any(|known| known.eq_ignore_ascii_case(&address)) {
+                    card.emails.push(address);
+                }
+            }
+            ("TEL", Some(card)) => {
+                let phone = unescape(&value).trim().trim_start_matches("tel:").to_string();
+                if !phone.is_empty() {
+                    card.phones.push(phone);
+                }
+            }
+            ("ORG", Some(card)) => {
+                card.organization = components(&value).into_iter().next().filter(|organization| !organization.is_empty())
+            }
+            _ => {}
+        }
+    }
+    cards
+}
+
+/// Escape a TEXT value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace("\r\n", "\\n").replace('\n', "\\n")
+}
+
+/// `cards` as a vCard 3.0 file, which every address book reads
+pub fn write(cards: &[Card]) -> String {
+    let mut text = String::new();
+    for card in cards {
+        let name = card.name.clone().or_else(|| card.emails.first().cloned()).unwrap_or_default();
+        // `N` is required in 3.0; the last word is taken for the family name
+        let (given, family) = match card.name.as_deref().and_then(|name| name.trim().rsplit_once(' ')) {
+            Some((given, family)) => (given, family),
+            None => ("", card.name.as_deref().unwrap_or_default()),
+        };
+        text.push_str("BEGIN:VCARD\r\nVERSION:3.0\r\n");
+        text.push_str(&fold(&format!("FN:{}", escape(&name))));
+        text.push_str(&fold(&format!("N:{};{};;;", escape(family), escape(given))));
+        for address in &card.emails {
+            text.push_str(&fold(&format!("EMAIL;TYPE=INTERNET:{}", escape(address))));
+        }
+        for phone in &card.phones {
+            text.push_str(&fold(&format!("TEL:{}", escape(phone))));
+        }
+        if let Some(organization) = &card.organization {
+            text.push_str(&fold(&format!("ORG:{}", escape(organization))));
+        }
+        text.push_str("END:VCARD\r\n");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_are_read_from_all_versions() {
+        let text = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Ann Smith\r\nN:Smith;Ann;;;\r\nitem1.EMAIL;TYPE=work:ann@example.com\r\n\
+                    EMAIL:mailto:ANN@example.com\r\nEMAIL:ann.smith@example.org\r\nTEL;VALUE=uri:tel:+1-555-0100\r\n\
+                    ORG:Example\\, Inc.;Research\r\nNOTE:A long note that is folded\r\n  over two lines\r\nEND:VCARD\r\n\
+                    BEGIN:VCARD\r\nVERSION:2.1\r\nN;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:M=C3=BCller;J=C3=\r\n=BCrgen;;;\r\n\
+                    EMAIL;INTERNET:jm@example.de\r\nEND:VCARD\r\n";
+        let cards = parse(text);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].name.as_deref(), Some("Ann Smith"));
+        assert_eq!(cards[0].emails, vec!["ann@example.com", "ann.smith@example.org"]);
+        assert_eq!(cards[0].phones, vec!["+1-555-0100"]);
+        assert_eq!(cards[0].organization.as_deref(), Some("Example, Inc."));
+        assert_eq!(cards[1].name.as_deref(), Some("Jürgen Müller"));
+        assert_eq!(cards[1].emails, vec!["jm@example.de"]);
+    }
+
+    #[test]
+    fn written_cards_read_back() {
+        let cards = vec![
+            Card {
+                name: Some("Ann; \"Annie\" Smith".to_string()),
+                emails: vec!["ann@example.com".to_string()],
+                phones: vec!["+1 555 0100".to_string()],
+                organization: Some("Example, Inc.".to_string()),
+            },
+            Card { name: None, emails: vec!["bob@example.net".to_string()], ..Card::default() },
+        ];
+        let text = write(&cards);
+        assert!(text.contains("N:Smith;Ann\\; \"Annie\";;;\r\n"));
+        let mut read = parse(&text);
+        assert_eq!(read[1].name.as_deref(), Some("bob@example.net"));
+        read[1].name = None;
+        assert_eq!(read, cards);
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+
+use chrono::NaiveTime;
+
+use crate::config::NotificationConfig;
+use crate::email::Email;
+
+/// Whether `email` was sent by one of `vips` (lowercase addresses)
+pub fn is_vip(email: &Email, vips: &HashSet<String>) -> bool {
+    email.from.iter().any(|from| vips.contains(&from.address.to_lowercase()))
+}
+
+/// Whether `time` falls in the configured quiet hours. The start is included and the end
+/// is not; a start later than the end spans midnight. Unset or unparsable hours are never quiet.
+pub fn in_quiet_hours(config: &NotificationConfig, time: NaiveTime) -> bool {
+    let parse = |value: &Option<String>| value.as_deref().and_then(|v| NaiveTime::parse_from_str(v.trim(), "%H:%M").ok());
+    let (Some(start), Some(end)) = (parse(&config.quiet_hours_start), parse(&config.quiet_hours_end)) else {
+        return false;
+    };
+    if start <= end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// The notice for newly arrived mail: VIP mail is named separately and is the only mail
+/// announced during quiet hours. A single message is shown with its subject and stored
+/// preview. `None` when there is nothing to announce.
+pub fn new_mail_notice(new_emails: &[&Email], vips: &HashSet<String>, quiet: bool) -> Option<String> {
+    if let [email] = new_emails {
+        let vip = is_vip(email, vips);
+        if quiet && !vip {
+            return None;
+        }
+        let sender = email.from.first().map(|from| from.name.clone().filter(|name| !name.is_empty()).unwrap_or_else(|| from.address.clone()));
+        let mut notice = format!("New mail from {}{}: {}", if vip { "VIP " } else { "" }, sender.unwrap_or_default(), email.subject);
+        if let Some(snippet) = email.snippet.as_deref().filter(|snippet| !snippet.is_empty()) {
+            notice.push_str(" — ");
+            notice.push_str(snippet);
+        }
+        return Some(notice);
+    }
+    let vip_senders: Vec<String> = new_emails
+        .iter()
+        .filter(|email| is_vip(email, vips))
+        .filter_map(|email| email.from.first())
+        .map(|from| from.name.clone().filter(|name| !name.is_empty()).unwrap_or_else(|| from.address.clone()))
+        .fold(Vec::new(), |mut senders, sender| {
+            if !senders.contains(&sender) {
+                senders.push(sender);
+            }
+            senders
+        });
+
+    match (vip_senders.is_empty(), quiet) {
+        (false, _) => Some(format!("New mail from VIP {} ({} new emails)", vip_senders.join(", "), new_emails.len())),
+        (true, false) if !new_emails.is_empty() => Some(format!("Found {} new emails", new_emails.len())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailAddress;
+
+    fn email(name: &str, address: &str) -> Email {
+        let mut email = Email::new();
+        email.from = vec![EmailAddress { name: Some(name.to_string()), address: address.to_string() }];
+        email
+    }
+
+    #[test]
+    fn test_quiet_hours_and_notice() {
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let overnight = NotificationConfig { quiet_hours_start: Some("22:00".to_string()), quiet_hours_end: Some("07:30".to_string()) };
+        assert!(in_quiet_hours(&overnight, at(23, 15)));
+        assert!(in_quiet_hours(&overnight, at(6, 0)));
+        assert!(!in_quiet_hours(&overnight, at(7, 30)));
+        assert!(!in_quiet_hours(&overnight, at(12, 0)));
+        let lunch = NotificationConfig { quiet_hours_start: Some("12:00".to_string()), quiet_hours_end: Some("13:00".to_string()) };
+        assert!(in_quiet_hours(&lunch, at(12, 30)));
+        assert!(!in_quiet_hours(&lunch, at(23, 0)));
+        assert!(!in_quiet_hours(&NotificationConfig::default(), at(23, 0)));
+
+        let vips: HashSet<String> = ["boss@example.com".to_string()].into_iter().collect();
+        let boss = email("The Boss", "Boss@Example.com");
+        let other = email("", "news@example.com");
+        assert!(is_vip(&boss, &vips));
+        assert!(!is_vip(&other, &vips));
+
+        assert_eq!(new_mail_notice(&[&other, &other], &vips, false).as_deref(), Some("Found 2 new emails"));
+        assert_eq!(new_mail_notice(&[&other], &vips, true), None);
+        let mut single = boss.clone();
+        single.subject = "Budget".to_string();
+        single.snippet = Some("Can you look at the numbers before Friday?".to_string());
+        assert_eq!(
+            new_mail_notice(&[&single], &vips, true).as_deref(),
+            Some("New mail from VIP The Boss: Budget — Can you look at the numbers before Friday?")
+        );
+        assert_eq!(new_mail_notice(&[&other], &vips, false).as_deref(), Some("New mail from news@example.com: "));
+        assert_eq!(
+            new_mail_notice(&[&boss, &other, &boss], &vips, true).as_deref(),
+            Some("New mail from VIP The Boss (3 new emails)")
+        );
+        assert_eq!(new_mail_notice(&[], &vips, false), None);
+    }
+}
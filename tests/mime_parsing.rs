@@ -0,0 +1,193 @@
+use std::path::Path;
+
+use proptest::prelude::*;
+use tuimail::config::Priority;
+use tuimail::email::{Email, EmailAddress, EmailAttachment};
+use tuimail::mime::{attachment_from_part, parse_message, sanitize_filename};
+use tuimail::transport::build_message;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mime").join(name);
+    std::fs::read(&path).unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e))
+}
+
+fn parse(raw: &[u8]) -> Email {
+    let parsed = parse_message(raw).expect("fixture parses");
+    Email::from_parsed_email(&parsed, "1", "INBOX", Vec::new()).expect("email builds")
+}
+
+fn names(email: &Email) -> Vec<&str> {
+    email.attachments.iter().map(|a| a.filename.as_str()).collect()
+}
+
+#[test]
+fn corpus_parses_without_panicking() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mime");
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let raw = std::fs::read(entry.unwrap().path()).unwrap();
+        let email = parse(&raw);
+        assert!(!email.from.is_empty());
+        for attachment in &email.attachments {
+            assert_eq!(attachment.filename, sanitize_filename(&attachment.filename));
+            assert!(!attachment.filename.is_empty());
+            assert!(!attachment.data.is_empty());
+        }
+        count += 1;
+    }
+    assert!(count >= 7);
+}
+
+#[test]
+fn rfc2231_filenames_are_decoded() {
+    let email = parse(&fixture("rfc2231_filenames.eml"));
+    assert_eq!(names(&email), vec!["€ rates 2024.pdf", "Quarterly report ü.pdf"]);
+    assert!(email.attachments.iter().all(|a| a.content_type == "application/pdf"));
+    assert!(email.attachments[0].data.starts_with(b"%PDF"));
+}
+
+#[test]
+fn nested_multiparts_keep_bodies_and_attachments_apart() {
+    let email = parse(&fixture("nested_multipart.eml"));
+    assert_eq!(email.body_text.as_deref().map(str::trim), Some("Plain body"));
+    assert!(email.body_html.as_deref().unwrap_or_default().contains("HTML body"));
+
+    assert_eq!(names(&email), vec!["image.png", "bundle.zip"]);
+    assert_eq!(email.attachments[0].content_type, "image/png");
+    assert_eq!(email.attachments[1].data, b"PK\x03\x04fake zip");
+}
+
+#[test]
+fn tnef_is_kept_as_winmail_dat() {
+    let email = parse(&fixture("tnef.eml"));
+    assert_eq!(names(&email), vec!["winmail.dat"]);
+    assert_eq!(email.attachments[0].content_type, "application/ms-tnef");
+}
+
+#[test]
+fn bad_charsets_degrade_gracefully() {
+    let email = parse(&fixture("bad_charset.eml"));
+    assert!(email.body_text.as_deref().unwrap_or_default().contains("Caf"));
+    assert_eq!(names(&email), vec!["menu.txt"]);
+    assert_eq!(email.attachments[0].content_type, "text/plain");
+    assert_eq!(String::from_utf8_lossy(&email.attachments[0].data).trim(), "Crème brûlée");
+}
+
+#[test]
+fn forwarded_message_becomes_eml_attachment() {
+    let email = parse(&fixture("forwarded_message.eml"));
+    assert_eq!(names(&email), vec!["message.eml"]);
+    assert_eq!(email.attachments[0].content_type, "message/rfc822");
+    assert!(String::from_utf8_lossy(&email.attachments[0].data).contains("Subject: Hello"));
+}
+
+#[test]
+fn unsafe_and_missing_filenames_are_replaced() {
+    let email = parse(&fixture("unsafe_filenames.eml"));
+    assert_eq!(names(&email), vec!["bashrc", "document.pdf"]);
+}
+
+#[test]
+fn inline_text_parts_are_not_attachments() {
+    let email = parse(&fixture("inline_text_parts.eml"));
+    assert_eq!(names(&email), vec!["numbers.csv"]);
+    assert_eq!(email.attachments[0].content_type, "text/csv");
+}
+
+#[test]
+fn delivery_failures_are_read_from_bounces() {
+    let email = parse(&fixture("dsn_bounce.eml"));
+    let report = tuimail::dsn::parse(&email).expect("a bounce");
+    assert_eq!(report.failures.len(), 1);
+    let failure = &report.failures[0];
+    assert_eq!((failure.recipient.as_str(), failure.status.as_str()), ("bob@exmaple.com", "5.1.1"));
+    assert!(failure.reason.starts_with("550 5.1.1") && failure.reason.ends_with("virtual mailbox table"));
+    assert_eq!(report.original_message_id.as_deref(), Some("report-42@example.com"));
+    assert_eq!(report.original_subject.as_deref(), Some("Quarterly report"));
+
+    assert!(tuimail::dsn::parse(&parse(&fixture("forwarded_message.eml"))).is_none());
+}
+
+#[test]
+fn priority_headers_are_recognised() {
+    let priority = |headers: &str| parse(format!("From: a@example.com\r\n{}\r\n\r\nHi\r\n", headers).as_bytes()).priority();
+    assert_eq!(priority("X-Priority: 1 (Highest)"), Some(Priority::High));
+    assert_eq!(priority("X-Priority: 5"), Some(Priority::Low));
+    assert_eq!(priority("Importance: High"), Some(Priority::High));
+    assert_eq!(priority("Priority: non-urgent"), Some(Priority::Low));
+    assert_eq!(priority("X-MSMail-Priority: Normal"), Some(Priority::Normal));
+    assert_eq!(priority("Subject: plain"), None);
+}
+
+fn sent_with_attachment(filename: &str, data: &[u8]) -> Vec<u8> {
+    let mut email = Email::new();
+    email.to = vec![EmailAddress { name: None, address: "bob@example.com".to_string() }];
+    email.subject = "Property".to_string();
+    email.body_text = Some("body".to_string());
+    email.attachments.push(EmailAttachment {
+        filename: filename.to_string(),
+        content_type: "application/octet-stream".to_string(),
+        data: data.to_vec(),
+        path: None,
+        size: data.len(),
+    });
+    let me = EmailAddress { name: None, address: "me@example.com".to_string() };
+    build_message(&email, &me).unwrap().formatted()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn arbitrary_bytes_never_panic(raw in proptest::collection::vec(any::<u8>(), 0..2048)) {
+        if let Some(parsed) = parse_message(&raw) {
+            let email = Email::from_parsed_email(&parsed, "1", "INBOX", Vec::new()).unwrap();
+            for part in &parsed.parts {
+                let _ = attachment_from_part(part);
+            }
+            prop_assert!(email.attachments.iter().all(|a| !a.data.is_empty()));
+        }
+    }
+
+    #[test]
+    fn mangled_headers_never_panic(
+        ctype in "[a-z/;=\"* %'-]{0,40}",
+        disposition in "[a-z/;=\"* %'0-9-]{0,60}",
+        body in "[ -~]{0,80}",
+    ) {
+        let raw = format!(
+            "From: a@example.com\r\nContent-Type: multipart/mixed; boundary=x\r\n\r\n--x\r\n\
+             Content-Type: {ctype}\r\nContent-Disposition: {disposition}\r\n\r\n{body}\r\n--x--\r\n"
+        );
+        if let Some(parsed) = parse_message(raw.as_bytes()) {
+            let email = Email::from_parsed_email(&parsed, "1", "INBOX", Vec::new()).unwrap();
+            for attachment in &email.attachments {
+                prop_assert!(!attachment.filename.is_empty());
+                prop_assert!(!attachment.filename.contains(['/', '\\']));
+            }
+        }
+    }
+
+    #[test]
+    fn sanitized_filenames_are_safe(name in "\\PC{0,300}") {
+        let clean = sanitize_filename(&name);
+        prop_assert!(!clean.contains(['/', '\\']));
+        prop_assert!(!clean.chars().any(char::is_control));
+        prop_assert!(!clean.starts_with('.'));
+        prop_assert!(clean.len() <= 255);
+        prop_assert_eq!(sanitize_filename(&clean), clean.clone());
+    }
+
+    #[test]
+    fn sent_attachments_survive_a_round_trip(
+        stem in "[\\p{L}\\p{N} _-]{1,40}",
+        data in proptest::collection::vec(any::<u8>(), 1..4096),
+    ) {
+        let filename = format!("{}.bin", stem.trim());
+        let raw = sent_with_attachment(&filename, &data);
+        let email = parse(&raw);
+        prop_assert_eq!(email.attachments.len(), 1);
+        prop_assert_eq!(&email.attachments[0].filename, &sanitize_filename(&filename));
+        prop_assert_eq!(&email.attachments[0].data, &data);
+    }
+}
@@ -0,0 +1,1041 @@
+use std::path::PathBuf;
+
+use tuimail::bundle;
+use tuimail::drafts::{self, Resolution};
+use tuimail::database::EmailDatabase;
+use tuimail::config::{EmailAccount, IpFamily, SmtpFallback, SmtpSecurity};
+use tuimail::email::{Email, EmailAddress, EmailAttachment, EmailError, READ_LATER_KEYWORD};
+use tuimail::maildir::{self, MaildirStore};
+use tuimail::mbox;
+use tuimail::mock_transport::MockMailServer;
+use tuimail::search::SearchQuery;
+use tuimail::transport::{self, ImapBackend};
+
+const ACCOUNT: &str = "me@example.com";
+
+/// A throwaway database that is removed when the test ends
+struct TestDb {
+    dir: PathBuf,
+    db: EmailDatabase,
+}
+
+impl TestDb {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("tuimail-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = EmailDatabase::new(&dir.join("emails.db")).unwrap();
+        Self { dir, db }
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn raw_message(from: &str, subject: &str, body: &str) -> Vec<u8> {
+    format!(
+        "From: {from}\r\nTo: {ACCOUNT}\r\nSubject: {subject}\r\nDate: Mon, 4 Mar 2024 10:00:00 +0000\r\n\
+         Message-ID: <{subject}@example.com>\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{body}\r\n"
+    )
+    .into_bytes()
+}
+
+#[test]
+fn sync_stores_only_new_messages() {
+    let test = TestDb::new("sync");
+    let mut server = MockMailServer::new();
+    server.deliver("INBOX", &raw_message("alice@example.com", "First", "hello"), &[]);
+    server.deliver("INBOX", &raw_message("bob@example.com", "Second", "hi"), &["\\Seen"]);
+
+    let added = transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    assert_eq!(added, 2);
+    assert_eq!(server.unseen_count("INBOX").unwrap(), 1);
+    assert_eq!(test.db.get_unread_count(ACCOUNT, "INBOX").unwrap(), 1);
+
+    let emails = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap();
+    assert_eq!(emails.len(), 2);
+    let second = emails.iter().find(|e| e.subject == "Second").unwrap();
+    assert!(second.seen);
+    assert_eq!(second.from[0].address, "bob@example.com");
+
+    // Nothing new on the server: nothing fetched
+    assert_eq!(transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), 0);
+
+    server.deliver("INBOX", &raw_message("carol@example.com", "Third", "hey"), &[]);
+    assert_eq!(transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), 1);
+    assert_eq!(test.db.get_all_emails(ACCOUNT, "INBOX").unwrap().len(), 3);
+}
+
+#[test]
+fn large_attachments_with_the_same_name_are_cached_apart() {
+    let test = TestDb::new("spill");
+    let mut server = MockMailServer::new();
+    let big = tuimail::database::ATTACHMENT_SPILL_THRESHOLD + 1;
+    let mut email = Email::new();
+    email.to = vec![EmailAddress { name: None, address: ACCOUNT.to_string() }];
+    email.subject = "Scans".to_string();
+    for fill in [b'a', b'b'] {
+        email.attachments.push(EmailAttachment {
+            filename: "scan.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            data: vec![fill; big],
+            path: None,
+            size: big,
+        });
+    }
+    let from = EmailAddress { name: None, address: "alice@example.com".to_string() };
+    server.deliver("INBOX", &transport::build_message(&email, &from).unwrap().formatted(), &[]);
+
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    let stored = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap().remove(0);
+    let paths: Vec<_> = stored.attachments.iter().map(|a| a.path.clone().unwrap()).collect();
+    assert_ne!(paths[0], paths[1]);
+    for (attachment, fill) in stored.attachments.iter().zip([b'a', b'b']) {
+        assert!(attachment.data.is_empty());
+        assert_eq!(attachment.size(), big);
+        assert_eq!(attachment.load_data().unwrap(), vec![fill; big]);
+    }
+}
+
+#[test]
+fn flag_changes_flow_both_ways() {
+    let test = TestDb::new("flags");
+    let mut server = MockMailServer::new();
+    let uid = server.deliver("INBOX", &raw_message("alice@example.com", "Unread", "body"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    // Read on another device
+    server.set_message_flags("INBOX", uid, &["\\Seen"]);
+    let changed = transport::sync_seen_flags(&mut server, &test.db, ACCOUNT, "INBOX", &[(uid, false)]).unwrap();
+    assert_eq!(changed.get(&uid), Some(&true));
+    assert!(test.db.get_all_emails(ACCOUNT, "INBOX").unwrap()[0].seen);
+
+    // Marked unread locally
+    let email = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap().remove(0);
+    transport::set_seen(&mut server, &test.db, ACCOUNT, &email, false).unwrap();
+    assert_eq!(server.message_flags("INBOX", uid), Some(Vec::new()));
+    assert!(!test.db.get_all_emails(ACCOUNT, "INBOX").unwrap()[0].seen);
+
+    // Nothing changed since, so nothing to report
+    let changed = transport::sync_seen_flags(&mut server, &test.db, ACCOUNT, "INBOX", &[(uid, false)]).unwrap();
+    assert!(changed.is_empty());
+
+    server.delete("INBOX", uid).unwrap();
+    assert!(!server.contains("INBOX", uid));
+}
+
+#[test]
+fn read_state_set_offline_reaches_the_server() {
+    let test = TestDb::new("offline_seen");
+    let mut server = MockMailServer::new();
+    let uid = server.deliver("INBOX", &raw_message("alice@example.com", "Unread", "body"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    // Read while offline: queued, and the cache already has it read
+    test.db.queue_email_operation(ACCOUNT, "mark_read", uid, "INBOX", None, Some("Unread")).unwrap();
+    test.db.update_email_seen_status(ACCOUNT, "INBOX", uid, true).unwrap();
+
+    // The server's unread state doesn't undo it; the read state goes out instead
+    let changed = transport::sync_seen_flags(&mut server, &test.db, ACCOUNT, "INBOX", &[(uid, true)]).unwrap();
+    assert!(changed.is_empty());
+    assert_eq!(server.message_flags("INBOX", uid), Some(vec!["\\Seen".to_string()]));
+    assert!(test.db.get_all_emails(ACCOUNT, "INBOX").unwrap()[0].seen);
+    assert!(test.db.get_pending_operations().unwrap().is_empty());
+}
+
+#[test]
+fn only_changes_since_the_last_sync_are_fetched() {
+    let test = TestDb::new("modseq");
+    let mut server = MockMailServer::new();
+    server.enable_qresync();
+    let read = server.deliver("INBOX", &raw_message("alice@example.com", "Read", "body"), &[]);
+    let gone = server.deliver("INBOX", &raw_message("bob@example.com", "Gone", "body"), &[]);
+    let kept = server.deliver("INBOX", &raw_message("carol@example.com", "Kept", "body"), &["\\Seen"]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    // The first call only records where the folder stands
+    assert_eq!(transport::sync_changes(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), None);
+    assert!(test.db.get_modseq(ACCOUNT, "INBOX").unwrap().is_some());
+
+    // Read and expunged on another device
+    server.set_message_flags("INBOX", read, &["\\Seen", "\\Flagged"]);
+    server.delete("INBOX", gone).unwrap();
+    let synced = transport::sync_changes(&mut server, &test.db, ACCOUNT, "INBOX").unwrap().unwrap();
+    assert_eq!(synced.seen, std::collections::HashMap::from([(read, true)]));
+    assert_eq!(synced.expunged, 1);
+    let emails = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap();
+    assert_eq!(emails.len(), 2);
+    assert!(emails.iter().find(|e| e.id == read.to_string()).unwrap().seen);
+    assert_eq!(test.db.get_deleted_messages(ACCOUNT).unwrap().len(), 1);
+
+    // The regular sync keeps the mod-sequence, and nothing changed since
+    test.db.save_folder_metadata(ACCOUNT, "INBOX", kept, 2).unwrap();
+    let synced = transport::sync_changes(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    assert_eq!(synced, Some(transport::SyncedChanges::default()));
+}
+
+#[test]
+fn triage_moves_and_flags_messages() {
+    let test = TestDb::new("triage");
+    let mut server = MockMailServer::new();
+    let receipt = server.deliver("INBOX", &raw_message("shop@example.com", "Receipt", "paid"), &[]);
+    let report = server.deliver("INBOX", &raw_message("boss@example.com", "Report", "see attached"), &["\\Seen"]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    let cached = |subject: &str| test.db.get_all_emails(ACCOUNT, "INBOX").unwrap().into_iter().find(|e| e.subject == subject);
+
+    // The target folder must exist, as on a real server
+    let email = cached("Receipt").unwrap();
+    assert!(transport::move_email(&mut server, &test.db, ACCOUNT, &email, "Receipts").is_err());
+    assert!(server.contains("INBOX", receipt));
+
+    server.create_folder("Receipts");
+    transport::move_email(&mut server, &test.db, ACCOUNT, &email, "Receipts").unwrap();
+    assert!(!server.contains("INBOX", receipt));
+    assert_eq!(server.uids("Receipts").unwrap().len(), 1);
+    assert!(cached("Receipt").is_none());
+
+    let email = cached("Report").unwrap();
+    transport::set_flag(&mut server, &test.db, ACCOUNT, &email, "\\Flagged", true).unwrap();
+    assert_eq!(server.message_flags("INBOX", report), Some(vec!["\\Seen".to_string(), "\\Flagged".to_string()]));
+    let email = cached("Report").unwrap();
+    assert!(email.seen && email.flags.contains(&"\\Flagged".to_string()));
+}
+
+#[test]
+fn moves_without_move_keep_other_deleted_messages() {
+    let test = TestDb::new("move_by_copy");
+    let mut server = MockMailServer::new();
+    server.disable_move();
+    server.create_folder("Receipts");
+    let receipt = server.deliver("INBOX", &raw_message("shop@example.com", "Receipt", "paid"), &[]);
+    let pending = server.deliver("INBOX", &raw_message("spam@example.com", "Offer", "buy"), &["\\Deleted"]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    // EXPUNGE would take the message the user flagged \Deleted too; it stays, still flagged
+    let email = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap().into_iter().find(|e| e.subject == "Receipt").unwrap();
+    transport::move_email(&mut server, &test.db, ACCOUNT, &email, "Receipts").unwrap();
+    assert!(!server.contains("INBOX", receipt));
+    assert_eq!(server.uids("Receipts").unwrap().len(), 1);
+    assert_eq!(server.message_flags("INBOX", pending), Some(vec!["\\Deleted".to_string()]));
+}
+
+#[test]
+fn queued_operations_retry_and_cancel() {
+    let test = TestDb::new("queue");
+    let mut server = MockMailServer::new();
+    let unread = server.deliver("INBOX", &raw_message("alice@example.com", "Unread", "body"), &[]);
+    let receipt = server.deliver("INBOX", &raw_message("shop@example.com", "Receipt", "paid"), &[]);
+    let spam = server.deliver("INBOX", &raw_message("spam@example.com", "Offer", "buy"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    test.db.queue_email_operation(ACCOUNT, "mark_read", unread, "INBOX", None, Some("Unread")).unwrap();
+    test.db.queue_email_operation(ACCOUNT, "move", receipt, "INBOX", Some("Receipts"), Some("Receipt")).unwrap();
+    test.db.queue_email_operation(ACCOUNT, "delete", spam, "INBOX", None, Some("Offer")).unwrap();
+    let queued = test.db.get_pending_operations().unwrap();
+    assert_eq!(queued.len(), 3);
+    assert_eq!(queued[1].describe(), "move to Receipts");
+    assert_eq!(queued[1].subject.as_deref(), Some("Receipt"));
+
+    // The move fails as the folder doesn't exist; it waits for its next attempt
+    let now = 1_700_000_000;
+    for op in test.db.get_due_operations(ACCOUNT, now).unwrap() {
+        match transport::apply_operation(&mut server, &test.db, &op) {
+            Ok(()) => test.db.mark_operation_processed(op.id).unwrap(),
+            Err(e) => test.db.mark_operation_failed(op.id, &e.to_string(), now + 60).unwrap(),
+        }
+    }
+    assert_eq!(server.message_flags("INBOX", unread), Some(vec!["\\Seen".to_string()]));
+    assert!(!server.contains("INBOX", spam));
+    let queued = test.db.get_pending_operations().unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].attempts, 1);
+    assert!(queued[0].error.is_some());
+    assert!(test.db.get_due_operations(ACCOUNT, now).unwrap().is_empty());
+
+    // A new target makes it due again
+    server.create_folder("Archive");
+    test.db.set_operation_target(queued[0].id, "Archive").unwrap();
+    let due = test.db.get_due_operations(ACCOUNT, now).unwrap();
+    transport::apply_operation(&mut server, &test.db, &due[0]).unwrap();
+    test.db.mark_operation_processed(due[0].id).unwrap();
+    assert_eq!(server.uids("Archive").unwrap().len(), 1);
+    assert!(test.db.get_pending_operations().unwrap().is_empty());
+
+    // Cancelled operations are never carried out
+    test.db.queue_email_operation(ACCOUNT, "mark_unread", unread, "INBOX", None, None).unwrap();
+    let id = test.db.get_pending_operations().unwrap()[0].id;
+    test.db.cancel_operation(id).unwrap();
+    assert!(test.db.get_due_operations(ACCOUNT, now).unwrap().is_empty());
+}
+
+#[test]
+fn replies_and_forwards_are_flagged_through_the_queue() {
+    let test = TestDb::new("answered");
+    let mut server = MockMailServer::new();
+    let question = server.deliver("INBOX", &raw_message("alice@example.com", "Question", "body"), &["\\Seen"]);
+    let report = server.deliver("INBOX", &raw_message("bob@example.com", "Report", "body"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    test.db.queue_email_operation(ACCOUNT, "mark_answered", question, "INBOX", None, Some("Question")).unwrap();
+    test.db.queue_email_operation(ACCOUNT, "mark_forwarded", report, "INBOX", None, Some("Report")).unwrap();
+    test.db.queue_email_operation(ACCOUNT, "mark_answered", report, "INBOX", None, Some("Report")).unwrap();
+    assert_eq!(test.db.get_pending_operations().unwrap()[0].describe(), "mark answered");
+    for op in test.db.get_due_operations(ACCOUNT, 1_700_000_000).unwrap() {
+        transport::apply_operation(&mut server, &test.db, &op).unwrap();
+    }
+
+    assert_eq!(server.message_flags("INBOX", question), Some(vec!["\\Seen".to_string(), "\\Answered".to_string()]));
+    assert_eq!(server.message_flags("INBOX", report), Some(vec!["$Forwarded".to_string(), "\\Answered".to_string()]));
+    let cached = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap();
+    let flags = |uid: u32| cached.iter().find(|e| e.id == uid.to_string()).unwrap().flags.clone();
+    assert_eq!(flags(question), vec!["\\Seen", "\\Answered"]);
+    assert_eq!(flags(report), vec!["$Forwarded", "\\Answered"]);
+    // Seen is kept as it was
+    assert!(cached.iter().find(|e| e.id == question.to_string()).unwrap().seen);
+}
+
+#[test]
+fn server_search_finds_what_is_not_cached() {
+    let test = TestDb::new("server-search");
+    let mut server = MockMailServer::new();
+    let listed = server.deliver("INBOX", &raw_message("alice@example.com", "Plans", "The budget is attached"), &[]);
+    server.deliver("INBOX", &raw_message("bob@example.com", "Lunch", "Noon?"), &[]);
+    let old = server.deliver("Archive", &raw_message("carol@example.com", "Old", "Last year's budget"), &["\\Seen"]);
+    server.deliver("Archive", &raw_message("dave@example.com", "Older", "Nothing here"), &[]);
+    server.deliver("Sent", &raw_message("me@example.com", "Mine", "My budget"), &[]);
+    // INBOX only listed from headers, Archive never synced, Sent cached in full
+    transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "Sent").unwrap();
+    let pending = test.db.get_pending_body_uids(ACCOUNT, "Sent", None).unwrap();
+    transport::fetch_bodies(&mut server, &test.db, ACCOUNT, "Sent", &pending).unwrap();
+
+    let query = tuimail::search::SearchQuery::parse("budget on:server").unwrap();
+    assert!(test.db.search_emails(ACCOUNT, &query).unwrap().iter().all(|e| e.folder == "Sent"));
+    let folders = ["INBOX", "Archive", "Sent"].map(String::from);
+    let found = transport::search_server(&mut server, &test.db, ACCOUNT, &folders, &query.to_imap().unwrap()).unwrap();
+    let mut hits: Vec<(&str, u32)> = found.iter().map(|e| (e.folder.as_str(), e.id.parse().unwrap())).collect();
+    hits.sort();
+    assert_eq!(hits, vec![("Archive", old), ("INBOX", listed)]);
+    // Found messages come with their body, and are not stored
+    let archived = found.iter().find(|e| e.folder == "Archive").unwrap();
+    assert!(archived.body_text.as_deref().unwrap().contains("Last year's budget"));
+    assert!(archived.seen);
+    assert!(test.db.get_uids(ACCOUNT, "Archive").unwrap().is_empty());
+
+    let query = tuimail::search::SearchQuery::parse("from:dave is:unread").unwrap();
+    let found = transport::search_server(&mut server, &test.db, ACCOUNT, &folders, &query.to_imap().unwrap()).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].subject, "Older");
+}
+
+#[test]
+fn tags_follow_the_message_and_are_searchable() {
+    let test = TestDb::new("tags");
+    let mut server = MockMailServer::new();
+    server.create_folder("Archive");
+    server.deliver("INBOX", &raw_message("alice@example.com", "Plan", "see you"), &[]);
+    server.deliver("INBOX", &raw_message("bob@example.com", "Lunch", "hungry"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    let tags = tuimail::tags::apply_changes(&[], "+todo work").unwrap();
+    test.db.set_tags(ACCOUNT, "Plan@example.com", &tags).unwrap();
+    let found = test.db.search_emails(ACCOUNT, &SearchQuery::parse("tag:todo").unwrap()).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].subject, "Plan");
+
+    // Tags are kept by Message-ID, so moving the message keeps them
+    let uid: u32 = found[0].id.parse().unwrap();
+    transport::remove_messages(&mut server, &test.db, ACCOUNT, "INBOX", &[uid], Some("Archive")).unwrap();
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "Archive").unwrap();
+    let found = test.db.search_emails(ACCOUNT, &SearchQuery::parse("tag:work folder:Archive").unwrap()).unwrap();
+    assert_eq!(found.len(), 1);
+
+    test.db.set_tags(ACCOUNT, "Plan@example.com", &[]).unwrap();
+    assert!(test.db.get_tags(ACCOUNT).unwrap().is_empty());
+}
+
+#[test]
+fn deleted_messages_are_kept_and_restored() {
+    let test = TestDb::new("local-trash");
+    let mut server = MockMailServer::new();
+    let mistake = server.deliver("INBOX", &raw_message("alice@example.com", "Mistake", "keep me"), &["\\Seen"]);
+    let other = server.deliver("INBOX", &raw_message("bob@example.com", "Elsewhere", "gone"), &[]);
+    server.deliver("INBOX", &raw_message("carol@example.com", "Stays", "here"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    // Deleted here: the raw message is kept before it goes
+    transport::delete_message(&mut server, &test.db, ACCOUNT, "INBOX", mistake).unwrap();
+    assert!(!server.contains("INBOX", mistake));
+    // Expunged by another client: found missing and rebuilt from the cache
+    server.delete("INBOX", other).unwrap();
+    assert_eq!(transport::keep_expunged(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), 1);
+    assert_eq!(test.db.get_email_count(ACCOUNT, "INBOX").unwrap(), 1);
+
+    let kept = test.db.get_deleted_messages(ACCOUNT).unwrap();
+    assert_eq!(kept.len(), 2);
+    let mistake = kept.iter().find(|m| m.subject == "Mistake").unwrap();
+    assert!(!mistake.elsewhere);
+    assert_eq!((mistake.sender.as_str(), mistake.flags.clone()), ("alice@example.com", vec!["\\Seen".to_string()]));
+    let elsewhere = kept.iter().find(|m| m.subject == "Elsewhere").unwrap();
+    assert!(elsewhere.elsewhere);
+
+    transport::restore_deleted(&mut server, &test.db, mistake).unwrap();
+    transport::restore_deleted(&mut server, &test.db, elsewhere).unwrap();
+    assert!(test.db.get_deleted_messages(ACCOUNT).unwrap().is_empty());
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    let emails = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap();
+    assert_eq!(emails.len(), 3);
+    let restored = emails.iter().find(|e| e.subject == "Mistake").unwrap();
+    assert!(restored.seen);
+    assert_eq!(restored.body_text.as_deref().map(str::trim), Some("keep me"));
+    let rebuilt = emails.iter().find(|e| e.subject == "Elsewhere").unwrap();
+    assert_eq!(rebuilt.from[0].address, "bob@example.com");
+
+    // Nothing expunged now, and old copies are purged
+    assert_eq!(transport::keep_expunged(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), 0);
+    let uid = server.uids("INBOX").unwrap()[0];
+    transport::delete_message(&mut server, &test.db, ACCOUNT, "INBOX", uid).unwrap();
+    assert_eq!(test.db.purge_deleted(i64::MAX).unwrap(), 1);
+    assert!(test.db.get_deleted_messages(ACCOUNT).unwrap().is_empty());
+}
+
+#[test]
+fn special_use_folders_are_found_and_preferred_over_names() {
+    let mut server = MockMailServer::new();
+    server.create_folder("Sent");
+    server.mark_special_use("Sent Items", "\\Sent");
+    server.mark_special_use("Bin", "\\Trash");
+    server.mark_special_use("[Gmail]/All Mail", "\\All");
+    let found = server.special_use_folders().unwrap();
+
+    let mut account = EmailAccount::default();
+    account.special_folders.trash = Some("Deleted".to_string());
+    account.special_folders.fill_from(found);
+    let folders: Vec<String> = ["INBOX", "Sent", "Sent Items", "Drafts", "Spam"].iter().map(|f| f.to_string()).collect();
+    assert_eq!(account.sent_folder(&folders).map(String::as_str), Some("Sent Items"));
+    // Set by hand, so kept
+    assert_eq!(account.special_folders.trash.as_deref(), Some("Deleted"));
+    // Not marked, so found by name
+    assert_eq!(account.drafts_folder(&folders).map(String::as_str), Some("Drafts"));
+    assert_eq!(account.junk_folder(&folders).map(String::as_str), Some("Spam"));
+    assert_eq!(account.archive_folder("Archive"), "[Gmail]/All Mail");
+}
+
+#[test]
+fn concurrent_connections_take_turns_writing() {
+    let test = TestDb::new("contention");
+    let path = test.dir.join("emails.db");
+    let writers: Vec<_> = (0..4)
+        .map(|n| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                // Each thread has its own connection, like the TUI and its sync thread
+                let db = EmailDatabase::new(&path).unwrap();
+                let folder = format!("Folder{}", n);
+                let mut server = MockMailServer::new();
+                for i in 0..10 {
+                    let uid = server.deliver(&folder, &raw_message("alice@example.com", &format!("M{}-{}", n, i), "hi"), &[]);
+                    transport::sync_folder(&mut server, &db, ACCOUNT, &folder).unwrap();
+                    db.update_email_seen_status(ACCOUNT, &folder, uid, true).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    for n in 0..4 {
+        let folder = format!("Folder{}", n);
+        assert_eq!(test.db.get_email_count(ACCOUNT, &folder).unwrap(), 10);
+        assert_eq!(test.db.get_unread_count(ACCOUNT, &folder).unwrap(), 0);
+    }
+}
+
+#[test]
+fn sent_copy_shows_until_the_server_copy_arrives() {
+    let test = TestDb::new("sent-copy");
+    let mut server = MockMailServer::new();
+    let mut reply = Email::new();
+    reply.subject = "Re: Lunch".to_string();
+    reply.from = vec![EmailAddress { name: None, address: ACCOUNT.to_string() }];
+    reply.to = vec![EmailAddress { name: None, address: "bob@example.com".to_string() }];
+    reply.body_text = Some("Noon works".to_string());
+    reply.headers.insert("Message-ID".to_string(), "reply-1@example.com".to_string());
+
+    assert_eq!(test.db.save_sent_copy(ACCOUNT, "Sent", &reply).unwrap(), u32::MAX);
+    assert_eq!(test.db.save_sent_copy(ACCOUNT, "Sent", &Email::new()).unwrap(), u32::MAX - 1);
+    let thread = test.db.get_emails_with_contact(ACCOUNT, "bob@example.com").unwrap();
+    assert_eq!(thread.len(), 1);
+    assert_eq!((thread[0].folder.as_str(), thread[0].body_text.as_deref()), ("Sent", Some("Noon works")));
+
+    // The local copies don't hold back the sync, and the server's copy replaces its own
+    let sent = format!(
+        "From: {ACCOUNT}\r\nTo: bob@example.com\r\nSubject: Re: Lunch\r\n\
+         Date: Tue, 5 Mar 2024 09:14:50 +0000\r\nMessage-ID: <reply-1@example.com>\r\n\r\nNoon works\r\n"
+    );
+    let uid = server.deliver("Sent", sent.as_bytes(), &["\\Seen"]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "Sent").unwrap();
+    assert_eq!(test.db.get_uids(ACCOUNT, "Sent").unwrap(), vec![uid, u32::MAX - 1]);
+    let thread = test.db.get_emails_with_contact(ACCOUNT, "bob@example.com").unwrap();
+    assert_eq!(thread.len(), 1);
+    assert_eq!(thread[0].id, uid.to_string());
+}
+
+#[test]
+fn read_later_queue_spans_folders_and_keeps_seen() {
+    let test = TestDb::new("read-later");
+    let mut server = MockMailServer::new();
+    server.create_folder("Newsletters");
+    server.deliver("INBOX", &raw_message("alice@example.com", "Essay", "long read"), &["\\Seen"]);
+    server.deliver("Newsletters", &raw_message("news@example.com", "Weekly", "links"), &[]);
+    for folder in ["INBOX", "Newsletters"] {
+        transport::sync_folder(&mut server, &test.db, ACCOUNT, folder).unwrap();
+    }
+    let queue = || test.db.get_emails_with_flag(ACCOUNT, READ_LATER_KEYWORD).unwrap();
+    assert!(queue().is_empty());
+
+    for folder in ["INBOX", "Newsletters"] {
+        let email = test.db.get_all_emails(ACCOUNT, folder).unwrap().remove(0);
+        transport::set_flag(&mut server, &test.db, ACCOUNT, &email, READ_LATER_KEYWORD, true).unwrap();
+    }
+    let queued = queue();
+    assert_eq!(queued.iter().map(|e| e.subject.as_str()).collect::<Vec<_>>(), vec!["Essay", "Weekly"]);
+    assert_eq!(queued.iter().map(|e| e.seen).collect::<Vec<_>>(), vec![true, false]);
+
+    // Done: only the keyword goes, the message stays unread
+    transport::set_flag(&mut server, &test.db, ACCOUNT, &queued[1], READ_LATER_KEYWORD, false).unwrap();
+    assert_eq!(queue().len(), 1);
+    assert!(!test.db.get_all_emails(ACCOUNT, "Newsletters").unwrap()[0].seen);
+}
+
+#[test]
+fn bounces_mark_the_sent_message() {
+    let test = TestDb::new("bounces");
+    let mut server = MockMailServer::new();
+    let sent = format!(
+        "From: {ACCOUNT}\r\nTo: bob@exmaple.com\r\nSubject: Quarterly report\r\n\
+         Date: Tue, 5 Mar 2024 09:14:50 +0000\r\nMessage-ID: <report-42@example.com>\r\n\r\nNumbers\r\n"
+    );
+    server.deliver("Sent", sent.as_bytes(), &["\\Seen"]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "Sent").unwrap();
+
+    let dsn = std::fs::read(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mime/dsn_bounce.eml")).unwrap();
+    let uid = server.deliver("INBOX", &dsn, &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    let bounces = test.db.get_bounces(ACCOUNT).unwrap();
+    assert_eq!(bounces.len(), 1);
+    assert_eq!(bounces[0].message_id, "report-42@example.com");
+    assert_eq!((bounces[0].recipient.as_str(), bounces[0].bounce_uid), ("bob@exmaple.com", uid));
+    let original = test.db.find_email_by_message_id(ACCOUNT, "<report-42@example.com>").unwrap().unwrap();
+    assert_eq!(original.folder, "Sent");
+
+    // A 5.1.1 is a dead address, so it goes on the suppression list
+    let suppressed = test.db.get_suppressed_addresses(ACCOUNT).unwrap();
+    assert_eq!(suppressed.len(), 1);
+    assert_eq!((suppressed[0].address.as_str(), suppressed[0].status.as_str()), ("bob@exmaple.com", "5.1.1"));
+    test.db.unsuppress_address(ACCOUNT, "Bob@Exmaple.com").unwrap();
+    assert!(test.db.get_suppressed_addresses(ACCOUNT).unwrap().is_empty());
+
+    // Bounces recorded before the list existed are added when it is created
+    test.db.execute_sql("DROP TABLE suppressed_addresses").unwrap();
+    let reopened = EmailDatabase::new(&test.dir.join("emails.db")).unwrap();
+    assert_eq!(reopened.get_suppressed_addresses(ACCOUNT).unwrap(), suppressed);
+}
+
+#[test]
+fn contacts_count_each_message_once() {
+    let test = TestDb::new("contacts");
+    let mut server = MockMailServer::new();
+    let both = format!(
+        "From: {ACCOUNT}\r\nTo: Jane Doe <jane@corp.example>\r\nCc: JANE@corp.example, bob@example.com\r\n\
+         Subject: Plan\r\nDate: Tue, 5 Mar 2024 09:14:50 +0000\r\nMessage-ID: <plan@example.com>\r\n\r\nSee you\r\n"
+    );
+    let reply = format!(
+        "From: Jane Doe <jane@corp.example>\r\nTo: {ACCOUNT}, jane@corp.example\r\nSubject: Re: Plan\r\n\
+         Date: Tue, 5 Mar 2024 10:02:11 +0000\r\nMessage-ID: <re-plan@example.com>\r\n\r\nFine\r\n"
+    );
+    server.deliver("Sent", both.as_bytes(), &["\\Seen"]);
+    server.deliver("INBOX", reply.as_bytes(), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "Sent").unwrap();
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    let contacts = test.db.get_contacts(ACCOUNT).unwrap();
+    let count = |address: &str| contacts.iter().find(|c| c.address == address).map(|c| c.message_count);
+    assert_eq!(count("jane@corp.example"), Some(2));
+    assert_eq!(count("bob@example.com"), Some(1));
+    assert_eq!(count(ACCOUNT), None);
+}
+
+#[test]
+fn headers_first_then_bodies() {
+    let test = TestDb::new("headers");
+    let mut server = MockMailServer::new();
+    let old = server.deliver("Archive", &raw_message("alice@example.com", "Old", "from last year"), &["\\Seen"]);
+    let new = server.deliver("Archive", &raw_message("bob@example.com", "New", "from today"), &[]);
+
+    assert_eq!(transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "Archive").unwrap(), 2);
+    let emails = test.db.get_all_emails(ACCOUNT, "Archive").unwrap();
+    assert_eq!(emails.len(), 2);
+    assert!(emails.iter().all(|e| e.body_text.is_none() && e.snippet.is_none()));
+    let mut pending = test.db.get_pending_body_uids(ACCOUNT, "Archive", None).unwrap();
+    pending.sort();
+    assert_eq!(pending, vec![old, new]);
+
+    // The message on screen first, the rest later
+    assert_eq!(transport::fetch_bodies(&mut server, &test.db, ACCOUNT, "Archive", &[new]).unwrap(), vec![new]);
+    let fetched = test.db.get_emails_by_uids(ACCOUNT, "Archive", &[new]).unwrap();
+    assert_eq!(fetched[0].body_text.as_deref().map(str::trim), Some("from today"));
+    assert_eq!(fetched[0].snippet.as_deref(), Some("from today"));
+    assert_eq!(test.db.get_pending_body_uids(ACCOUNT, "Archive", None).unwrap(), vec![old]);
+
+    transport::fetch_bodies(&mut server, &test.db, ACCOUNT, "Archive", &[old]).unwrap();
+    assert!(test.db.get_pending_body_uids(ACCOUNT, "Archive", None).unwrap().is_empty());
+    // Stored previews are made again once, for messages cached before they changed
+    assert_eq!(test.db.regenerate_snippets().unwrap(), 2);
+    assert_eq!(test.db.regenerate_snippets().unwrap(), 0);
+
+    // Headers were recorded as synced, so the regular sync only looks for new mail
+    assert_eq!(transport::sync_folder(&mut server, &test.db, ACCOUNT, "Archive").unwrap(), 0);
+}
+
+#[test]
+fn sorting_servers_list_newest_first_and_thread_conversations() {
+    let test = TestDb::new("sort-thread");
+    let mut server = MockMailServer::new();
+    let dated = |raw: Vec<u8>, date: &str| String::from_utf8(raw).unwrap().replace("Mon, 4 Mar 2024 10:00:00", date).into_bytes();
+    let first = server.deliver("INBOX", &raw_message("alice@example.com", "Plans", "Shall we?"), &[]);
+    assert_eq!(server.sort("INBOX").unwrap(), None);
+    assert_eq!(server.threads("INBOX").unwrap(), None);
+    server.enable_sort_and_thread();
+    assert_eq!(transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), 1);
+
+    let mut reply = b"In-Reply-To: <Plans@example.com>\r\n".to_vec();
+    reply.extend(dated(raw_message("bob@example.com", "Re-Plans", "Yes"), "Wed, 6 Mar 2024 10:00:00"));
+    let older = server.deliver("INBOX", &dated(raw_message("carol@example.com", "Backdated", "Old"), "Mon, 1 Jan 2024 10:00:00"), &[]);
+    let newest = server.deliver("INBOX", &reply, &[]);
+    assert_eq!(server.sort("INBOX").unwrap(), Some(vec![newest, first, older]));
+    assert_eq!(server.threads("INBOX").unwrap(), Some(vec![vec![first, newest], vec![older]]));
+
+    // Interrupted after sorting: the listing starts over from the same point, not after
+    // the highest UID it happened to reach
+    server.fail_after(1, "connection reset");
+    assert!(transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "INBOX").is_err());
+    assert_eq!(test.db.get_sync_progress(ACCOUNT, "INBOX").unwrap(), Some(first));
+    assert_eq!(transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), 2);
+    assert_eq!(test.db.get_sync_progress(ACCOUNT, "INBOX").unwrap(), None);
+    assert_eq!(test.db.get_uids(ACCOUNT, "INBOX").unwrap(), vec![first, older, newest]);
+    assert_eq!(transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "INBOX").unwrap(), 0);
+}
+
+#[test]
+fn gmail_labels_share_one_body() {
+    let test = TestDb::new("labels");
+    let mut server = MockMailServer::new();
+    let raw = raw_message("alice@example.com", "Lunch", "see you at noon");
+    let in_inbox = server.deliver("INBOX", &raw, &[]);
+    let in_all_mail = server.deliver("[Gmail]/All Mail", &raw, &[]);
+    server.set_gmail_id("INBOX", in_inbox, "18e0a1b2c3d4e5f6");
+    server.set_gmail_id("[Gmail]/All Mail", in_all_mail, "18e0a1b2c3d4e5f6");
+
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    // The label's copy needs no download of its own
+    transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "[Gmail]/All Mail").unwrap();
+    assert!(test.db.get_pending_body_uids(ACCOUNT, "[Gmail]/All Mail", None).unwrap().is_empty());
+    let copy = test.db.get_emails_by_uids(ACCOUNT, "[Gmail]/All Mail", &[in_all_mail]).unwrap();
+    assert_eq!(copy[0].body_text.as_deref().map(str::trim), Some("see you at noon"));
+
+    // The body stays as long as one copy does
+    test.db.delete_email(ACCOUNT, "INBOX", in_inbox).unwrap();
+    let copy = test.db.load_emails(ACCOUNT, "[Gmail]/All Mail").unwrap();
+    assert_eq!(copy[0].body_text.as_deref().map(str::trim), Some("see you at noon"));
+}
+
+#[test]
+fn reused_message_ids_are_downloaded() {
+    let test = TestDb::new("reused-id");
+    let mut server = MockMailServer::new();
+    server.deliver("INBOX", &raw_message("alice@example.com", "Lunch", "see you at noon"), &[]);
+    let other = server.deliver("Archive", &raw_message("mallory@example.com", "Lunch", "send me your password"), &[]);
+
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+    // Without Gmail ids a matching Message-ID proves nothing, so the body is fetched and
+    // kept apart from the first one
+    transport::sync_folder_headers(&mut server, &test.db, ACCOUNT, "Archive").unwrap();
+    assert_eq!(test.db.get_pending_body_uids(ACCOUNT, "Archive", None).unwrap(), vec![other]);
+    transport::fetch_bodies(&mut server, &test.db, ACCOUNT, "Archive", &[other]).unwrap();
+    let copy = test.db.load_emails(ACCOUNT, "Archive").unwrap();
+    assert_eq!(copy[0].body_text.as_deref().map(str::trim), Some("send me your password"));
+    let first = test.db.load_emails(ACCOUNT, "INBOX").unwrap();
+    assert_eq!(first[0].body_text.as_deref().map(str::trim), Some("see you at noon"));
+}
+
+#[test]
+fn interrupted_initial_sync_resumes() {
+    let test = TestDb::new("resume");
+    let mut server = MockMailServer::new();
+    for n in 0..600 {
+        server.deliver("Archive", &raw_message("alice@example.com", &format!("Note {}", n), "text"), &[]);
+    }
+
+    // The connection drops while the second batch is fetched: the first one is kept
+    server.fail_after(2, "connection reset");
+    assert!(transport::initial_sync(&mut server, &test.db, ACCOUNT, "Archive").is_err());
+    assert_eq!(test.db.get_email_count(ACCOUNT, "Archive").unwrap(), 500);
+    assert!(test.db.get_sync_progress(ACCOUNT, "Archive").unwrap().is_some());
+
+    let fetched = transport::initial_sync(&mut server, &test.db, ACCOUNT, "Archive").unwrap();
+    assert_eq!(fetched.len(), 100);
+    assert_eq!(test.db.get_email_count(ACCOUNT, "Archive").unwrap(), 600);
+    assert_eq!(test.db.get_sync_progress(ACCOUNT, "Archive").unwrap(), None);
+    assert_eq!(test.db.load_folder_metadata(ACCOUNT, "Archive").unwrap().1, 600);
+}
+
+#[test]
+fn bundles_export_and_import_raw_messages() {
+    let raw = b"From: alice@example.com\r\nSubject: Contract\r\nContent-Type: multipart/mixed; boundary=b\r\n\r\n\
+                --b\r\nContent-Type: text/plain\r\n\r\nSigned copy attached.\r\n\
+                --b\r\nContent-Type: application/pdf\r\nContent-Disposition: attachment; filename=contract.pdf\r\n\
+                Content-Transfer-Encoding: base64\r\n\r\nJVBERi0xLjQ=\r\n--b--\r\n";
+    let mut server = MockMailServer::new();
+    let uid = server.deliver("Legal", raw, &["\\Seen"]);
+    server.deliver("Legal", &raw_message("bob@example.com", "Other", "not exported"), &[]);
+
+    let bundle = bundle::export(&mut server, ACCOUNT, "Legal", Some(&[uid])).unwrap();
+    assert_eq!(bundle.index.len(), 1);
+    assert_eq!(bundle.index[0].attachments, vec!["contract.pdf".to_string()]);
+    assert!(bundle::export(&mut server, ACCOUNT, "Legal", Some(&[999])).is_err());
+    assert_eq!(bundle::export(&mut server, ACCOUNT, "Legal", None).unwrap().index.len(), 2);
+
+    // The folder must exist, as on a real server
+    assert!(bundle::import(&mut server, &bundle, Some("Restored")).is_err());
+    server.create_folder("Restored");
+    assert_eq!(bundle::import(&mut server, &bundle, Some("Restored")).unwrap(), 1);
+    let uids = server.uids("Restored").unwrap();
+    let restored = server.fetch("Restored", &uids).unwrap();
+    assert_eq!(restored[0].raw, raw.to_vec());
+    assert_eq!(restored[0].flags, vec!["\\Seen".to_string()]);
+}
+
+#[test]
+fn mbox_export_and_import_round_trip() {
+    let test = TestDb::new("mbox");
+    let mut server = MockMailServer::new();
+    server.deliver("INBOX", &raw_message("alice@example.com", "Seen", "From the start"), &["\\Seen", "\\Flagged"]);
+    server.deliver("INBOX", &raw_message("bob@example.com", "Unseen", "hi"), &[]);
+    let skipped = server.deliver("INBOX", &raw_message("carol@example.com", "Skipped", "no"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").unwrap();
+
+    let wanted: Vec<u32> = server.uids("INBOX").unwrap().into_iter().filter(|uid| *uid != skipped).collect();
+    let mut file = Vec::new();
+    let filter = mbox::ExportFilter { uids: Some(&wanted), ..Default::default() };
+    let exported = mbox::export(&mut server, &test.db, ACCOUNT, "INBOX", &filter, &mut file).unwrap();
+    assert_eq!((exported.written, exported.missing.len()), (2, 0));
+    let text = String::from_utf8(file.clone()).unwrap();
+    assert!(text.starts_with("From alice@example.com Mon Mar  4 "));
+    assert!(text.contains("\n\n>From the start\n"));
+    // Written as the server has it, not rebuilt from the cache
+    assert!(text.contains("\nDate: Mon, 4 Mar 2024 10:00:00 +0000\n"));
+    // A blank line ends each message before the next `From ` line
+    assert!(text.contains(">From the start\n\nFrom bob@example.com Mon Mar  4 "));
+    assert!(!text.contains("Skipped"));
+
+    // Only the messages of a date range
+    let date = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap()[0].date.timestamp();
+    let mut in_range = |after, before| {
+        let filter = mbox::ExportFilter { uids: None, after, before };
+        mbox::export(&mut server, &test.db, ACCOUNT, "INBOX", &filter, &mut Vec::new()).unwrap().written
+    };
+    assert_eq!(in_range(Some(date), Some(date + 1)), 3);
+    assert_eq!(in_range(Some(date + 1), None), 0);
+    assert_eq!(in_range(None, Some(date)), 0);
+
+    // A cached message gone from the server is reported, not rebuilt
+    server.delete("INBOX", skipped).unwrap();
+    let exported = mbox::export(&mut server, &test.db, ACCOUNT, "INBOX", &mbox::ExportFilter::default(), &mut Vec::new()).unwrap();
+    assert_eq!((exported.written, exported.missing), (2, vec![skipped]));
+
+    server.create_folder("Imported");
+    assert_eq!(mbox::import(file.as_slice(), &mut server, "Imported").unwrap(), 2);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "Imported").unwrap();
+    let emails = test.db.get_all_emails(ACCOUNT, "Imported").unwrap();
+    let seen = emails.iter().find(|e| e.subject == "Seen").unwrap();
+    assert_eq!(seen.body_text.as_deref().map(str::trim), Some("From the start"));
+    assert!(seen.seen && seen.flags.contains(&"\\Flagged".to_string()));
+    let unseen = emails.iter().find(|e| e.subject == "Unseen").unwrap();
+    assert!(!unseen.seen && unseen.flags.is_empty());
+}
+
+#[test]
+fn maildir_is_synced_and_imported_like_a_server() {
+    let test = TestDb::new("maildir");
+    let root = test.dir.join("Mail");
+    for folder in ["INBOX", "Lists/Rust"] {
+        for sub in ["cur", "new", "tmp"] {
+            std::fs::create_dir_all(root.join(folder).join(sub)).unwrap();
+        }
+    }
+    std::fs::write(root.join("INBOX/cur/1.a.host:2,S"), raw_message("alice@example.com", "Old", "read")).unwrap();
+    std::fs::write(root.join("INBOX/new/2.b.host"), raw_message("bob@example.com", "New", "unread")).unwrap();
+    std::fs::write(root.join("INBOX/cur/3.c.host:2,ST"), raw_message("spam@example.com", "Trashed", "gone")).unwrap();
+    std::fs::write(root.join("Lists/Rust/cur/4.d.host:2,F"), raw_message("list@example.com", "Release", "news")).unwrap();
+
+    // Browsed as a local account, through the same sync code as a server
+    let mut store = MaildirStore::open(&root).unwrap();
+    assert_eq!(store.list_folders().unwrap(), vec!["INBOX", "Lists/Rust"]);
+    assert_eq!(transport::sync_folder(&mut store, &test.db, ACCOUNT, "INBOX").unwrap(), 3);
+    assert_eq!(test.db.get_unread_count(ACCOUNT, "INBOX").unwrap(), 1);
+    let new = test.db.get_all_emails(ACCOUNT, "INBOX").unwrap().into_iter().find(|e| e.subject == "New").unwrap();
+    transport::set_seen(&mut store, &test.db, ACCOUNT, &new, true).unwrap();
+    assert_eq!(store.unseen_count("INBOX").unwrap(), 0);
+
+    // Imported into an account, leaving out the message marked for deletion
+    let mut server = MockMailServer::new();
+    server.create_folder("INBOX");
+    server.create_folder("Lists/Rust");
+    let imported = maildir::import(&mut store, &mut server, None, None).unwrap();
+    assert_eq!(imported, vec![("INBOX".to_string(), 2), ("Lists/Rust".to_string(), 1)]);
+    let release = server.uids("Lists/Rust").unwrap()[0];
+    assert_eq!(server.message_flags("Lists/Rust", release), Some(vec!["\\Flagged".to_string()]));
+
+    assert!(maildir::import(&mut store, &mut server, Some("INBOX"), Some("Missing")).is_err());
+}
+
+#[test]
+fn folders_are_created_renamed_and_deleted() {
+    let test = TestDb::new("folder-management");
+    let mut server = MockMailServer::new();
+    server.deliver("Projects", &raw_message("alice@example.com", "Plan", "draft"), &[]);
+    server.deliver("Projects/2023", &raw_message("bob@example.com", "Review", "done"), &[]);
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "Projects").unwrap();
+    transport::sync_folder(&mut server, &test.db, ACCOUNT, "Projects/2023").unwrap();
+
+    transport::create_folder(&mut server, "Archive").unwrap();
+    assert!(transport::create_folder(&mut server, "Archive").is_err());
+    assert!(server.uids("Archive").unwrap().is_empty());
+
+    // The folders below it and their cached messages go along
+    transport::rename_folder(&mut server, &test.db, ACCOUNT, "Projects", "Work").unwrap();
+    assert!(server.uids("Projects").is_err());
+    assert_eq!(server.uids("Work/2023").unwrap().len(), 1);
+    assert_eq!(test.db.get_all_emails(ACCOUNT, "Work").unwrap()[0].subject, "Plan");
+    assert_eq!(test.db.get_all_emails(ACCOUNT, "Work/2023").unwrap()[0].subject, "Review");
+    assert!(test.db.get_all_emails(ACCOUNT, "Projects").unwrap().is_empty());
+    assert!(transport::rename_folder(&mut server, &test.db, ACCOUNT, "INBOX", "Old").is_err());
+
+    transport::delete_folder(&mut server, &test.db, ACCOUNT, "Work").unwrap();
+    assert!(server.uids("Work").is_err());
+    assert!(test.db.get_all_emails(ACCOUNT, "Work").unwrap().is_empty());
+    assert_eq!(test.db.get_all_emails(ACCOUNT, "Work/2023").unwrap().len(), 1);
+
+    // A Maildir tree keeps its folders as directories
+    let root = test.dir.join("Mail");
+    std::fs::create_dir_all(root.join("INBOX/cur")).unwrap();
+    let mut store = MaildirStore::open(&root).unwrap();
+    store.create_folder("Lists/Rust").unwrap();
+    store.rename_folder("Lists", "Mailing").unwrap_err();
+    store.rename_folder("Lists/Rust", "Rust").unwrap();
+    assert!(store.list_folders().unwrap().contains(&"Rust".to_string()));
+    store.delete_folder("Rust").unwrap();
+    assert!(!root.join("Rust").exists());
+}
+
+#[test]
+fn drafts_sync_both_ways() {
+    let test = TestDb::new("drafts");
+    let mut server = MockMailServer::new();
+    server.create_folder("Drafts");
+    let me = EmailAddress { name: None, address: ACCOUNT.to_string() };
+    let sync = |server: &mut MockMailServer, now| drafts::sync_drafts(server, &test.db, ACCOUNT, &me, "Drafts", now).unwrap();
+    let local = |id: &str| test.db.get_drafts(ACCOUNT).unwrap().into_iter().find(|d| d.message_id == id).unwrap();
+
+    // Started here: uploaded with its Message-ID and the \Draft flag
+    let mut email = Email::new();
+    email.subject = "Plan".to_string();
+    email.body_text = Some("first take".to_string());
+    test.db.save_draft(ACCOUNT, &drafts::local_draft("Plan@example.com", &email, 100)).unwrap();
+    assert_eq!(sync(&mut server, 110).uploaded, 1);
+    let uid = local("Plan@example.com").server_uid.unwrap();
+    assert!(server.message_flags("Drafts", uid).unwrap().contains(&"\\Draft".to_string()));
+    assert_eq!(sync(&mut server, 120), drafts::DraftSyncReport::default());
+
+    // Finished on the phone, which saves a new copy; another draft started there too
+    server.delete("Drafts", uid).unwrap();
+    let uid = server.deliver("Drafts", &raw_message(ACCOUNT, "Plan", "phone edit"), &["\\Draft"]);
+    server.deliver("Drafts", &raw_message(ACCOUNT, "Idea", "from the phone"), &["\\Draft"]);
+    assert_eq!(sync(&mut server, 130).downloaded, 2);
+    let plan = local("Plan@example.com");
+    assert_eq!(plan.server_uid, Some(uid));
+    assert!(plan.email.body_text.unwrap().contains("phone edit"));
+
+    // Edited on both sides: nothing is overwritten until the user chooses
+    let mut plan = local("Plan@example.com");
+    plan.email.body_text = Some("laptop edit".to_string());
+    plan.modified = 140;
+    test.db.save_draft(ACCOUNT, &plan).unwrap();
+    server.delete("Drafts", uid).unwrap();
+    server.deliver("Drafts", &raw_message(ACCOUNT, "Plan", "second phone edit"), &["\\Draft"]);
+    assert_eq!(sync(&mut server, 150).conflicts, 1);
+    assert!(local("Plan@example.com").conflict.is_some());
+
+    drafts::resolve_conflict(&test.db, ACCOUNT, "Plan@example.com", Resolution::Both, 160).unwrap();
+    assert_eq!(sync(&mut server, 170).uploaded, 1);
+    assert_eq!(server.uids("Drafts").unwrap().len(), 3);
+    let bodies: Vec<String> = test.db.get_drafts(ACCOUNT).unwrap().into_iter().filter_map(|d| d.email.body_text).collect();
+    assert!(bodies.iter().any(|b| b.contains("laptop edit")) && bodies.iter().any(|b| b.contains("second phone edit")));
+
+    // Discarded here: removed from the server too
+    let mut idea = local("Idea@example.com");
+    idea.deleted = true;
+    test.db.save_draft(ACCOUNT, &idea).unwrap();
+    assert_eq!(sync(&mut server, 180).removed, 1);
+    assert_eq!(server.uids("Drafts").unwrap().len(), 2);
+}
+
+#[test]
+fn send_builds_and_submits_message() {
+    let mut server = MockMailServer::new();
+
+    let mut email = Email::new();
+    email.to = vec![EmailAddress { name: Some("Bob".to_string()), address: "bob@example.com".to_string() }];
+    email.subject = "Quarterly report".to_string();
+    email.body_text = Some("Numbers attached.".to_string());
+
+    let me = EmailAddress { name: Some("Me".to_string()), address: ACCOUNT.to_string() };
+    transport::send_email(&mut server, &email, &me).unwrap();
+
+    let sent = server.sent();
+    assert_eq!(sent.len(), 1);
+    let text = String::from_utf8_lossy(&sent[0]);
+    assert!(text.contains("Subject: Quarterly report"));
+    assert!(text.contains("bob@example.com"));
+    assert!(text.contains(ACCOUNT));
+    assert!(text.contains("Numbers attached."));
+    assert!(!text.contains("text/html"));
+
+    // Compose defaults: Markdown sent as HTML too, priority and read-receipt headers
+    email.body_html = Some("<p><strong>Numbers</strong> attached.</p>".to_string());
+    email.headers.insert("X-Priority".to_string(), "1 (Highest)".to_string());
+    email.headers.insert("Disposition-Notification-To".to_string(), ACCOUNT.to_string());
+    email.headers.insert("In-Reply-To".to_string(), "<q3@example.com>".to_string());
+    transport::send_email(&mut server, &email, &me).unwrap();
+    let text = String::from_utf8_lossy(&server.sent()[1]).into_owned();
+    assert!(text.contains("text/html") && text.contains("<strong>Numbers</strong>"));
+    assert!(text.contains("X-Priority: 1 (Highest)"));
+    assert!(text.contains(&format!("Disposition-Notification-To: {}", ACCOUNT)));
+    assert!(text.contains("In-Reply-To: <q3@example.com>"));
+}
+
+#[test]
+fn invitation_replies_carry_the_calendar_beside_the_text() {
+    let mut server = MockMailServer::new();
+    let mut email = Email::new();
+    email.to = vec![EmailAddress { name: None, address: "ann@example.com".to_string() }];
+    email.subject = "Accepted: Planning".to_string();
+    email.body_text = Some("Me has accepted this invitation.".to_string());
+    email.attachments.push(EmailAttachment {
+        filename: "reply.ics".to_string(),
+        content_type: "text/calendar; method=REPLY; charset=utf-8".to_string(),
+        data: b"BEGIN:VCALENDAR\r\nMETHOD:REPLY\r\nEND:VCALENDAR\r\n".to_vec(),
+        path: None,
+        size: 46,
+    });
+
+    let me = EmailAddress { name: Some("Me".to_string()), address: ACCOUNT.to_string() };
+    transport::send_email(&mut server, &email, &me).unwrap();
+    let text = String::from_utf8_lossy(&server.sent()[0]).into_owned();
+    assert!(text.contains("multipart/alternative"));
+    assert!(text.contains("Content-Type: text/calendar; method=REPLY; charset=utf-8"));
+    assert!(text.contains("METHOD:REPLY"));
+    assert!(!text.contains("multipart/mixed") && !text.contains("reply.ics"));
+}
+
+#[test]
+fn send_falls_back_to_other_smtp_settings() {
+    let mut account = EmailAccount::default();
+    let routes = transport::smtp_routes(&account);
+    assert_eq!((routes[0].port, &routes[0].security, routes[0].family), (587, &SmtpSecurity::StartTLS, None));
+    assert_eq!((routes[1].port, &routes[1].security, routes[1].family), (465, &SmtpSecurity::SSL, None));
+    assert_eq!(routes.len(), 6);
+    assert!(routes.iter().any(|route| route.family == Some(IpFamily::V6)));
+    account.smtp_security = SmtpSecurity::SSL;
+    assert!(transport::smtp_routes(&account).iter().all(|route| route.security != SmtpSecurity::None));
+    account.smtp_fallback = SmtpFallback::Off;
+    assert_eq!(transport::smtp_routes(&account).len(), 1);
+
+    let server = MockMailServer::new();
+    let mut email = Email::new();
+    email.to = vec![EmailAddress { name: None, address: "bob@example.com".to_string() }];
+    email.subject = "Fallback".to_string();
+    let me = EmailAddress { name: None, address: ACCOUNT.to_string() };
+
+    // The configured port can't be reached; the next route delivers
+    let connect = |route: &transport::SmtpRoute| -> Result<Box<dyn transport::SmtpBackend>, EmailError> {
+        if route == &routes[0] {
+            return Err(EmailError::ConnectionError("connection refused".to_string()));
+        }
+        Ok(Box::new(server.clone()))
+    };
+    let used = transport::send_with_fallback(&routes, connect, &email, &me).unwrap();
+    assert_eq!(used, &routes[1]);
+    assert_eq!(server.sent().len(), 1);
+
+    // Once connected, a dropped connection may come after the server took the message,
+    // so it isn't sent again on another route
+    server.fail_next("timed out after DATA");
+    let attempts = std::cell::Cell::new(0);
+    let connect = |_: &transport::SmtpRoute| {
+        attempts.set(attempts.get() + 1);
+        Ok(Box::new(server.clone()) as Box<dyn transport::SmtpBackend>)
+    };
+    assert!(matches!(transport::send_with_fallback(&routes, connect, &email, &me), Err(EmailError::ConnectionError(_))));
+    assert_eq!(attempts.get(), 1);
+    assert_eq!(server.sent().len(), 1);
+
+    // A server reply is not a reason to try elsewhere
+    let refuse = |_: &transport::SmtpRoute| -> Result<Box<dyn transport::SmtpBackend>, EmailError> {
+        Err(EmailError::SmtpError("550 5.7.1 relaying denied".to_string()))
+    };
+    assert!(matches!(transport::send_with_fallback(&routes, refuse, &email, &me), Err(EmailError::SmtpError(_))));
+    let unreachable = |_: &transport::SmtpRoute| -> Result<Box<dyn transport::SmtpBackend>, EmailError> {
+        Err(EmailError::ConnectionError("timed out".to_string()))
+    };
+    assert!(transport::send_with_fallback(&routes, unreachable, &email, &me).is_err());
+    assert_eq!(server.sent().len(), 1);
+}
+
+#[test]
+fn bounce_resends_the_original_unchanged() {
+    let mut server = MockMailServer::new();
+    let original = raw_message("alice@example.com", "Invoice", "for accounts, not me");
+    let me = EmailAddress { name: None, address: ACCOUNT.to_string() };
+    let accounts = vec![EmailAddress { name: None, address: "accounts@example.com".to_string() }];
+
+    transport::bounce_email(&mut server, &original, &me, &accounts).unwrap();
+    let sent = server.sent().remove(0);
+    let text = String::from_utf8_lossy(&sent);
+    assert!(text.starts_with(&format!("Resent-From: {}\r\n", ACCOUNT)));
+    assert!(text.contains("Resent-To: accounts@example.com\r\n") && text.contains("Resent-Message-ID: <"));
+    assert!(sent.ends_with(&original));
+
+    let bad = vec![EmailAddress { name: None, address: "not an address".to_string() }];
+    assert!(transport::bounce_email(&mut server, &original, &me, &bad).is_err());
+    assert!(transport::bounce_email(&mut server, &original, &me, &[]).is_err());
+    assert_eq!(server.sent().len(), 1);
+}
+
+#[test]
+fn transport_errors_surface() {
+    let test = TestDb::new("errors");
+    let mut server = MockMailServer::new();
+    server.deliver("INBOX", &raw_message("alice@example.com", "Hello", "body"), &[]);
+
+    server.fail_next("connection reset");
+    assert!(transport::sync_folder(&mut server, &test.db, ACCOUNT, "INBOX").is_err());
+    assert!(test.db.get_all_emails(ACCOUNT, "INBOX").unwrap().is_empty());
+
+    assert!(transport::sync_folder(&mut server, &test.db, ACCOUNT, "Missing").is_err());
+}